@@ -1,16 +1,17 @@
 //! CLI binary for Markdown to docx conversion
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use md2docx_converter::{
     config::{ConversionConfig, service::ConfigurationService},
-    conversion::ConversionEngine,
+    conversion::{ConversionEngine, ConversionSummary},
     error::{ConversionError, ConfigError},
+    logging::WarningCounter,
 };
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::time::Instant;
 use tracing::{info, error, warn, debug};
-use tracing_subscriber;
+use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Parser)]
 #[command(name = "md2docx")]
@@ -53,6 +54,22 @@ enum Commands {
         /// Show conversion statistics
         #[arg(long)]
         stats: bool,
+        
+        /// Also render a PDF next to the docx (requires LibreOffice)
+        #[arg(long)]
+        pdf: bool,
+        
+        /// Print a recap of elements, warnings, output size and duration
+        #[arg(long)]
+        summary: bool,
+        
+        /// Set a template variable used as `{{NAME}}` (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
+        
+        /// Also write the parsed document in this format next to the docx (repeatable)
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        emit: Vec<EmitFormat>,
     },
     
     /// Convert multiple Markdown files (batch processing)
@@ -77,13 +94,21 @@ enum Commands {
         #[arg(short, long)]
         recursive: bool,
         
-        /// Maximum number of parallel conversions
-        #[arg(long, default_value = "4")]
-        parallel: usize,
+        /// Maximum number of parallel conversions (defaults to the number of CPUs)
+        #[arg(long)]
+        parallel: Option<usize>,
         
         /// Show detailed progress information
         #[arg(long)]
         progress: bool,
+        
+        /// Print a recap of each file and a total for the batch
+        #[arg(long)]
+        summary: bool,
+        
+        /// Set a template variable used as `{{NAME}}` (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
     },
     
     /// Start the web server
@@ -113,6 +138,13 @@ enum Commands {
     },
 }
 
+/// Formats `convert --emit` can write next to the docx
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EmitFormat {
+    /// The document as parsed, as normalized Markdown, to `<output>.parsed.md`
+    Markdown,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
@@ -128,9 +160,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             output, 
             config, 
             config_prompt, 
-            stats 
+            stats,
+            pdf,
+            summary,
+            variables,
+            emit
         } => {
-            handle_convert(input, output, config, config_prompt, stats).await
+            handle_convert(input, output, config, config_prompt, stats, pdf, summary, variables, emit).await
         }
         
         Commands::Batch { 
@@ -140,9 +176,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             config_prompt, 
             recursive, 
             parallel, 
-            progress 
+            progress,
+            summary,
+            variables
         } => {
-            handle_batch(input_dir, output_dir, config, config_prompt, recursive, parallel, progress).await
+            handle_batch(input_dir, output_dir, config, config_prompt, recursive, parallel, progress, summary, variables).await
         }
         
         Commands::Server { port, host } => {
@@ -177,9 +215,11 @@ fn init_logging(verbose: bool, debug: bool) {
         tracing::Level::WARN
     };
     
-    tracing_subscriber::fmt()
-        .with_max_level(level)
-        .with_target(false)
+    // Warnings are counted for conversion summaries
+    tracing_subscriber::registry()
+        .with(LevelFilter::from_level(level))
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(WarningCounter)
         .init();
 }
 
@@ -190,6 +230,10 @@ async fn handle_convert(
     config_path: Option<PathBuf>,
     config_prompt: Option<String>,
     show_stats: bool,
+    pdf: bool,
+    show_summary: bool,
+    variables: Vec<(String, String)>,
+    emit: Vec<EmitFormat>,
 ) -> Result<(), ConversionError> {
     info!("Starting single file conversion");
     
@@ -221,6 +265,11 @@ async fn handle_convert(
         println!("Configuration updated successfully");
     }
     
+    if pdf {
+        config.output.pdf.enabled = true;
+    }
+    config.parsing.variables.values.extend(variables);
+    
     // Create conversion engine
     let mut engine = ConversionEngine::new(config);
     
@@ -237,7 +286,7 @@ async fn handle_convert(
     // Perform conversion
     let start_time = Instant::now();
     
-    engine.convert_file(
+    let summary = engine.convert_file_with_summary(
         input.to_str().unwrap(),
         output_path.to_str().unwrap(),
     ).await?;
@@ -252,6 +301,23 @@ async fn handle_convert(
         println!("  Size: {} bytes", metadata.len());
     }
     
+    match (&summary.pdf, &summary.pdf_error) {
+        (Some(pdf_path), _) => println!("  PDF: {}", pdf_path.display()),
+        (None, Some(reason)) => println!("  PDF: not produced ({})", reason),
+        (None, None) => {}
+    }
+    
+    if emit.contains(&EmitFormat::Markdown) {
+        let markdown_path = output_path.with_extension("parsed.md");
+        let markdown = engine.normalized_markdown_file(input.to_str().unwrap())?;
+        fs::write(&markdown_path, markdown).map_err(ConversionError::Io)?;
+        println!("  Markdown: {}", markdown_path.display());
+    }
+    
+    if show_summary {
+        println!("Summary: {}", summary);
+    }
+    
     Ok(())
 }
 
@@ -262,8 +328,10 @@ async fn handle_batch(
     config_path: Option<PathBuf>,
     config_prompt: Option<String>,
     recursive: bool,
-    parallel: usize,
+    parallel: Option<usize>,
     show_progress: bool,
+    show_summary: bool,
+    variables: Vec<(String, String)>,
 ) -> Result<(), ConversionError> {
     info!("Starting batch conversion");
     
@@ -304,9 +372,14 @@ async fn handle_batch(
         config = update_config_with_prompt(config, &prompt).await?;
         println!("Configuration updated successfully");
     }
+    config.parsing.variables.values.extend(variables);
     
     // Create conversion engine
     let mut engine = ConversionEngine::new(config);
+    if let Some(parallel) = parallel {
+        engine.set_concurrency(parallel);
+    }
+    let parallel = engine.concurrency();
     
     // Prepare file pairs for batch conversion
     let file_pairs: Vec<(String, String)> = markdown_files
@@ -333,7 +406,7 @@ async fn handle_batch(
         println!("Starting conversion of {} files with {} parallel workers...", file_pairs.len(), parallel);
     }
     
-    let results = engine.convert_batch(&file_pairs).await?;
+    let results = engine.convert_batch_with_summaries(&file_pairs).await?;
     
     let duration = start_time.elapsed();
     
@@ -356,6 +429,18 @@ async fn handle_batch(
         }
     }
     
+    if show_summary {
+        println!("Summary:");
+        for ((input_path, _), result) in file_pairs.iter().zip(&results) {
+            if let Ok(summary) = result {
+                println!("  {}: {}", input_path, summary);
+            }
+        }
+        // Files are converted in parallel, so the total takes the wall-clock time
+        let total = ConversionSummary::total(results.iter().flatten());
+        println!("  Total: {}", ConversionSummary { duration, ..total });
+    }
+    
     Ok(())
 }
 
@@ -428,16 +513,15 @@ async fn load_config(config_path: Option<&Path>) -> Result<ConversionConfig, Con
             let yaml_content = fs::read_to_string(path)
                 .map_err(ConversionError::Io)?;
             
-            let config: ConversionConfig = serde_yaml::from_str(&yaml_content)
+            let config = ConversionConfig::from_yaml_str(&yaml_content)
                 .map_err(|e| ConversionError::Configuration(
                     ConfigError::InvalidYaml(format!("Failed to parse YAML: {}", e))
                 ))?;
             
             // Validate configuration
-            config.validate()
-                .map_err(|e| ConversionError::Configuration(
-                    ConfigError::Validation(e.to_string())
-                ))?;
+            if let Some(report) = config.validation_report() {
+                return Err(ConversionError::Configuration(ConfigError::Validation(report)));
+            }
             
             info!("Configuration loaded successfully from {}", path.display());
             Ok(config)
@@ -463,6 +547,14 @@ async fn update_config_with_prompt(
         .map_err(ConversionError::Configuration)
 }
 
+/// Parse a `--var NAME=VALUE` argument
+fn parse_variable(argument: &str) -> Result<(String, String), String> {
+    match argument.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected NAME=VALUE, got '{}'", argument)),
+    }
+}
+
 /// Find all Markdown files in a directory
 fn find_markdown_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, ConversionError> {
     let mut markdown_files = Vec::new();
@@ -38,6 +38,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(60),
+        // Worker count for async processing and batch conversions
+        max_concurrency: std::env::var("WORKER_COUNT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_else(md2docx_converter::conversion::default_concurrency),
     };
     
     // Get server port from environment
@@ -46,12 +51,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .and_then(|s| s.parse().ok())
         .unwrap_or(3000);
     
-    // Get worker count for async processing
-    let worker_count = std::env::var("WORKER_COUNT")
-        .ok()
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(4);
-    
+    let worker_count = resource_config.max_concurrency;
+
     // Create and configure web server
     let server = WebServer::with_resource_config(engine, port, resource_config)
         .with_task_queue(worker_count);
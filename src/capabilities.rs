@@ -0,0 +1,138 @@
+//! Runtime discovery of what this build supports
+
+use crate::config::PdfConfig;
+use crate::conversion::pdf::converter_available;
+use crate::docx::SUPPORTED_IMAGE_FORMATS;
+use crate::markdown::code_block::CodeBlockProcessor;
+use serde::{Deserialize, Serialize};
+
+/// Features, languages and formats supported by this build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub version: String,
+    /// Code block languages with a dedicated processing strategy
+    pub code_languages: Vec<CodeLanguageCapability>,
+    /// Image formats that can be embedded from local files
+    pub image_formats: Vec<String>,
+    /// Document formats the converter can produce
+    pub output_backends: Vec<String>,
+    pub features: FeatureFlags,
+}
+
+/// A code block strategy available at runtime
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeLanguageCapability {
+    pub language: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// Optional components compiled into this build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureFlags {
+    /// HTTP API server (`web` cargo feature)
+    pub web: bool,
+    /// Natural language configuration updates; the LLM client has no
+    /// feature gate and is always available
+    pub llm: bool,
+}
+
+/// Report the capabilities of the running build, with the default PDF
+/// converter
+pub fn capabilities() -> Capabilities {
+    capabilities_for(&PdfConfig::default())
+}
+
+/// Report the capabilities of the running build with a PDF configuration
+///
+/// PDF is an output backend when PDF rendering is enabled or the
+/// configured converter can be found.
+pub fn capabilities_for(pdf: &PdfConfig) -> Capabilities {
+    let processor = CodeBlockProcessor::new();
+    let mut code_languages: Vec<CodeLanguageCapability> = processor
+        .get_registry()
+        .list_all_strategies()
+        .into_iter()
+        .filter(|info| !info.is_default_strategy)
+        .map(|info| CodeLanguageCapability {
+            language: info.language_name,
+            version: info.version,
+            description: info.description,
+        })
+        .collect();
+    code_languages.sort_by(|a, b| a.language.cmp(&b.language));
+
+    let mut output_backends = vec!["docx".to_string()];
+    if pdf.enabled || converter_available(pdf) {
+        output_backends.push("pdf".to_string());
+    }
+
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        code_languages,
+        image_formats: SUPPORTED_IMAGE_FORMATS.iter().map(|f| f.to_string()).collect(),
+        output_backends,
+        features: FeatureFlags {
+            web: cfg!(feature = "web"),
+            llm: true,
+        },
+    }
+}
+
+impl Capabilities {
+    /// Check whether a code language has a dedicated strategy
+    pub fn supports_code_language(&self, language: &str) -> bool {
+        self.code_languages
+            .iter()
+            .any(|lang| lang.language.eq_ignore_ascii_case(language))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_build_capabilities() {
+        let caps = capabilities();
+
+        assert!(caps.supports_code_language("rust"));
+        assert!(caps.supports_code_language("note"));
+        assert!(!caps.supports_code_language("cobol"));
+        for format in ["png", "jpeg", "gif", "bmp"] {
+            assert!(caps.image_formats.iter().any(|f| f == format), "missing {}", format);
+        }
+        assert_eq!(caps.output_backends[0], "docx");
+        assert_eq!(caps.features.web, cfg!(feature = "web"));
+    }
+
+    #[test]
+    fn test_pdf_backend_follows_converter() {
+        let missing = PdfConfig {
+            converter_path: "/nonexistent/md2docx-test/soffice".to_string(),
+            ..PdfConfig::default()
+        };
+        assert_eq!(capabilities_for(&missing).output_backends, vec!["docx".to_string()]);
+
+        // Enabled in the configuration
+        let enabled = PdfConfig { enabled: true, ..missing.clone() };
+        assert_eq!(capabilities_for(&enabled).output_backends, vec!["docx".to_string(), "pdf".to_string()]);
+
+        // Found on disk
+        let dir = tempfile::TempDir::new().unwrap();
+        let converter = dir.path().join("soffice");
+        std::fs::write(&converter, "").unwrap();
+        let available = PdfConfig {
+            converter_path: converter.to_string_lossy().into_owned(),
+            ..missing
+        };
+        assert_eq!(capabilities_for(&available).output_backends, vec!["docx".to_string(), "pdf".to_string()]);
+    }
+
+    #[test]
+    fn test_capabilities_serialization() {
+        let json = serde_json::to_value(capabilities()).unwrap();
+        assert!(json["code_languages"].is_array());
+        assert!(json["features"]["web"].is_boolean());
+    }
+}
@@ -2,6 +2,8 @@
 
 pub mod models;
 pub mod service;
+pub mod themes;
+pub mod units;
 pub mod yaml_processor;
 
 pub use models::*;
@@ -1,5 +1,6 @@
 //! Configuration data models
 
+use crate::config::units::{deserialize_length, resolve_relative_sizes, Length};
 use crate::numbering::NumberingFormatter;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -28,76 +29,1477 @@ pub enum ValidationError {
     InvalidNumberingFormat(String),
     #[error("Invalid border width: must be non-negative")]
     InvalidBorderWidth,
+    #[error("Invalid watermark: {0}")]
+    InvalidWatermark(String),
+    #[error("Invalid section title: cannot be empty")]
+    InvalidSectionTitle,
+    #[error("Invalid nesting depth: must be at least 1")]
+    InvalidNestingDepth,
+    #[error("Invalid image cache: {0}")]
+    InvalidImageCache(String),
+    #[error("Invalid remote images: {0}")]
+    InvalidRemoteImages(&'static str),
+    #[error("Invalid compact factor: {0} must be greater than 0 and at most 1")]
+    InvalidCompactFactor(&'static str),
+    #[error("Invalid wide table overflow factor: must be at least 1")]
+    InvalidOverflowFactor,
+    #[error("Invalid abbreviation: {0}")]
+    InvalidAbbreviation(String),
+    #[error("Invalid blockquote style: {0}")]
+    InvalidBlockquote(&'static str),
+    #[error("Invalid paragraph length limit: must be positive")]
+    InvalidParagraphLimit,
+}
+
+/// A validation error with the path of the configuration field it concerns
+#[derive(Debug, Error)]
+#[error("{path}: {error}")]
+pub struct FieldError {
+    /// Dotted path as written in YAML, such as `styles.headings.3.font.size`
+    pub path: String,
+    pub error: ValidationError,
+}
+
+/// Collects validation errors under a field path
+struct Checker<'a> {
+    path: String,
+    errors: &'a mut Vec<FieldError>,
+}
+
+impl<'a> Checker<'a> {
+    /// Run `check` against a root checker, returning the first error found
+    fn first_error(check: impl FnOnce(&mut Checker)) -> Result<(), ValidationError> {
+        Self::all_errors(check).into_iter().next().map_or(Ok(()), |e| Err(e.error))
+    }
+
+    /// Run `check` against a root checker, returning every error found
+    fn all_errors(check: impl FnOnce(&mut Checker)) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check(&mut Checker { path: String::new(), errors: &mut errors });
+        errors
+    }
+
+    fn join(&self, name: impl std::fmt::Display) -> String {
+        if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.path, name)
+        }
+    }
+
+    /// A checker for the field `name` below this one
+    fn field(&mut self, name: impl std::fmt::Display) -> Checker<'_> {
+        Checker { path: self.join(name), errors: &mut *self.errors }
+    }
+
+    /// Record an error against the field `name`
+    fn fail(&mut self, name: impl std::fmt::Display, error: ValidationError) {
+        let path = self.join(name);
+        self.errors.push(FieldError { path, error });
+    }
+
+    /// Record an error against this checker's own field
+    fn fail_here(&mut self, error: ValidationError) {
+        self.errors.push(FieldError { path: self.path.clone(), error });
+    }
+
+    /// Record an error if the field `name` is not a hex color
+    fn color(&mut self, name: &str, color: &str) {
+        if let Err(error) = validate_color(color) {
+            self.fail(name, error);
+        }
+    }
 }
 
 /// Main configuration structure for conversion
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ConversionConfig {
-    pub document: DocumentConfig,
-    pub styles: StyleConfig,
-    pub elements: ElementConfig,
+pub struct ConversionConfig {
+    pub document: DocumentConfig,
+    pub styles: StyleConfig,
+    pub elements: ElementConfig,
+    #[serde(default)]
+    pub code_block_processing: Option<crate::markdown::code_block::CodeBlockConfig>,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub sections: AppendedSectionsConfig,
+    #[serde(default)]
+    pub parsing: ParsingConfig,
+    #[serde(default)]
+    pub compact: CompactConfig,
+}
+
+/// Dense layout for reference sheets
+///
+/// When enabled, spacing, line spacing, page margins and font sizes across
+/// the configuration are scaled by these factors before rendering (see
+/// [`ConversionConfig::compacted`]). Line spacing never drops below single.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompactConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Scale of spacing before and after headings, paragraphs, code blocks
+    /// and list items
+    #[serde(default = "default_compact_spacing_factor")]
+    pub spacing_factor: f32,
+    /// Scale of paragraph and code block line spacing
+    #[serde(default = "default_compact_line_spacing_factor")]
+    pub line_spacing_factor: f32,
+    /// Scale of the page margins
+    #[serde(default = "default_compact_margin_factor")]
+    pub margin_factor: f32,
+    /// Scale of every configured font size
+    #[serde(default = "default_compact_font_size_factor")]
+    pub font_size_factor: f32,
+}
+
+fn default_compact_spacing_factor() -> f32 {
+    0.5
+}
+
+fn default_compact_line_spacing_factor() -> f32 {
+    0.9
+}
+
+fn default_compact_margin_factor() -> f32 {
+    0.6
+}
+
+fn default_compact_font_size_factor() -> f32 {
+    0.9
+}
+
+impl Default for CompactConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spacing_factor: default_compact_spacing_factor(),
+            line_spacing_factor: default_compact_line_spacing_factor(),
+            margin_factor: default_compact_margin_factor(),
+            font_size_factor: default_compact_font_size_factor(),
+        }
+    }
+}
+
+impl CompactConfig {
+    /// Validate compact factors
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        let factors = [
+            ("spacing_factor", self.spacing_factor),
+            ("line_spacing_factor", self.line_spacing_factor),
+            ("margin_factor", self.margin_factor),
+            ("font_size_factor", self.font_size_factor),
+        ];
+        for (name, factor) in factors {
+            if !(factor > 0.0 && factor <= 1.0) {
+                checker.fail(name, ValidationError::InvalidCompactFactor(name));
+            }
+        }
+    }
+}
+
+/// How the Markdown source is read
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParsingConfig {
+    #[serde(default)]
+    pub profile: ParsingProfile,
+    #[serde(default)]
+    pub includes: IncludesConfig,
+    #[serde(default)]
+    pub front_matter: FrontMatterConfig,
+    #[serde(default)]
+    pub variables: VariablesConfig,
+}
+
+/// Template variables written as `{{name}}`
+///
+/// Placeholders in text are replaced with the value of the variable they
+/// name; code spans and code blocks are left alone. Values come from
+/// `values` and the CLI's `--var name=value`, which overrides them; a
+/// document's front matter `variables` mapping only fills in the rest.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VariablesConfig {
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    /// What happens to placeholders naming no variable
+    #[serde(default)]
+    pub undefined: UndefinedVariableMode,
+}
+
+/// Handling of `{{name}}` placeholders whose variable is not defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndefinedVariableMode {
+    /// Leave the placeholder as written, with a warning
+    #[default]
+    Keep,
+    /// Fail the conversion, naming the undefined variables
+    Error,
+}
+
+/// Per-document settings from YAML front matter
+///
+/// `theme` selects a built-in theme (see [`themes`](crate::config::themes))
+/// and the `document`, `styles`, `elements`, `sections` and `compact` keys
+/// override the configuration, both for that document only. Other keys are
+/// left alone. An unknown theme or invalid overrides are logged and the
+/// active configuration is used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrontMatterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Table of the front matter's fields at the top of the document
+    #[serde(default)]
+    pub metadata_table: MetadataTableConfig,
+}
+
+/// Front matter fields shown as a table before the body
+///
+/// When enabled, the scalar fields of a document's front matter (all but
+/// `theme`) become the rows of a two-column table, labels styled like table
+/// headers and values like table cells. The `fields` listed come first, in
+/// that order, followed by the others in the order they are written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataTableConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_metadata_fields")]
+    pub fields: Vec<String>,
+}
+
+fn default_metadata_fields() -> Vec<String> {
+    ["title", "author", "date", "version"].iter().map(|field| field.to_string()).collect()
+}
+
+impl Default for MetadataTableConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            fields: default_metadata_fields(),
+        }
+    }
+}
+
+/// Transclusion of other Markdown files with `{{include: path}}` or
+/// `!INCLUDE "path"` lines
+///
+/// Only file conversions resolve includes, relative to the including file;
+/// Markdown given as a string (such as through the web API) is left as is.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncludesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Deepest chain of nested includes followed
+    #[serde(default = "default_include_depth")]
+    pub max_depth: usize,
+}
+
+fn default_include_depth() -> usize {
+    8
+}
+
+impl Default for IncludesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_depth: default_include_depth(),
+        }
+    }
+}
+
+/// Markdown dialect accepted by the parser
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParsingProfile {
+    /// GitHub Flavored Markdown (tables, strikethrough, task lists, footnotes)
+    /// plus `{.class}` attributes and `?width=`/`?height=` image sizes
+    #[default]
+    Gfm,
+    /// CommonMark only; extension syntax is kept as literal text
+    Commonmark,
+}
+
+impl ParsingProfile {
+    /// Name as written in configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            ParsingProfile::Gfm => "gfm",
+            ParsingProfile::Commonmark => "commonmark",
+        }
+    }
+}
+
+/// Headings of sections appended after the document body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendedSectionsConfig {
+    /// Footnote definitions collected at the end of the document
+    #[serde(default = "AppendedSectionConfig::footnotes")]
+    pub footnotes: AppendedSectionConfig,
+    /// Reference lists built from document links
+    #[serde(default = "AppendedSectionConfig::references")]
+    pub references: AppendedSectionConfig,
+    /// Index of the terms marked with `<!-- index: term -->`
+    #[serde(default = "AppendedSectionConfig::index")]
+    pub index: AppendedSectionConfig,
+    /// Notes written as `<!-- rev: ... -->`, in `section` revision notes mode
+    #[serde(default = "AppendedSectionConfig::revision_notes")]
+    pub revision_notes: AppendedSectionConfig,
+}
+
+impl Default for AppendedSectionsConfig {
+    fn default() -> Self {
+        Self {
+            footnotes: AppendedSectionConfig::footnotes(),
+            references: AppendedSectionConfig::references(),
+            index: AppendedSectionConfig::index(),
+            revision_notes: AppendedSectionConfig::revision_notes(),
+        }
+    }
+}
+
+impl AppendedSectionsConfig {
+    /// Validate appended section headings
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.footnotes.check(&mut checker.field("footnotes"));
+        self.references.check(&mut checker.field("references"));
+        self.index.check(&mut checker.field("index"));
+        self.revision_notes.check(&mut checker.field("revision_notes"));
+    }
+}
+
+/// Heading of an appended section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendedSectionConfig {
+    pub title: String,
+    /// Heading level, styled like the document's own headings
+    #[serde(default = "default_section_level")]
+    pub level: u8,
+    /// Run the heading through the heading numbering for its level
+    #[serde(default)]
+    pub numbered: bool,
+    /// Give the heading an outline level so it shows in the navigation pane and TOC
+    #[serde(default = "default_true")]
+    pub in_toc: bool,
+}
+
+fn default_section_level() -> u8 {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl AppendedSectionConfig {
+    /// Create an unnumbered level 1 section heading
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            level: default_section_level(),
+            numbered: false,
+            in_toc: true,
+        }
+    }
+
+    fn footnotes() -> Self {
+        Self::new("Notes")
+    }
+
+    fn references() -> Self {
+        Self::new("References")
+    }
+
+    fn index() -> Self {
+        Self::new("Index")
+    }
+
+    fn revision_notes() -> Self {
+        Self::new("Revision notes")
+    }
+
+    /// Validate section heading configuration
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        if !(1..=6).contains(&self.level) {
+            checker.fail("level", ValidationError::InvalidHeadingLevel);
+        }
+        if self.title.trim().is_empty() {
+            checker.fail("title", ValidationError::InvalidSectionTitle);
+        }
+    }
+}
+
+/// Output package configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OutputConfig {
+    /// Re-open the generated package and check it for corruption before
+    /// returning it. Also enabled by setting `MD2DOCX_SELF_CHECK=1`.
+    #[serde(default)]
+    pub self_check: bool,
+    /// Companion PDF rendered by an external converter
+    #[serde(default)]
+    pub pdf: PdfConfig,
+    /// Source Markdown kept in custom document properties
+    #[serde(default)]
+    pub embed_source: EmbedSourceConfig,
+    /// Record of the converter that produced the document
+    #[serde(default)]
+    pub provenance: ProvenanceConfig,
+}
+
+/// Converter name and version written into the document
+///
+/// The colophon date is the current date, or the date of
+/// `SOURCE_DATE_EPOCH` when set so that builds stay reproducible.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvenanceConfig {
+    /// Store the converter name and version in the `Generator` custom
+    /// document property
+    #[serde(default = "default_true")]
+    pub property: bool,
+    /// Append a "Generated by md2docx vX on DATE" paragraph at the end
+    #[serde(default)]
+    pub colophon: bool,
+}
+
+impl Default for ProvenanceConfig {
+    fn default() -> Self {
+        Self {
+            property: true,
+            colophon: false,
+        }
+    }
+}
+
+/// Traceability copy of the source Markdown inside the docx
+///
+/// When enabled the SHA-256 of the source is always stored; the Markdown
+/// itself only while it fits in `max_bytes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedSourceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Largest source, in bytes, stored verbatim
+    #[serde(default = "default_embed_source_max_bytes")]
+    pub max_bytes: usize,
+}
+
+fn default_embed_source_max_bytes() -> usize {
+    64 * 1024
+}
+
+impl Default for EmbedSourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: default_embed_source_max_bytes(),
+        }
+    }
+}
+
+/// Companion PDF rendering through a headless office suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfConfig {
+    /// Render a PDF next to the docx when converting
+    #[serde(default)]
+    pub enabled: bool,
+    /// Converter executable, invoked as `<path> --headless --convert-to pdf`
+    #[serde(default = "default_pdf_converter")]
+    pub converter_path: String,
+    /// Seconds to wait for the converter before giving up
+    #[serde(default = "default_pdf_timeout")]
+    pub timeout_seconds: u64,
+}
+
+fn default_pdf_converter() -> String {
+    "soffice".to_string()
+}
+
+fn default_pdf_timeout() -> u64 {
+    120
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            converter_path: default_pdf_converter(),
+            timeout_seconds: default_pdf_timeout(),
+        }
+    }
+}
+
+/// Document-level configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentConfig {
+    pub page_size: PageSize,
+    pub margins: Margins,
+    pub default_font: FontConfig,
+    /// Distance between default tab stops, in points; tabs without an explicit
+    /// stop advance to the next multiple of it
+    #[serde(default = "default_tab_stop", deserialize_with = "deserialize_length")]
+    pub default_tab_stop: f32,
+    #[serde(default)]
+    pub watermark: Option<WatermarkConfig>,
+    /// Table of contents at the top of the document
+    #[serde(default)]
+    pub table_of_contents: TableOfContentsConfig,
+}
+
+/// Default for [`DocumentConfig::default_tab_stop`], half an inch as in Word
+fn default_tab_stop() -> f32 {
+    36.0
+}
+
+/// Table of contents written before the body
+///
+/// The table is a Word `TOC` field over the headings' outline levels, from
+/// 1 to `max_depth`, linking to bookmarks on the headings, which are written
+/// whether or not `styles.heading_bookmarks` is enabled. Its entries are
+/// filled in from the document's outline, and Word refreshes them, with
+/// page numbers, when the document is opened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableOfContentsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Heading written above the table, kept out of the table itself; none
+    /// when empty
+    #[serde(default = "default_toc_title")]
+    pub title: String,
+    /// Deepest heading level listed
+    #[serde(default = "default_toc_max_depth")]
+    pub max_depth: u8,
+    /// Show page numbers after the entries
+    #[serde(default = "default_true")]
+    pub page_numbers: bool,
+}
+
+fn default_toc_title() -> String {
+    "Contents".to_string()
+}
+
+fn default_toc_max_depth() -> u8 {
+    3
+}
+
+impl Default for TableOfContentsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            title: default_toc_title(),
+            max_depth: default_toc_max_depth(),
+            page_numbers: true,
+        }
+    }
+}
+
+impl TableOfContentsConfig {
+    fn check(&self, checker: &mut Checker) {
+        if !(1..=6).contains(&self.max_depth) {
+            checker.fail("max_depth", ValidationError::InvalidHeadingLevel);
+        }
+    }
+}
+
+/// Style configuration for different elements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StyleConfig {
+    pub headings: HashMap<u8, HeadingStyle>,
+    pub paragraph: ParagraphStyle,
+    pub code_block: CodeBlockStyle,
+    pub table: TableStyle,
+    /// Line handling inside code blocks
+    #[serde(default)]
+    pub code_lines: CodeLinesStyle,
+    /// Styles applied to paragraphs and spans tagged with `{.class}`
+    #[serde(default)]
+    pub classes: HashMap<String, ClassStyle>,
+    /// Rendering of an H1 directly followed by an H2 as a title block
+    #[serde(default)]
+    pub title_block: TitleBlockConfig,
+    /// Rendering of the document's first H1 as its title
+    #[serde(default)]
+    pub first_heading_title: FirstHeadingTitleConfig,
+    /// Named bookmarks on headings for linking from outside the document
+    #[serde(default)]
+    pub heading_bookmarks: HeadingBookmarksConfig,
+    /// Styles for the `<kbd>` and `<samp>` inline HTML tags
+    #[serde(default)]
+    pub inline_html: InlineHtmlStyles,
+    /// Formatting through named styles instead of run properties
+    #[serde(default)]
+    pub named_styles: NamedStylesConfig,
+    /// Font, indentation and left border of blockquotes by nesting depth
+    #[serde(default)]
+    pub blockquote: BlockQuoteStyle,
+}
+
+/// Formatting of blockquotes by nesting depth
+///
+/// Each quote is indented by the `indent` of its level on top of the quotes
+/// around it, with a left border in the level's `border_color`. Quotes
+/// nested deeper than the last level use it again, so every depth is
+/// indented further. Nesting deeper than `elements.max_nesting_depth` is
+/// flattened into the deepest allowed quote.
+///
+/// Quoted text is set in `font`, applied under any classes of the quoted
+/// paragraph; fields left unset keep the paragraph font.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockQuoteStyle {
+    /// Font family, size, emphasis and color of quoted text
+    #[serde(default)]
+    pub font: ClassStyle,
+    /// Levels from the outermost quote in
+    #[serde(default = "default_blockquote_levels")]
+    pub levels: Vec<BlockQuoteLevel>,
+    /// Width of the left border in points; 0 for none
+    #[serde(default = "default_blockquote_border_width", deserialize_with = "deserialize_length")]
+    pub border_width: f32,
+}
+
+/// Formatting of blockquotes at one nesting depth
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockQuoteLevel {
+    /// Left indent added at this depth, in points
+    #[serde(deserialize_with = "deserialize_length")]
+    pub indent: f32,
+    /// Left border color, as a hex color; no border when unset
+    #[serde(default)]
+    pub border_color: Option<String>,
+}
+
+fn default_blockquote_levels() -> Vec<BlockQuoteLevel> {
+    ["#BBBBBB", "#999999", "#777777"]
+        .iter()
+        .map(|color| BlockQuoteLevel {
+            indent: 18.0,
+            border_color: Some(color.to_string()),
+        })
+        .collect()
+}
+
+fn default_blockquote_border_width() -> f32 {
+    1.5
+}
+
+impl Default for BlockQuoteStyle {
+    fn default() -> Self {
+        Self {
+            font: ClassStyle::default(),
+            levels: default_blockquote_levels(),
+            border_width: default_blockquote_border_width(),
+        }
+    }
+}
+
+impl BlockQuoteStyle {
+    /// Level of quotes at a nesting depth, counting from 1
+    pub fn level(&self, depth: usize) -> Option<&BlockQuoteLevel> {
+        self.levels.get(depth.max(1) - 1).or(self.levels.last())
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.font.check(&mut checker.field("font"));
+        if self.levels.is_empty() {
+            checker.fail("levels", ValidationError::InvalidBlockquote("at least one level is required"));
+        }
+        for (index, level) in self.levels.iter().enumerate() {
+            let mut checker = checker.field(format!("levels.{}", index));
+            if level.indent < 0.0 {
+                checker.fail("indent", ValidationError::InvalidSpacing);
+            }
+            if let Some(color) = &level.border_color {
+                checker.color("border_color", color);
+            }
+        }
+        if self.border_width < 0.0 {
+            checker.fail("border_width", ValidationError::InvalidBorderWidth);
+        }
+    }
+}
+
+/// Formatting of body text, headings and code blocks through named styles
+///
+/// When enabled, the paragraph font becomes the document defaults that
+/// `Normal` inherits, and headings and code blocks reference the styles
+/// `Heading1` to `Heading6` and `CodeBlock`, defined once from `headings`
+/// and `code_block`. Their runs carry no font or size of their own, so
+/// tooling can restyle a document by editing its styles. Emphasis, classes
+/// and inline code keep their direct formatting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NamedStylesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Formatting of keyboard input and sample output written as inline HTML
+///
+/// `<kbd>Ctrl</kbd>` and `<samp>output</samp>` are styled independently of
+/// inline code. A class named `kbd` or `samp` in `classes` is applied on top.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InlineHtmlStyles {
+    #[serde(default = "default_kbd_style")]
+    pub kbd: ClassStyle,
+    #[serde(default = "default_samp_style")]
+    pub samp: ClassStyle,
+}
+
+impl Default for InlineHtmlStyles {
+    fn default() -> Self {
+        Self {
+            kbd: default_kbd_style(),
+            samp: default_samp_style(),
+        }
+    }
+}
+
+fn default_kbd_style() -> ClassStyle {
+    ClassStyle {
+        font_family: Some("Consolas".to_string()),
+        border: Some("#999999".to_string()),
+        background_color: Some("#F3F3F3".to_string()),
+        ..ClassStyle::default()
+    }
+}
+
+fn default_samp_style() -> ClassStyle {
+    ClassStyle {
+        font_family: Some("Consolas".to_string()),
+        color: Some("#555555".to_string()),
+        ..ClassStyle::default()
+    }
+}
+
+/// Bookmarks on every heading shown in the navigation pane
+///
+/// Names are derived from the heading anchors (see the outline API), so
+/// they are deterministic and unique within a document, and are written
+/// whether or not the document has a table of contents.
+///
+/// With `by_number`, numbered headings also get a bookmark named by their
+/// number (`2.3.1`), and links such as `[see 2.3.1](#2.3.1)` jump to it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HeadingBookmarksConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub by_number: bool,
+}
+
+/// Title block made from an H1 immediately followed by an H2
+///
+/// When enabled the H1 is written with the `Title` paragraph style and the H2
+/// with the `Subtitle` style. The subtitle takes the H2 font but is not
+/// numbered and stays out of the navigation pane.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TitleBlockConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// The first H1 of a document as its title
+///
+/// When enabled the first non-empty H1 is written with the `Title` paragraph
+/// style and `style` on top of the H1 font. It is not numbered, so the
+/// numbering of the H1s after it starts from the first of them. With
+/// `core_title` off, the title no longer sets the document's title property.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FirstHeadingTitleConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_title_style")]
+    pub style: ClassStyle,
+    #[serde(default = "default_true")]
+    pub core_title: bool,
+}
+
+impl Default for FirstHeadingTitleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: default_title_style(),
+            core_title: true,
+        }
+    }
+}
+
+fn default_title_style() -> ClassStyle {
+    ClassStyle {
+        font_size: Some(26.0),
+        bold: Some(true),
+        alignment: Some("center".to_string()),
+        ..ClassStyle::default()
+    }
+}
+
+/// Formatting for a Markdown class such as `{.callout}`
+///
+/// `style_name` references a paragraph or character style by id; the other
+/// fields are applied as direct formatting on top of it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassStyle {
+    #[serde(default)]
+    pub style_name: Option<String>,
+    #[serde(default)]
+    pub font_family: Option<String>,
+    /// Size in points; may be relative to the default font (see [`FontConfig::size`])
+    #[serde(default, deserialize_with = "deserialize_optional_length")]
+    pub font_size: Option<f32>,
+    #[serde(default)]
+    pub bold: Option<bool>,
+    #[serde(default)]
+    pub italic: Option<bool>,
+    #[serde(default)]
+    pub strikethrough: Option<bool>,
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Shading behind the text, as a hex color
+    #[serde(default)]
+    pub background_color: Option<String>,
+    /// Single-line border around the text, as a hex color; ignored on
+    /// paragraphs
+    #[serde(default)]
+    pub border: Option<String>,
+    /// Word highlight, as a highlight color name (`yellow`, `lightGray`, ...)
+    /// or a hex color mapped to the nearest of them
+    #[serde(default)]
+    pub highlight: Option<String>,
+    /// Paragraph alignment (left, center, right, justify); ignored on spans
+    #[serde(default)]
+    pub alignment: Option<String>,
+    /// Left indent in points; ignored on spans
+    #[serde(default, deserialize_with = "deserialize_optional_length")]
+    pub indent: Option<f32>,
+}
+
+fn deserialize_optional_length<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<Length>::deserialize(deserializer).map(|length| length.map(|l| l.0))
+}
+
+impl ClassStyle {
+    /// Validate class style configuration
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        if let Some(family) = &self.font_family {
+            if family.trim().is_empty() {
+                checker.fail("font_family", ValidationError::InvalidFontFamily);
+            }
+        }
+        if matches!(self.font_size, Some(size) if size <= 0.0) {
+            checker.fail("font_size", ValidationError::InvalidFontSize);
+        }
+        if matches!(self.indent, Some(indent) if indent < 0.0) {
+            checker.fail("indent", ValidationError::InvalidSpacing);
+        }
+        if let Some(color) = &self.color {
+            checker.color("color", color);
+        }
+        if let Some(color) = &self.background_color {
+            checker.color("background_color", color);
+        }
+        if let Some(color) = &self.border {
+            checker.color("border", color);
+        }
+        if let Some(color) = &self.highlight {
+            if crate::docx::highlight::nearest_highlight(color).is_none() {
+                checker.fail("highlight", ValidationError::InvalidColor(color.clone()));
+            }
+        }
+    }
+}
+
+/// Element-specific configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementConfig {
+    pub image: ImageConfig,
+    pub list: ListConfig,
+    pub link: LinkConfig,
+    #[serde(default)]
+    pub emoji: EmojiConfig,
+    #[serde(default)]
+    pub checkboxes: CheckboxConfig,
+    #[serde(default)]
+    pub whitespace: WhitespaceConfig,
+    #[serde(default)]
+    pub references: ReferencesConfig,
+    /// Deepest list or HTML table nesting kept; deeper list items are
+    /// flattened onto the last allowed level and deeper tables become cell
+    /// text, with a warning
+    #[serde(default = "default_max_nesting_depth")]
+    pub max_nesting_depth: usize,
+    /// Handling of headings whose text is empty after trimming (`## `)
+    #[serde(default)]
+    pub empty_headings: EmptyHeadingMode,
+    /// How `---` horizontal rules are converted
+    #[serde(default)]
+    pub horizontal_rule: HorizontalRuleConfig,
+    /// Handling of footnotes that are defined but never referenced
+    #[serde(default)]
+    pub footnotes: FootnotesConfig,
+    /// Captions taken from a leading comment line in code blocks
+    #[serde(default)]
+    pub code_captions: CodeCaptionConfig,
+    /// Title bars naming the language of code blocks
+    #[serde(default)]
+    pub code_title_bar: CodeTitleBarConfig,
+    /// Conversion of pasted `•` bullet lines into lists
+    #[serde(default)]
+    pub unicode_bullets: UnicodeBulletsConfig,
+    /// Styling of `— Author` lines ending blockquotes
+    #[serde(default)]
+    pub quote_attribution: QuoteAttributionConfig,
+    /// Callout styling of paragraphs that are entirely bold or italic
+    #[serde(default)]
+    pub emphasis_callouts: EmphasisCalloutConfig,
+    /// Button styling of paragraphs that are a single link
+    #[serde(default)]
+    pub link_buttons: LinkButtonConfig,
+    /// Expansion of abbreviations in running text
+    #[serde(default)]
+    pub abbreviations: AbbreviationsConfig,
+    /// Layout of `[NOTE_BLOCK_START]` note blocks
+    #[serde(default)]
+    pub note_block: NoteBlockConfig,
+    /// Handling of `<!-- rev: ... -->` revision notes
+    #[serde(default)]
+    pub revision_notes: RevisionNotesConfig,
+    /// Safeguard against pathologically long paragraphs
+    #[serde(default)]
+    pub long_paragraphs: LongParagraphsConfig,
+}
+
+/// Cap on the characters in one paragraph
+///
+/// Paragraphs over `max_chars` are split into several, or truncated, with
+/// a warning; in `strict` mode they fail the conversion. Code blocks are
+/// never split. Off when `max_chars` is absent.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LongParagraphsConfig {
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    #[serde(default)]
+    pub mode: LongParagraphMode,
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// What becomes of a paragraph over the length limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LongParagraphMode {
+    /// Continued in further paragraphs of at most `max_chars` each, broken
+    /// at a space where there is one
+    #[default]
+    Split,
+    /// Cut at `max_chars`, ending with an ellipsis
+    Truncate,
+}
+
+/// Revision notes written as `<!-- rev: ... -->`
+///
+/// Like review comments, a note is anchored to the text before it, or on a
+/// line of its own to the end of the preceding paragraph or list item.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RevisionNotesConfig {
+    #[serde(default)]
+    pub mode: RevisionNotesMode,
+}
+
+/// What becomes of revision notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionNotesMode {
+    /// Dropped, like other HTML comments
+    #[default]
+    Off,
+    /// Listed in document order under the `sections.revision_notes` heading
+    Section,
+    /// Word comments where they were written
+    Comments,
+}
+
+/// Layout of note blocks
+///
+/// A note is a bordered table with its title and content on the left and its
+/// `[ICON]` image in a narrow column on the right. Notes without an icon, or
+/// whose icon cannot be loaded, take a single full-width column;
+/// `single_column` forces that layout for every note, with the icon above
+/// the title.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteBlockConfig {
     #[serde(default)]
-    pub code_block_processing: Option<crate::markdown::code_block::CodeBlockConfig>,
+    pub single_column: bool,
+    /// Box, in pixels, the icon is fitted into keeping its aspect ratio;
+    /// the icon column is as wide as `icon_width`
+    #[serde(default = "default_note_icon_width")]
+    pub icon_width: f32,
+    #[serde(default = "default_note_icon_height")]
+    pub icon_height: f32,
 }
 
-/// Document-level configuration
+fn default_note_icon_width() -> f32 {
+    90.0
+}
+
+fn default_note_icon_height() -> f32 {
+    60.0
+}
+
+impl Default for NoteBlockConfig {
+    fn default() -> Self {
+        Self {
+            single_column: false,
+            icon_width: default_note_icon_width(),
+            icon_height: default_note_icon_height(),
+        }
+    }
+}
+
+impl NoteBlockConfig {
+    fn check(&self, checker: &mut Checker) {
+        if self.icon_width <= 0.0 {
+            checker.fail("icon_width", ValidationError::InvalidImageDimensions);
+        }
+        if self.icon_height <= 0.0 {
+            checker.fail("icon_height", ValidationError::InvalidImageDimensions);
+        }
+    }
+}
+
+/// Abbreviations and their full names
+///
+/// `terms` maps each abbreviation to its full name; a document's front
+/// matter may add more under `abbreviations`. Abbreviations are matched as
+/// whole words, case-sensitively, in the plain text of paragraphs, list
+/// items and footnotes; code, headings and tables are left alone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AbbreviationsConfig {
+    #[serde(default)]
+    pub terms: HashMap<String, String>,
+    #[serde(default)]
+    pub mode: AbbreviationMode,
+}
+
+/// How abbreviations are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AbbreviationMode {
+    /// The first occurrence as "Full Name (ABBR)", later ones as they are
+    #[default]
+    FirstUse,
+    /// Every occurrence as is, with the full name as a tooltip
+    Tooltip,
+}
+
+/// Paragraphs written entirely in bold or entirely in italics
+///
+/// When enabled, such paragraphs are formatted with `style`, a shaded and
+/// indented box by default. Paragraphs mixing emphasis with plain text are
+/// left alone.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DocumentConfig {
-    pub page_size: PageSize,
-    pub margins: Margins,
-    pub default_font: FontConfig,
+pub struct EmphasisCalloutConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_callout_style")]
+    pub style: ClassStyle,
 }
 
-/// Style configuration for different elements
+impl Default for EmphasisCalloutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: default_callout_style(),
+        }
+    }
+}
+
+fn default_callout_style() -> ClassStyle {
+    ClassStyle {
+        background_color: Some("#F2F2F2".to_string()),
+        indent: Some(18.0),
+        ..ClassStyle::default()
+    }
+}
+
+/// Paragraphs consisting of a single link, such as a call to action
+///
+/// When enabled, such paragraphs are formatted with `style`, centered with
+/// the link text bordered and shaded like a button by default. The link
+/// stays a hyperlink; links within running text are left alone.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StyleConfig {
-    pub headings: HashMap<u8, HeadingStyle>,
-    pub paragraph: ParagraphStyle,
-    pub code_block: CodeBlockStyle,
-    pub table: TableStyle,
+pub struct LinkButtonConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_button_style")]
+    pub style: ClassStyle,
 }
 
-/// Element-specific configuration
+impl Default for LinkButtonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: default_button_style(),
+        }
+    }
+}
+
+fn default_button_style() -> ClassStyle {
+    ClassStyle {
+        bold: Some(true),
+        background_color: Some("#EAF2FB".to_string()),
+        border: Some("#0066CC".to_string()),
+        alignment: Some("center".to_string()),
+        ..ClassStyle::default()
+    }
+}
+
+/// Attribution lines of blockquotes
+///
+/// When enabled, a blockquote whose last line starts with `—` has that line
+/// split into its own paragraph and formatted with `style`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ElementConfig {
-    pub image: ImageConfig,
-    pub list: ListConfig,
-    pub link: LinkConfig,
+pub struct QuoteAttributionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_attribution_style")]
+    pub style: ClassStyle,
+}
+
+impl Default for QuoteAttributionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: default_attribution_style(),
+        }
+    }
+}
+
+fn default_attribution_style() -> ClassStyle {
+    ClassStyle {
+        italic: Some(true),
+        alignment: Some("right".to_string()),
+        ..ClassStyle::default()
+    }
+}
+
+/// Unicode bullet normalization
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnicodeBulletsConfig {
+    /// Turn paragraphs whose every line starts with a bullet glyph such as
+    /// `•` or `▪` into unordered lists
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Code block title bars
+///
+/// When enabled, a code block with a language gets a header row naming it,
+/// followed by the `title` (or `filename`) of its fence info string if one
+/// is given, formatted with `style`. Blocks without a language and a title
+/// get no title bar. The style's background color shades the whole row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeTitleBarConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_code_title_bar_style")]
+    pub style: ClassStyle,
+}
+
+impl Default for CodeTitleBarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            style: default_code_title_bar_style(),
+        }
+    }
+}
+
+fn default_code_title_bar_style() -> ClassStyle {
+    ClassStyle {
+        font_size: Some(9.0),
+        bold: Some(true),
+        color: Some("#555555".to_string()),
+        background_color: Some("#E8E8E8".to_string()),
+        ..ClassStyle::default()
+    }
+}
+
+/// Code block captions from a leading comment
+///
+/// When enabled, a code block in one of `languages` whose first line is a
+/// line comment gets that comment, without its marker, as a `Caption`
+/// paragraph above the block, and the line is removed from the code.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeCaptionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Line comment marker by language, e.g. `rust: "//"`; other languages
+    /// never get captions
+    #[serde(default = "default_caption_comment_markers")]
+    pub languages: HashMap<String, String>,
+}
+
+fn default_caption_comment_markers() -> HashMap<String, String> {
+    let markers: [(&[&str], &str); 3] = [
+        (
+            &["rust", "c", "cpp", "csharp", "java", "javascript", "typescript", "go", "kotlin", "swift"],
+            "//",
+        ),
+        (&["python", "ruby", "bash", "sh", "shell", "perl", "r", "yaml", "toml"], "#"),
+        (&["sql", "lua", "haskell"], "--"),
+    ];
+    markers
+        .iter()
+        .flat_map(|(languages, marker)| {
+            languages.iter().map(move |language| (language.to_string(), marker.to_string()))
+        })
+        .collect()
+}
+
+impl Default for CodeCaptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            languages: default_caption_comment_markers(),
+        }
+    }
+}
+
+/// Footnote configuration
+///
+/// References to labels without a definition always keep their marker and
+/// log a warning.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FootnotesConfig {
+    #[serde(default)]
+    pub orphan_definitions: OrphanFootnoteMode,
+}
+
+/// What happens to footnote definitions nothing refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanFootnoteMode {
+    /// Number them after the referenced footnotes and list them, with a warning
+    #[default]
+    List,
+    /// Leave them out of the document
+    Omit,
+}
+
+/// Conversion of horizontal rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HorizontalRuleConfig {
+    #[serde(default)]
+    pub mode: HorizontalRuleMode,
+    /// Text centered on its own line in `ornament` mode
+    #[serde(default = "default_ornament")]
+    pub ornament: String,
+}
+
+fn default_ornament() -> String {
+    "* * *".to_string()
+}
+
+impl Default for HorizontalRuleConfig {
+    fn default() -> Self {
+        Self {
+            mode: HorizontalRuleMode::default(),
+            ornament: default_ornament(),
+        }
+    }
+}
+
+/// What a horizontal rule becomes in the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HorizontalRuleMode {
+    /// An empty paragraph with a bottom border across the text width
+    #[default]
+    Line,
+    /// A page break; the following content starts on a new page
+    PageBreak,
+    /// The configured ornament text, centered
+    Ornament,
+}
+
+/// How headings with no text are converted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyHeadingMode {
+    /// Number and render them like any other heading
+    #[default]
+    Number,
+    /// Render them without a number; they do not advance the numbering
+    Unnumbered,
+    /// Leave them out of the document
+    Skip,
+}
+
+/// Default for [`ElementConfig::max_nesting_depth`]
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 8;
+
+fn default_max_nesting_depth() -> usize {
+    DEFAULT_MAX_NESTING_DEPTH
 }
 
 /// Page size configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageSize {
+    #[serde(deserialize_with = "deserialize_length")]
     pub width: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub height: f32,
 }
 
 impl PageSize {
     /// Validate page size
     pub fn validate(&self) -> Result<(), ValidationError> {
-        if self.width <= 0.0 || self.height <= 0.0 {
-            return Err(ValidationError::InvalidPageSize);
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        if self.width <= 0.0 {
+            checker.fail("width", ValidationError::InvalidPageSize);
+        }
+        if self.height <= 0.0 {
+            checker.fail("height", ValidationError::InvalidPageSize);
+        }
+    }
+}
+
+/// Watermark orientation on the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatermarkOrientation {
+    /// Text runs from bottom-left to top-right
+    #[default]
+    Diagonal,
+    Horizontal,
+}
+
+/// Watermark configuration, drawn behind the body text on every page.
+///
+/// Exactly one of `text` or `image_path` must be set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatermarkConfig {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub image_path: Option<String>,
+    #[serde(default = "default_watermark_color")]
+    pub color: String,
+    /// Fill opacity between 0.0 (invisible) and 1.0 (solid)
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f32,
+    #[serde(default)]
+    pub orientation: WatermarkOrientation,
+}
+
+fn default_watermark_color() -> String {
+    "#C0C0C0".to_string()
+}
+
+fn default_watermark_opacity() -> f32 {
+    0.5
+}
+
+impl WatermarkConfig {
+    /// Create a text watermark with the default color, opacity and orientation
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            image_path: None,
+            color: default_watermark_color(),
+            opacity: default_watermark_opacity(),
+            orientation: WatermarkOrientation::default(),
+        }
+    }
+
+    /// Create an image watermark from a local file path
+    pub fn image(path: impl Into<String>) -> Self {
+        Self {
+            text: None,
+            image_path: Some(path.into()),
+            color: default_watermark_color(),
+            opacity: default_watermark_opacity(),
+            orientation: WatermarkOrientation::default(),
+        }
+    }
+
+    /// Validate watermark configuration
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        match (&self.text, &self.image_path) {
+            (Some(_), Some(_)) => checker.fail_here(ValidationError::InvalidWatermark(
+                "text and image_path are mutually exclusive".to_string(),
+            )),
+            (None, None) => checker.fail_here(ValidationError::InvalidWatermark(
+                "either text or image_path must be set".to_string(),
+            )),
+            (Some(text), None) if text.trim().is_empty() => checker.fail(
+                "text",
+                ValidationError::InvalidWatermark("text cannot be empty".to_string()),
+            ),
+            _ => {}
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            checker.fail(
+                "opacity",
+                ValidationError::InvalidWatermark(format!(
+                    "opacity must be between 0.0 and 1.0, got {}",
+                    self.opacity
+                )),
+            );
         }
-        Ok(())
+        checker.color("color", &self.color);
     }
 }
 
 /// Margin configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Margins {
+    #[serde(deserialize_with = "deserialize_length")]
     pub top: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub bottom: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub left: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub right: f32,
 }
 
 impl Margins {
     /// Validate margins
     pub fn validate(&self) -> Result<(), ValidationError> {
-        if self.top < 0.0 || self.bottom < 0.0 || self.left < 0.0 || self.right < 0.0 {
-            return Err(ValidationError::InvalidMargins);
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        for (name, margin) in [("top", self.top), ("bottom", self.bottom), ("left", self.left), ("right", self.right)] {
+            if margin < 0.0 {
+                checker.fail(name, ValidationError::InvalidMargins);
+            }
         }
-        Ok(())
     }
 }
 
@@ -105,6 +1507,10 @@ impl Margins {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FontConfig {
     pub family: String,
+    /// Size in points. When loaded with [`ConversionConfig::from_yaml_str`] it
+    /// may be written relative to `document.default_font.size`, as `"150%"`
+    /// or `"1.5x"`
+    #[serde(deserialize_with = "deserialize_length")]
     pub size: f32,
     pub bold: bool,
     pub italic: bool,
@@ -113,13 +1519,16 @@ pub struct FontConfig {
 impl FontConfig {
     /// Validate font configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
         if self.family.trim().is_empty() {
-            return Err(ValidationError::InvalidFontFamily);
+            checker.fail("family", ValidationError::InvalidFontFamily);
         }
         if self.size <= 0.0 {
-            return Err(ValidationError::InvalidFontSize);
+            checker.fail("size", ValidationError::InvalidFontSize);
         }
-        Ok(())
     }
 }
 
@@ -127,30 +1536,108 @@ impl FontConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeadingStyle {
     pub font: FontConfig,
+    #[serde(deserialize_with = "deserialize_length")]
     pub spacing_before: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub spacing_after: f32,
     pub alignment: Option<String>,
     pub numbering: Option<String>,
+    /// Formatting of the numbering prefix; when set the prefix is written as
+    /// its own run with this formatting on top of the heading font
+    #[serde(default)]
+    pub numbering_style: Option<ClassStyle>,
+    /// What separates the numbering prefix from the heading text
+    #[serde(default)]
+    pub numbering_separator: NumberingSeparator,
+    /// Tab stop, in points from the left margin, used by the `tab` separator;
+    /// defaults to `document.default_tab_stop`
+    #[serde(default, deserialize_with = "deserialize_optional_length")]
+    pub numbering_tab_stop: Option<f32>,
+}
+
+/// Separator between a heading's numbering prefix and its text
+///
+/// Written in YAML as `space`, `tab`, `em_space` or any other string, which
+/// is used literally.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum NumberingSeparator {
+    /// A single space
+    #[default]
+    Space,
+    /// A tab to `numbering_tab_stop`, so text of differently long numbers aligns
+    Tab,
+    /// An em space (U+2003)
+    EmSpace,
+    /// Any other text
+    Custom(String),
+}
+
+impl NumberingSeparator {
+    /// Text of the separator; a tab is `\t`
+    pub fn as_str(&self) -> &str {
+        match self {
+            NumberingSeparator::Space => " ",
+            NumberingSeparator::Tab => "\t",
+            NumberingSeparator::EmSpace => "\u{2003}",
+            NumberingSeparator::Custom(text) => text,
+        }
+    }
+}
+
+impl From<String> for NumberingSeparator {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "space" => NumberingSeparator::Space,
+            "tab" => NumberingSeparator::Tab,
+            "em_space" => NumberingSeparator::EmSpace,
+            _ => NumberingSeparator::Custom(value),
+        }
+    }
+}
+
+impl From<NumberingSeparator> for String {
+    fn from(separator: NumberingSeparator) -> Self {
+        match separator {
+            NumberingSeparator::Space => "space".to_string(),
+            NumberingSeparator::Tab => "tab".to_string(),
+            NumberingSeparator::EmSpace => "em_space".to_string(),
+            NumberingSeparator::Custom(text) => text,
+        }
+    }
 }
 
 impl HeadingStyle {
     /// Validate heading style
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.font.validate()?;
-        if self.spacing_before < 0.0 || self.spacing_after < 0.0 {
-            return Err(ValidationError::InvalidSpacing);
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.font.check(&mut checker.field("font"));
+        if self.spacing_before < 0.0 {
+            checker.fail("spacing_before", ValidationError::InvalidSpacing);
+        }
+        if self.spacing_after < 0.0 {
+            checker.fail("spacing_after", ValidationError::InvalidSpacing);
+        }
+
+        if let Some(style) = &self.numbering_style {
+            style.check(&mut checker.field("numbering_style"));
+        }
+        if self.numbering_tab_stop.is_some_and(|stop| stop <= 0.0) {
+            checker.fail("numbering_tab_stop", ValidationError::InvalidSpacing);
         }
 
         // Validate numbering format if present
         if let Some(numbering) = &self.numbering {
             if let Err(numbering_error) = NumberingFormatter::parse_format(numbering) {
-                return Err(ValidationError::InvalidNumberingFormat(
-                    numbering_error.to_string(),
-                ));
+                checker.fail(
+                    "numbering",
+                    ValidationError::InvalidNumberingFormat(numbering_error.to_string()),
+                );
             }
         }
-
-        Ok(())
     }
 }
 
@@ -159,17 +1646,24 @@ impl HeadingStyle {
 pub struct ParagraphStyle {
     pub font: FontConfig,
     pub line_spacing: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub spacing_after: f32,
 }
 
 impl ParagraphStyle {
     /// Validate paragraph style
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.font.validate()?;
-        if self.line_spacing <= 0.0 || self.spacing_after < 0.0 {
-            return Err(ValidationError::InvalidSpacing);
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.font.check(&mut checker.field("font"));
+        if self.line_spacing <= 0.0 {
+            checker.fail("line_spacing", ValidationError::InvalidSpacing);
+        }
+        if self.spacing_after < 0.0 {
+            checker.fail("spacing_after", ValidationError::InvalidSpacing);
         }
-        Ok(())
     }
 }
 
@@ -268,7 +1762,7 @@ impl<'de> Deserialize<'de> for CodeBlockStyle {
                             if paragraph_spacing.is_some() {
                                 return Err(de::Error::duplicate_field("paragraph_spacing"));
                             }
-                            paragraph_spacing = Some(map.next_value()?);
+                            paragraph_spacing = Some(map.next_value::<Length>()?.0);
                         }
                     }
                 }
@@ -316,37 +1810,153 @@ impl<'de> Deserialize<'de> for CodeBlockStyle {
 impl CodeBlockStyle {
     /// Validate code block style
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.font.validate()?;
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.font.check(&mut checker.field("font"));
         if let Some(color) = &self.background_color {
-            validate_color(color)?;
+            checker.color("background_color", color);
+        }
+        if self.line_spacing <= 0.0 {
+            checker.fail("line_spacing", ValidationError::InvalidSpacing);
         }
-        if self.line_spacing <= 0.0 || self.paragraph_spacing < 0.0 {
-            return Err(ValidationError::InvalidSpacing);
+        if self.paragraph_spacing < 0.0 {
+            checker.fail("paragraph_spacing", ValidationError::InvalidSpacing);
         }
         if self.border_width < 0.0 {
-            return Err(ValidationError::InvalidBorderWidth);
+            checker.fail("border_width", ValidationError::InvalidBorderWidth);
+        }
+    }
+}
+
+/// Line handling inside code blocks, on top of their `CodeBlockStyle`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeLinesStyle {
+    /// Keep `\r\n` and lone `\r` as written instead of normalizing them to `\n`
+    #[serde(default)]
+    pub preserve_line_endings: bool,
+    /// Shading of lines picked out with a `highlight` fence attribute, as a
+    /// hex color; only applied when line breaks are preserved
+    #[serde(default = "default_code_highlight_color")]
+    pub highlight_color: String,
+}
+
+fn default_code_highlight_color() -> String {
+    "#FFF2CC".to_string()
+}
+
+impl Default for CodeLinesStyle {
+    fn default() -> Self {
+        Self {
+            preserve_line_endings: false,
+            highlight_color: default_code_highlight_color(),
+        }
+    }
+}
+
+impl CodeLinesStyle {
+    fn check(&self, checker: &mut Checker) {
+        checker.color("highlight_color", &self.highlight_color);
+    }
+}
+
+/// Table style configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStyle {
+    pub header_font: FontConfig,
+    pub cell_font: FontConfig,
+    pub border_width: f32,
+    /// Shading of header cells, as a hex color
+    #[serde(default)]
+    pub header_background: Option<String>,
+    /// Shading of every second data row (the 2nd, 4th, ...), as a hex color
+    #[serde(default)]
+    pub alternate_row_background: Option<String>,
+    /// Style the first cell of each data row like a header cell: header font,
+    /// and `header_background` over any row shading
+    #[serde(default)]
+    pub row_header_column: bool,
+    /// Horizontal position of tables between the page margins
+    #[serde(default)]
+    pub table_alignment: TableAlignment,
+    /// Stretch tables across the full text width instead of the fixed
+    /// default width
+    #[serde(default)]
+    pub full_width: bool,
+    /// Handling of tables estimated to be wider than the page
+    #[serde(default)]
+    pub wide_tables: WideTableConfig,
+}
+
+/// Tables too wide for a portrait page
+///
+/// A table's width is estimated from the longest text in each column. When
+/// it exceeds the text width by more than `overflow_factor`, `handling`
+/// decides what happens; every such table is logged with a warning.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WideTableConfig {
+    #[serde(default)]
+    pub handling: WideTableHandling,
+    #[serde(default = "default_overflow_factor")]
+    pub overflow_factor: f32,
+}
+
+fn default_overflow_factor() -> f32 {
+    1.25
+}
+
+impl Default for WideTableConfig {
+    fn default() -> Self {
+        Self {
+            handling: WideTableHandling::default(),
+            overflow_factor: default_overflow_factor(),
         }
-        Ok(())
     }
 }
 
-/// Table style configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TableStyle {
-    pub header_font: FontConfig,
-    pub cell_font: FontConfig,
-    pub border_width: f32,
+/// What to do with a table too wide for the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WideTableHandling {
+    /// Squeeze the table into the page as usual
+    #[default]
+    Keep,
+    /// Put the table on landscape pages of its own, spanning their text width
+    Landscape,
+}
+
+/// Horizontal alignment of a table on the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TableAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
 }
 
 impl TableStyle {
     /// Validate table style
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.header_font.validate()?;
-        self.cell_font.validate()?;
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.header_font.check(&mut checker.field("header_font"));
+        self.cell_font.check(&mut checker.field("cell_font"));
         if self.border_width < 0.0 {
-            return Err(ValidationError::InvalidSpacing);
+            checker.fail("border_width", ValidationError::InvalidSpacing);
+        }
+        if let Some(color) = &self.header_background {
+            checker.color("header_background", color);
+        }
+        if let Some(color) = &self.alternate_row_background {
+            checker.color("alternate_row_background", color);
+        }
+        if self.wide_tables.overflow_factor < 1.0 {
+            checker.fail("wide_tables.overflow_factor", ValidationError::InvalidOverflowFactor);
         }
-        Ok(())
     }
 }
 
@@ -355,13 +1965,226 @@ impl TableStyle {
 pub struct ImageConfig {
     pub max_width: f32,
     pub max_height: f32,
+    /// Largest pixel width or height embedded; bigger images are downscaled
+    /// before embedding. Display size is unaffected.
+    #[serde(default)]
+    pub max_pixel_dimension: Option<u32>,
+    /// Download and embed `http(s)://` images; when off, or when a download
+    /// fails, they are written as placeholders
+    #[serde(default)]
+    pub fetch_remote: bool,
+    /// Time allowed for each remote image download
+    #[serde(default = "default_remote_timeout_seconds")]
+    pub remote_timeout_seconds: u64,
+    /// Largest remote image embedded; bigger ones are written as placeholders
+    #[serde(default = "default_remote_max_bytes")]
+    pub remote_max_bytes: u64,
+    /// On-disk cache for downloaded remote images; off when absent
+    #[serde(default)]
+    pub remote_cache: Option<RemoteImageCacheConfig>,
+    /// Cap on the combined size of the images embedded in one document;
+    /// no cap when absent
+    #[serde(default)]
+    pub total_size: Option<ImageTotalSizeConfig>,
+    /// Where images sit relative to the surrounding text
+    #[serde(default)]
+    pub placement: ImagePlacement,
+    /// Image shown for `![qrcode](url)`, above the URL
+    #[serde(default)]
+    pub qrcode: QrCodeConfig,
+}
+
+/// QR code images: `![qrcode](url)` shows the configured image above the
+/// URL; without an image the URL follows a placeholder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QrCodeConfig {
+    /// Local image file of the QR code
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Width and height in pixels
+    #[serde(default = "default_qrcode_size")]
+    pub size: u32,
+    /// Center the image and the URL
+    #[serde(default = "default_true")]
+    pub centered: bool,
+}
+
+fn default_qrcode_size() -> u32 {
+    50
+}
+
+impl Default for QrCodeConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            size: default_qrcode_size(),
+            centered: true,
+        }
+    }
+}
+
+/// Placement of images relative to the text around them
+///
+/// Inline and floating images are attached to the paragraph that follows
+/// them; an image with no paragraph after it is written on its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImagePlacement {
+    /// In a paragraph of its own
+    #[default]
+    Block,
+    /// In line with the text of the next paragraph
+    Inline,
+    /// At the left margin, with the next paragraphs wrapping around it
+    FloatLeft,
+    /// At the right margin, with the next paragraphs wrapping around it
+    FloatRight,
+}
+
+/// Cap on the cumulative bytes of images embedded in one document
+///
+/// Sizes are counted as embedded, after any downscaling. An image that
+/// would take the total past `max_bytes` is written as a placeholder with a
+/// warning, or fails the conversion when `strict` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageTotalSizeConfig {
+    pub max_bytes: u64,
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Default for [`ImageConfig::remote_timeout_seconds`]
+fn default_remote_timeout_seconds() -> u64 {
+    10
+}
+
+/// Default for [`ImageConfig::remote_max_bytes`], 10 MiB
+fn default_remote_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+/// On-disk cache of downloaded remote images, keyed by URL
+///
+/// Entries younger than `ttl_seconds` are used without a request; older ones
+/// are revalidated with their `ETag`/`Last-Modified`. The oldest entries are
+/// evicted once the cache grows past `max_bytes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteImageCacheConfig {
+    pub directory: String,
+    #[serde(default = "default_cache_ttl_seconds")]
+    pub ttl_seconds: u64,
+    #[serde(default = "default_cache_max_bytes")]
+    pub max_bytes: u64,
+}
+
+/// Default for [`RemoteImageCacheConfig::ttl_seconds`], one day
+fn default_cache_ttl_seconds() -> u64 {
+    24 * 60 * 60
+}
+
+/// Default for [`RemoteImageCacheConfig::max_bytes`], 100 MiB
+fn default_cache_max_bytes() -> u64 {
+    100 * 1024 * 1024
 }
 
 /// List configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListConfig {
+    #[serde(deserialize_with = "deserialize_length")]
     pub indent: f32,
+    #[serde(deserialize_with = "deserialize_length")]
     pub spacing: f32,
+    /// Number style of ordered list items by nesting depth, starting at the
+    /// top level; deeper levels cycle through the styles again
+    #[serde(default = "default_ordered_styles")]
+    pub ordered_styles: Vec<ListNumberStyle>,
+    /// Rendering of task list items (`- [x] Done`)
+    #[serde(default)]
+    pub tasks: TaskListConfig,
+}
+
+fn default_ordered_styles() -> Vec<ListNumberStyle> {
+    vec![ListNumberStyle::Decimal]
+}
+
+/// How completed and open task list items are marked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskListConfig {
+    /// Start task items with a ☑ or ☐ box
+    #[serde(default = "default_true")]
+    pub checkbox: bool,
+    /// Strike through the text of completed items
+    #[serde(default)]
+    pub strike_completed: bool,
+}
+
+impl Default for TaskListConfig {
+    fn default() -> Self {
+        Self {
+            checkbox: true,
+            strike_completed: false,
+        }
+    }
+}
+
+/// How ordered list items are numbered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListNumberStyle {
+    /// 1, 2, 3
+    #[default]
+    Decimal,
+    /// a, b, c, ..., z, aa, ab
+    LowerAlpha,
+    /// A, B, C
+    UpperAlpha,
+    /// i, ii, iii
+    LowerRoman,
+    /// I, II, III
+    UpperRoman,
+}
+
+impl ListNumberStyle {
+    /// A number, counting from 1, written in this style
+    pub fn format(self, number: usize) -> String {
+        match self {
+            ListNumberStyle::Decimal => number.to_string(),
+            ListNumberStyle::LowerAlpha => alphabetic(number),
+            ListNumberStyle::UpperAlpha => alphabetic(number).to_uppercase(),
+            ListNumberStyle::LowerRoman => roman(number),
+            ListNumberStyle::UpperRoman => roman(number).to_uppercase(),
+        }
+    }
+}
+
+/// Letters of a number in bijective base 26: a-z, then aa, ab, ...
+fn alphabetic(mut number: usize) -> String {
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push(b'a' + (number % 26) as u8);
+        number /= 26;
+    }
+    letters.iter().rev().map(|&letter| letter as char).collect()
+}
+
+/// Lowercase Roman numeral of a number; 0 has none and is written as `0`
+fn roman(mut number: usize) -> String {
+    if number == 0 {
+        return "0".to_string();
+    }
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+        (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    let mut result = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
 }
 
 /// Link configuration
@@ -369,15 +2192,168 @@ pub struct ListConfig {
 pub struct LinkConfig {
     pub color: String,
     pub underline: bool,
+    /// Turn `<user@host>` autolinks and bare email addresses into `mailto:` links
+    #[serde(default)]
+    pub autolink_emails: bool,
+}
+
+/// Reference list built from external links
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReferencesConfig {
+    /// Replace external links with numbered superscripts and list their URLs
+    /// under the `sections.references` heading; repeated URLs share a number
+    #[serde(default)]
+    pub enabled: bool,
+    /// Group the list under one paragraph per domain
+    #[serde(default)]
+    pub group_by_domain: bool,
+}
+
+/// Emoji shortcode configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmojiConfig {
+    /// Replace shortcodes such as `:rocket:` with emoji characters
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Inline checkbox configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CheckboxConfig {
+    /// Replace standalone `[x]` and `[ ]` in prose with ☒ and ☐
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Whitespace cleanup in prose; code spans and code blocks are untouched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhitespaceConfig {
+    /// Trim spaces, tabs and non-breaking spaces at the end of paragraphs
+    /// and before line breaks
+    #[serde(default)]
+    pub trim_trailing: bool,
+    /// Collapse runs of spaces to a single space
+    #[serde(default)]
+    pub collapse_spaces: bool,
+    /// How tab characters in prose are written
+    #[serde(default)]
+    pub tabs: ProseTabMode,
+    /// Spaces written for each tab in `spaces` mode
+    #[serde(default = "default_tab_spaces")]
+    pub tab_spaces: usize,
+}
+
+impl Default for WhitespaceConfig {
+    fn default() -> Self {
+        Self {
+            trim_trailing: false,
+            collapse_spaces: false,
+            tabs: ProseTabMode::default(),
+            tab_spaces: default_tab_spaces(),
+        }
+    }
+}
+
+fn default_tab_spaces() -> usize {
+    4
+}
+
+/// Handling of tab characters in prose
+///
+/// Code blocks have their own tab handling and are not affected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProseTabMode {
+    /// A Word tab, advancing to the next tab stop (`document.default_tab_stop`
+    /// unless the paragraph sets its own)
+    #[default]
+    Tab,
+    /// `tab_spaces` spaces
+    Spaces,
 }
 
 impl ConversionConfig {
+    /// Parse a YAML configuration, resolving relative font sizes and em
+    /// spacings to points
+    pub fn from_yaml_str(yaml: &str) -> Result<Self, serde_yaml::Error> {
+        let mut value: serde_yaml::Value = serde_yaml::from_str(yaml)?;
+        resolve_relative_sizes(&mut value).map_err(<serde_yaml::Error as serde::de::Error>::custom)?;
+        serde_yaml::from_value(value)
+    }
+
     /// Validate the entire configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.document.validate()?;
-        self.styles.validate()?;
-        self.elements.validate()?;
-        Ok(())
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    /// Every validation error in the configuration, each with the path of
+    /// the field it concerns
+    ///
+    /// Unlike [`validate`](Self::validate), which stops at the first error,
+    /// this reports them all, in the order `validate` checks them.
+    pub fn validate_all(&self) -> Vec<FieldError> {
+        Checker::all_errors(|checker| self.check(checker))
+    }
+
+    /// Every validation error as `path: error`, separated by `; `, or `None`
+    /// when the configuration is valid
+    pub fn validation_report(&self) -> Option<String> {
+        let errors = self.validate_all();
+        (!errors.is_empty()).then(|| {
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+        })
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.document.check(&mut checker.field("document"));
+        self.styles.check(&mut checker.field("styles"));
+        self.elements.check(&mut checker.field("elements"));
+        self.sections.check(&mut checker.field("sections"));
+        self.compact.check(&mut checker.field("compact"));
+    }
+
+    /// The configuration as rendered
+    ///
+    /// With compact mode enabled, spacing, line spacing, margins and font
+    /// sizes are scaled by the compact factors and compact mode is switched
+    /// off, so applying it again changes nothing. Otherwise unchanged.
+    pub fn compacted(mut self) -> Self {
+        if !self.compact.enabled {
+            return self;
+        }
+        let CompactConfig { spacing_factor: spacing, line_spacing_factor: line, margin_factor: margin, font_size_factor: font, .. } = self.compact;
+        // Font sizes stay on the half-point grid docx stores them in
+        let scale_font = |config: &mut FontConfig| {
+            config.size = ((config.size * font * 2.0).round() / 2.0).max(1.0);
+        };
+        let scale_line = |line_spacing: &mut f32| {
+            *line_spacing = (*line_spacing * line).max(line_spacing.min(1.0));
+        };
+
+        let margins = &mut self.document.margins;
+        for side in [&mut margins.top, &mut margins.bottom, &mut margins.left, &mut margins.right] {
+            *side *= margin;
+        }
+        scale_font(&mut self.document.default_font);
+
+        let styles = &mut self.styles;
+        for heading in styles.headings.values_mut() {
+            scale_font(&mut heading.font);
+            heading.spacing_before *= spacing;
+            heading.spacing_after *= spacing;
+        }
+        scale_font(&mut styles.paragraph.font);
+        scale_line(&mut styles.paragraph.line_spacing);
+        styles.paragraph.spacing_after *= spacing;
+        scale_font(&mut styles.code_block.font);
+        scale_line(&mut styles.code_block.line_spacing);
+        styles.code_block.paragraph_spacing *= spacing;
+        scale_font(&mut styles.table.header_font);
+        scale_font(&mut styles.table.cell_font);
+        self.elements.list.spacing *= spacing;
+
+        self.compact.enabled = false;
+        self
     }
 }
 
@@ -388,6 +2364,10 @@ impl Default for ConversionConfig {
             styles: StyleConfig::default(),
             elements: ElementConfig::default(),
             code_block_processing: None,
+            output: OutputConfig::default(),
+            sections: AppendedSectionsConfig::default(),
+            parsing: ParsingConfig::default(),
+            compact: CompactConfig::default(),
         }
     }
 }
@@ -395,10 +2375,20 @@ impl Default for ConversionConfig {
 impl DocumentConfig {
     /// Validate document configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.page_size.validate()?;
-        self.margins.validate()?;
-        self.default_font.validate()?;
-        Ok(())
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.page_size.check(&mut checker.field("page_size"));
+        self.margins.check(&mut checker.field("margins"));
+        self.default_font.check(&mut checker.field("default_font"));
+        if self.default_tab_stop <= 0.0 {
+            checker.fail("default_tab_stop", ValidationError::InvalidSpacing);
+        }
+        if let Some(watermark) = &self.watermark {
+            watermark.check(&mut checker.field("watermark"));
+        }
+        self.table_of_contents.check(&mut checker.field("table_of_contents"));
     }
 }
 
@@ -421,6 +2411,9 @@ impl Default for DocumentConfig {
                 bold: false,
                 italic: false,
             },
+            default_tab_stop: default_tab_stop(),
+            watermark: None,
+            table_of_contents: TableOfContentsConfig::default(),
         }
     }
 }
@@ -428,18 +2421,35 @@ impl Default for DocumentConfig {
 impl StyleConfig {
     /// Validate style configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        // Validate heading levels and styles
-        for (&level, style) in &self.headings {
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        // Validate heading levels and styles, in level order so errors are
+        // reported in a stable order
+        let mut levels: Vec<_> = self.headings.keys().copied().collect();
+        levels.sort_unstable();
+        for level in levels {
+            let mut heading = checker.field(format!("headings.{}", level));
             if !(1..=6).contains(&level) {
-                return Err(ValidationError::InvalidHeadingLevel);
+                heading.fail_here(ValidationError::InvalidHeadingLevel);
             }
-            style.validate()?;
+            self.headings[&level].check(&mut heading);
         }
 
-        self.paragraph.validate()?;
-        self.code_block.validate()?;
-        self.table.validate()?;
-        Ok(())
+        self.paragraph.check(&mut checker.field("paragraph"));
+        self.code_block.check(&mut checker.field("code_block"));
+        self.code_lines.check(&mut checker.field("code_lines"));
+        self.table.check(&mut checker.field("table"));
+        self.first_heading_title.style.check(&mut checker.field("first_heading_title.style"));
+        let mut classes: Vec<_> = self.classes.iter().collect();
+        classes.sort_unstable_by_key(|(name, _)| name.as_str());
+        for (name, class) in classes {
+            class.check(&mut checker.field(format!("classes.{}", name)));
+        }
+        self.inline_html.kbd.check(&mut checker.field("inline_html.kbd"));
+        self.inline_html.samp.check(&mut checker.field("inline_html.samp"));
+        self.blockquote.check(&mut checker.field("blockquote"));
     }
 }
 
@@ -472,6 +2482,9 @@ impl Default for StyleConfig {
                     spacing_after: 6.0,
                     alignment: None,
                     numbering: None,
+                    numbering_style: None,
+                    numbering_separator: NumberingSeparator::default(),
+                    numbering_tab_stop: None,
                 },
             );
         }
@@ -498,7 +2511,7 @@ impl Default for StyleConfig {
                 background_color: Some("#f5f5f5".to_string()),
                 border_width: 1.0,
                 preserve_line_breaks: true,
-                line_spacing: 1.0,
+                    line_spacing: 1.0,
                 paragraph_spacing: 6.0,
             },
             table: TableStyle {
@@ -515,7 +2528,21 @@ impl Default for StyleConfig {
                     italic: false,
                 },
                 border_width: 1.0,
+                header_background: None,
+                alternate_row_background: None,
+                row_header_column: false,
+                table_alignment: TableAlignment::default(),
+                full_width: false,
+                wide_tables: WideTableConfig::default(),
             },
+            code_lines: CodeLinesStyle::default(),
+            classes: HashMap::new(),
+            title_block: TitleBlockConfig::default(),
+            first_heading_title: FirstHeadingTitleConfig::default(),
+            heading_bookmarks: HeadingBookmarksConfig::default(),
+            inline_html: InlineHtmlStyles::default(),
+            blockquote: BlockQuoteStyle::default(),
+            named_styles: NamedStylesConfig::default(),
         }
     }
 }
@@ -523,38 +2550,115 @@ impl Default for StyleConfig {
 impl ElementConfig {
     /// Validate element configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        self.image.validate()?;
-        self.list.validate()?;
-        self.link.validate()?;
-        Ok(())
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        self.image.check(&mut checker.field("image"));
+        self.list.check(&mut checker.field("list"));
+        self.link.check(&mut checker.field("link"));
+        self.quote_attribution.style.check(&mut checker.field("quote_attribution.style"));
+        self.emphasis_callouts.style.check(&mut checker.field("emphasis_callouts.style"));
+        self.link_buttons.style.check(&mut checker.field("link_buttons.style"));
+        self.code_title_bar.style.check(&mut checker.field("code_title_bar.style"));
+        self.abbreviations.check(&mut checker.field("abbreviations"));
+        self.note_block.check(&mut checker.field("note_block"));
+        if self.long_paragraphs.max_chars == Some(0) {
+            checker.fail("long_paragraphs.max_chars", ValidationError::InvalidParagraphLimit);
+        }
+        if self.max_nesting_depth == 0 {
+            checker.fail("max_nesting_depth", ValidationError::InvalidNestingDepth);
+        }
+    }
+}
+
+impl AbbreviationsConfig {
+    fn check(&self, checker: &mut Checker) {
+        let mut terms: Vec<_> = self.terms.iter().collect();
+        terms.sort();
+        for (abbreviation, full_name) in terms {
+            if abbreviation.is_empty() || abbreviation.contains(char::is_whitespace) {
+                let error = ValidationError::InvalidAbbreviation(format!("{:?} must be a single word", abbreviation));
+                checker.fail(format_args!("terms.{}", abbreviation), error);
+            } else if full_name.trim().is_empty() {
+                let error = ValidationError::InvalidAbbreviation("full name cannot be empty".to_string());
+                checker.fail(format_args!("terms.{}", abbreviation), error);
+            }
+        }
     }
 }
 
 impl ImageConfig {
     /// Validate image configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        if self.max_width <= 0.0 || self.max_height <= 0.0 {
-            return Err(ValidationError::InvalidImageDimensions);
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        if self.max_width <= 0.0 {
+            checker.fail("max_width", ValidationError::InvalidImageDimensions);
+        }
+        if self.max_height <= 0.0 {
+            checker.fail("max_height", ValidationError::InvalidImageDimensions);
+        }
+        if self.max_pixel_dimension == Some(0) {
+            checker.fail("max_pixel_dimension", ValidationError::InvalidImageDimensions);
+        }
+        if self.qrcode.size == 0 {
+            checker.fail("qrcode.size", ValidationError::InvalidImageDimensions);
+        }
+        if self.remote_timeout_seconds == 0 {
+            checker.fail("remote_timeout_seconds", ValidationError::InvalidRemoteImages("timeout must be positive"));
+        }
+        if self.remote_max_bytes == 0 {
+            checker.fail("remote_max_bytes", ValidationError::InvalidRemoteImages("size limit must be positive"));
+        }
+        if let Some(ref cache) = self.remote_cache {
+            let mut cache_checker = checker.field("remote_cache");
+            if cache.directory.trim().is_empty() {
+                cache_checker.fail(
+                    "directory",
+                    ValidationError::InvalidImageCache("directory cannot be empty".to_string()),
+                );
+            }
+            if cache.max_bytes == 0 {
+                cache_checker.fail(
+                    "max_bytes",
+                    ValidationError::InvalidImageCache("max_bytes must be positive".to_string()),
+                );
+            }
         }
-        Ok(())
     }
 }
 
 impl ListConfig {
     /// Validate list configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        if self.indent < 0.0 || self.spacing < 0.0 {
-            return Err(ValidationError::InvalidSpacing);
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        if self.indent < 0.0 {
+            checker.fail("indent", ValidationError::InvalidSpacing);
+        }
+        if self.spacing < 0.0 {
+            checker.fail("spacing", ValidationError::InvalidSpacing);
+        }
+        if self.ordered_styles.is_empty() {
+            let error = ValidationError::InvalidNumberingFormat("at least one ordered list style is required".to_string());
+            checker.fail("ordered_styles", error);
         }
-        Ok(())
     }
 }
 
 impl LinkConfig {
     /// Validate link configuration
     pub fn validate(&self) -> Result<(), ValidationError> {
-        validate_color(&self.color)?;
-        Ok(())
+        Checker::first_error(|checker| self.check(checker))
+    }
+
+    fn check(&self, checker: &mut Checker) {
+        checker.color("color", &self.color);
     }
 }
 
@@ -575,15 +2679,44 @@ impl Default for ElementConfig {
             image: ImageConfig {
                 max_width: 500.0,
                 max_height: 400.0,
+                max_pixel_dimension: None,
+                fetch_remote: false,
+                remote_timeout_seconds: default_remote_timeout_seconds(),
+                remote_max_bytes: default_remote_max_bytes(),
+                remote_cache: None,
+                total_size: None,
+                placement: ImagePlacement::Block,
+                qrcode: QrCodeConfig::default(),
             },
             list: ListConfig {
                 indent: 36.0,
                 spacing: 6.0,
+                ordered_styles: default_ordered_styles(),
+                tasks: TaskListConfig::default(),
             },
             link: LinkConfig {
                 color: "#0066cc".to_string(),
                 underline: true,
+                autolink_emails: false,
             },
+            emoji: EmojiConfig::default(),
+            checkboxes: CheckboxConfig::default(),
+            whitespace: WhitespaceConfig::default(),
+            references: ReferencesConfig::default(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            empty_headings: EmptyHeadingMode::default(),
+            horizontal_rule: HorizontalRuleConfig::default(),
+            footnotes: FootnotesConfig::default(),
+            code_captions: CodeCaptionConfig::default(),
+            code_title_bar: CodeTitleBarConfig::default(),
+            unicode_bullets: UnicodeBulletsConfig::default(),
+            quote_attribution: QuoteAttributionConfig::default(),
+            emphasis_callouts: EmphasisCalloutConfig::default(),
+            link_buttons: LinkButtonConfig::default(),
+            abbreviations: AbbreviationsConfig::default(),
+            note_block: NoteBlockConfig::default(),
+            revision_notes: RevisionNotesConfig::default(),
+            long_paragraphs: LongParagraphsConfig::default(),
         }
     }
 }
@@ -611,6 +2744,43 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_compact_shrinks_spacing_and_still_validates() {
+        let default = ConversionConfig::default();
+        let mut config = default.clone();
+        config.compact.enabled = true;
+        let compact = config.compacted();
+        assert!(compact.validate().is_ok());
+        assert!(!compact.compact.enabled);
+
+        assert!(compact.styles.paragraph.spacing_after < default.styles.paragraph.spacing_after);
+        assert!(compact.styles.paragraph.line_spacing < default.styles.paragraph.line_spacing);
+        assert!(compact.styles.paragraph.line_spacing >= 1.0);
+        assert!(compact.styles.code_block.paragraph_spacing < default.styles.code_block.paragraph_spacing);
+        assert!(compact.elements.list.spacing < default.elements.list.spacing);
+        assert!(compact.document.margins.top < default.document.margins.top);
+        for (level, heading) in &compact.styles.headings {
+            let original = &default.styles.headings[level];
+            assert!(heading.spacing_before < original.spacing_before);
+            assert!(heading.font.size < original.font.size);
+            assert_eq!(heading.font.size * 2.0, (heading.font.size * 2.0).round());
+        }
+
+        // Applied once only, and a no-op when disabled
+        let again = compact.clone().compacted();
+        assert_eq!(again.styles.paragraph.spacing_after, compact.styles.paragraph.spacing_after);
+        assert_eq!(default.clone().compacted().document.margins.left, default.document.margins.left);
+    }
+
+    #[test]
+    fn test_invalid_compact_factor() {
+        let mut config = ConversionConfig::default();
+        config.compact.margin_factor = 1.5;
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidCompactFactor("margin_factor"))));
+        config.compact.margin_factor = 0.0;
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_invalid_margins() {
         let mut config = ConversionConfig::default();
@@ -650,6 +2820,39 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_validation_errors_carry_field_paths() {
+        let mut config = ConversionConfig::default();
+        assert!(config.validate_all().is_empty());
+        assert!(config.validation_report().is_none());
+
+        config.styles.headings.get_mut(&3).unwrap().font.size = 0.0;
+        config.styles.classes.insert(
+            "note".to_string(),
+            ClassStyle { color: Some("red".to_string()), ..Default::default() },
+        );
+        config.document.margins.left = -1.0;
+
+        let errors = config.validate_all();
+        let paths: Vec<_> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["document.margins.left", "styles.headings.3.font.size", "styles.classes.note.color"]
+        );
+        assert!(matches!(errors[1].error, ValidationError::InvalidFontSize));
+        assert_eq!(
+            errors[1].to_string(),
+            "styles.headings.3.font.size: Invalid font size: must be positive"
+        );
+        assert!(config
+            .validation_report()
+            .unwrap()
+            .contains("styles.classes.note.color: Invalid color format: red"));
+
+        // validate still stops at the first error
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidMargins)));
+    }
+
     #[test]
     fn test_invalid_color() {
         let mut config = ConversionConfig::default();
@@ -663,6 +2866,226 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_watermark_validation() {
+        let mut config = ConversionConfig::default();
+        config.document.watermark = Some(WatermarkConfig::text("DRAFT"));
+        assert!(config.validate().is_ok());
+
+        let mut both = WatermarkConfig::text("DRAFT");
+        both.image_path = Some("logo.png".to_string());
+        config.document.watermark = Some(both);
+        assert!(matches!(
+            config.validate().unwrap_err(),
+            ValidationError::InvalidWatermark(_)
+        ));
+
+        let mut opaque = WatermarkConfig::text("DRAFT");
+        opaque.opacity = 1.5;
+        config.document.watermark = Some(opaque);
+        assert!(matches!(
+            config.validate().unwrap_err(),
+            ValidationError::InvalidWatermark(_)
+        ));
+    }
+
+    #[test]
+    fn test_watermark_yaml_defaults() {
+        let yaml = "text: CONFIDENTIAL\norientation: horizontal\n";
+        let watermark: WatermarkConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(watermark.text.as_deref(), Some("CONFIDENTIAL"));
+        assert_eq!(watermark.orientation, WatermarkOrientation::Horizontal);
+        assert_eq!(watermark.color, "#C0C0C0");
+        assert_eq!(watermark.opacity, 0.5);
+    }
+
+    #[test]
+    fn test_code_lines_yaml_defaults() {
+        let code_lines: CodeLinesStyle = serde_yaml::from_str("preserve_line_endings: true\n").unwrap();
+        assert!(code_lines.preserve_line_endings);
+        assert_eq!(code_lines.highlight_color, "#FFF2CC");
+
+        let mut config = ConversionConfig::default();
+        config.styles.code_lines.highlight_color = "yellowish".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_length_units_in_config() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        yaml["document"]["margins"]["top"] = "2cm".into();
+        yaml["document"]["margins"]["left"] = "1in".into();
+        yaml["document"]["margins"]["bottom"] = "36pt".into();
+        yaml["document"]["margins"]["right"] = 50.into();
+        yaml["elements"]["list"]["indent"] = "0.5in".into();
+        yaml["styles"]["paragraph"]["spacing_after"] = "5mm".into();
+        yaml["styles"]["code_block"]["paragraph_spacing"] = "0.25cm".into();
+
+        let config: ConversionConfig = serde_yaml::from_value(yaml).unwrap();
+        let margins = &config.document.margins;
+        assert!((margins.top - 56.69).abs() < 0.01);
+        assert_eq!(margins.left, 72.0);
+        assert_eq!(margins.bottom, 36.0);
+        assert_eq!(margins.right, 50.0);
+        assert_eq!(config.elements.list.indent, 36.0);
+        assert!((config.styles.paragraph.spacing_after - 14.17).abs() < 0.01);
+        assert!((config.styles.code_block.paragraph_spacing - 7.09).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_class_styles_from_yaml() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        yaml["styles"]["classes"] = serde_yaml::from_str(
+            "callout:\n  style_name: Callout\n  indent: 1cm\n  color: \"#003366\"\nbadge:\n  bold: true\n",
+        )
+        .unwrap();
+
+        let config: ConversionConfig = serde_yaml::from_value(yaml).unwrap();
+        let callout = &config.styles.classes["callout"];
+        assert_eq!(callout.style_name.as_deref(), Some("Callout"));
+        assert!((callout.indent.unwrap() - 28.35).abs() < 0.01);
+        assert_eq!(config.styles.classes["badge"].bold, Some(true));
+        assert!(config.validate().is_ok());
+
+        let mut invalid = config.clone();
+        invalid.styles.classes.get_mut("badge").unwrap().color = Some("red".to_string());
+        assert!(matches!(invalid.validate(), Err(ValidationError::InvalidColor(_))));
+
+        let mut highlighted = config.clone();
+        highlighted.styles.classes.get_mut("badge").unwrap().highlight = Some("red".to_string());
+        assert!(highlighted.validate().is_ok());
+        highlighted.styles.classes.get_mut("badge").unwrap().highlight = Some("orange".to_string());
+        assert!(matches!(highlighted.validate(), Err(ValidationError::InvalidColor(_))));
+    }
+
+    #[test]
+    fn test_relative_font_sizes_resolved_on_load() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        yaml["document"]["default_font"]["size"] = 12.into();
+        yaml["styles"]["headings"][1]["font"]["size"] = "150%".into();
+        yaml["styles"]["table"]["cell_font"]["size"] = "0.9x".into();
+        yaml["styles"]["paragraph"]["font"]["size"] = "11pt".into();
+
+        let config = ConversionConfig::from_yaml_str(&serde_yaml::to_string(&yaml).unwrap()).unwrap();
+        assert_eq!(config.styles.headings[&1].font.size, 18.0);
+        assert!((config.styles.table.cell_font.size - 10.8).abs() < 0.01);
+        assert_eq!(config.styles.paragraph.font.size, 11.0);
+        assert!(config.validate().is_ok());
+
+        yaml["document"]["default_font"]["size"] = "200%".into();
+        assert!(ConversionConfig::from_yaml_str(&serde_yaml::to_string(&yaml).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_numbering_style_from_yaml() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        yaml["styles"]["headings"][1]["numbering_style"] =
+            serde_yaml::from_str("bold: false\ncolor: \"#cc0000\"\n").unwrap();
+
+        let mut config: ConversionConfig = serde_yaml::from_value(yaml).unwrap();
+        let prefix_style = config.styles.headings[&1].numbering_style.as_ref().unwrap();
+        assert_eq!(prefix_style.bold, Some(false));
+        assert_eq!(prefix_style.color.as_deref(), Some("#cc0000"));
+        assert!(config.styles.headings[&2].numbering_style.is_none());
+        assert!(config.validate().is_ok());
+
+        config.styles.headings.get_mut(&1).unwrap().numbering_style.as_mut().unwrap().color =
+            Some("red".to_string());
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidColor(_))));
+    }
+
+    #[test]
+    fn test_numbering_separator_from_yaml() {
+        let separators: Vec<NumberingSeparator> =
+            serde_yaml::from_str("[space, tab, em_space, \" - \"]").unwrap();
+        assert_eq!(
+            separators.iter().map(NumberingSeparator::as_str).collect::<Vec<_>>(),
+            vec![" ", "\t", "\u{2003}", " - "]
+        );
+
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        yaml["styles"]["headings"][1]["numbering_tab_stop"] = "1cm".into();
+        let mut config: ConversionConfig = serde_yaml::from_value(yaml).unwrap();
+        assert!((config.styles.headings[&1].numbering_tab_stop.unwrap() - 28.35).abs() < 0.01);
+        assert_eq!(config.styles.headings[&1].numbering_separator, NumberingSeparator::Space);
+
+        config.styles.headings.get_mut(&1).unwrap().numbering_tab_stop = Some(0.0);
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidSpacing)));
+    }
+
+    #[test]
+    fn test_appended_section_validation() {
+        let mut config = ConversionConfig::default();
+        assert_eq!(config.sections.footnotes.title, "Notes");
+        assert!(config.sections.footnotes.in_toc);
+        assert!(!config.sections.footnotes.numbered);
+
+        config.sections.references.title = "  ".to_string();
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidSectionTitle)));
+
+        config.sections.references = AppendedSectionConfig::new("Bibliography");
+        config.sections.footnotes.level = 7;
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidHeadingLevel)));
+    }
+
+    #[test]
+    fn test_max_nesting_depth_validation() {
+        let mut config = ConversionConfig::default();
+        assert_eq!(config.elements.max_nesting_depth, DEFAULT_MAX_NESTING_DEPTH);
+        config.elements.max_nesting_depth = 0;
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidNestingDepth)));
+    }
+
+    #[test]
+    fn test_remote_image_cache_defaults_and_validation() {
+        let image: ImageConfig = serde_yaml::from_str(
+            "max_width: 500\nmax_height: 400\nremote_cache:\n  directory: .md2docx-cache\n",
+        )
+        .unwrap();
+        let cache = image.remote_cache.as_ref().unwrap();
+        assert_eq!(cache.ttl_seconds, 24 * 60 * 60);
+        assert_eq!(cache.max_bytes, 100 * 1024 * 1024);
+        assert!(!image.fetch_remote);
+        assert_eq!((image.remote_timeout_seconds, image.remote_max_bytes), (10, 10 * 1024 * 1024));
+        assert!(image.validate().is_ok());
+        let no_timeout = ImageConfig { remote_timeout_seconds: 0, ..image.clone() };
+        assert!(matches!(no_timeout.validate(), Err(ValidationError::InvalidRemoteImages(_))));
+
+        let mut config = ConversionConfig::default();
+        config.elements.image.remote_cache = Some(RemoteImageCacheConfig {
+            directory: " ".to_string(),
+            ttl_seconds: 60,
+            max_bytes: 1024,
+        });
+        assert!(matches!(config.validate(), Err(ValidationError::InvalidImageCache(_))));
+    }
+
+    #[test]
+    fn test_horizontal_rule_mode_from_yaml() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        assert_eq!(yaml["elements"]["horizontal_rule"]["mode"], serde_yaml::Value::from("line"));
+        yaml["elements"]["horizontal_rule"] = serde_yaml::from_str("mode: ornament\nornament: \"~ ❦ ~\"\n").unwrap();
+        let config: ConversionConfig = serde_yaml::from_value(yaml).unwrap();
+        assert_eq!(config.elements.horizontal_rule.mode, HorizontalRuleMode::Ornament);
+        assert_eq!(config.elements.horizontal_rule.ornament, "~ ❦ ~");
+    }
+
+    #[test]
+    fn test_empty_headings_mode_from_yaml() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        assert_eq!(yaml["elements"]["empty_headings"], serde_yaml::Value::from("number"));
+        yaml["elements"]["empty_headings"] = "skip".into();
+        let config: ConversionConfig = serde_yaml::from_value(yaml).unwrap();
+        assert_eq!(config.elements.empty_headings, EmptyHeadingMode::Skip);
+    }
+
+    #[test]
+    fn test_length_unit_rejects_unknown_suffix() {
+        let mut yaml = serde_yaml::to_value(ConversionConfig::default()).unwrap();
+        yaml["document"]["margins"]["top"] = "2parsecs".into();
+        assert!(serde_yaml::from_value::<ConversionConfig>(yaml).is_err());
+    }
+
     #[test]
     fn test_valid_color_formats() {
         let valid_colors = vec!["#ff0000", "#00FF00", "#0000ff", "#123456"];
@@ -721,6 +3144,22 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_heading_numbering_accepts_roman_and_letters() {
+        let mut config = ConversionConfig::default();
+        for format in ["%I.", "%i.", "%A.", "%a)", "%I.%A.", "%1.%a", "%A2"] {
+            config.styles.headings.get_mut(&1).unwrap().numbering = Some(format.to_string());
+            assert!(config.validate().is_ok(), "Format '{}' should be valid", format);
+        }
+        for format in ["%A7", "%1.%2.%3.%4.%5.%6.%a"] {
+            config.styles.headings.get_mut(&1).unwrap().numbering = Some(format.to_string());
+            assert!(matches!(
+                config.validate(),
+                Err(ValidationError::InvalidNumberingFormat(_))
+            ), "Format '{}' should be invalid", format);
+        }
+    }
+
     #[test]
     fn test_numbering_field_serialization() {
         let mut config = ConversionConfig::default();
@@ -1364,4 +3803,19 @@ font:
             ValidationError::InvalidSpacing
         ));
     }
+
+    #[test]
+    fn test_list_number_styles() {
+        assert_eq!(ListNumberStyle::Decimal.format(12), "12");
+        assert_eq!(ListNumberStyle::LowerAlpha.format(3), "c");
+        assert_eq!(ListNumberStyle::LowerAlpha.format(27), "aa");
+        assert_eq!(ListNumberStyle::UpperAlpha.format(26), "Z");
+        assert_eq!(ListNumberStyle::LowerRoman.format(4), "iv");
+        assert_eq!(ListNumberStyle::UpperRoman.format(1994), "MCMXCIV");
+
+        let list: ListConfig = serde_yaml::from_str("indent: 36\nspacing: 6\nordered_styles: [decimal, lower_alpha, lower_roman]").unwrap();
+        assert_eq!(list.ordered_styles[2], ListNumberStyle::LowerRoman);
+        let list = ListConfig { ordered_styles: Vec::new(), ..list };
+        assert!(list.validate().is_err());
+    }
 }
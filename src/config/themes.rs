@@ -0,0 +1,137 @@
+//! Built-in themes and configuration overrides
+//!
+//! A theme is a partial configuration laid over the active one: only the
+//! settings it names change. Overrides given as YAML, such as those in a
+//! document's front matter, are applied the same way.
+
+use crate::config::units::resolve_relative_sizes;
+use crate::config::ConversionConfig;
+use crate::error::ConfigError;
+use serde_yaml::Value;
+
+/// Serif body text with double line spacing and plain black headings
+const ACADEMIC: &str = r##"
+document:
+  default_font: { family: "Times New Roman", size: 12.0 }
+styles:
+  paragraph:
+    font: { family: "Times New Roman", size: 12.0 }
+    line_spacing: 2.0
+    spacing_after: 0.0
+  headings:
+    1: { font: { family: "Times New Roman", size: 16.0, bold: true }, alignment: "center" }
+    2: { font: { family: "Times New Roman", size: 14.0, bold: true } }
+    3: { font: { family: "Times New Roman", size: 12.0, bold: true } }
+  table:
+    header_font: { family: "Times New Roman", size: 11.0 }
+    cell_font: { family: "Times New Roman", size: 11.0 }
+elements:
+  link: { color: "#000000" }
+"##;
+
+/// Sans-serif text with colored links and shaded table headers
+const MODERN: &str = r##"
+document:
+  default_font: { family: "Calibri", size: 11.0 }
+styles:
+  paragraph:
+    font: { family: "Calibri", size: 11.0 }
+    line_spacing: 1.15
+  headings:
+    1: { font: { family: "Calibri Light", size: 20.0 } }
+    2: { font: { family: "Calibri Light", size: 16.0 } }
+    3: { font: { family: "Calibri Light", size: 13.0 } }
+  table:
+    header_font: { family: "Calibri", size: 11.0 }
+    cell_font: { family: "Calibri", size: 11.0 }
+    header_background: "#DEEAF6"
+elements:
+  link: { color: "#2E74B5" }
+"##;
+
+/// Names of the built-in themes
+pub const THEMES: [&str; 2] = ["academic", "modern"];
+
+/// The overrides making up a built-in theme, if `name` is one
+pub fn theme(name: &str) -> Option<Value> {
+    let yaml = match name.trim().to_lowercase().as_str() {
+        "academic" => ACADEMIC,
+        "modern" => MODERN,
+        _ => return None,
+    };
+    Some(serde_yaml::from_str(yaml).expect("built-in themes are valid YAML"))
+}
+
+/// Apply a built-in theme to a configuration
+pub fn apply_theme(config: &ConversionConfig, name: &str) -> Result<ConversionConfig, ConfigError> {
+    let overrides = theme(name).ok_or_else(|| {
+        ConfigError::NotFound(format!("theme '{}' (available: {})", name, THEMES.join(", ")))
+    })?;
+    apply_overrides(config, overrides)
+}
+
+/// Lay a partial configuration over `config`
+///
+/// Mappings are merged key by key; any other value replaces the one it
+/// overrides. Font sizes may be relative, as in a configuration file. The
+/// result is validated.
+pub fn apply_overrides(config: &ConversionConfig, overrides: Value) -> Result<ConversionConfig, ConfigError> {
+    let mut value = serde_yaml::to_value(config)?;
+    merge(&mut value, overrides);
+    resolve_relative_sizes(&mut value).map_err(ConfigError::InvalidYaml)?;
+    let merged: ConversionConfig = serde_yaml::from_value(value)?;
+    if let Some(report) = merged.validation_report() {
+        return Err(ConfigError::Validation(report));
+    }
+    Ok(merged)
+}
+
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Mapping(base), Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_themes_override_only_what_they_name() {
+        let mut config = ConversionConfig::default();
+        config.elements.image.max_width = 321.0;
+
+        let academic = apply_theme(&config, "Academic").unwrap();
+        assert_eq!(academic.styles.paragraph.font.family, "Times New Roman");
+        assert_eq!(academic.styles.paragraph.line_spacing, 2.0);
+        assert_eq!(academic.styles.headings[&1].alignment.as_deref(), Some("center"));
+        assert_eq!(academic.styles.headings[&4].font.family, config.styles.headings[&4].font.family);
+        assert_eq!(academic.elements.image.max_width, 321.0);
+
+        for name in THEMES {
+            assert!(apply_theme(&config, name).is_ok(), "theme {} applies", name);
+        }
+        assert!(matches!(apply_theme(&config, "neon"), Err(ConfigError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_overrides_validated() {
+        let config = ConversionConfig::default();
+        let overrides = serde_yaml::from_str("styles: { paragraph: { font: { size: '150%' } } }").unwrap();
+        let merged = apply_overrides(&config, overrides).unwrap();
+        assert_eq!(merged.styles.paragraph.font.size, config.document.default_font.size * 1.5);
+
+        let invalid = serde_yaml::from_str("elements: { link: { color: 'blue' } }").unwrap();
+        assert!(matches!(apply_overrides(&config, invalid), Err(ConfigError::Validation(_))));
+    }
+}
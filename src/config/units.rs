@@ -0,0 +1,317 @@
+//! Length values with unit suffixes
+//!
+//! Lengths in the configuration are stored in points. In YAML or JSON they may
+//! also be written as strings with a unit suffix (`"2cm"`, `"0.5in"`,
+//! `"14pt"`, `"10mm"`); bare numbers are points.
+//!
+//! Font sizes may additionally be relative to `document.default_font.size`,
+//! as a percentage (`"150%"`) or a multiplier (`"1.5x"`, `"1.5em"`), and
+//! spacings relative to the font size of the style they belong to, in ems
+//! (`"0.5em"`). These are resolved to points on the raw YAML value before it
+//! is deserialized.
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::fmt;
+
+const POINTS_PER_INCH: f32 = 72.0;
+const POINTS_PER_CM: f32 = POINTS_PER_INCH / 2.54;
+
+/// A length converted to points during deserialization
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length(pub f32);
+
+/// Parse a length such as `"2cm"` or `"12"` into points
+pub fn parse_length(value: &str) -> Result<f32, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f32 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid length '{}'", value))?;
+
+    let factor = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "pt" => 1.0,
+        "in" => POINTS_PER_INCH,
+        "cm" => POINTS_PER_CM,
+        "mm" => POINTS_PER_CM / 10.0,
+        other => {
+            return Err(format!(
+                "unknown unit '{}' in length '{}' (expected pt, cm, mm or in)",
+                other, value
+            ))
+        }
+    };
+    Ok(number * factor)
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct LengthVisitor;
+
+        impl<'de> Visitor<'de> for LengthVisitor {
+            type Value = Length;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number of points or a string like \"2cm\", \"0.5in\" or \"14pt\"")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Length, E> {
+                Ok(Length(value as f32))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Length, E> {
+                Ok(Length(value as f32))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Length, E> {
+                Ok(Length(value as f32))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Length, E> {
+                parse_length(value).map(Length).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(LengthVisitor)
+    }
+}
+
+/// `deserialize_with` helper for `f32` fields holding points
+pub fn deserialize_length<'de, D>(deserializer: D) -> Result<f32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Length::deserialize(deserializer).map(|length| length.0)
+}
+
+/// Scale factor of a relative font size such as `"150%"` or `"1.5x"`, or
+/// `None` when the value is an absolute length
+pub fn parse_relative_size(value: &str) -> Result<Option<f32>, String> {
+    let value = value.trim();
+    let (number, divisor) = if let Some(number) = value.strip_suffix('%') {
+        (number, 100.0)
+    } else if let Some(number) = value.strip_suffix("em").or_else(|| value.strip_suffix('x')) {
+        (number, 1.0)
+    } else {
+        return Ok(None);
+    };
+    let scale: f32 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid relative size '{}'", value))?;
+    if scale <= 0.0 {
+        return Err(format!("relative size '{}' must be positive", value));
+    }
+    Ok(Some(scale / divisor))
+}
+
+/// Replace relative font sizes and spacings in a raw configuration with points
+///
+/// Relative values are accepted in the `size` of any `*font` mapping and in
+/// class `font_size`s. The base, `document.default_font.size`, must itself
+/// be absolute. `spacing_before` and `spacing_after` in ems are
+/// multiplied by the `font` size next to them, or by the base when their
+/// style has no font of its own.
+pub fn resolve_relative_sizes(config: &mut serde_yaml::Value) -> Result<(), String> {
+    let base = match config
+        .get("document")
+        .and_then(|document| document.get("default_font"))
+        .and_then(|font| font.get("size"))
+    {
+        Some(serde_yaml::Value::String(size)) => {
+            if parse_relative_size(size)?.is_some() {
+                return Err(format!(
+                    "document.default_font.size '{}' must be an absolute size",
+                    size
+                ));
+            }
+            parse_length(size)?
+        }
+        Some(size) => match size.as_f64() {
+            Some(size) => size as f32,
+            None => return Ok(()),
+        },
+        None => return Ok(()),
+    };
+    resolve_sizes_in(config, base)
+}
+
+fn resolve_sizes_in(value: &mut serde_yaml::Value, base: f32) -> Result<(), String> {
+    match value {
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter_mut() {
+                let key = key.as_str().unwrap_or_default();
+                if key.ends_with("font") {
+                    if let Some(size) = child.get_mut("size") {
+                        resolve_size(size, base)?;
+                    }
+                } else if key == "font_size" {
+                    resolve_size(child, base)?;
+                }
+                resolve_sizes_in(child, base)?;
+            }
+
+            // Font sizes are in points by now
+            let font_size = mapping
+                .get("font")
+                .and_then(|font| font.get("size"))
+                .and_then(serde_yaml::Value::as_f64)
+                .map_or(base, |size| size as f32);
+            for (key, child) in mapping.iter_mut() {
+                if matches!(key.as_str(), Some("spacing_before" | "spacing_after")) {
+                    resolve_spacing(child, font_size)?;
+                }
+            }
+        }
+        serde_yaml::Value::Sequence(items) => {
+            for item in items {
+                resolve_sizes_in(item, base)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn resolve_size(size: &mut serde_yaml::Value, base: f32) -> Result<(), String> {
+    if let serde_yaml::Value::String(text) = size {
+        if let Some(scale) = parse_relative_size(text)? {
+            *size = serde_yaml::Value::from(f64::from(base * scale));
+        }
+    }
+    Ok(())
+}
+
+/// Spacing in ems, such as `"0.5em"`, as a multiple of the font size; `None`
+/// when the value is an absolute length
+pub fn parse_em(value: &str) -> Result<Option<f32>, String> {
+    let value = value.trim();
+    let Some(number) = value.strip_suffix("em") else {
+        return Ok(None);
+    };
+    let ems: f32 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid spacing '{}'", value))?;
+    if ems < 0.0 {
+        return Err(format!("spacing '{}' cannot be negative", value));
+    }
+    Ok(Some(ems))
+}
+
+fn resolve_spacing(spacing: &mut serde_yaml::Value, font_size: f32) -> Result<(), String> {
+    if let serde_yaml::Value::String(text) = spacing {
+        if let Some(ems) = parse_em(text)? {
+            *spacing = serde_yaml::Value::from(f64::from(font_size * ems));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert!(approx(parse_length("2cm").unwrap(), 56.69));
+        assert!(approx(parse_length("0.5in").unwrap(), 36.0));
+        assert!(approx(parse_length("14pt").unwrap(), 14.0));
+        assert!(approx(parse_length("10 mm").unwrap(), 28.35));
+        assert!(approx(parse_length("12").unwrap(), 12.0));
+    }
+
+    #[test]
+    fn test_parse_invalid_lengths() {
+        assert!(parse_length("2furlongs").is_err());
+        assert!(parse_length("cm").is_err());
+        assert!(parse_length("").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_number_and_string() {
+        let values: Vec<Length> = serde_yaml::from_str("[72, 36.5, \"1in\", \"2.54cm\"]").unwrap();
+        assert_eq!(values[0], Length(72.0));
+        assert_eq!(values[1], Length(36.5));
+        assert!(approx(values[2].0, 72.0));
+        assert!(approx(values[3].0, 72.0));
+
+        let json: Length = serde_json::from_str("\"14pt\"").unwrap();
+        assert_eq!(json, Length(14.0));
+    }
+
+    #[test]
+    fn test_parse_relative_sizes() {
+        assert_eq!(parse_relative_size("150%").unwrap(), Some(1.5));
+        assert_eq!(parse_relative_size("1.25x").unwrap(), Some(1.25));
+        assert_eq!(parse_relative_size("0.8em").unwrap(), Some(0.8));
+        assert_eq!(parse_relative_size("14pt").unwrap(), None);
+        assert!(parse_relative_size("big%").is_err());
+        assert!(parse_relative_size("-50%").is_err());
+    }
+
+    #[test]
+    fn test_resolve_relative_font_sizes() {
+        let yaml = r#"
+document:
+  default_font: { family: Arial, size: "12pt" }
+styles:
+  headings:
+    1:
+      font: { family: Arial, size: "150%" }
+  classes:
+    small: { font_size: 0.75x }
+"#;
+        let mut config: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        resolve_relative_sizes(&mut config).unwrap();
+        assert_eq!(config["styles"]["headings"][1]["font"]["size"].as_f64(), Some(18.0));
+        assert_eq!(config["styles"]["classes"]["small"]["font_size"].as_f64(), Some(9.0));
+
+        let mut relative_base: serde_yaml::Value =
+            serde_yaml::from_str("document:\n  default_font: { family: Arial, size: 120% }\n").unwrap();
+        assert!(resolve_relative_sizes(&mut relative_base).is_err());
+    }
+
+    #[test]
+    fn test_resolve_em_spacing_against_font_size() {
+        let yaml = r#"
+document:
+  default_font: { family: Arial, size: 12 }
+styles:
+  headings:
+    1:
+      font: { family: Arial, size: 24 }
+      spacing_before: 0.5em
+      spacing_after: "6pt"
+    2:
+      font: { family: Arial, size: "150%" }
+      spacing_before: 1em
+  paragraph:
+    spacing_after: 0.5em
+"#;
+        let mut config: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        resolve_relative_sizes(&mut config).unwrap();
+        let headings = &config["styles"]["headings"];
+        assert_eq!(headings[1]["spacing_before"].as_f64(), Some(12.0));
+        assert_eq!(headings[1]["spacing_after"].as_str(), Some("6pt"));
+        // Against the heading's resolved font size
+        assert_eq!(headings[2]["spacing_before"].as_f64(), Some(18.0));
+        // No font of its own: against the default font
+        assert_eq!(config["styles"]["paragraph"]["spacing_after"].as_f64(), Some(6.0));
+
+        assert_eq!(parse_em("1.25em").unwrap(), Some(1.25));
+        assert_eq!(parse_em("12pt").unwrap(), None);
+        assert!(parse_em("-1em").is_err());
+    }
+}
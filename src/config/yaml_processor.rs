@@ -16,12 +16,11 @@ impl YamlProcessor {
 
     /// Parse YAML string into configuration
     pub async fn parse(&self, yaml: &str) -> Result<ConversionConfig, ConfigError> {
-        let config: ConversionConfig = serde_yaml::from_str(yaml)
+        let config = ConversionConfig::from_yaml_str(yaml)
             .map_err(|e| ConfigError::InvalidYaml(e.to_string()))?;
         
         // Validate the parsed configuration
-        config.validate()
-            .map_err(|e| ConfigError::Validation(e.to_string()))?;
+        self.validate(&config)?;
         
         Ok(config)
     }
@@ -37,8 +36,7 @@ impl YamlProcessor {
     /// Serialize configuration to YAML string
     pub fn serialize(&self, config: &ConversionConfig) -> Result<String, ConfigError> {
         // Validate before serializing
-        config.validate()
-            .map_err(|e| ConfigError::Validation(e.to_string()))?;
+        self.validate(config)?;
         
         serde_yaml::to_string(config)
             .map_err(|e| ConfigError::InvalidYaml(e.to_string()))
@@ -56,8 +54,10 @@ impl YamlProcessor {
 
     /// Validate configuration structure and values
     pub fn validate(&self, config: &ConversionConfig) -> Result<(), ConfigError> {
-        config.validate()
-            .map_err(|e| ConfigError::Validation(e.to_string()))
+        match config.validation_report() {
+            Some(report) => Err(ConfigError::Validation(report)),
+            None => Ok(()),
+        }
     }
 
     /// Create a default configuration and save it to a file
@@ -79,8 +79,7 @@ impl YamlProcessor {
         merged.elements = override_config.elements.clone();
         
         // Validate the merged configuration
-        merged.validate()
-            .map_err(|e| ConfigError::Validation(e.to_string()))?;
+        self.validate(&merged)?;
         
         Ok(merged)
     }
@@ -0,0 +1,99 @@
+//! Worker-pool sizing shared by batch conversion, code block processing and
+//! the web task queue
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Default worker-pool size: the number of CPUs available to the process
+pub fn default_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Run `work` on every item using at most `workers` threads
+///
+/// Each thread builds its own state with `init` and takes items in order
+/// until none are left. Results are returned in item order. With one worker
+/// (or one item) everything runs on the calling thread.
+pub fn run_bounded<T, S, R>(
+    items: &[T],
+    workers: usize,
+    init: impl Fn() -> S + Sync,
+    work: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let workers = workers.clamp(1, items.len().max(1));
+    if workers == 1 {
+        let mut state = init();
+        return items.iter().map(|item| work(&mut state, item)).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                let mut state = init();
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = work(&mut state, item);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Run 8 items that each take 20ms and report the peak number running at once
+    fn peak_parallelism(workers: usize) -> usize {
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let items: Vec<usize> = (0..8).collect();
+        let results = run_bounded(&items, workers, || (), |_, item| {
+            let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            active.fetch_sub(1, Ordering::SeqCst);
+            item * 2
+        });
+        assert_eq!(results, (0..8).map(|i| i * 2).collect::<Vec<_>>());
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_single_worker_serializes_work() {
+        assert_eq!(peak_parallelism(1), 1);
+    }
+
+    #[test]
+    fn test_more_workers_run_concurrently_within_limit() {
+        let peak = peak_parallelism(4);
+        assert!(peak > 1 && peak <= 4, "peak parallelism {}", peak);
+        // More workers than items and a zero limit both still finish
+        assert_eq!(peak_parallelism(64), 8);
+        assert_eq!(peak_parallelism(0), 1);
+    }
+
+    #[test]
+    fn test_default_concurrency_is_positive() {
+        assert!(default_concurrency() >= 1);
+    }
+}
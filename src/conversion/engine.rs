@@ -1,18 +1,32 @@
 //! Main conversion engine that orchestrates Markdown parsing and docx generation
 
-use crate::config::ConversionConfig;
-use crate::docx::DocxGenerator;
-use crate::error::ConversionError;
-use crate::markdown::MarkdownParser;
+use crate::config::{themes, ConversionConfig, ParsingProfile, UndefinedVariableMode};
+use crate::conversion::{concurrency, pdf, ConversionSummary, TimingReport};
+use crate::docx::{DocxGenerator, OutlineEntry};
+use crate::error::{CodeBlockFailure, ConversionError};
+use crate::markdown::{front_matter, includes, variables, CodeBlockMut, MarkdownDocument, MarkdownElement, MarkdownParser};
 use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::Path;
-use tracing::{info, debug, error, instrument};
+use std::time::{Duration, Instant};
+use tracing::{info, debug, error, warn, instrument};
+
+/// Callback run on every top-level code block between parsing and generation
+pub type CodeBlockHook = Box<dyn FnMut(CodeBlockMut<'_>) + Send>;
+
+/// Front matter keys that override configuration sections
+const FRONT_MATTER_SECTIONS: [&str; 5] = ["document", "styles", "elements", "sections", "compact"];
 
 /// Main conversion engine that coordinates the conversion process
 pub struct ConversionEngine {
     config: ConversionConfig,
     markdown_parser: MarkdownParser,
     docx_generator: DocxGenerator,
+    code_block_hook: Option<CodeBlockHook>,
+    /// Most files converted at once by `convert_batch`
+    concurrency: usize,
+    /// Top-level elements of the last document converted
+    last_element_count: usize,
 }
 
 impl ConversionEngine {
@@ -22,54 +36,336 @@ impl ConversionEngine {
         debug!("Configuration: {:?}", config);
         
         // Create markdown parser with code block processing config if available
-        let markdown_parser = if let Some(code_block_config) = &config.code_block_processing {
+        let mut markdown_parser = if let Some(code_block_config) = &config.code_block_processing {
             info!("Using code block processing configuration");
             MarkdownParser::with_code_block_config(code_block_config.clone())
         } else {
             info!("Using default markdown parser (no code block processing config)");
             MarkdownParser::new()
         };
+        Self::configure_parser(&mut markdown_parser, &config);
         
         Self {
             config: config.clone(),
             markdown_parser,
             docx_generator: DocxGenerator::new(config),
+            code_block_hook: None,
+            concurrency: concurrency::default_concurrency(),
+            last_element_count: 0,
         }
     }
 
+    /// Apply the parser settings taken from the configuration, for both a
+    /// new engine and [`update_config`](Self::update_config)
+    fn configure_parser(parser: &mut MarkdownParser, config: &ConversionConfig) {
+        parser.set_max_nesting_depth(config.elements.max_nesting_depth);
+        parser.set_profile(config.parsing.profile);
+        parser.set_unicode_bullets(config.elements.unicode_bullets.enabled);
+        parser.set_quote_attribution(config.elements.quote_attribution.enabled);
+    }
+
+    /// Limit how many files [`convert_batch`](Self::convert_batch) converts
+    /// at once; `1` converts them one after another
+    ///
+    /// Defaults to the number of CPUs. Values below 1 are treated as 1.
+    pub fn set_concurrency(&mut self, workers: usize) {
+        self.concurrency = workers.max(1);
+    }
+
+    /// Most files converted at once by [`convert_batch`](Self::convert_batch)
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Run `hook` on every top-level code block before the document is
+    /// generated
+    ///
+    /// The hook sees blocks after the configured code block processing and
+    /// may inspect, validate or rewrite them through their `processed`
+    /// result. Errors it records there fail the conversion in strict mode.
+    pub fn set_code_block_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(CodeBlockMut<'_>) + Send + 'static,
+    {
+        self.code_block_hook = Some(Box::new(hook));
+    }
+
     /// Convert Markdown string to docx bytes
     #[instrument(skip(self, markdown), fields(markdown_length = markdown.len()))]
     pub async fn convert(&mut self, markdown: &str) -> Result<Vec<u8>, ConversionError> {
+        self.convert_timed(markdown, None)
+    }
+
+    /// Convert Markdown string to docx bytes, reporting the time spent in
+    /// each phase
+    ///
+    /// Timing is only collected by this method; [`convert`](Self::convert)
+    /// takes no timestamps.
+    #[instrument(skip(self, markdown), fields(markdown_length = markdown.len()))]
+    pub async fn convert_with_timing(&mut self, markdown: &str) -> Result<(Vec<u8>, TimingReport), ConversionError> {
+        let mut report = TimingReport::default();
+        let docx_bytes = self.convert_timed(markdown, Some(&mut report))?;
+        debug!(?report, "Conversion timing");
+        Ok((docx_bytes, report))
+    }
+
+    /// Convert Markdown string to docx, packing it straight into `writer`
+    /// rather than into memory
+    pub async fn convert_to_writer<W: Write>(&mut self, markdown: &str, writer: W) -> Result<(), ConversionError> {
+        self.convert_into(markdown, None, writer)
+    }
+
+    fn convert_timed(&mut self, markdown: &str, report: Option<&mut TimingReport>) -> Result<Vec<u8>, ConversionError> {
+        let mut docx_bytes = Vec::new();
+        self.convert_into(markdown, report, &mut docx_bytes)?;
+        info!("Successfully generated docx document ({} bytes)", docx_bytes.len());
+        Ok(docx_bytes)
+    }
+
+    fn convert_into<W: Write>(
+        &mut self,
+        markdown: &str,
+        report: Option<&mut TimingReport>,
+        writer: W,
+    ) -> Result<(), ConversionError> {
+        if let Some((mut engine, body)) = self.front_matter_engine(markdown) {
+            engine.code_block_hook = self.code_block_hook.take();
+            let result = engine.convert_into(body, report, writer);
+            self.code_block_hook = engine.code_block_hook.take();
+            self.last_element_count = engine.last_element_count;
+            return result;
+        }
+
+        let start = report.is_some().then(Instant::now);
         info!("Starting Markdown to docx conversion");
         debug!("Markdown content length: {} characters", markdown.len());
         
         // Step 1: Parse Markdown to AST
         debug!("Parsing Markdown to AST");
-        let document = self.markdown_parser.parse(markdown)
+        let mut document = self.markdown_parser.parse(markdown)
             .map_err(|e| {
                 error!("Failed to parse Markdown: {}", e);
                 e
             })?;
         
+        let parsed = start.map(|start| start.elapsed());
+        
         info!("Successfully parsed Markdown into {} elements", document.elements.len());
+        self.last_element_count = document.elements.len();
+        self.substitute_variables(&mut document)?;
         debug!("Document elements: {:?}", document.elements);
         
+        if let Some(hook) = self.code_block_hook.as_mut() {
+            debug!("Running code block hook");
+            document.code_blocks_mut().for_each(hook);
+        }
+        
+        if self.is_strict() {
+            Self::check_code_blocks(&document)?;
+        }
+        
         // Step 2: Generate docx from AST
         debug!("Generating docx from AST");
-        let docx_bytes = self.docx_generator.generate(&document)
+        let generation_start = start.map(|_| Instant::now());
+        self.docx_generator.set_timing(start.is_some());
+        let generated = self.docx_generator.generate_with_source_to_writer(&document, markdown, writer);
+        let timings = self.docx_generator.timings().unwrap_or_default();
+        self.docx_generator.set_timing(false);
+        generated
             .map_err(|e| {
                 error!("Failed to generate docx: {}", e);
                 e
             })?;
         
-        info!("Successfully generated docx document ({} bytes)", docx_bytes.len());
-        Ok(docx_bytes)
+        if let (Some(report), Some(start), Some(parsed), Some(generation_start)) =
+            (report, start, parsed, generation_start)
+        {
+            let generation = generation_start.elapsed();
+
+            let code_blocks: Duration = document
+                .code_blocks()
+                .filter_map(|block| block.processed)
+                .map(|processed| processed.metadata.processing_time)
+                .sum();
+            *report = TimingReport {
+                parse: parsed.saturating_sub(code_blocks),
+                code_blocks: code_blocks.min(parsed),
+                numbering: timings.numbering,
+                images: timings.images,
+                docx_build: generation.saturating_sub(timings.numbering + timings.images),
+                total: start.elapsed(),
+            };
+        }
+        
+        Ok(())
+    }
+
+    /// An engine configured by the document's front matter, and the Markdown
+    /// after it, when front matter is enabled and the document has some
+    ///
+    /// The theme is applied first and the overrides on top. An unknown theme
+    /// or invalid overrides are skipped with a warning. `abbreviations` and
+    /// `variables` mappings add to the configured abbreviations and variables,
+    /// without replacing configured variable values.
+    fn front_matter_engine<'a>(&self, markdown: &'a str) -> Option<(ConversionEngine, &'a str)> {
+        if !self.config.parsing.front_matter.enabled {
+            return None;
+        }
+        let front_matter = front_matter::split(markdown)?;
+
+        let mut config = self.config.clone();
+        if let Some(theme) = front_matter.fields.get("theme") {
+            match theme.as_str().map(|name| themes::apply_theme(&config, name)) {
+                Some(Ok(themed)) => config = themed,
+                Some(Err(e)) => warn!("Ignoring front matter theme, using the active configuration: {}", e),
+                None => warn!("Ignoring front matter theme {:?}; expected a theme name", theme),
+            }
+        }
+        let overrides: serde_yaml::Mapping = front_matter
+            .fields
+            .iter()
+            .filter(|(key, _)| key.as_str().is_some_and(|key| FRONT_MATTER_SECTIONS.contains(&key)))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        if !overrides.is_empty() {
+            match themes::apply_overrides(&config, serde_yaml::Value::Mapping(overrides)) {
+                Ok(overridden) => config = overridden,
+                Err(e) => warn!("Ignoring front matter overrides: {}", e),
+            }
+        }
+
+        match front_matter.fields.get("abbreviations") {
+            Some(serde_yaml::Value::Mapping(terms)) => {
+                for (abbreviation, full_name) in terms {
+                    match (abbreviation.as_str(), full_name.as_str()) {
+                        (Some(abbreviation), Some(full_name)) => {
+                            config.elements.abbreviations.terms.insert(abbreviation.to_string(), full_name.to_string());
+                        }
+                        _ => warn!("Ignoring front matter abbreviation {:?}; expected a full name", abbreviation),
+                    }
+                }
+            }
+            Some(other) => warn!("Ignoring front matter abbreviations {:?}; expected a mapping", other),
+            None => {}
+        }
+        match front_matter.fields.get("variables") {
+            Some(serde_yaml::Value::Mapping(values)) => {
+                for (name, value) in values {
+                    let value = match value {
+                        serde_yaml::Value::String(value) => Some(value.clone()),
+                        serde_yaml::Value::Number(value) => Some(value.to_string()),
+                        serde_yaml::Value::Bool(value) => Some(value.to_string()),
+                        _ => None,
+                    };
+                    match (name.as_str(), value) {
+                        (Some(name), Some(value)) => {
+                            // Configured and command-line values take precedence
+                            config.parsing.variables.values.entry(name.to_string()).or_insert(value);
+                        }
+                        _ => warn!("Ignoring front matter variable {:?}; expected a name and a scalar value", name),
+                    }
+                }
+            }
+            Some(other) => warn!("Ignoring front matter variables {:?}; expected a mapping", other),
+            None => {}
+        }
+
+        let metadata_table = &config.parsing.front_matter.metadata_table;
+        let metadata = if metadata_table.enabled { front_matter.metadata(&metadata_table.fields) } else { Vec::new() };
+
+        // The body is plain Markdown; a second `---` block is not front matter
+        config.parsing.front_matter.enabled = false;
+        let mut engine = ConversionEngine::new(config);
+        engine.concurrency = self.concurrency;
+        engine.docx_generator.set_metadata(metadata);
+        Some((engine, front_matter.body))
+    }
+
+    /// Fill in `{{name}}` placeholders from the configured variables
+    ///
+    /// Undefined variables are kept as written with a warning, or fail the
+    /// conversion when `parsing.variables.undefined` is `error`.
+    fn substitute_variables(&self, document: &mut MarkdownDocument) -> Result<(), ConversionError> {
+        let config = &self.config.parsing.variables;
+        let undefined = variables::apply_to_document(document, &config.values);
+        if undefined.is_empty() {
+            return Ok(());
+        }
+        match config.undefined {
+            UndefinedVariableMode::Keep => {
+                warn!(variables = %undefined.join(", "), "Leaving placeholders of undefined variables as written");
+                Ok(())
+            }
+            UndefinedVariableMode::Error => {
+                error!(variables = %undefined.join(", "), "Document uses undefined variables");
+                Err(ConversionError::UndefinedVariables(undefined))
+            }
+        }
+    }
+
+    /// Whether code block processing errors fail the conversion
+    fn is_strict(&self) -> bool {
+        self.config
+            .code_block_processing
+            .as_ref()
+            .is_some_and(|config| config.global.strict)
+    }
+
+    /// Reject the document if any code block strategy reported errors
+    fn check_code_blocks(document: &MarkdownDocument) -> Result<(), ConversionError> {
+        let failures: Vec<CodeBlockFailure> = document
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                MarkdownElement::CodeBlock { language, processed, .. } => Some((language, processed)),
+                _ => None,
+            })
+            .enumerate()
+            .filter_map(|(index, (language, processed))| {
+                let errors = &processed.as_ref()?.errors;
+                if errors.is_empty() {
+                    return None;
+                }
+                Some(CodeBlockFailure {
+                    index: index + 1,
+                    language: language.clone(),
+                    messages: errors.iter().map(|e| e.to_string()).collect(),
+                })
+            })
+            .collect();
+        
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            error!(blocks = failures.len(), "Code blocks failed processing in strict mode");
+            Err(ConversionError::InvalidCodeBlocks(failures))
+        }
+    }
+
+    /// Convert Markdown to docx and, when `output.pdf` is enabled, a companion PDF
+    ///
+    /// A missing or failing PDF converter does not fail the conversion; the
+    /// docx is returned with the reason in `pdf_error`.
+    pub async fn convert_with_pdf(&mut self, markdown: &str) -> Result<ConversionOutput, ConversionError> {
+        let docx = self.convert(markdown).await?;
+        let (pdf, pdf_error) = pdf::render_companion(&docx, &self.config.output.pdf).await;
+        Ok(ConversionOutput { docx, pdf, pdf_error })
     }
 
     /// Convert Markdown file to docx file
-    #[instrument(skip(self), fields(input_path, output_path))]
     pub async fn convert_file(&mut self, input_path: &str, output_path: &str) -> Result<(), ConversionError> {
+        self.convert_file_with_summary(input_path, output_path).await.map(|_| ())
+    }
+
+    /// Convert Markdown file to docx file, summarizing the conversion
+    ///
+    /// Warnings are those logged on the converting thread, as counted by
+    /// [`WarningCounter`](crate::logging::WarningCounter).
+    #[instrument(skip(self), fields(input_path, output_path))]
+    pub async fn convert_file_with_summary(&mut self, input_path: &str, output_path: &str) -> Result<ConversionSummary, ConversionError> {
         info!("Starting file conversion from {} to {}", input_path, output_path);
+        let start = Instant::now();
+        let warnings_before = crate::logging::warnings_on_current_thread();
         
         // Validate input file exists
         if !Path::new(input_path).exists() {
@@ -90,9 +386,13 @@ impl ConversionEngine {
             })?;
         
         info!("Read {} characters from input file", markdown_content.len());
-        
-        // Convert to docx
-        let docx_bytes = self.convert(&markdown_content).await?;
+
+        let includes = &self.config.parsing.includes;
+        let markdown_content = if includes.enabled {
+            includes::expand(&markdown_content, Path::new(input_path), includes.max_depth)?
+        } else {
+            markdown_content
+        };
         
         // Create output directory if it doesn't exist
         if let Some(parent) = Path::new(output_path).parent() {
@@ -106,37 +406,113 @@ impl ConversionEngine {
             }
         }
         
+        let write_error = |e: std::io::Error| {
+            error!("Failed to write output file {}: {}", output_path, e);
+            ConversionError::Io(e)
+        };
+
+        // Without a PDF to render from the docx bytes, stream the docx
+        // straight into the output file
+        if !self.config.output.pdf.enabled {
+            debug!("Streaming docx file: {}", output_path);
+            let mut file = BufWriter::new(fs::File::create(output_path).map_err(write_error)?);
+            if let Err(e) = self.convert_to_writer(&markdown_content, &mut file).await {
+                drop(file);
+                let _ = fs::remove_file(output_path);
+                return Err(e);
+            }
+            let file = file.into_inner().map_err(|e| write_error(e.into_error()))?;
+            let output_bytes = file.metadata().map_err(write_error)?.len();
+
+            info!("Successfully converted {} to {}", input_path, output_path);
+            return Ok(ConversionSummary {
+                files: 1,
+                elements: self.last_element_count,
+                warnings: crate::logging::warnings_on_current_thread() - warnings_before,
+                output_bytes,
+                duration: start.elapsed(),
+                pdf: None,
+                pdf_error: None,
+            });
+        }
+
+        // Convert to docx
+        let output = self.convert_with_pdf(&markdown_content).await?;
+        
         // Write docx file
         debug!("Writing docx file: {}", output_path);
-        fs::write(output_path, docx_bytes)
-            .map_err(|e| {
-                error!("Failed to write output file {}: {}", output_path, e);
-                ConversionError::Io(e)
-            })?;
+        fs::write(output_path, &output.docx).map_err(write_error)?;
+        
+        let (mut pdf, mut pdf_error) = (None, output.pdf_error);
+        if let Some(pdf_bytes) = &output.pdf {
+            let pdf_path = Path::new(output_path).with_extension("pdf");
+            debug!("Writing PDF file: {:?}", pdf_path);
+            match fs::write(&pdf_path, pdf_bytes) {
+                Ok(()) => pdf = Some(pdf_path),
+                Err(e) => {
+                    warn!("Failed to write PDF file {:?}: {}", pdf_path, e);
+                    pdf_error = Some(format!("cannot write {}: {}", pdf_path.display(), e));
+                }
+            }
+        }
         
         info!("Successfully converted {} to {}", input_path, output_path);
-        Ok(())
+        Ok(ConversionSummary {
+            files: 1,
+            elements: self.last_element_count,
+            warnings: crate::logging::warnings_on_current_thread() - warnings_before,
+            output_bytes: output.docx.len() as u64,
+            duration: start.elapsed(),
+            pdf,
+            pdf_error,
+        })
     }
 
     /// Convert multiple Markdown files to docx files
-    #[instrument(skip(self, files))]
+    ///
+    /// Up to [`concurrency`](Self::concurrency) files are converted at once,
+    /// each worker with its own engine built from the current configuration.
+    /// With a code block hook set the files are converted one at a time by
+    /// this engine, since the hook cannot be shared. Results are in the order
+    /// of `files`.
     pub async fn convert_batch(&mut self, files: &[(String, String)]) -> Result<Vec<Result<(), ConversionError>>, ConversionError> {
+        let results = self.convert_batch_with_summaries(files).await?;
+        Ok(results.into_iter().map(|result| result.map(|_| ())).collect())
+    }
+
+    /// Convert multiple Markdown files to docx files like
+    /// [`convert_batch`](Self::convert_batch), summarizing each conversion
+    #[instrument(skip(self, files))]
+    pub async fn convert_batch_with_summaries(
+        &mut self,
+        files: &[(String, String)],
+    ) -> Result<Vec<Result<ConversionSummary, ConversionError>>, ConversionError> {
         info!("Starting batch conversion of {} files", files.len());
         
-        let mut results = Vec::new();
-        
-        for (i, (input_path, output_path)) in files.iter().enumerate() {
-            info!("Processing file {} of {}: {}", i + 1, files.len(), input_path);
-            
-            let result = self.convert_file(input_path, output_path).await;
-            
-            match &result {
-                Ok(_) => info!("Successfully converted: {} -> {}", input_path, output_path),
-                Err(e) => error!("Failed to convert {}: {}", input_path, e),
+        let results = if self.concurrency > 1 && files.len() > 1 && self.code_block_hook.is_none() {
+            info!(workers = self.concurrency.min(files.len()), "Converting files in parallel");
+            let config = &self.config;
+            concurrency::run_bounded(
+                files,
+                self.concurrency,
+                || ConversionEngine::new(config.clone()),
+                |engine, (input_path, output_path)| {
+                    let result = futures::executor::block_on(engine.convert_file_with_summary(input_path, output_path));
+                    Self::log_batch_result(input_path, output_path, &result);
+                    result
+                },
+            )
+        } else {
+            let mut results = Vec::new();
+            for (i, (input_path, output_path)) in files.iter().enumerate() {
+                info!("Processing file {} of {}: {}", i + 1, files.len(), input_path);
+                
+                let result = self.convert_file_with_summary(input_path, output_path).await;
+                Self::log_batch_result(input_path, output_path, &result);
+                results.push(result);
             }
-            
-            results.push(result);
-        }
+            results
+        };
         
         let successful = results.iter().filter(|r| r.is_ok()).count();
         let failed = results.len() - successful;
@@ -146,6 +522,13 @@ impl ConversionEngine {
         Ok(results)
     }
 
+    fn log_batch_result(input_path: &str, output_path: &str, result: &Result<ConversionSummary, ConversionError>) {
+        match result {
+            Ok(_) => info!("Successfully converted: {} -> {}", input_path, output_path),
+            Err(e) => error!("Failed to convert {}: {}", input_path, e),
+        }
+    }
+
     /// Get current configuration
     pub fn config(&self) -> &ConversionConfig {
         &self.config
@@ -157,6 +540,7 @@ impl ConversionEngine {
         info!("Updating conversion engine configuration");
         debug!("New configuration: {:?}", config);
         
+        Self::configure_parser(&mut self.markdown_parser, &config);
         self.config = config.clone();
         self.docx_generator = DocxGenerator::new(config);
         
@@ -177,6 +561,47 @@ impl ConversionEngine {
         Ok(())
     }
 
+    /// Compute the numbered heading outline without generating a document
+    ///
+    /// The numbers and texts match the headings a full conversion of the same
+    /// Markdown would produce with the current configuration.
+    #[instrument(skip(self, markdown))]
+    pub fn outline(&mut self, markdown: &str) -> Result<Vec<OutlineEntry>, ConversionError> {
+        if let Some((mut engine, body)) = self.front_matter_engine(markdown) {
+            return engine.outline(body);
+        }
+        let mut document = self.markdown_parser.parse(markdown)?;
+        self.substitute_variables(&mut document)?;
+        Ok(self.docx_generator.outline(&document))
+    }
+
+    /// The document as parsed, written back as normalized Markdown
+    ///
+    /// Front matter is applied and variables substituted as in a conversion;
+    /// transforms made while generating the docx are not.
+    #[instrument(skip(self, markdown))]
+    pub fn normalized_markdown(&mut self, markdown: &str) -> Result<String, ConversionError> {
+        if let Some((mut engine, body)) = self.front_matter_engine(markdown) {
+            return engine.normalized_markdown(body);
+        }
+        let mut document = self.markdown_parser.parse(markdown)?;
+        self.substitute_variables(&mut document)?;
+        Ok(document.to_markdown())
+    }
+
+    /// [`normalized_markdown`](Self::normalized_markdown) of a file, with its
+    /// includes expanded as in [`convert_file`](Self::convert_file)
+    pub fn normalized_markdown_file(&mut self, input_path: &str) -> Result<String, ConversionError> {
+        let markdown_content = fs::read_to_string(input_path).map_err(ConversionError::Io)?;
+        let includes = &self.config.parsing.includes;
+        let markdown_content = if includes.enabled {
+            includes::expand(&markdown_content, Path::new(input_path), includes.max_depth)?
+        } else {
+            markdown_content
+        };
+        self.normalized_markdown(&markdown_content)
+    }
+
     /// Get conversion statistics for a markdown string
     #[instrument(skip(self, markdown))]
     pub fn get_conversion_stats(&self, markdown: &str) -> Result<ConversionStats, ConversionError> {
@@ -186,6 +611,7 @@ impl ConversionEngine {
         
         let mut stats = ConversionStats::default();
         stats.total_elements = document.elements.len();
+        stats.profile = self.markdown_parser.profile();
         
         for element in &document.elements {
             match element {
@@ -193,9 +619,14 @@ impl ConversionEngine {
                 crate::markdown::ast::MarkdownElement::Paragraph { .. } => stats.paragraphs += 1,
                 crate::markdown::ast::MarkdownElement::CodeBlock { .. } => stats.code_blocks += 1,
                 crate::markdown::ast::MarkdownElement::List { .. } => stats.lists += 1,
-                crate::markdown::ast::MarkdownElement::Table { .. } => stats.tables += 1,
+                crate::markdown::ast::MarkdownElement::Table { .. }
+                | crate::markdown::ast::MarkdownElement::HtmlTable(_) => stats.tables += 1,
                 crate::markdown::ast::MarkdownElement::Image { .. } => stats.images += 1,
                 crate::markdown::ast::MarkdownElement::HorizontalRule => stats.horizontal_rules += 1,
+                crate::markdown::ast::MarkdownElement::FootnoteDefinition { .. } => stats.footnotes += 1,
+                crate::markdown::ast::MarkdownElement::SectionBreak { .. }
+                | crate::markdown::ast::MarkdownElement::Details { .. }
+                | crate::markdown::ast::MarkdownElement::BlockQuote { .. } => {}
             }
         }
         
@@ -204,6 +635,16 @@ impl ConversionEngine {
     }
 }
 
+/// Result of a conversion that may include a companion PDF
+#[derive(Debug, Clone)]
+pub struct ConversionOutput {
+    pub docx: Vec<u8>,
+    /// Rendered PDF, if enabled and the converter succeeded
+    pub pdf: Option<Vec<u8>>,
+    /// Why no PDF was produced although one was requested
+    pub pdf_error: Option<String>,
+}
+
 /// Statistics about a conversion
 #[derive(Debug, Default, Clone)]
 pub struct ConversionStats {
@@ -215,13 +656,16 @@ pub struct ConversionStats {
     pub tables: usize,
     pub images: usize,
     pub horizontal_rules: usize,
+    pub footnotes: usize,
+    /// Parsing profile the Markdown was read with
+    pub profile: ParsingProfile,
 }
 
 impl ConversionStats {
     /// Get a summary string of the statistics
     pub fn summary(&self) -> String {
         format!(
-            "Total elements: {}, Headings: {}, Paragraphs: {}, Code blocks: {}, Lists: {}, Tables: {}, Images: {}, Horizontal rules: {}",
+            "Total elements: {}, Headings: {}, Paragraphs: {}, Code blocks: {}, Lists: {}, Tables: {}, Images: {}, Horizontal rules: {}, Footnotes: {}, Profile: {}",
             self.total_elements,
             self.headings,
             self.paragraphs,
@@ -229,7 +673,9 @@ impl ConversionStats {
             self.lists,
             self.tables,
             self.images,
-            self.horizontal_rules
+            self.horizontal_rules,
+            self.footnotes,
+            self.profile.name()
         )
     }
 }
@@ -241,6 +687,30 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[tokio::test]
+    async fn test_code_block_hook_rewrites_rendered_code() {
+        let samples = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut engine = ConversionEngine::new(crate::test_utils::create_test_config());
+        let collected = samples.clone();
+        engine.set_code_block_hook(move |mut block| {
+            if block.language.as_deref() != Some("rust") {
+                return;
+            }
+            collected.lock().unwrap().push(block.code.clone());
+            let annotated = format!("// doc-test: passed\n{}", block.code);
+            block.replace_code(annotated);
+        });
+
+        let markdown = "```rust\nfn main() {}\n```\n\n```text\nleft alone\n```\n";
+        let docx = engine.convert(markdown).await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+
+        assert_eq!(*samples.lock().unwrap(), vec!["fn main() {}\n".to_string()]);
+        assert!(xml.contains("// doc-test: passed"));
+        assert_eq!(xml.matches("doc-test").count(), 1);
+        assert!(xml.contains("left alone"));
+    }
+
     #[tokio::test]
     async fn test_convert_simple_markdown() {
         let config = ConversionConfig::default();
@@ -301,6 +771,132 @@ That's all!
         assert!(!docx_bytes.is_empty());
     }
 
+    fn code_block_config(strict: bool) -> ConversionConfig {
+        let mut code_blocks = crate::markdown::code_block::CodeBlockConfig::default();
+        code_blocks.global.enable_processing = true;
+        code_blocks.global.strict = strict;
+        let mut config = crate::test_utils::create_test_config();
+        config.code_block_processing = Some(code_blocks);
+        config
+    }
+
+    const INVALID_RUST: &str =
+        "# Samples\n\n```rust\nfn ok() {}\n```\n\n```rust\nfn main( {\n    let x = ;\n```\n";
+
+    #[tokio::test]
+    async fn test_strict_mode_rejects_invalid_code_blocks() {
+        let mut engine = ConversionEngine::new(code_block_config(true));
+        match engine.convert(INVALID_RUST).await {
+            Err(ConversionError::InvalidCodeBlocks(failures)) => {
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].index, 2);
+                assert_eq!(failures[0].language.as_deref(), Some("rust"));
+                assert!(failures[0].messages[0].starts_with("syntax_error"));
+            }
+            other => panic!("expected strict mode failure, got {:?}", other.map(|bytes| bytes.len())),
+        }
+
+        // Lenient mode (the default) falls back to the original code
+        let mut engine = ConversionEngine::new(code_block_config(false));
+        assert!(engine.convert(INVALID_RUST).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pdf_skipped_when_converter_missing() {
+        let mut config = ConversionConfig::default();
+        config.output.pdf.enabled = true;
+        config.output.pdf.converter_path = "/nonexistent/md2docx-test/soffice".to_string();
+        let mut engine = ConversionEngine::new(config);
+
+        let output = engine.convert_with_pdf("# Title\n\nBody").await.unwrap();
+        assert!(!output.docx.is_empty());
+        assert!(output.pdf.is_none());
+        assert!(output.pdf_error.unwrap().contains("cannot run PDF converter"));
+
+        let temp_dir = TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("doc.md");
+        let output_path = temp_dir.path().join("doc.docx");
+        fs::write(&input_path, "# Title").unwrap();
+        engine
+            .convert_file(input_path.to_str().unwrap(), output_path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert!(output_path.exists());
+        assert!(!temp_dir.path().join("doc.pdf").exists());
+
+        // A PDF left over from an earlier run is not reported as this one's
+        fs::write(temp_dir.path().join("doc.pdf"), "stale").unwrap();
+        let summary = engine
+            .convert_file_with_summary(input_path.to_str().unwrap(), output_path.to_str().unwrap())
+            .await
+            .unwrap();
+        assert!(summary.pdf.is_none());
+        assert!(summary.pdf_error.unwrap().contains("cannot run PDF converter"));
+    }
+
+    /// Texts of the paragraphs that carry an outline level, in document order
+    fn rendered_headings(docx: &[u8]) -> Vec<String> {
+        let xml = crate::test_utils::read_docx_part(docx, "word/document.xml").unwrap();
+        xml.split("</w:p>")
+            .filter(|paragraph| paragraph.contains("<w:outlineLvl "))
+            .map(|paragraph| {
+                paragraph
+                    .split("<w:t xml:space=\"preserve\">")
+                    .skip(1)
+                    .filter_map(|part| part.split("</w:t>").next())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_outline_matches_conversion() {
+        let mut config = ConversionConfig::default();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+        config.sections.footnotes.numbered = true;
+        let mut engine = ConversionEngine::new(config);
+
+        let markdown = "# Intro\n\n## Setup\n\n## Setup\n\n### Details\n\n# Usage\n\nText[^n]\n\n[^n]: A note\n";
+        let outline = engine.outline(markdown).unwrap();
+        let docx = engine.convert(markdown).await.unwrap();
+
+        let numbered: Vec<&str> = outline.iter().map(|e| e.numbered_text.as_str()).collect();
+        assert_eq!(numbered, vec!["1. Intro", "1.1. Setup", "1.2. Setup", "Details", "2. Usage", "3. Notes"]);
+        assert_eq!(rendered_headings(&docx), numbered);
+
+        assert_eq!(outline[1].text, "Setup");
+        assert_eq!(outline[1].level, 2);
+        let anchors: Vec<&str> = outline.iter().map(|e| e.anchor.as_str()).collect();
+        assert_eq!(anchors, vec!["intro", "setup", "setup-1", "details", "usage", "notes"]);
+
+        // Computing the outline again starts numbering afresh
+        assert_eq!(engine.outline(markdown).unwrap(), outline);
+    }
+
+    #[tokio::test]
+    async fn test_outline_without_numbering() {
+        let mut engine = ConversionEngine::new(ConversionConfig::default());
+        let markdown = "# Title\n\nBody\n\n## Part\n";
+        let outline = engine.outline(markdown).unwrap();
+        let docx = engine.convert(markdown).await.unwrap();
+
+        assert_eq!(outline.len(), 2);
+        assert!(outline.iter().all(|e| e.numbered_text == e.text));
+        let numbered: Vec<&str> = outline.iter().map(|e| e.numbered_text.as_str()).collect();
+        assert_eq!(rendered_headings(&docx), numbered);
+    }
+
+    #[test]
+    fn test_normalized_markdown_substitutes_variables() {
+        let mut config = ConversionConfig::default();
+        config.parsing.variables.values.insert("name".to_string(), "Atlas".to_string());
+        let mut engine = ConversionEngine::new(config);
+
+        let markdown = engine.normalized_markdown("Setext {{name}}\n---\n\n* one\n* __two__\n").unwrap();
+        assert_eq!(markdown, "Setext Atlas\n---\n\n- one\n- **two**\n");
+    }
+
     #[tokio::test]
     async fn test_convert_file() {
         let config = ConversionConfig::default();
@@ -329,6 +925,162 @@ That's all!
         assert!(output_size > 0);
     }
 
+    #[tokio::test]
+    async fn test_convert_file_streams_docx() {
+        let temp_dir = TempDir::new().unwrap();
+        let input_path = temp_dir.path().join("test.md");
+        let output_path = temp_dir.path().join("test.docx");
+        fs::write(&input_path, "# Streamed\n\nStraight into the file.").unwrap();
+
+        let mut engine = ConversionEngine::new(crate::test_utils::create_test_config());
+        let summary = engine
+            .convert_file_with_summary(input_path.to_str().unwrap(), output_path.to_str().unwrap())
+            .await
+            .unwrap();
+        let written = fs::read(&output_path).unwrap();
+        assert_eq!(summary.output_bytes, written.len() as u64);
+        crate::docx::self_check::check_package(&written).unwrap();
+
+        // A failed conversion leaves no partial file behind
+        fs::write(&input_path, INVALID_RUST).unwrap();
+        let mut engine = ConversionEngine::new(code_block_config(true));
+        let result = engine
+            .convert_file(input_path.to_str().unwrap(), output_path.to_str().unwrap())
+            .await;
+        assert!(result.is_err());
+        assert!(!output_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_front_matter_theme_per_file() {
+        let mut config = ConversionConfig::default();
+        config.parsing.front_matter.enabled = true;
+        let mut engine = ConversionEngine::new(config);
+
+        let temp_dir = TempDir::new().unwrap();
+        let sources = [
+            ("academic", "---\ntheme: academic\n---\n# Title\n\nBody text.\n"),
+            ("modern", "---\ntheme: modern\nstyles:\n  paragraph:\n    font: { family: Georgia }\n---\n# Title\n\nBody text.\n"),
+            ("unknown", "---\ntheme: neon\n---\n# Title\n\nBody text.\n"),
+        ];
+        let files: Vec<(String, String)> = sources
+            .iter()
+            .map(|(name, markdown)| {
+                let input = temp_dir.path().join(format!("{}.md", name));
+                fs::write(&input, markdown).unwrap();
+                let output = temp_dir.path().join(format!("{}.docx", name));
+                (input.to_str().unwrap().to_string(), output.to_str().unwrap().to_string())
+            })
+            .collect();
+        for result in engine.convert_batch(&files).await.unwrap() {
+            result.unwrap();
+        }
+
+        let document_xml = |name: &str| {
+            let docx = fs::read(temp_dir.path().join(format!("{}.docx", name))).unwrap();
+            let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+            assert!(!xml.contains("theme:"), "front matter is not rendered");
+            xml
+        };
+        let body_run = |xml: &str| {
+            let end = xml.find(">Body text.</w:t>").unwrap();
+            xml[xml[..end].rfind("<w:r>").unwrap()..end].to_string()
+        };
+        let centered = r#"<w:jc w:val="center" />"#;
+
+        let academic = document_xml("academic");
+        assert!(academic.contains(centered));
+        assert!(body_run(&academic).contains("w:ascii=\"Times New Roman\""));
+        // Inline overrides apply on top of the theme
+        let modern = document_xml("modern");
+        assert!(!modern.contains(centered));
+        assert!(body_run(&modern).contains("w:ascii=\"Georgia\""));
+        assert!(body_run(&modern).contains("<w:sz w:val=\"22\" />"));
+        // An unknown theme falls back to the active configuration
+        let unknown = document_xml("unknown");
+        assert!(!unknown.contains(centered));
+        assert!(body_run(&unknown).contains("<w:sz w:val=\"24\" />"));
+    }
+
+    #[tokio::test]
+    async fn test_front_matter_abbreviations() {
+        let mut config = ConversionConfig::default();
+        config.parsing.front_matter.enabled = true;
+        config.elements.abbreviations.terms.insert("CPU".to_string(), "Central Processing Unit".to_string());
+        let markdown = "---\nabbreviations:\n  SLA: Service Level Agreement\n---\nThe SLA covers CPU use.\n\nSLA and CPU again.\n";
+
+        let docx = ConversionEngine::new(config.clone()).convert(markdown).await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+        assert!(xml.contains(">The Service Level Agreement (SLA) covers Central Processing Unit (CPU) use.</w:t>"));
+        assert!(xml.contains(">SLA and CPU again.</w:t>"));
+
+        config.elements.abbreviations.mode = crate::config::AbbreviationMode::Tooltip;
+        let docx = ConversionEngine::new(config).convert(markdown).await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+        assert_eq!(xml.matches(r#"<w:hyperlink w:tooltip="Service Level Agreement""#).count(), 2);
+        assert_eq!(xml.matches(r#"<w:hyperlink w:tooltip="Central Processing Unit""#).count(), 2);
+        assert!(!xml.contains("w:anchor="));
+    }
+
+    #[tokio::test]
+    async fn test_front_matter_metadata_table() {
+        let mut config = ConversionConfig::default();
+        config.parsing.front_matter.enabled = true;
+        let markdown = "---\nrelease_date: 2024-05-01\ntheme: modern\nauthor: Ada Lovelace\ntitle: Engine Notes\nversion: 3\n---\nBody text.\n";
+
+        let docx = ConversionEngine::new(config.clone()).convert(markdown).await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+        assert!(!xml.contains("<w:tbl>"));
+
+        config.parsing.front_matter.metadata_table.enabled = true;
+        let docx = ConversionEngine::new(config).convert(markdown).await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+        let table = &xml[xml.find("<w:tbl>").unwrap()..xml.find("</w:tbl>").unwrap()];
+        let rows: Vec<Vec<&str>> = table
+            .split("</w:tr>")
+            .filter(|row| row.contains("<w:tr"))
+            .map(|row| {
+                row.split("</w:t>")
+                    .filter_map(|text| text.rfind('>').map(|start| &text[start + 1..]))
+                    .filter(|text| !text.is_empty())
+                    .collect()
+            })
+            .collect();
+        // Configured fields first, then the others as written; no theme
+        assert_eq!(
+            rows,
+            [
+                ["Title", "Engine Notes"],
+                ["Author", "Ada Lovelace"],
+                ["Version", "3"],
+                ["Release date", "2024-05-01"],
+            ]
+        );
+        assert!(xml.find("</w:tbl>").unwrap() < xml.find(">Body text.</w:t>").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_variable_substitution() {
+        let mut config = ConversionConfig::default();
+        config.parsing.front_matter.enabled = true;
+        config.parsing.variables.values.insert("project_name".to_string(), "Atlas".to_string());
+        // The configured value wins over the front matter's
+        let markdown = "---\nvariables:\n  version: 2.1\n  project_name: Draft\n---\n# {{project_name}} {{ version }}\n\nOwned by {{owner}}, see `{{project_name}}`.\n\n```\n{{project_name}}\n```\n";
+
+        let docx = ConversionEngine::new(config.clone()).convert(markdown).await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+        assert!(xml.contains(">Atlas 2.1</w:t>"));
+        assert!(xml.contains(">Owned by {{owner}}, see </w:t>"));
+        // Code spans and code blocks stay literal
+        assert_eq!(xml.matches(">{{project_name}}</w:t>").count(), 2);
+
+        config.parsing.variables.undefined = crate::config::UndefinedVariableMode::Error;
+        match ConversionEngine::new(config).convert(markdown).await {
+            Err(ConversionError::UndefinedVariables(names)) => assert_eq!(names, ["owner"]),
+            other => panic!("Expected undefined variables error, got {:?}", other.map(|_| ())),
+        }
+    }
+
     #[tokio::test]
     async fn test_convert_file_nonexistent_input() {
         let config = ConversionConfig::default();
@@ -387,6 +1139,42 @@ That's all!
         }
     }
 
+    #[tokio::test]
+    async fn test_convert_batch_respects_concurrency() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut file_pairs = Vec::new();
+        for i in 0..6 {
+            let input_path = temp_dir.path().join(format!("doc{}.md", i));
+            fs::write(&input_path, format!("# Document {}\n\nContent", i)).unwrap();
+            file_pairs.push((
+                input_path.to_string_lossy().to_string(),
+                temp_dir.path().join(format!("doc{}.docx", i)).to_string_lossy().to_string(),
+            ));
+        }
+        // A missing input fails on its own without stopping the others
+        file_pairs.insert(2, ("missing.md".to_string(), "missing.docx".to_string()));
+
+        for workers in [1, 4] {
+            let mut engine = ConversionEngine::new(ConversionConfig::default());
+            engine.set_concurrency(workers);
+            assert_eq!(engine.concurrency(), workers);
+
+            let results = engine.convert_batch(&file_pairs).await.unwrap();
+            assert_eq!(results.len(), file_pairs.len());
+            for (result, (input_path, output_path)) in results.iter().zip(&file_pairs) {
+                assert_eq!(result.is_ok(), input_path != "missing.md");
+                if result.is_ok() {
+                    assert!(Path::new(output_path).exists());
+                    fs::remove_file(output_path).unwrap();
+                }
+            }
+        }
+
+        let mut engine = ConversionEngine::new(ConversionConfig::default());
+        engine.set_concurrency(0);
+        assert_eq!(engine.concurrency(), 1);
+    }
+
     #[test]
     fn test_update_config() {
         let mut config = ConversionConfig::default();
@@ -401,6 +1189,52 @@ That's all!
         assert_eq!(engine.config().document.default_font.size, 16.0);
     }
 
+    #[tokio::test]
+    async fn test_convert_with_timing_reports_phases() {
+        let temp_dir = TempDir::new().unwrap();
+        let image_path = temp_dir.path().join("chart.png");
+        image::RgbImage::new(64, 48).save(&image_path).unwrap();
+        let markdown = format!(
+            "# Intro\n\n## Details\n\n![chart]({})\n\n```rust\nfn main() {{}}\n```\n\nDone.\n",
+            image_path.display()
+        );
+
+        let mut config = code_block_config(false);
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        let mut engine = ConversionEngine::new(config);
+        let (docx, report) = engine.convert_with_timing(&markdown).await.unwrap();
+        assert!(!docx.is_empty());
+
+        for (phase, duration) in [
+            ("parse", report.parse),
+            ("code_blocks", report.code_blocks),
+            ("numbering", report.numbering),
+            ("images", report.images),
+            ("docx_build", report.docx_build),
+        ] {
+            assert!(duration > Duration::ZERO, "{} not recorded", phase);
+        }
+        // Phases account for (nearly) all of the total
+        assert!(report.phases_total() <= report.total);
+        assert!(report.phases_total() * 10 >= report.total * 9, "{:?}", report);
+
+        // Plain conversions leave timing off
+        assert!(!engine.convert(&markdown).await.unwrap().is_empty());
+        assert!(engine.docx_generator.timings().is_none());
+    }
+
+    #[test]
+    fn test_unicode_bullets_follow_config() {
+        let markdown = "• one\n• two\n";
+        let mut config = ConversionConfig::default();
+        let mut engine = ConversionEngine::new(config.clone());
+        assert_eq!(engine.get_conversion_stats(markdown).unwrap().lists, 0);
+
+        config.elements.unicode_bullets.enabled = true;
+        engine.update_config(config);
+        assert_eq!(engine.get_conversion_stats(markdown).unwrap().lists, 1);
+    }
+
     #[test]
     fn test_validate_config() {
         let config = ConversionConfig::default();
@@ -460,6 +1294,22 @@ code block
         assert_eq!(stats.total_elements, 8);
     }
 
+    #[test]
+    fn test_commonmark_profile_recorded_in_stats() {
+        let markdown = "| a | b |\n|---|---|\n| 1 | 2 |\n";
+
+        let engine = ConversionEngine::new(ConversionConfig::default());
+        let stats = engine.get_conversion_stats(markdown).unwrap();
+        assert_eq!((stats.profile, stats.tables, stats.paragraphs), (ParsingProfile::Gfm, 1, 0));
+
+        let mut config = ConversionConfig::default();
+        config.parsing.profile = ParsingProfile::Commonmark;
+        let engine = ConversionEngine::new(config);
+        let stats = engine.get_conversion_stats(markdown).unwrap();
+        assert_eq!((stats.profile, stats.tables, stats.paragraphs), (ParsingProfile::Commonmark, 0, 1));
+        assert!(stats.summary().contains("Profile: commonmark"));
+    }
+
     #[test]
     fn test_conversion_stats_summary() {
         let stats = ConversionStats {
@@ -471,6 +1321,8 @@ code block
             tables: 0,
             images: 0,
             horizontal_rules: 0,
+            footnotes: 0,
+            profile: ParsingProfile::Gfm,
         };
         
         let summary = stats.summary();
@@ -478,5 +1330,6 @@ code block
         assert!(summary.contains("Headings: 2"));
         assert!(summary.contains("Paragraphs: 2"));
         assert!(summary.contains("Code blocks: 1"));
+        assert!(summary.contains("Profile: gfm"));
     }
 }
\ No newline at end of file
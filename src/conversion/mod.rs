@@ -1,5 +1,13 @@
 //! Core conversion engine module
 
+pub mod concurrency;
 pub mod engine;
+pub mod pdf;
+pub mod summary;
+pub mod timing;
 
-pub use engine::ConversionEngine;
\ No newline at end of file
+pub use concurrency::default_concurrency;
+pub use engine::{CodeBlockHook, ConversionEngine, ConversionOutput};
+pub use summary::ConversionSummary;
+pub use timing::TimingReport;
+pub use crate::docx::OutlineEntry;
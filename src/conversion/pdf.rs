@@ -0,0 +1,177 @@
+//! Optional PDF rendering through a headless office suite
+//!
+//! The generated docx is handed to an external converter (LibreOffice's
+//! `soffice` by default). Nothing is linked in: when the tool is missing the
+//! conversion still succeeds and reports why no PDF was produced. The
+//! converter runs as a Tokio child process, so waiting on it does not block
+//! the executor.
+
+use crate::config::PdfConfig;
+use crate::error::ConversionError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Render docx bytes to PDF with the configured converter
+pub async fn render_pdf(docx_bytes: &[u8], config: &PdfConfig) -> Result<Vec<u8>, ConversionError> {
+    let work_dir = WorkDir::create()?;
+    let input = work_dir.path().join("document.docx");
+    tokio::fs::write(&input, docx_bytes).await?;
+
+    debug!(converter = %config.converter_path, "Rendering PDF");
+    let mut child = Command::new(&config.converter_path)
+        .arg("--headless")
+        .arg("--convert-to")
+        .arg("pdf")
+        .arg("--outdir")
+        .arg(work_dir.path())
+        .arg(&input)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            ConversionError::pdf_rendering(format!(
+                "cannot run PDF converter '{}': {}",
+                config.converter_path, e
+            ))
+        })?;
+
+    let timeout = Duration::from_secs(config.timeout_seconds);
+    let status = match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(status) => status?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(ConversionError::pdf_rendering(format!(
+                "PDF converter timed out after {} seconds",
+                config.timeout_seconds
+            )));
+        }
+    };
+    if !status.success() {
+        return Err(ConversionError::pdf_rendering(format!(
+            "PDF converter exited with {}",
+            status
+        )));
+    }
+
+    tokio::fs::read(work_dir.path().join("document.pdf")).await.map_err(|e| {
+        ConversionError::pdf_rendering(format!("PDF converter produced no output: {}", e))
+    })
+}
+
+/// Whether the configured converter can be found: as a file when
+/// `converter_path` is a path, otherwise in a `PATH` directory
+pub fn converter_available(config: &PdfConfig) -> bool {
+    let converter = Path::new(&config.converter_path);
+    if converter.components().count() > 1 {
+        return converter.is_file();
+    }
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| {
+            let candidate = dir.join(converter);
+            candidate.is_file() || (cfg!(windows) && candidate.with_extension("exe").is_file())
+        })
+    })
+}
+
+/// Render a PDF if enabled, turning failures into a warning and a reason
+pub(crate) async fn render_companion(
+    docx_bytes: &[u8],
+    config: &PdfConfig,
+) -> (Option<Vec<u8>>, Option<String>) {
+    if !config.enabled {
+        return (None, None);
+    }
+    match render_pdf(docx_bytes, config).await {
+        Ok(pdf) => (Some(pdf), None),
+        Err(e) => {
+            warn!(error = %e, "PDF rendering skipped; returning docx only");
+            (None, Some(e.to_string()))
+        }
+    }
+}
+
+/// Temporary directory removed on drop
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    fn create() -> Result<Self, ConversionError> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        let path = std::env::temp_dir()
+            .join(format!("md2docx-pdf-{}-{}", std::process::id(), nanos));
+        fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn missing_tool() -> PdfConfig {
+        PdfConfig {
+            enabled: true,
+            converter_path: "/nonexistent/md2docx-test/soffice".to_string(),
+            ..PdfConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_converter_is_an_error() {
+        let err = render_pdf(b"docx", &missing_tool()).await.unwrap_err();
+        assert!(matches!(err, ConversionError::PdfRendering(_)));
+        assert!(err.to_string().contains("/nonexistent/md2docx-test/soffice"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_slow_converter_times_out_without_blocking() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("soffice");
+        fs::write(&script, "#!/bin/sh\nsleep 30\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let config = PdfConfig {
+            enabled: true,
+            converter_path: script.display().to_string(),
+            timeout_seconds: 1,
+            ..PdfConfig::default()
+        };
+
+        // The single-threaded test runtime runs other tasks while the
+        // converter is waited on
+        let ticker = tokio::spawn(tokio::time::sleep(Duration::from_millis(100)));
+        let err = render_pdf(b"docx", &config).await.unwrap_err();
+        assert!(err.to_string().contains("timed out after 1 seconds"));
+        assert!(ticker.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_companion_skipped_gracefully() {
+        let (pdf, reason) = render_companion(b"docx", &missing_tool()).await;
+        assert!(pdf.is_none());
+        assert!(reason.unwrap().contains("cannot run PDF converter"));
+
+        let (pdf, reason) = render_companion(b"docx", &PdfConfig::default()).await;
+        assert!(pdf.is_none() && reason.is_none());
+    }
+}
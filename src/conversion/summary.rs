@@ -0,0 +1,92 @@
+//! Short recaps of finished conversions, as printed by the CLI's `--summary`
+//!
+//! Warnings are counted by the [`WarningCounter`] logging layer; without it
+//! installed every summary reports none.
+//!
+//! [`WarningCounter`]: crate::logging::WarningCounter
+
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// What one conversion, or several together, processed and produced
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConversionSummary {
+    /// Files converted
+    pub files: usize,
+    /// Top-level Markdown elements converted
+    pub elements: usize,
+    /// Warnings logged while converting
+    pub warnings: usize,
+    /// Size of the docx output
+    pub output_bytes: u64,
+    pub duration: Duration,
+    /// Companion PDF written by this conversion, when `output.pdf` is on
+    pub pdf: Option<PathBuf>,
+    /// Why no companion PDF was written although one was requested
+    pub pdf_error: Option<String>,
+}
+
+impl ConversionSummary {
+    /// Summary of several conversions together; their durations add up
+    ///
+    /// The total names no PDF.
+    pub fn total<'a>(summaries: impl IntoIterator<Item = &'a ConversionSummary>) -> Self {
+        summaries.into_iter().fold(Self::default(), |total, summary| Self {
+            files: total.files + summary.files,
+            elements: total.elements + summary.elements,
+            warnings: total.warnings + summary.warnings,
+            output_bytes: total.output_bytes + summary.output_bytes,
+            duration: total.duration + summary.duration,
+            ..Self::default()
+        })
+    }
+}
+
+impl fmt::Display for ConversionSummary {
+    /// One line such as `2 files, 14 elements, 1 warning, 18204 bytes, 0.04s`;
+    /// the file count is left out for a single file
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.files != 1 {
+            write!(f, "{}, ", plural(self.files, "file"))?;
+        }
+        write!(
+            f,
+            "{}, {}, {} bytes, {:.2}s",
+            plural(self.elements, "element"),
+            plural(self.warnings, "warning"),
+            self.output_bytes,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_lines() {
+        let first = ConversionSummary {
+            files: 1,
+            elements: 12,
+            warnings: 1,
+            output_bytes: 9000,
+            duration: Duration::from_millis(30),
+            ..ConversionSummary::default()
+        };
+        assert_eq!(first.to_string(), "12 elements, 1 warning, 9000 bytes, 0.03s");
+
+        let second = ConversionSummary { elements: 1, warnings: 0, ..first.clone() };
+        let total = ConversionSummary::total([&first, &second]);
+        assert_eq!(total.to_string(), "2 files, 13 elements, 1 warning, 18000 bytes, 0.06s");
+    }
+}
@@ -0,0 +1,66 @@
+//! Where conversion time goes, phase by phase
+//!
+//! Collected only by [`ConversionEngine::convert_with_timing`]; plain
+//! conversions take no timestamps.
+//!
+//! [`ConversionEngine::convert_with_timing`]: crate::conversion::ConversionEngine::convert_with_timing
+
+use serde::{Serialize, Serializer};
+use std::time::Duration;
+
+/// Time spent in each phase of one conversion
+///
+/// The phases do not overlap, so together they account for the total apart
+/// from small steps between them (code block hooks, strict checks).
+/// Durations serialize as fractional milliseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TimingReport {
+    /// Markdown parsing, excluding code block processing
+    #[serde(rename = "parse_ms", serialize_with = "millis")]
+    pub parse: Duration,
+    /// Code block strategies run during parsing
+    #[serde(rename = "code_blocks_ms", serialize_with = "millis")]
+    pub code_blocks: Duration,
+    /// Heading numbering
+    #[serde(rename = "numbering_ms", serialize_with = "millis")]
+    pub numbering: Duration,
+    /// Loading, scaling and embedding images
+    #[serde(rename = "images_ms", serialize_with = "millis")]
+    pub images: Duration,
+    /// The rest of docx generation and packaging
+    #[serde(rename = "docx_build_ms", serialize_with = "millis")]
+    pub docx_build: Duration,
+    /// Whole conversion, from Markdown to docx bytes
+    #[serde(rename = "total_ms", serialize_with = "millis")]
+    pub total: Duration,
+}
+
+impl TimingReport {
+    /// Sum of the individual phases
+    pub fn phases_total(&self) -> Duration {
+        self.parse + self.code_blocks + self.numbering + self.images + self.docx_build
+    }
+}
+
+fn millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_serializes_milliseconds() {
+        let report = TimingReport {
+            parse: Duration::from_micros(1500),
+            total: Duration::from_millis(4),
+            ..TimingReport::default()
+        };
+        let json = serde_json::to_value(report).unwrap();
+        assert_eq!(json["parse_ms"], 1.5);
+        assert_eq!(json["total_ms"], 4.0);
+        assert_eq!(json["images_ms"], 0.0);
+        assert_eq!(report.phases_total(), Duration::from_micros(1500));
+    }
+}
@@ -1,27 +1,116 @@
 //! docx document generator
 
-use crate::config::ConversionConfig;
+use crate::config::{AbbreviationMode, ClassStyle, ConversionConfig, EmptyHeadingMode, HorizontalRuleMode, ListNumberStyle, LongParagraphMode, Margins, NumberingSeparator, OrphanFootnoteMode, ProseTabMode, RevisionNotesMode, TableAlignment, WideTableHandling};
 use crate::error::ConversionError;
-use crate::markdown::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
-use crate::numbering::HeadingProcessor;
-use crate::config::ImageConfig;
+use crate::markdown::{abbreviations, callouts, checkbox, email, emoji, index, link_buttons, long_paragraphs, references, revisions, table_links, whitespace, DetailsLookup, HtmlTable, InlineElement, ListItem, ListItemBlock, MarkdownDocument, MarkdownElement, PageOrientation, TableLink};
+use crate::markdown::fence_info::CodeAttributes;
+use crate::markdown::parser::ATTRIBUTION_CLASS;
+use crate::numbering::{HeadingProcessor, NumberedHeading};
+use crate::config::{ImageConfig, ImagePlacement};
+use crate::docx::outline::{is_clause_number, number_bookmark, AnchorAllocator, BookmarkAllocator, OutlineEntry};
+use crate::docx::image_cache::{self, RemoteImage, RemoteImageCache};
+use crate::docx::{highlight, image_scaling, package, postprocess, provenance, self_check, watermark};
 use docx_rs::*;
-use std::io::Cursor;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Write};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, trace, warn};
 
+/// Image formats accepted for embedding, as reported by `get_image_format`
+pub const SUPPORTED_IMAGE_FORMATS: &[&str] = &["png", "jpeg", "gif", "bmp"];
+
+/// Author recorded on Word comments created from `<!-- comment: ... -->` directives
+pub const COMMENT_AUTHOR: &str = "md2docx";
+
+/// Paragraph styles of a title block (H1 directly followed by an H2)
+const TITLE_STYLE: &str = "Title";
+const SUBTITLE_STYLE: &str = "Subtitle";
+
+/// Paragraph style of code block captions
+const CAPTION_STYLE: &str = "Caption";
+
+/// Abstract numberings of bulleted and numbered lists; docx-rs always
+/// writes a default abstract numbering and numbering with id 1
+const BULLET_ABSTRACT_NUMBERING: usize = 2;
+const ORDERED_ABSTRACT_NUMBERING: usize = 3;
+
+/// Numbering id of the first list; every list gets a numbering of its own
+/// so its numbers start over at 1
+const FIRST_LIST_NUMBERING: usize = 2;
+
+/// Levels of the list numberings; deeper items are written at the last one
+const LIST_LEVELS: usize = 9;
+
+/// Custom document property holding the source Markdown
+pub const SOURCE_PROPERTY: &str = "MarkdownSource";
+
+/// Custom document property holding the hex SHA-256 of the source Markdown
+pub const SOURCE_HASH_PROPERTY: &str = "MarkdownSourceSHA256";
+
 /// Generator for creating docx documents from Markdown AST
 pub struct DocxGenerator {
     config: ConversionConfig,
     heading_processor: Option<HeadingProcessor>,
     /// Track if we've encountered the first H1 heading to avoid page break before it
     first_h1_encountered: bool,
+    /// Whether the next non-empty H1 is the document title, see
+    /// [`take_document_title`](Self::take_document_title)
+    title_pending: bool,
+    /// Footnote numbers by label, assigned in order of first reference
+    footnote_numbers: HashMap<String, usize>,
+    /// External links collected by the references transform, in number order
+    references: Vec<references::Reference>,
+    /// Index entries collected by the index transform, in alphabetical order
+    index_entries: Vec<index::IndexEntry>,
+    /// Revision notes collected for their appended section, in document order
+    revision_notes: Vec<String>,
+    /// Id for the next Word comment; ids are unique within a document
+    next_comment_id: usize,
+    /// Orientation of the section currently being written
+    orientation: PageOrientation,
+    /// Page margins of the document being written: the configured margins
+    /// with the document's `<!-- margins: ... -->` overrides applied
+    margins: Margins,
+    /// Heading anchors handed out so far in the current document
+    anchors: AnchorAllocator,
+    /// Heading bookmark names handed out so far in the current document
+    bookmarks: BookmarkAllocator,
+    /// Heading-number bookmark names written so far in the current document
+    number_bookmarks: HashSet<String>,
+    /// Id for the next bookmark; ids are unique within a document
+    next_bookmark_id: usize,
+    /// Numbering id for the next list
+    next_list_numbering: usize,
+    /// Nesting depth of the blockquote being written; 0 outside quotes
+    quote_depth: usize,
+    /// Labels and values of the metadata table written before the body
+    metadata: Vec<(String, String)>,
+    /// Time spent numbering headings and embedding images, when timing is on
+    timings: Option<GeneratorTimings>,
+    /// Inline or floating image waiting to be attached to the next paragraph
+    pending_image: Option<Run>,
+    /// Bytes of the images embedded so far in the current document
+    embedded_image_bytes: u64,
+    /// Element details of the document being written
+    details: DetailsLookup,
+}
+
+/// Time spent in generator phases that are interleaved with the rest of
+/// docx generation, reset at the start of every document
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GeneratorTimings {
+    pub numbering: Duration,
+    pub images: Duration,
 }
 
 impl DocxGenerator {
     /// Create a new docx generator with the given configuration
     pub fn new(config: ConversionConfig) -> Self {
+        let config = config.compacted();
         let config_arc = Arc::new(config.clone());
 
         // Initialize heading processor if any heading levels have numbering configured
@@ -52,116 +141,667 @@ impl DocxGenerator {
         };
 
         Self {
+            margins: config.document.margins.clone(),
             config,
             heading_processor,
             first_h1_encountered: false,
+            title_pending: false,
+            footnote_numbers: HashMap::new(),
+            references: Vec::new(),
+            index_entries: Vec::new(),
+            revision_notes: Vec::new(),
+            next_comment_id: 0,
+            orientation: PageOrientation::Portrait,
+            anchors: AnchorAllocator::new(),
+            bookmarks: BookmarkAllocator::new(),
+            number_bookmarks: HashSet::new(),
+            next_bookmark_id: 0,
+            next_list_numbering: FIRST_LIST_NUMBERING,
+            quote_depth: 0,
+            metadata: Vec::new(),
+            timings: None,
+            pending_image: None,
+            embedded_image_bytes: 0,
+            details: DetailsLookup::default(),
         }
     }
 
+    /// Write a table of these front matter fields, as names and values,
+    /// before the body of the documents generated from now on
+    pub fn set_metadata(&mut self, fields: Vec<(String, String)>) {
+        self.metadata = fields;
+    }
+
+    /// Record time spent numbering headings and embedding images
+    pub fn set_timing(&mut self, enabled: bool) {
+        self.timings = enabled.then(GeneratorTimings::default);
+    }
+
+    /// Phase timings of the last generated document, if timing is on
+    pub fn timings(&self) -> Option<GeneratorTimings> {
+        self.timings
+    }
+
     /// Generate docx document from Markdown AST
     pub fn generate(&mut self, document: &MarkdownDocument) -> Result<Vec<u8>, ConversionError> {
+        let mut buf = Vec::new();
+        self.generate_to_writer(document, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Generate a docx document from Markdown AST, packing it straight into
+    /// `writer` (a file, socket or response body) rather than into memory
+    ///
+    /// With the self-check on, the package is still assembled in memory so
+    /// it can be verified before any of it is written.
+    pub fn generate_to_writer<W: Write>(&mut self, document: &MarkdownDocument, writer: W) -> Result<(), ConversionError> {
+        self.render(document, None, writer)
+    }
+
+    /// Generate a docx document from the AST parsed out of `markdown`,
+    /// embedding the source when `output.embed_source` is enabled
+    pub fn generate_with_source(
+        &mut self,
+        document: &MarkdownDocument,
+        markdown: &str,
+    ) -> Result<Vec<u8>, ConversionError> {
+        let mut buf = Vec::new();
+        self.render(document, Some(markdown), &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Like [`generate_with_source`](Self::generate_with_source), packing the
+    /// document straight into `writer`
+    pub fn generate_with_source_to_writer<W: Write>(
+        &mut self,
+        document: &MarkdownDocument,
+        markdown: &str,
+        writer: W,
+    ) -> Result<(), ConversionError> {
+        self.render(document, Some(markdown), writer)
+    }
+
+    fn render<W: Write>(
+        &mut self,
+        document: &MarkdownDocument,
+        source: Option<&str>,
+        mut writer: W,
+    ) -> Result<(), ConversionError> {
+        // The outline pass prepares the document itself and its state is
+        // reset below
+        let toc_entries = if self.config.document.table_of_contents.enabled {
+            self.outline(document)
+        } else {
+            Vec::new()
+        };
+        let document = self.prepare_document(document);
+        let document = self.limit_paragraph_length(document)?;
+        self.details = document.details_lookup();
+
         let mut docx = Docx::new();
 
         // Apply document-level settings
+        self.margins = self.document_margins(&document);
         docx = self.apply_document_settings(docx)?;
+        if let Some(markdown) = source.filter(|_| self.config.output.embed_source.enabled) {
+            docx = self.embed_source(docx, markdown);
+        }
+        if self.config.output.provenance.property {
+            docx = docx.custom_property(provenance::GENERATOR_PROPERTY, provenance::generator_name());
+        }
 
         // Reset state at the beginning of document generation
         self.first_h1_encountered = false;
-        
-        if let Some(ref mut processor) = self.heading_processor {
-            info!("Resetting numbering state for new document generation");
-            processor.reset_state();
+        self.title_pending = self.config.styles.first_heading_title.enabled;
+        self.footnote_numbers = self.number_footnotes(&document);
+        self.next_comment_id = 0;
+        self.orientation = self.page_orientation();
+        self.anchors = AnchorAllocator::new();
+        self.bookmarks = BookmarkAllocator::new();
+        self.number_bookmarks.clear();
+        // Index markers use their marker numbers as bookmark ids
+        self.next_bookmark_id = self.index_entries.iter().map(|entry| entry.markers.len()).sum();
+        self.next_list_numbering = FIRST_LIST_NUMBERING;
+        self.quote_depth = 0;
+        if self.timings.is_some() {
+            self.timings = Some(GeneratorTimings::default());
+        }
+        self.pending_image = None;
+        self.embedded_image_bytes = 0;
+        self.reset_numbering();
 
-            // Validate numbering formats before starting document generation
-            if let Err(e) = processor.validate_numbering_formats() {
-                error!(
-                    error = %e,
-                    "Numbering format validation failed during document generation"
-                );
-                warn!("Continuing with document generation, numbering may be degraded");
-            }
+        if !self.metadata.is_empty() {
+            docx = self.add_metadata_table(docx)?;
+        }
+        if self.config.document.table_of_contents.enabled {
+            docx = self.add_table_of_contents(docx, &toc_entries)?;
         }
 
         // Process each markdown element
-        for element in &document.elements {
+        let mut elements = document.elements.iter().peekable();
+        while let Some(element) = elements.next() {
+            if let Some((title, subtitle)) = self.title_block(element, elements.peek().copied()) {
+                docx = self.add_title_block(docx, title, subtitle)?;
+                elements.next();
+                continue;
+            }
             docx = self.process_element(docx, element)?;
         }
+        docx = self.flush_pending_image(docx);
+        docx = self.add_footnotes_section(docx, &document)?;
+        docx = self.add_references_section(docx)?;
+        docx = self.add_index_section(docx)?;
+        docx = self.add_revision_notes_section(docx)?;
+        if self.config.output.provenance.colophon {
+            let colophon = InlineElement::Italic(provenance::colophon_text(&provenance::document_date()));
+            docx = docx.add_paragraph(Paragraph::new().add_run(self.create_run_from_inline(&colophon)?));
+        }
 
-        // Build and return the document bytes
-        let mut buf = Vec::new();
-        let mut cursor = Cursor::new(&mut buf);
-        docx.build()
-            .pack(&mut cursor)
-            .map_err(|e| ConversionError::DocxGeneration(format!("Failed to build docx: {}", e)))?;
+        // The final section takes the document-level section properties
+        docx = self.set_page_size(docx, self.orientation);
+
+        // Build the package parts and apply post-build fixups
+        let mut xml = docx.build();
+        xml.document = postprocess::mark_table_header_rows(&String::from_utf8_lossy(&xml.document))
+            .into_bytes();
+        xml.document = postprocess::set_run_languages(&String::from_utf8_lossy(&xml.document)).into_bytes();
+        xml.document = postprocess::drop_empty_table_borders(&String::from_utf8_lossy(&xml.document)).into_bytes();
+        xml.styles = postprocess::drop_empty_table_borders(&String::from_utf8_lossy(&xml.styles)).into_bytes();
+        if self.config.elements.abbreviations.mode == AbbreviationMode::Tooltip {
+            xml.document = postprocess::set_hyperlink_tooltips(&String::from_utf8_lossy(&xml.document))
+                .into_bytes();
+        }
+        let first_heading_title = &self.config.styles.first_heading_title;
+        let sets_core_title = !first_heading_title.enabled || first_heading_title.core_title;
+        if let Some(title) = Self::document_title(&document).filter(|_| sets_core_title) {
+            xml.doc_props.core =
+                postprocess::set_core_title(&String::from_utf8_lossy(&xml.doc_props.core), title)
+                    .into_bytes();
+        }
+        if let Some(ref wm) = self.config.document.watermark {
+            watermark::apply_to_package(
+                &mut xml,
+                wm,
+                self.content_area(),
+                &self.config.document.default_font.family,
+            );
+        }
 
-        Ok(buf)
+        // Write the package, verifying it first when the self-check is on
+        let pack_error = |e| ConversionError::DocxGeneration(format!("Failed to build docx: {}", e));
+        if self.config.output.self_check || self_check::forced_by_env() {
+            let mut buf = Vec::new();
+            package::write_package(xml, &mut buf).map_err(pack_error)?;
+            self_check::verify_package(&buf)?;
+            writer.write_all(&buf)?;
+        } else {
+            package::write_package(xml, writer).map_err(pack_error)?;
+        }
+        Ok(())
+    }
+
+    /// Store the source Markdown, or only its hash when over the size limit,
+    /// in custom document properties
+    fn embed_source(&self, docx: Docx, markdown: &str) -> Docx {
+        let hash = format!("{:x}", Sha256::digest(markdown.as_bytes()));
+        let docx = docx.custom_property(SOURCE_HASH_PROPERTY, hash);
+        if markdown.len() > self.config.output.embed_source.max_bytes {
+            debug!(
+                bytes = markdown.len(),
+                "Source Markdown over the embedding limit; storing its hash only"
+            );
+            return docx;
+        }
+        // docx-rs writes property values without escaping them
+        docx.custom_property(SOURCE_PROPERTY, postprocess::escape_xml(markdown))
+    }
+
+    /// Configured margins with the document's overrides applied
+    ///
+    /// Overrides that leave no room for text between the margins are ignored
+    /// with a warning.
+    fn document_margins(&self, document: &MarkdownDocument) -> Margins {
+        let configured = &self.config.document.margins;
+        let overrides = &document.margins;
+        if overrides.is_empty() {
+            return configured.clone();
+        }
+
+        let margins = Margins {
+            top: overrides.top.unwrap_or(configured.top),
+            bottom: overrides.bottom.unwrap_or(configured.bottom),
+            left: overrides.left.unwrap_or(configured.left),
+            right: overrides.right.unwrap_or(configured.right),
+        };
+        let page = &self.config.document.page_size;
+        if margins.validate().is_err()
+            || margins.left + margins.right >= page.width
+            || margins.top + margins.bottom >= page.height
+        {
+            warn!(?overrides, "Margin directive leaves no room on the page; ignoring it");
+            return configured.clone();
+        }
+        debug!(?margins, "Applying margin directive");
+        margins
     }
 
     /// Apply document-level settings (page size, margins, default font)
     fn apply_document_settings(&self, mut docx: Docx) -> Result<Docx, ConversionError> {
         let doc_config = &self.config.document;
 
-        // Apply page settings - docx.page_size takes width and height as separate u32 parameters
-        // FIXME Commented out for unexpected small page size
-        // docx = docx.page_size(
-        //     doc_config.page_size.width as u32,
-        //     doc_config.page_size.height as u32
-        // );
+        docx = self.set_page_size(docx, self.page_orientation());
 
         // Apply margins - create PageMargin and apply it
         let page_margin = PageMargin::new()
-            .top(doc_config.margins.top as i32)
-            .bottom(doc_config.margins.bottom as i32)
-            .left(doc_config.margins.left as i32)
-            .right(doc_config.margins.right as i32);
+            .top(self.margins.top as i32)
+            .bottom(self.margins.bottom as i32)
+            .left(self.margins.left as i32)
+            .right(self.margins.right as i32);
+
+        docx = docx
+            .page_margin(page_margin)
+            .default_tab_stop((doc_config.default_tab_stop * 20.0) as usize); // twips
+
+        if let Some(ref wm) = doc_config.watermark {
+            debug!("Adding watermark header");
+            docx = docx.header(watermark::build_header(wm, self.content_area())?);
+        }
 
-        docx = docx.page_margin(page_margin);
+        if self.config.styles.named_styles.enabled {
+            docx = self.add_named_styles(docx);
+        }
 
         // Add CodeBlock style for preserving formatting
         docx = self.add_code_block_style(docx)?;
+        docx = self.add_list_numberings(docx);
+
+        // Styles that give lists and tables their semantic structure
+        docx = docx
+            .add_style(
+                Style::new(postprocess::LIST_PARAGRAPH_STYLE, StyleType::Paragraph)
+                    .name("List Paragraph")
+                    .based_on("Normal"),
+            )
+            .add_style(
+                // Without borders, which docx-rs gives table styles by
+                // default; tables set their own
+                Style::new(postprocess::MARKDOWN_TABLE_STYLE, StyleType::Table)
+                    .name("Markdown Table")
+                    .table_property(TableProperty::without_borders()),
+            );
+        if self.config.styles.title_block.enabled || self.config.styles.first_heading_title.enabled {
+            docx = docx.add_style(Style::new(TITLE_STYLE, StyleType::Paragraph).name("Title").based_on("Normal"));
+        }
+        if self.config.styles.title_block.enabled {
+            docx = docx.add_style(Style::new(SUBTITLE_STYLE, StyleType::Paragraph).name("Subtitle").based_on("Normal"));
+        }
+        if self.config.elements.code_captions.enabled {
+            docx = docx.add_style(Style::new(CAPTION_STYLE, StyleType::Paragraph).name("Caption").based_on("Normal"));
+        }
 
         Ok(docx)
     }
 
+    /// Compute the heading outline the generated document would have
+    ///
+    /// Runs the same numbering pass as [`generate`](Self::generate) without
+    /// rendering anything, taking headings inside blockquotes and details
+    /// bodies in document order as they are written. Headings kept out of the
+    /// navigation pane (appended sections with `in_toc: false`) are not
+    /// included.
+    pub fn outline(&mut self, document: &MarkdownDocument) -> Vec<OutlineEntry> {
+        let document = self.prepare_document(document);
+        self.reset_numbering();
+        self.anchors = AnchorAllocator::new();
+        self.bookmarks = BookmarkAllocator::new();
+        self.title_pending = self.config.styles.first_heading_title.enabled;
+
+        let mut entries = Vec::new();
+        let mut previous = None;
+        for element in &document.elements {
+            let subtitle = previous.and_then(|title| self.title_block(title, Some(element)));
+            previous = Some(element);
+            if subtitle.is_some() {
+                continue;
+            }
+            // Container bodies are rendered through process_element, so
+            // their headings are numbered where they appear
+            let mut headings = Vec::new();
+            let mut collect = |element: &MarkdownElement| {
+                if let MarkdownElement::Heading { level, text, .. } = element {
+                    headings.push((*level, text.clone()));
+                }
+            };
+            collect(element);
+            element.traverse_children(&mut collect);
+            for (level, text) in headings {
+                if self.skips_heading(&text) {
+                    continue;
+                }
+                let numbered_text = if self.take_document_title(level, &text) {
+                    text.clone()
+                } else {
+                    self.number_heading(level, &text)
+                };
+                let (anchor, bookmark) = self.allocate_anchor(&text);
+                entries.push(OutlineEntry {
+                    level,
+                    numbered_text,
+                    text,
+                    anchor,
+                    bookmark,
+                });
+            }
+        }
+
+        let footnote_numbers = self.number_footnotes(&document);
+        let has_footnotes = document.elements.iter().any(|element| {
+            matches!(element, MarkdownElement::FootnoteDefinition { label, .. } if footnote_numbers.contains_key(label))
+        });
+        let appended = [
+            (has_footnotes, self.config.sections.footnotes.clone()),
+            (!self.references.is_empty(), self.config.sections.references.clone()),
+            (!self.index_entries.is_empty(), self.config.sections.index.clone()),
+            (!self.revision_notes.is_empty(), self.config.sections.revision_notes.clone()),
+        ];
+        for (present, section) in appended {
+            if !present || !section.in_toc {
+                continue;
+            }
+            let numbered_text = if section.numbered {
+                self.number_heading(section.level, &section.title)
+            } else {
+                section.title.clone()
+            };
+            let (anchor, bookmark) = self.allocate_anchor(&section.title);
+            entries.push(OutlineEntry {
+                level: section.level,
+                numbered_text,
+                anchor,
+                bookmark,
+                text: section.title,
+            });
+        }
+        entries
+    }
+
+    /// Next unique anchor for a heading, plus its bookmark name when heading
+    /// bookmarks are enabled
+    ///
+    /// Called for headings in outline order, so [`outline`](Self::outline)
+    /// reports the same names the rendered headings carry.
+    fn allocate_anchor(&mut self, text: &str) -> (String, Option<String>) {
+        let anchor = self.anchors.allocate(text);
+        let bookmark = (self.config.styles.heading_bookmarks.enabled || self.config.document.table_of_contents.enabled)
+            .then(|| self.bookmarks.allocate(&anchor));
+        (anchor, bookmark)
+    }
+
+    /// Apply text post-processing passes to a copy so the caller's AST is untouched
+    ///
+    /// Also collects the references list when links are rendered as references,
+    /// the index entries and the revision notes for their section.
+    fn prepare_document<'a>(&mut self, document: &'a MarkdownDocument) -> Cow<'a, MarkdownDocument> {
+        self.references.clear();
+        self.index_entries.clear();
+        self.revision_notes.clear();
+        let elements = &self.config.elements;
+        let cleanup = whitespace::WhitespaceCleanup {
+            trim_trailing: elements.whitespace.trim_trailing,
+            collapse_spaces: elements.whitespace.collapse_spaces,
+            tab_spaces: (elements.whitespace.tabs == ProseTabMode::Spaces).then_some(elements.whitespace.tab_spaces),
+        };
+        if !elements.emoji.enabled
+            && !elements.checkboxes.enabled
+            && !elements.link.autolink_emails
+            && !elements.references.enabled
+            && cleanup == whitespace::WhitespaceCleanup::default()
+            && !elements.emphasis_callouts.enabled
+            && !elements.link_buttons.enabled
+            && elements.abbreviations.terms.is_empty()
+            && !index::has_terms(document)
+            && (elements.revision_notes.mode == RevisionNotesMode::Off || !revisions::has_notes(document))
+        {
+            return Cow::Borrowed(document);
+        }
+
+        let mut document = document.clone();
+        match elements.revision_notes.mode {
+            RevisionNotesMode::Off => {}
+            RevisionNotesMode::Section => self.revision_notes = revisions::apply_to_document(&mut document, false),
+            RevisionNotesMode::Comments => {
+                revisions::apply_to_document(&mut document, true);
+            }
+        }
+        if cleanup != whitespace::WhitespaceCleanup::default() {
+            whitespace::apply_to_document(&mut document, cleanup);
+        }
+        if elements.emoji.enabled {
+            emoji::apply_to_document(&mut document);
+        }
+        if elements.link.autolink_emails {
+            email::apply_to_document(&mut document);
+        }
+        if elements.checkboxes.enabled {
+            checkbox::apply_to_document(&mut document);
+        }
+        // Before references turn links into text and markers
+        if elements.link_buttons.enabled {
+            link_buttons::apply_to_document(&mut document);
+        }
+        if elements.references.enabled {
+            self.references = references::apply_to_document(&mut document);
+        }
+        if elements.emphasis_callouts.enabled {
+            callouts::apply_to_document(&mut document);
+        }
+        if !elements.abbreviations.terms.is_empty() {
+            let tooltips = elements.abbreviations.mode == AbbreviationMode::Tooltip;
+            abbreviations::apply_to_document(&mut document, &elements.abbreviations.terms, tooltips);
+        }
+        self.index_entries = index::apply_to_document(&mut document);
+        Cow::Owned(document)
+    }
+
+    /// Split or truncate paragraphs over `elements.long_paragraphs.max_chars`
+    /// with a warning, or fail in strict mode
+    fn limit_paragraph_length<'a>(
+        &self,
+        document: Cow<'a, MarkdownDocument>,
+    ) -> Result<Cow<'a, MarkdownDocument>, ConversionError> {
+        let limit = &self.config.elements.long_paragraphs;
+        let Some(max_chars) = limit.max_chars else {
+            return Ok(document);
+        };
+        let oversized = long_paragraphs::oversized(&document, max_chars);
+        let Some(&longest) = oversized.iter().max() else {
+            return Ok(document);
+        };
+        if limit.strict {
+            return Err(ConversionError::ResourceLimit(format!(
+                "{} paragraph(s) over the {} character limit, the longest {} characters",
+                oversized.len(),
+                max_chars,
+                longest
+            )));
+        }
+        let truncate = limit.mode == LongParagraphMode::Truncate;
+        warn!(
+            paragraphs = oversized.len(),
+            longest,
+            max_chars,
+            "Paragraphs over the length limit; {}",
+            if truncate { "truncating them" } else { "splitting them" }
+        );
+        let mut document = document.into_owned();
+        long_paragraphs::apply_to_document(&mut document, max_chars, truncate);
+        Ok(Cow::Owned(document))
+    }
+
+    /// Restart heading numbering for a new document
+    fn reset_numbering(&mut self) {
+        if let Some(ref mut processor) = self.heading_processor {
+            info!("Resetting numbering state for new document generation");
+            processor.reset_state();
+
+            // Validate numbering formats before starting document generation
+            if let Err(e) = processor.validate_numbering_formats() {
+                error!(
+                    error = %e,
+                    "Numbering format validation failed during document generation"
+                );
+                warn!("Continuing with document generation, numbering may be degraded");
+            }
+        }
+    }
+
+    /// Title for the document properties, taken from the first H1 heading
+    fn document_title(document: &MarkdownDocument) -> Option<&str> {
+        document.elements.iter().find_map(|element| match element {
+            MarkdownElement::Heading { level: 1, text, .. } if !text.trim().is_empty() => {
+                Some(text.trim())
+            }
+            _ => None,
+        })
+    }
+
+    /// Printable area of the page in points
+    fn content_area(&self) -> watermark::ContentArea {
+        let page_size = &self.config.document.page_size;
+        watermark::ContentArea {
+            width: page_size.width - self.margins.left - self.margins.right,
+            height: page_size.height - self.margins.top - self.margins.bottom,
+        }
+    }
+
     /// Add CodeBlock style to prevent text wrapping and preserve formatting
     fn add_code_block_style(&self, mut docx: Docx) -> Result<Docx, ConversionError> {
         // Create a style for code blocks that preserves formatting
-        let style = Style::new("CodeBlock", StyleType::Paragraph)
+        let mut style = Style::new("CodeBlock", StyleType::Paragraph)
             .name("Code Block")
             .based_on("Normal");
 
-        // Note: For now, we'll keep the style simple as docx-rs has limited style configuration
-        // The main formatting will be applied directly to runs and paragraphs
+        // Without named styles the formatting is applied directly to runs
+        // and paragraphs
+        if self.config.styles.named_styles.enabled {
+            style = Self::apply_font_to_style(style, &self.config.styles.code_block.font);
+        }
 
         docx = docx.add_style(style);
         Ok(docx)
     }
 
+    /// Define the named styles of body text and headings from the configured
+    /// fonts: `Normal` through the document defaults, which docx-rs always
+    /// writes it empty on top of, and `Heading1` to `Heading6`
+    fn add_named_styles(&self, mut docx: Docx) -> Docx {
+        let font = &self.config.styles.paragraph.font;
+        docx = docx
+            .default_fonts(RunFonts::new().ascii(&font.family).east_asia(&font.family))
+            .default_size((font.size * 2.0) as usize);
+        for level in 1..=6u8 {
+            let heading_style = self
+                .config
+                .styles
+                .headings
+                .get(&level)
+                .unwrap_or_else(|| self.config.styles.headings.get(&1).unwrap());
+            let mut style = Style::new(heading_style_id(level), StyleType::Paragraph)
+                .name(format!("heading {}", level))
+                .based_on("Normal")
+                .next("Normal")
+                .outline_lvl((level - 1) as usize);
+            style = Self::apply_font_to_style(style, &heading_style.font)
+                .line_spacing(Self::heading_spacing(heading_style));
+            if let Some(alignment) = heading_style.alignment.as_deref().and_then(|a| AlignmentType::from_str(a).ok()) {
+                style = style.align(alignment);
+            }
+            docx = docx.add_style(style);
+        }
+        docx
+    }
+
+    /// Space before and after a heading, in twips
+    fn heading_spacing(heading_style: &crate::config::HeadingStyle) -> LineSpacing {
+        LineSpacing::new()
+            .before(Self::points_to_twips(heading_style.spacing_before))
+            .after(Self::points_to_twips(heading_style.spacing_after))
+    }
+
+    /// Give a named style the family, size, bold and italic of a font
+    fn apply_font_to_style(mut style: Style, font: &crate::config::FontConfig) -> Style {
+        style = style
+            .fonts(RunFonts::new().ascii(&font.family).east_asia(&font.family))
+            .size((font.size * 2.0) as usize);
+        if font.bold {
+            style = style.bold();
+        }
+        if font.italic {
+            style = style.italic();
+        }
+        style
+    }
+
+    /// Apply the paragraph font to a body text run, unless it comes from the
+    /// named styles
+    fn apply_body_font(&self, run: Run) -> Run {
+        if self.config.styles.named_styles.enabled {
+            return run;
+        }
+        let base_font = &self.config.styles.paragraph.font;
+        run.fonts(
+            RunFonts::new()
+                .ascii(&base_font.family)
+                .east_asia(&base_font.family),
+        )
+        .size((base_font.size * 2.0) as usize)
+    }
+
     /// Process a single markdown element
     fn process_element(
         &mut self,
         mut docx: Docx,
         element: &MarkdownElement,
     ) -> Result<Docx, ConversionError> {
+        if !matches!(element, MarkdownElement::Paragraph { .. }) {
+            docx = self.flush_pending_image(docx);
+        }
         match element {
-            MarkdownElement::Heading { level, text } => {
-                docx = self.add_heading(docx, *level, text)?;
+            MarkdownElement::Heading { level, text, .. } => {
+                if self.skips_heading(text) {
+                    debug!(level = *level, "Skipping empty heading");
+                } else {
+                    docx = self.add_heading(docx, *level, text)?;
+                }
             }
             MarkdownElement::Paragraph { content } => {
-                docx = self.add_paragraph(docx, content)?;
+                let classes = self.details.classes(element).to_vec();
+                docx = self.add_paragraph(docx, content, &classes)?;
             }
-            MarkdownElement::CodeBlock { language: _, code, processed } => {
+            MarkdownElement::CodeBlock { language, code, processed } => {
                 // Use processed code if available, otherwise use original
                 let final_code = processed.as_ref()
                     .map(|p| p.get_final_code())
                     .unwrap_or(code);
-                docx = self.add_code_block(docx, final_code)?;
+                let attributes = self.details.code_attributes(element).clone();
+                docx = self.add_code_block(docx, language.as_deref(), final_code, &attributes)?;
             }
             MarkdownElement::List { ordered, items } => {
-                docx = self.add_list(docx, *ordered, items)?;
+                docx = self.add_list(docx, *ordered, items, 0)?;
             }
-            MarkdownElement::Table { headers, rows } => {
-                docx = self.add_table(docx, headers, rows)?;
+            MarkdownElement::Table { headers, rows, links } => {
+                let handling = self.config.styles.table.wide_tables.handling;
+                if handling == WideTableHandling::Landscape && self.is_wide_table(headers, rows) {
+                    docx = self.add_landscape_table(docx, headers, rows, links)?;
+                } else {
+                    docx = self.add_table(docx, headers, rows, links, self.table_width())?;
+                }
+            }
+            MarkdownElement::HtmlTable(table) => {
+                if !table.rows.iter().all(Vec::is_empty) {
+                    let table = self.build_html_table(table, self.table_width(), 1)?;
+                    docx = docx.add_table(self.align_table(table));
+                }
             }
             MarkdownElement::Image {
                 alt_text,
@@ -170,42 +810,212 @@ impl DocxGenerator {
                 width,
                 height,
             } => {
+                let start = self.timings.is_some().then(Instant::now);
                 docx = self.add_image(docx, alt_text, url, *width, *height)?;
+                if let (Some(timings), Some(start)) = (self.timings.as_mut(), start) {
+                    timings.images += start.elapsed();
+                }
             }
             MarkdownElement::HorizontalRule => {
                 docx = self.add_horizontal_rule(docx)?;
             }
+            MarkdownElement::Details { summary, body } => {
+                docx = self.add_details(docx, summary, body, 1)?;
+            }
+            MarkdownElement::BlockQuote { content: body } => {
+                docx = self.add_blockquote(docx, body)?;
+            }
+            MarkdownElement::FootnoteDefinition { .. } => {
+                // Rendered in the appended notes section
+            }
+            MarkdownElement::SectionBreak { orientation } => {
+                docx = self.add_section_break(docx, *orientation);
+            }
         }
 
         Ok(docx)
     }
 
+    /// Orientation of the configured page size
+    fn page_orientation(&self) -> PageOrientation {
+        let page_size = &self.config.document.page_size;
+        if page_size.width > page_size.height {
+            PageOrientation::Landscape
+        } else {
+            PageOrientation::Portrait
+        }
+    }
+
+    /// Page width and height in twips for an orientation; the configured
+    /// page size, in points, gives the dimensions whichever way round it is
+    fn page_size_twips(&self, orientation: PageOrientation) -> (u32, u32) {
+        let page_size = &self.config.document.page_size;
+        let twips = |points: f32| (points * 20.0).round().max(0.0) as u32;
+        let short = twips(page_size.width.min(page_size.height));
+        let long = twips(page_size.width.max(page_size.height));
+        match orientation {
+            PageOrientation::Portrait => (short, long),
+            PageOrientation::Landscape => (long, short),
+        }
+    }
+
+    /// Set the document-level page size for an orientation
+    fn set_page_size(&self, docx: Docx, orientation: PageOrientation) -> Docx {
+        let (width, height) = self.page_size_twips(orientation);
+        let docx = docx.page_size(width, height);
+        match orientation {
+            PageOrientation::Portrait => docx,
+            PageOrientation::Landscape => docx.page_orient(PageOrientationType::Landscape),
+        }
+    }
+
+    /// End the current section and start one with the given orientation
+    ///
+    /// Word stores a section's properties on its last paragraph, so the
+    /// break paragraph carries the orientation of the section it closes.
+    /// The margins and headers are copied from the document's section.
+    fn add_section_break(&mut self, docx: Docx, orientation: PageOrientation) -> Docx {
+        if orientation == self.orientation {
+            return docx;
+        }
+
+        let (width, height) = self.page_size_twips(self.orientation);
+        let page_size = PageSize::new().size(width, height).orient(match self.orientation {
+            PageOrientation::Portrait => PageOrientationType::Portrait,
+            PageOrientation::Landscape => PageOrientationType::Landscape,
+        });
+        let section = docx.document.section_property.clone().page_size(page_size);
+        self.orientation = orientation;
+        docx.add_paragraph(Paragraph::new().section_property(section))
+    }
+
     /// Add a heading to the document
     fn add_heading(
         &mut self,
-        mut docx: Docx,
+        docx: Docx,
         level: u8,
         text: &str,
     ) -> Result<Docx, ConversionError> {
-        // Get heading style from config, fallback to level 1 if not found
-        let heading_style = self
+        if self.take_document_title(level, text) {
+            let title_style = self.config.styles.first_heading_title.style.clone();
+            let heading = NumberedHeading::unnumbered(text);
+            return self.write_heading(docx, level, &heading, true, Some(TITLE_STYLE), Some(&title_style));
+        }
+        let heading = self.number_heading_parts(level, text);
+        self.write_heading(docx, level, &heading, true, None, None)
+    }
+
+    /// Whether a heading is the document title: the first non-empty H1 when
+    /// `styles.first_heading_title` is enabled
+    ///
+    /// Call once for every heading written, in document order.
+    fn take_document_title(&mut self, level: u8, text: &str) -> bool {
+        if !self.title_pending || level != 1 || text.trim().is_empty() {
+            return false;
+        }
+        self.title_pending = false;
+        true
+    }
+
+    /// Title and subtitle texts when title blocks are enabled, `element` is
+    /// an H1 and `next` is an H2
+    fn title_block<'a>(
+        &self,
+        element: &'a MarkdownElement,
+        next: Option<&'a MarkdownElement>,
+    ) -> Option<(&'a str, &'a str)> {
+        if !self.config.styles.title_block.enabled {
+            return None;
+        }
+        match (element, next?) {
+            (
+                MarkdownElement::Heading { level: 1, text: title, .. },
+                MarkdownElement::Heading { level: 2, text: subtitle, .. },
+            ) if !title.trim().is_empty() && !subtitle.trim().is_empty() => Some((title, subtitle)),
+            _ => None,
+        }
+    }
+
+    /// Write an H1 as a title followed by an unnumbered subtitle
+    fn add_title_block(
+        &mut self,
+        docx: Docx,
+        title: &str,
+        subtitle: &str,
+    ) -> Result<Docx, ConversionError> {
+        let mut docx = if self.take_document_title(1, title) {
+            let title_style = self.config.styles.first_heading_title.style.clone();
+            let heading = NumberedHeading::unnumbered(title);
+            self.write_heading(docx, 1, &heading, true, Some(TITLE_STYLE), Some(&title_style))?
+        } else {
+            let heading = self.number_heading_parts(1, title);
+            self.write_heading(docx, 1, &heading, true, Some(TITLE_STYLE), None)?
+        };
+
+        let style = self
             .config
             .styles
             .headings
-            .get(&level)
+            .get(&2)
             .unwrap_or_else(|| self.config.styles.headings.get(&1).unwrap());
+        let mut run = Run::new()
+            .add_text(subtitle)
+            .fonts(RunFonts::new().ascii(&style.font.family).east_asia(&style.font.family))
+            .size((style.font.size * 2.0) as usize);
+        if style.font.bold {
+            run = run.bold();
+        }
+        if style.font.italic {
+            run = run.italic();
+        }
+        let mut paragraph = Paragraph::new().style(SUBTITLE_STYLE).add_run(run);
+        if let Some(alignment) = style.alignment.as_deref().and_then(|a| AlignmentType::from_str(a).ok()) {
+            paragraph = paragraph.align(alignment);
+        }
+        docx = docx.add_paragraph(paragraph);
+        Ok(docx)
+    }
+
+    /// Whether a heading is left out because it is empty and configured to be skipped
+    fn skips_heading(&self, text: &str) -> bool {
+        text.trim().is_empty() && self.config.elements.empty_headings == EmptyHeadingMode::Skip
+    }
+
+    /// Apply heading numbering to the text if a numbering processor is configured
+    fn number_heading(&mut self, level: u8, text: &str) -> String {
+        let heading = self.number_heading_parts(level, text);
+        self.heading_text(level, &heading)
+    }
+
+    /// Rendered text of a numbered heading
+    fn heading_text(&self, level: u8, heading: &NumberedHeading) -> String {
+        match self.heading_processor {
+            Some(ref processor) => processor.merge_heading(level, heading),
+            None => heading.text.clone(),
+        }
+    }
+
+    /// Number a heading, keeping the prefix apart from the text
+    fn number_heading_parts(&mut self, level: u8, text: &str) -> NumberedHeading {
+        let start = self.timings.is_some().then(Instant::now);
+        let heading = self.apply_heading_numbering(level, text);
+        if let (Some(timings), Some(start)) = (self.timings.as_mut(), start) {
+            timings.numbering += start.elapsed();
+        }
+        heading
+    }
 
-        // Process heading text with numbering if configured
-        let processed_text = if let Some(ref mut processor) = self.heading_processor {
-            match processor.process_heading(level, text) {
-                Ok(numbered_text) => {
+    fn apply_heading_numbering(&mut self, level: u8, text: &str) -> NumberedHeading {
+        if let Some(ref mut processor) = self.heading_processor {
+            match processor.process_heading_parts(level, text) {
+                Ok(heading) => {
                     debug!(
                         level = level,
                         original_text = text,
-                        numbered_text = %numbered_text,
+                        prefix = ?heading.prefix,
                         "Successfully processed heading with numbering"
                     );
-                    numbered_text
+                    heading
                 }
                 Err(e) => {
                     error!(
@@ -225,7 +1035,7 @@ impl DocxGenerator {
                         "Heading numbering degraded to fallback mode"
                     );
 
-                    text.to_string()
+                    NumberedHeading::unnumbered(text)
                 }
             }
         } else {
@@ -234,8 +1044,48 @@ impl DocxGenerator {
                 text = text,
                 "No numbering processor configured, using original text"
             );
-            text.to_string()
-        };
+            NumberedHeading::unnumbered(text)
+        }
+    }
+
+    /// Write heading paragraphs; `outline` controls whether the heading
+    /// appears in the navigation pane and table of contents, `style` sets a
+    /// paragraph style on the heading and `overrides` is applied on top of
+    /// the heading's configured formatting
+    fn write_heading(
+        &mut self,
+        mut docx: Docx,
+        level: u8,
+        heading: &NumberedHeading,
+        outline: bool,
+        style: Option<&str>,
+        overrides: Option<&crate::config::ClassStyle>,
+    ) -> Result<Docx, ConversionError> {
+        // Headings outside the outline get no anchor, matching outline()
+        let mut bookmarks = Vec::new();
+        if outline {
+            bookmarks.extend(self.allocate_anchor(&heading.text).1);
+            if self.config.styles.heading_bookmarks.by_number {
+                let number = heading.prefix.as_deref().and_then(number_bookmark);
+                // A number repeated after a numbering reset keeps its first heading
+                bookmarks.extend(number.filter(|name| self.number_bookmarks.insert(name.clone())));
+            }
+        }
+        let bookmarks: Vec<(usize, String)> = bookmarks
+            .into_iter()
+            .map(|name| {
+                self.next_bookmark_id += 1;
+                (self.next_bookmark_id - 1, name)
+            })
+            .collect();
+
+        // Get heading style from config, fallback to level 1 if not found
+        let heading_style = self
+            .config
+            .styles
+            .headings
+            .get(&level)
+            .unwrap_or_else(|| self.config.styles.headings.get(&1).unwrap());
 
         // For level 1 headings, add a page break before the heading (except for the first H1)
         if level == 1 {
@@ -249,30 +1099,93 @@ impl DocxGenerator {
             }
         }
 
-        let mut run = Run::new()
-            .add_text(&processed_text)
-            .fonts(
-                RunFonts::new()
-                    .ascii(&heading_style.font.family)
-                    .east_asia(&heading_style.font.family),
-            )
-            .size((heading_style.font.size * 2.0) as usize); // docx uses half-points
-
-        // Apply bold/italic conditionally
-        if heading_style.font.bold {
-            run = run.bold();
+        let named_styles = self.config.styles.named_styles.enabled;
+        let heading_run = || {
+            let mut run = Run::new();
+            // With named styles the heading font comes from the heading style
+            if !named_styles {
+                run = run
+                    .fonts(
+                        RunFonts::new()
+                            .ascii(&heading_style.font.family)
+                            .east_asia(&heading_style.font.family),
+                    )
+                    .size((heading_style.font.size * 2.0) as usize); // docx uses half-points
+
+                // Apply bold/italic conditionally
+                if heading_style.font.bold {
+                    run = run.bold();
+                }
+                if heading_style.font.italic {
+                    run = run.italic();
+                }
+            }
+            match overrides {
+                Some(class) => Self::apply_class_to_run(run, class),
+                None => run,
+            }
+        };
+
+        // A styled prefix or a tab separator needs separate runs; otherwise
+        // the heading is one run
+        let tab_separated = heading_style.numbering_separator == NumberingSeparator::Tab;
+        let start = bookmarks
+            .iter()
+            .fold(Paragraph::new(), |paragraph, (id, name)| paragraph.add_bookmark_start(*id, name));
+        let mut paragraph = match &heading.prefix {
+            Some(prefix) if heading_style.numbering_style.is_some() || tab_separated => {
+                let mut prefix_run = heading_run().add_text(prefix);
+                if let Some(prefix_style) = &heading_style.numbering_style {
+                    if let Some(style_name) = &prefix_style.style_name {
+                        prefix_run = prefix_run.style(style_name);
+                    }
+                    prefix_run = Self::apply_class_to_run(prefix_run, prefix_style);
+                }
+                let mut paragraph = start.add_run(prefix_run);
+                if tab_separated {
+                    let stop = heading_style
+                        .numbering_tab_stop
+                        .unwrap_or(self.config.document.default_tab_stop);
+                    let stop = (stop * 20.0) as usize; // twips
+                    paragraph = paragraph.add_tab(Tab::new().val(TabValueType::Left).pos(stop));
+                }
+                if !heading.text.is_empty() {
+                    if tab_separated {
+                        paragraph = paragraph
+                            .add_run(heading_run().add_tab())
+                            .add_run(heading_run().add_text(&heading.text));
+                    } else {
+                        let separator = heading_style.numbering_separator.as_str();
+                        paragraph = paragraph
+                            .add_run(heading_run().add_text(format!("{}{}", separator, heading.text)));
+                    }
+                }
+                paragraph
+            }
+            _ => start.add_run(heading_run().add_text(self.heading_text(level, heading))),
+        };
+        for (id, _) in bookmarks.iter().rev() {
+            paragraph = paragraph.add_bookmark_end(*id);
         }
-        if heading_style.font.italic {
-            run = run.italic();
+        if outline {
+            paragraph = paragraph.outline_lvl((level - 1) as usize);
+        } else if named_styles {
+            // Body text level, overriding the heading style's outline level
+            paragraph = paragraph.outline_lvl(9);
+        }
+        let named_style = named_styles.then(|| heading_style_id(level));
+        if let Some(style) = overrides
+            .and_then(|class| class.style_name.as_deref())
+            .or(style)
+            .or(named_style.as_deref())
+        {
+            paragraph = paragraph.style(style);
         }
 
-        // Create paragraph with spacing
-        let paragraph = Paragraph::new()
-            .add_run(run)
-            .outline_lvl((level - 1) as usize);
-
-        // Adding alignment
-        let paragraph = match heading_style.alignment.clone() {
+        // Adding alignment; a heading style already carries the configured one
+        let alignment = overrides.and_then(|class| class.alignment.clone());
+        let configured = heading_style.alignment.clone().filter(|_| named_style.is_none());
+        let paragraph = match alignment.or(configured) {
             Some(alignment) => match AlignmentType::from_str(alignment.as_str()) {
                 Ok(alignment_type) => paragraph.align(alignment_type),
                 Err(e) => {
@@ -283,40 +1196,163 @@ impl DocxGenerator {
             None => paragraph,
         };
 
-        // Apply paragraph spacing - for now, we'll skip this as docx-rs API is different
-        // TODO: Implement proper spacing when docx-rs API is clarified
-        // Add spacing before heading using empty paragraph
-        // Since docx-rs doesn't support paragraph spacing directly, we use empty paragraphs
-        let spacing_before = Paragraph::new()
-        .add_run(Run::new().add_text("\u{00A0}")) // Non-breaking space for minimal visibility
-        .size(1); // Small font size for minimal visual impact
-        docx = docx.add_paragraph(spacing_before);
+        // Adding spacing; a heading style already carries it
+        let paragraph = match named_style {
+            Some(_) => paragraph,
+            None => paragraph.line_spacing(Self::heading_spacing(heading_style)),
+        };
 
         docx = docx.add_paragraph(paragraph);
+        Ok(docx)
+    }
+
+    /// Write a `<details>` block: the summary as a bold line kept with the
+    /// body, then the body indented by one list indent per nesting `level`
+    ///
+    /// Word has no collapsible sections, so the body is always shown.
+    fn add_details(
+        &mut self,
+        mut docx: Docx,
+        summary: &str,
+        body: &[MarkdownElement],
+        level: usize,
+    ) -> Result<Docx, ConversionError> {
+        let step = self.config.elements.list.indent * 20.0; // twips
+        let outer = (step * (level - 1) as f32) as i32;
+        let indent = (step * level as f32) as i32;
+
+        if !summary.is_empty() {
+            let font = &self.config.styles.paragraph.font;
+            let run = Run::new()
+                .add_text(summary)
+                .fonts(RunFonts::new().ascii(&font.family).east_asia(&font.family))
+                .size((font.size * 2.0) as usize)
+                .bold();
+            let mut paragraph = Paragraph::new().keep_next(true).add_run(run);
+            if outer > 0 {
+                paragraph = paragraph.indent(Some(outer), None, None, None);
+            }
+            docx = docx.add_paragraph(paragraph);
+        }
 
-        // Add spacing after heading using empty paragraph
-        let spacing_after = Paragraph::new()
-            .add_run(Run::new().add_text("\u{00A0}")) // Non-breaking space for minimal visibility
-            .size(1); // Small font size for minimal visual impact
-        docx = docx.add_paragraph(spacing_after);
+        for element in body {
+            docx = match element {
+                MarkdownElement::Paragraph { content } => {
+                    let classes = self.details.classes(element).to_vec();
+                    let class_styles = self.resolve_classes(&classes);
+                    let paragraph = Paragraph::new().indent(Some(indent), None, None, None);
+                    let mut next_comment_id = self.next_comment_id;
+                    let paragraph = self.add_inline_runs(paragraph, content, &class_styles, &mut next_comment_id)?;
+                    self.next_comment_id = next_comment_id;
+                    docx.add_paragraph(paragraph)
+                }
+                MarkdownElement::CodeBlock { language, code, processed } => {
+                    let final_code = processed.as_ref().map(|p| p.get_final_code()).unwrap_or(code);
+                    let attributes = self.details.code_attributes(element).clone();
+                    self.add_code_block_indented(docx, language.as_deref(), final_code, &attributes, indent)?
+                }
+                MarkdownElement::List { ordered, items } => self.add_list(docx, *ordered, items, level)?,
+                MarkdownElement::Details { summary, body } => self.add_details(docx, summary, body, level + 1)?,
+                other => self.process_element(docx, other)?,
+            };
+        }
+        Ok(docx)
+    }
 
+    /// Add the elements of a blockquote, one level deeper than the quote
+    /// around it
+    fn add_blockquote(&mut self, mut docx: Docx, body: &[MarkdownElement]) -> Result<Docx, ConversionError> {
+        self.quote_depth += 1;
+        for element in body {
+            match self.process_element(docx, element) {
+                Ok(next) => docx = next,
+                Err(error) => {
+                    self.quote_depth -= 1;
+                    return Err(error);
+                }
+            }
+        }
+        self.quote_depth -= 1;
         Ok(docx)
     }
 
+    /// Left indent in twips and left border of paragraphs in the current
+    /// blockquote, per `styles.blockquote`
+    ///
+    /// The indent sums the indents of every depth up to the current one; the
+    /// border takes the color of the current depth.
+    fn blockquote_formatting(&self) -> Option<(i32, Option<ParagraphBorder>)> {
+        if self.quote_depth == 0 {
+            return None;
+        }
+        let style = &self.config.styles.blockquote;
+        let indent: f32 = (1..=self.quote_depth)
+            .filter_map(|depth| style.level(depth))
+            .map(|level| level.indent)
+            .sum();
+        let border = style
+            .level(self.quote_depth)
+            .and_then(|level| level.border_color.as_deref())
+            .filter(|_| style.border_width > 0.0)
+            .map(|color| {
+                ParagraphBorder::new(ParagraphBorderPosition::Left)
+                    .val(BorderType::Single)
+                    .size((style.border_width * 8.0) as usize)
+                    .space(4)
+                    .color(color.trim_start_matches('#'))
+            });
+        Some(((indent * 20.0) as i32, border))
+    }
+
     /// Add a paragraph to the document
     fn add_paragraph(
-        &self,
+        &mut self,
         mut docx: Docx,
         content: &[InlineElement],
+        classes: &[String],
     ) -> Result<Docx, ConversionError> {
-        let mut paragraph =
-            Paragraph::new().indent(None, Some(SpecialIndentType::FirstLine(315)), None, None);
+        let pending_image = self.pending_image.take();
+        let mut class_styles = self.resolve_classes(classes);
+        let quote = self.blockquote_formatting();
+        if quote.is_some() {
+            class_styles.insert(0, &self.config.styles.blockquote.font);
+        }
+        let left_indent = class_styles
+            .iter()
+            .rev()
+            .find_map(|class| class.indent)
+            .map(|indent| (indent * 20.0) as i32)
+            .or(quote.as_ref().map(|(indent, _)| *indent));
+        let mut paragraph = Paragraph::new().indent(
+            left_indent,
+            Some(SpecialIndentType::FirstLine(315)),
+            None,
+            None,
+        );
+        if let Some((_, Some(border))) = quote {
+            paragraph = paragraph.set_borders(ParagraphBorders::with_empty().set(border));
+        }
 
-        for inline in content {
-            let run = self.create_run_from_inline(inline)?;
-            paragraph = paragraph.add_run(run);
+        for class in &class_styles {
+            if let Some(style_name) = &class.style_name {
+                paragraph = paragraph.style(style_name);
+            }
+            if let Some(alignment) = &class.alignment {
+                match AlignmentType::from_str(alignment) {
+                    Ok(alignment_type) => paragraph = paragraph.align(alignment_type),
+                    Err(e) => error!("Unknown alignment: {}. Error: {}", alignment, e),
+                }
+            }
+        }
+
+        if let Some(image_run) = pending_image {
+            paragraph = paragraph.add_run(image_run);
         }
 
+        let mut next_comment_id = self.next_comment_id;
+        paragraph = self.add_inline_runs(paragraph, content, &class_styles, &mut next_comment_id)?;
+        self.next_comment_id = next_comment_id;
+
         // Apply paragraph style settings - for now, we'll skip spacing as docx-rs API is different
         // TODO: Implement proper paragraph spacing and line spacing when docx-rs API is clarified
 
@@ -410,20 +1446,121 @@ impl DocxGenerator {
         Ok(docx)
     }
 
+    /// Add a run per inline element, wrapping each run that reviewer comments
+    /// follow in the comments' ranges
+    fn add_inline_runs(
+        &self,
+        mut paragraph: Paragraph,
+        content: &[InlineElement],
+        class_styles: &[&crate::config::ClassStyle],
+        next_comment_id: &mut usize,
+    ) -> Result<Paragraph, ConversionError> {
+        let mut inlines = content.iter().peekable();
+        while let Some(inline) = inlines.next() {
+            if let InlineElement::IndexMarker(marker) = inline {
+                paragraph = paragraph
+                    .add_bookmark_start(*marker, index::bookmark_name(*marker))
+                    .add_bookmark_end(*marker);
+                continue;
+            }
+            if let InlineElement::Comment(text) = inline {
+                // Nothing precedes the comment, so it marks a point rather than a range
+                let id = *next_comment_id;
+                *next_comment_id += 1;
+                paragraph = paragraph
+                    .add_comment_start(self.create_comment(id, text))
+                    .add_comment_end(id);
+                continue;
+            }
+
+            let mut comment_ids = Vec::new();
+            while let Some(InlineElement::Comment(text)) = inlines.peek() {
+                let id = *next_comment_id;
+                *next_comment_id += 1;
+                paragraph = paragraph.add_comment_start(self.create_comment(id, text));
+                comment_ids.push(id);
+                inlines.next();
+            }
+
+            let clause = self.clause_reference(inline);
+            let mut run = match (clause, inline) {
+                // An empty clause link shows the clause number
+                (Some(number), InlineElement::Link { text, title, .. }) if text.is_empty() => {
+                    self.create_run_from_inline(&InlineElement::Link {
+                        text: number.to_string(),
+                        url: format!("#{}", number),
+                        title: title.clone(),
+                    })?
+                }
+                _ => self.create_run_from_inline(inline)?,
+            };
+            for class in class_styles {
+                run = Self::apply_class_to_run(run, class);
+            }
+            paragraph = match (clause, inline) {
+                (Some(number), _) => paragraph.add_hyperlink(Hyperlink::new(number, HyperlinkType::Anchor).add_run(run)),
+                (None, InlineElement::Abbreviation { title, .. }) => paragraph.add_hyperlink(
+                    Hyperlink::new(postprocess::tooltip_anchor(title), HyperlinkType::Anchor).add_run(run),
+                ),
+                (None, InlineElement::Link { url, .. }) if is_external_link(url) => {
+                    paragraph.add_hyperlink(Hyperlink::new(url, HyperlinkType::External).add_run(run))
+                }
+                (None, _) => paragraph.add_run(run),
+            };
+            for id in comment_ids {
+                paragraph = paragraph.add_comment_end(id);
+            }
+        }
+        Ok(paragraph)
+    }
+
+    /// Apply the configured link color and underline to a run
+    fn style_link_run(&self, run: Run) -> Run {
+        let link_color = self.config.elements.link.color.trim_start_matches('#');
+        let run = run.color(link_color);
+        if self.config.elements.link.underline {
+            run.underline("single")
+        } else {
+            run
+        }
+    }
+
+    /// Clause number a link points at, for `#2.3.1` style links when
+    /// headings are bookmarked by number
+    fn clause_reference<'a>(&self, inline: &'a InlineElement) -> Option<&'a str> {
+        if !self.config.styles.heading_bookmarks.by_number {
+            return None;
+        }
+        match inline {
+            InlineElement::Link { url, .. } => url
+                .strip_prefix('#')
+                .filter(|target| is_clause_number(target)),
+            _ => None,
+        }
+    }
+
+    /// Word comment holding the text of a comment directive
+    fn create_comment(&self, id: usize, text: &str) -> Comment {
+        let base_font = &self.config.styles.paragraph.font;
+        let run = Run::new()
+            .add_text(text)
+            .fonts(
+                RunFonts::new()
+                    .ascii(&base_font.family)
+                    .east_asia(&base_font.family),
+            );
+        Comment::new(id)
+            .author(COMMENT_AUTHOR)
+            .add_paragraph(Paragraph::new().add_run(run))
+    }
+
     /// Create a run from an inline element
     fn create_run_from_inline(&self, inline: &InlineElement) -> Result<Run, ConversionError> {
         let base_font = &self.config.styles.paragraph.font;
 
         match inline {
             InlineElement::Text(text) => {
-                let mut run = Run::new()
-                    .add_text(text)
-                    .fonts(
-                        RunFonts::new()
-                            .ascii(&base_font.family)
-                            .east_asia(&base_font.family),
-                    )
-                    .size((base_font.size * 2.0) as usize);
+                let mut run = self.apply_body_font(Self::add_prose_text(Run::new(), text));
 
                 // Apply base font formatting
                 if base_font.bold {
@@ -435,33 +1572,9 @@ impl DocxGenerator {
 
                 Ok(run)
             }
-            InlineElement::Bold(text) => Ok(Run::new()
-                .add_text(text)
-                .fonts(
-                    RunFonts::new()
-                        .ascii(&base_font.family)
-                        .east_asia(&base_font.family),
-                )
-                .size((base_font.size * 2.0) as usize)
-                .bold()),
-            InlineElement::Italic(text) => Ok(Run::new()
-                .add_text(text)
-                .fonts(
-                    RunFonts::new()
-                        .ascii(&base_font.family)
-                        .east_asia(&base_font.family),
-                )
-                .size((base_font.size * 2.0) as usize)
-                .italic()),
-            InlineElement::Strikethrough(text) => Ok(Run::new()
-                .add_text(text)
-                .fonts(
-                    RunFonts::new()
-                        .ascii(&base_font.family)
-                        .east_asia(&base_font.family),
-                )
-                .size((base_font.size * 2.0) as usize)
-                .strike()),
+            InlineElement::Bold(text) => Ok(self.apply_body_font(Self::add_prose_text(Run::new(), text)).bold()),
+            InlineElement::Italic(text) => Ok(self.apply_body_font(Self::add_prose_text(Run::new(), text)).italic()),
+            InlineElement::Strikethrough(text) => Ok(self.apply_body_font(Self::add_prose_text(Run::new(), text)).strike()),
             InlineElement::Code(text) => {
                 let code_font = &self.config.styles.code_block.font;
                 let mut run = Run::new()
@@ -484,941 +1597,1598 @@ impl DocxGenerator {
                 // Add background color if specified
                 if let Some(bg_color) = &self.config.styles.code_block.background_color {
                     let color = bg_color.trim_start_matches('#');
-                    run = run.highlight(color);
+                    run = run.shading(Shading::new().fill(color));
                 }
 
                 Ok(run)
             }
-            InlineElement::Link {
-                text,
-                url: _,
-                title: _,
-            } => {
-                let link_color = self.config.elements.link.color.trim_start_matches('#');
-                let mut run = Run::new()
-                    .add_text(text)
-                    .fonts(
-                        RunFonts::new()
-                            .ascii(&base_font.family)
-                            .east_asia(&base_font.family),
-                    )
-                    .size((base_font.size * 2.0) as usize)
-                    .color(link_color);
-
-                // Add underline if configured
-                if self.config.elements.link.underline {
-                    run = run.underline("single");
+            // The target comes from the hyperlink the run is wrapped in
+            InlineElement::Link { text, .. } => Ok(self.style_link_run(self.apply_body_font(Run::new().add_text(text)))),
+            // The tooltip comes from the hyperlink the run is wrapped in
+            InlineElement::Abbreviation { text, .. } => self.create_run_from_inline(&InlineElement::Text(text.clone())),
+            InlineElement::Span { text, classes, lang } => {
+                let mut run = self.create_run_from_inline(&InlineElement::Text(text.clone()))?;
+                let mut style = None;
+                for class in self.resolve_classes(classes) {
+                    if let Some(style_name) = &class.style_name {
+                        style = Some(style_name.as_str());
+                    }
+                    run = Self::apply_class_to_run(run, class);
+                }
+                match (lang, style) {
+                    // The language is set by `postprocess::set_run_languages`
+                    (Some(lang), style) => run = run.style(&postprocess::language_style(lang, style)),
+                    (None, Some(style)) => run = run.style(style),
+                    (None, None) => {}
                 }
-
                 Ok(run)
             }
+            InlineElement::FootnoteReference(label) => {
+                let number = self
+                    .footnote_numbers
+                    .get(label)
+                    .map(|n| n.to_string())
+                    .unwrap_or_else(|| label.clone());
+                Ok(self.create_footnote_marker(&number))
+            }
+            InlineElement::ReferenceMarker(number) => {
+                Ok(self.create_footnote_marker(&format!("[{}]", number)))
+            }
+            // Comments become comment ranges around runs, and index markers
+            // bookmarks between them, not runs of their own; revision notes
+            // are collected or made comments before rendering
+            InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_) => Ok(Run::new()),
         }
     }
 
-    /// Add a code block to the document as a single-row table
-    fn add_code_block(&self, mut docx: Docx, code: &str) -> Result<Docx, ConversionError> {
-        // Check if this is a note block with special formatting
-        if code.contains("[NOTE_BLOCK_START]") && code.contains("[NOTE_BLOCK_END]") {
-            return self.add_note_block(docx, code);
+    /// Add prose text to a run, writing its tab characters as tabs to the
+    /// next tab stop
+    fn add_prose_text(mut run: Run, text: &str) -> Run {
+        if !text.contains('\t') {
+            return run.add_text(text);
         }
-
-        let code_style = &self.config.styles.code_block;
-
-        // Add spacing before code block using empty paragraph
-        // Since docx-rs doesn't support paragraph spacing directly, we use empty paragraphs
-        let spacing_before = Paragraph::new()
-            .add_run(Run::new().add_text("\u{00A0}")) // Non-breaking space for minimal visibility
-            .size(1); // Small font size for minimal visual impact
-        docx = docx.add_paragraph(spacing_before);
-
-        // Create table cell with code content using the helper method
-        let cell = self
-            .create_code_block_cell_with_markdown(code, code_style)?
-            .width(8300, WidthType::Dxa);
-
-        // Create single-row, single-column table
-        let row = TableRow::new(vec![cell]);
-        let mut table =
-            Table::new(vec![row]).margins(TableCellMargins::new().margin(100, 100, 100, 100));
-
-        // Apply border styling based on border_width configuration
-        if code_style.border_width > 0.0 {
-            table = self.apply_table_borders(table, code_style.border_width)?;
+        for (index, part) in text.split('\t').enumerate() {
+            if index > 0 {
+                run = run.add_tab();
+            }
+            if !part.is_empty() {
+                run = run.add_text(part);
+            }
         }
-
-        // Add the table to the document
-        docx = docx.add_table(table);
-
-        // Add spacing after code block using empty paragraph
-        let spacing_after = Paragraph::new()
-            .add_run(Run::new().add_text("\u{00A0}")) // Non-breaking space for minimal visibility
-            .size(1); // Small font size for minimal visual impact
-        docx = docx.add_paragraph(spacing_after);
-
-        Ok(docx)
+        run
     }
 
-    /// Add a note block with special formatting (title, icon, content)
-    fn add_note_block(&self, mut docx: Docx, code: &str) -> Result<Docx, ConversionError> {
-        // Parse the note block markers
-        let title = self.extract_marker_content(code, "[TITLE]", "[/TITLE]");
-        let icon_path = self.extract_marker_content(code, "[ICON]", "[/ICON]");
-        let content = self.extract_marker_content(code, "[CONTENT]", "[/CONTENT]");
-
-        // Add spacing before note block
-        let spacing_before = Paragraph::new()
-            .add_run(Run::new().add_text("\u{00A0}"))
-            .size(1);
-        docx = docx.add_paragraph(spacing_before);
-
-        // Create the note block as a two-column table
-        // Left column: title + content
-        // Right column: icon (if available)
+    /// Superscript footnote marker
+    fn create_footnote_marker(&self, number: &str) -> Run {
+        let base_font = &self.config.styles.paragraph.font;
+        let mut run = Run::new()
+            .add_text(number)
+            .fonts(
+                RunFonts::new()
+                    .ascii(&base_font.family)
+                    .east_asia(&base_font.family),
+            )
+            .size((base_font.size * 2.0) as usize);
+        run.run_property = run.run_property.vert_align(VertAlignType::SuperScript);
+        run
+    }
 
-        let mut left_cell = TableCell::new().clear_border(TableCellBorderPosition::Right);
-        
-        // Add title with special formatting (larger, bold, italic)
-        if let Some(title_text) = title {
-            let title_run = Run::new()
-                .add_text(&title_text)
-                .bold()
-                .italic()
-                .size(23); // 14pt * 2 = 28 half-points (1.2x of 12pt ≈ 14pt)
-            
-            let title_paragraph = Paragraph::new()
-                .add_run(title_run);
-            
-            left_cell = left_cell.add_paragraph(title_paragraph);
-            
-            // Add spacing after title
-            left_cell = left_cell.add_paragraph(Paragraph::new());
+    /// Number footnotes by first reference; unreferenced definitions follow
+    /// in document order unless configured to be omitted
+    ///
+    /// Logs a warning for each unreferenced definition and each reference
+    /// without a definition. The latter keep their number so the marker is
+    /// still rendered.
+    fn number_footnotes(&self, document: &MarkdownDocument) -> HashMap<String, usize> {
+        fn visit(inlines: &[InlineElement], numbers: &mut HashMap<String, usize>) {
+            for inline in inlines {
+                if let InlineElement::FootnoteReference(label) = inline {
+                    let next = numbers.len() + 1;
+                    numbers.entry(label.clone()).or_insert(next);
+                }
+            }
         }
-        
-        // Add content
-        if let Some(content_text) = content {
-            for line in content_text.lines() {
-                if !line.trim().is_empty() {
-                    let content_run = Run::new().add_text(line);
-                    let content_paragraph = Paragraph::new().add_run(content_run);
-                    left_cell = left_cell.add_paragraph(content_paragraph);
-                } else {
-                    // Empty line
-                    left_cell = left_cell.add_paragraph(Paragraph::new());
+        fn visit_items(items: &[ListItem], numbers: &mut HashMap<String, usize>) {
+            for item in items {
+                for content in item.paragraphs() {
+                    visit(content, numbers);
                 }
+                visit_items(&item.sub_items, numbers);
             }
         }
-        
-        // Set cell width and styling
-        left_cell = left_cell
-            .width(6800, WidthType::Dxa)
-            .vertical_align(docx_rs::VAlignType::Top);
-
-        // Create right cell for icon
-        let mut right_cell = TableCell::new().clear_border(TableCellBorderPosition::Left);
-        
-        // Try to add icon if path is provided
-        if let Some(icon_path) = icon_path {
-            // // For now, we'll add a placeholder text instead of an actual image
-            // // In a full implementation, you would load and embed the image
-            // let icon_run = Run::new()
-            //     .add_text("💡") // Unicode lightbulb as placeholder
-            //     .size(32); // 16pt
-            
-            // let icon_paragraph = Paragraph::new()
-            //     .add_run(icon_run)
-            //     .align(docx_rs::AlignmentType::Right);
-            
-            // left_cell = left_cell.add_paragraph(icon_paragraph);
 
-            // Try to embed local image
-            let right_style = Style::new("Right", StyleType::Paragraph)
-                .name("Right")
-                .align(AlignmentType::Right);
-            docx = docx.add_style(right_style);
-
-            match self.embed_local_image_sized(icon_path.as_str(), "", 90, 60, &ImageConfig { max_width: 1500.0, max_height: 1000.0, }) {
-                Ok(image_run) => {
-                    let paragraph = Paragraph::new().add_run(image_run);
-                    right_cell = right_cell.add_paragraph(paragraph.style("Right"));
+        let mut numbers = HashMap::new();
+        for element in &document.elements {
+            match element {
+                MarkdownElement::Paragraph { content, .. } => visit(content, &mut numbers),
+                MarkdownElement::List { items, .. } => visit_items(items, &mut numbers),
+                _ => {}
+            }
+        }
+        let referenced = numbers.len();
+        let mut defined = HashSet::new();
+        for element in &document.elements {
+            let MarkdownElement::FootnoteDefinition { label, .. } = element else {
+                continue;
+            };
+            defined.insert(label.as_str());
+            if numbers.contains_key(label) {
+                continue;
+            }
+            match self.config.elements.footnotes.orphan_definitions {
+                OrphanFootnoteMode::List => {
+                    warn!(label = %label, "Footnote is never referenced; listing it after the referenced ones");
+                    let next = numbers.len() + 1;
+                    numbers.insert(label.clone(), next);
                 }
-                Err(e) => {
-                    warn!("embed_local_image_sized failed: {:?}", e);
+                OrphanFootnoteMode::Omit => {
+                    warn!(label = %label, "Footnote is never referenced; omitting it");
                 }
             }
         }
-        
-        right_cell = right_cell
-            .width(1500, WidthType::Dxa)
-            .vertical_align(docx_rs::VAlignType::Top);
-
-        // Create table row with both cells
-        let row = TableRow::new(vec![left_cell, right_cell]);
-        // let row = TableRow::new(vec![left_cell]);
-        
-        // Create table with light background and border
-        let mut table = Table::new(vec![row])
-            .margins(TableCellMargins::new().margin(100, 100, 100, 100));
-        
-        // Apply light border
-        table = self.apply_table_borders_with_type(table, 1.0, BorderType::Dashed)?;
-        
-        // Add the table to the document
-        docx = docx.add_table(table);
-
-        // Add spacing after note block
-        let spacing_after = Paragraph::new()
-            .add_run(Run::new().add_text("\u{00A0}"))
-            .size(1);
-        docx = docx.add_paragraph(spacing_after);
-
-        Ok(docx)
-    }
-
-    /// Extract content between markers
-    fn extract_marker_content(&self, text: &str, start_marker: &str, end_marker: &str) -> Option<String> {
-        if let Some(start_pos) = text.find(start_marker) {
-            let content_start = start_pos + start_marker.len();
-            if let Some(end_pos) = text[content_start..].find(end_marker) {
-                let content = &text[content_start..content_start + end_pos];
-                // return Some(content.trim().to_string());
-                return Some(content.to_string());
+        for (label, number) in &numbers {
+            if *number <= referenced && !defined.contains(label.as_str()) {
+                warn!(label = %label, "Footnote reference has no definition");
             }
         }
-        None
+        numbers
     }
 
-    /// Add a list to the document
-    fn add_list(
-        &self,
-        mut docx: Docx,
-        ordered: bool,
-        items: &[ListItem],
+    /// Add the heading of an appended section such as the footnotes
+    fn add_section_heading(
+        &mut self,
+        docx: Docx,
+        section: &crate::config::AppendedSectionConfig,
     ) -> Result<Docx, ConversionError> {
-        docx = self.add_list_with_depth(docx, ordered, items, 0)?;
-        Ok(docx)
+        let heading = if section.numbered {
+            self.number_heading_parts(section.level, &section.title)
+        } else {
+            NumberedHeading::unnumbered(&section.title)
+        };
+        self.write_heading(docx, section.level, &heading, section.in_toc, None, None)
     }
 
-    /// Add a list to the document with specified depth for indentation
-    fn add_list_with_depth(
-        &self,
+    /// Append footnote definitions, in footnote number order, under the notes heading
+    fn add_footnotes_section(
+        &mut self,
         mut docx: Docx,
-        ordered: bool,
-        items: &[ListItem],
-        depth: usize,
+        document: &MarkdownDocument,
     ) -> Result<Docx, ConversionError> {
-        let list_config = &self.config.elements.list;
-        let _indent_amount = list_config.indent * (depth + 1) as f32;
-
-        for (index, item) in items.iter().enumerate() {
-            let bullet = if ordered {
-                format!("{}. ", index + 1)
-            } else {
-                match depth % 3 {
-                    0 => "• ".to_string(),
-                    1 => "◦ ".to_string(),
-                    _ => "▪ ".to_string(),
+        let mut definitions: Vec<(usize, &[InlineElement])> = document
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                MarkdownElement::FootnoteDefinition { label, content } => {
+                    self.footnote_numbers.get(label).map(|n| (*n, content.as_slice()))
                 }
-            };
-
-            let mut paragraph = Paragraph::new();
+                _ => None,
+            })
+            .collect();
+        if definitions.is_empty() {
+            return Ok(docx);
+        }
+        definitions.sort_by_key(|(number, _)| *number);
+        debug!(footnotes = definitions.len(), "Appending footnotes section");
+
+        let section = self.config.sections.footnotes.clone();
+        docx = self.add_section_heading(docx, &section)?;
+        for (number, content) in definitions {
+            let mut paragraph = Paragraph::new().add_run(self.create_footnote_marker(&number.to_string()));
+            paragraph = paragraph.add_run(self.create_run_from_inline(&InlineElement::Text(" ".to_string()))?);
+            let mut next_comment_id = self.next_comment_id;
+            paragraph = self.add_inline_runs(paragraph, content, &[], &mut next_comment_id)?;
+            self.next_comment_id = next_comment_id;
+            docx = docx.add_paragraph(paragraph);
+        }
+        Ok(docx)
+    }
 
-            // TODO: Add proper indentation when docx-rs API supports it
-            // For now, we'll use spaces for indentation
-            if depth > 0 {
-                let indent_spaces = "    ".repeat(depth);
-                paragraph = paragraph.add_run(Run::new().add_text(&indent_spaces));
+    /// Append the collected link references under the references heading
+    fn add_references_section(&mut self, mut docx: Docx) -> Result<Docx, ConversionError> {
+        if self.references.is_empty() {
+            return Ok(docx);
+        }
+        debug!(references = self.references.len(), "Appending references section");
+
+        let section = self.config.sections.references.clone();
+        docx = self.add_section_heading(docx, &section)?;
+        if self.config.elements.references.group_by_domain {
+            for (domain, group) in references::group_by_domain(&self.references) {
+                let heading = self.create_run_from_inline(&InlineElement::Bold(domain.to_string()))?;
+                docx = docx.add_paragraph(Paragraph::new().add_run(heading));
+                for reference in group {
+                    docx = docx.add_paragraph(self.create_reference_paragraph(reference)?);
+                }
             }
-
-            // Add bullet/number
-            paragraph = paragraph.add_run(Run::new().add_text(&bullet));
-
-            // Add item content
-            for inline in &item.content {
-                let run = self.create_run_from_inline(inline)?;
-                paragraph = paragraph.add_run(run);
+        } else {
+            for reference in &self.references {
+                docx = docx.add_paragraph(self.create_reference_paragraph(reference)?);
             }
+        }
+        Ok(docx)
+    }
 
+    /// Append the index under the index heading: one paragraph per term,
+    /// followed by the page of each place it is marked
+    fn add_index_section(&mut self, mut docx: Docx) -> Result<Docx, ConversionError> {
+        if self.index_entries.is_empty() {
+            return Ok(docx);
+        }
+        debug!(terms = self.index_entries.len(), "Appending index section");
+
+        let section = self.config.sections.index.clone();
+        docx = self.add_section_heading(docx, &section)?;
+        for entry in &self.index_entries {
+            let mut paragraph = Paragraph::new().add_run(self.create_run_from_inline(&InlineElement::Text(entry.term.clone()))?);
+            for marker in &entry.markers {
+                paragraph = paragraph.add_run(self.create_run_from_inline(&InlineElement::Text(", ".to_string()))?);
+                for run in Self::page_reference_runs(&index::bookmark_name(*marker)) {
+                    paragraph = paragraph.add_run(run);
+                }
+            }
             docx = docx.add_paragraph(paragraph);
+        }
+        Ok(docx)
+    }
 
-            // Handle sub-items recursively with increased depth
-            if !item.sub_items.is_empty() {
-                docx = self.add_list_with_depth(docx, ordered, &item.sub_items, depth + 1)?;
-            }
+    /// Append the revision notes, numbered in document order, under the
+    /// revision notes heading
+    fn add_revision_notes_section(&mut self, mut docx: Docx) -> Result<Docx, ConversionError> {
+        if self.revision_notes.is_empty() {
+            return Ok(docx);
         }
+        debug!(notes = self.revision_notes.len(), "Appending revision notes section");
 
+        let section = self.config.sections.revision_notes.clone();
+        docx = self.add_section_heading(docx, &section)?;
+        for (number, note) in (1..).zip(&self.revision_notes) {
+            let text = InlineElement::Text(format!("{}. {}", number, note));
+            docx = docx.add_paragraph(Paragraph::new().add_run(self.create_run_from_inline(&text)?));
+        }
         Ok(docx)
     }
 
-    /// Add a table to the document
-    fn add_table(
-        &self,
-        mut docx: Docx,
-        headers: &[String],
-        rows: &[Vec<String>],
-    ) -> Result<Docx, ConversionError> {
-        let table_style = &self.config.styles.table;
-
-        // Calculate column widths based on content
-        let column_widths = self.calculate_column_widths(headers, rows);
-        let mut table_rows = vec![];
+    /// Runs of a PAGEREF field showing the page of a bookmark
+    ///
+    /// The field is marked dirty so Word computes the page when it opens
+    /// the document; until then it shows `#`.
+    fn page_reference_runs(bookmark: &str) -> [Run; 5] {
+        [
+            Run::new().add_field_char(FieldCharType::Begin, true),
+            Run::new().add_instr_text(InstrText::PAGEREF(InstrPAGEREF::new(bookmark).hyperlink())),
+            Run::new().add_field_char(FieldCharType::Separate, false),
+            Run::new().add_text("#"),
+            Run::new().add_field_char(FieldCharType::End, false),
+        ]
+    }
 
-        let table_width = 8300;
-        let total_column_width: usize = column_widths.iter().sum();
+    /// References list entry: `[n] text: url`, or just the URL for bare links
+    fn create_reference_paragraph(
+        &self,
+        reference: &references::Reference,
+    ) -> Result<Paragraph, ConversionError> {
+        let mut paragraph = Paragraph::new()
+            .add_run(self.create_footnote_marker(&format!("[{}]", reference.number)));
+        let text = reference.text.trim();
+        let label = if text.is_empty() || text == reference.url {
+            " ".to_string()
+        } else {
+            format!(" {}: ", text)
+        };
+        paragraph = paragraph.add_run(self.create_run_from_inline(&InlineElement::Text(label))?);
+        let url = InlineElement::Link {
+            text: reference.url.clone(),
+            url: reference.url.clone(),
+            title: None,
+        };
+        let run = self.create_run_from_inline(&url)?;
+        Ok(paragraph.add_hyperlink(Hyperlink::new(&reference.url, HyperlinkType::External).add_run(run)))
+    }
 
-        // Add header row
-        if !headers.is_empty() {
-            let mut header_cells = vec![];
+    /// Look up the configured styles for Markdown classes, warning about unknown ones
+    ///
+    /// The `kbd` and `samp` classes, given to `<kbd>`/`<samp>` spans, and the
+    /// classes of blockquote attributions, emphasis callouts and link buttons
+    /// start from their built-in styles and take any same-named class on top.
+    fn resolve_classes(&self, classes: &[String]) -> Vec<&crate::config::ClassStyle> {
+        let inline_html = &self.config.styles.inline_html;
+        classes
+            .iter()
+            .flat_map(|name| {
+                let tag_style = match name.as_str() {
+                    "kbd" => Some(&inline_html.kbd),
+                    "samp" => Some(&inline_html.samp),
+                    ATTRIBUTION_CLASS => Some(&self.config.elements.quote_attribution.style),
+                    callouts::CALLOUT_CLASS => Some(&self.config.elements.emphasis_callouts.style),
+                    link_buttons::BUTTON_CLASS => Some(&self.config.elements.link_buttons.style),
+                    _ => None,
+                };
+                let style = self.config.styles.classes.get(name);
+                if style.is_none() && tag_style.is_none() {
+                    warn!(class = %name, "Unknown Markdown class; no style is configured for it");
+                }
+                tag_style.into_iter().chain(style)
+            })
+            .collect()
+    }
 
-            for (index, header) in headers.iter().enumerate() {
-                let mut header_run = Run::new()
-                    .add_text(header)
-                    .fonts(
-                        RunFonts::new()
-                            .ascii(&table_style.header_font.family)
-                            .east_asia(&table_style.header_font.family),
-                    )
-                    .size((table_style.header_font.size * 2.0) as usize);
+    /// Apply the direct run formatting of a class style
+    fn apply_class_to_run(mut run: Run, class: &crate::config::ClassStyle) -> Run {
+        if let Some(family) = &class.font_family {
+            run = run.fonts(RunFonts::new().ascii(family).east_asia(family));
+        }
+        if let Some(size) = class.font_size {
+            run = run.size((size * 2.0) as usize);
+        }
+        match class.bold {
+            Some(true) => run = run.bold(),
+            Some(false) => run = run.disable_bold(),
+            None => {}
+        }
+        match class.italic {
+            Some(true) => run = run.italic(),
+            Some(false) => run = run.disable_italic(),
+            None => {}
+        }
+        match class.strikethrough {
+            Some(true) => run = run.strike(),
+            Some(false) => run.run_property = run.run_property.disable_strike(),
+            None => {}
+        }
+        if let Some(color) = &class.color {
+            run = run.color(color.trim_start_matches('#'));
+        }
+        if let Some(background) = &class.background_color {
+            run = run.shading(Shading::new().fill(background.trim_start_matches('#')));
+        }
+        if let Some(border) = &class.border {
+            run = run.text_border(TextBorder::new().color(border.trim_start_matches('#')));
+        }
+        if let Some(name) = class.highlight.as_deref().and_then(highlight::nearest_highlight) {
+            run = run.highlight(name);
+        }
+        run
+    }
 
-                if table_style.header_font.bold {
-                    header_run = header_run.bold();
-                }
-                if table_style.header_font.italic {
-                    header_run = header_run.italic();
-                }
+    /// Caption text and remaining code when captions are enabled for the
+    /// block's language and its first line is a non-empty line comment
+    ///
+    /// Shebangs and doc comments (`#!`, `//!`, `///`) are code, not captions.
+    fn code_caption<'a>(&self, language: Option<&str>, code: &'a str) -> Option<(&'a str, &'a str)> {
+        let captions = &self.config.elements.code_captions;
+        if !captions.enabled {
+            return None;
+        }
+        let language = language?.to_lowercase();
+        let marker = captions.languages.get(&language).filter(|marker| !marker.is_empty())?;
+        let (first, rest) = code.split_once('\n').unwrap_or((code, ""));
+        let comment = first.trim().strip_prefix(marker.as_str())?;
+        if comment.starts_with(['!', '/']) {
+            return None;
+        }
+        let caption = comment.trim();
+        (!caption.is_empty()).then_some((caption, rest))
+    }
 
-                let cell_paragraph = Paragraph::new().add_run(header_run);
-                let mut cell = TableCell::new().add_paragraph(cell_paragraph);
-                
-                // Set cell width based on content
-                if let Some(&width) = column_widths.get(index) {
-                    // cell = cell.width(width, WidthType::Dxa);
-                    cell = cell.width(((table_width * width) as f32 * 1.0 / total_column_width as f32) as usize, WidthType::Dxa);
-                }
-                
-                header_cells.push(cell);
-            }
+    /// Caption paragraph written above a code block
+    fn create_code_caption(&self, caption: &str, indent: i32) -> Paragraph {
+        let font = &self.config.styles.paragraph.font;
+        let run = Run::new()
+            .add_text(caption)
+            .fonts(RunFonts::new().ascii(&font.family).east_asia(&font.family))
+            .size((font.size * 2.0) as usize)
+            .italic();
+        let mut paragraph = Paragraph::new().style(CAPTION_STYLE).add_run(run);
+        if indent > 0 {
+            paragraph = paragraph.indent(Some(indent), None, None, None);
+        }
+        paragraph
+    }
 
-            table_rows.push(TableRow::new(header_cells));
+    /// Header row naming a code block's language and file, when title bars
+    /// are enabled and the block has either
+    fn create_code_title_row(
+        &self,
+        language: Option<&str>,
+        attributes: &CodeAttributes,
+        width: usize,
+    ) -> Option<TableRow> {
+        let title_bar = &self.config.elements.code_title_bar;
+        if !title_bar.enabled {
+            return None;
         }
+        let language = language.filter(|language| !language.is_empty());
+        let text = match (language, attributes.title.as_deref()) {
+            (Some(language), Some(title)) => format!("{} — {}", language, title),
+            (Some(text), None) | (None, Some(text)) => text.to_string(),
+            (None, None) => return None,
+        };
 
-        // Add data rows
-        for row in rows {
-            let mut row_cells = vec![];
+        // The background shades the whole row rather than the text
+        let style = &title_bar.style;
+        let font = &self.config.styles.code_block.font;
+        let run = Run::new()
+            .add_text(text)
+            .fonts(RunFonts::new().ascii(&font.family).east_asia(&font.family))
+            .size((font.size * 2.0) as usize);
+        let run = Self::apply_class_to_run(
+            run,
+            &crate::config::ClassStyle {
+                background_color: None,
+                ..style.clone()
+            },
+        );
+        let mut paragraph = Paragraph::new().add_run(run);
+        if let Some(alignment) = style.alignment.as_deref().and_then(|a| AlignmentType::from_str(a).ok()) {
+            paragraph = paragraph.align(alignment);
+        }
+        let mut cell = TableCell::new().add_paragraph(paragraph).width(width, WidthType::Dxa);
+        if let Some(background) = &style.background_color {
+            cell = cell.shading(Shading::new().fill(background.trim_start_matches('#')));
+        }
+        Some(TableRow::new(vec![cell]))
+    }
 
-            for (index, cell_data) in row.iter().enumerate() {
-                let mut cell_run = Run::new()
-                    .add_text(cell_data)
-                    .fonts(
-                        RunFonts::new()
-                            .ascii(&table_style.cell_font.family)
-                            .east_asia(&table_style.cell_font.family),
-                    )
-                    .size((table_style.cell_font.size * 2.0) as usize);
+    /// Add a code block to the document as a single-column table
+    fn add_code_block(
+        &self,
+        docx: Docx,
+        language: Option<&str>,
+        code: &str,
+        attributes: &CodeAttributes,
+    ) -> Result<Docx, ConversionError> {
+        self.add_code_block_indented(docx, language, code, attributes, 0)
+    }
 
-                if table_style.cell_font.bold {
-                    cell_run = cell_run.bold();
-                }
-                if table_style.cell_font.italic {
-                    cell_run = cell_run.italic();
-                }
+    /// Add a code block table indented by `indent` twips, narrowed to match
+    fn add_code_block_indented(
+        &self,
+        mut docx: Docx,
+        language: Option<&str>,
+        code: &str,
+        attributes: &CodeAttributes,
+        indent: i32,
+    ) -> Result<Docx, ConversionError> {
+        // Check if this is a note block with special formatting
+        if code.contains("[NOTE_BLOCK_START]") && code.contains("[NOTE_BLOCK_END]") {
+            return self.add_note_block(docx, code);
+        }
 
-                let cell_paragraph = Paragraph::new().add_run(cell_run);
-                let mut cell = TableCell::new().add_paragraph(cell_paragraph);
-                
-                // Set cell width based on content
-                if let Some(&width) = column_widths.get(index) {
-                    // cell = cell.width(width, WidthType::Dxa);
-                    cell = cell.width(((table_width * width) as f32 * 1.0 / total_column_width as f32) as usize, WidthType::Dxa);
-                }
-                
-                row_cells.push(cell);
+        // Highlighted line numbers count the caption line, which is not rendered
+        let (code, skipped_lines) = match self.code_caption(language, code) {
+            Some((caption, rest)) => {
+                docx = docx.add_paragraph(self.create_code_caption(caption, indent));
+                (rest, 1)
             }
+            None => (code, 0),
+        };
+        let highlight: Vec<usize> = attributes
+            .highlight
+            .iter()
+            .filter(|&&line| line > skipped_lines)
+            .map(|line| line - skipped_lines)
+            .collect();
 
-            table_rows.push(TableRow::new(row_cells));
-        }
+        let code_style = &self.config.styles.code_block;
 
-        // Create table with auto-layout for better content fitting
-        let mut table = Table::new(table_rows)
-            .layout(TableLayoutType::Fixed);
+        // Create table cell with code content using the helper method
+        let width = (8300 - indent).max(0) as usize;
+        let spacing = Self::points_to_twips(code_style.paragraph_spacing);
+        let cell = self
+            .create_code_block_cell_with_markdown(code, code_style, &highlight)?
+            .width(width, WidthType::Dxa);
+        let cell = Self::space_cell_ends(cell, spacing, spacing);
 
-        // Apply table borders if configured
-        if table_style.border_width > 0.0 {
-            table = self.apply_table_borders(table, table_style.border_width)?;
+        // Create single-column table, with the title bar as its first row
+        let rows = self
+            .create_code_title_row(language, attributes, width)
+            .into_iter()
+            .chain([TableRow::new(vec![cell])])
+            .collect();
+        let mut table =
+            Table::new(rows).margins(TableCellMargins::new().margin(100, 100, 100, 100));
+        if indent > 0 {
+            table = table.indent(indent);
+        }
+
+        // Apply border styling based on border_width configuration
+        if code_style.border_width > 0.0 {
+            table = self.apply_table_borders(table, code_style.border_width)?;
         }
 
-        docx = docx.add_table(table.width(table_width, WidthType::Dxa));
+        // Add the table to the document
+        docx = docx.add_table(table);
         Ok(docx)
     }
 
-    /// Add an image to the document
-    fn add_image(
-        &self,
-        mut docx: Docx,
-        alt_text: &str,
-        url: &str,
-        width: Option<u32>,
-        height: Option<u32>,
-    ) -> Result<Docx, ConversionError> {
-        let image_config = &self.config.elements.image;
+    /// Add a note block with special formatting (title, icon, content)
+    fn add_note_block(&self, mut docx: Docx, code: &str) -> Result<Docx, ConversionError> {
+        // Parse the note block markers
+        let title = self.extract_marker_content(code, "[TITLE]", "[/TITLE]");
+        let icon_path = self.extract_marker_content(code, "[ICON]", "[/ICON]");
+        let content = self.extract_marker_content(code, "[CONTENT]", "[/CONTENT]");
 
-        // QR code
-        if alt_text == "qrcode" {
-            let center_style = Style::new("Center", StyleType::Paragraph)
-                .name("Center")
-                .align(AlignmentType::Center);
-            docx = docx.add_style(center_style);
-
-            // Try to embed local image
-            match self.embed_local_image_sized("/Users/lindagao/Workspace/md2docx/default-qrcode.png", alt_text, 50, 50, image_config) {
-                Ok(image_run) => {
-                    let paragraph = Paragraph::new().add_run(image_run);
-                    docx = docx.add_paragraph(paragraph.style("Center"));
-                }
-                Err(_) => {
-                    // Fallback to placeholder text if image can't be loaded
-                    let paragraph = Paragraph::new().add_run(
-                        Run::new()
-                            .add_text(&format!("[Image: {} - File not found: {}]", alt_text, url)),
-                    );
-                    docx = docx.add_paragraph(paragraph);
+        // Load the icon first: without one the note is a single column
+        let note_block = &self.config.elements.note_block;
+        let icon = match icon_path {
+            Some(icon_path) => {
+                let right_style = Style::new("Right", StyleType::Paragraph)
+                    .name("Right")
+                    .align(AlignmentType::Right);
+                docx = docx.add_style(right_style);
+
+                // Fitted into the icon box at its own aspect ratio
+                let icon_config = ImageConfig {
+                    max_width: note_block.icon_width,
+                    max_height: note_block.icon_height,
+                    placement: ImagePlacement::Block,
+                    ..self.config.elements.image.clone()
+                };
+                match self.load_local_image(icon_path.as_str(), None, None, &icon_config) {
+                    Ok(image) => Some(Paragraph::new().add_run(Run::new().add_image(image)).style("Right")),
+                    Err(e) => {
+                        warn!("Failed to load note icon: {:?}", e);
+                        None
+                    }
                 }
             }
+            None => None,
+        };
+        let single_column = icon.is_none() || note_block.single_column;
+        // The icon column fits the icon box (15 twips a pixel) and the cell margins
+        let table_width = self.table_width();
+        let icon_column_width = ((note_block.icon_width * 15.0) as usize + 200).min(table_width / 2);
 
-            let paragraph = Paragraph::new().add_run(
-                Run::new().add_text(url.to_string()),
-            );
-            docx = docx.add_paragraph(paragraph.style("Center"));
+        // Create the note block as a two-column table
+        // Left column: title + content
+        // Right column: icon
+        // A single-column note has the icon, if any, above the title
 
-            return Ok(docx)
+        let (mut left_cell, icon) = match icon {
+            Some(paragraph) if single_column => (TableCell::new().add_paragraph(paragraph), None),
+            None => (TableCell::new(), None),
+            icon => (TableCell::new().clear_border(TableCellBorderPosition::Right), icon),
+        };
+        
+        // Add title with special formatting (larger, bold, italic)
+        if let Some(title_text) = title {
+            let title_run = Run::new()
+                .add_text(&title_text)
+                .bold()
+                .italic()
+                .size(23); // 14pt * 2 = 28 half-points (1.2x of 12pt ≈ 14pt)
+            
+            let title_paragraph = Paragraph::new()
+                .add_run(title_run);
+            
+            left_cell = left_cell.add_paragraph(title_paragraph);
+            
+            // Add spacing after title
+            left_cell = left_cell.add_paragraph(Paragraph::new());
         }
-
-        // Check if it's a local file path
-        if self.is_local_image_path(url) {
-            // Try to embed local image with custom dimensions if provided
-            let result = if width.is_some() || height.is_some() {
-                // Use custom dimensions
-                let w = width.unwrap_or(image_config.max_width as u32);
-                let h = height.unwrap_or(image_config.max_height as u32);
-                self.embed_local_image_sized(url, alt_text, w, h, image_config)
-            } else {
-                // Use default dimensions from config
-                self.embed_local_image(url, alt_text, image_config)
-            };
-
-            match result {
-                Ok(image_run) => {
-                    let paragraph = Paragraph::new().add_run(image_run);
-                    docx = docx.add_paragraph(paragraph);
-                }
-                Err(_) => {
-                    // Fallback to placeholder text if image can't be loaded
-                    let paragraph = Paragraph::new().add_run(
-                        Run::new()
-                            .add_text(&format!("[Image: {} - File not found: {}]", alt_text, url)),
-                    );
-                    docx = docx.add_paragraph(paragraph);
+        
+        // Add content
+        if let Some(content_text) = content {
+            for line in content_text.lines() {
+                if !line.trim().is_empty() {
+                    let content_run = Run::new().add_text(line);
+                    let content_paragraph = Paragraph::new().add_run(content_run);
+                    left_cell = left_cell.add_paragraph(content_paragraph);
+                } else {
+                    // Empty line
+                    left_cell = left_cell.add_paragraph(Paragraph::new());
                 }
             }
-        } else {
-            // For remote URLs, add a placeholder with the URL
-            let paragraph = Paragraph::new()
-                .add_run(Run::new().add_text(&format!("[Image: {} - URL: {}]", alt_text, url)));
-            docx = docx.add_paragraph(paragraph);
+        }
+        
+        // Set cell width and styling
+        left_cell = left_cell
+            .width(if single_column { table_width } else { table_width - icon_column_width }, WidthType::Dxa)
+            .vertical_align(docx_rs::VAlignType::Top);
+
+        // Spaced like a code block, inside the top and bottom of each cell
+        let spacing = Self::points_to_twips(self.config.styles.code_block.paragraph_spacing);
+        let mut cells = vec![Self::space_cell_ends(left_cell, spacing, spacing)];
+        if let Some(paragraph) = icon {
+            // Create right cell for icon
+            let right_cell = TableCell::new()
+                .clear_border(TableCellBorderPosition::Left)
+                .add_paragraph(paragraph)
+                .width(icon_column_width, WidthType::Dxa)
+                .vertical_align(docx_rs::VAlignType::Top);
+            cells.push(Self::space_cell_ends(right_cell, spacing, spacing));
         }
 
+        // Create table row with the note's cells
+        let row = TableRow::new(cells);
+        
+        // Create table with light background and border
+        let mut table = Table::new(vec![row])
+            .margins(TableCellMargins::new().margin(100, 100, 100, 100));
+        
+        // Apply light border
+        table = self.apply_table_borders_with_type(table, 1.0, BorderType::Dashed)?;
+        
+        // Add the table to the document
+        docx = docx.add_table(table);
         Ok(docx)
     }
 
-    /// Check if a URL is a local image path
-    fn is_local_image_path(&self, url: &str) -> bool {
-        !url.starts_with("http://") && !url.starts_with("https://") && !url.starts_with("ftp://")
+    /// Points as whole twips, negative values as 0
+    fn points_to_twips(points: f32) -> u32 {
+        (points.max(0.0) * 20.0).round() as u32
     }
 
-    /// Embed a local image file
-    fn embed_local_image(
-        &self,
-        path: &str,
-        _alt_text: &str,
-        image_config: &crate::config::ImageConfig,
-    ) -> Result<Run, ConversionError> {
-        self.embed_local_image_sized(
-            path,
-            _alt_text,
-            image_config.max_width as u32,
-            image_config.max_height as u32,
-            image_config,
-        )
+    /// Space `before` the first paragraph of a cell and `after` its last, in
+    /// twips, keeping their line spacing
+    fn space_cell_ends(mut cell: TableCell, before: u32, after: u32) -> TableCell {
+        let mut paragraphs: Vec<&mut Box<Paragraph>> = cell
+            .children
+            .iter_mut()
+            .filter_map(|child| match child {
+                TableCellContent::Paragraph(paragraph) => Some(paragraph),
+                _ => None,
+            })
+            .collect();
+        if let Some(first) = paragraphs.first_mut() {
+            let spacing = first.property.line_spacing.take().unwrap_or_default().before(before);
+            first.property.line_spacing = Some(spacing);
+        }
+        if let Some(last) = paragraphs.last_mut() {
+            let spacing = last.property.line_spacing.take().unwrap_or_default().after(after);
+            last.property.line_spacing = Some(spacing);
+        }
+        cell
     }
 
-    /// Embed a local image file
-    fn embed_local_image_sized(
-        &self,
-        path: &str,
-        _alt_text: &str,
-        width: u32,
-        height: u32,
-        image_config: &crate::config::ImageConfig,
-    ) -> Result<Run, ConversionError> {
-        use std::fs;
-        use std::path::Path;
-
-        // Check if file exists
-        if !Path::new(path).exists() {
-            return Err(ConversionError::DocxGeneration(format!(
-                "Image file not found: {}",
-                path
-            )));
+    /// Extract content between markers
+    fn extract_marker_content(&self, text: &str, start_marker: &str, end_marker: &str) -> Option<String> {
+        if let Some(start_pos) = text.find(start_marker) {
+            let content_start = start_pos + start_marker.len();
+            if let Some(end_pos) = text[content_start..].find(end_marker) {
+                let content = &text[content_start..content_start + end_pos];
+                // return Some(content.trim().to_string());
+                return Some(content.to_string());
+            }
         }
+        None
+    }
 
-        // Read image file
-        let image_data = fs::read(path).map_err(|e| {
-            ConversionError::DocxGeneration(format!("Failed to read image file {}: {}", path, e))
-        })?;
+    /// Add the abstract numberings of bulleted and numbered lists
+    ///
+    /// Each nesting depth is a level indented one list indent further, with
+    /// the bullet or number hanging one list indent to the left of the text.
+    /// Bullets cycle through `•`, `◦` and `▪`; numbers through the configured
+    /// ordered styles.
+    fn add_list_numberings(&self, docx: Docx) -> Docx {
+        let list_config = &self.config.elements.list;
+        let indent = (list_config.indent * 20.0) as i32;
+        let level = |depth: usize, format: &str, text: String| {
+            Level::new(depth, Start::new(1), NumberFormat::new(format), LevelText::new(text), LevelJc::new("left"))
+                .indent(Some(indent * (depth + 1) as i32), Some(SpecialIndentType::Hanging(indent)), None, None)
+        };
 
-        // Determine image format from file extension
-        let _format = self.get_image_format(path)?;
+        let mut bullets = AbstractNumbering::new(BULLET_ABSTRACT_NUMBERING);
+        let mut numbers = AbstractNumbering::new(ORDERED_ABSTRACT_NUMBERING);
+        for depth in 0..LIST_LEVELS {
+            let bullet = ["•", "◦", "▪"][depth % 3];
+            bullets = bullets.add_level(level(depth, "bullet", bullet.to_string()));
 
-        // Create image with size constraints
-        // '9525' is from here: https://github.com/bokuweb/docx-rs/blob/main/docx-core/examples/image_floating.rs
-        let image = Pic::new(&image_data).size(
-            width * 9525,
-            height * 9525,
-        );
+            let styles = &list_config.ordered_styles;
+            let style = styles.get(depth % styles.len().max(1)).copied().unwrap_or_default();
+            numbers = numbers.add_level(level(depth, list_number_format(style), format!("%{}.", depth + 1)));
+        }
+        docx.add_abstract_numbering(bullets).add_abstract_numbering(numbers)
+    }
 
-        // Create run with the image
-        let run = Run::new().add_image(image);
+    /// Add a list to the document, numbered from 1, with its top-level
+    /// items at `depth`
+    fn add_list(
+        &mut self,
+        mut docx: Docx,
+        ordered: bool,
+        items: &[ListItem],
+        depth: usize,
+    ) -> Result<Docx, ConversionError> {
+        let numbering_id;
+        (docx, numbering_id) = self.add_list_numbering(docx, ordered, depth);
+        docx = self.add_list_with_depth(docx, numbering_id, items, depth)?;
+        Ok(docx)
+    }
 
-        Ok(run)
+    /// Add a numbering instance for one list of the given kind, counting its
+    /// items at `depth` from 1, and return its id
+    fn add_list_numbering(&mut self, docx: Docx, ordered: bool, depth: usize) -> (Docx, usize) {
+        let numbering_id = self.next_list_numbering;
+        self.next_list_numbering += 1;
+        let abstract_id = if ordered { ORDERED_ABSTRACT_NUMBERING } else { BULLET_ABSTRACT_NUMBERING };
+        let restart = LevelOverride::new(depth.min(LIST_LEVELS - 1)).start(1);
+        (docx.add_numbering(Numbering::new(numbering_id, abstract_id).add_override(restart)), numbering_id)
     }
 
-    /// Get image format from file extension
-    fn get_image_format(&self, path: &str) -> Result<&'static str, ConversionError> {
-        let path_lower = path.to_lowercase();
+    /// Add list items at a nesting depth, which is their numbering level
+    fn add_list_with_depth(
+        &mut self,
+        mut docx: Docx,
+        numbering_id: usize,
+        items: &[ListItem],
+        depth: usize,
+    ) -> Result<Docx, ConversionError> {
+        let max_depth = self.config.elements.max_nesting_depth.max(1);
+
+        for item in items {
+            docx = docx.add_paragraph(self.create_list_paragraph(numbering_id, item, depth)?);
+            docx = self.add_list_item_blocks(docx, &item.blocks, depth)?;
 
-        if path_lower.ends_with(".png") {
-            Ok("png")
-        } else if path_lower.ends_with(".jpg") || path_lower.ends_with(".jpeg") {
-            Ok("jpeg")
-        } else if path_lower.ends_with(".gif") {
-            Ok("gif")
-        } else if path_lower.ends_with(".bmp") {
-            Ok("bmp")
-        } else {
-            Err(ConversionError::DocxGeneration(format!(
-                "Unsupported image format: {}",
-                path
-            )))
+            if item.sub_items.is_empty() {
+                continue;
+            }
+            if depth + 1 < max_depth {
+                // Each nested list, a run of sub-items of one kind, is
+                // numbered on its own
+                for nested in item.sub_items.chunk_by(|a, b| a.ordered == b.ordered) {
+                    let nested_id;
+                    (docx, nested_id) = self.add_list_numbering(docx, nested[0].ordered, depth + 1);
+                    docx = self.add_list_with_depth(docx, nested_id, nested, depth + 1)?;
+                }
+            } else {
+                // Beyond the nesting limit, descendants are written at this
+                // depth, starting a new numbering whenever their kind changes
+                warn!(max_depth, "List nesting exceeds the maximum depth; flattening deeper items");
+                let mut current: Option<(bool, usize)> = None;
+                let mut pending: Vec<&ListItem> = item.sub_items.iter().rev().collect();
+                while let Some(descendant) = pending.pop() {
+                    let descendant_id = match current {
+                        Some((ordered, id)) if ordered == descendant.ordered => id,
+                        _ => {
+                            let id;
+                            (docx, id) = self.add_list_numbering(docx, descendant.ordered, depth);
+                            current = Some((descendant.ordered, id));
+                            id
+                        }
+                    };
+                    docx = docx.add_paragraph(self.create_list_paragraph(descendant_id, descendant, depth)?);
+                    docx = self.add_list_item_blocks(docx, &descendant.blocks, depth)?;
+                    pending.extend(descendant.sub_items.iter().rev());
+                }
+            }
         }
-    }
 
-    /// Add a horizontal rule to the document
-    fn add_horizontal_rule(&self, mut docx: Docx) -> Result<Docx, ConversionError> {
-        // Add a paragraph with a line of dashes as a simple horizontal rule
-        let paragraph = Paragraph::new().add_run(Run::new().add_text("─".repeat(50)));
+        Ok(docx)
+    }
 
-        docx = docx.add_paragraph(paragraph);
+    /// Add the paragraphs and code blocks of a list item, indented to line
+    /// up with the text of nested items
+    fn add_list_item_blocks(
+        &mut self,
+        mut docx: Docx,
+        blocks: &[ListItemBlock],
+        depth: usize,
+    ) -> Result<Docx, ConversionError> {
+        let indent = (self.config.elements.list.indent * (depth + 1) as f32 * 20.0) as i32;
+        for block in blocks {
+            match block {
+                ListItemBlock::Paragraph(content) => {
+                    let paragraph = Paragraph::new()
+                        .style(postprocess::LIST_PARAGRAPH_STYLE)
+                        .indent(Some(indent), None, None, None);
+                    let mut next_comment_id = self.next_comment_id;
+                    let paragraph = self.add_inline_runs(paragraph, content, &[], &mut next_comment_id)?;
+                    self.next_comment_id = next_comment_id;
+                    docx = docx.add_paragraph(paragraph);
+                }
+                ListItemBlock::CodeBlock { language, code, attributes } => {
+                    docx = self.add_code_block_indented(docx, language.as_deref(), code, attributes, indent)?;
+                }
+            }
+        }
         Ok(docx)
     }
 
-    /// Create a table cell with code content that may contain Markdown formatting
-    fn create_code_block_cell_with_markdown(
-        &self,
-        code: &str,
-        style: &crate::config::CodeBlockStyle,
-    ) -> Result<TableCell, ConversionError> {
-        // // Check if the code contains Markdown formatting indicators
-        // if self.contains_markdown_formatting(code) {
-            self.create_code_block_cell_with_parsed_markdown(code, style)
-        // } else {
-        //     // Fall back to plain text rendering
-        //     self.create_code_block_cell(code, style)
-        // }
+    /// Create the paragraph for a single list item; its bullet or number
+    /// and indent come from the numbering level of its depth
+    fn create_list_paragraph(
+        &mut self,
+        numbering_id: usize,
+        item: &ListItem,
+        depth: usize,
+    ) -> Result<Paragraph, ConversionError> {
+        let level = depth.min(LIST_LEVELS - 1);
+        let mut paragraph = Paragraph::new()
+            .style(postprocess::LIST_PARAGRAPH_STYLE)
+            .numbering(NumberingId::new(numbering_id), IndentLevel::new(level));
+
+        // Add item content, after the box of a task item
+        let tasks = &self.config.elements.list.tasks;
+        if let Some(checked) = item.checked.filter(|_| tasks.checkbox) {
+            let glyph = if checked { checkbox::TASK_CHECKED } else { checkbox::UNCHECKED };
+            paragraph = paragraph.add_run(self.create_run_from_inline(&InlineElement::Text(format!("{} ", glyph)))?);
+        }
+        let struck = ClassStyle {
+            strikethrough: Some(true),
+            ..ClassStyle::default()
+        };
+        let class_styles: &[&ClassStyle] = if item.checked == Some(true) && tasks.strike_completed {
+            &[&struck]
+        } else {
+            &[]
+        };
+        let mut next_comment_id = self.next_comment_id;
+        paragraph = self.add_inline_runs(paragraph, &item.content, class_styles, &mut next_comment_id)?;
+        self.next_comment_id = next_comment_id;
+
+        Ok(paragraph)
     }
 
-    /// Check if text contains common Markdown formatting
-    fn contains_markdown_formatting(&self, text: &str) -> bool {
-        text.contains("**") || // Bold
-        text.contains("*") ||  // Italic (but not ** which is already checked)
-        text.contains("![") || // Images
-        text.contains("[") ||  // Links
-        text.contains("`")     // Inline code
+    /// Whether a table is estimated to overflow the text width by more than
+    /// `wide_tables.overflow_factor`, logging a warning when it does
+    fn is_wide_table(&self, headers: &[String], rows: &[Vec<String>]) -> bool {
+        let wide_tables = &self.config.styles.table.wide_tables;
+        let estimated: usize = self.calculate_column_widths(headers, rows).iter().sum();
+        let text_width = self.content_area().width * 20.0;
+        let wide = estimated as f32 > text_width * wide_tables.overflow_factor;
+        if wide {
+            warn!(
+                estimated_width = estimated,
+                text_width = text_width as usize,
+                "Table is too wide for the page; putting it on landscape pages"
+            );
+        }
+        wide
     }
 
-    /// Create a table cell with parsed Markdown content
-    fn create_code_block_cell_with_parsed_markdown(
+    /// Add a table across the text width of landscape pages of its own
+    ///
+    /// A table already in a landscape section stays in it.
+    fn add_landscape_table(
+        &mut self,
+        mut docx: Docx,
+        headers: &[String],
+        rows: &[Vec<String>],
+        links: &[TableLink],
+    ) -> Result<Docx, ConversionError> {
+        if self.orientation == PageOrientation::Landscape {
+            return self.add_table(docx, headers, rows, links, self.landscape_text_width());
+        }
+        docx = self.add_section_break(docx, PageOrientation::Landscape);
+        docx = self.add_table(docx, headers, rows, links, self.landscape_text_width())?;
+        Ok(self.add_section_break(docx, PageOrientation::Portrait))
+    }
+
+    /// Text width of a landscape page in twips
+    fn landscape_text_width(&self) -> usize {
+        let page_size = &self.config.document.page_size;
+        let width = page_size.width.max(page_size.height);
+        ((width - self.margins.left - self.margins.right) * 20.0).max(0.0) as usize
+    }
+
+    /// Add a table `table_width` twips wide to the document
+    fn add_table(
         &self,
-        code: &str,
-        style: &crate::config::CodeBlockStyle,
-    ) -> Result<TableCell, ConversionError> {
-        
-        let mut cell = TableCell::new();
+        mut docx: Docx,
+        headers: &[String],
+        rows: &[Vec<String>],
+        links: &[TableLink],
+        table_width: usize,
+    ) -> Result<Docx, ConversionError> {
+        let table_style = &self.config.styles.table;
 
-        if style.preserve_line_breaks {
-            // Handle edge case: empty code block
-            if code.is_empty() {
-                let paragraph = self.create_code_paragraph("\u{00A0}", style)?; // Use non-breaking space for visibility
-                cell = cell.add_paragraph(paragraph);
-            } else {
-                // Trim trailing newlines to avoid extra empty lines at the end
-                let trimmed_code = code.trim_end_matches('\n');
+        // Calculate column widths based on content
+        let column_widths = self.calculate_column_widths(headers, rows);
+        let mut table_rows = vec![];
 
-                // Split code by lines, preserving empty lines
-                let lines: Vec<&str> = trimmed_code.split('\n').collect();
+        let total_column_width: usize = column_widths.iter().sum();
 
-                for line in lines.iter() {
-                    // Convert tabs to spaces (4 spaces per tab) for consistent formatting
-                    let processed_line = line.replace('\t', "    ");
+        // Add header row
+        if !headers.is_empty() {
+            let mut header_cells = vec![];
 
-                    // Handle empty lines by using a non-breaking space to preserve the line
-                    if processed_line.trim().is_empty() {
-                        let paragraph = self.create_code_paragraph("\u{00A0}", style)?;
-                        cell = cell.add_paragraph(paragraph);
-                    } else {
-                        // Parse the line as Markdown and create paragraph with formatted runs
-                        let paragraph = self.create_code_paragraph_with_markdown(&processed_line, style)?;
-                        cell = cell.add_paragraph(paragraph);
-                    }
+            for (index, header) in headers.iter().enumerate() {
+                let cell_links = Self::cell_links(links, None, index);
+                let cell_paragraph = self.create_cell_paragraph(header, &table_style.header_font, &cell_links);
+                let mut cell = TableCell::new().add_paragraph(cell_paragraph);
+                if let Some(ref background) = table_style.header_background {
+                    cell = cell.shading(Shading::new().fill(background.trim_start_matches('#')));
                 }
+                
+                // Set cell width based on content
+                if let Some(&width) = column_widths.get(index) {
+                    // cell = cell.width(width, WidthType::Dxa);
+                    cell = cell.width(((table_width * width) as f32 * 1.0 / total_column_width as f32) as usize, WidthType::Dxa);
+                }
+                
+                header_cells.push(cell);
             }
-        } else {
-            // Single paragraph for entire code block with tab conversion
-            // Trim trailing newlines to avoid extra empty lines
-            let trimmed_code = code.trim_end_matches('\n');
-            let processed_code = trimmed_code.replace('\t', "    ");
-            let paragraph = self.create_code_paragraph_with_markdown(&processed_code, style)?;
-            cell = cell.add_paragraph(paragraph);
-        }
 
-        // Apply background color to table cell if specified
-        if let Some(bg_color) = &style.background_color {
-            let color = bg_color.trim_start_matches('#');
-            // Apply cell shading/background color using docx-rs API
-            cell = cell.shading(Shading::new().fill(color));
+            // cantSplit also tags the row for the tblHeader fixup in postprocess
+            table_rows.push(TableRow::new(header_cells).cant_split());
         }
 
-        Ok(cell)
-    }
+        // Add data rows
+        for (row_index, row) in rows.iter().enumerate() {
+            let mut row_cells = vec![];
+            let row_background = table_style
+                .alternate_row_background
+                .as_ref()
+                .filter(|_| row_index % 2 == 1);
 
-    /// Create a code paragraph with Markdown formatting
-    fn create_code_paragraph_with_markdown(
-        &self,
-        text: &str,
-        style: &crate::config::CodeBlockStyle,
-    ) -> Result<Paragraph, ConversionError> {
-        let mut leading_spaces = String::new();
-        for ch in text.chars() {
-            if ch == ' ' {
-                leading_spaces.push(ch);
-            } else {
-                break;
+            for (index, cell_data) in row.iter().enumerate() {
+                let row_header = index == 0 && table_style.row_header_column;
+                let (font, background) = if row_header {
+                    (&table_style.header_font, table_style.header_background.as_ref().or(row_background))
+                } else {
+                    (&table_style.cell_font, row_background)
+                };
+                let cell_links = Self::cell_links(links, Some(row_index), index);
+                let cell_paragraph = self.create_cell_paragraph(cell_data, font, &cell_links);
+                let mut cell = TableCell::new().add_paragraph(cell_paragraph);
+                if let Some(background) = background {
+                    cell = cell.shading(Shading::new().fill(background.trim_start_matches('#')));
+                }
+                
+                // Set cell width based on content
+                if let Some(&width) = column_widths.get(index) {
+                    // cell = cell.width(width, WidthType::Dxa);
+                    cell = cell.width(((table_width * width) as f32 * 1.0 / total_column_width as f32) as usize, WidthType::Dxa);
+                }
+                
+                row_cells.push(cell);
             }
+
+            table_rows.push(TableRow::new(row_cells));
         }
-        let text_without_leading_spaces = &text[leading_spaces.len()..];
-        
-        // Parse inline formatting including [BOLD] tags
-        let inline_elements = self.parse_inline_formatting_with_tags(text_without_leading_spaces);
 
-        let mut paragraph = Paragraph::new().style("CodeBlock");
+        // Create table with auto-layout for better content fitting
+        let mut table = Table::new(table_rows)
+            .style(postprocess::MARKDOWN_TABLE_STYLE)
+            .layout(TableLayoutType::Fixed);
 
-        if !leading_spaces.is_empty() {
-            let run = self.create_code_run(&leading_spaces, style)?;
-            paragraph = paragraph.add_run(run);
+        table = self.apply_markdown_table_borders(table)?;
+
+        docx = docx.add_table(self.align_table(table.width(table_width, WidthType::Dxa)));
+        Ok(docx)
+    }
+
+    /// Add the front matter metadata as a two-column table: labels in the
+    /// table header style, values in the cell style
+    fn add_metadata_table(&self, docx: Docx) -> Result<Docx, ConversionError> {
+        let table_style = &self.config.styles.table;
+        let table_width = self.table_width();
+        let label_width = table_width * 3 / 10;
+
+        let rows = self
+            .metadata
+            .iter()
+            .map(|(name, value)| {
+                let mut label = TableCell::new()
+                    .add_paragraph(self.create_cell_paragraph(&metadata_label(name), &table_style.header_font, &[]))
+                    .width(label_width, WidthType::Dxa);
+                if let Some(background) = &table_style.header_background {
+                    label = label.shading(Shading::new().fill(background.trim_start_matches('#')));
+                }
+                let value = TableCell::new()
+                    .add_paragraph(self.create_cell_paragraph(value, &table_style.cell_font, &[]))
+                    .width(table_width - label_width, WidthType::Dxa);
+                TableRow::new(vec![label, value])
+            })
+            .collect();
+        let table = Table::new(rows)
+            .style(postprocess::MARKDOWN_TABLE_STYLE)
+            .layout(TableLayoutType::Fixed);
+        let table = self.apply_markdown_table_borders(table)?;
+        Ok(docx.add_table(self.align_table(table.width(table_width, WidthType::Dxa))))
+    }
+
+    /// Add a TOC field over heading levels 1 to `max_depth`, pre-filled
+    /// with the outline entries so the table reads correctly before Word
+    /// updates the field
+    ///
+    /// Each entry links to the bookmark on its heading. The field is
+    /// marked dirty, so Word refreshes entries and page numbers on open.
+    fn add_table_of_contents(&self, docx: Docx, entries: &[OutlineEntry]) -> Result<Docx, ConversionError> {
+        let toc_config = &self.config.document.table_of_contents;
+        let depth = toc_config.max_depth as usize;
+        let mut instr = InstrToC::new()
+            .heading_styles_range(1, depth)
+            .hyperlink()
+            .use_applied_paragraph_line_level()
+            .hide_tab_and_page_numbers_in_webview();
+        if !toc_config.page_numbers {
+            instr = instr.omit_page_numbers_level_range(1, depth);
         }
 
-        // Process the inline elements
-        for inline in inline_elements {
-            let run = self.create_code_run_from_inline(&inline, style)?;
-            paragraph = paragraph.add_run(run);
+        let mut toc = TableOfContents::new().alias("Table of Contents").dirty();
+        toc.instr = instr;
+        if !toc_config.title.trim().is_empty() {
+            let title = InlineElement::Bold(toc_config.title.clone());
+            toc = toc.add_before_paragraph(Paragraph::new().add_run(self.create_run_from_inline(&title)?));
         }
+        let page_ref = if toc_config.page_numbers { "#" } else { "" };
+        for entry in entries.iter().filter(|entry| entry.level as usize <= depth) {
+            let Some(bookmark) = &entry.bookmark else { continue };
+            toc = toc.add_item(
+                TableOfContentsItem::new()
+                    .text(&entry.numbered_text)
+                    .level(entry.level as usize)
+                    .toc_key(bookmark)
+                    .page_ref(page_ref),
+            );
+        }
+        Ok(docx.add_table_of_contents(toc))
+    }
 
-        // Apply line spacing configuration
-        let line_spacing_value = (style.line_spacing * 240.0) as i32;
-        paragraph = paragraph.line_spacing(LineSpacing::new().line(line_spacing_value));
+    /// Width of top-level tables in twips: the text width when `full_width`
+    /// is set, otherwise a fixed default
+    fn table_width(&self) -> usize {
+        if self.config.styles.table.full_width {
+            (self.content_area().width * 20.0).max(0.0) as usize
+        } else {
+            8300
+        }
+    }
 
-        Ok(paragraph)
+    /// Apply the configured page alignment to a top-level table
+    fn align_table(&self, table: Table) -> Table {
+        match self.config.styles.table.table_alignment {
+            TableAlignment::Left => table,
+            TableAlignment::Center => table.align(TableAlignmentType::Center),
+            TableAlignment::Right => table.align(TableAlignmentType::Right),
+        }
     }
 
-    /// Parse inline formatting (bold, italic) without treating # as heading
-    /// This is a simple parser that only handles ** and * for bold/italic
-    fn parse_inline_formatting(&self, text: &str) -> Vec<crate::markdown::InlineElement> {
-        let mut elements = Vec::new();
-        let mut current_text = String::new();
-        let mut chars = text.chars().peekable();
-        
-        elements.push(crate::markdown::InlineElement::Text(text.to_string()));
+    /// Build a table parsed from HTML, with nested tables inside its cells
+    ///
+    /// Tables nested deeper than `max_nesting_depth` are written as the text
+    /// of the enclosing cell.
+    fn build_html_table(
+        &self,
+        table: &HtmlTable,
+        width: usize,
+        depth: usize,
+    ) -> Result<Table, ConversionError> {
+        let table_style = &self.config.styles.table;
+        let columns = table.column_count().max(1);
+        let cell_width = width / columns;
+        let nest = depth < self.config.elements.max_nesting_depth;
+
+        let mut rows = Vec::new();
+        for (row_index, row) in table.rows.iter().enumerate().filter(|(_, row)| !row.is_empty()) {
+            let mut cells = Vec::new();
+            for cell in row {
+                let font = if cell.header {
+                    &table_style.header_font
+                } else {
+                    &table_style.cell_font
+                };
+                let mut text = cell.text.clone();
+                if !nest {
+                    for nested in &cell.tables {
+                        text.push(' ');
+                        text.push_str(nested.extract_text().trim_end());
+                    }
+                }
 
-        /// Disabled Markdown
-        // while let Some(ch) = chars.next() {
-        //     if ch == '*' {
-        //         // Check for bold (**) or italic (*)
-        //         if chars.peek() == Some(&'*') {
-        //             // Bold: **text**
-        //             chars.next(); // consume second *
-                    
-        //             // Save current text if any
-        //             if !current_text.is_empty() {
-        //                 elements.push(crate::markdown::InlineElement::Text(current_text.clone()));
-        //                 current_text.clear();
-        //             }
-                    
-        //             // Collect bold text
-        //             let mut bold_text = String::new();
-        //             let mut found_end = false;
-        //             while let Some(ch) = chars.next() {
-        //                 if ch == '*' && chars.peek() == Some(&'*') {
-        //                     chars.next(); // consume second *
-        //                     found_end = true;
-        //                     break;
-        //                 }
-        //                 bold_text.push(ch);
-        //             }
-                    
-        //             if found_end && !bold_text.is_empty() {
-        //                 elements.push(crate::markdown::InlineElement::Bold(bold_text));
-        //             } else {
-        //                 // If no closing **, treat as literal text
-        //                 current_text.push_str("**");
-        //                 current_text.push_str(&bold_text);
-        //             }
-        //         } else {
-        //             // Italic: *text*
-        //             // Save current text if any
-        //             if !current_text.is_empty() {
-        //                 elements.push(crate::markdown::InlineElement::Text(current_text.clone()));
-        //                 current_text.clear();
-        //             }
-                    
-        //             // Collect italic text
-        //             let mut italic_text = String::new();
-        //             let mut found_end = false;
-        //             while let Some(ch) = chars.next() {
-        //                 if ch == '*' {
-        //                     found_end = true;
-        //                     break;
-        //                 }
-        //                 italic_text.push(ch);
-        //             }
-                    
-        //             if found_end && !italic_text.is_empty() {
-        //                 elements.push(crate::markdown::InlineElement::Italic(italic_text));
-        //             } else {
-        //                 // If no closing *, treat as literal text
-        //                 current_text.push('*');
-        //                 current_text.push_str(&italic_text);
-        //             }
-        //         }
-        //     } else {
-        //         current_text.push(ch);
-        //     }
-        // }
-        
-        // // Add remaining text
-        // if !current_text.is_empty() {
-        //     elements.push(crate::markdown::InlineElement::Text(current_text));
-        // }
-        
-        // // If no elements were parsed, return the original text
-        // if elements.is_empty() {
-        //     elements.push(crate::markdown::InlineElement::Text(text.to_string()));
-        // }
-        
-        elements
-    }
-
-    /// Parse inline formatting including [BOLD] and [ITALIC] tags
-    fn parse_inline_formatting_with_tags(&self, text: &str) -> Vec<crate::markdown::InlineElement> {
-        let mut elements = Vec::new();
-        let mut current_pos = 0;
-        
-        while current_pos < text.len() {
-            // Look for the next formatting tag (either [BOLD] or [ITALIC])
-            let bold_pos = text[current_pos..].find("[BOLD]").map(|p| (p, "[BOLD]", "[/BOLD]", true));
-            let italic_pos = text[current_pos..].find("[ITALIC]").map(|p| (p, "[ITALIC]", "[/ITALIC]", false));
-            
-            // Determine which tag comes first
-            let next_tag = match (bold_pos, italic_pos) {
-                (Some((bp, _, _, _)), Some((ip, _, _, _))) => {
-                    if bp < ip { bold_pos } else { italic_pos }
-                }
-                (Some(_), None) => bold_pos,
-                (None, Some(_)) => italic_pos,
-                (None, None) => None,
-            };
-            
-            if let Some((tag_start, open_tag, close_tag, is_bold)) = next_tag {
-                let absolute_tag_start = current_pos + tag_start;
-                
-                // Add text before tag
-                if tag_start > 0 {
-                    elements.push(crate::markdown::InlineElement::Text(
-                        text[current_pos..absolute_tag_start].to_string()
-                    ));
+                let mut table_cell = TableCell::new().width(cell_width, WidthType::Dxa);
+                if !text.is_empty() || !nest || cell.tables.is_empty() {
+                    let links: Vec<&TableLink> = cell.links.iter().collect();
+                    table_cell = table_cell.add_paragraph(self.create_cell_paragraph(&text, font, &links));
                 }
-                
-                // Look for closing tag
-                let search_start = absolute_tag_start + open_tag.len();
-                if let Some(tag_end) = text[search_start..].find(close_tag) {
-                    let absolute_tag_end = search_start + tag_end;
-                    
-                    // Extract formatted content
-                    let content = &text[search_start..absolute_tag_end];
-                    if is_bold {
-                        elements.push(crate::markdown::InlineElement::Bold(content.to_string()));
-                    } else {
-                        elements.push(crate::markdown::InlineElement::Italic(
-                            content.to_string()
-                            .replace("[BOLD]", "")
-                            .replace("[/BOLD]", "")));
+                if nest {
+                    for nested in &cell.tables {
+                        // Leave room for the cell margins around the nested table
+                        let nested_width = cell_width.saturating_sub(216);
+                        table_cell = table_cell
+                            .add_table(self.build_html_table(nested, nested_width, depth + 1)?)
+                            // A cell must end with a paragraph
+                            .add_paragraph(Paragraph::new());
                     }
-                    
-                    // Move position past closing tag
-                    current_pos = absolute_tag_end + close_tag.len();
-                } else {
-                    // No closing tag found, treat opening tag as literal text
-                    elements.push(crate::markdown::InlineElement::Text(
-                        text[absolute_tag_start..absolute_tag_start + open_tag.len()].to_string()
-                    ));
-                    current_pos = absolute_tag_start + open_tag.len();
                 }
-            } else {
-                // No more tags, add remaining text
-                if current_pos < text.len() {
-                    elements.push(crate::markdown::InlineElement::Text(
-                        text[current_pos..].to_string()
-                    ));
-                }
-                break;
+                cells.push(table_cell);
             }
-        }
-        
-        // If no elements were parsed, return the original text
-        if elements.is_empty() {
-            elements.push(crate::markdown::InlineElement::Text(text.to_string()));
-        }
-        
-        elements
-    }
-
-    /// Create a code run from an inline element with code styling
-    fn create_code_run_from_inline(
-        &self,
-        inline: &crate::markdown::InlineElement,
-        style: &crate::config::CodeBlockStyle,
-    ) -> Result<Run, ConversionError> {
-        let run = match inline {
-            crate::markdown::InlineElement::Text(text) => {
-                self.create_code_run(text, style)?
-            }
-            crate::markdown::InlineElement::Bold(text) => {
-                let mut run = self.create_code_run(text, style)?;
-                run = run.bold();
-                run
-            }
-            crate::markdown::InlineElement::Italic(text) => {
-                let mut run = self.create_code_run(text, style)?;
-                run = run.italic();
-                run
-            }
-            crate::markdown::InlineElement::Strikethrough(text) => {
-                let mut run = self.create_code_run(text, style)?;
-                run = run.strike();
-                run
-            }
-            crate::markdown::InlineElement::Code(text) => {
-                // Nested code - render with different background or styling
-                let mut run = self.create_code_run(text, style)?;
-                // Add a subtle highlight for nested code
-                run = run.highlight("E0E0E0");
-                run
+            while cells.len() < columns {
+                cells.push(TableCell::new().width(cell_width, WidthType::Dxa).add_paragraph(Paragraph::new()));
             }
-            crate::markdown::InlineElement::Link { text, url, title: _ } => {
-                let link_text = format!("{} ({})", text, url);
-                let mut run = self.create_code_run(&link_text, style)?;
-                run = run.color("0000FF"); // Blue color for links
-                run
+
+            let mut table_row = TableRow::new(cells);
+            if row_index == 0 && row.iter().all(|cell| cell.header) {
+                // cantSplit also tags the row for the tblHeader fixup in postprocess
+                table_row = table_row.cant_split();
             }
-        };
+            rows.push(table_row);
+        }
 
-        Ok(run)
+        let mut result = Table::new(rows)
+            .style(postprocess::MARKDOWN_TABLE_STYLE)
+            .layout(TableLayoutType::Fixed)
+            .set_grid(vec![cell_width; columns]);
+        result = self.apply_markdown_table_borders(result)?;
+        Ok(result.width(width, WidthType::Dxa))
     }
 
-    /// Create a basic code run with standard code styling
-    fn create_code_run(
+    /// Links of the pipe table cell in `row` (`None` for the header row) and `column`
+    fn cell_links(links: &[TableLink], row: Option<usize>, column: usize) -> Vec<&TableLink> {
+        links.iter().filter(|link| link.row == row && link.column == column).collect()
+    }
+
+    /// Paragraph for the text of a table cell; `\n` becomes a line break and
+    /// the cell's external links become hyperlinks where they were written
+    fn create_cell_paragraph(
         &self,
         text: &str,
-        style: &crate::config::CodeBlockStyle,
-    ) -> Result<Run, ConversionError> {
-        let mut run = Run::new()
-            .add_text(text)
-            .fonts(
-                RunFonts::new()
-                    .ascii(&style.font.family)
-                    .east_asia(&style.font.family),
-            )
-            .size((style.font.size * 2.0) as usize); // docx uses half-points
+        font: &crate::config::FontConfig,
+        links: &[&TableLink],
+    ) -> Paragraph {
+        let external: Vec<&TableLink> = links.iter().copied().filter(|link| is_external_link(&link.url)).collect();
+        let mut paragraph = Paragraph::new();
+        for (part, link) in table_links::segments(text, &external) {
+            paragraph = match link {
+                Some(link) => {
+                    let run = self.style_link_run(self.create_cell_run(part, font));
+                    paragraph.add_hyperlink(Hyperlink::new(&link.url, HyperlinkType::External).add_run(run))
+                }
+                None => paragraph.add_run(self.create_cell_run(part, font)),
+            };
+        }
+        paragraph
+    }
 
-        // Apply bold/italic conditionally based on font configuration
-        if style.font.bold {
+    /// Run of table cell text in the cell font
+    fn create_cell_run(&self, text: &str, font: &crate::config::FontConfig) -> Run {
+        let mut run = Run::new()
+            .fonts(RunFonts::new().ascii(&font.family).east_asia(&font.family))
+            .size((font.size * 2.0) as usize);
+        for (index, line) in text.split('\n').enumerate() {
+            if index > 0 {
+                run = run.add_break(BreakType::TextWrapping);
+            }
+            run = run.add_text(line);
+        }
+        if font.bold {
             run = run.bold();
         }
-        if style.font.italic {
+        if font.italic {
             run = run.italic();
         }
-
-        // Add background color if specified (applied to run for better compatibility)
-        if let Some(bg_color) = &style.background_color {
-            let color = bg_color.trim_start_matches('#');
-            run = run.highlight(color);
-        }
-
-        Ok(run)
+        run
     }
 
-    /// Extract text content from a Markdown element
-    fn extract_text_from_element(&self, element: &crate::markdown::MarkdownElement) -> String {
-        match element {
-            crate::markdown::MarkdownElement::Heading { text, .. } => text.clone(),
-            crate::markdown::MarkdownElement::Paragraph { content } => {
-                content.iter().map(|inline| self.extract_text_from_inline(inline)).collect::<Vec<_>>().join("")
-            }
-            crate::markdown::MarkdownElement::CodeBlock { code, .. } => code.clone(),
-            crate::markdown::MarkdownElement::List { items, .. } => {
-                items.iter().map(|item| {
-                    item.content.iter().map(|inline| self.extract_text_from_inline(inline)).collect::<Vec<_>>().join("")
-                }).collect::<Vec<_>>().join(" ")
+    /// Add an image to the document
+    fn add_image(
+        &mut self,
+        mut docx: Docx,
+        alt_text: &str,
+        url: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<Docx, ConversionError> {
+        let image_config = &self.config.elements.image;
+
+        // QR code
+        if alt_text == "qrcode" {
+            let qrcode = &image_config.qrcode;
+            let align = |paragraph: Paragraph| {
+                if qrcode.centered {
+                    paragraph.style("Center")
+                } else {
+                    paragraph
+                }
+            };
+            if qrcode.centered {
+                let center_style = Style::new("Center", StyleType::Paragraph)
+                    .name("Center")
+                    .align(AlignmentType::Center);
+                docx = docx.add_style(center_style);
             }
-            crate::markdown::MarkdownElement::Table { headers, rows } => {
-                let mut text = headers.join(" ");
-                for row in rows {
-                    text.push(' ');
-                    text.push_str(&row.join(" "));
+
+            // Try to embed the configured image
+            let image_run = qrcode.path.as_deref().map(|path| {
+                self.embed_local_image_sized(path, alt_text, qrcode.size, qrcode.size, image_config)
+            });
+            match image_run {
+                Some(Ok(image_run)) => {
+                    docx = docx.add_paragraph(align(Paragraph::new().add_run(image_run)));
+                }
+                Some(Err(_)) | None => {
+                    // Fallback to placeholder text if image can't be loaded
+                    let paragraph = Paragraph::new().add_run(
+                        Run::new()
+                            .add_text(format!("[Image: {} - File not found: {}]", alt_text, url)),
+                    );
+                    docx = docx.add_paragraph(paragraph);
                 }
-                text
             }
-            crate::markdown::MarkdownElement::Image { alt_text, .. } => alt_text.clone(),
-            crate::markdown::MarkdownElement::HorizontalRule => "---".to_string(),
+
+            let paragraph = Paragraph::new().add_run(
+                Run::new().add_text(url.to_string()),
+            );
+            docx = docx.add_paragraph(align(paragraph));
+
+            return Ok(docx)
         }
-    }
 
-    /// Extract text from an inline element
-    fn extract_text_from_inline(&self, inline: &crate::markdown::InlineElement) -> String {
-        match inline {
-            crate::markdown::InlineElement::Text(text) => text.clone(),
-            crate::markdown::InlineElement::Bold(text) => text.clone(),
-            crate::markdown::InlineElement::Italic(text) => text.clone(),
-            crate::markdown::InlineElement::Strikethrough(text) => text.clone(),
-            crate::markdown::InlineElement::Code(text) => text.clone(),
-            crate::markdown::InlineElement::Link { text, .. } => text.clone(),
+        let local = self.is_local_image_path(url);
+        if !local && !image_config.fetch_remote {
+            // For remote URLs, add a placeholder with the URL
+            return Ok(docx.add_paragraph(Self::remote_image_placeholder(alt_text, url)));
+        }
+
+        let placement = image_config.placement;
+        let loaded = if local {
+            self.load_local_image(url, width, height, image_config)
+        } else {
+            self.load_remote_image(url, width, height, image_config)
+        };
+        match loaded {
+            Ok(image) if !self.reserve_image_bytes(url, image.image.len() as u64)? => {
+                let paragraph = Paragraph::new().add_run(
+                    Run::new()
+                        .add_text(format!("[Image: {} - Image size limit reached: {}]", alt_text, url)),
+                );
+                docx = docx.add_paragraph(paragraph);
+            }
+            Ok(image) => {
+                let image_run = Run::new().add_image(Self::place_image(image, placement));
+                if placement == ImagePlacement::Block {
+                    docx = docx.add_paragraph(Paragraph::new().add_run(image_run));
+                } else {
+                    self.pending_image = Some(image_run);
+                }
+            }
+            Err(_) if local => {
+                // Fallback to placeholder text if image can't be loaded
+                let paragraph = Paragraph::new().add_run(
+                    Run::new()
+                        .add_text(format!("[Image: {} - File not found: {}]", alt_text, url)),
+                );
+                docx = docx.add_paragraph(paragraph);
+            }
+            Err(e) => {
+                warn!(image = %url, error = %e, "Failed to fetch remote image; writing a placeholder");
+                docx = docx.add_paragraph(Self::remote_image_placeholder(alt_text, url));
+            }
         }
+
+        Ok(docx)
     }
 
-    /// Create a table cell with code content for table-based code block rendering
-    fn create_code_block_cell(
+    /// Placeholder paragraph for a remote image that is not embedded
+    fn remote_image_placeholder(alt_text: &str, url: &str) -> Paragraph {
+        Paragraph::new().add_run(Run::new().add_text(format!("[Image: {} - URL: {}]", alt_text, url)))
+    }
+
+    /// Count an image of `bytes` towards the document's `total_size` cap,
+    /// returning whether it may be embedded
+    ///
+    /// An image over the cap is not counted; in strict mode it is an error.
+    fn reserve_image_bytes(&mut self, url: &str, bytes: u64) -> Result<bool, ConversionError> {
+        if let Some(limit) = &self.config.elements.image.total_size {
+            let total = self.embedded_image_bytes + bytes;
+            if total > limit.max_bytes {
+                if limit.strict {
+                    return Err(ConversionError::ResourceLimit(format!(
+                        "embedding {} would bring images to {} bytes, over the {} byte limit",
+                        url, total, limit.max_bytes
+                    )));
+                }
+                warn!(image = %url, bytes, max_bytes = limit.max_bytes, "Image size limit reached; writing a placeholder");
+                return Ok(false);
+            }
+        }
+        self.embedded_image_bytes += bytes;
+        Ok(true)
+    }
+
+    /// Display size in pixels of an image whose data is `natural` pixels
+    /// in size, scaled down proportionally to fit the configured maximum
+    ///
+    /// Explicit dimensions win; a single explicit side takes the other from
+    /// the image's aspect ratio. Images are never scaled up. When the pixel
+    /// size is unknown a missing side uses the maximum.
+    fn display_image_size(
+        natural: Option<(u32, u32)>,
+        width: Option<u32>,
+        height: Option<u32>,
+        image_config: &crate::config::ImageConfig,
+    ) -> (u32, u32) {
+        let (width, height) = match (natural.filter(|&(w, h)| w > 0 && h > 0), width, height) {
+            (_, Some(w), Some(h)) => (w as f32, h as f32),
+            (Some((nw, nh)), Some(w), None) => (w as f32, w as f32 * nh as f32 / nw as f32),
+            (Some((nw, nh)), None, Some(h)) => (h as f32 * nw as f32 / nh as f32, h as f32),
+            (Some((nw, nh)), None, None) => (nw as f32, nh as f32),
+            (None, w, h) => (
+                w.map_or(image_config.max_width, |w| w as f32),
+                h.map_or(image_config.max_height, |h| h as f32),
+            ),
+        };
+        let scale = (image_config.max_width / width)
+            .min(image_config.max_height / height)
+            .min(1.0);
+        (
+            ((width * scale).round() as u32).max(1),
+            ((height * scale).round() as u32).max(1),
+        )
+    }
+
+    /// Anchor a floating image at the left or right of the column, with
+    /// text wrapping around it
+    fn place_image(image: Pic, placement: ImagePlacement) -> Pic {
+        // Gap between a floating image and the text beside it, 1/8 inch
+        const WRAP_GAP_EMU: i32 = 114300;
+        let (align, image) = match placement {
+            ImagePlacement::Block | ImagePlacement::Inline => return image,
+            ImagePlacement::FloatLeft => (PicAlign::Left, image.dist_r(WRAP_GAP_EMU)),
+            ImagePlacement::FloatRight => (PicAlign::Right, image.dist_l(WRAP_GAP_EMU)),
+        };
+        image
+            .floating()
+            .relative_from_h(RelativeFromHType::Column)
+            .position_h(DrawingPosition::Align(align))
+            .relative_from_v(RelativeFromVType::Paragraph)
+            .position_v(DrawingPosition::Offset(0))
+    }
+
+    /// Write an inline or floating image that no paragraph followed in a
+    /// paragraph of its own
+    fn flush_pending_image(&mut self, docx: Docx) -> Docx {
+        match self.pending_image.take() {
+            Some(image_run) => docx.add_paragraph(Paragraph::new().add_run(image_run)),
+            None => docx,
+        }
+    }
+
+    /// Check if a URL is a local image path
+    fn is_local_image_path(&self, url: &str) -> bool {
+        !url.starts_with("http://") && !url.starts_with("https://") && !url.starts_with("ftp://")
+    }
+
+    /// Embed a local image file
+    fn embed_local_image_sized(
+        &self,
+        path: &str,
+        _alt_text: &str,
+        width: u32,
+        height: u32,
+        image_config: &crate::config::ImageConfig,
+    ) -> Result<Run, ConversionError> {
+        let image = self.load_local_image(path, Some(width), Some(height), image_config)?;
+        Ok(Run::new().add_image(image))
+    }
+
+    /// Load a local image file, displayed at the given size in pixels or
+    /// fitted to the configured maximum
+    fn load_local_image(
+        &self,
+        path: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        image_config: &crate::config::ImageConfig,
+    ) -> Result<Pic, ConversionError> {
+        use std::fs;
+        use std::path::Path;
+
+        // Check if file exists
+        if !Path::new(path).exists() {
+            return Err(ConversionError::DocxGeneration(format!(
+                "Image file not found: {}",
+                path
+            )));
+        }
+
+        // Read image file
+        let image_data = fs::read(path).map_err(|e| {
+            ConversionError::DocxGeneration(format!("Failed to read image file {}: {}", path, e))
+        })?;
+        let declared = Path::new(path).extension().and_then(image::ImageFormat::from_extension);
+        Self::create_pic(path, image_data, declared, width, height, image_config)
+    }
+
+    /// Download a remote image, displayed at the given size in pixels or
+    /// fitted to the configured maximum
+    ///
+    /// Its format is taken from the data, then from the URL's extension,
+    /// then from the content type it was served with.
+    fn load_remote_image(
+        &self,
+        url: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+        image_config: &crate::config::ImageConfig,
+    ) -> Result<Pic, ConversionError> {
+        let remote = Self::fetch_remote_image(url, image_config)?;
+        let url_path = url.split(['?', '#']).next().unwrap_or(url);
+        let declared = std::path::Path::new(url_path)
+            .extension()
+            .and_then(image::ImageFormat::from_extension)
+            .or_else(|| {
+                let content_type = remote.content_type.as_deref()?;
+                image::ImageFormat::from_mime_type(content_type.split(';').next()?.trim())
+            });
+        Self::create_pic(url, remote.data, declared, width, height, image_config)
+    }
+
+    /// Download a remote image within the configured timeout and size limit,
+    /// through the remote image cache when one is configured
+    fn fetch_remote_image(url: &str, image_config: &crate::config::ImageConfig) -> Result<RemoteImage, ConversionError> {
+        let timeout = Duration::from_secs(image_config.remote_timeout_seconds);
+        let max_bytes = image_config.remote_max_bytes;
+        let cache = image_config.remote_cache.as_ref().map(RemoteImageCache::new);
+        // The blocking client cannot be used on an async runtime thread,
+        // where the CLI and the server run conversions
+        let remote = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let client = reqwest::blocking::Client::builder().timeout(timeout).build()?;
+                    match &cache {
+                        Some(cache) => cache.fetch(&client, url),
+                        None => image_cache::download(&client, url, max_bytes),
+                    }
+                })
+                .join()
+                .unwrap_or_else(|_| Err(ConversionError::DocxGeneration(format!("Image download panicked: {}", url))))
+        })?;
+        if remote.data.len() as u64 > max_bytes {
+            return Err(ConversionError::ResourceLimit(format!(
+                "image {} is {} bytes, over the {} byte limit",
+                url,
+                remote.data.len(),
+                max_bytes
+            )));
+        }
+        Ok(remote)
+    }
+
+    /// Create an image from image file data, `declared` being the format its
+    /// name or content type gives, sized by [`Self::display_image_size`]
+    fn create_pic(
+        source: &str,
+        data: Vec<u8>,
+        declared: Option<image::ImageFormat>,
+        width: Option<u32>,
+        height: Option<u32>,
+        image_config: &crate::config::ImageConfig,
+    ) -> Result<Pic, ConversionError> {
+        let image_data = Self::embeddable_image_data(source, data, declared)?;
+        // Measured before any downscaling, which keeps the aspect ratio
+        let natural = image_scaling::pixel_dimensions(&image_data);
+        let (width, height) = Self::display_image_size(natural, width, height, image_config);
+        let image_data = match image_config.max_pixel_dimension {
+            Some(max_dimension) => image_scaling::limit_dimensions(image_data, max_dimension, source),
+            None => image_data,
+        };
+
+        // Create image with size constraints
+        // '9525' is from here: https://github.com/bokuweb/docx-rs/blob/main/docx-core/examples/image_floating.rs
+        let image = Pic::new(&image_data).size(
+            width * 9525,
+            height * 9525,
+        );
+
+        Ok(image)
+    }
+
+    /// Check that image data is in a supported format, detected from its
+    /// leading bytes
+    ///
+    /// The detected format wins over the `declared` one, from the file
+    /// extension or content type, with a warning when they disagree. Data
+    /// whose format cannot be detected is decoded in the declared format and
+    /// re-encoded as PNG, since docx-rs only recognizes images by their
+    /// content.
+    fn embeddable_image_data(
+        path: &str,
+        data: Vec<u8>,
+        declared: Option<image::ImageFormat>,
+    ) -> Result<Vec<u8>, ConversionError> {
+        use image::ImageFormat;
+        const SUPPORTED: [ImageFormat; 4] = [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Gif, ImageFormat::Bmp];
+
+        let unsupported = |detail: String| {
+            ConversionError::DocxGeneration(format!("Unsupported image format: {} ({})", path, detail))
+        };
+        match image::guess_format(&data) {
+            Ok(format) if SUPPORTED.contains(&format) => {
+                if declared.is_some_and(|declared| declared != format) {
+                    warn!(image = %path, detected = ?format, "Image extension or content type does not match its content; using the detected format");
+                }
+                Ok(data)
+            }
+            Ok(format) => Err(unsupported(format!("{:?} data", format))),
+            Err(_) => {
+                let format = declared
+                    .filter(|format| SUPPORTED.contains(format))
+                    .ok_or_else(|| unsupported("unrecognized data".to_string()))?;
+                let image = image::load_from_memory_with_format(&data, format)
+                    .map_err(|e| unsupported(format!("not a valid {:?} image: {}", format, e)))?;
+                let mut encoded = Cursor::new(Vec::new());
+                image
+                    .write_to(&mut encoded, ImageFormat::Png)
+                    .map_err(|e| unsupported(format!("cannot re-encode as PNG: {}", e)))?;
+                Ok(encoded.into_inner())
+            }
+        }
+    }
+
+    /// Add a horizontal rule to the document
+    fn add_horizontal_rule(&self, mut docx: Docx) -> Result<Docx, ConversionError> {
+        let rule = &self.config.elements.horizontal_rule;
+        let paragraph = match rule.mode {
+            HorizontalRuleMode::Line => Paragraph::new().set_borders(
+                ParagraphBorders::with_empty().set(
+                    ParagraphBorder::new(ParagraphBorderPosition::Bottom)
+                        .val(BorderType::Single)
+                        .size(6)
+                        .space(1)
+                        .color("auto"),
+                ),
+            ),
+            HorizontalRuleMode::PageBreak => {
+                Paragraph::new().add_run(Run::new().add_break(BreakType::Page))
+            }
+            HorizontalRuleMode::Ornament => Paragraph::new()
+                .align(AlignmentType::Center)
+                .add_run(Run::new().add_text(&rule.ornament)),
+        };
+
+        docx = docx.add_paragraph(paragraph);
+        Ok(docx)
+    }
+
+    /// Create a table cell with code content that may contain Markdown
+    /// formatting, shading the lines numbered in `highlight` (from 1)
+    fn create_code_block_cell_with_markdown(
+        &self,
+        code: &str,
+        style: &crate::config::CodeBlockStyle,
+        highlight: &[usize],
+    ) -> Result<TableCell, ConversionError> {
+        // // Check if the code contains Markdown formatting indicators
+        // if self.contains_markdown_formatting(code) {
+            self.create_code_block_cell_with_parsed_markdown(code, style, highlight)
+        // } else {
+        //     // Fall back to plain text rendering
+        //     self.create_code_block_cell(code, style)
+        // }
+    }
+
+    /// Shade every run of a paragraph; docx-rs has no paragraph shading
+    fn shade_runs(mut paragraph: Paragraph, color: &str) -> Paragraph {
+        let fill = color.trim_start_matches('#');
+        paragraph.children = paragraph
+            .children
+            .into_iter()
+            .map(|child| match child {
+                ParagraphChild::Run(run) => ParagraphChild::Run(Box::new(run.shading(Shading::new().fill(fill)))),
+                other => other,
+            })
+            .collect();
+        paragraph
+    }
+
+    /// Check if text contains common Markdown formatting
+    fn contains_markdown_formatting(&self, text: &str) -> bool {
+        text.contains("**") || // Bold
+        text.contains("*") ||  // Italic (but not ** which is already checked)
+        text.contains("![") || // Images
+        text.contains("[") ||  // Links
+        text.contains("`")     // Inline code
+    }
+
+    /// Create a table cell with parsed Markdown content
+    fn create_code_block_cell_with_parsed_markdown(
         &self,
         code: &str,
         style: &crate::config::CodeBlockStyle,
+        highlight: &[usize],
     ) -> Result<TableCell, ConversionError> {
         let mut cell = TableCell::new();
+        let code = if self.config.styles.code_lines.preserve_line_endings {
+            Cow::Borrowed(code)
+        } else {
+            normalize_line_endings(code)
+        };
 
         if style.preserve_line_breaks {
             // Handle edge case: empty code block
@@ -1432,24 +3202,20 @@ impl DocxGenerator {
                 // Split code by lines, preserving empty lines
                 let lines: Vec<&str> = trimmed_code.split('\n').collect();
 
-                for (_index, line) in lines.iter().enumerate() {
+                for (number, line) in (1..).zip(lines.iter()) {
                     // Convert tabs to spaces (4 spaces per tab) for consistent formatting
                     let processed_line = line.replace('\t', "    ");
 
                     // Handle empty lines by using a non-breaking space to preserve the line
-                    let line_content = if processed_line.trim().is_empty() {
-                        "\u{00A0}".to_string() // Non-breaking space to preserve empty lines
+                    let mut paragraph = if processed_line.trim().is_empty() {
+                        self.create_code_paragraph("\u{00A0}", style)?
                     } else {
-                        processed_line
+                        // Parse the line as Markdown and create paragraph with formatted runs
+                        self.create_code_paragraph_with_markdown(&processed_line, style)?
                     };
-
-                    let paragraph = self.create_code_paragraph(&line_content, style)?;
-
-                    // TODO: Apply paragraph spacing after each paragraph except the last one
-                    // The docx-rs 0.4 API may not support paragraph spacing configuration
-                    // This would require: paragraph.spacing_after(style.paragraph_spacing) or similar
-                    // For now, spacing is handled through line spacing within paragraphs
-
+                    if highlight.contains(&number) {
+                        paragraph = Self::shade_runs(paragraph, &self.config.styles.code_lines.highlight_color);
+                    }
                     cell = cell.add_paragraph(paragraph);
                 }
             }
@@ -1458,7 +3224,7 @@ impl DocxGenerator {
             // Trim trailing newlines to avoid extra empty lines
             let trimmed_code = code.trim_end_matches('\n');
             let processed_code = trimmed_code.replace('\t', "    ");
-            let paragraph = self.create_code_paragraph(&processed_code, style)?;
+            let paragraph = self.create_code_paragraph_with_markdown(&processed_code, style)?;
             cell = cell.add_paragraph(paragraph);
         }
 
@@ -1472,284 +3238,1798 @@ impl DocxGenerator {
         Ok(cell)
     }
 
-    fn apply_table_borders_with_type(
+    /// Create a code paragraph with Markdown formatting
+    fn create_code_paragraph_with_markdown(
         &self,
-        table: Table,
-        border_width: f32,
-        border_type: BorderType,
-    ) -> Result<Table, ConversionError> {
-        if border_width <= 0.0 {
-            // No borders when border_width is 0 or negative
-            return Ok(table);
+        text: &str,
+        style: &crate::config::CodeBlockStyle,
+    ) -> Result<Paragraph, ConversionError> {
+        let mut leading_spaces = String::new();
+        for ch in text.chars() {
+            if ch == ' ' {
+                leading_spaces.push(ch);
+            } else {
+                break;
+            }
         }
+        let text_without_leading_spaces = &text[leading_spaces.len()..];
+        
+        // Parse inline formatting including [BOLD] tags
+        let inline_elements = self.parse_inline_formatting_with_tags(text_without_leading_spaces);
 
-        // Convert border_width from points to docx-rs border units (eighths of a point)
-        let border_size = (border_width * 8.0) as usize;
+        let mut paragraph = Paragraph::new().style("CodeBlock");
 
-        // Apply borders to the table using docx-rs API
-        // Create table borders with the specified width using the correct API
-        let table_borders = TableBorders::new()
-            .set(
-                TableBorder::new(TableBorderPosition::Top)
-                    .border_type(border_type)
-                    .size(border_size)
-                    .color("000000"),
-            )
-            .set(
-                TableBorder::new(TableBorderPosition::Bottom)
-                    .border_type(border_type)
-                    .size(border_size)
-                    .color("000000"),
-            )
-            .set(
-                TableBorder::new(TableBorderPosition::Left)
-                    .border_type(border_type)
-                    .size(border_size)
-                    .color("000000"),
-            )
-            .set(
-                TableBorder::new(TableBorderPosition::Right)
-                    .border_type(border_type)
-                    .size(border_size)
-                    .color("000000"),
-            )
-            .set(
-                TableBorder::new(TableBorderPosition::InsideH)
-                    .border_type(border_type)
-                    .size(border_size)
-                    .color("000000"),
-            )
-            .set(
-                TableBorder::new(TableBorderPosition::InsideV)
-                    .border_type(border_type)
-                    .size(border_size)
-                    .color("000000"),
-            );
+        if !leading_spaces.is_empty() {
+            let run = self.create_code_run(&leading_spaces, style)?;
+            paragraph = paragraph.add_run(run);
+        }
 
-        let bordered_table = table.set_borders(table_borders);
+        // Process the inline elements
+        for inline in inline_elements {
+            let run = self.create_code_run_from_inline(&inline, style)?;
+            paragraph = paragraph.add_run(run);
+        }
+
+        // Apply line spacing configuration
+        let line_spacing_value = (style.line_spacing * 240.0) as i32;
+        paragraph = paragraph.line_spacing(LineSpacing::new().line(line_spacing_value));
+
+        Ok(paragraph)
+    }
+
+    /// Parse inline formatting (bold, italic) without treating # as heading
+    /// This is a simple parser that only handles ** and * for bold/italic
+    fn parse_inline_formatting(&self, text: &str) -> Vec<crate::markdown::InlineElement> {
+        let mut elements = Vec::new();
+        let mut current_text = String::new();
+        let mut chars = text.chars().peekable();
+        
+        elements.push(crate::markdown::InlineElement::Text(text.to_string()));
+
+        /// Disabled Markdown
+        // while let Some(ch) = chars.next() {
+        //     if ch == '*' {
+        //         // Check for bold (**) or italic (*)
+        //         if chars.peek() == Some(&'*') {
+        //             // Bold: **text**
+        //             chars.next(); // consume second *
+                    
+        //             // Save current text if any
+        //             if !current_text.is_empty() {
+        //                 elements.push(crate::markdown::InlineElement::Text(current_text.clone()));
+        //                 current_text.clear();
+        //             }
+                    
+        //             // Collect bold text
+        //             let mut bold_text = String::new();
+        //             let mut found_end = false;
+        //             while let Some(ch) = chars.next() {
+        //                 if ch == '*' && chars.peek() == Some(&'*') {
+        //                     chars.next(); // consume second *
+        //                     found_end = true;
+        //                     break;
+        //                 }
+        //                 bold_text.push(ch);
+        //             }
+                    
+        //             if found_end && !bold_text.is_empty() {
+        //                 elements.push(crate::markdown::InlineElement::Bold(bold_text));
+        //             } else {
+        //                 // If no closing **, treat as literal text
+        //                 current_text.push_str("**");
+        //                 current_text.push_str(&bold_text);
+        //             }
+        //         } else {
+        //             // Italic: *text*
+        //             // Save current text if any
+        //             if !current_text.is_empty() {
+        //                 elements.push(crate::markdown::InlineElement::Text(current_text.clone()));
+        //                 current_text.clear();
+        //             }
+                    
+        //             // Collect italic text
+        //             let mut italic_text = String::new();
+        //             let mut found_end = false;
+        //             while let Some(ch) = chars.next() {
+        //                 if ch == '*' {
+        //                     found_end = true;
+        //                     break;
+        //                 }
+        //                 italic_text.push(ch);
+        //             }
+                    
+        //             if found_end && !italic_text.is_empty() {
+        //                 elements.push(crate::markdown::InlineElement::Italic(italic_text));
+        //             } else {
+        //                 // If no closing *, treat as literal text
+        //                 current_text.push('*');
+        //                 current_text.push_str(&italic_text);
+        //             }
+        //         }
+        //     } else {
+        //         current_text.push(ch);
+        //     }
+        // }
+        
+        // // Add remaining text
+        // if !current_text.is_empty() {
+        //     elements.push(crate::markdown::InlineElement::Text(current_text));
+        // }
+        
+        // // If no elements were parsed, return the original text
+        // if elements.is_empty() {
+        //     elements.push(crate::markdown::InlineElement::Text(text.to_string()));
+        // }
+        
+        elements
+    }
+
+    /// Parse inline formatting including [BOLD] and [ITALIC] tags
+    fn parse_inline_formatting_with_tags(&self, text: &str) -> Vec<crate::markdown::InlineElement> {
+        let mut elements = Vec::new();
+        let mut current_pos = 0;
+        
+        while current_pos < text.len() {
+            // Look for the next formatting tag (either [BOLD] or [ITALIC])
+            let bold_pos = text[current_pos..].find("[BOLD]").map(|p| (p, "[BOLD]", "[/BOLD]", true));
+            let italic_pos = text[current_pos..].find("[ITALIC]").map(|p| (p, "[ITALIC]", "[/ITALIC]", false));
+            
+            // Determine which tag comes first
+            let next_tag = match (bold_pos, italic_pos) {
+                (Some((bp, _, _, _)), Some((ip, _, _, _))) => {
+                    if bp < ip { bold_pos } else { italic_pos }
+                }
+                (Some(_), None) => bold_pos,
+                (None, Some(_)) => italic_pos,
+                (None, None) => None,
+            };
+            
+            if let Some((tag_start, open_tag, close_tag, is_bold)) = next_tag {
+                let absolute_tag_start = current_pos + tag_start;
+                
+                // Add text before tag
+                if tag_start > 0 {
+                    elements.push(crate::markdown::InlineElement::Text(
+                        text[current_pos..absolute_tag_start].to_string()
+                    ));
+                }
+                
+                // Look for closing tag
+                let search_start = absolute_tag_start + open_tag.len();
+                if let Some(tag_end) = text[search_start..].find(close_tag) {
+                    let absolute_tag_end = search_start + tag_end;
+                    
+                    // Extract formatted content
+                    let content = &text[search_start..absolute_tag_end];
+                    if is_bold {
+                        elements.push(crate::markdown::InlineElement::Bold(content.to_string()));
+                    } else {
+                        elements.push(crate::markdown::InlineElement::Italic(
+                            content.to_string()
+                            .replace("[BOLD]", "")
+                            .replace("[/BOLD]", "")));
+                    }
+                    
+                    // Move position past closing tag
+                    current_pos = absolute_tag_end + close_tag.len();
+                } else {
+                    // No closing tag found, treat opening tag as literal text
+                    elements.push(crate::markdown::InlineElement::Text(
+                        text[absolute_tag_start..absolute_tag_start + open_tag.len()].to_string()
+                    ));
+                    current_pos = absolute_tag_start + open_tag.len();
+                }
+            } else {
+                // No more tags, add remaining text
+                if current_pos < text.len() {
+                    elements.push(crate::markdown::InlineElement::Text(
+                        text[current_pos..].to_string()
+                    ));
+                }
+                break;
+            }
+        }
+        
+        // If no elements were parsed, return the original text
+        if elements.is_empty() {
+            elements.push(crate::markdown::InlineElement::Text(text.to_string()));
+        }
+        
+        elements
+    }
+
+    /// Create a code run from an inline element with code styling
+    fn create_code_run_from_inline(
+        &self,
+        inline: &crate::markdown::InlineElement,
+        style: &crate::config::CodeBlockStyle,
+    ) -> Result<Run, ConversionError> {
+        let run = match inline {
+            crate::markdown::InlineElement::Text(text) => {
+                self.create_code_run(text, style)?
+            }
+            crate::markdown::InlineElement::Bold(text) => {
+                let mut run = self.create_code_run(text, style)?;
+                run = run.bold();
+                run
+            }
+            crate::markdown::InlineElement::Italic(text) => {
+                let mut run = self.create_code_run(text, style)?;
+                run = run.italic();
+                run
+            }
+            crate::markdown::InlineElement::Strikethrough(text) => {
+                let mut run = self.create_code_run(text, style)?;
+                run = run.strike();
+                run
+            }
+            crate::markdown::InlineElement::Code(text) => {
+                // Nested code - render with different background or styling
+                let mut run = self.create_code_run(text, style)?;
+                // Add a subtle highlight for nested code
+                run = run.highlight("lightGray");
+                run
+            }
+            crate::markdown::InlineElement::Link { text, url, title: _ } => {
+                let link_text = format!("{} ({})", text, url);
+                let mut run = self.create_code_run(&link_text, style)?;
+                run = run.color("0000FF"); // Blue color for links
+                run
+            }
+            crate::markdown::InlineElement::Span { text, .. }
+            | crate::markdown::InlineElement::Abbreviation { text, .. } => {
+                self.create_code_run(text, style)?
+            }
+            crate::markdown::InlineElement::FootnoteReference(label) => {
+                self.create_code_run(&format!("[^{}]", label), style)?
+            }
+            crate::markdown::InlineElement::ReferenceMarker(number) => {
+                self.create_code_run(&format!("[{}]", number), style)?
+            }
+            crate::markdown::InlineElement::Comment(_)
+            | crate::markdown::InlineElement::IndexTerm(_)
+            | crate::markdown::InlineElement::IndexMarker(_)
+            | crate::markdown::InlineElement::RevisionNote(_) => Run::new(),
+        };
+
+        Ok(run)
+    }
+
+    /// Create a basic code run with standard code styling
+    fn create_code_run(
+        &self,
+        text: &str,
+        style: &crate::config::CodeBlockStyle,
+    ) -> Result<Run, ConversionError> {
+        let mut run = Run::new().add_text(text);
+
+        // With named styles the code font comes from the CodeBlock style
+        if !self.config.styles.named_styles.enabled {
+            run = run
+                .fonts(
+                    RunFonts::new()
+                        .ascii(&style.font.family)
+                        .east_asia(&style.font.family),
+                )
+                .size((style.font.size * 2.0) as usize); // docx uses half-points
+
+            // Apply bold/italic conditionally based on font configuration
+            if style.font.bold {
+                run = run.bold();
+            }
+            if style.font.italic {
+                run = run.italic();
+            }
+        }
+
+        // Add background color if specified (applied to run for better compatibility)
+        if let Some(bg_color) = &style.background_color {
+            let color = bg_color.trim_start_matches('#');
+            run = run.shading(Shading::new().fill(color));
+        }
+
+        Ok(run)
+    }
+
+    /// Extract text content from a Markdown element
+    fn extract_text_from_element(&self, element: &crate::markdown::MarkdownElement) -> String {
+        match element {
+            crate::markdown::MarkdownElement::Heading { text, .. } => text.clone(),
+            crate::markdown::MarkdownElement::Paragraph { content, .. } => {
+                content.iter().map(|inline| self.extract_text_from_inline(inline)).collect::<Vec<_>>().join("")
+            }
+            crate::markdown::MarkdownElement::CodeBlock { code, .. } => code.clone(),
+            crate::markdown::MarkdownElement::List { items, .. } => {
+                items.iter().map(|item| {
+                    item.content.iter().map(|inline| self.extract_text_from_inline(inline)).collect::<Vec<_>>().join("")
+                }).collect::<Vec<_>>().join(" ")
+            }
+            crate::markdown::MarkdownElement::Table { headers, rows, .. } => {
+                let mut text = headers.join(" ");
+                for row in rows {
+                    text.push(' ');
+                    text.push_str(&row.join(" "));
+                }
+                text
+            }
+            crate::markdown::MarkdownElement::HtmlTable(table) => table.extract_text(),
+            crate::markdown::MarkdownElement::Image { alt_text, .. } => alt_text.clone(),
+            crate::markdown::MarkdownElement::HorizontalRule => "---".to_string(),
+            crate::markdown::MarkdownElement::SectionBreak { .. } => String::new(),
+            crate::markdown::MarkdownElement::FootnoteDefinition { content, .. } => {
+                content.iter().map(|inline| self.extract_text_from_inline(inline)).collect::<Vec<_>>().join("")
+            }
+            crate::markdown::MarkdownElement::Details { summary, body } => std::iter::once(summary.clone())
+                .chain(body.iter().map(|element| self.extract_text_from_element(element)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            crate::markdown::MarkdownElement::BlockQuote { content: body } => {
+                body.iter().map(|element| self.extract_text_from_element(element)).collect::<Vec<_>>().join(" ")
+            }
+        }
+    }
+
+    /// Extract text from an inline element
+    fn extract_text_from_inline(&self, inline: &crate::markdown::InlineElement) -> String {
+        match inline {
+            crate::markdown::InlineElement::Text(text) => text.clone(),
+            crate::markdown::InlineElement::Bold(text) => text.clone(),
+            crate::markdown::InlineElement::Italic(text) => text.clone(),
+            crate::markdown::InlineElement::Strikethrough(text) => text.clone(),
+            crate::markdown::InlineElement::Code(text) => text.clone(),
+            crate::markdown::InlineElement::Link { text, .. } => text.clone(),
+            crate::markdown::InlineElement::Span { text, .. } => text.clone(),
+            crate::markdown::InlineElement::Abbreviation { text, .. } => text.clone(),
+            crate::markdown::InlineElement::FootnoteReference(_)
+            | crate::markdown::InlineElement::ReferenceMarker(_)
+            | crate::markdown::InlineElement::Comment(_)
+            | crate::markdown::InlineElement::IndexTerm(_)
+            | crate::markdown::InlineElement::IndexMarker(_)
+            | crate::markdown::InlineElement::RevisionNote(_) => String::new(),
+        }
+    }
+
+    /// Create a table cell with code content for table-based code block rendering
+    fn create_code_block_cell(
+        &self,
+        code: &str,
+        style: &crate::config::CodeBlockStyle,
+    ) -> Result<TableCell, ConversionError> {
+        let mut cell = TableCell::new();
+        let code = if self.config.styles.code_lines.preserve_line_endings {
+            Cow::Borrowed(code)
+        } else {
+            normalize_line_endings(code)
+        };
+
+        if style.preserve_line_breaks {
+            // Handle edge case: empty code block
+            if code.is_empty() {
+                let paragraph = self.create_code_paragraph("\u{00A0}", style)?; // Use non-breaking space for visibility
+                cell = cell.add_paragraph(paragraph);
+            } else {
+                // Trim trailing newlines to avoid extra empty lines at the end
+                let trimmed_code = code.trim_end_matches('\n');
+
+                // Split code by lines, preserving empty lines
+                let lines: Vec<&str> = trimmed_code.split('\n').collect();
+
+                for (_index, line) in lines.iter().enumerate() {
+                    // Convert tabs to spaces (4 spaces per tab) for consistent formatting
+                    let processed_line = line.replace('\t', "    ");
+
+                    // Handle empty lines by using a non-breaking space to preserve the line
+                    let line_content = if processed_line.trim().is_empty() {
+                        "\u{00A0}".to_string() // Non-breaking space to preserve empty lines
+                    } else {
+                        processed_line
+                    };
+
+                    let paragraph = self.create_code_paragraph(&line_content, style)?;
+
+                    // TODO: Apply paragraph spacing after each paragraph except the last one
+                    // The docx-rs 0.4 API may not support paragraph spacing configuration
+                    // This would require: paragraph.spacing_after(style.paragraph_spacing) or similar
+                    // For now, spacing is handled through line spacing within paragraphs
+
+                    cell = cell.add_paragraph(paragraph);
+                }
+            }
+        } else {
+            // Single paragraph for entire code block with tab conversion
+            // Trim trailing newlines to avoid extra empty lines
+            let trimmed_code = code.trim_end_matches('\n');
+            let processed_code = trimmed_code.replace('\t', "    ");
+            let paragraph = self.create_code_paragraph(&processed_code, style)?;
+            cell = cell.add_paragraph(paragraph);
+        }
+
+        // Apply background color to table cell if specified
+        if let Some(bg_color) = &style.background_color {
+            let color = bg_color.trim_start_matches('#');
+            // Apply cell shading/background color using docx-rs API
+            cell = cell.shading(Shading::new().fill(color));
+        }
+
+        Ok(cell)
+    }
+
+    fn apply_table_borders_with_type(
+        &self,
+        table: Table,
+        border_width: f32,
+        border_type: BorderType,
+    ) -> Result<Table, ConversionError> {
+        if border_width <= 0.0 {
+            // No borders when border_width is 0 or negative
+            return Ok(table);
+        }
+
+        // Convert border_width from points to docx-rs border units (eighths of a point)
+        let border_size = (border_width * 8.0) as usize;
+
+        // Apply borders to the table using docx-rs API
+        // Create table borders with the specified width using the correct API
+        let table_borders = TableBorders::new()
+            .set(
+                TableBorder::new(TableBorderPosition::Top)
+                    .border_type(border_type)
+                    .size(border_size)
+                    .color("000000"),
+            )
+            .set(
+                TableBorder::new(TableBorderPosition::Bottom)
+                    .border_type(border_type)
+                    .size(border_size)
+                    .color("000000"),
+            )
+            .set(
+                TableBorder::new(TableBorderPosition::Left)
+                    .border_type(border_type)
+                    .size(border_size)
+                    .color("000000"),
+            )
+            .set(
+                TableBorder::new(TableBorderPosition::Right)
+                    .border_type(border_type)
+                    .size(border_size)
+                    .color("000000"),
+            )
+            .set(
+                TableBorder::new(TableBorderPosition::InsideH)
+                    .border_type(border_type)
+                    .size(border_size)
+                    .color("000000"),
+            )
+            .set(
+                TableBorder::new(TableBorderPosition::InsideV)
+                    .border_type(border_type)
+                    .size(border_size)
+                    .color("000000"),
+            );
+
+        let bordered_table = table.set_borders(table_borders);
+
+        Ok(bordered_table)
+    }
+
+    /// Apply the configured borders to a table converted from Markdown or HTML
+    ///
+    /// With a `border_width` of 0 the table gets no border definitions at
+    /// all (docx-rs would otherwise write thin single borders), so nothing
+    /// prints; Word's View Gridlines shows its layout while editing.
+    fn apply_markdown_table_borders(&self, table: Table) -> Result<Table, ConversionError> {
+        let table_style = &self.config.styles.table;
+        if table_style.border_width > 0.0 {
+            return self.apply_table_borders(table, table_style.border_width);
+        }
+        Ok(table.set_borders(TableBorders::with_empty()))
+    }
+
+    /// Apply border styling to table based on border_width configuration
+    fn apply_table_borders(
+        &self,
+        table: Table,
+        border_width: f32,
+    ) -> Result<Table, ConversionError> {
+        self.apply_table_borders_with_type(table, border_width, BorderType::Single)
+    }
+
+    /// Create a consistent code paragraph with proper styling for use within table cells
+    fn create_code_paragraph(
+        &self,
+        text: &str,
+        style: &crate::config::CodeBlockStyle,
+    ) -> Result<Paragraph, ConversionError> {
+        let mut run = Run::new().add_text(text);
+
+        // With named styles the code font comes from the CodeBlock style
+        if !self.config.styles.named_styles.enabled {
+            run = run
+                .fonts(
+                    RunFonts::new()
+                        .ascii(&style.font.family)
+                        .east_asia(&style.font.family),
+                )
+                .size((style.font.size * 2.0) as usize); // docx uses half-points
+
+            // Apply bold/italic conditionally based on font configuration
+            if style.font.bold {
+                run = run.bold();
+            }
+            if style.font.italic {
+                run = run.italic();
+            }
+        }
+
+        // Add background color if specified (applied to run for better compatibility)
+        if let Some(bg_color) = &style.background_color {
+            let color = bg_color.trim_start_matches('#');
+            run = run.shading(Shading::new().fill(color));
+        }
+
+        // Create paragraph with the styled run and apply line spacing
+        let mut paragraph = Paragraph::new().add_run(run).style("CodeBlock"); // Apply code block style to prevent text wrapping
+
+        // Apply line spacing configuration - convert to docx-rs format
+        // docx-rs expects line spacing in 240ths of a line (240 = single spacing)
+        let line_spacing_value = (style.line_spacing * 240.0) as i32;
+        paragraph = paragraph.line_spacing(LineSpacing::new().line(line_spacing_value));
+
+        Ok(paragraph)
+    }
+
+    /// Calculate column widths based on content length
+    fn calculate_column_widths(&self, headers: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+        if headers.is_empty() && rows.is_empty() {
+            return vec![];
+        }
+
+        // Determine number of columns
+        let num_columns = if !headers.is_empty() {
+            headers.len()
+        } else {
+            rows.iter().map(|row| row.len()).max().unwrap_or(0)
+        };
+
+        if num_columns == 0 {
+            return vec![];
+        }
+
+        let mut max_lengths = vec![0; num_columns];
+
+        // Check header lengths
+        for (i, header) in headers.iter().enumerate() {
+            if i < num_columns {
+                max_lengths[i] = max_lengths[i].max(self.estimate_cell_width(header));
+            }
+        }
+
+        // Check data row lengths
+        for row in rows {
+            for (i, cell) in row.iter().enumerate() {
+                if i < num_columns {
+                    max_lengths[i] = max_lengths[i].max(self.estimate_cell_width(cell));
+                }
+            }
+        }
+
+        // Convert character lengths to docx width units (DXA - twentieths of a point)
+        // Base calculation: approximately 120 DXA per character for typical fonts
+        // Add padding and ensure minimum/maximum widths
+        max_lengths
+            .into_iter()
+            .map(|len| {
+                let base_width = len * 120; // Base width calculation
+                let padded_width = base_width + 240; // Add padding (240 DXA = 12pt)
+                let min_width = 720; // Minimum width (720 DXA = 36pt)
+                let max_width = 2880; // Maximum width (2880 DXA = 144pt)
+                
+                padded_width.max(min_width).min(max_width)
+            })
+            .collect()
+    }
+
+    /// Estimate cell width in characters from its longest line
+    fn estimate_cell_width(&self, text: &str) -> usize {
+        text.split('\n')
+            .map(|line| self.estimate_text_width(line))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Estimate text width in characters (accounting for different character types)
+    fn estimate_text_width(&self, text: &str) -> usize {
+        let mut width = 0;
+        
+        for ch in text.chars() {
+            width += match ch {
+                // CJK characters (Chinese, Japanese, Korean) are typically wider
+                '\u{4E00}'..='\u{9FFF}' | // CJK Unified Ideographs
+                '\u{3400}'..='\u{4DBF}' | // CJK Extension A
+                '\u{3040}'..='\u{309F}' | // Hiragana
+                '\u{30A0}'..='\u{30FF}' | // Katakana
+                '\u{AC00}'..='\u{D7AF}' => 2, // Hangul
+                
+                // Wide ASCII characters
+                'W' | 'M' | 'Q' | 'G' | 'O' | 'D' | 'H' | 'B' | 'R' | 'U' => 2,
+                
+                // Narrow characters
+                'i' | 'l' | 'I' | 'j' | 'f' | 't' | 'r' => 1,
+                
+                // Regular characters
+                _ => if ch.is_ascii() { 1 } else { 2 }
+            };
+        }
+        
+        width
+    }
+
+    /// Get current configuration
+    pub fn config(&self) -> &ConversionConfig {
+        &self.config
+    }
+
+    /// Update configuration
+    pub fn update_config(&mut self, config: ConversionConfig) {
+        let config = config.compacted();
+        let config_arc = Arc::new(config.clone());
+
+        // Update heading processor if any heading levels have numbering configured
+        let old_has_numbering = self.heading_processor.is_some();
+        let new_has_numbering = config
+            .styles
+            .headings
+            .values()
+            .any(|style| style.numbering.is_some());
+
+        self.heading_processor = if new_has_numbering {
+            info!("Updating heading processor with new numbering configuration");
+
+            // Validate new configuration
+            match HeadingProcessor::new(config_arc.clone()).validate_numbering_formats() {
+                Ok(_) => {
+                    debug!("New numbering formats validated successfully");
+                }
+                Err(e) => {
+                    error!(
+                        error = %e,
+                        "Invalid numbering formats in new configuration"
+                    );
+                    warn!("Proceeding with configuration update for graceful degradation");
+                }
+            }
+
+            Some(HeadingProcessor::new(config_arc))
+        } else {
+            if old_has_numbering {
+                info!("Removing heading processor - no numbering in new configuration");
+            }
+            None
+        };
+
+        debug!(
+            old_has_numbering = old_has_numbering,
+            new_has_numbering = new_has_numbering,
+            "Heading processor update completed"
+        );
+
+        self.config = config;
+    }
+}
+
+/// Id of the named style of headings at `level`
+fn heading_style_id(level: u8) -> String {
+    format!("Heading{}", level)
+}
+
+/// Label of a front matter field: `release_date` becomes "Release date"
+fn metadata_label(name: &str) -> String {
+    let name = name.replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
+/// Word number format of an ordered list style
+fn list_number_format(style: ListNumberStyle) -> &'static str {
+    match style {
+        ListNumberStyle::Decimal => "decimal",
+        ListNumberStyle::LowerAlpha => "lowerLetter",
+        ListNumberStyle::UpperAlpha => "upperLetter",
+        ListNumberStyle::LowerRoman => "lowerRoman",
+        ListNumberStyle::UpperRoman => "upperRoman",
+    }
+}
+
+/// Whether a link leaves the document, so it becomes an external hyperlink
+/// rather than a plain run; `#fragment` links stay within it
+fn is_external_link(url: &str) -> bool {
+    !url.is_empty() && !url.starts_with('#')
+}
+
+/// Convert `\r\n` and lone `\r` line endings to `\n`
+fn normalize_line_endings(code: &str) -> Cow<'_, str> {
+    if code.contains('\r') {
+        Cow::Owned(code.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConversionConfig;
+    use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+    use crate::test_utils::{create_test_config, create_test_document};
+
+    #[test]
+    fn test_heading_generation() {
+        let config = ConversionConfig::default();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Test Heading".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_paragraph_with_formatting() {
+        let config = ConversionConfig::default();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("This is ".to_string()),
+                InlineElement::Bold("bold".to_string()),
+                InlineElement::Text(" and ".to_string()),
+                InlineElement::Italic("italic".to_string()),
+                InlineElement::Text(" and ".to_string()),
+                InlineElement::Strikethrough("strikethrough".to_string()),
+                InlineElement::Text(" text.".to_string()),
+            ],
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_create_code_block_cell() {
+        let config = ConversionConfig::default();
+        let generator = DocxGenerator::new(config);
+
+        // Test single line code
+        let code = "println!(\"Hello, world!\");";
+        let result = generator.create_code_block_cell(code, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+
+        // Test multi-line code with line breaks preserved
+        let multi_line_code = "fn main() {\n    println!(\"Hello\");\n}";
+        let result =
+            generator.create_code_block_cell(multi_line_code, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+
+        // Test empty code
+        let empty_code = "";
+        let result =
+            generator.create_code_block_cell(empty_code, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_apply_table_borders() {
+        let config = ConversionConfig::default();
+        let generator = DocxGenerator::new(config);
+
+        // Create a simple table
+        let cell =
+            TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("test")));
+        let row = TableRow::new(vec![cell]);
+        let table = Table::new(vec![row]);
+
+        // Test with positive border width
+        let result = generator.apply_table_borders(table.clone(), 1.0);
+        assert!(result.is_ok());
+
+        // Test with zero border width (no borders)
+        let result = generator.apply_table_borders(table.clone(), 0.0);
+        assert!(result.is_ok());
+
+        // Test with negative border width (no borders)
+        let result = generator.apply_table_borders(table, -1.0);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_create_code_paragraph() {
+        let config = ConversionConfig::default();
+        let generator = DocxGenerator::new(config);
+
+        // Test normal text
+        let text = "let x = 42;";
+        let result = generator.create_code_paragraph(text, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+
+        // Test empty text
+        let empty_text = "";
+        let result =
+            generator.create_code_paragraph(empty_text, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+
+        // Test text with special characters
+        let special_text = "fn test() -> Result<(), Error> { Ok(()) }";
+        let result =
+            generator.create_code_paragraph(special_text, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_code_block_font_styling_application() {
+        let mut config = ConversionConfig::default();
+
+        // Configure custom font styling
+        config.styles.code_block.font.family = "Monaco".to_string();
+        config.styles.code_block.font.size = 14.0;
+        config.styles.code_block.font.bold = true;
+        config.styles.code_block.font.italic = true;
+        config.styles.code_block.background_color = Some("#ffff00".to_string());
+        config.styles.code_block.line_spacing = 1.5;
+
+        let generator = DocxGenerator::new(config);
+
+        // Test that create_code_paragraph applies all font styling
+        let text = "console.log('Hello, World!');";
+        let result = generator.create_code_paragraph(text, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+
+        // Test that create_code_block_cell applies styling and background color
+        let code = "function test() {\n    return 42;\n}";
+        let result = generator.create_code_block_cell(code, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_code_block_background_color_application() {
+        let mut config = ConversionConfig::default();
+
+        // Test with background color
+        config.styles.code_block.background_color = Some("#f0f0f0".to_string());
+        let generator = DocxGenerator::new(config);
+
+        let code = "print('test')";
+        let result = generator.create_code_block_cell(code, &generator.config.styles.code_block);
+        assert!(result.is_ok());
+
+        // Test without background color
+        let mut config_no_bg = ConversionConfig::default();
+        config_no_bg.styles.code_block.background_color = None;
+        let generator_no_bg = DocxGenerator::new(config_no_bg);
+
+        let result_no_bg =
+            generator_no_bg.create_code_block_cell(code, &generator_no_bg.config.styles.code_block);
+        assert!(result_no_bg.is_ok());
+    }
+
+    #[test]
+    fn test_table_generation() {
+        let config = ConversionConfig::default();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Name".to_string(), "Age".to_string(), "City".to_string()],
+            rows: vec![
+                vec![
+                    "Alice".to_string(),
+                    "30".to_string(),
+                    "New York".to_string(),
+                ],
+                vec!["Bob".to_string(), "25".to_string(), "London".to_string()],
+                vec!["Charlie".to_string(), "35".to_string(), "Tokyo".to_string()],
+            ],
+            links: vec![],
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_list_generation() {
+        let config = ConversionConfig::default();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::List {
+            ordered: false,
+            items: vec![
+                ListItem::new(vec![InlineElement::Text("First item".to_string())]),
+                ListItem::new(vec![InlineElement::Text("Second item".to_string())]),
+                ListItem::new(vec![InlineElement::Text("Third item".to_string())]),
+            ],
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_image_placeholder_generation() {
+        let config = ConversionConfig::default();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Image {
+            alt_text: "Test Image".to_string(),
+            url: "https://example.com/image.jpg".to_string(),
+            title: Some("Image Title".to_string()),
+            width: None,
+            height: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_generation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: "fn main() {\n    println!(\"Hello, world!\");\n}".to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_horizontal_rule_generation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::HorizontalRule);
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_horizontal_rule_modes() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::HorizontalRule);
+        let render = |mode| {
+            let mut config = create_test_config();
+            config.elements.horizontal_rule.mode = mode;
+            config.elements.horizontal_rule.ornament = "❦".to_string();
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
+        };
+
+        let line = render(HorizontalRuleMode::Line);
+        assert!(line.contains("<w:bottom w:val=\"single\""));
+        assert!(!line.contains("w:type=\"page\""));
+
+        let page_break = render(HorizontalRuleMode::PageBreak);
+        assert!(page_break.contains("<w:br w:type=\"page\" />"));
+        assert!(!page_break.contains("<w:pBdr>"));
+
+        let ornament = render(HorizontalRuleMode::Ornament);
+        assert!(ornament.contains("<w:jc w:val=\"center\" />"));
+        assert!(ornament.contains(">❦</w:t>"));
+    }
+
+    #[test]
+    fn test_complex_document_generation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+        let document = create_test_document();
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+        // Complex document should be larger
+        assert!(docx_bytes.len() > 1000);
+    }
+
+    #[test]
+    fn test_empty_document_generation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+        let document = MarkdownDocument::new();
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_inline_code_generation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Use ".to_string()),
+                InlineElement::Code("println!()".to_string()),
+                InlineElement::Text(" to print.".to_string()),
+            ],
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_line_break_preservation() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        // Test code with empty lines, tabs, and long lines
+        let code_with_edge_cases = "fn main() {\n\tprintln!(\"Hello, world!\");\n\n\t// This is a very long comment that should not be wrapped automatically in the docx output to preserve the original formatting\n\tlet x = 42;\n}";
+
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: code_with_edge_cases.to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_without_line_break_preservation() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = false;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: "fn main() {\n    println!(\"Hello, world!\");\n}".to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_empty_code_block() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("text".to_string()),
+            code: "".to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_with_tabs() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        // Code with tabs that should be converted to spaces
+        let code_with_tabs =
+            "function example() {\n\tif (true) {\n\t\tconsole.log('Hello');\n\t}\n}";
+
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("javascript".to_string()),
+            code: code_with_tabs.to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_with_empty_lines() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        // Code with empty lines that should be preserved
+        let code_with_empty_lines = "line1\n\nline3\n\n\nline6";
+
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("text".to_string()),
+            code: code_with_empty_lines.to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_with_long_lines() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        // Very long line that should not be wrapped
+        let long_line = "This is a very long line of code that should not be automatically wrapped in the docx output to preserve the original formatting and maintain code readability";
+        let code_with_long_lines = format!("short line\n{}\nanother short line", long_line);
+
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("text".to_string()),
+            code: code_with_long_lines,
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_spacing_configuration() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+        config.styles.code_block.line_spacing = 1.5;
+        config.styles.code_block.paragraph_spacing = 12.0;
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: "fn main() {\n    println!(\"Hello\");\n}".to_string(),
+            processed: None,
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_code_block_preserve_vs_no_preserve() {
+        // Test with preserve_line_breaks = true
+        let mut config_preserve = create_test_config();
+        config_preserve.styles.code_block.preserve_line_breaks = true;
+        let mut generator_preserve = DocxGenerator::new(config_preserve);
+
+        // Test with preserve_line_breaks = false
+        let mut config_no_preserve = create_test_config();
+        config_no_preserve.styles.code_block.preserve_line_breaks = false;
+        let mut generator_no_preserve = DocxGenerator::new(config_no_preserve);
+
+        let mut document = MarkdownDocument::new();
+        let code = "line1\nline2\nline3";
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("text".to_string()),
+            code: code.to_string(),
+            processed: None,
+        });
+
+        // Both should succeed but may produce different output
+        let result_preserve = generator_preserve.generate(&document);
+        let result_no_preserve = generator_no_preserve.generate(&document);
+
+        assert!(result_preserve.is_ok());
+        assert!(result_no_preserve.is_ok());
+
+        let docx_bytes_preserve = result_preserve.unwrap();
+        let docx_bytes_no_preserve = result_no_preserve.unwrap();
+
+        assert!(!docx_bytes_preserve.is_empty());
+        assert!(!docx_bytes_no_preserve.is_empty());
+
+        // The outputs should be different (though we can't easily test the exact difference)
+        // This test mainly ensures both modes work without errors
+    }
+
+    #[test]
+    fn test_link_generation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Visit ".to_string()),
+                InlineElement::Link {
+                    text: "Google".to_string(),
+                    url: "https://google.com".to_string(),
+                    title: None,
+                },
+                InlineElement::Text(" for search.".to_string()),
+            ],
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_multiple_headings() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        for level in 1..=6 {
+            document.add_element(MarkdownElement::Heading {
+                level,
+                text: format!("Heading Level {}", level),
+            });
+        }
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_config_application() {
+        let mut config = create_test_config();
+        config.document.default_font.size = 16.0;
+        config.document.default_font.family = "Arial".to_string();
+
+        let generator = DocxGenerator::new(config.clone());
+
+        // Verify config is stored
+        assert_eq!(generator.config.document.default_font.size, 16.0);
+        assert_eq!(generator.config.document.default_font.family, "Arial");
+    }
+
+    #[test]
+    fn test_heading_numbering_integration() {
+        let mut config = create_test_config();
+
+        // Configure numbering for H1 and H2
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Introduction".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Overview".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Details".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Conclusion".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+
+        // Verify that heading processor was created
+        assert!(generator.heading_processor.is_some());
+    }
+
+    #[test]
+    fn test_heading_without_numbering() {
+        let config = create_test_config(); // Default config has no numbering
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Plain Heading".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+
+        // Verify that no heading processor was created
+        assert!(generator.heading_processor.is_none());
+    }
+
+    #[test]
+    fn test_mixed_numbering_scenario() {
+        let mut config = create_test_config();
+
+        // Configure numbering only for H1 and H3 (skip H2)
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&3).unwrap().numbering = Some("%1.%2.%3".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Chapter".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Section".to_string(), // No numbering
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 3,
+            text: "Subsection".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_config_update_with_numbering() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        // Initially no numbering
+        assert!(generator.heading_processor.is_none());
+
+        // Update config to include numbering
+        let mut new_config = create_test_config();
+        new_config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+
+        generator.update_config(new_config);
+
+        // Now should have heading processor
+        assert!(generator.heading_processor.is_some());
+
+        // Update config to remove numbering
+        let no_numbering_config = create_test_config();
+        generator.update_config(no_numbering_config);
+
+        // Should no longer have heading processor
+        assert!(generator.heading_processor.is_none());
+    }
+
+    #[test]
+    fn test_numbering_state_reset_between_documents() {
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        // Generate first document
+        let mut document1 = MarkdownDocument::new();
+        document1.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "First Document".to_string(),
+        });
+
+        let result1 = generator.generate(&document1);
+        assert!(result1.is_ok());
+
+        // Generate second document - numbering should reset
+        let mut document2 = MarkdownDocument::new();
+        document2.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Second Document".to_string(),
+        });
+
+        let result2 = generator.generate(&document2);
+        assert!(result2.is_ok());
+
+        // Both should succeed and numbering should start fresh for each document
+        assert!(!result1.unwrap().is_empty());
+        assert!(!result2.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_numbering_error_handling() {
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        // Test with invalid heading level (should be handled gracefully)
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Valid Heading".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_numbering_with_empty_text() {
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "".to_string(), // Empty text
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "   ".to_string(), // Whitespace only
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_complex_numbering_scenario() {
+        let mut config = create_test_config();
+
+        // Configure numbering for multiple levels
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+        config.styles.headings.get_mut(&3).unwrap().numbering = Some("%1.%2.%3".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+
+        // Create a complex document structure
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Chapter 1".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Section 1.1".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 3,
+            text: "Subsection 1.1.1".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 3,
+            text: "Subsection 1.1.2".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Section 1.2".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Chapter 2".to_string(),
+        });
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Section 2.1".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+
+        // Verify that heading processor was created and is working
+        assert!(generator.heading_processor.is_some());
+    }
+
+    #[test]
+    fn test_numbering_graceful_degradation() {
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+
+        let mut generator = DocxGenerator::new(config);
+
+        // Test that the generator handles potential numbering errors gracefully
+        // by ensuring the document generation still succeeds even if numbering fails
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Test Heading".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+
+        // Verify that the heading processor exists and is functional
+        assert!(generator.heading_processor.is_some());
+
+        // Test that the processor can handle multiple headings without issues
+        let mut document2 = MarkdownDocument::new();
+        for i in 1..=5 {
+            document2.add_element(MarkdownElement::Heading {
+                level: 1,
+                text: format!("Heading {}", i),
+            });
+        }
+
+        let result2 = generator.generate(&document2);
+        assert!(result2.is_ok());
+        assert!(!result2.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_numbering_style_consistency() {
+        let mut config = create_test_config();
+
+        // Configure numbering and ensure styles are applied consistently
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&1).unwrap().font.bold = true;
+        config.styles.headings.get_mut(&1).unwrap().font.size = 18.0;
+
+        let mut generator = DocxGenerator::new(config);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Styled Heading".to_string(),
+        });
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+
+        // The test verifies that the docx generation succeeds with styled numbered headings
+        // The actual style application is verified by the docx library integration
+    }
+
+    #[test]
+    fn test_line_break_preservation_with_empty_lines() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
+
+        let generator = DocxGenerator::new(config);
+
+        // Test code with empty lines and tabs
+        let code_with_empty_lines =
+            "function test() {\n\n    console.log('hello');\n\n    return true;\n}";
 
-        Ok(bordered_table)
-    }
+        let cell_result = generator
+            .create_code_block_cell(code_with_empty_lines, &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
 
-    /// Apply border styling to table based on border_width configuration
-    fn apply_table_borders(
-        &self,
-        table: Table,
-        border_width: f32,
-    ) -> Result<Table, ConversionError> {
-        self.apply_table_borders_with_type(table, border_width, BorderType::Single)
+        // Test that empty code block is handled
+        let empty_code_result =
+            generator.create_code_block_cell("", &generator.config.styles.code_block);
+        assert!(empty_code_result.is_ok());
     }
 
-    /// Create a consistent code paragraph with proper styling for use within table cells
-    fn create_code_paragraph(
-        &self,
-        text: &str,
-        style: &crate::config::CodeBlockStyle,
-    ) -> Result<Paragraph, ConversionError> {
-        let mut run = Run::new()
-            .add_text(text)
-            .fonts(
-                RunFonts::new()
-                    .ascii(&style.font.family)
-                    .east_asia(&style.font.family),
-            )
-            .size((style.font.size * 2.0) as usize); // docx uses half-points
+    #[test]
+    fn test_tab_conversion_in_code_blocks() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
 
-        // Apply bold/italic conditionally based on font configuration
-        if style.font.bold {
-            run = run.bold();
-        }
-        if style.font.italic {
-            run = run.italic();
-        }
+        let generator = DocxGenerator::new(config);
 
-        // Add background color if specified (applied to run for better compatibility)
-        if let Some(bg_color) = &style.background_color {
-            let color = bg_color.trim_start_matches('#');
-            run = run.highlight(color);
-        }
+        // Test code with tabs that should be converted to spaces
+        let code_with_tabs = "function test() {\n\tconsole.log('hello');\n\t\treturn true;\n}";
 
-        // Create paragraph with the styled run and apply line spacing
-        let mut paragraph = Paragraph::new().add_run(run).style("CodeBlock"); // Apply code block style to prevent text wrapping
+        let cell_result =
+            generator.create_code_block_cell(code_with_tabs, &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
 
-        // Apply line spacing configuration - convert to docx-rs format
-        // docx-rs expects line spacing in 240ths of a line (240 = single spacing)
-        let line_spacing_value = (style.line_spacing * 240.0) as i32;
-        paragraph = paragraph.line_spacing(LineSpacing::new().line(line_spacing_value));
+        // Test without line break preservation (single paragraph)
+        let mut config_no_preserve = create_test_config();
+        config_no_preserve.styles.code_block.preserve_line_breaks = false;
+        let generator_no_preserve = DocxGenerator::new(config_no_preserve);
 
-        Ok(paragraph)
+        let cell_result_no_preserve = generator_no_preserve.create_code_block_cell(
+            code_with_tabs,
+            &generator_no_preserve.config.styles.code_block,
+        );
+        assert!(cell_result_no_preserve.is_ok());
     }
 
-    /// Calculate column widths based on content length
-    fn calculate_column_widths(&self, headers: &[String], rows: &[Vec<String>]) -> Vec<usize> {
-        if headers.is_empty() && rows.is_empty() {
-            return vec![];
-        }
+    #[test]
+    fn test_multi_line_code_block_table_generation() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
 
-        // Determine number of columns
-        let num_columns = if !headers.is_empty() {
-            headers.len()
-        } else {
-            rows.iter().map(|row| row.len()).max().unwrap_or(0)
-        };
+        let mut generator = DocxGenerator::new(config);
 
-        if num_columns == 0 {
-            return vec![];
-        }
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("python".to_string()),
+            code: "def hello_world():\n    print('Hello, World!')\n\n    return 'success'\n\nif __name__ == '__main__':\n    hello_world()".to_string(),
+            processed: None,
+        });
 
-        let mut max_lengths = vec![0; num_columns];
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
 
-        // Check header lengths
-        for (i, header) in headers.iter().enumerate() {
-            if i < num_columns {
-                max_lengths[i] = max_lengths[i].max(self.estimate_text_width(header));
-            }
-        }
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
 
-        // Check data row lengths
-        for row in rows {
-            for (i, cell) in row.iter().enumerate() {
-                if i < num_columns {
-                    max_lengths[i] = max_lengths[i].max(self.estimate_text_width(cell));
-                }
-            }
-        }
+    #[test]
+    fn test_code_block_non_breaking_space_preservation() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
 
-        // Convert character lengths to docx width units (DXA - twentieths of a point)
-        // Base calculation: approximately 120 DXA per character for typical fonts
-        // Add padding and ensure minimum/maximum widths
-        max_lengths
-            .into_iter()
-            .map(|len| {
-                let base_width = len * 120; // Base width calculation
-                let padded_width = base_width + 240; // Add padding (240 DXA = 12pt)
-                let min_width = 720; // Minimum width (720 DXA = 36pt)
-                let max_width = 2880; // Maximum width (2880 DXA = 144pt)
-                
-                padded_width.max(min_width).min(max_width)
-            })
-            .collect()
-    }
+        let generator = DocxGenerator::new(config);
 
-    /// Estimate text width in characters (accounting for different character types)
-    fn estimate_text_width(&self, text: &str) -> usize {
-        let mut width = 0;
-        
-        for ch in text.chars() {
-            width += match ch {
-                // CJK characters (Chinese, Japanese, Korean) are typically wider
-                '\u{4E00}'..='\u{9FFF}' | // CJK Unified Ideographs
-                '\u{3400}'..='\u{4DBF}' | // CJK Extension A
-                '\u{3040}'..='\u{309F}' | // Hiragana
-                '\u{30A0}'..='\u{30FF}' | // Katakana
-                '\u{AC00}'..='\u{D7AF}' => 2, // Hangul
-                
-                // Wide ASCII characters
-                'W' | 'M' | 'Q' | 'G' | 'O' | 'D' | 'H' | 'B' | 'R' | 'U' => 2,
-                
-                // Narrow characters
-                'i' | 'l' | 'I' | 'j' | 'f' | 't' | 'r' => 1,
-                
-                // Regular characters
-                _ => if ch.is_ascii() { 1 } else { 2 }
-            };
-        }
-        
-        width
-    }
+        // Test that empty lines are preserved using non-breaking spaces
+        let code_with_multiple_empty_lines = "line1\n\n\nline4\n\n\nline7";
 
-    /// Get current configuration
-    pub fn config(&self) -> &ConversionConfig {
-        &self.config
-    }
+        let cell_result = generator.create_code_block_cell(
+            code_with_multiple_empty_lines,
+            &generator.config.styles.code_block,
+        );
+        assert!(cell_result.is_ok());
 
-    /// Update configuration
-    pub fn update_config(&mut self, config: ConversionConfig) {
-        let config_arc = Arc::new(config.clone());
+        // Test edge case: only empty lines
+        let only_empty_lines = "\n\n\n";
+        let empty_lines_result =
+            generator.create_code_block_cell(only_empty_lines, &generator.config.styles.code_block);
+        assert!(empty_lines_result.is_ok());
+    }
 
-        // Update heading processor if any heading levels have numbering configured
-        let old_has_numbering = self.heading_processor.is_some();
-        let new_has_numbering = config
-            .styles
-            .headings
-            .values()
-            .any(|style| style.numbering.is_some());
+    // Task 9: Unit tests for table-based code block generation
 
-        self.heading_processor = if new_has_numbering {
-            info!("Updating heading processor with new numbering configuration");
+    #[test]
+    fn test_single_line_code_block_table_creation() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
 
-            // Validate new configuration
-            match HeadingProcessor::new(config_arc.clone()).validate_numbering_formats() {
-                Ok(_) => {
-                    debug!("New numbering formats validated successfully");
-                }
-                Err(e) => {
-                    error!(
-                        error = %e,
-                        "Invalid numbering formats in new configuration"
-                    );
-                    warn!("Proceeding with configuration update for graceful degradation");
-                }
-            }
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: "let x = 42;".to_string(),
+            processed: None,
+        });
 
-            Some(HeadingProcessor::new(config_arc))
-        } else {
-            if old_has_numbering {
-                info!("Removing heading processor - no numbering in new configuration");
-            }
-            None
-        };
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
 
-        debug!(
-            old_has_numbering = old_has_numbering,
-            new_has_numbering = new_has_numbering,
-            "Heading processor update completed"
-        );
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
 
-        self.config = config;
+        // Test that the code block is rendered as a table
+        let cell_result =
+            generator.create_code_block_cell("let x = 42;", &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
     }
-}
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::ConversionConfig;
-    use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
-    use crate::test_utils::{create_test_config, create_test_document};
 
     #[test]
-    fn test_heading_generation() {
-        let config = ConversionConfig::default();
+    fn test_multi_line_code_block_with_line_break_preservation() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
         let mut generator = DocxGenerator::new(config);
 
+        let multi_line_code = "fn main() {\n    println!(\"Hello, world!\");\n    let x = 42;\n    println!(\"x = {}\", x);\n}";
+
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Test Heading".to_string(),
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: multi_line_code.to_string(),
+            processed: None,
         });
 
         let result = generator.generate(&document);
         assert!(result.is_ok());
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+
+        // Test that each line is preserved as separate paragraphs within the table cell
+        let cell_result =
+            generator.create_code_block_cell(multi_line_code, &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
+
+        // Test with preserve_line_breaks = false for comparison
+        let mut config_no_preserve = create_test_config();
+        config_no_preserve.styles.code_block.preserve_line_breaks = false;
+        let generator_no_preserve = DocxGenerator::new(config_no_preserve);
+        let cell_result_no_preserve = generator_no_preserve.create_code_block_cell(
+            multi_line_code,
+            &generator_no_preserve.config.styles.code_block,
+        );
+        assert!(cell_result_no_preserve.is_ok());
     }
 
     #[test]
-    fn test_paragraph_with_formatting() {
-        let config = ConversionConfig::default();
+    fn test_empty_code_block_table_handling() {
+        let mut config = create_test_config();
+        config.styles.code_block.preserve_line_breaks = true;
         let mut generator = DocxGenerator::new(config);
 
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("This is ".to_string()),
-                InlineElement::Bold("bold".to_string()),
-                InlineElement::Text(" and ".to_string()),
-                InlineElement::Italic("italic".to_string()),
-                InlineElement::Text(" and ".to_string()),
-                InlineElement::Strikethrough("strikethrough".to_string()),
-                InlineElement::Text(" text.".to_string()),
-            ],
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("text".to_string()),
+            code: "".to_string(),
+            processed: None,
         });
 
         let result = generator.generate(&document);
@@ -1757,142 +5037,96 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
-    }
-
-    #[test]
-    fn test_create_code_block_cell() {
-        let config = ConversionConfig::default();
-        let generator = DocxGenerator::new(config);
-
-        // Test single line code
-        let code = "println!(\"Hello, world!\");";
-        let result = generator.create_code_block_cell(code, &generator.config.styles.code_block);
-        assert!(result.is_ok());
 
-        // Test multi-line code with line breaks preserved
-        let multi_line_code = "fn main() {\n    println!(\"Hello\");\n}";
-        let result =
-            generator.create_code_block_cell(multi_line_code, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        // Test that empty code block creates a table with non-breaking space for visibility
+        let cell_result = generator.create_code_block_cell("", &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
 
-        // Test empty code
-        let empty_code = "";
-        let result =
-            generator.create_code_block_cell(empty_code, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        // Test with preserve_line_breaks = false
+        let mut config_no_preserve = create_test_config();
+        config_no_preserve.styles.code_block.preserve_line_breaks = false;
+        let generator_no_preserve = DocxGenerator::new(config_no_preserve);
+        let cell_result_no_preserve = generator_no_preserve
+            .create_code_block_cell("", &generator_no_preserve.config.styles.code_block);
+        assert!(cell_result_no_preserve.is_ok());
     }
 
     #[test]
-    fn test_apply_table_borders() {
-        let config = ConversionConfig::default();
+    fn test_border_application_with_various_border_width_values() {
+        let config = create_test_config();
         let generator = DocxGenerator::new(config);
 
-        // Create a simple table
+        // Create a simple table for testing borders
         let cell =
             TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("test")));
         let row = TableRow::new(vec![cell]);
         let table = Table::new(vec![row]);
 
-        // Test with positive border width
-        let result = generator.apply_table_borders(table.clone(), 1.0);
-        assert!(result.is_ok());
+        // Test with border_width = 0.0 (no borders)
+        let result_no_border = generator.apply_table_borders(table.clone(), 0.0);
+        assert!(result_no_border.is_ok());
 
-        // Test with zero border width (no borders)
-        let result = generator.apply_table_borders(table.clone(), 0.0);
-        assert!(result.is_ok());
+        // Test with border_width = 1.0 (standard border)
+        let result_standard_border = generator.apply_table_borders(table.clone(), 1.0);
+        assert!(result_standard_border.is_ok());
 
-        // Test with negative border width (no borders)
-        let result = generator.apply_table_borders(table, -1.0);
-        assert!(result.is_ok());
-    }
+        // Test with border_width = 2.5 (thick border)
+        let result_thick_border = generator.apply_table_borders(table.clone(), 2.5);
+        assert!(result_thick_border.is_ok());
 
-    #[test]
-    fn test_create_code_paragraph() {
-        let config = ConversionConfig::default();
-        let generator = DocxGenerator::new(config);
+        // Test with border_width = 0.5 (thin border)
+        let result_thin_border = generator.apply_table_borders(table.clone(), 0.5);
+        assert!(result_thin_border.is_ok());
 
-        // Test normal text
-        let text = "let x = 42;";
-        let result = generator.create_code_paragraph(text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        // Test with negative border_width (should be treated as no border)
+        let result_negative_border = generator.apply_table_borders(table.clone(), -1.0);
+        assert!(result_negative_border.is_ok());
 
-        // Test empty text
-        let empty_text = "";
-        let result =
-            generator.create_code_paragraph(empty_text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        // Test complete code block generation with different border widths
+        let mut config_no_border = create_test_config();
+        config_no_border.styles.code_block.border_width = 0.0;
+        let mut generator_no_border = DocxGenerator::new(config_no_border);
 
-        // Test text with special characters
-        let special_text = "fn test() -> Result<(), Error> { Ok(()) }";
-        let result =
-            generator.create_code_paragraph(special_text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        let mut config_thick_border = create_test_config();
+        config_thick_border.styles.code_block.border_width = 3.0;
+        let mut generator_thick_border = DocxGenerator::new(config_thick_border);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: "println!(\"test\");".to_string(),
+            processed: None,
+        });
+
+        let result_no_border_doc = generator_no_border.generate(&document);
+        assert!(result_no_border_doc.is_ok());
+
+        let result_thick_border_doc = generator_thick_border.generate(&document);
+        assert!(result_thick_border_doc.is_ok());
     }
 
     #[test]
-    fn test_code_block_font_styling_application() {
-        let mut config = ConversionConfig::default();
+    fn test_font_and_styling_application_within_table_cells() {
+        let mut config = create_test_config();
 
-        // Configure custom font styling
+        // Configure custom font styling for comprehensive testing
         config.styles.code_block.font.family = "Monaco".to_string();
         config.styles.code_block.font.size = 14.0;
         config.styles.code_block.font.bold = true;
         config.styles.code_block.font.italic = true;
-        config.styles.code_block.background_color = Some("#ffff00".to_string());
-        config.styles.code_block.line_spacing = 1.5;
-
-        let generator = DocxGenerator::new(config);
-
-        // Test that create_code_paragraph applies all font styling
-        let text = "console.log('Hello, World!');";
-        let result = generator.create_code_paragraph(text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
-
-        // Test that create_code_block_cell applies styling and background color
-        let code = "function test() {\n    return 42;\n}";
-        let result = generator.create_code_block_cell(code, &generator.config.styles.code_block);
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_code_block_background_color_application() {
-        let mut config = ConversionConfig::default();
-
-        // Test with background color
         config.styles.code_block.background_color = Some("#f0f0f0".to_string());
-        let generator = DocxGenerator::new(config);
-
-        let code = "print('test')";
-        let result = generator.create_code_block_cell(code, &generator.config.styles.code_block);
-        assert!(result.is_ok());
-
-        // Test without background color
-        let mut config_no_bg = ConversionConfig::default();
-        config_no_bg.styles.code_block.background_color = None;
-        let generator_no_bg = DocxGenerator::new(config_no_bg);
-
-        let result_no_bg =
-            generator_no_bg.create_code_block_cell(code, &generator_no_bg.config.styles.code_block);
-        assert!(result_no_bg.is_ok());
-    }
+        config.styles.code_block.line_spacing = 1.2;
+        config.styles.code_block.preserve_line_breaks = true;
 
-    #[test]
-    fn test_table_generation() {
-        let config = ConversionConfig::default();
         let mut generator = DocxGenerator::new(config);
 
+        let code_with_styling = "// This is a comment\nfn styled_function() {\n    let variable = \"value\";\n    return variable;\n}";
+
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Table {
-            headers: vec!["Name".to_string(), "Age".to_string(), "City".to_string()],
-            rows: vec![
-                vec![
-                    "Alice".to_string(),
-                    "30".to_string(),
-                    "New York".to_string(),
-                ],
-                vec!["Bob".to_string(), "25".to_string(), "London".to_string()],
-                vec!["Charlie".to_string(), "35".to_string(), "Tokyo".to_string()],
-            ],
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("rust".to_string()),
+            code: code_with_styling.to_string(),
+            processed: None,
         });
 
         let result = generator.generate(&document);
@@ -1900,60 +5134,81 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
-    }
 
-    #[test]
-    fn test_list_generation() {
-        let config = ConversionConfig::default();
-        let mut generator = DocxGenerator::new(config);
+        // Test that create_code_paragraph applies all font styling correctly
+        let paragraph_result =
+            generator.create_code_paragraph("test code", &generator.config.styles.code_block);
+        assert!(paragraph_result.is_ok());
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::List {
-            ordered: false,
-            items: vec![
-                ListItem::new(vec![InlineElement::Text("First item".to_string())]),
-                ListItem::new(vec![InlineElement::Text("Second item".to_string())]),
-                ListItem::new(vec![InlineElement::Text("Third item".to_string())]),
-            ],
-        });
+        // Test that create_code_block_cell applies styling and background color
+        let cell_result = generator
+            .create_code_block_cell(code_with_styling, &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        // Test with different font configurations
+        let mut config_different_font = create_test_config();
+        config_different_font.styles.code_block.font.family = "Consolas".to_string();
+        config_different_font.styles.code_block.font.size = 11.0;
+        config_different_font.styles.code_block.font.bold = false;
+        config_different_font.styles.code_block.font.italic = false;
+        config_different_font.styles.code_block.background_color = None;
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let generator_different = DocxGenerator::new(config_different_font);
+        let cell_result_different = generator_different
+            .create_code_block_cell("test", &generator_different.config.styles.code_block);
+        assert!(cell_result_different.is_ok());
+
+        // Test paragraph creation with different styling
+        let paragraph_result_different = generator_different
+            .create_code_paragraph("test", &generator_different.config.styles.code_block);
+        assert!(paragraph_result_different.is_ok());
     }
 
     #[test]
-    fn test_image_placeholder_generation() {
-        let config = ConversionConfig::default();
-        let mut generator = DocxGenerator::new(config);
+    fn test_code_block_table_with_background_color_variations() {
+        // Test with hex color with #
+        let mut config_hex = create_test_config();
+        config_hex.styles.code_block.background_color = Some("#ffff00".to_string());
+        let generator_hex = DocxGenerator::new(config_hex);
+
+        // Test with hex color without #
+        let mut config_hex_no_hash = create_test_config();
+        config_hex_no_hash.styles.code_block.background_color = Some("ff0000".to_string());
+        let generator_hex_no_hash = DocxGenerator::new(config_hex_no_hash);
+
+        // Test with no background color
+        let mut config_no_bg = create_test_config();
+        config_no_bg.styles.code_block.background_color = None;
+        let generator_no_bg = DocxGenerator::new(config_no_bg);
+
+        let test_code = "console.log('background test');";
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Image {
-            alt_text: "Test Image".to_string(),
-            url: "https://example.com/image.jpg".to_string(),
-            title: Some("Image Title".to_string()),
-            width: None,
-            height: None,
-        });
+        // All should succeed
+        let cell_result_hex = generator_hex
+            .create_code_block_cell(test_code, &generator_hex.config.styles.code_block);
+        assert!(cell_result_hex.is_ok());
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let cell_result_hex_no_hash = generator_hex_no_hash
+            .create_code_block_cell(test_code, &generator_hex_no_hash.config.styles.code_block);
+        assert!(cell_result_hex_no_hash.is_ok());
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let cell_result_no_bg = generator_no_bg
+            .create_code_block_cell(test_code, &generator_no_bg.config.styles.code_block);
+        assert!(cell_result_no_bg.is_ok());
     }
 
     #[test]
-    fn test_code_block_generation() {
+    fn test_code_block_table_with_special_characters_and_formatting() {
         let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
+        // Test code with special characters, unicode, and various formatting
+        let special_code = "// Special chars: <>\"'&\nfn test() -> Result<(), Box<dyn Error>> {\n    let emoji = \"🚀\";\n    let unicode = \"αβγδε\";\n    let xml = \"<tag attr=\\\"value\\\">content</tag>\";\n    Ok(())\n}";
+
         let mut document = MarkdownDocument::new();
         document.add_element(MarkdownElement::CodeBlock {
             language: Some("rust".to_string()),
-            code: "fn main() {\n    println!(\"Hello, world!\");\n}".to_string(),
+            code: special_code.to_string(),
             processed: None,
         });
 
@@ -1962,15 +5217,33 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
+
+        // Test that special characters are preserved in table cells
+        let cell_result =
+            generator.create_code_block_cell(special_code, &generator.config.styles.code_block);
+        assert!(cell_result.is_ok());
+
+        // Test with tabs and mixed whitespace
+        let whitespace_code = "function test() {\n\tif (condition) {\n\t\treturn true;\n\t}\n    return false; // 4 spaces\n}";
+        let cell_result_whitespace =
+            generator.create_code_block_cell(whitespace_code, &generator.config.styles.code_block);
+        assert!(cell_result_whitespace.is_ok());
     }
 
     #[test]
-    fn test_horizontal_rule_generation() {
+    fn test_code_block_with_markdown_formatting() {
         let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
+        // Test code block with Markdown formatting
+        let markdown_code = "This is **bold** text and *italic* text.\nAlso `inline code` and [link](http://example.com).\n![image](image.jpg)";
+
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::HorizontalRule);
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("markdown".to_string()),
+            code: markdown_code.to_string(),
+            processed: None,
+        });
 
         let result = generator.generate(&document);
         assert!(result.is_ok());
@@ -1980,352 +5253,382 @@ mod tests {
     }
 
     #[test]
-    fn test_complex_document_generation() {
+    fn test_code_block_with_bold_tags() {
         let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
-        let document = create_test_document();
+
+        // Test code block with [BOLD] tags
+        let code_with_bold_tags = "This is [BOLD]bold text[/BOLD] and normal text.\nAnother [BOLD]bold section[/BOLD] here.";
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("text".to_string()),
+            code: code_with_bold_tags.to_string(),
+            processed: None,
+        });
 
         let result = generator.generate(&document);
         assert!(result.is_ok());
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
-        // Complex document should be larger
-        assert!(docx_bytes.len() > 1000);
+
+        // Test the parsing directly
+        let elements = generator.parse_inline_formatting_with_tags("This is [BOLD]bold[/BOLD] text");
+        assert_eq!(elements.len(), 3);
+        
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is "),
+            _ => panic!("Expected Text element"),
+        }
+        
+        match &elements[1] {
+            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "bold"),
+            _ => panic!("Expected Bold element"),
+        }
+        
+        match &elements[2] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " text"),
+            _ => panic!("Expected Text element"),
+        }
     }
 
     #[test]
-    fn test_empty_document_generation() {
+    fn test_parse_inline_formatting_with_tags_multiple_bold() {
         let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
-        let document = MarkdownDocument::new();
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let generator = DocxGenerator::new(config);
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test multiple [BOLD] tags
+        let text = "[BOLD]First[/BOLD] and [BOLD]Second[/BOLD]";
+        let elements = generator.parse_inline_formatting_with_tags(text);
+        
+        assert_eq!(elements.len(), 3);
+        match &elements[0] {
+            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "First"),
+            _ => panic!("Expected Bold element"),
+        }
+        match &elements[1] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " and "),
+            _ => panic!("Expected Text element"),
+        }
+        match &elements[2] {
+            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "Second"),
+            _ => panic!("Expected Bold element"),
+        }
     }
 
     #[test]
-    fn test_inline_code_generation() {
+    fn test_parse_inline_formatting_with_tags_unclosed() {
         let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("Use ".to_string()),
-                InlineElement::Code("println!()".to_string()),
-                InlineElement::Text(" to print.".to_string()),
-            ],
-        });
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let generator = DocxGenerator::new(config);
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test unclosed [BOLD] tag
+        let text = "This is [BOLD]unclosed bold";
+        let elements = generator.parse_inline_formatting_with_tags(text);
+        
+        // Should treat [BOLD] as literal text when no closing tag, plus remaining text
+        assert_eq!(elements.len(), 3);
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is "),
+            _ => panic!("Expected Text element"),
+        }
+        match &elements[1] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "[BOLD]"),
+            _ => panic!("Expected Text element"),
+        }
+        match &elements[2] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "unclosed bold"),
+            _ => panic!("Expected Text element"),
+        }
     }
 
     #[test]
-    fn test_code_block_line_break_preservation() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        // Test code with empty lines, tabs, and long lines
-        let code_with_edge_cases = "fn main() {\n\tprintln!(\"Hello, world!\");\n\n\t// This is a very long comment that should not be wrapped automatically in the docx output to preserve the original formatting\n\tlet x = 42;\n}";
-
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: code_with_edge_cases.to_string(),
-            processed: None,
-        });
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    fn test_parse_inline_formatting_with_italic_tags() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test single [ITALIC] tag
+        let text = "This is [ITALIC]italic[/ITALIC] text";
+        let elements = generator.parse_inline_formatting_with_tags(text);
+        
+        assert_eq!(elements.len(), 3);
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is "),
+            _ => panic!("Expected Text element"),
+        }
+        match &elements[1] {
+            crate::markdown::InlineElement::Italic(t) => assert_eq!(t, "italic"),
+            _ => panic!("Expected Italic element"),
+        }
+        match &elements[2] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " text"),
+            _ => panic!("Expected Text element"),
+        }
     }
 
     #[test]
-    fn test_code_block_without_line_break_preservation() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = false;
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: "fn main() {\n    println!(\"Hello, world!\");\n}".to_string(),
-            processed: None,
-        });
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    fn test_parse_inline_formatting_with_mixed_tags() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test mixed [BOLD] and [ITALIC] tags
+        let text = "[BOLD]bold[/BOLD] and [ITALIC]italic[/ITALIC]";
+        let elements = generator.parse_inline_formatting_with_tags(text);
+        
+        assert_eq!(elements.len(), 3);
+        match &elements[0] {
+            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "bold"),
+            _ => panic!("Expected Bold element"),
+        }
+        match &elements[1] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " and "),
+            _ => panic!("Expected Text element"),
+        }
+        match &elements[2] {
+            crate::markdown::InlineElement::Italic(t) => assert_eq!(t, "italic"),
+            _ => panic!("Expected Italic element"),
+        }
     }
 
     #[test]
-    fn test_empty_code_block() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("text".to_string()),
-            code: "".to_string(),
-            processed: None,
-        });
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    fn test_parse_inline_formatting_with_nested_order() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test that tags are processed in order of appearance
+        let text = "[ITALIC]first[/ITALIC] then [BOLD]second[/BOLD]";
+        let elements = generator.parse_inline_formatting_with_tags(text);
+        
+        assert_eq!(elements.len(), 3);
+        match &elements[0] {
+            crate::markdown::InlineElement::Italic(t) => assert_eq!(t, "first"),
+            _ => panic!("Expected Italic element"),
+        }
+        match &elements[1] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " then "),
+            _ => panic!("Expected Text element"),
+        }
+        match &elements[2] {
+            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "second"),
+            _ => panic!("Expected Bold element"),
+        }
     }
 
     #[test]
-    fn test_code_block_with_tabs() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        // Code with tabs that should be converted to spaces
-        let code_with_tabs =
-            "function example() {\n\tif (true) {\n\t\tconsole.log('Hello');\n\t}\n}";
-
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("javascript".to_string()),
-            code: code_with_tabs.to_string(),
-            processed: None,
-        });
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    fn test_contains_markdown_formatting() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test various Markdown patterns
+        assert!(generator.contains_markdown_formatting("**bold**"));
+        assert!(generator.contains_markdown_formatting("*italic*"));
+        assert!(generator.contains_markdown_formatting("`code`"));
+        assert!(generator.contains_markdown_formatting("[link](url)"));
+        assert!(generator.contains_markdown_formatting("![image](url)"));
+        
+        // Test plain text
+        assert!(!generator.contains_markdown_formatting("plain text"));
+        assert!(!generator.contains_markdown_formatting("no formatting here"));
     }
 
     #[test]
-    fn test_code_block_with_empty_lines() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        // Code with empty lines that should be preserved
-        let code_with_empty_lines = "line1\n\nline3\n\n\nline6";
+    fn test_create_code_block_cell_with_markdown() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("text".to_string()),
-            code: code_with_empty_lines.to_string(),
-            processed: None,
-        });
+        // Test with Markdown formatting
+        let markdown_text = "**Bold** and *italic* text";
+        let result = generator.create_code_block_cell_with_markdown(markdown_text, &generator.config.styles.code_block, &[]);
+        assert!(result.is_ok());
 
-        let result = generator.generate(&document);
+        // Test with plain text (should fall back to regular method)
+        let plain_text = "plain text without formatting";
+        let result = generator.create_code_block_cell_with_markdown(plain_text, &generator.config.styles.code_block, &[]);
         assert!(result.is_ok());
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test with mixed content
+        let mixed_text = "Code: `function()` and **important** note";
+        let result = generator.create_code_block_cell_with_markdown(mixed_text, &generator.config.styles.code_block, &[]);
+        assert!(result.is_ok());
     }
 
     #[test]
-    fn test_code_block_with_long_lines() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
+    fn test_create_code_run_from_inline() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let mut document = MarkdownDocument::new();
-        // Very long line that should not be wrapped
-        let long_line = "This is a very long line of code that should not be automatically wrapped in the docx output to preserve the original formatting and maintain code readability";
-        let code_with_long_lines = format!("short line\n{}\nanother short line", long_line);
+        // Test different inline elements
+        let bold_inline = InlineElement::Bold("bold text".to_string());
+        let result = generator.create_code_run_from_inline(&bold_inline, &generator.config.styles.code_block);
+        assert!(result.is_ok());
 
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("text".to_string()),
-            code: code_with_long_lines,
-            processed: None,
-        });
+        let italic_inline = InlineElement::Italic("italic text".to_string());
+        let result = generator.create_code_run_from_inline(&italic_inline, &generator.config.styles.code_block);
+        assert!(result.is_ok());
 
-        let result = generator.generate(&document);
+        let code_inline = InlineElement::Code("nested code".to_string());
+        let result = generator.create_code_run_from_inline(&code_inline, &generator.config.styles.code_block);
         assert!(result.is_ok());
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let link_inline = InlineElement::Link {
+            text: "link text".to_string(),
+            url: "http://example.com".to_string(),
+            title: None,
+        };
+        let result = generator.create_code_run_from_inline(&link_inline, &generator.config.styles.code_block);
+        assert!(result.is_ok());
     }
 
     #[test]
-    fn test_code_block_spacing_configuration() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        config.styles.code_block.line_spacing = 1.5;
-        config.styles.code_block.paragraph_spacing = 12.0;
-        let mut generator = DocxGenerator::new(config);
+    fn test_extract_text_from_element() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: "fn main() {\n    println!(\"Hello\");\n}".to_string(),
-            processed: None,
-        });
+        // Test heading
+        let heading = MarkdownElement::Heading {
+            level: 1,
+            text: "Test Heading".to_string(),
+        };
+        let text = generator.extract_text_from_element(&heading);
+        assert_eq!(text, "Test Heading");
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        // Test paragraph
+        let paragraph = MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Hello ".to_string()),
+                InlineElement::Bold("world".to_string()),
+            ],
+        };
+        let text = generator.extract_text_from_element(&paragraph);
+        assert_eq!(text, "Hello world");
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test image
+        let image = MarkdownElement::Image {
+            alt_text: "Test Image".to_string(),
+            url: "image.jpg".to_string(),
+            title: None,
+            width: None,
+            height: None,
+        };
+        let text = generator.extract_text_from_element(&image);
+        assert_eq!(text, "Test Image");
     }
 
     #[test]
-    fn test_code_block_preserve_vs_no_preserve() {
-        // Test with preserve_line_breaks = true
-        let mut config_preserve = create_test_config();
-        config_preserve.styles.code_block.preserve_line_breaks = true;
-        let mut generator_preserve = DocxGenerator::new(config_preserve);
-
-        // Test with preserve_line_breaks = false
-        let mut config_no_preserve = create_test_config();
-        config_no_preserve.styles.code_block.preserve_line_breaks = false;
-        let mut generator_no_preserve = DocxGenerator::new(config_no_preserve);
-
-        let mut document = MarkdownDocument::new();
-        let code = "line1\nline2\nline3";
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("text".to_string()),
-            code: code.to_string(),
-            processed: None,
-        });
+    fn test_code_block_table_line_spacing_configuration() {
+        // Test various line spacing values
+        let mut config_single = create_test_config();
+        config_single.styles.code_block.line_spacing = 1.0;
+        let generator_single = DocxGenerator::new(config_single);
 
-        // Both should succeed but may produce different output
-        let result_preserve = generator_preserve.generate(&document);
-        let result_no_preserve = generator_no_preserve.generate(&document);
+        let mut config_double = create_test_config();
+        config_double.styles.code_block.line_spacing = 2.0;
+        let generator_double = DocxGenerator::new(config_double);
 
-        assert!(result_preserve.is_ok());
-        assert!(result_no_preserve.is_ok());
+        let mut config_custom = create_test_config();
+        config_custom.styles.code_block.line_spacing = 1.5;
+        let generator_custom = DocxGenerator::new(config_custom);
 
-        let docx_bytes_preserve = result_preserve.unwrap();
-        let docx_bytes_no_preserve = result_no_preserve.unwrap();
+        let test_code = "line1\nline2\nline3";
 
-        assert!(!docx_bytes_preserve.is_empty());
-        assert!(!docx_bytes_no_preserve.is_empty());
+        // Test paragraph creation with different line spacing
+        let paragraph_single = generator_single
+            .create_code_paragraph(test_code, &generator_single.config.styles.code_block);
+        assert!(paragraph_single.is_ok());
 
-        // The outputs should be different (though we can't easily test the exact difference)
-        // This test mainly ensures both modes work without errors
-    }
+        let paragraph_double = generator_double
+            .create_code_paragraph(test_code, &generator_double.config.styles.code_block);
+        assert!(paragraph_double.is_ok());
 
-    #[test]
-    fn test_link_generation() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+        let paragraph_custom = generator_custom
+            .create_code_paragraph(test_code, &generator_custom.config.styles.code_block);
+        assert!(paragraph_custom.is_ok());
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("Visit ".to_string()),
-                InlineElement::Link {
-                    text: "Google".to_string(),
-                    url: "https://google.com".to_string(),
-                    title: None,
-                },
-                InlineElement::Text(" for search.".to_string()),
-            ],
-        });
+        // Test complete table cell creation with different line spacing
+        let cell_single = generator_single
+            .create_code_block_cell(test_code, &generator_single.config.styles.code_block);
+        assert!(cell_single.is_ok());
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let cell_double = generator_double
+            .create_code_block_cell(test_code, &generator_double.config.styles.code_block);
+        assert!(cell_double.is_ok());
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let cell_custom = generator_custom
+            .create_code_block_cell(test_code, &generator_custom.config.styles.code_block);
+        assert!(cell_custom.is_ok());
     }
 
     #[test]
-    fn test_multiple_headings() {
+    fn test_calculate_column_widths() {
         let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
-
-        let mut document = MarkdownDocument::new();
-        for level in 1..=6 {
-            document.add_element(MarkdownElement::Heading {
-                level,
-                text: format!("Heading Level {}", level),
-            });
-        }
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
-
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
-    }
-
-    #[test]
-    fn test_config_application() {
-        let mut config = create_test_config();
-        config.document.default_font.size = 16.0;
-        config.document.default_font.family = "Arial".to_string();
+        let generator = DocxGenerator::new(config);
 
-        let generator = DocxGenerator::new(config.clone());
+        // Test with headers and data
+        let headers = vec!["Name".to_string(), "Age".to_string(), "Very Long Description".to_string()];
+        let rows = vec![
+            vec!["Alice".to_string(), "30".to_string(), "Short".to_string()],
+            vec!["Bob".to_string(), "25".to_string(), "Medium length text".to_string()],
+            vec!["Charlie".to_string(), "35".to_string(), "This is a very long description that should determine the column width".to_string()],
+        ];
 
-        // Verify config is stored
-        assert_eq!(generator.config.document.default_font.size, 16.0);
-        assert_eq!(generator.config.document.default_font.family, "Arial");
+        let widths = generator.calculate_column_widths(&headers, &rows);
+        assert_eq!(widths.len(), 3);
+        
+        // The third column should be wider due to the long content
+        assert!(widths[2] > widths[0]);
+        assert!(widths[2] > widths[1]);
+        
+        // All widths should be within reasonable bounds
+        for width in &widths {
+            assert!(*width >= 720); // Minimum width
+            assert!(*width <= 2880); // Maximum width
+        }
     }
 
     #[test]
-    fn test_heading_numbering_integration() {
-        let mut config = create_test_config();
-
-        // Configure numbering for H1 and H2
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+    fn test_estimate_text_width() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
 
-        let mut generator = DocxGenerator::new(config);
+        // Test ASCII text
+        let ascii_text = "Hello";
+        let ascii_width = generator.estimate_text_width(ascii_text);
+        assert_eq!(ascii_width, 6); // H(2) + e(1) + l(1) + l(1) + o(1) = 6
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Introduction".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 2,
-            text: "Overview".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 2,
-            text: "Details".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Conclusion".to_string(),
-        });
+        // Test wide characters
+        let wide_text = "WWW";
+        let wide_width = generator.estimate_text_width(wide_text);
+        assert_eq!(wide_width, 6); // 3 wide characters = 6 units
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        // Test narrow characters
+        let narrow_text = "iii";
+        let narrow_width = generator.estimate_text_width(narrow_text);
+        assert_eq!(narrow_width, 3); // 3 narrow characters = 3 units
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        // Test CJK characters
+        let cjk_text = "你好";
+        let cjk_width = generator.estimate_text_width(cjk_text);
+        assert_eq!(cjk_width, 4); // 2 CJK characters = 4 units
 
-        // Verify that heading processor was created
-        assert!(generator.heading_processor.is_some());
+        // Test mixed content
+        let mixed_text = "Hello你好";
+        let mixed_width = generator.estimate_text_width(mixed_text);
+        assert_eq!(mixed_width, 10); // H(2) + e(1) + l(1) + l(1) + o(1) + 你(2) + 好(2) = 10 units
     }
 
     #[test]
-    fn test_heading_without_numbering() {
-        let config = create_test_config(); // Default config has no numbering
+    fn test_table_with_auto_width() {
+        let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Plain Heading".to_string(),
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Short".to_string(), "Medium Length".to_string(), "Very Long Header Name".to_string()],
+            rows: vec![
+                vec!["A".to_string(), "Medium".to_string(), "This is a very long cell content that should make the column wider".to_string()],
+                vec!["B".to_string(), "Text".to_string(), "Short".to_string()],
+            ],
+            links: vec![],
         });
 
         let result = generator.generate(&document);
@@ -2333,111 +5636,69 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
-
-        // Verify that no heading processor was created
-        assert!(generator.heading_processor.is_none());
     }
 
     #[test]
-    fn test_mixed_numbering_scenario() {
-        let mut config = create_test_config();
-
-        // Configure numbering only for H1 and H3 (skip H2)
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-        config.styles.headings.get_mut(&3).unwrap().numbering = Some("%1.%2.%3".to_string());
-
+    fn test_table_cell_with_two_lines() {
+        let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
+        let two_lines = "first line\nsecond one".to_string();
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Chapter".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 2,
-            text: "Section".to_string(), // No numbering
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 3,
-            text: "Subsection".to_string(),
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Notes".to_string()],
+            rows: vec![vec![two_lines.clone()]],
+            links: vec![],
         });
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
-
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains("first line"));
+        assert!(xml.contains("second one"));
+        assert!(xml.contains("<w:br w:type=\"textWrapping\""));
+
+        // Width follows the longest line, not the whole text
+        let split = generator.calculate_column_widths(&[], &[vec![two_lines]]);
+        let single = generator.calculate_column_widths(&[], &[vec!["first line".to_string()]]);
+        let joined = generator.calculate_column_widths(&[], &[vec!["first linesecond one".to_string()]]);
+        assert_eq!(split, single);
+        assert!(joined[0] > split[0]);
     }
 
     #[test]
-    fn test_config_update_with_numbering() {
+    fn test_empty_table_handling() {
         let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
-
-        // Initially no numbering
-        assert!(generator.heading_processor.is_none());
-
-        // Update config to include numbering
-        let mut new_config = create_test_config();
-        new_config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-
-        generator.update_config(new_config);
-
-        // Now should have heading processor
-        assert!(generator.heading_processor.is_some());
-
-        // Update config to remove numbering
-        let no_numbering_config = create_test_config();
-        generator.update_config(no_numbering_config);
-
-        // Should no longer have heading processor
-        assert!(generator.heading_processor.is_none());
-    }
-
-    #[test]
-    fn test_numbering_state_reset_between_documents() {
-        let mut config = create_test_config();
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-
-        let mut generator = DocxGenerator::new(config);
-
-        // Generate first document
-        let mut document1 = MarkdownDocument::new();
-        document1.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "First Document".to_string(),
-        });
-
-        let result1 = generator.generate(&document1);
-        assert!(result1.is_ok());
+        let generator = DocxGenerator::new(config);
 
-        // Generate second document - numbering should reset
-        let mut document2 = MarkdownDocument::new();
-        document2.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Second Document".to_string(),
-        });
+        // Test empty headers and rows
+        let widths = generator.calculate_column_widths(&[], &[]);
+        assert!(widths.is_empty());
 
-        let result2 = generator.generate(&document2);
-        assert!(result2.is_ok());
+        // Test empty headers with data
+        let rows = vec![vec!["data".to_string()]];
+        let widths = generator.calculate_column_widths(&[], &rows);
+        assert_eq!(widths.len(), 1);
 
-        // Both should succeed and numbering should start fresh for each document
-        assert!(!result1.unwrap().is_empty());
-        assert!(!result2.unwrap().is_empty());
+        // Test headers with empty rows
+        let headers = vec!["Header".to_string()];
+        let widths = generator.calculate_column_widths(&headers, &[]);
+        assert_eq!(widths.len(), 1);
     }
 
     #[test]
-    fn test_numbering_error_handling() {
+    fn test_table_with_borders_and_auto_width() {
         let mut config = create_test_config();
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-
+        config.styles.table.border_width = 1.5;
         let mut generator = DocxGenerator::new(config);
 
-        // Test with invalid heading level (should be handled gracefully)
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Valid Heading".to_string(),
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Name".to_string(), "Description".to_string()],
+            rows: vec![
+                vec!["Item 1".to_string(), "Short description".to_string()],
+                vec!["Item 2".to_string(), "This is a much longer description that should affect the column width".to_string()],
+            ],
+            links: vec![],
         });
 
         let result = generator.generate(&document);
@@ -2448,20 +5709,16 @@ mod tests {
     }
 
     #[test]
-    fn test_numbering_with_empty_text() {
-        let mut config = create_test_config();
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-
+    fn test_paragraph_with_hard_breaks() {
+        let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
+        // Test paragraph with hard breaks (from <br /> tags)
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "".to_string(), // Empty text
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "   ".to_string(), // Whitespace only
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("First line\nSecond line\nThird line".to_string()),
+            ],
         });
 
         let result = generator.generate(&document);
@@ -2472,46 +5729,40 @@ mod tests {
     }
 
     #[test]
-    fn test_complex_numbering_scenario() {
-        let mut config = create_test_config();
-
-        // Configure numbering for multiple levels
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
-        config.styles.headings.get_mut(&3).unwrap().numbering = Some("%1.%2.%3".to_string());
-
+    fn test_paragraph_with_hard_breaks_and_formatting() {
+        let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
+        // Test paragraph with hard breaks and formatting
         let mut document = MarkdownDocument::new();
-
-        // Create a complex document structure
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Chapter 1".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 2,
-            text: "Section 1.1".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 3,
-            text: "Subsection 1.1.1".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 3,
-            text: "Subsection 1.1.2".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 2,
-            text: "Section 1.2".to_string(),
-        });
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Chapter 2".to_string(),
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("First line with ".to_string()),
+                InlineElement::Bold("bold".to_string()),
+                InlineElement::Text("\nSecond line with ".to_string()),
+                InlineElement::Italic("italic".to_string()),
+                InlineElement::Text("\nThird line".to_string()),
+            ],
         });
-        document.add_element(MarkdownElement::Heading {
-            level: 2,
-            text: "Section 2.1".to_string(),
+
+        let result = generator.generate(&document);
+        assert!(result.is_ok());
+
+        let docx_bytes = result.unwrap();
+        assert!(!docx_bytes.is_empty());
+    }
+
+    #[test]
+    fn test_paragraph_with_multiple_consecutive_hard_breaks() {
+        let config = create_test_config();
+        let mut generator = DocxGenerator::new(config);
+
+        // Test paragraph with multiple consecutive hard breaks
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("First line\n\nThird line (with empty line in between)".to_string()),
+            ],
         });
 
         let result = generator.generate(&document);
@@ -2519,24 +5770,19 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
-
-        // Verify that heading processor was created and is working
-        assert!(generator.heading_processor.is_some());
     }
 
     #[test]
-    fn test_numbering_graceful_degradation() {
-        let mut config = create_test_config();
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-
+    fn test_paragraph_without_hard_breaks() {
+        let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
-        // Test that the generator handles potential numbering errors gracefully
-        // by ensuring the document generation still succeeds even if numbering fails
+        // Test normal paragraph without hard breaks
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Test Heading".to_string(),
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("This is a normal paragraph without any hard breaks.".to_string()),
+            ],
         });
 
         let result = generator.generate(&document);
@@ -2544,39 +5790,109 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
+    }
 
-        // Verify that the heading processor exists and is functional
-        assert!(generator.heading_processor.is_some());
+    #[test]
+    fn test_parse_inline_formatting_plain_text() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
+        
+        let text = "# This is a comment";
+        let elements = generator.parse_inline_formatting(text);
+        
+        assert_eq!(elements.len(), 1);
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => {
+                assert_eq!(t, "# This is a comment");
+            }
+            _ => panic!("Expected Text element"),
+        }
+    }
 
-        // Test that the processor can handle multiple headings without issues
-        let mut document2 = MarkdownDocument::new();
-        for i in 1..=5 {
-            document2.add_element(MarkdownElement::Heading {
-                level: 1,
-                text: format!("Heading {}", i),
-            });
+    #[test]
+    fn test_parse_inline_formatting_bold() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
+        
+        let text = "This is **bold** text";
+        let elements = generator.parse_inline_formatting(text);
+        
+        // Since Markdown is disabled by default, it returns the text as-is
+        assert_eq!(elements.len(), 1);
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is **bold** text"),
+            _ => panic!("Expected Text element"),
         }
+    }
 
-        let result2 = generator.generate(&document2);
-        assert!(result2.is_ok());
-        assert!(!result2.unwrap().is_empty());
+    #[test]
+    fn test_parse_inline_formatting_italic() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
+        
+        let text = "This is *italic* text";
+        let elements = generator.parse_inline_formatting(text);
+        
+        // Since Markdown is disabled by default, it returns the text as-is
+        assert_eq!(elements.len(), 1);
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is *italic* text"),
+            _ => panic!("Expected Text element"),
+        }
     }
 
     #[test]
-    fn test_numbering_style_consistency() {
-        let mut config = create_test_config();
+    fn test_parse_inline_formatting_mixed() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
+        
+        let text = "# Comment with **bold** and *italic*";
+        let elements = generator.parse_inline_formatting(text);
+        
+        // Since Markdown is disabled by default, it returns the text as-is
+        assert_eq!(elements.len(), 1);
+        match &elements[0] {
+            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "# Comment with **bold** and *italic*"),
+            _ => panic!("Expected Text element"),
+        }
+    }
 
-        // Configure numbering and ensure styles are applied consistently
-        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
-        config.styles.headings.get_mut(&1).unwrap().font.bold = true;
-        config.styles.headings.get_mut(&1).unwrap().font.size = 18.0;
+    #[test]
+    fn test_parse_inline_formatting_unclosed_markers() {
+        let config = create_test_config();
+        let generator = DocxGenerator::new(config);
+        
+        // Unclosed bold marker - should be treated as literal text
+        let text = "This is **bold without closing";
+        let elements = generator.parse_inline_formatting(text);
+        
+        // The parser will try to find closing ** and fail, so it treats ** as literal
+        // Result: "This is ", "**bold without closing"
+        assert!(elements.len() >= 1);
+        
+        // Check that the text contains the original content
+        let combined_text: String = elements.iter().map(|e| match e {
+            crate::markdown::InlineElement::Text(t) => t.clone(),
+            crate::markdown::InlineElement::Bold(t) => format!("**{}**", t),
+            crate::markdown::InlineElement::Italic(t) => format!("*{}*", t),
+            _ => String::new(),
+        }).collect();
+        
+        assert!(combined_text.contains("**bold without closing") || combined_text == text);
+    }
 
+    #[test]
+    fn test_code_block_with_hash_comments() {
+        let config = create_test_config();
         let mut generator = DocxGenerator::new(config);
 
+        let code_with_hash = "# This is a comment\necho \"Hello\"\n# Another comment\ncargo build";
+
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Heading {
-            level: 1,
-            text: "Styled Heading".to_string(),
+        document.add_element(MarkdownElement::CodeBlock {
+            language: Some("bash".to_string()),
+            code: code_with_hash.to_string(),
+            processed: None,
         });
 
         let result = generator.generate(&document);
@@ -2584,1023 +5900,2346 @@ mod tests {
 
         let docx_bytes = result.unwrap();
         assert!(!docx_bytes.is_empty());
-
-        // The test verifies that the docx generation succeeds with styled numbered headings
-        // The actual style application is verified by the docx library integration
+        
+        // Verify that the code block cell can be created with hash comments
+        let cell_result = generator.create_code_block_cell_with_markdown(
+            code_with_hash,
+            &generator.config.styles.code_block,
+            &[],
+        );
+        assert!(cell_result.is_ok());
     }
 
     #[test]
-    fn test_line_break_preservation_with_empty_lines() {
+    fn test_text_watermark_in_header() {
         let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-
-        let generator = DocxGenerator::new(config);
+        config.document.watermark = Some(crate::config::WatermarkConfig::text("DRAFT"));
+        let mut generator = DocxGenerator::new(config);
 
-        // Test code with empty lines and tabs
-        let code_with_empty_lines =
-            "function test() {\n\n    console.log('hello');\n\n    return true;\n}";
+        let docx_bytes = generator.generate(&create_test_document()).unwrap();
 
-        let cell_result = generator
-            .create_code_block_cell(code_with_empty_lines, &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
+        let header = crate::test_utils::read_docx_part(&docx_bytes, "word/header1.xml")
+            .expect("watermark header part should exist");
+        assert!(header.contains("<v:textpath"));
+        assert!(header.contains("string=\"DRAFT\""));
+        assert!(header.contains("rotation:315"));
+        assert!(!header.contains("__MD2DOCX_WATERMARK__"));
 
-        // Test that empty code block is handled
-        let empty_code_result =
-            generator.create_code_block_cell("", &generator.config.styles.code_block);
-        assert!(empty_code_result.is_ok());
+        let document = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(document.contains("w:headerReference"));
     }
 
     #[test]
-    fn test_tab_conversion_in_code_blocks() {
+    fn test_image_watermark_in_header() {
         let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-
-        let generator = DocxGenerator::new(config);
+        let icon = concat!(env!("CARGO_MANIFEST_DIR"), "/default-qrcode.png");
+        config.document.watermark = Some(crate::config::WatermarkConfig::image(icon));
+        let mut generator = DocxGenerator::new(config);
 
-        // Test code with tabs that should be converted to spaces
-        let code_with_tabs = "function test() {\n\tconsole.log('hello');\n\t\treturn true;\n}";
+        let docx_bytes = generator.generate(&create_test_document()).unwrap();
 
-        let cell_result =
-            generator.create_code_block_cell(code_with_tabs, &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
+        let header = crate::test_utils::read_docx_part(&docx_bytes, "word/header1.xml")
+            .expect("watermark header part should exist");
+        assert!(header.contains("<wp:anchor"));
+        assert!(header.contains("behindDoc=\"1\""));
+        let rels = crate::test_utils::read_docx_part(&docx_bytes, "word/_rels/header1.xml.rels")
+            .expect("header relationships should exist");
+        assert!(rels.contains("relationships/image"));
+    }
 
-        // Test without line break preservation (single paragraph)
-        let mut config_no_preserve = create_test_config();
-        config_no_preserve.styles.code_block.preserve_line_breaks = false;
-        let generator_no_preserve = DocxGenerator::new(config_no_preserve);
+    #[test]
+    fn test_no_watermark_by_default() {
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&create_test_document()).unwrap();
 
-        let cell_result_no_preserve = generator_no_preserve.create_code_block_cell(
-            code_with_tabs,
-            &generator_no_preserve.config.styles.code_block,
-        );
-        assert!(cell_result_no_preserve.is_ok());
+        assert!(crate::test_utils::read_docx_part(&docx_bytes, "word/header1.xml").is_none());
     }
 
     #[test]
-    fn test_multi_line_code_block_table_generation() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
+    fn test_emoji_shortcodes_converted_when_enabled() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Launch :rocket: and :nope: ".to_string()),
+                InlineElement::Code(":tada:".to_string()),
+            ],
+        });
 
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(":rocket:"), "shortcodes are off by default");
+
+        let mut config = create_test_config();
+        config.elements.emoji.enabled = true;
         let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains("Launch 🚀 and :nope: "));
+        assert!(xml.contains(":tada:"));
+        assert!(!xml.contains("🎉"));
+    }
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("python".to_string()),
-            code: "def hello_world():\n    print('Hello, World!')\n\n    return 'success'\n\nif __name__ == '__main__':\n    hello_world()".to_string(),
-            processed: None,
-        });
+    #[test]
+    fn test_inline_checkboxes_converted_when_enabled() {
+        let markdown = "Done [x], todo [ ] and [x](https://example.com).\n\n- [x] task item";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains("Done [x], todo [ ] and "), "checkboxes are off by default");
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let mut config = create_test_config();
+        config.elements.checkboxes.enabled = true;
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains("Done ☒, todo ☐ and "));
+        assert!(xml.contains("<w:u w:val=\"single\" /><w:rFonts w:ascii=\"Times New Roman\" w:eastAsia=\"Times New Roman\" /></w:rPr><w:t xml:space=\"preserve\">x</w:t>"), "link is kept");
+        assert_eq!(xml.matches('☒').count(), 1, "task list markers are not inline checkboxes");
     }
 
     #[test]
-    fn test_code_block_non_breaking_space_preservation() {
+    fn test_task_list_items_render_boxes() {
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("- [x] Write docs\n- [ ] Ship it\n- Regular item\n  - [ ] Nested task\n")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let paragraph = |text: &str| {
+            let at = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            xml[xml[..at].rfind("<w:p ").unwrap()..at].to_string()
+        };
+        assert!(paragraph("Write docs").contains(">☑ </w:t>"));
+        assert!(paragraph("Ship it").contains(">☐ </w:t>"));
+        assert!(paragraph("Nested task").contains(">☐ </w:t>"));
+        assert!(!paragraph("Regular item").contains('☐') && !paragraph("Regular item").contains('☑'), "non-task items have no box");
+        assert!(!xml.contains("[x]") && !xml.contains("[ ]"));
+        assert_eq!(list_level(&xml, "Regular item"), list_level(&xml, "Write docs"));
+    }
+
+    #[test]
+    fn test_completed_tasks_struck_through() {
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("- [x] Write **docs**\n- [ ] Ship it\n")
+            .unwrap();
         let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
+        config.elements.list.tasks.strike_completed = true;
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        let generator = DocxGenerator::new(config);
+        let run = |text: &str| {
+            let at = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            xml[xml[..at].rfind("<w:r>").unwrap()..at].to_string()
+        };
+        assert!(run("Write ").contains("<w:strike />"));
+        assert!(run("docs").contains("<w:strike />"));
+        assert!(!run("☑ ").contains("<w:strike"), "the box is not struck through");
+        assert!(!run("Ship it").contains("<w:strike"));
+        assert!(!run("☐ ").contains("<w:strike"));
+
+        // Strikethrough instead of the box
+        config.elements.list.tasks.checkbox = false;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains('☑') && !xml.contains('☐'));
+        let at = xml.find(">Write </w:t>").unwrap();
+        assert!(xml[xml[..at].rfind("<w:r>").unwrap()..at].contains("<w:strike />"));
+    }
 
-        // Test that empty lines are preserved using non-breaking spaces
-        let code_with_multiple_empty_lines = "line1\n\n\nline4\n\n\nline7";
+    #[test]
+    fn test_table_header_row_semantics() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Name".to_string(), "Value".to_string()],
+            rows: vec![
+                vec!["a".to_string(), "1".to_string()],
+                vec!["b".to_string(), "2".to_string()],
+            ],
+            links: vec![],
+        });
 
-        let cell_result = generator.create_code_block_cell(
-            code_with_multiple_empty_lines,
-            &generator.config.styles.code_block,
-        );
-        assert!(cell_result.is_ok());
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        // Test edge case: only empty lines
-        let only_empty_lines = "\n\n\n";
-        let empty_lines_result =
-            generator.create_code_block_cell(only_empty_lines, &generator.config.styles.code_block);
-        assert!(empty_lines_result.is_ok());
-    }
+        assert_eq!(xml.matches("<w:tblHeader />").count(), 1);
+        let header_pos = xml.find("<w:tblHeader />").unwrap();
+        let first_row = xml.find("<w:tr>").unwrap();
+        let second_row = xml[first_row + 1..].find("<w:tr>").unwrap() + first_row + 1;
+        assert!(first_row < header_pos && header_pos < second_row);
+        assert!(xml.contains("<w:tblStyle w:val=\"MarkdownTable\" />"));
 
-    // Task 9: Unit tests for table-based code block generation
+        let styles = crate::test_utils::read_docx_part(&docx_bytes, "word/styles.xml").unwrap();
+        assert!(styles.contains("w:styleId=\"MarkdownTable\""));
+    }
 
     #[test]
-    fn test_single_line_code_block_table_creation() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
-
+    fn test_list_items_use_list_paragraph_style() {
+        let mut item = ListItem::new(vec![InlineElement::Text("Parent".to_string())]);
+        item.add_sub_item(ListItem::new(vec![InlineElement::Text("Child".to_string())]));
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: "let x = 42;".to_string(),
-            processed: None,
+        document.add_element(MarkdownElement::List {
+            ordered: false,
+            items: vec![item],
         });
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert_eq!(xml.matches("<w:pStyle w:val=\"ListParagraph\" />").count(), 2);
+        // Items are numbered paragraphs, without bullet text of their own
+        assert_eq!(list_level(&xml, "Parent"), Some((FIRST_LIST_NUMBERING, 0)));
+        assert_eq!(list_level(&xml, "Child"), Some((FIRST_LIST_NUMBERING + 1, 1)));
+        assert!(!xml.contains('•'));
+
+        // Each level is indented by the configured list indent (20pt = 400
+        // twips), with the bullet hanging
+        let numbering = crate::test_utils::read_docx_part(&docx_bytes, "word/numbering.xml").unwrap();
+        let bullets = abstract_numbering(&numbering, BULLET_ABSTRACT_NUMBERING);
+        let level = |level: usize| bullets.split("</w:lvl>").nth(level).unwrap().to_string();
+        assert!(level(0).contains("<w:numFmt w:val=\"bullet\" />") && level(0).contains("<w:lvlText w:val=\"•\" />"));
+        assert!(level(1).contains("<w:lvlText w:val=\"◦\" />"));
+        assert!(level(1).contains("w:left=\"800\"") && level(1).contains("w:hanging=\"400\""));
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    /// Numbering id and level of the list paragraph holding `text`
+    fn list_level(xml: &str, text: &str) -> Option<(usize, usize)> {
+        let end = xml.find(&format!(">{}</w:t>", text))?;
+        let paragraph = &xml[xml[..end].rfind("<w:p ")?..end];
+        let value = |tag: &str| {
+            let start = paragraph.find(&format!("<{} w:val=\"", tag))? + tag.len() + 9;
+            paragraph[start..start + paragraph[start..].find('"')?].parse().ok()
+        };
+        Some((value("w:numId")?, value("w:ilvl")?))
+    }
 
-        // Test that the code block is rendered as a table
-        let cell_result =
-            generator.create_code_block_cell("let x = 42;", &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
+    /// An abstract numbering of the numbering part
+    fn abstract_numbering(numbering: &str, id: usize) -> String {
+        let start = numbering.find(&format!("<w:abstractNum w:abstractNumId=\"{}\"", id)).unwrap();
+        let end = start + numbering[start..].find("</w:abstractNum>").unwrap();
+        numbering[start..end].to_string()
     }
 
     #[test]
-    fn test_multi_line_code_block_with_line_break_preservation() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
-
-        let multi_line_code = "fn main() {\n    println!(\"Hello, world!\");\n    let x = 42;\n    println!(\"x = {}\", x);\n}";
-
+    fn test_ordered_list_styles_cycle_by_depth() {
+        let item = |text: &str, sub_items: Vec<ListItem>| {
+            let mut item = ListItem::new(vec![InlineElement::Text(text.to_string())]);
+            item.ordered = true;
+            sub_items.into_iter().for_each(|sub_item| item.add_sub_item(sub_item));
+            item
+        };
+        let fourth = vec![item("Fourth", vec![])];
+        let third = vec![item("Third one", vec![]), item("Third two", fourth)];
+        let second = vec![item("Second one", vec![]), item("Second two", third)];
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: multi_line_code.to_string(),
-            processed: None,
+        document.add_element(MarkdownElement::List {
+            ordered: true,
+            items: vec![item("First", vec![]), item("First two", second)],
         });
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let mut config = create_test_config();
+        config.elements.list.ordered_styles = vec![
+            crate::config::ListNumberStyle::Decimal,
+            crate::config::ListNumberStyle::LowerAlpha,
+            crate::config::ListNumberStyle::LowerRoman,
+        ];
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert_eq!(list_level(&xml, "First two"), Some((FIRST_LIST_NUMBERING, 0)));
+        assert_eq!(list_level(&xml, "Second two"), Some((FIRST_LIST_NUMBERING + 1, 1)));
+        assert_eq!(list_level(&xml, "Third two"), Some((FIRST_LIST_NUMBERING + 2, 2)));
+        assert_eq!(list_level(&xml, "Fourth"), Some((FIRST_LIST_NUMBERING + 3, 3)));
+
+        let numbering = crate::test_utils::read_docx_part(&docx_bytes, "word/numbering.xml").unwrap();
+        let numbers = abstract_numbering(&numbering, ORDERED_ABSTRACT_NUMBERING);
+        let level = |level: usize| numbers.split("</w:lvl>").nth(level).unwrap().to_string();
+        assert!(level(0).contains("<w:numFmt w:val=\"decimal\" />") && level(0).contains("<w:lvlText w:val=\"%1.\" />"));
+        assert!(level(1).contains("<w:numFmt w:val=\"lowerLetter\" />") && level(1).contains("<w:lvlText w:val=\"%2.\" />"));
+        assert!(level(2).contains("<w:numFmt w:val=\"lowerRoman\" />"));
+        // Deeper levels start over with the first style
+        assert!(level(3).contains("<w:numFmt w:val=\"decimal\" />") && level(3).contains("<w:lvlText w:val=\"%4.\" />"));
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    #[test]
+    fn test_each_list_numbered_from_one() {
+        let markdown = "1. One\n2. Two\n\nBetween.\n\n1. Again\n\n- Bullet\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let first = FIRST_LIST_NUMBERING;
+        assert_eq!(list_level(&xml, "Two"), Some((first, 0)));
+        assert_eq!(list_level(&xml, "Again"), Some((first + 1, 0)));
+        assert_eq!(list_level(&xml, "Bullet"), Some((first + 2, 0)));
+        assert!(!xml.contains(">1. </w:t>"));
+
+        let numbering = crate::test_utils::read_docx_part(&docx_bytes, "word/numbering.xml").unwrap();
+        let num = |id: usize| {
+            let start = numbering.find(&format!("<w:num w:numId=\"{}\">", id)).unwrap();
+            numbering[start..start + numbering[start..].find("</w:num>").unwrap()].to_string()
+        };
+        assert!(num(first + 1).contains(&format!("<w:abstractNumId w:val=\"{}\" />", ORDERED_ABSTRACT_NUMBERING)));
+        assert!(num(first + 1).contains("<w:startOverride w:val=\"1\" />"));
+        assert!(num(first + 2).contains(&format!("<w:abstractNumId w:val=\"{}\" />", BULLET_ABSTRACT_NUMBERING)));
+    }
 
-        // Test that each line is preserved as separate paragraphs within the table cell
-        let cell_result =
-            generator.create_code_block_cell(multi_line_code, &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
+    #[test]
+    fn test_nested_lists_numbered_by_their_own_kind() {
+        let markdown = "1. Step\n   - note\n   - more\n   1. sub-step\n2. Next\n\n- Point\n  1. first\n  2. second\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let numbering = crate::test_utils::read_docx_part(&docx_bytes, "word/numbering.xml").unwrap();
+        let abstract_of = |text: &str| {
+            let (id, level) = list_level(&xml, text).unwrap();
+            let start = numbering.find(&format!("<w:num w:numId=\"{}\">", id)).unwrap();
+            let num = &numbering[start..start + numbering[start..].find("</w:num>").unwrap()];
+            let abstract_id = if num.contains(&format!("<w:abstractNumId w:val=\"{}\" />", ORDERED_ABSTRACT_NUMBERING)) {
+                ORDERED_ABSTRACT_NUMBERING
+            } else {
+                BULLET_ABSTRACT_NUMBERING
+            };
+            (id, level, abstract_id)
+        };
 
-        // Test with preserve_line_breaks = false for comparison
-        let mut config_no_preserve = create_test_config();
-        config_no_preserve.styles.code_block.preserve_line_breaks = false;
-        let generator_no_preserve = DocxGenerator::new(config_no_preserve);
-        let cell_result_no_preserve = generator_no_preserve.create_code_block_cell(
-            multi_line_code,
-            &generator_no_preserve.config.styles.code_block,
-        );
-        assert!(cell_result_no_preserve.is_ok());
+        // Bullets under a numbered item
+        let (step, _, kind) = abstract_of("Step");
+        assert_eq!(kind, ORDERED_ABSTRACT_NUMBERING);
+        let (note, level, kind) = abstract_of("note");
+        assert_eq!((level, kind), (1, BULLET_ABSTRACT_NUMBERING));
+        assert_ne!(note, step);
+        assert_eq!(abstract_of("more").0, note);
+        // A numbered list after the bullets of the same item starts its own numbering
+        let (sub_step, level, kind) = abstract_of("sub-step");
+        assert_eq!((level, kind), (1, ORDERED_ABSTRACT_NUMBERING));
+        assert!(sub_step != step && sub_step != note);
+        assert_eq!(abstract_of("Next").0, step);
+
+        // Numbers under a bullet
+        assert_eq!(abstract_of("Point").2, BULLET_ABSTRACT_NUMBERING);
+        let (first, level, kind) = abstract_of("first");
+        assert_eq!((level, kind), (1, ORDERED_ABSTRACT_NUMBERING));
+        assert_eq!(abstract_of("second").0, first);
     }
 
     #[test]
-    fn test_empty_code_block_table_handling() {
-        let mut config = create_test_config();
-        config.styles.code_block.preserve_line_breaks = true;
-        let mut generator = DocxGenerator::new(config);
+    fn test_core_title_from_first_h1() {
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&create_test_document()).unwrap();
+        let core = crate::test_utils::read_docx_part(&docx_bytes, "docProps/core.xml").unwrap();
+        assert!(core.contains("<dc:title>Main Title</dc:title>"));
 
+        // Without an H1 no title is set
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("text".to_string()),
-            code: "".to_string(),
-            processed: None,
+        document.add_element(MarkdownElement::Heading {
+            level: 2,
+            text: "Section".to_string(),
         });
-
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
-
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
-
-        // Test that empty code block creates a table with non-breaking space for visibility
-        let cell_result = generator.create_code_block_cell("", &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
-
-        // Test with preserve_line_breaks = false
-        let mut config_no_preserve = create_test_config();
-        config_no_preserve.styles.code_block.preserve_line_breaks = false;
-        let generator_no_preserve = DocxGenerator::new(config_no_preserve);
-        let cell_result_no_preserve = generator_no_preserve
-            .create_code_block_cell("", &generator_no_preserve.config.styles.code_block);
-        assert!(cell_result_no_preserve.is_ok());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let core = crate::test_utils::read_docx_part(&docx_bytes, "docProps/core.xml").unwrap();
+        assert!(!core.contains("<dc:title>"));
+        assert!(!core.contains("Section"));
     }
 
     #[test]
-    fn test_border_application_with_various_border_width_values() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_classed_paragraph_uses_configured_style() {
+        let mut config = create_test_config();
+        config.styles.classes.insert(
+            "callout".to_string(),
+            crate::config::ClassStyle {
+                style_name: Some("Callout".to_string()),
+                italic: Some(true),
+                alignment: Some("center".to_string()),
+                indent: Some(36.0),
+                ..Default::default()
+            },
+        );
+        let mut document = MarkdownDocument::new();
+        document.add_classed_paragraph(
+            vec![InlineElement::Text("Important".to_string())],
+            vec!["callout".to_string(), "missing".to_string()],
+        );
 
-        // Create a simple table for testing borders
-        let cell =
-            TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text("test")));
-        let row = TableRow::new(vec![cell]);
-        let table = Table::new(vec![row]);
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        // Test with border_width = 0.0 (no borders)
-        let result_no_border = generator.apply_table_borders(table.clone(), 0.0);
-        assert!(result_no_border.is_ok());
+        assert!(xml.contains("<w:pStyle w:val=\"Callout\" />"));
+        assert!(xml.contains("<w:jc w:val=\"center\" />"));
+        assert!(xml.contains("w:left=\"720\""));
+        assert!(xml.contains("<w:i />"));
+    }
 
-        // Test with border_width = 1.0 (standard border)
-        let result_standard_border = generator.apply_table_borders(table.clone(), 1.0);
-        assert!(result_standard_border.is_ok());
+    #[test]
+    fn test_classed_span_gets_run_formatting() {
+        let mut config = create_test_config();
+        config.styles.classes.insert(
+            "badge".to_string(),
+            crate::config::ClassStyle {
+                bold: Some(true),
+                color: Some("#AA0000".to_string()),
+                background_color: Some("#FFEEEE".to_string()),
+                ..Default::default()
+            },
+        );
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Status: ".to_string()),
+                InlineElement::Span {
+                    text: "done".to_string(),
+                    classes: vec!["badge".to_string()],
+                    lang: None,
+                },
+            ],
+        });
 
-        // Test with border_width = 2.5 (thick border)
-        let result_thick_border = generator.apply_table_borders(table.clone(), 2.5);
-        assert!(result_thick_border.is_ok());
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        // Test with border_width = 0.5 (thin border)
-        let result_thin_border = generator.apply_table_borders(table.clone(), 0.5);
-        assert!(result_thin_border.is_ok());
+        assert!(xml.contains("<w:color w:val=\"AA0000\" />"));
+        assert!(xml.contains("w:fill=\"FFEEEE\""));
+        assert_eq!(xml.matches("<w:b />").count(), 1);
+    }
 
-        // Test with negative border_width (should be treated as no border)
-        let result_negative_border = generator.apply_table_borders(table.clone(), -1.0);
-        assert!(result_negative_border.is_ok());
+    #[test]
+    fn test_lang_span_tags_run_language() {
+        let mut config = create_test_config();
+        config.styles.classes.insert(
+            "quote".to_string(),
+            crate::config::ClassStyle {
+                style_name: Some("Quote".to_string()),
+                ..Default::default()
+            },
+        );
+        let parser = crate::markdown::MarkdownParser::new();
+        let mut document = parser
+            .parse("He said <span lang=\"fr-FR\">bonjour</span> and left.")
+            .unwrap();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![InlineElement::Span {
+                text: "guten Tag".to_string(),
+                classes: vec!["quote".to_string()],
+                lang: Some("de".to_string()),
+            }],
+        });
 
-        // Test complete code block generation with different border widths
-        let mut config_no_border = create_test_config();
-        config_no_border.styles.code_block.border_width = 0.0;
-        let mut generator_no_border = DocxGenerator::new(config_no_border);
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        let mut config_thick_border = create_test_config();
-        config_thick_border.styles.code_block.border_width = 3.0;
-        let mut generator_thick_border = DocxGenerator::new(config_thick_border);
+        let run = |text: &str| {
+            let end = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            xml[xml[..end].rfind("<w:r>").unwrap()..end].to_string()
+        };
+        assert!(run("bonjour").contains("<w:lang w:val=\"fr-FR\" />"));
+        assert!(!run("He said ").contains("<w:lang"));
+        assert!(run("guten Tag").contains("<w:rStyle w:val=\"Quote\" />"));
+        assert!(run("guten Tag").contains("<w:lang w:val=\"de\" />"));
+        assert!(!xml.contains("_Lang:"));
+    }
 
+    #[test]
+    fn test_highlight_uses_word_palette_and_backgrounds_use_shading() {
+        let mut config = create_test_config();
+        config.styles.code_block.background_color = Some("#F0F0F0".to_string());
+        config.styles.classes.insert(
+            "marked".to_string(),
+            crate::config::ClassStyle {
+                highlight: Some("#FFFF66".to_string()),
+                ..Default::default()
+            },
+        );
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: "println!(\"test\");".to_string(),
-            processed: None,
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Span {
+                    text: "look".to_string(),
+                    classes: vec!["marked".to_string()],
+                    lang: None,
+                },
+                InlineElement::Code("x".to_string()),
+            ],
         });
 
-        let result_no_border_doc = generator_no_border.generate(&document);
-        assert!(result_no_border_doc.is_ok());
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        let result_thick_border_doc = generator_thick_border.generate(&document);
-        assert!(result_thick_border_doc.is_ok());
+        assert!(xml.contains("<w:highlight w:val=\"yellow\" />"));
+        assert!(xml.contains("w:fill=\"F0F0F0\""));
+        assert!(!xml.contains("<w:highlight w:val=\"F0F0F0\" />"));
     }
 
     #[test]
-    fn test_font_and_styling_application_within_table_cells() {
-        let mut config = create_test_config();
-
-        // Configure custom font styling for comprehensive testing
-        config.styles.code_block.font.family = "Monaco".to_string();
-        config.styles.code_block.font.size = 14.0;
-        config.styles.code_block.font.bold = true;
-        config.styles.code_block.font.italic = true;
-        config.styles.code_block.background_color = Some("#f0f0f0".to_string());
-        config.styles.code_block.line_spacing = 1.2;
-        config.styles.code_block.preserve_line_breaks = true;
+    fn test_pathological_list_nesting_is_clamped() {
+        let mut item = ListItem::new(vec![InlineElement::Text("deepest".to_string())]);
+        for level in (0..200).rev() {
+            let mut parent = ListItem::new(vec![InlineElement::Text(format!("level {}", level))]);
+            parent.add_sub_item(item);
+            item = parent;
+        }
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::List {
+            ordered: false,
+            items: vec![item],
+        });
 
+        let mut config = create_test_config();
+        config.elements.max_nesting_depth = 4;
         let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert_eq!(xml.matches("<w:pStyle w:val=\"ListParagraph\" />").count(), 201);
+        assert!(xml.contains(">deepest</w:t>"));
+        // Indentation stops at the last allowed level
+        assert_eq!(list_level(&xml, "deepest"), Some((FIRST_LIST_NUMBERING + 4, 3)));
+        assert!(xml.contains("<w:ilvl w:val=\"3\" />"));
+        assert!(!xml.contains("<w:ilvl w:val=\"4\" />"));
+    }
 
-        let code_with_styling = "// This is a comment\nfn styled_function() {\n    let variable = \"value\";\n    return variable;\n}";
-
+    fn footnote_document() -> MarkdownDocument {
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: code_with_styling.to_string(),
-            processed: None,
+        document.add_element(MarkdownElement::Heading {
+            level: 1,
+            text: "Intro".to_string(),
+        });
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Claim".to_string()),
+                InlineElement::FootnoteReference("src".to_string()),
+            ],
         });
+        document.add_element(MarkdownElement::FootnoteDefinition {
+            label: "src".to_string(),
+            content: vec![InlineElement::Text("The source.".to_string())],
+        });
+        document
+    }
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    #[test]
+    fn test_comment_directives_become_word_comments() {
+        let markdown = "Ship **today** <!-- comment: Confirm the date --> please.";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let comments = crate::test_utils::read_docx_part(&docx_bytes, "word/comments.xml").unwrap();
+        assert!(comments.contains("w:author=\"md2docx\""));
+        assert!(comments.contains(">Confirm the date</w:t>"));
+
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let start = xml.find("<w:commentRangeStart w:id=\"0\" />").expect("comment range start");
+        let anchored = xml.find(">today</w:t>").unwrap();
+        let end = xml.find("<w:commentRangeEnd w:id=\"0\" />").expect("comment range end");
+        assert!(start < anchored && anchored < end);
+        assert!(xml.find(">Ship </w:t>").unwrap() < start);
+        assert!(xml.find("please.</w:t>").unwrap() > end);
+        assert!(xml.contains("<w:commentReference w:id=\"0\" />"));
+        assert!(!xml.contains("Confirm the date"));
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    #[test]
+    fn test_footnotes_appended_under_configured_heading() {
+        let mut config = create_test_config();
+        config.sections.footnotes.title = "Endnotes".to_string();
+        config.sections.footnotes.level = 2;
 
-        // Test that create_code_paragraph applies all font styling correctly
-        let paragraph_result =
-            generator.create_code_paragraph("test code", &generator.config.styles.code_block);
-        assert!(paragraph_result.is_ok());
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&footnote_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let heading = xml.find(">Endnotes</w:t>").expect("section heading emitted");
+        assert!(xml.find(">The source.</w:t>").unwrap() > heading);
+        assert!(xml.find(">Claim</w:t>").unwrap() < heading);
+        assert!(xml.contains("<w:vertAlign w:val=\"superscript\" />"));
+        assert!(xml.contains("<w:outlineLvl w:val=\"1\" />"));
+    }
 
-        // Test that create_code_block_cell applies styling and background color
-        let cell_result = generator
-            .create_code_block_cell(code_with_styling, &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
+    #[test]
+    fn test_section_heading_respects_numbering_settings() {
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.sections.footnotes.numbered = true;
 
-        // Test with different font configurations
-        let mut config_different_font = create_test_config();
-        config_different_font.styles.code_block.font.family = "Consolas".to_string();
-        config_different_font.styles.code_block.font.size = 11.0;
-        config_different_font.styles.code_block.font.bold = false;
-        config_different_font.styles.code_block.font.italic = false;
-        config_different_font.styles.code_block.background_color = None;
+        let mut generator = DocxGenerator::new(config.clone());
+        let docx_bytes = generator.generate(&footnote_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">1. Intro</w:t>"));
+        assert!(xml.contains(">2. Notes</w:t>"));
 
-        let generator_different = DocxGenerator::new(config_different_font);
-        let cell_result_different = generator_different
-            .create_code_block_cell("test", &generator_different.config.styles.code_block);
-        assert!(cell_result_different.is_ok());
+        config.sections.footnotes.numbered = false;
+        config.sections.footnotes.in_toc = false;
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&footnote_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">Notes</w:t>"));
+        // Only the body heading carries an outline level
+        assert_eq!(xml.matches("<w:outlineLvl ").count(), 1);
+    }
 
-        // Test paragraph creation with different styling
-        let paragraph_result_different = generator_different
-            .create_code_paragraph("test", &generator_different.config.styles.code_block);
-        assert!(paragraph_result_different.is_ok());
+    #[test]
+    fn test_no_footnotes_section_without_definitions() {
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&create_test_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(">Notes</w:t>"));
     }
 
     #[test]
-    fn test_code_block_table_with_background_color_variations() {
-        // Test with hex color with #
-        let mut config_hex = create_test_config();
-        config_hex.styles.code_block.background_color = Some("#ffff00".to_string());
-        let generator_hex = DocxGenerator::new(config_hex);
+    fn test_orphan_footnote_definitions_listed_or_omitted() {
+        let mut document = footnote_document();
+        document.add_element(MarkdownElement::FootnoteDefinition {
+            label: "unused".to_string(),
+            content: vec![InlineElement::Text("Never cited.".to_string())],
+        });
 
-        // Test with hex color without #
-        let mut config_hex_no_hash = create_test_config();
-        config_hex_no_hash.styles.code_block.background_color = Some("ff0000".to_string());
-        let generator_hex_no_hash = DocxGenerator::new(config_hex_no_hash);
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.find(">Never cited.</w:t>").unwrap() > xml.find(">The source.</w:t>").unwrap());
+        assert!(xml.contains(">2</w:t>"));
 
-        // Test with no background color
-        let mut config_no_bg = create_test_config();
-        config_no_bg.styles.code_block.background_color = None;
-        let generator_no_bg = DocxGenerator::new(config_no_bg);
+        let mut config = create_test_config();
+        config.elements.footnotes.orphan_definitions = OrphanFootnoteMode::Omit;
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">The source.</w:t>"));
+        assert!(!xml.contains("Never cited."));
+    }
 
-        let test_code = "console.log('background test');";
+    #[test]
+    fn test_footnote_only_document() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::FootnoteDefinition {
+            label: "only".to_string(),
+            content: vec![InlineElement::Text("Lonely note.".to_string())],
+        });
 
-        // All should succeed
-        let cell_result_hex = generator_hex
-            .create_code_block_cell(test_code, &generator_hex.config.styles.code_block);
-        assert!(cell_result_hex.is_ok());
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">Notes</w:t>"));
+        assert!(xml.contains(">Lonely note.</w:t>"));
+
+        // With nothing left to list, the section heading is dropped as well
+        let mut config = create_test_config();
+        config.elements.footnotes.orphan_definitions = OrphanFootnoteMode::Omit;
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(">Notes</w:t>"));
+        assert!(!xml.contains("Lonely note."));
+    }
 
-        let cell_result_hex_no_hash = generator_hex_no_hash
-            .create_code_block_cell(test_code, &generator_hex_no_hash.config.styles.code_block);
-        assert!(cell_result_hex_no_hash.is_ok());
+    #[test]
+    fn test_footnote_reference_without_definition_keeps_marker() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Dangling".to_string()),
+                InlineElement::FootnoteReference("missing".to_string()),
+            ],
+        });
 
-        let cell_result_no_bg = generator_no_bg
-            .create_code_block_cell(test_code, &generator_no_bg.config.styles.code_block);
-        assert!(cell_result_no_bg.is_ok());
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">Dangling</w:t>"));
+        assert!(xml.contains("<w:vertAlign w:val=\"superscript\" />"));
+        assert!(xml.contains(">1</w:t>"));
+        assert!(!xml.contains(">Notes</w:t>"));
     }
 
     #[test]
-    fn test_code_block_table_with_special_characters_and_formatting() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+    fn test_generated_package_passes_self_check() {
+        let mut generator = DocxGenerator::new(ConversionConfig::default());
+        let docx_bytes = generator.generate(&create_test_document()).unwrap();
+        assert!(crate::docx::self_check::check_package(&docx_bytes).is_ok());
 
-        // Test code with special characters, unicode, and various formatting
-        let special_code = "// Special chars: <>\"'&\nfn test() -> Result<(), Box<dyn Error>> {\n    let emoji = \"🚀\";\n    let unicode = \"αβγδε\";\n    let xml = \"<tag attr=\\\"value\\\">content</tag>\";\n    Ok(())\n}";
+        let mut config = create_test_config();
+        config.document.watermark = Some(crate::config::WatermarkConfig::text("DRAFT"));
+        assert!(config.output.self_check);
+        let mut generator = DocxGenerator::new(config);
+        assert!(generator.generate(&create_test_document()).is_ok());
+    }
 
+    fn code_block_document(code: &str) -> MarkdownDocument {
         let mut document = MarkdownDocument::new();
         document.add_element(MarkdownElement::CodeBlock {
-            language: Some("rust".to_string()),
-            code: special_code.to_string(),
+            language: None,
+            code: code.to_string(),
             processed: None,
         });
+        document
+    }
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    #[test]
+    fn test_crlf_and_cr_code_blocks_render_cleanly() {
+        for code in ["let a = 1;\r\nlet b = 2;\r\n", "let a = 1;\rlet b = 2;\r"] {
+            let mut generator = DocxGenerator::new(create_test_config());
+            let docx_bytes = generator.generate(&code_block_document(code)).unwrap();
+            let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+            assert!(!xml.contains('\r') && !xml.contains("&#xD;"), "stray CR in {:?}", code);
+            assert!(xml.contains("let a = 1;</w:t>"));
+            assert!(xml.contains("let b = 2;</w:t>"));
+        }
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    #[test]
+    fn test_line_endings_preserved_when_configured() {
+        let mut config = create_test_config();
+        config.styles.code_lines.preserve_line_endings = true;
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&code_block_document("a\rb")).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        // The lone CR is left alone, so the block stays a single line
+        assert!(xml.contains(">ab</w:t>"));
+        assert!(!xml.contains(">a</w:t>"));
+
+        assert_eq!(normalize_line_endings("a\r\nb\rc\n"), "a\nb\nc\n");
+        assert!(matches!(normalize_line_endings("a\nb"), Cow::Borrowed(_)));
+    }
 
-        // Test that special characters are preserved in table cells
-        let cell_result =
-            generator.create_code_block_cell(special_code, &generator.config.styles.code_block);
-        assert!(cell_result.is_ok());
+    fn nested_html_table() -> HtmlTable {
+        let cell = |text: &str| crate::markdown::HtmlTableCell {
+            text: text.to_string(),
+            ..Default::default()
+        };
+        let inner = HtmlTable {
+            rows: vec![vec![cell("cpu"), cell("4")], vec![cell("ram"), cell("8")]],
+        };
+        let mut specs = cell("Specs");
+        specs.tables.push(inner);
+        HtmlTable {
+            rows: vec![
+                vec![
+                    crate::markdown::HtmlTableCell { header: true, ..cell("Part") },
+                    crate::markdown::HtmlTableCell { header: true, ..cell("Details") },
+                ],
+                vec![cell("Board"), specs],
+            ],
+        }
+    }
 
-        // Test with tabs and mixed whitespace
-        let whitespace_code = "function test() {\n\tif (condition) {\n\t\treturn true;\n\t}\n    return false; // 4 spaces\n}";
-        let cell_result_whitespace =
-            generator.create_code_block_cell(whitespace_code, &generator.config.styles.code_block);
-        assert!(cell_result_whitespace.is_ok());
+    #[test]
+    fn test_nested_html_table_renders_both_tables() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::HtmlTable(nested_html_table()));
+
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert_eq!(xml.matches("<w:tbl>").count(), 2);
+        // The inner table sits inside a cell of the outer one, followed by a paragraph
+        let inner_start = xml.rfind("<w:tbl>").unwrap();
+        assert!(xml[..inner_start].ends_with("Specs</w:t></w:r></w:p>"));
+        let inner_end = xml.find("</w:tbl>").unwrap();
+        assert!(xml[inner_end..].starts_with("</w:tbl><w:p"));
+        for text in ["Part", "Board", "cpu", "8"] {
+            assert!(xml.contains(&format!(">{}</w:t>", text)), "missing {}", text);
+        }
+        assert_eq!(xml.matches("<w:tblHeader />").count(), 1);
     }
 
     #[test]
-    fn test_code_block_with_markdown_formatting() {
-        let config = create_test_config();
+    fn test_html_table_nesting_limited_by_recursion_guard() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::HtmlTable(nested_html_table()));
+
+        let mut config = create_test_config();
+        config.elements.max_nesting_depth = 1;
         let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        // Test code block with Markdown formatting
-        let markdown_code = "This is **bold** text and *italic* text.\nAlso `inline code` and [link](http://example.com).\n![image](image.jpg)";
+        assert_eq!(xml.matches("<w:tbl>").count(), 1);
+        assert!(xml.contains(">Specs cpu | 4</w:t>"));
+        assert!(xml.contains(">ram | 8</w:t>"));
+    }
 
+    fn headings_with_empty_one(mode: EmptyHeadingMode) -> Vec<String> {
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("markdown".to_string()),
-            code: markdown_code.to_string(),
-            processed: None,
-        });
+        for (level, text) in [(1, "One"), (2, ""), (2, "Two")] {
+            document.add_element(MarkdownElement::Heading { level, text: text.to_string() });
+        }
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+        config.elements.empty_headings = mode;
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let mut generator = DocxGenerator::new(config);
+        let outline: Vec<String> = generator.outline(&document).into_iter().map(|e| e.numbered_text).collect();
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let rendered: Vec<String> = xml
+            .split("</w:p>")
+            .filter(|paragraph| paragraph.contains("<w:outlineLvl "))
+            .map(|paragraph| {
+                paragraph
+                    .split("<w:t xml:space=\"preserve\">")
+                    .skip(1)
+                    .filter_map(|part| part.split("</w:t>").next())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(rendered, outline);
+        rendered
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    #[test]
+    fn test_empty_heading_numbered_by_default() {
+        assert_eq!(headings_with_empty_one(EmptyHeadingMode::Number), vec!["1. One", "1.1.", "1.2. Two"]);
     }
 
     #[test]
-    fn test_code_block_with_bold_tags() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+    fn test_empty_heading_unnumbered() {
+        assert_eq!(headings_with_empty_one(EmptyHeadingMode::Unnumbered), vec!["1. One", "", "1.1. Two"]);
+    }
 
-        // Test code block with [BOLD] tags
-        let code_with_bold_tags = "This is [BOLD]bold text[/BOLD] and normal text.\nAnother [BOLD]bold section[/BOLD] here.";
+    #[test]
+    fn test_empty_heading_skipped() {
+        assert_eq!(headings_with_empty_one(EmptyHeadingMode::Skip), vec!["1. One", "1.1. Two"]);
+    }
 
+    fn linked_document() -> MarkdownDocument {
+        let link = |text: &str, url: &str| InlineElement::Link {
+            text: text.to_string(),
+            url: url.to_string(),
+            title: None,
+        };
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("text".to_string()),
-            code: code_with_bold_tags.to_string(),
-            processed: None,
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                link("Rust", "https://www.rust-lang.org/learn"),
+                InlineElement::Text(" and ".to_string()),
+                link("crates", "https://docs.rs/"),
+            ],
+        });
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![link("Rust", "https://www.rust-lang.org/learn")],
         });
+        document
+    }
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    #[test]
+    fn test_links_rendered_as_references() {
+        let mut config = create_test_config();
+        config.elements.references.enabled = true;
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&linked_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let heading = xml.find(">References</w:t>").expect("references heading emitted");
+        // The repeated URL is listed once and both links point at number 1
+        assert_eq!(xml.matches(">https://www.rust-lang.org/learn</w:t>").count(), 1);
+        assert_eq!(xml[..heading].matches(">[1]</w:t>").count(), 2);
+        assert_eq!(xml[..heading].matches(">[2]</w:t>").count(), 1);
+        assert!(xml[heading..].contains("> Rust: </w:t>"));
+        assert!(xml.find(">https://docs.rs/</w:t>").unwrap() > heading);
+
+        let outline = generator.outline(&linked_document());
+        assert_eq!(outline.last().map(|entry| entry.text.as_str()), Some("References"));
+    }
 
-        // Test the parsing directly
-        let elements = generator.parse_inline_formatting_with_tags("This is [BOLD]bold[/BOLD] text");
-        assert_eq!(elements.len(), 3);
-        
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is "),
-            _ => panic!("Expected Text element"),
-        }
-        
-        match &elements[1] {
-            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "bold"),
-            _ => panic!("Expected Bold element"),
+    #[test]
+    fn test_references_grouped_by_domain() {
+        let mut config = create_test_config();
+        config.elements.references.enabled = true;
+        config.elements.references.group_by_domain = true;
+
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&linked_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let rust = xml.find(">rust-lang.org</w:t>").expect("domain group emitted");
+        let docs = xml.find(">docs.rs</w:t>").expect("domain group emitted");
+        assert!(rust < docs);
+        assert!(xml.find(">https://www.rust-lang.org/learn</w:t>").unwrap() < docs);
+    }
+
+    #[test]
+    fn test_links_become_external_hyperlinks() {
+        let markdown = "See [Rust](https://www.rust-lang.org/) and [below](#usage).\n\n- Read [the docs](https://docs.rs/)\n\n| Crate | Home |\n|-------|------|\n| serde | [serde.rs](https://serde.rs/) site |\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let rels = crate::test_utils::read_docx_part(&docx_bytes, "word/_rels/document.xml.rels").unwrap();
+
+        // Paragraph, list item and table cell links, but not the fragment link
+        assert_eq!(xml.matches("<w:hyperlink r:id=").count(), 3);
+        for url in ["https://www.rust-lang.org/", "https://docs.rs/", "https://serde.rs/"] {
+            assert!(rels.contains(&format!("Target=\"{}\" TargetMode=\"External\"", url)), "{}", url);
         }
-        
-        match &elements[2] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " text"),
-            _ => panic!("Expected Text element"),
+        // Link styling is kept inside the hyperlinks
+        let cell_link = &xml[xml.rfind("<w:hyperlink").unwrap()..];
+        assert!(cell_link.contains("<w:u w:val=\"single\" />"));
+        assert!(cell_link[..cell_link.find("</w:hyperlink>").unwrap()].contains(">serde.rs</w:t>"));
+        assert!(cell_link.contains("> site</w:t>"));
+        assert!(xml.contains(">below</w:t>"));
+    }
+
+    #[test]
+    fn test_table_cell_links_placed_where_written() {
+        let markdown = "| Crate |\n|-------|\n| serde [serde](https://serde.rs/) |\n| :smile: go [go](https://go.dev/) |\n\n<table><tr><td>docs <a href=\"https://docs.rs/\">docs</a></td></tr></table>\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let mut config = create_test_config();
+        config.elements.emoji.enabled = true;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        // The linked word follows the plain one, also after emoji shortcodes
+        // change the text before it
+        assert_eq!(xml.matches("<w:hyperlink r:id=").count(), 3);
+        assert!(xml.contains(">😄 go </w:t>"));
+        for word in ["serde", "go", "docs"] {
+            let plain = xml.find(&format!("{} </w:t>", word)).unwrap_or_else(|| panic!("plain {}", word));
+            let rest = &xml[plain..];
+            let (before, link) = rest.split_at(rest.find("<w:hyperlink").unwrap());
+            assert!(!before.contains("</w:tc>"), "{} linked in the same cell", word);
+            assert!(link[..link.find("</w:hyperlink>").unwrap()].contains(&format!(">{}</w:t>", word)));
         }
     }
 
     #[test]
-    fn test_parse_inline_formatting_with_tags_multiple_bold() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_lone_link_paragraph_becomes_button() {
+        let markdown = "[Get started](https://example.com/start)\n\nOr read [the guide](https://example.com/guide) first.\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let mut config = create_test_config();
+        let render = |config: &ConversionConfig| {
+            let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+            let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+            let paragraph = |text: &str| {
+                let at = xml.find(&format!(">{}</w:t>", text)).unwrap();
+                let start = xml[..at].rfind("<w:p ").unwrap();
+                xml[start..at + xml[at..].find("</w:p>").unwrap()].to_string()
+            };
+            (paragraph("Get started"), paragraph("the guide"))
+        };
 
-        // Test multiple [BOLD] tags
-        let text = "[BOLD]First[/BOLD] and [BOLD]Second[/BOLD]";
-        let elements = generator.parse_inline_formatting_with_tags(text);
-        
-        assert_eq!(elements.len(), 3);
-        match &elements[0] {
-            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "First"),
-            _ => panic!("Expected Bold element"),
-        }
-        match &elements[1] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " and "),
-            _ => panic!("Expected Text element"),
-        }
-        match &elements[2] {
-            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "Second"),
-            _ => panic!("Expected Bold element"),
-        }
+        let (button, _) = render(&config);
+        assert!(!button.contains("<w:jc w:val=\"center\" />"));
+        assert!(!button.contains("<w:bdr "));
+
+        config.elements.link_buttons.enabled = true;
+        let (button, inline) = render(&config);
+        assert!(button.contains("<w:jc w:val=\"center\" />"));
+        assert!(button.contains("<w:bdr "));
+        assert!(button.contains("<w:hyperlink r:id="));
+        assert!(inline.contains("<w:hyperlink r:id="));
+        assert!(!inline.contains("<w:bdr ") && !inline.contains("<w:jc "));
     }
 
     #[test]
-    fn test_parse_inline_formatting_with_tags_unclosed() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_links_untouched_without_references() {
+        let mut generator = DocxGenerator::new(create_test_config());
+        let docx_bytes = generator.generate(&linked_document()).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(">References</w:t>"));
+        assert!(!xml.contains(">[1]</w:t>"));
+    }
 
-        // Test unclosed [BOLD] tag
-        let text = "This is [BOLD]unclosed bold";
-        let elements = generator.parse_inline_formatting_with_tags(text);
-        
-        // Should treat [BOLD] as literal text when no closing tag, plus remaining text
-        assert_eq!(elements.len(), 3);
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is "),
-            _ => panic!("Expected Text element"),
-        }
-        match &elements[1] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "[BOLD]"),
-            _ => panic!("Expected Text element"),
-        }
-        match &elements[2] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "unclosed bold"),
-            _ => panic!("Expected Text element"),
+    fn embedded_media_size(docx_bytes: &[u8]) -> u64 {
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(docx_bytes)).unwrap();
+        let mut total = 0;
+        for index in 0..archive.len() {
+            let file = archive.by_index(index).unwrap();
+            if file.name().starts_with("word/media/") {
+                total += file.size();
+            }
         }
+        total
     }
 
     #[test]
-    fn test_parse_inline_formatting_with_italic_tags() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_oversized_image_downscaled_before_embedding() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("large.png");
+        image::RgbImage::from_fn(1200, 900, |x, y| {
+            let seed = x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503);
+            image::Rgb([seed as u8, (seed >> 8) as u8, (seed >> 16) as u8])
+        })
+        .save(&path)
+        .unwrap();
 
-        // Test single [ITALIC] tag
-        let text = "This is [ITALIC]italic[/ITALIC] text";
-        let elements = generator.parse_inline_formatting_with_tags(text);
-        
-        assert_eq!(elements.len(), 3);
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is "),
-            _ => panic!("Expected Text element"),
-        }
-        match &elements[1] {
-            crate::markdown::InlineElement::Italic(t) => assert_eq!(t, "italic"),
-            _ => panic!("Expected Italic element"),
-        }
-        match &elements[2] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " text"),
-            _ => panic!("Expected Text element"),
-        }
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Image {
+            alt_text: "large".to_string(),
+            url: path.to_string_lossy().into_owned(),
+            title: None,
+            width: None,
+            height: None,
+        });
+
+        let mut config = create_test_config();
+        let original = embedded_media_size(&DocxGenerator::new(config.clone()).generate(&document).unwrap());
+        config.elements.image.max_pixel_dimension = Some(300);
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        assert!(embedded_media_size(&docx_bytes) * 4 < original);
+
+        // Display size still follows the original pixels, fitted to 600x400 px
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 533 * 9525, 400 * 9525)));
     }
 
     #[test]
-    fn test_parse_inline_formatting_with_mixed_tags() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_image_fitted_to_bounds_keeping_aspect_ratio() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wide = dir.path().join("wide.png");
+        image::RgbImage::new(200, 100).save(&wide).unwrap();
+        let small = dir.path().join("small.png");
+        image::RgbImage::new(40, 30).save(&small).unwrap();
 
-        // Test mixed [BOLD] and [ITALIC] tags
-        let text = "[BOLD]bold[/BOLD] and [ITALIC]italic[/ITALIC]";
-        let elements = generator.parse_inline_formatting_with_tags(text);
-        
-        assert_eq!(elements.len(), 3);
-        match &elements[0] {
-            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "bold"),
-            _ => panic!("Expected Bold element"),
-        }
-        match &elements[1] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " and "),
-            _ => panic!("Expected Text element"),
-        }
-        match &elements[2] {
-            crate::markdown::InlineElement::Italic(t) => assert_eq!(t, "italic"),
-            _ => panic!("Expected Italic element"),
-        }
+        let mut config = create_test_config();
+        config.elements.image.max_width = 100.0;
+        config.elements.image.max_height = 100.0;
+        let markdown = format!("![wide]({})\n\n![small]({})\n", wide.display(), small.display());
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 100 * 9525, 50 * 9525)));
+        // Small images are not scaled up
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 40 * 9525, 30 * 9525)));
     }
 
     #[test]
-    fn test_parse_inline_formatting_with_nested_order() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_table_alignment_and_full_width() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["A".to_string(), "B".to_string()],
+            rows: vec![vec!["1".to_string(), "2".to_string()]],
+            links: vec![],
+        });
+        let render = |config: ConversionConfig| {
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
+        };
 
-        // Test that tags are processed in order of appearance
-        let text = "[ITALIC]first[/ITALIC] then [BOLD]second[/BOLD]";
-        let elements = generator.parse_inline_formatting_with_tags(text);
-        
-        assert_eq!(elements.len(), 3);
-        match &elements[0] {
-            crate::markdown::InlineElement::Italic(t) => assert_eq!(t, "first"),
-            _ => panic!("Expected Italic element"),
-        }
-        match &elements[1] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, " then "),
-            _ => panic!("Expected Text element"),
-        }
-        match &elements[2] {
-            crate::markdown::InlineElement::Bold(t) => assert_eq!(t, "second"),
-            _ => panic!("Expected Bold element"),
+        let left = render(create_test_config());
+        assert!(!left.contains("<w:jc w:val=\"center\" />"));
+        assert!(left.contains("<w:tblW w:w=\"8300\" w:type=\"dxa\" />"));
+
+        let mut config = create_test_config();
+        config.styles.table.table_alignment = TableAlignment::Center;
+        config.styles.table.full_width = true;
+        let centered = render(config);
+        let table_props = &centered[centered.find("<w:tblPr>").unwrap()..centered.find("</w:tblPr>").unwrap()];
+        assert!(table_props.contains("<w:jc w:val=\"center\" />"));
+        // 595pt page less two 72pt margins
+        assert!(table_props.contains("<w:tblW w:w=\"9020\" w:type=\"dxa\" />"));
+    }
+
+    #[test]
+    fn test_row_header_column_styled_like_header() {
+        let mut config = create_test_config();
+        let table_style = &mut config.styles.table;
+        table_style.row_header_column = true;
+        table_style.header_background = Some("#DDDDDD".to_string());
+        table_style.alternate_row_background = Some("#F5F5F5".to_string());
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Metric".to_string(), "Q1".to_string()],
+            rows: vec![
+                vec!["Revenue".to_string(), "10".to_string()],
+                vec!["Cost".to_string(), "7".to_string()],
+            ],
+            links: vec![],
+        });
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let cell = |text: &str| {
+            xml.split("<w:tc>")
+                .find(|cell| cell.contains(&format!(">{}</w:t>", text)))
+                .unwrap()
+                .to_string()
+        };
+        for row_header in ["Revenue", "Cost"] {
+            let cell = cell(row_header);
+            assert!(cell.contains("<w:b />"));
+            assert!(cell.contains("w:fill=\"DDDDDD\""));
         }
+        assert!(!cell("10").contains("<w:b />"));
+        assert!(!cell("10").contains("w:shd"));
+        // The alternate row keeps its shading outside the row header
+        assert!(!cell("7").contains("<w:b />"));
+        assert!(cell("7").contains("w:fill=\"F5F5F5\""));
     }
 
     #[test]
-    fn test_contains_markdown_formatting() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_list_item_blocks_indented_under_item() {
+        let mut item = ListItem::new(vec![InlineElement::Text("Install".to_string())]);
+        item.blocks = vec![
+            ListItemBlock::Paragraph(vec![InlineElement::Text("Then run".to_string())]),
+            ListItemBlock::CodeBlock { language: None, code: "cargo run".to_string(), attributes: Default::default() },
+        ];
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::List { ordered: true, items: vec![item] });
 
-        // Test various Markdown patterns
-        assert!(generator.contains_markdown_formatting("**bold**"));
-        assert!(generator.contains_markdown_formatting("*italic*"));
-        assert!(generator.contains_markdown_formatting("`code`"));
-        assert!(generator.contains_markdown_formatting("[link](url)"));
-        assert!(generator.contains_markdown_formatting("![image](url)"));
-        
-        // Test plain text
-        assert!(!generator.contains_markdown_formatting("plain text"));
-        assert!(!generator.contains_markdown_formatting("no formatting here"));
+        let config = create_test_config();
+        let indent = (config.elements.list.indent * 20.0) as i32;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let item = xml.find(">Install</w:t>").unwrap();
+        let paragraph = xml.find(">Then run</w:t>").unwrap();
+        let code = xml.find("cargo run").unwrap();
+        assert!(item < paragraph && paragraph < code);
+        assert!(xml[item..paragraph].contains(&format!("<w:ind w:left=\"{}\"", indent)));
+        assert!(xml[paragraph..code].contains(&format!("<w:tblInd w:w=\"{}\"", indent)));
     }
 
     #[test]
-    fn test_create_code_block_cell_with_markdown() {
+    fn test_ordered_item_blocks_hang_under_text() {
+        let markdown = "1. Prepare\n\n    1. Install\n\n       Then run it.\n\n    2. Check\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
         let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+        let indent = (config.elements.list.indent * 20.0) as i32;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
 
-        // Test with Markdown formatting
-        let markdown_text = "**Bold** and *italic* text";
-        let result = generator.create_code_block_cell_with_markdown(markdown_text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        let paragraph = |text: &str| {
+            let end = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            xml[xml[..end].rfind("<w:p ").unwrap()..end].to_string()
+        };
+        // The number hangs from its level, whose text lines up with the
+        // continuation indent
+        assert!(paragraph("Install").contains("<w:ilvl w:val=\"1\" />"));
+        assert!(paragraph("Check").contains("<w:ilvl w:val=\"1\" />"));
+        assert!(paragraph("Then run it.").contains(&format!("<w:ind w:left=\"{}\"", 2 * indent)));
+        let numbering = crate::test_utils::read_docx_part(&docx_bytes, "word/numbering.xml").unwrap();
+        let level = abstract_numbering(&numbering, ORDERED_ABSTRACT_NUMBERING).split("</w:lvl>").nth(1).unwrap().to_string();
+        assert!(level.contains(&format!("w:left=\"{}\"", 2 * indent)));
+        assert!(level.contains(&format!("w:hanging=\"{}\"", indent)));
+    }
 
-        // Test with plain text (should fall back to regular method)
-        let plain_text = "plain text without formatting";
-        let result = generator.create_code_block_cell_with_markdown(plain_text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+    #[test]
+    fn test_configured_page_size_applied() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![InlineElement::Text("Body".to_string())],
+        });
+        let page_size = |width: f32, height: f32| {
+            let mut config = create_test_config();
+            config.document.page_size = crate::config::PageSize { width, height };
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+            let start = xml.rfind("<w:pgSz ").unwrap();
+            xml[start..start + xml[start..].find("/>").unwrap() + 2].to_string()
+        };
 
-        // Test with mixed content
-        let mixed_text = "Code: `function()` and **important** note";
-        let result = generator.create_code_block_cell_with_markdown(mixed_text, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        // A4 in points
+        assert_eq!(page_size(595.0, 842.0), r#"<w:pgSz w:w="11900" w:h="16840" />"#);
+        assert_eq!(page_size(842.0, 595.0), r#"<w:pgSz w:w="16840" w:h="11900" w:orient="landscape" />"#);
     }
 
     #[test]
-    fn test_create_code_run_from_inline() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_landscape_section_reverts_to_portrait() {
+        let markdown = "Intro\n\n<!-- section: landscape -->\n\n| wide | table |\n|---|---|\n| 1 | 2 |\n\n<!-- section: portrait -->\n\nAfter\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let sections: Vec<&str> = xml.split("<w:sectPr>").skip(1).collect();
+        assert_eq!(sections.len(), 3);
+        assert!(sections[0].contains(r#"<w:pgSz w:w="11900" w:h="16840" w:orient="portrait" />"#));
+        assert!(sections[1].contains(r#"<w:pgSz w:w="16840" w:h="11900" w:orient="landscape" />"#));
+        // The table is in the landscape section, the last paragraph after it
+        let table = xml.find("<w:tbl>").unwrap();
+        let landscape_end = xml.find("w:orient=\"landscape\"").unwrap();
+        assert!(xml.find("<w:sectPr>").unwrap() < table && table < landscape_end);
+        assert!(xml.find(">After</w:t>").unwrap() > landscape_end);
+        assert!(!sections[2].contains("landscape"));
+    }
 
-        // Test different inline elements
-        let bold_inline = InlineElement::Bold("bold text".to_string());
-        let result = generator.create_code_run_from_inline(&bold_inline, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+    #[test]
+    fn test_source_markdown_embedded_as_custom_property() {
+        let markdown = "# Notes\n\nSmall & simple.\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let hash = format!("{:x}", Sha256::digest(markdown.as_bytes()));
 
-        let italic_inline = InlineElement::Italic("italic text".to_string());
-        let result = generator.create_code_run_from_inline(&italic_inline, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        let mut config = create_test_config();
+        config.output.embed_source.enabled = true;
+        let docx_bytes = DocxGenerator::new(config.clone())
+            .generate_with_source(&document, markdown)
+            .unwrap();
+        let custom = crate::test_utils::read_docx_part(&docx_bytes, "docProps/custom.xml").unwrap();
+        assert!(custom.contains(&format!("name=\"{}\"", SOURCE_PROPERTY)));
+        assert!(custom.contains("# Notes\n\nSmall &amp; simple.\n"));
+        assert!(custom.contains(&hash));
+
+        // Over the limit only the hash is kept
+        config.output.embed_source.max_bytes = 8;
+        let docx_bytes = DocxGenerator::new(config).generate_with_source(&document, markdown).unwrap();
+        let custom = crate::test_utils::read_docx_part(&docx_bytes, "docProps/custom.xml").unwrap();
+        assert!(custom.contains(&hash));
+        assert!(!custom.contains(&format!("name=\"{}\"", SOURCE_PROPERTY)));
+
+        // Disabled by default
+        let docx_bytes = DocxGenerator::new(create_test_config())
+            .generate_with_source(&document, markdown)
+            .unwrap();
+        let custom = crate::test_utils::read_docx_part(&docx_bytes, "docProps/custom.xml").unwrap_or_default();
+        assert!(!custom.contains(SOURCE_HASH_PROPERTY));
+    }
 
-        let code_inline = InlineElement::Code("nested code".to_string());
-        let result = generator.create_code_run_from_inline(&code_inline, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+    #[test]
+    fn test_provenance_property_and_optional_colophon() {
+        let document = crate::markdown::MarkdownParser::new().parse("# Notes\n\nBody text\n").unwrap();
 
-        let link_inline = InlineElement::Link {
-            text: "link text".to_string(),
-            url: "http://example.com".to_string(),
-            title: None,
-        };
-        let result = generator.create_code_run_from_inline(&link_inline, &generator.config.styles.code_block);
-        assert!(result.is_ok());
+        // Property only by default
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let custom = crate::test_utils::read_docx_part(&docx_bytes, "docProps/custom.xml").unwrap();
+        assert!(custom.contains(&format!("name=\"{}\"", provenance::GENERATOR_PROPERTY)));
+        assert!(custom.contains(&provenance::generator_name()));
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains("Generated by md2docx"));
+
+        let mut config = create_test_config();
+        config.output.provenance.colophon = true;
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let colophon = format!("Generated by md2docx v{} on ", env!("CARGO_PKG_VERSION"));
+        let body_end = xml.rfind("Body text").unwrap();
+        assert!(xml.rfind(&colophon).is_some_and(|at| at > body_end));
+
+        config.output.provenance.property = false;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let custom = crate::test_utils::read_docx_part(&docx_bytes, "docProps/custom.xml").unwrap_or_default();
+        assert!(!custom.contains(provenance::GENERATOR_PROPERTY));
     }
 
     #[test]
-    fn test_extract_text_from_element() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_margin_directive_overrides_configured_margins() {
+        let markdown = "<!-- margins: top=36pt left=18pt right=18pt -->\n\nNarrow page\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let section = &xml[xml.rfind("<w:sectPr>").unwrap()..];
+        assert!(section.contains(r#"w:top="36""#));
+        assert!(section.contains(r#"w:left="18""#));
+        assert!(section.contains(r#"w:right="18""#));
+        // The bottom margin keeps the configured value
+        assert!(section.contains(r#"w:bottom="72""#));
+
+        // Margins wider than the page are ignored
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("<!-- margins: left=400pt right=400pt -->\n\nText\n")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(r#"w:left="72""#));
+    }
 
-        // Test heading
-        let heading = MarkdownElement::Heading {
-            level: 1,
-            text: "Test Heading".to_string(),
+    #[test]
+    fn test_adjacent_h1_h2_rendered_as_title_block() {
+        let mut config = create_test_config();
+        config.styles.title_block.enabled = true;
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+        let render = |markdown: &str| {
+            let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+            let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
         };
-        let text = generator.extract_text_from_element(&heading);
-        assert_eq!(text, "Test Heading");
 
-        // Test paragraph
-        let paragraph = MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("Hello ".to_string()),
-                InlineElement::Bold("world".to_string()),
-            ],
-        };
-        let text = generator.extract_text_from_element(&paragraph);
-        assert_eq!(text, "Hello world");
+        let xml = render("# Annual Report\n## Fiscal Year 2024\n\nBody\n");
+        let subtitle = xml.split("</w:p>").find(|p| p.contains("Fiscal Year 2024")).unwrap();
+        assert!(subtitle.contains(r#"<w:pStyle w:val="Subtitle" />"#));
+        assert!(!subtitle.contains("w:outlineLvl"));
+        assert!(!subtitle.contains("1.1."));
+        let title = xml.split("</w:p>").find(|p| p.contains("Annual Report")).unwrap();
+        assert!(title.contains(r#"<w:pStyle w:val="Title" />"#));
+
+        let xml = render("# Annual Report\n\nIntro\n\n## Fiscal Year 2024\n");
+        assert!(!xml.contains(r#"w:val="Title""#) && !xml.contains(r#"w:val="Subtitle""#));
+        assert!(xml.contains("1.1. Fiscal Year 2024"));
+
+        // The outline leaves the subtitle out, like the navigation pane
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("# Annual Report\n## Fiscal Year 2024\n\n## Results\n")
+            .unwrap();
+        let outline = DocxGenerator::new(config.clone()).outline(&document);
+        let texts: Vec<&str> = outline.iter().map(|entry| entry.numbered_text.as_str()).collect();
+        assert_eq!(texts, ["1. Annual Report", "1.1. Results"]);
+    }
 
-        // Test image
-        let image = MarkdownElement::Image {
-            alt_text: "Test Image".to_string(),
-            url: "image.jpg".to_string(),
-            title: None,
-            width: None,
-            height: None,
-        };
-        let text = generator.extract_text_from_element(&image);
-        assert_eq!(text, "Test Image");
+    #[test]
+    fn test_first_h1_rendered_as_unnumbered_title() {
+        let mut config = create_test_config();
+        config.styles.first_heading_title.enabled = true;
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("# Field Guide\n\nIntro\n\n# Birds\n\n# Insects\n")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let title = xml.split("</w:p>").find(|p| p.contains(">Field Guide</w:t>")).unwrap();
+        assert!(title.contains(r#"<w:pStyle w:val="Title" />"#));
+        assert!(title.contains(r#"<w:jc w:val="center" />"#));
+        assert!(title.contains(r#"<w:sz w:val="52" />"#));
+        // The title is left out of the H1 counter
+        assert!(xml.contains("1. Birds") && xml.contains("2. Insects"));
+        assert!(!xml.contains("1. Field Guide"));
+        let core = crate::test_utils::read_docx_part(&docx_bytes, "docProps/core.xml").unwrap();
+        assert!(core.contains("Field Guide"));
+
+        let outline = DocxGenerator::new(config.clone()).outline(&document);
+        let texts: Vec<&str> = outline.iter().map(|entry| entry.numbered_text.as_str()).collect();
+        assert_eq!(texts, ["Field Guide", "1. Birds", "2. Insects"]);
+
+        config.styles.first_heading_title.core_title = false;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let core = crate::test_utils::read_docx_part(&docx_bytes, "docProps/core.xml").unwrap();
+        assert!(!core.contains("Field Guide"));
     }
 
     #[test]
-    fn test_code_block_table_line_spacing_configuration() {
-        // Test various line spacing values
-        let mut config_single = create_test_config();
-        config_single.styles.code_block.line_spacing = 1.0;
-        let generator_single = DocxGenerator::new(config_single);
+    fn test_heading_bookmarks_unique_and_match_outline() {
+        let mut config = create_test_config();
+        config.styles.heading_bookmarks.enabled = true;
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("# Usage
 
-        let mut config_double = create_test_config();
-        config_double.styles.code_block.line_spacing = 2.0;
-        let generator_double = DocxGenerator::new(config_double);
+## Setup
 
-        let mut config_custom = create_test_config();
-        config_custom.styles.code_block.line_spacing = 1.5;
-        let generator_custom = DocxGenerator::new(config_custom);
+# Usage
 
-        let test_code = "line1\nline2\nline3";
+## Setup
 
-        // Test paragraph creation with different line spacing
-        let paragraph_single = generator_single
-            .create_code_paragraph(test_code, &generator_single.config.styles.code_block);
-        assert!(paragraph_single.is_ok());
+## 2024 Plans
+")
+            .unwrap();
 
-        let paragraph_double = generator_double
-            .create_code_paragraph(test_code, &generator_double.config.styles.code_block);
-        assert!(paragraph_double.is_ok());
+        let outline = DocxGenerator::new(config.clone()).outline(&document);
+        let names: Vec<&str> = outline.iter().filter_map(|entry| entry.bookmark.as_deref()).collect();
+        assert_eq!(names, ["usage", "setup", "usage_1", "setup_1", "h_2024_plans"]);
 
-        let paragraph_custom = generator_custom
-            .create_code_paragraph(test_code, &generator_custom.config.styles.code_block);
-        assert!(paragraph_custom.is_ok());
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        for (id, name) in names.iter().enumerate() {
+            let start = format!(r#"<w:bookmarkStart w:id="{}" w:name="{}" />"#, id, name);
+            let paragraph = xml.split("</w:p>").find(|p| p.contains(&start)).expect("bookmark on heading");
+            assert!(paragraph.contains("w:outlineLvl"));
+            assert!(paragraph.contains(&format!(r#"<w:bookmarkEnd w:id="{}" />"#, id)));
+        }
+        assert_eq!(xml.matches("<w:bookmarkStart ").count(), names.len());
+
+        // Off by default: no bookmarks and none reported
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains("bookmarkStart"));
+        let outline = DocxGenerator::new(create_test_config()).outline(&document);
+        assert!(outline.iter().all(|entry| entry.bookmark.is_none()));
+    }
 
-        // Test complete table cell creation with different line spacing
-        let cell_single = generator_single
-            .create_code_block_cell(test_code, &generator_single.config.styles.code_block);
-        assert!(cell_single.is_ok());
+    #[test]
+    fn test_outline_includes_quoted_and_details_headings() {
+        let mut config = create_test_config();
+        config.styles.heading_bookmarks.enabled = true;
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+        config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("# One\n\n> ## Quoted\n\n<details>\n<summary>More</summary>\n\n## Folded\n\n</details>\n\n## Two\n\n# Three\n")
+            .unwrap();
+
+        let outline = DocxGenerator::new(config.clone()).outline(&document);
+        let numbered: Vec<&str> = outline.iter().map(|entry| entry.numbered_text.as_str()).collect();
+        assert_eq!(numbered, ["1. One", "1.1. Quoted", "1.2. Folded", "1.3. Two", "2. Three"]);
+        let names: Vec<&str> = outline.iter().filter_map(|entry| entry.bookmark.as_deref()).collect();
+        assert_eq!(names, ["one", "quoted", "folded", "two", "three"]);
+
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let positions: Vec<usize> = numbered
+            .iter()
+            .map(|text| xml.find(&format!(">{}<", text)).unwrap_or_else(|| panic!("{} in document", text)))
+            .collect();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        for name in names {
+            assert!(xml.contains(&format!(r#"w:name="{}" />"#, name)));
+        }
+    }
 
-        let cell_double = generator_double
-            .create_code_block_cell(test_code, &generator_double.config.styles.code_block);
-        assert!(cell_double.is_ok());
+    #[test]
+    fn test_table_of_contents_field_links_heading_bookmarks() {
+        let mut config = create_test_config();
+        config.document.table_of_contents.enabled = true;
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("# Overview\n\n## Setup\n\n### Install\n\n#### Details\n")
+            .unwrap();
+
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let instr = xml.split("<w:instrText").find(|part| part.contains("TOC ")).expect("TOC field");
+        assert!(instr.contains(r#"\o &quot;1-3&quot;"#));
+        assert!(instr.contains(r#"\h"#));
+        assert!(!instr.contains(r#"\n"#));
+        assert!(xml.contains("Contents"));
+        for name in ["overview", "setup", "install"] {
+            assert!(xml.contains(&format!(r#"<w:hyperlink w:anchor="{}""#, name)));
+            assert!(xml.contains(&format!(r#"w:name="{}" />"#, name)));
+        }
+        // Below the depth: bookmarked but not listed
+        assert!(xml.contains(r#"w:name="details" />"#));
+        assert!(!xml.contains(r#"w:anchor="details""#));
+
+        config.document.table_of_contents.page_numbers = false;
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(r#"\n &quot;1-3&quot;"#));
+
+        assert!(config.validate().is_ok());
+        for depth in [0, 7] {
+            config.document.table_of_contents.max_depth = depth;
+            assert!(config.validate().is_err());
+        }
+    }
 
-        let cell_custom = generator_custom
-            .create_code_block_cell(test_code, &generator_custom.config.styles.code_block);
-        assert!(cell_custom.is_ok());
+    #[test]
+    fn test_code_captions_from_leading_comment() {
+        let markdown = "```rust\n// Parsing the config\nlet config = load();\n```\n\n\
+                        ```python\n# Retry loop\nfor attempt in range(3):\n    run()\n```\n\n\
+                        ```python\n#!/usr/bin/env python\nprint(1)\n```\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+
+        let mut config = create_test_config();
+        config.elements.code_captions.enabled = true;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let captions: Vec<&str> = xml
+            .split("</w:p>")
+            .filter(|p| p.contains(r#"<w:pStyle w:val="Caption" />"#))
+            .collect();
+        assert_eq!(captions.len(), 2);
+        assert!(captions[0].contains(">Parsing the config</w:t>"));
+        assert!(captions[1].contains(">Retry loop</w:t>"));
+        assert!(!xml.contains("// Parsing"));
+        assert!(!xml.contains("# Retry"));
+        assert!(xml.find(">Parsing the config</w:t>").unwrap() < xml.find("let config = load();").unwrap());
+        assert!(xml.contains("#!/usr/bin/env python"));
+
+        // Off by default: the comments stay in the code
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains("// Parsing the config"));
+        assert!(!xml.contains(r#"w:val="Caption""#));
     }
 
     #[test]
-    fn test_calculate_column_widths() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_code_title_bar_names_language_and_file() {
+        let markdown = "```rust title=\"src/main.rs\"\nfn main() {}\n```\n\n```\nplain output\n```\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
 
-        // Test with headers and data
-        let headers = vec!["Name".to_string(), "Age".to_string(), "Very Long Description".to_string()];
-        let rows = vec![
-            vec!["Alice".to_string(), "30".to_string(), "Short".to_string()],
-            vec!["Bob".to_string(), "25".to_string(), "Medium length text".to_string()],
-            vec!["Charlie".to_string(), "35".to_string(), "This is a very long description that should determine the column width".to_string()],
-        ];
+        let mut config = create_test_config();
+        config.elements.code_title_bar.enabled = true;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let tables: Vec<&str> = xml.split("</w:tbl>").filter(|t| t.contains("<w:tbl>")).collect();
+        assert_eq!(tables.len(), 2);
+
+        let rows: Vec<&str> = tables[0].split("</w:tr>").filter(|r| r.contains("<w:tr")).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].contains(">rust — src/main.rs</w:t>"));
+        assert!(rows[0].contains("w:fill=\"E8E8E8\""));
+        assert!(rows[0].contains("<w:b />"));
+        assert!(rows[1].contains("fn main()"));
+
+        // No language: no title bar
+        assert_eq!(tables[1].matches("</w:tr>").count(), 1);
+        assert!(tables[1].contains("plain output"));
+
+        // Off by default
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains("src/main.rs"));
+    }
 
-        let widths = generator.calculate_column_widths(&headers, &rows);
-        assert_eq!(widths.len(), 3);
-        
-        // The third column should be wider due to the long content
-        assert!(widths[2] > widths[0]);
-        assert!(widths[2] > widths[1]);
-        
-        // All widths should be within reasonable bounds
-        for width in &widths {
-            assert!(*width >= 720); // Minimum width
-            assert!(*width <= 2880); // Maximum width
+    #[test]
+    fn test_details_summary_bold_and_body_indented() {
+        let markdown = "<details>\n<summary>Build output</summary>\n\nIt **worked**.\n\n```\nok\n```\n\n</details>\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let summary = xml.split("</w:p>").find(|p| p.contains(">Build output</w:t>")).unwrap();
+        assert!(summary.contains("<w:b />"));
+        assert!(summary.contains("<w:keepNext />"));
+        let body = xml.split("</w:p>").find(|p| p.contains(">It </w:t>")).unwrap();
+        // One list indent (20pt)
+        assert!(body.contains(r#"w:left="400""#));
+        assert!(xml.find(">Build output</w:t>").unwrap() < xml.find(">ok</w:t>").unwrap());
+    }
+
+    #[test]
+    fn test_float_right_image_anchored_with_wrap() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("photo.png");
+        image::RgbImage::new(40, 30).save(&path).unwrap();
+
+        let mut config = create_test_config();
+        config.elements.image.placement = ImagePlacement::FloatRight;
+        let markdown = format!("![photo]({})\n\nText beside the photo.\n\n![photo]({})", path.display(), path.display());
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        // The first image is anchored in the paragraph that wraps around it
+        let paragraphs: Vec<&str> = xml.split("</w:p>").filter(|p| p.contains("<w:drawing>")).collect();
+        assert_eq!(paragraphs.len(), 2);
+        assert!(paragraphs[0].contains("Text beside the photo."));
+        assert!(paragraphs[0].contains("<wp:anchor"));
+        assert!(paragraphs[0].contains("<wp:positionH relativeFrom=\"column\"><wp:align>right</wp:align>"));
+        assert!(paragraphs[0].contains("<wp:wrapSquare wrapText=\"bothSides\" />"));
+        assert!(paragraphs[0].contains("distL=\"114300\""));
+        // With no paragraph after it, the last image stands alone, still anchored
+        assert!(paragraphs[1].contains("<wp:anchor"));
+        assert!(!paragraphs[1].contains("<w:t"));
+        assert!(!xml.contains("<wp:inline"));
+    }
+
+    #[test]
+    fn test_image_format_detected_from_content() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // A PNG without a usable extension and a JPEG named .png
+        let png = dir.path().join("chart.img");
+        image::RgbImage::new(40, 30).save_with_format(&png, image::ImageFormat::Png).unwrap();
+        let jpeg = dir.path().join("photo.png");
+        image::RgbImage::new(40, 30).save_with_format(&jpeg, image::ImageFormat::Jpeg).unwrap();
+        let text = dir.path().join("notes.png");
+        std::fs::write(&text, "not an image").unwrap();
+
+        let markdown = format!(
+            "![chart]({})\n\n![photo]({})\n\n![notes]({})\n",
+            png.display(),
+            jpeg.display(),
+            text.display()
+        );
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert_eq!(xml.matches("<w:drawing>").count(), 2);
+        assert!(!xml.contains("[Image: chart"));
+        assert!(!xml.contains("[Image: photo"));
+        // Data that is no image at all falls back to the placeholder
+        assert!(xml.contains("[Image: notes"));
+    }
+
+    #[tokio::test]
+    async fn test_remote_images_fetched_when_enabled() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let mut png = Cursor::new(Vec::new());
+        image::RgbImage::new(40, 30).write_to(&mut png, image::ImageFormat::Png).unwrap();
+        Mock::given(path("/logo"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Type", "image/png")
+                    .set_body_bytes(png.into_inner()),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/missing.png"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let markdown = format!("![logo]({0}/logo)\n\n![gone]({0}/missing.png)\n", server.uri());
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let render = |config: ConversionConfig| {
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+            (xml, embedded_media_size(&docx_bytes))
+        };
+        let logo_placeholder = format!("[Image: logo - URL: {}/logo]", server.uri());
+        let gone_placeholder = format!("[Image: gone - URL: {}/missing.png]", server.uri());
+
+        // Placeholders only, without any request, by default
+        let mut config = create_test_config();
+        let (xml, media) = render(config.clone());
+        assert!(xml.contains(&logo_placeholder) && xml.contains(&gone_placeholder));
+        assert_eq!(media, 0);
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        // The failed download falls back to its placeholder
+        config.elements.image.fetch_remote = true;
+        let (xml, media) = render(config.clone());
+        assert!(!xml.contains(&logo_placeholder) && xml.contains(&gone_placeholder));
+        assert!(xml.contains("<pic:pic"));
+        assert!(media > 0);
+
+        // Too large to embed
+        config.elements.image.remote_max_bytes = 16;
+        let (xml, media) = render(config);
+        assert!(xml.contains(&logo_placeholder));
+        assert_eq!(media, 0);
+    }
+
+    #[test]
+    fn test_image_total_size_cap() {
+        let dir = tempfile::TempDir::new().unwrap();
+        // Noise compresses poorly, so each image embeds at roughly 120 KB
+        let mut seed = 7u32;
+        let noise = image::RgbImage::from_fn(200, 200, |_, _| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let [r, g, b, _] = seed.to_be_bytes();
+            image::Rgb([r, g, b])
+        });
+        let mut markdown = String::new();
+        for index in 0..5 {
+            let path = dir.path().join(format!("large{}.png", index));
+            noise.save(&path).unwrap();
+            markdown.push_str(&format!("![large{}]({})\n\n", index, path.display()));
         }
+        let embedded = Pic::new(&std::fs::read(dir.path().join("large0.png")).unwrap()).image.len() as u64;
+
+        let mut config = create_test_config();
+        config.elements.image.total_size = Some(crate::config::ImageTotalSizeConfig {
+            max_bytes: embedded * 5 / 2,
+            strict: false,
+        });
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert_eq!(xml.matches("<w:drawing>").count(), 2);
+        assert_eq!(xml.matches("Image size limit reached").count(), 3);
+        assert!(xml.contains("[Image: large2 - Image size limit reached: "));
+
+        config.elements.image.total_size.as_mut().unwrap().strict = true;
+        let result = DocxGenerator::new(config).generate(&document);
+        assert!(matches!(result, Err(ConversionError::ResourceLimit(_))));
     }
 
     #[test]
-    fn test_estimate_text_width() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_note_block_columns() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let icon = dir.path().join("icon.png");
+        image::RgbImage::new(16, 16).save(&icon).unwrap();
+        let note = |icon: Option<&std::path::Path>| {
+            let icon = icon.map_or(String::new(), |path| format!("[ICON]{}[/ICON]", path.display()));
+            format!("```\n[NOTE_BLOCK_START][TITLE]Tip[/TITLE]{}[CONTENT]Save often.[/CONTENT][NOTE_BLOCK_END]\n```\n", icon)
+        };
+        let render = |config: ConversionConfig, markdown: &str| {
+            let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
+        };
 
-        // Test ASCII text
-        let ascii_text = "Hello";
-        let ascii_width = generator.estimate_text_width(ascii_text);
-        assert_eq!(ascii_width, 6); // H(2) + e(1) + l(1) + l(1) + o(1) = 6
+        let xml = render(create_test_config(), &note(Some(&icon)));
+        assert_eq!(xml.matches("<w:tc>").count(), 2);
+        // Spaced like code blocks: before the title, after the content
+        let paragraph = |text: &str| xml.split("</w:p>").find(|p| p.contains(text)).unwrap().to_string();
+        assert!(paragraph(">Tip</w:t>").contains("w:before=\"120\""));
+        assert!(paragraph(">Save often.</w:t>").contains("w:after=\"120\""));
+
+        // Without an icon, or with one that cannot be loaded, no empty column
+        for markdown in [note(None), note(Some(&dir.path().join("missing.png")))] {
+            let xml = render(create_test_config(), &markdown);
+            assert_eq!(xml.matches("<w:tc>").count(), 1);
+            assert!(xml.contains(r#"<w:tcW w:w="8300" w:type="dxa" />"#));
+        }
 
-        // Test wide characters
-        let wide_text = "WWW";
-        let wide_width = generator.estimate_text_width(wide_text);
-        assert_eq!(wide_width, 6); // 3 wide characters = 6 units
+        // Forced single column keeps the icon above the title
+        let mut config = create_test_config();
+        config.elements.note_block.single_column = true;
+        let xml = render(config, &note(Some(&icon)));
+        assert_eq!(xml.matches("<w:tc>").count(), 1);
+        assert!(xml.find("<w:drawing>").unwrap() < xml.find(">Tip</w:t>").unwrap());
+
+        // The icon is fitted into the configured box; its column follows the width
+        let wide_icon = dir.path().join("wide.png");
+        image::RgbImage::new(200, 50).save(&wide_icon).unwrap();
+        let mut config = create_test_config();
+        config.elements.note_block.icon_width = 40.0;
+        config.elements.note_block.icon_height = 40.0;
+        let xml = render(config, &note(Some(&wide_icon)));
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 40 * 9525, 10 * 9525)));
+        assert!(xml.contains(r#"<w:tcW w:w="800" w:type="dxa" />"#));
+        assert!(xml.contains(r#"<w:tcW w:w="7500" w:type="dxa" />"#));
+    }
 
-        // Test narrow characters
-        let narrow_text = "iii";
-        let narrow_width = generator.estimate_text_width(narrow_text);
-        assert_eq!(narrow_width, 3); // 3 narrow characters = 3 units
+    #[test]
+    fn test_long_paragraphs_split_or_rejected() {
+        let words = "lorem ipsum ".repeat(2_000);
+        let markdown = format!("{}\n\n```\n{}\n```\n", words, words);
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let mut config = create_test_config();
+        config.elements.long_paragraphs.max_chars = Some(5_000);
+
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let body = &xml[..xml.find("<w:tbl>").unwrap()];
+        let pieces: Vec<&str> = body.split("</w:p>").filter(|p| p.contains("lorem")).collect();
+        assert_eq!(pieces.len(), 5);
+        // The code block is not split
+        assert!(xml[xml.find("<w:tbl>").unwrap()..].contains(words.trim_end()));
+
+        config.elements.long_paragraphs.strict = true;
+        let error = DocxGenerator::new(config).generate(&document).unwrap_err();
+        assert!(matches!(error, ConversionError::ResourceLimit(_)));
+    }
 
-        // Test CJK characters
-        let cjk_text = "你好";
-        let cjk_width = generator.estimate_text_width(cjk_text);
-        assert_eq!(cjk_width, 4); // 2 CJK characters = 4 units
+    #[test]
+    fn test_generate_to_unseekable_writer() {
+        /// A writer that cannot seek, like a socket
+        struct Sink(Vec<u8>);
+        impl std::io::Write for Sink {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
 
-        // Test mixed content
-        let mixed_text = "Hello你好";
-        let mixed_width = generator.estimate_text_width(mixed_text);
-        assert_eq!(mixed_width, 10); // H(2) + e(1) + l(1) + l(1) + o(1) + 你(2) + 好(2) = 10 units
+        let document = crate::markdown::MarkdownParser::new().parse("# Streamed\n\nStraight to the writer.\n").unwrap();
+        let mut config = create_test_config();
+        config.output.self_check = false;
+        let mut sink = Sink(Vec::new());
+        DocxGenerator::new(config.clone()).generate_to_writer(&document, &mut sink).unwrap();
+
+        self_check::check_package(&sink.0).unwrap();
+        let xml = crate::test_utils::read_docx_part(&sink.0, "word/document.xml").unwrap();
+        assert!(xml.contains("Straight to the writer."));
+        assert_eq!(DocxGenerator::new(config).generate(&document).unwrap(), sink.0);
     }
 
     #[test]
-    fn test_table_with_auto_width() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+    fn test_qrcode_image_from_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("qrcode.png");
+        image::RgbImage::new(64, 64).save(&path).unwrap();
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("![qrcode](https://example.com/join)\n")
+            .unwrap();
+        let render = |config: ConversionConfig| {
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
+        };
+
+        // No image configured: placeholder, then the URL
+        let xml = render(create_test_config());
+        assert!(!xml.contains("<w:drawing>"));
+        assert!(xml.contains("[Image: qrcode - File not found: https://example.com/join]"));
+        assert!(xml.contains(">https://example.com/join</w:t>"));
+
+        let mut config = create_test_config();
+        config.elements.image.qrcode.path = Some(path.to_string_lossy().into_owned());
+        config.elements.image.qrcode.size = 80;
+        let xml = render(config.clone());
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 80 * 9525, 80 * 9525)));
+        assert_eq!(xml.matches(r#"<w:pStyle w:val="Center" />"#).count(), 2);
+
+        config.elements.image.qrcode.centered = false;
+        let xml = render(config);
+        assert!(xml.contains("<w:drawing>"));
+        assert!(!xml.contains(r#"<w:pStyle w:val="Center" />"#));
+    }
 
+    #[test]
+    fn test_prose_tabs_as_tabs_or_spaces() {
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Table {
-            headers: vec!["Short".to_string(), "Medium Length".to_string(), "Very Long Header Name".to_string()],
-            rows: vec![
-                vec!["A".to_string(), "Medium".to_string(), "This is a very long cell content that should make the column wider".to_string()],
-                vec!["B".to_string(), "Text".to_string(), "Short".to_string()],
-            ],
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![InlineElement::Text("\tIndented\tvalue".to_string())],
         });
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(r#"<w:tab /><w:t xml:space="preserve">Indented</w:t><w:tab /><w:t xml:space="preserve">value</w:t>"#), "{}", xml);
+        assert!(!xml.contains('\t'));
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let mut config = create_test_config();
+        config.elements.whitespace.tabs = ProseTabMode::Spaces;
+        config.elements.whitespace.tab_spaces = 2;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">  Indented  value</w:t>"));
+        assert!(!xml.contains("<w:tab />"));
     }
 
     #[test]
-    fn test_empty_table_handling() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
+    fn test_spacing_is_native_without_spacer_paragraphs() {
+        let markdown = "# Title\n\nIntro.\n\n```\nlet x = 1;\n```\n\n## Next\n\nMore.\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert!(!xml.contains('\u{00A0}'));
+        assert!(!xml.contains("<w:sz w:val=\"1\" />"));
+        // Every paragraph of the body has text: headings, the two body
+        // paragraphs and the code line
+        let paragraphs: Vec<&str> = xml.split("</w:p>").filter(|p| p.contains("<w:p ")).collect();
+        assert_eq!(paragraphs.len(), 5);
+        assert!(paragraphs.iter().all(|p| p.contains("<w:t")));
+
+        // The code block is spaced by styles.code_block.paragraph_spacing,
+        // keeping its line spacing
+        let code = paragraphs.iter().find(|p| p.contains(">let x = 1;</w:t>")).unwrap();
+        assert!(code.contains("w:before=\"120\"") && code.contains("w:after=\"120\""));
+        assert!(code.contains("w:line=\"240\""));
+
+        // Heading spacing in twips, from the configured points
+        let title = paragraphs.iter().find(|p| p.contains(">Title</w:t>")).unwrap();
+        assert!(title.contains("w:before=\"240\"") && title.contains("w:after=\"120\""));
+        let next = paragraphs.iter().find(|p| p.contains(">Next</w:t>")).unwrap();
+        assert!(next.contains("w:before=\"200\"") && next.contains("w:after=\"100\""));
+
+        // Named styles carry the spacing instead
+        let mut config = create_test_config();
+        config.styles.named_styles.enabled = true;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let styles = crate::test_utils::read_docx_part(&docx_bytes, "word/styles.xml").unwrap();
+        let heading1 = styles.split("</w:style>").find(|s| s.contains("w:styleId=\"Heading1\"")).unwrap();
+        assert!(heading1.contains("w:before=\"240\"") && heading1.contains("w:after=\"120\""));
+    }
 
-        // Test empty headers and rows
-        let widths = generator.calculate_column_widths(&[], &[]);
-        assert!(widths.is_empty());
+    #[test]
+    fn test_named_styles_replace_run_formatting() {
+        let mut config = create_test_config();
+        config.styles.named_styles.enabled = true;
+        let markdown = "# Overview\n\n## Details\n\nBody text.\n\n```\nlet x = 1;\n```\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let styles = crate::test_utils::read_docx_part(&docx_bytes, "word/styles.xml").unwrap();
+
+        let paragraph = |text: &str| xml.split("</w:p>").find(|p| p.contains(text)).unwrap().to_string();
+        assert!(paragraph(">Overview<").contains(r#"<w:pStyle w:val="Heading1" />"#));
+        assert!(paragraph(">Details<").contains(r#"<w:pStyle w:val="Heading2" />"#));
+        assert!(paragraph(">let x = 1;<").contains(r#"<w:pStyle w:val="CodeBlock" />"#));
+        for text in [">Overview<", ">Body text.<", ">let x = 1;<"] {
+            assert!(!paragraph(text).contains("<w:rFonts"), "{}", text);
+            assert!(!paragraph(text).contains("<w:sz "), "{}", text);
+        }
 
-        // Test empty headers with data
-        let rows = vec![vec!["data".to_string()]];
-        let widths = generator.calculate_column_widths(&[], &rows);
-        assert_eq!(widths.len(), 1);
+        // The styles part defines each style once, from the configuration
+        for level in 1..=6 {
+            let id = format!(r#"w:styleId="Heading{}""#, level);
+            assert_eq!(styles.matches(&id).count(), 1);
+            assert!(styles.contains(&format!(r#"<w:name w:val="heading {}" />"#, level)));
+        }
+        let heading1 = styles.split("</w:style>").find(|s| s.contains(r#"w:styleId="Heading1""#)).unwrap();
+        let size = (config.styles.headings[&1].font.size * 2.0) as usize;
+        assert!(heading1.contains(&format!(r#"<w:sz w:val="{}" />"#, size)), "{}", heading1);
+        let code = styles.split("</w:style>").find(|s| s.contains(r#"w:styleId="CodeBlock""#)).unwrap();
+        assert!(code.contains(&config.styles.code_block.font.family));
+
+        // Without named styles headings keep their run formatting
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(r#"<w:pStyle w:val="Heading1" />"#));
+        assert!(xml.split("</w:p>").find(|p| p.contains(">Overview<")).unwrap().contains("<w:rFonts"));
+    }
 
-        // Test headers with empty rows
-        let headers = vec!["Header".to_string()];
-        let widths = generator.calculate_column_widths(&headers, &[]);
-        assert_eq!(widths.len(), 1);
+    #[test]
+    fn test_inline_image_joins_next_paragraph() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("icon.png");
+        image::RgbImage::new(16, 16).save(&path).unwrap();
+
+        let mut config = create_test_config();
+        config.elements.image.placement = ImagePlacement::Inline;
+        let markdown = format!("![icon]({})\n\nFollowing text.", path.display());
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let paragraph = xml.split("</w:p>").find(|p| p.contains("<w:drawing>")).unwrap();
+        assert!(paragraph.contains("<wp:inline"));
+        assert!(paragraph.contains("Following text."));
     }
 
     #[test]
-    fn test_table_with_borders_and_auto_width() {
+    fn test_numbered_headings_bookmarked_by_number() {
         let mut config = create_test_config();
-        config.styles.table.border_width = 1.5;
-        let mut generator = DocxGenerator::new(config);
+        config.styles.heading_bookmarks.by_number = true;
+        for (level, format) in [(1, "%1."), (2, "%1.%2."), (3, "%1.%2.%3")] {
+            config.styles.headings.get_mut(&level).unwrap().numbering = Some(format.to_string());
+        }
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("# Scope\n\n# Terms\n\n## Parties\n\n## Term\n\n## Payment\n\n### Invoices\n\nSee [clause 2.3.1](#2.3.1) and [](#2.3.1).\n")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let paragraph = xml
+            .split("</w:p>")
+            .find(|p| p.contains(r#"w:name="2.3.1""#))
+            .expect("bookmark named 2.3.1");
+        assert!(paragraph.contains("Invoices"));
+        assert!(xml.split("</w:p>").any(|p| p.contains(r#"w:name="1""#) && p.contains("Scope")));
+
+        // Clause links jump to the bookmark; an empty one shows the number
+        assert_eq!(xml.matches(r#"<w:hyperlink w:anchor="2.3.1""#).count(), 2);
+        assert!(xml.contains(">clause 2.3.1</w:t>"));
+        assert!(xml.contains(">2.3.1</w:t>"));
+
+        // Ids stay unique alongside anchor bookmarks
+        let ids: Vec<&str> = xml
+            .split(r#"<w:bookmarkStart w:id=""#)
+            .skip(1)
+            .map(|rest| rest.split('"').next().unwrap())
+            .collect();
+        assert_eq!(ids, ["0", "1", "2", "3", "4", "5"]);
+    }
 
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Table {
-            headers: vec!["Name".to_string(), "Description".to_string()],
-            rows: vec![
-                vec!["Item 1".to_string(), "Short description".to_string()],
-                vec!["Item 2".to_string(), "This is a much longer description that should affect the column width".to_string()],
-            ],
-        });
+    #[test]
+    fn test_whitespace_cleanup_trims_prose_but_not_code() {
+        let markdown = "Ends with spaces&nbsp;&nbsp;\n\nSome **bold    text** and `code  span`\n\n```\nkeep  this  \n```\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let mut config = create_test_config();
+        config.elements.whitespace.trim_trailing = true;
+        config.elements.whitespace.collapse_spaces = true;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        assert!(xml.contains(">Ends with spaces</w:t>"));
+        assert!(xml.contains(">bold text</w:t>"));
+        assert!(xml.contains(">code  span</w:t>"));
+        let code = xml.split("</w:p>").find(|p| p.contains("keep")).unwrap();
+        assert!(code.contains("this  </w:t>"));
+
+        // Off by default
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">Ends with spaces </w:t>"));
+        assert!(xml.contains(">bold    text</w:t>"));
+    }
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    #[test]
+    fn test_kbd_and_samp_spans_styled_apart_from_inline_code() {
+        let markdown = "Press <kbd>Ctrl</kbd> to see <samp>output</samp> or `code`.\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let mut config = create_test_config();
+        config.styles.inline_html.samp.color = Some("#336699".to_string());
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let run_of = |text: &str| {
+            let end = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            let start = xml[..end].rfind("<w:r>").unwrap();
+            xml[start..end].to_string()
+        };
+        let kbd = run_of("Ctrl");
+        assert!(kbd.contains("<w:bdr w:val=\"single\""));
+        assert!(kbd.contains("w:ascii=\"Consolas\""));
+        let samp = run_of("output");
+        assert!(samp.contains("<w:color w:val=\"336699\" />"));
+        assert!(samp.contains("w:ascii=\"Consolas\""));
+        assert!(!samp.contains("<w:bdr"));
+        let code = run_of("code");
+        assert!(code.contains("w:ascii=\"Courier New\""));
+        assert!(!code.contains("<w:bdr"));
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    #[test]
+    fn test_index_terms_collected_into_alphabetized_index() {
+        let markdown = "Zebras graze <!-- index: zebra --> on grass.\n\nApples grow on trees.\n<!-- index: apple -->\n\n- More zebra facts <!-- index: zebra -->\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        // Markers are numbered in document order
+        let zebra_mark = xml.find(r#"w:name="_Idx0""#).expect("first zebra marked");
+        assert!(zebra_mark > xml.find(">Zebras graze </w:t>").unwrap());
+        assert!(xml.find(r#"w:name="_Idx1""#).unwrap() > xml.find(">Apples grow on trees.</w:t>").unwrap());
+        assert!(xml.contains(r#"w:name="_Idx2""#));
+
+        let heading = xml.find(">Index</w:t>").expect("index heading emitted");
+        let apple = xml.find(">apple</w:t>").unwrap();
+        let zebra = xml.find(">zebra</w:t>").unwrap();
+        assert!(heading < apple && apple < zebra);
+        assert!(xml[apple..zebra].contains("PAGEREF _Idx1 \\h"));
+        let zebra_entry = &xml[zebra..];
+        assert!(zebra_entry.find("PAGEREF _Idx0").unwrap() < zebra_entry.find("PAGEREF _Idx2").unwrap());
     }
 
     #[test]
-    fn test_paragraph_with_hard_breaks() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+    fn test_no_index_without_index_terms() {
+        let document = crate::markdown::MarkdownParser::new().parse("Plain text <!-- other -->\n").unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(">Index</w:t>"));
+        assert!(!xml.contains("_Idx"));
+    }
 
-        // Test paragraph with hard breaks (from <br /> tags)
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("First line\nSecond line\nThird line".to_string()),
-            ],
-        });
+    #[test]
+    fn test_revision_notes_section_in_document_order() {
+        let markdown = "Prices in euros. <!-- rev: switched from dollars -->\n\n- Shipping is free\n<!-- rev: free shipping added -->\n\nClosing text.\n\n<!-- rev:   reworded\n  the closing -->\n";
+        let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+        let mut config = create_test_config();
+        config.sections.revision_notes.title = "Changes".to_string();
+
+        // Dropped by default
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(">Changes</w:t>"));
+        assert!(!xml.contains("switched from dollars"));
+
+        config.elements.revision_notes.mode = RevisionNotesMode::Section;
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let heading = xml.find(">Changes</w:t>").expect("revision notes heading emitted");
+        assert!(heading > xml.find(">Closing text.</w:t>").unwrap());
+        let notes: Vec<usize> = ["1. switched from dollars", "2. free shipping added", "3. reworded the closing"]
+            .iter()
+            .map(|note| xml.find(&format!(">{}</w:t>", note)).expect(note))
+            .collect();
+        assert!(heading < notes[0] && notes[0] < notes[1] && notes[1] < notes[2]);
+
+        // As comments, anchored where they were written
+        config.elements.revision_notes.mode = RevisionNotesMode::Comments;
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains(">Changes</w:t>"));
+        assert_eq!(xml.matches("<w:commentRangeStart").count(), 3);
+        let comments = crate::test_utils::read_docx_part(&docx_bytes, "word/comments.xml").unwrap();
+        assert!(comments.contains("free shipping added"));
+    }
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+    #[test]
+    fn test_blockquote_attribution_right_aligned_and_italic() {
+        let mut config = create_test_config();
+        config.elements.quote_attribution.enabled = true;
+        let mut parser = crate::markdown::MarkdownParser::new();
+        parser.set_quote_attribution(true);
+        let render = |markdown: &str| {
+            let document = parser.parse(markdown).unwrap();
+            let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
+        };
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let xml = render("> Simplicity is prerequisite\n> for reliability.\n> \u{2014} Edsger Dijkstra\n");
+        let quote = xml.find(">Simplicity is prerequisite for reliability.</w:t>").expect("quote kept in one paragraph");
+        let attribution = xml.find(">\u{2014} Edsger Dijkstra</w:t>").expect("attribution split off");
+        let paragraph = &xml[xml[..attribution].rfind("<w:p ").unwrap()..attribution];
+        assert!(paragraph.contains(r#"<w:jc w:val="right" />"#));
+        assert!(paragraph.contains("<w:i />"));
+        let quote_paragraph = &xml[xml[..quote].rfind("<w:p ").unwrap()..quote];
+        assert!(!quote_paragraph.contains(r#"<w:jc w:val="right" />"#));
+
+        // Without an attribution line the quote is left alone
+        let xml = render("> Just a quote\n> \u{2014} with a dash mid-quote\n>\n> and more.\n");
+        assert!(!xml.contains(r#"<w:jc w:val="right" />"#));
+        assert!(!xml.contains("<w:i />"));
     }
 
     #[test]
-    fn test_paragraph_with_hard_breaks_and_formatting() {
+    fn test_nested_blockquotes_indent_and_border_by_depth() {
+        let render = |config: &crate::config::ConversionConfig, markdown: &str| {
+            let document = crate::markdown::MarkdownParser::new().parse(markdown).unwrap();
+            let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+            crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
+        };
+        let paragraph = |xml: &str, text: &str| {
+            let at = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            xml[xml[..at].rfind("<w:p ").unwrap()..at].to_string()
+        };
         let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
 
-        // Test paragraph with hard breaks and formatting
-        let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("First line with ".to_string()),
-                InlineElement::Bold("bold".to_string()),
-                InlineElement::Text("\nSecond line with ".to_string()),
-                InlineElement::Italic("italic".to_string()),
-                InlineElement::Text("\nThird line".to_string()),
-            ],
-        });
+        // Two levels
+        let xml = render(&config, "> Outer
+>
+> > Inner
+>
+> Outer again
+
+Plain
+");
+        for (text, indent, color) in [("Outer", 360, "BBBBBB"), ("Inner", 720, "999999"), ("Outer again", 360, "BBBBBB")] {
+            let paragraph = paragraph(&xml, text);
+            assert!(paragraph.contains(&format!(r#"w:left="{}""#, indent)), "{}", paragraph);
+            assert!(paragraph.contains(&format!(r#"w:space="4" w:sz="12" w:color="{}""#, color)), "{}", paragraph);
+        }
+        assert!(!paragraph(&xml, "Plain").contains("<w:pBdr>"));
+
+        // Three levels, with depths past the configured levels reusing the last
+        let xml = render(&config, "> One
+>
+> > Two
+> >
+> > > Three
+> > >
+> > > > Four
+");
+        for (text, indent, color) in [("One", 360, "BBBBBB"), ("Two", 720, "999999"), ("Three", 1080, "777777"), ("Four", 1440, "777777")] {
+            let paragraph = paragraph(&xml, text);
+            assert!(paragraph.contains(&format!(r#"w:left="{}""#, indent)), "{}", paragraph);
+            assert!(paragraph.contains(&format!(r#"w:color="{}""#, color)), "{}", paragraph);
+        }
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        // Per-depth indents, a level without a border
+        let mut config = create_test_config();
+        config.styles.blockquote.levels = vec![
+            crate::config::BlockQuoteLevel { indent: 24.0, border_color: None },
+            crate::config::BlockQuoteLevel { indent: 12.0, border_color: Some("#336699".to_string()) },
+        ];
+        let xml = render(&config, "> One
+>
+> > Two
+");
+        assert!(paragraph(&xml, "One").contains(r#"w:left="480""#));
+        assert!(!paragraph(&xml, "One").contains("<w:pBdr>"));
+        assert!(paragraph(&xml, "Two").contains(r#"w:left="720""#));
+        assert!(paragraph(&xml, "Two").contains(r#"w:color="336699""#));
+    }
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+    #[test]
+    fn test_blockquote_font_applies_to_quoted_text() {
+        let mut config = create_test_config();
+        config.styles.blockquote.font.italic = Some(true);
+        config.styles.blockquote.font.color = Some("#555555".to_string());
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("Before
+
+> Quoted **bold**
+
+After
+")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let paragraph = |text: &str| {
+            let at = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            let start = xml[..at].rfind("<w:p ").unwrap();
+            xml[start..start + xml[start..].find("</w:p>").unwrap()].to_string()
+        };
+        let quote = paragraph("Quoted ");
+        assert!(quote.contains("<w:i />"), "{}", quote);
+        assert!(quote.contains(r#"<w:color w:val="555555" />"#), "{}", quote);
+        assert!(quote.contains("<w:pBdr>"));
+        for text in ["Before", "After"] {
+            let plain = paragraph(text);
+            assert!(!plain.contains("<w:i />") && !plain.contains("<w:pBdr>"), "{}", plain);
+        }
     }
 
     #[test]
-    fn test_paragraph_with_multiple_consecutive_hard_breaks() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+    fn test_emphasis_only_paragraph_becomes_callout() {
+        let mut config = create_test_config();
+        config.elements.emphasis_callouts.enabled = true;
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("**Back up your data before upgrading.**\n\n**Note:** mixed paragraphs stay normal.\n")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let callout = xml.find(">Back up your data before upgrading.</w:t>").unwrap();
+        let paragraph = &xml[xml[..callout].rfind("<w:p ").unwrap()..callout];
+        assert!(paragraph.contains(r#"w:left="360""#));
+        assert!(paragraph.contains(r#"w:fill="F2F2F2""#));
+
+        let mixed = xml.find(">Note:</w:t>").unwrap();
+        let end = mixed + xml[mixed..].find("</w:p>").unwrap();
+        let paragraph = &xml[xml[..mixed].rfind("<w:p ").unwrap()..end];
+        assert!(!paragraph.contains(r#"w:left="360""#));
+        assert!(!paragraph.contains("F2F2F2"));
+    }
 
-        // Test paragraph with multiple consecutive hard breaks
+    #[test]
+    fn test_code_block_highlight_attribute_shades_lines() {
+        let document = crate::markdown::MarkdownParser::new()
+            .parse("```text {highlight=2}\nfirst line\nsecond line\nthird line\n```\n")
+            .unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let run_before = |text: &str| {
+            let at = xml.find(&format!(">{}</w:t>", text)).unwrap();
+            &xml[xml[..at].rfind("<w:r>").unwrap()..at]
+        };
+        assert!(run_before("second line").contains(r#"w:fill="FFF2CC""#));
+        assert!(!run_before("first line").contains("FFF2CC"));
+        assert!(!run_before("third line").contains("FFF2CC"));
+    }
+
+    #[test]
+    fn test_borderless_table_has_no_border_definitions() {
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("First line\n\nThird line (with empty line in between)".to_string()),
-            ],
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Item".to_string(), "Price".to_string()],
+            rows: vec![vec!["Widget".to_string(), "4.00".to_string()]],
+            links: vec![],
         });
+        let table_properties = |config: ConversionConfig| {
+            let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+            let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+            let start = xml.find("<w:tblPr>").unwrap();
+            let end = xml[start..].find("</w:tblPr>").unwrap();
+            xml[start..start + end].to_string()
+        };
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
-
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let mut config = create_test_config();
+        config.styles.table.border_width = 0.0;
+        let properties = table_properties(config.clone());
+        assert!(!properties.contains("<w:tblBorders"), "{}", properties);
+
+        // Nor does the table style bring borders of its own
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let styles = crate::test_utils::read_docx_part(&docx_bytes, "word/styles.xml").unwrap();
+        let start = styles.find(r#"w:styleId="MarkdownTable""#).unwrap();
+        let end = styles[start..].find("</w:style>").unwrap();
+        assert!(!styles[start..start + end].contains("<w:tblBorders"), "{}", &styles[start..start + end]);
     }
 
     #[test]
-    fn test_paragraph_without_hard_breaks() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
-
-        // Test normal paragraph without hard breaks
+    fn test_wide_table_moved_to_landscape_pages() {
+        let headers: Vec<String> = (1..=6).map(|n| format!("Measurement column number {}", n)).collect();
+        let rows = vec![headers.iter().map(|h| h.to_uppercase()).collect::<Vec<_>>()];
         let mut document = MarkdownDocument::new();
         document.add_element(MarkdownElement::Paragraph {
-            content: vec![
-                InlineElement::Text("This is a normal paragraph without any hard breaks.".to_string()),
-            ],
+            content: vec![InlineElement::Text("Results".to_string())],
+        });
+        document.add_element(MarkdownElement::Table { headers, rows, links: vec![] });
+        document.add_element(MarkdownElement::Table {
+            headers: vec!["Narrow".to_string()],
+            rows: vec![vec!["1".to_string()]],
+            links: vec![],
         });
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
+        // Kept as is by default
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(!xml.contains("landscape"));
 
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
+        let mut config = create_test_config();
+        config.styles.table.wide_tables.handling = crate::config::WideTableHandling::Landscape;
+        let docx_bytes = DocxGenerator::new(config.clone()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        let sections: Vec<&str> = xml.split("<w:sectPr>").skip(1).collect();
+        assert_eq!(sections.len(), 3);
+        assert!(sections[0].contains(r#"w:orient="portrait""#));
+        assert!(sections[1].contains(r#"w:orient="landscape""#));
+        // The wide table ends the landscape section; the narrow one is back in portrait
+        let wide = xml.find(">Measurement column number 1</w:t>").unwrap();
+        let landscape_end = xml.find(r#"w:orient="landscape""#).unwrap();
+        let narrow = xml.find(">Narrow</w:t>").unwrap();
+        assert!(xml.find("<w:sectPr>").unwrap() < wide && wide < landscape_end && landscape_end < narrow);
+        let landscape_width = ((config.document.page_size.height - config.document.margins.left - config.document.margins.right) * 20.0) as usize;
+        assert!(xml.contains(&format!(r#"<w:tblW w:w="{}" w:type="dxa" />"#, landscape_width)));
     }
 
     #[test]
-    fn test_parse_inline_formatting_plain_text() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
-        
-        let text = "# This is a comment";
-        let elements = generator.parse_inline_formatting(text);
-        
-        assert_eq!(elements.len(), 1);
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => {
-                assert_eq!(t, "# This is a comment");
-            }
-            _ => panic!("Expected Text element"),
-        }
+    fn test_html_img_rendered_at_explicit_size_within_bounds() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("chart.png");
+        image::RgbImage::new(40, 30).save(&path).unwrap();
+
+        let markdown = format!(
+            "<img src=\"{0}\" alt=\"chart\" width=\"120\" height=\"300\">\n\n<img src=\"{0}\" width=\"1200\" height=\"200\">",
+            path.display()
+        );
+        let document = crate::markdown::MarkdownParser::new().parse(&markdown).unwrap();
+        let docx_bytes = DocxGenerator::new(create_test_config()).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        // Exact size inside the 600x400 px bound; oversized keeps its ratio
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 120 * 9525, 300 * 9525)));
+        assert!(xml.contains(&format!("cx=\"{}\" cy=\"{}\"", 600 * 9525, 100 * 9525)));
     }
 
     #[test]
-    fn test_parse_inline_formatting_bold() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
-        
-        let text = "This is **bold** text";
-        let elements = generator.parse_inline_formatting(text);
-        
-        // Since Markdown is disabled by default, it returns the text as-is
-        assert_eq!(elements.len(), 1);
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is **bold** text"),
-            _ => panic!("Expected Text element"),
-        }
+    fn test_numbering_prefix_rendered_as_styled_run() {
+        let mut config = create_test_config();
+        let h1 = config.styles.headings.get_mut(&1).unwrap();
+        h1.numbering = Some("%1.".to_string());
+        h1.numbering_style = Some(crate::config::ClassStyle {
+            bold: Some(false),
+            color: Some("#CC0000".to_string()),
+            ..Default::default()
+        });
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading { level: 1, text: "Intro".to_string() });
+        let mut generator = DocxGenerator::new(config);
+        let docx_bytes = generator.generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+
+        let runs: Vec<&str> = xml.split("<w:r>").filter(|run| run.contains("<w:t")).collect();
+        let prefix = runs.iter().find(|run| run.contains(">1.</w:t>")).expect("prefix run");
+        let text = runs.iter().find(|run| run.contains("> Intro</w:t>")).expect("text run");
+        assert!(prefix.contains("<w:color w:val=\"CC0000\" />"));
+        assert!(prefix.contains("<w:b w:val=\"false\" />"));
+        assert!(!text.contains("CC0000"));
+        assert!(text.contains("<w:b />"));
     }
 
     #[test]
-    fn test_parse_inline_formatting_italic() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
-        
-        let text = "This is *italic* text";
-        let elements = generator.parse_inline_formatting(text);
-        
-        // Since Markdown is disabled by default, it returns the text as-is
-        assert_eq!(elements.len(), 1);
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "This is *italic* text"),
-            _ => panic!("Expected Text element"),
-        }
+    fn test_numbering_prefix_shares_run_without_style() {
+        let mut config = create_test_config();
+        config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading { level: 1, text: "Intro".to_string() });
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains(">1. Intro</w:t>"));
     }
 
-    #[test]
-    fn test_parse_inline_formatting_mixed() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
-        
-        let text = "# Comment with **bold** and *italic*";
-        let elements = generator.parse_inline_formatting(text);
-        
-        // Since Markdown is disabled by default, it returns the text as-is
-        assert_eq!(elements.len(), 1);
-        match &elements[0] {
-            crate::markdown::InlineElement::Text(t) => assert_eq!(t, "# Comment with **bold** and *italic*"),
-            _ => panic!("Expected Text element"),
-        }
+    fn heading_with_separator(separator: NumberingSeparator) -> String {
+        let mut config = create_test_config();
+        let h1 = config.styles.headings.get_mut(&1).unwrap();
+        h1.numbering = Some("%1.".to_string());
+        h1.numbering_separator = separator;
+        h1.numbering_tab_stop = Some(54.0);
+
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Heading { level: 1, text: "Intro".to_string() });
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
+        crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap()
     }
 
     #[test]
-    fn test_parse_inline_formatting_unclosed_markers() {
-        let config = create_test_config();
-        let generator = DocxGenerator::new(config);
-        
-        // Unclosed bold marker - should be treated as literal text
-        let text = "This is **bold without closing";
-        let elements = generator.parse_inline_formatting(text);
-        
-        // The parser will try to find closing ** and fail, so it treats ** as literal
-        // Result: "This is ", "**bold without closing"
-        assert!(elements.len() >= 1);
-        
-        // Check that the text contains the original content
-        let combined_text: String = elements.iter().map(|e| match e {
-            crate::markdown::InlineElement::Text(t) => t.clone(),
-            crate::markdown::InlineElement::Bold(t) => format!("**{}**", t),
-            crate::markdown::InlineElement::Italic(t) => format!("*{}*", t),
-            _ => String::new(),
-        }).collect();
-        
-        assert!(combined_text.contains("**bold without closing") || combined_text == text);
+    fn test_numbering_separator_options() {
+        assert!(heading_with_separator(NumberingSeparator::Space).contains(">1. Intro</w:t>"));
+        assert!(heading_with_separator(NumberingSeparator::EmSpace).contains(">1.\u{2003}Intro</w:t>"));
+        let custom = heading_with_separator(NumberingSeparator::Custom(" – ".to_string()));
+        assert!(custom.contains(">1. – Intro</w:t>"));
+        assert!(!custom.contains("<w:tab "));
     }
 
     #[test]
-    fn test_code_block_with_hash_comments() {
-        let config = create_test_config();
-        let mut generator = DocxGenerator::new(config);
+    fn test_tab_separator_emits_tab_run_and_stop() {
+        let xml = heading_with_separator(NumberingSeparator::Tab);
+        assert!(xml.contains(">1.</w:t>"));
+        assert!(xml.contains(">Intro</w:t>"));
+        assert!(xml.contains("<w:tab />"));
+        assert!(xml.contains("<w:tab w:val=\"left\" w:pos=\"1080\" />"));
+    }
 
-        let code_with_hash = "# This is a comment\necho \"Hello\"\n# Another comment\ncargo build";
+    #[test]
+    fn test_default_tab_stop_in_settings_and_numbering() {
+        let mut config = create_test_config();
+        config.document.default_tab_stop = 45.0;
+        let h1 = config.styles.headings.get_mut(&1).unwrap();
+        h1.numbering = Some("%1.".to_string());
+        h1.numbering_separator = NumberingSeparator::Tab;
 
         let mut document = MarkdownDocument::new();
-        document.add_element(MarkdownElement::CodeBlock {
-            language: Some("bash".to_string()),
-            code: code_with_hash.to_string(),
-            processed: None,
-        });
+        document.add_element(MarkdownElement::Heading { level: 1, text: "Intro".to_string() });
+        let docx_bytes = DocxGenerator::new(config).generate(&document).unwrap();
 
-        let result = generator.generate(&document);
-        assert!(result.is_ok());
-
-        let docx_bytes = result.unwrap();
-        assert!(!docx_bytes.is_empty());
-        
-        // Verify that the code block cell can be created with hash comments
-        let cell_result = generator.create_code_block_cell_with_markdown(
-            code_with_hash,
-            &generator.config.styles.code_block
-        );
-        assert!(cell_result.is_ok());
+        let settings = crate::test_utils::read_docx_part(&docx_bytes, "word/settings.xml").unwrap();
+        assert!(settings.contains("<w:defaultTabStop w:val=\"900\" />"));
+        let xml = crate::test_utils::read_docx_part(&docx_bytes, "word/document.xml").unwrap();
+        assert!(xml.contains("<w:tab w:val=\"left\" w:pos=\"900\" />"));
     }
 }
+
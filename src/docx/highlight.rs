@@ -0,0 +1,85 @@
+//! Word highlight colors
+//!
+//! `w:highlight` only accepts the sixteen named colors below; Word ignores
+//! anything else, including hex values. Configured colors are mapped to the
+//! nearest named color. Exact backgrounds need run or cell shading instead.
+
+/// Word's highlight palette, as `(name, [r, g, b])`
+pub const WORD_HIGHLIGHT_COLORS: &[(&str, [u8; 3])] = &[
+    ("black", [0x00, 0x00, 0x00]),
+    ("blue", [0x00, 0x00, 0xFF]),
+    ("cyan", [0x00, 0xFF, 0xFF]),
+    ("green", [0x00, 0xFF, 0x00]),
+    ("magenta", [0xFF, 0x00, 0xFF]),
+    ("red", [0xFF, 0x00, 0x00]),
+    ("yellow", [0xFF, 0xFF, 0x00]),
+    ("white", [0xFF, 0xFF, 0xFF]),
+    ("darkBlue", [0x00, 0x00, 0x80]),
+    ("darkCyan", [0x00, 0x80, 0x80]),
+    ("darkGreen", [0x00, 0x80, 0x00]),
+    ("darkMagenta", [0x80, 0x00, 0x80]),
+    ("darkRed", [0x80, 0x00, 0x00]),
+    ("darkYellow", [0x80, 0x80, 0x00]),
+    ("darkGray", [0x80, 0x80, 0x80]),
+    ("lightGray", [0xC0, 0xC0, 0xC0]),
+];
+
+/// Word highlight name for a palette name (any case) or a `#rrggbb` color,
+/// choosing the nearest palette entry for the latter
+pub fn nearest_highlight(color: &str) -> Option<&'static str> {
+    let color = color.trim();
+    if let Some((name, _)) = WORD_HIGHLIGHT_COLORS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(color))
+    {
+        return Some(name);
+    }
+
+    let rgb = parse_hex(color)?;
+    WORD_HIGHLIGHT_COLORS
+        .iter()
+        .min_by_key(|(_, palette)| {
+            palette
+                .iter()
+                .zip(rgb)
+                .map(|(&a, b)| (i32::from(a) - i32::from(b)).pow(2))
+                .sum::<i32>()
+        })
+        .map(|(name, _)| *name)
+}
+
+/// Parse `#rrggbb` or `rrggbb`
+fn parse_hex(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_colors_map_to_nearest_highlight() {
+        assert_eq!(nearest_highlight("#FFFF00"), Some("yellow"));
+        assert_eq!(nearest_highlight("ffff66"), Some("yellow"));
+        assert_eq!(nearest_highlight("#D3D3D3"), Some("lightGray"));
+        assert_eq!(nearest_highlight("#f0f0f0"), Some("white"));
+        assert_eq!(nearest_highlight("#8B0000"), Some("darkRed"));
+        assert_eq!(nearest_highlight("#00CED1"), Some("cyan"));
+        assert_eq!(nearest_highlight("#228B22"), Some("darkGreen"));
+        assert_eq!(nearest_highlight("#000000"), Some("black"));
+    }
+
+    #[test]
+    fn test_palette_names_accepted_and_invalid_rejected() {
+        assert_eq!(nearest_highlight("darkBlue"), Some("darkBlue"));
+        assert_eq!(nearest_highlight("LIGHTGRAY"), Some("lightGray"));
+        assert_eq!(nearest_highlight("#FFF"), None);
+        assert_eq!(nearest_highlight("orange"), None);
+        assert_eq!(nearest_highlight("#GGGGGG"), None);
+    }
+}
@@ -0,0 +1,310 @@
+//! Downloads of remote images, optionally through an on-disk cache
+//!
+//! Each URL is stored as `<key>.bin` with the image bytes and `<key>.json`
+//! with the URL, its content type, its `ETag`/`Last-Modified` validators and
+//! when the entry was last known to be current. Cache read and write failures are logged
+//! and otherwise ignored, so a broken cache only costs a download.
+
+use crate::config::RemoteImageCacheConfig;
+use crate::error::ConversionError;
+use reqwest::blocking::Client;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// A downloaded image
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteImage {
+    pub data: Vec<u8>,
+    /// `Content-Type` the image was served with
+    pub content_type: Option<String>,
+}
+
+/// Download an image without caching it, refusing one over `max_bytes`
+pub fn download(client: &Client, url: &str, max_bytes: u64) -> Result<RemoteImage, ConversionError> {
+    let response = client.get(url).send()?.error_for_status()?;
+    let too_large = |size: u64| {
+        ConversionError::ResourceLimit(format!("image {} is {} bytes, over the {} byte limit", url, size, max_bytes))
+    };
+    if let Some(size) = response.content_length().filter(|&size| size > max_bytes) {
+        return Err(too_large(size));
+    }
+    let content_type = header_value(&response, CONTENT_TYPE);
+    let data = response.bytes()?.to_vec();
+    if data.len() as u64 > max_bytes {
+        return Err(too_large(data.len() as u64));
+    }
+    Ok(RemoteImage { data, content_type })
+}
+
+fn header_value(response: &reqwest::blocking::Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Downloads remote images through an on-disk cache
+#[derive(Debug, Clone)]
+pub struct RemoteImageCache {
+    directory: PathBuf,
+    ttl_millis: u64,
+    max_bytes: u64,
+}
+
+/// Contents of an entry's `.json` file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EntryMetadata {
+    url: String,
+    #[serde(default)]
+    content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// When the entry was stored or last revalidated, in ms since the epoch
+    checked_at: u64,
+    size: u64,
+}
+
+impl RemoteImageCache {
+    pub fn new(config: &RemoteImageCacheConfig) -> Self {
+        Self {
+            directory: PathBuf::from(&config.directory),
+            ttl_millis: config.ttl_seconds.saturating_mul(1000),
+            max_bytes: config.max_bytes,
+        }
+    }
+
+    /// Image for `url`, from the cache while fresh and otherwise
+    /// downloaded, with a conditional request when a stale entry exists
+    pub fn fetch(&self, client: &Client, url: &str) -> Result<RemoteImage, ConversionError> {
+        let cached = self.load(url);
+        if let Some((ref metadata, ref data)) = cached {
+            if now_millis().saturating_sub(metadata.checked_at) < self.ttl_millis {
+                debug!(url = %url, "Remote image served from cache");
+                return Ok(RemoteImage { data: data.clone(), content_type: metadata.content_type.clone() });
+            }
+        }
+
+        let mut request = client.get(url);
+        if let Some((ref metadata, _)) = cached {
+            if let Some(ref etag) = metadata.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(ref last_modified) = metadata.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send()?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            if let Some((mut metadata, data)) = cached {
+                debug!(url = %url, "Cached remote image revalidated");
+                metadata.checked_at = now_millis();
+                self.write_metadata(&metadata);
+                return Ok(RemoteImage { data, content_type: metadata.content_type });
+            }
+        }
+
+        let response = response.error_for_status()?;
+        let content_type = header_value(&response, CONTENT_TYPE);
+        let etag = header_value(&response, ETAG);
+        let last_modified = header_value(&response, LAST_MODIFIED);
+        let data = response.bytes()?.to_vec();
+
+        self.store(EntryMetadata {
+            url: url.to_string(),
+            content_type: content_type.clone(),
+            etag,
+            last_modified,
+            checked_at: now_millis(),
+            size: data.len() as u64,
+        }, &data);
+        Ok(RemoteImage { data, content_type })
+    }
+
+    /// Cached metadata and bytes for `url`, if present and readable
+    fn load(&self, url: &str) -> Option<(EntryMetadata, Vec<u8>)> {
+        let key = cache_key(url);
+        let metadata: EntryMetadata =
+            serde_json::from_slice(&fs::read(self.path(&key, "json")).ok()?).ok()?;
+        if metadata.url != url {
+            return None;
+        }
+        let data = fs::read(self.path(&key, "bin")).ok()?;
+        Some((metadata, data))
+    }
+
+    /// Write an entry, then evict the oldest others while over the size cap
+    fn store(&self, metadata: EntryMetadata, data: &[u8]) {
+        if metadata.size > self.max_bytes {
+            debug!(url = %metadata.url, "Remote image larger than the cache; not cached");
+            return;
+        }
+        if let Err(e) = fs::create_dir_all(&self.directory)
+            .and_then(|_| fs::write(self.path(&cache_key(&metadata.url), "bin"), data))
+        {
+            warn!(url = %metadata.url, error = %e, "Failed to write image cache entry");
+            return;
+        }
+        self.write_metadata(&metadata);
+        self.evict(&metadata.url);
+    }
+
+    fn write_metadata(&self, metadata: &EntryMetadata) {
+        let path = self.path(&cache_key(&metadata.url), "json");
+        let result = serde_json::to_vec(metadata)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&path, json));
+        if let Err(e) = result {
+            warn!(url = %metadata.url, error = %e, "Failed to write image cache metadata");
+        }
+    }
+
+    /// Remove the least recently checked entries, except `keep_url`, until
+    /// the cache fits in `max_bytes`
+    fn evict(&self, keep_url: &str) {
+        let Ok(entries) = fs::read_dir(&self.directory) else {
+            return;
+        };
+        let mut entries: Vec<EntryMetadata> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                serde_json::from_slice(&fs::read(path).ok()?).ok()
+            })
+            .collect();
+
+        let mut total: u64 = entries.iter().map(|entry| entry.size).sum();
+        entries.sort_by_key(|entry| entry.checked_at);
+        for entry in entries.iter().filter(|entry| entry.url != keep_url) {
+            if total <= self.max_bytes {
+                break;
+            }
+            let key = cache_key(&entry.url);
+            debug!(url = %entry.url, "Evicting cached remote image");
+            let _ = fs::remove_file(self.path(&key, "bin"));
+            let _ = fs::remove_file(self.path(&key, "json"));
+            total -= entry.size;
+        }
+    }
+
+    fn path(&self, key: &str, extension: &str) -> PathBuf {
+        self.directory.join(format!("{}.{}", key, extension))
+    }
+}
+
+/// File name for a URL: its 64-bit FNV-1a hash in hex, stable across builds
+fn cache_key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn cache_config(directory: &std::path::Path, ttl_seconds: u64, max_bytes: u64) -> RemoteImageCacheConfig {
+        RemoteImageCacheConfig {
+            directory: directory.to_string_lossy().into_owned(),
+            ttl_seconds,
+            max_bytes,
+        }
+    }
+
+    /// Fetch the URLs in order off the async runtime, as the blocking client requires
+    async fn fetch_all(config: RemoteImageCacheConfig, urls: Vec<String>) -> Vec<Vec<u8>> {
+        tokio::task::spawn_blocking(move || {
+            let cache = RemoteImageCache::new(&config);
+            let client = Client::new();
+            urls.iter().map(|url| cache.fetch(&client, url).unwrap().data).collect()
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_fresh_entry_served_without_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"png-bytes".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let url = format!("{}/logo.png", server.uri());
+        let results = fetch_all(cache_config(dir.path(), 3600, 1024), vec![url.clone(), url]).await;
+        assert_eq!(results, vec![b"png-bytes".to_vec(), b"png-bytes".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_stale_entry_revalidated_with_conditional_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .and(header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/logo.png"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_bytes(b"png-bytes".to_vec()),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let url = format!("{}/logo.png", server.uri());
+        let results = fetch_all(cache_config(dir.path(), 0, 1024), vec![url.clone(), url]).await;
+        assert_eq!(results[1], b"png-bytes");
+    }
+
+    #[tokio::test]
+    async fn test_oldest_entries_evicted_over_size_cap() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"123456".to_vec()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = cache_config(dir.path(), 3600, 10);
+        let first = format!("{}/a.png", server.uri());
+        let second = format!("{}/b.png", server.uri());
+        fetch_all(config.clone(), vec![first.clone(), second.clone()]).await;
+
+        let cache = RemoteImageCache::new(&config);
+        assert!(cache.load(&first).is_none());
+        assert!(cache.load(&second).is_some());
+    }
+
+    #[test]
+    fn test_cache_key_is_stable() {
+        assert_eq!(cache_key(""), "cbf29ce484222325");
+        assert_ne!(cache_key("https://a/x.png"), cache_key("https://a/y.png"));
+    }
+}
@@ -0,0 +1,101 @@
+//! Downscaling of embedded image pixel data
+//!
+//! Display size is set separately on the drawing, so shrinking the pixel data
+//! only reduces the package size. Images larger than the configured limit are
+//! resized, keeping their aspect ratio, and re-encoded as PNG (the format
+//! docx-rs embeds every image as).
+
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageReader};
+use std::io::Cursor;
+use tracing::{debug, warn};
+
+/// Downscale image data so neither side exceeds `max_dimension` pixels
+///
+/// Images within the limit, and data that cannot be decoded or re-encoded,
+/// are returned unchanged; the latter with a warning.
+pub fn limit_dimensions(data: Vec<u8>, max_dimension: u32, source: &str) -> Vec<u8> {
+    match pixel_dimensions(&data) {
+        Some((width, height)) if width <= max_dimension && height <= max_dimension => return data,
+        Some(_) => {}
+        None => {
+            warn!(image = %source, "Cannot read image dimensions; embedding as-is");
+            return data;
+        }
+    }
+
+    let image = match image::load_from_memory(&data) {
+        Ok(image) => image,
+        Err(e) => {
+            warn!(image = %source, error = %e, "Cannot decode image for downscaling; embedding as-is");
+            return data;
+        }
+    };
+    let resized = image.resize(max_dimension, max_dimension, FilterType::Triangle);
+
+    let mut encoded = Cursor::new(Vec::new());
+    if let Err(e) = resized.write_to(&mut encoded, ImageFormat::Png) {
+        warn!(image = %source, error = %e, "Cannot re-encode downscaled image; embedding as-is");
+        return data;
+    }
+    debug!(
+        image = %source,
+        from = %format!("{}x{}", image.width(), image.height()),
+        to = %format!("{}x{}", resized.width(), resized.height()),
+        "Downscaled image before embedding"
+    );
+    encoded.into_inner()
+}
+
+/// Width and height in pixels of image data, read from its header
+pub fn pixel_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// A noisy PNG so compression cannot hide the pixel count
+    fn noisy_png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            let seed = x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503);
+            Rgb([seed as u8, (seed >> 8) as u8, (seed >> 16) as u8])
+        });
+        let mut buf = Cursor::new(Vec::new());
+        image.write_to(&mut buf, ImageFormat::Png).unwrap();
+        buf.into_inner()
+    }
+
+    fn dimensions(data: &[u8]) -> (u32, u32) {
+        ImageReader::new(Cursor::new(data))
+            .with_guessed_format()
+            .unwrap()
+            .into_dimensions()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_oversized_png_is_reencoded_smaller() {
+        let original = noisy_png(800, 400);
+        let scaled = limit_dimensions(original.clone(), 200, "test.png");
+        assert_eq!(dimensions(&scaled), (200, 100));
+        assert!(scaled.len() < original.len() / 4);
+    }
+
+    #[test]
+    fn test_small_image_untouched() {
+        let original = noisy_png(40, 30);
+        assert_eq!(limit_dimensions(original.clone(), 200, "small.png"), original);
+    }
+
+    #[test]
+    fn test_undecodable_data_embedded_as_is() {
+        let data = b"not an image at all".to_vec();
+        assert_eq!(limit_dimensions(data.clone(), 10, "broken.png"), data);
+    }
+}
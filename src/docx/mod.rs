@@ -1,7 +1,17 @@
 //! docx generation module
 
 pub mod generator;
+pub mod highlight;
+pub mod image_cache;
+pub mod image_scaling;
+pub mod outline;
+pub mod package;
+pub mod postprocess;
+pub mod provenance;
+pub mod self_check;
 pub mod styles;
+pub mod watermark;
 
-pub use generator::DocxGenerator;
+pub use generator::{DocxGenerator, GeneratorTimings, SUPPORTED_IMAGE_FORMATS};
+pub use outline::OutlineEntry;
 pub use styles::*;
\ No newline at end of file
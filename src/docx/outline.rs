@@ -0,0 +1,204 @@
+//! Heading outline of a document as it would be rendered
+//!
+//! Entries are produced by the generator's own numbering pass, so the numbers
+//! always agree with the headings written to the docx.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A heading as it appears in the generated document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineEntry {
+    /// Heading level (1-6)
+    pub level: u8,
+    /// Heading text including any numbering prefix
+    pub numbered_text: String,
+    /// Heading text without numbering
+    pub text: String,
+    /// Unique anchor id derived from the text, e.g. `getting-started`
+    pub anchor: String,
+    /// Name of the Word bookmark on the heading, e.g. `getting_started`,
+    /// when heading bookmarks are enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bookmark: Option<String>,
+}
+
+/// Allocates unique anchor ids in document order
+///
+/// Ids follow the common Markdown convention: lowercase, spaces become `-`,
+/// punctuation is dropped and repeated ids get a `-1`, `-2`... suffix.
+#[derive(Debug, Default)]
+pub struct AnchorAllocator {
+    taken: HashSet<String>,
+    next_suffix: HashMap<String, usize>,
+}
+
+impl AnchorAllocator {
+    /// Create an allocator with no ids taken
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the next unique anchor for a heading text
+    pub fn allocate(&mut self, text: &str) -> String {
+        let mut base = slugify(text);
+        if base.is_empty() {
+            base = "section".to_string();
+        }
+        let suffix = self.next_suffix.entry(base.clone()).or_insert(0);
+        loop {
+            let candidate = match *suffix {
+                0 => base.clone(),
+                n => format!("{}-{}", base, n),
+            };
+            *suffix += 1;
+            if self.taken.insert(candidate.clone()) {
+                return candidate;
+            }
+        }
+    }
+}
+
+/// Longest bookmark name Word accepts
+pub const MAX_BOOKMARK_LENGTH: usize = 40;
+
+/// Allocates unique Word bookmark names for anchors in document order
+///
+/// Word only accepts letters, digits and `_` in bookmark names, at most
+/// [`MAX_BOOKMARK_LENGTH`] characters, starting with a letter. Anchors are
+/// mapped onto that alphabet and names that collide after the mapping or
+/// truncation get a `_1`, `_2`... suffix.
+#[derive(Debug, Default)]
+pub struct BookmarkAllocator {
+    taken: HashSet<String>,
+}
+
+impl BookmarkAllocator {
+    /// Create an allocator with no names taken
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of names handed out so far
+    pub fn len(&self) -> usize {
+        self.taken.len()
+    }
+
+    /// Whether no names have been handed out yet
+    pub fn is_empty(&self) -> bool {
+        self.taken.is_empty()
+    }
+
+    /// Return the next unique bookmark name for an anchor
+    pub fn allocate(&mut self, anchor: &str) -> String {
+        let mut base: String = anchor
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        if !base.starts_with(char::is_alphabetic) {
+            base.insert_str(0, "h_");
+        }
+        let mut suffix = 0;
+        loop {
+            let tail = match suffix {
+                0 => String::new(),
+                n => format!("_{}", n),
+            };
+            let room = MAX_BOOKMARK_LENGTH - tail.len();
+            let candidate: String = base.chars().take(room).chain(tail.chars()).collect();
+            suffix += 1;
+            if self.taken.insert(candidate.clone()) {
+                return candidate;
+            }
+        }
+    }
+}
+
+/// Bookmark name for a heading numbering prefix: the number without its
+/// surrounding punctuation, so `2.3.1.` becomes `2.3.1`
+///
+/// These names fall outside the alphabet Word's bookmark dialog allows but
+/// are valid in the file format and work as hyperlink targets.
+pub fn number_bookmark(prefix: &str) -> Option<String> {
+    let number = prefix.trim_matches(|c: char| !c.is_alphanumeric());
+    (!number.is_empty()).then(|| number.chars().take(MAX_BOOKMARK_LENGTH).collect())
+}
+
+/// Whether a link target (without the `#`) refers to a clause number such
+/// as `2.3.1`: dot-separated parts, the first starting with a digit
+pub fn is_clause_number(target: &str) -> bool {
+    target.starts_with(|c: char| c.is_ascii_digit())
+        && target
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(char::is_alphanumeric))
+}
+
+/// Convert heading text to an anchor slug
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                Some(c.to_lowercase().collect::<String>())
+            } else if c.is_whitespace() {
+                Some("-".to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Getting Started"), "getting-started");
+        assert_eq!(slugify("What's new in v2.0?"), "whats-new-in-v20");
+        assert_eq!(slugify("  Ünïcode_heading "), "ünïcode_heading");
+    }
+
+    #[test]
+    fn test_duplicate_anchors_are_suffixed() {
+        let mut anchors = AnchorAllocator::new();
+        assert_eq!(anchors.allocate("Usage"), "usage");
+        assert_eq!(anchors.allocate("Usage"), "usage-1");
+        assert_eq!(anchors.allocate("Usage"), "usage-2");
+        assert_eq!(anchors.allocate("Usage 1"), "usage-1-1");
+        assert_eq!(anchors.allocate("Intro 1"), "intro-1");
+        assert_eq!(anchors.allocate("Intro"), "intro");
+        assert_eq!(anchors.allocate("Intro"), "intro-2");
+        assert_eq!(anchors.allocate("!!!"), "section");
+    }
+
+    #[test]
+    fn test_bookmark_names_are_valid_and_unique() {
+        let mut bookmarks = BookmarkAllocator::new();
+        assert_eq!(bookmarks.allocate("getting-started"), "getting_started");
+        assert_eq!(bookmarks.allocate("getting_started"), "getting_started_1");
+        assert_eq!(bookmarks.allocate("2-results"), "h_2_results");
+
+        let long = "a".repeat(60);
+        let first = bookmarks.allocate(&long);
+        let second = bookmarks.allocate(&format!("{}-b", long));
+        assert_eq!(first.len(), MAX_BOOKMARK_LENGTH);
+        assert_eq!(second.len(), MAX_BOOKMARK_LENGTH);
+        assert!(second.ends_with("_1"));
+        assert_eq!(bookmarks.len(), 5);
+    }
+
+    #[test]
+    fn test_number_bookmarks_and_clause_references() {
+        assert_eq!(number_bookmark("2.3.1.").as_deref(), Some("2.3.1"));
+        assert_eq!(number_bookmark("(iv)").as_deref(), Some("iv"));
+        assert_eq!(number_bookmark(" - "), None);
+        assert!(is_clause_number("2.3.1"));
+        assert!(is_clause_number("4"));
+        assert!(is_clause_number("1.a"));
+        assert!(!is_clause_number("usage"));
+        assert!(!is_clause_number("2..1"));
+        assert!(!is_clause_number("2.3."));
+    }
+}
@@ -0,0 +1,186 @@
+//! Fixups applied to the rendered package parts before they are zipped
+//!
+//! Some WordprocessingML that matters to Word is not exposed by docx-rs, such
+//! as the repeating table header flag or the core document title. These
+//! helpers patch the XML produced by `Docx::build` directly.
+
+use tracing::debug;
+
+/// Table style applied to tables converted from Markdown
+pub const MARKDOWN_TABLE_STYLE: &str = "MarkdownTable";
+
+/// Paragraph style applied to list items
+pub const LIST_PARAGRAPH_STYLE: &str = "ListParagraph";
+
+/// Mark the header row of every Markdown table as a table header (`w:tblHeader`)
+///
+/// Header rows are emitted with `w:cantSplit`; only the first row of a table
+/// using [`MARKDOWN_TABLE_STYLE`] is considered.
+pub fn mark_table_header_rows(document_xml: &str) -> String {
+    let style_marker = format!("<w:tblStyle w:val=\"{}\" />", MARKDOWN_TABLE_STYLE);
+    let header_props = "<w:trPr><w:cantSplit /></w:trPr>";
+    let marked_props = "<w:trPr><w:cantSplit /><w:tblHeader /></w:trPr>";
+
+    let mut result = String::with_capacity(document_xml.len() + 64);
+    let mut rest = document_xml;
+    let mut marked = 0;
+
+    while let Some(pos) = rest.find(&style_marker) {
+        let after_style = pos + style_marker.len();
+        result.push_str(&rest[..after_style]);
+        rest = &rest[after_style..];
+
+        // The first row of this table follows its properties and grid
+        if let Some(row_pos) = rest.find("<w:trPr") {
+            if rest[row_pos..].starts_with(header_props) {
+                result.push_str(&rest[..row_pos]);
+                result.push_str(marked_props);
+                rest = &rest[row_pos + header_props.len()..];
+                marked += 1;
+            }
+        }
+    }
+    result.push_str(rest);
+
+    debug!(tables = marked, "Marked table header rows");
+    result
+}
+
+/// Prefix of the hyperlink anchors that stand in for tooltips
+const TOOLTIP_ANCHOR_PREFIX: &str = "_Tooltip:";
+
+/// Anchor for a hyperlink that only shows `tooltip`, turned into a
+/// `w:tooltip` attribute by [`set_hyperlink_tooltips`]
+///
+/// docx-rs cannot write tooltips, so the text travels in the anchor.
+pub fn tooltip_anchor(tooltip: &str) -> String {
+    format!("{}{}", TOOLTIP_ANCHOR_PREFIX, tooltip)
+}
+
+/// Replace the anchors made by [`tooltip_anchor`] with `w:tooltip`
+/// attributes, leaving hyperlinks that go nowhere but show the tooltip
+pub fn set_hyperlink_tooltips(document_xml: &str) -> String {
+    let marker = format!("w:anchor=\"{}", TOOLTIP_ANCHOR_PREFIX);
+    document_xml.replace(&marker, "w:tooltip=\"")
+}
+
+/// Prefix of the run style ids that stand in for run languages
+const LANGUAGE_STYLE_PREFIX: &str = "_Lang:";
+
+/// Run style id for a run in `language` whose real style is `style`,
+/// turned into a `w:lang` element by [`set_run_languages`]
+///
+/// docx-rs cannot write run languages, so the language travels in the style.
+pub fn language_style(language: &str, style: Option<&str>) -> String {
+    match style {
+        Some(style) => format!("{}{}:{}", LANGUAGE_STYLE_PREFIX, language, style),
+        None => format!("{}{}", LANGUAGE_STYLE_PREFIX, language),
+    }
+}
+
+/// Replace the run styles made by [`language_style`] with `w:lang`
+/// elements, restoring the real style of runs that have one
+pub fn set_run_languages(document_xml: &str) -> String {
+    let marker = format!("<w:rStyle w:val=\"{}", LANGUAGE_STYLE_PREFIX);
+    if !document_xml.contains(&marker) {
+        return document_xml.to_string();
+    }
+
+    let mut result = String::with_capacity(document_xml.len());
+    let mut rest = document_xml;
+    while let Some(start) = rest.find(&marker) {
+        let value_start = start + marker.len();
+        let (Some(value_len), Some(properties_end)) = (rest[value_start..].find('"'), rest[start..].find("</w:rPr>"))
+        else {
+            break;
+        };
+        let value = &rest[value_start..value_start + value_len];
+        let tag_end = value_start + value_len + rest[value_start + value_len..].find('>').map_or(0, |end| end + 1);
+        let properties_end = start + properties_end;
+        let (language, style) = match value.split_once(':') {
+            Some((language, style)) => (language, Some(style)),
+            None => (value, None),
+        };
+
+        result.push_str(&rest[..start]);
+        if let Some(style) = style {
+            result.push_str(&format!("<w:rStyle w:val=\"{}\" />", style));
+        }
+        result.push_str(&rest[tag_end..properties_end]);
+        result.push_str(&format!("<w:lang w:val=\"{}\" />", language));
+        rest = &rest[properties_end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Add a `dc:title` element to the core properties part
+pub fn set_core_title(core_xml: &str, title: &str) -> String {
+    if core_xml.contains("<dc:title>") {
+        return core_xml.to_string();
+    }
+    let element = format!("<dc:title>{}</dc:title>", escape_xml(title));
+    match core_xml.rfind("</cp:coreProperties>") {
+        Some(pos) => format!("{}{}{}", &core_xml[..pos], element, &core_xml[pos..]),
+        None => core_xml.to_string(),
+    }
+}
+
+/// Drop the empty `w:tblBorders` elements docx-rs writes for tables and
+/// table styles without borders
+///
+/// They define no borders, but leaving them out keeps borderless tables
+/// free of any border markup.
+pub fn drop_empty_table_borders(xml: &str) -> String {
+    xml.replace("<w:tblBorders />", "")
+}
+
+/// Escape text for use in XML content and attribute values
+pub fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_run_languages() {
+        let xml = format!(
+            "<w:r><w:rPr><w:rStyle w:val=\"{}\" /><w:b /></w:rPr></w:r><w:r><w:rPr><w:rStyle w:val=\"{}\" /></w:rPr></w:r>",
+            language_style("fr-FR", None),
+            language_style("de", Some("Quote")),
+        );
+        assert_eq!(
+            set_run_languages(&xml),
+            "<w:r><w:rPr><w:b /><w:lang w:val=\"fr-FR\" /></w:rPr></w:r>\
+             <w:r><w:rPr><w:rStyle w:val=\"Quote\" /><w:lang w:val=\"de\" /></w:rPr></w:r>"
+        );
+        assert_eq!(set_run_languages("<w:r />"), "<w:r />");
+    }
+
+    #[test]
+    fn test_only_first_row_of_markdown_tables_marked() {
+        let xml = concat!(
+            "<w:tbl><w:tblPr><w:tblStyle w:val=\"MarkdownTable\" /></w:tblPr>",
+            "<w:tr><w:trPr><w:cantSplit /></w:trPr></w:tr>",
+            "<w:tr><w:trPr /></w:tr></w:tbl>",
+            "<w:tbl><w:tr><w:trPr><w:cantSplit /></w:trPr></w:tr></w:tbl>"
+        );
+        let patched = mark_table_header_rows(xml);
+        assert_eq!(patched.matches("<w:tblHeader />").count(), 1);
+        assert!(patched.contains("MarkdownTable\" /></w:tblPr><w:tr><w:trPr><w:cantSplit /><w:tblHeader />"));
+    }
+
+    #[test]
+    fn test_set_core_title() {
+        let core = "<cp:coreProperties><cp:revision>1</cp:revision></cp:coreProperties>";
+        let patched = set_core_title(core, "Q3 <Report>");
+        assert!(patched.ends_with("<dc:title>Q3 &lt;Report&gt;</dc:title></cp:coreProperties>"));
+        assert_eq!(set_core_title(&patched, "Other"), patched);
+    }
+}
@@ -0,0 +1,68 @@
+//! Converter provenance
+//!
+//! Identifies the converter that produced a document, for the `Generator`
+//! custom document property and the optional colophon paragraph. The
+//! colophon date honours `SOURCE_DATE_EPOCH` so reproducible builds get the
+//! same bytes on every run.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Custom document property holding the converter name and version
+pub const GENERATOR_PROPERTY: &str = "Generator";
+
+/// Environment variable with a fixed Unix timestamp for reproducible output
+pub const SOURCE_DATE_EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+/// Converter name and version, e.g. `md2docx 0.1.0`
+pub fn generator_name() -> String {
+    format!("md2docx {}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Colophon text for the given `YYYY-MM-DD` date
+pub fn colophon_text(date: &str) -> String {
+    format!("Generated by md2docx v{} on {}", env!("CARGO_PKG_VERSION"), date)
+}
+
+/// Date of the document as `YYYY-MM-DD`: that of `SOURCE_DATE_EPOCH` when
+/// it holds a valid timestamp, otherwise today's (UTC)
+pub fn document_date() -> String {
+    let seconds = std::env::var(SOURCE_DATE_EPOCH_ENV)
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0)
+        });
+    format_date(seconds)
+}
+
+/// Format a Unix timestamp as a `YYYY-MM-DD` UTC date
+pub fn format_date(seconds: u64) -> String {
+    // Days since 1970-01-01 to a civil date, counting from 0000-03-01 so
+    // that leap days fall at the end of each year
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+        assert_eq!(format_date(1_735_689_600), "2025-01-01");
+    }
+}
@@ -0,0 +1,313 @@
+//! Post-generation package self-check
+//!
+//! Re-opens generated docx bytes and verifies the things Word is strict about:
+//! the archive can be read, required parts exist, every XML part is
+//! well-formed, every internal relationship points at a part that exists and
+//! every part has a content type.
+
+use crate::error::ConversionError;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+use std::collections::HashSet;
+use std::io::{Cursor, Read};
+use tracing::{debug, error};
+
+/// Environment variable that forces the self-check on (e.g. in CI)
+pub const SELF_CHECK_ENV: &str = "MD2DOCX_SELF_CHECK";
+
+const REQUIRED_PARTS: &[&str] = &["[Content_Types].xml", "_rels/.rels", "word/document.xml"];
+
+/// Whether the self-check is forced on through the environment
+pub fn forced_by_env() -> bool {
+    std::env::var(SELF_CHECK_ENV)
+        .map(|value| matches!(value.trim(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+/// Verify a generated docx package, returning every problem found
+pub fn check_package(docx_bytes: &[u8]) -> Result<(), Vec<String>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(docx_bytes))
+        .map_err(|e| vec![format!("package is not a readable zip archive: {}", e)])?;
+
+    let mut parts = Vec::with_capacity(archive.len());
+    let mut issues = Vec::new();
+    for index in 0..archive.len() {
+        let mut file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(e) => {
+                issues.push(format!("entry {} cannot be read: {}", index, e));
+                continue;
+            }
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let mut data = Vec::new();
+        if let Err(e) = file.read_to_end(&mut data) {
+            issues.push(format!("{}: cannot be decompressed: {}", file.name(), e));
+            continue;
+        }
+        parts.push((file.name().to_string(), data));
+    }
+
+    let names: HashSet<&str> = parts.iter().map(|(name, _)| name.as_str()).collect();
+    for required in REQUIRED_PARTS {
+        if !names.contains(required) {
+            issues.push(format!("required part {} is missing", required));
+        }
+    }
+
+    let mut content_types = ContentTypes::default();
+    for (name, data) in &parts {
+        if !(name.ends_with(".xml") || name.ends_with(".rels")) {
+            continue;
+        }
+        if let Err(e) = check_well_formed(data) {
+            issues.push(format!("{}: malformed XML: {}", name, e));
+            continue;
+        }
+        if name == "[Content_Types].xml" {
+            content_types = ContentTypes::parse(data);
+        } else if name.ends_with(".rels") {
+            for target in internal_targets(name, data) {
+                if !names.contains(target.as_str()) {
+                    issues.push(format!("{}: relationship target {} does not exist", name, target));
+                }
+            }
+        }
+    }
+
+    for (name, _) in &parts {
+        if name != "[Content_Types].xml" && !content_types.covers(name) {
+            issues.push(format!("{}: no content type declared", name));
+        }
+    }
+
+    if issues.is_empty() {
+        debug!(parts = parts.len(), "Package self-check passed");
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+/// Run the self-check and convert failures into a `ConversionError`
+pub fn verify_package(docx_bytes: &[u8]) -> Result<(), ConversionError> {
+    check_package(docx_bytes).map_err(|issues| {
+        error!(issues = issues.len(), "Generated package failed self-check");
+        ConversionError::DocxGeneration(format!(
+            "Generated package failed self-check: {}",
+            issues.join("; ")
+        ))
+    })
+}
+
+fn check_well_formed(data: &[u8]) -> Result<(), String> {
+    let mut reader = Reader::from_reader(data);
+    reader.config_mut().check_end_names = true;
+    let mut buf = Vec::new();
+    let mut depth = 0usize;
+    let mut root_seen = false;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(_)) => {
+                depth += 1;
+                root_seen = true;
+            }
+            Ok(Event::Empty(_)) => root_seen = true,
+            Ok(Event::End(_)) => depth = depth.saturating_sub(1),
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => {
+                return Err(format!("at byte {}: {}", reader.buffer_position(), e));
+            }
+        }
+        buf.clear();
+    }
+    if depth != 0 {
+        return Err("unclosed elements at end of document".to_string());
+    }
+    if !root_seen {
+        return Err("no root element".to_string());
+    }
+    Ok(())
+}
+
+fn attribute(reader: &Reader<&[u8]>, element: &BytesStart, key: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == key)
+        .and_then(|attr| {
+            attr.decoded_and_normalized_value(XmlVersion::Implicit1_0, reader.decoder())
+                .ok()
+        })
+        .map(|value| value.into_owned())
+}
+
+/// Resolve the internal relationship targets of a `.rels` part to part names
+fn internal_targets(rels_name: &str, data: &[u8]) -> Vec<String> {
+    // "word/_rels/document.xml.rels" describes parts relative to "word/"
+    let base = rels_name
+        .rsplit_once("_rels/")
+        .map(|(dir, _)| dir)
+        .unwrap_or("");
+
+    let mut reader = Reader::from_reader(data);
+    let mut buf = Vec::new();
+    let mut targets = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"Relationship" => {
+                let external = attribute(&reader, &e, b"TargetMode").as_deref() == Some("External");
+                if let (false, Some(target)) = (external, attribute(&reader, &e, b"Target")) {
+                    targets.push(resolve_part_name(base, &target));
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    targets
+}
+
+fn resolve_part_name(base: &str, target: &str) -> String {
+    let joined = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_string(),
+        None => format!("{}{}", base, target),
+    };
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            other => segments.push(other),
+        }
+    }
+    segments.join("/")
+}
+
+#[derive(Default)]
+struct ContentTypes {
+    extensions: HashSet<String>,
+    overrides: HashSet<String>,
+}
+
+impl ContentTypes {
+    fn parse(data: &[u8]) -> Self {
+        let mut types = Self::default();
+        let mut reader = Reader::from_reader(data);
+        let mut buf = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                    b"Default" => {
+                        if let Some(ext) = attribute(&reader, &e, b"Extension") {
+                            types.extensions.insert(ext.to_ascii_lowercase());
+                        }
+                    }
+                    b"Override" => {
+                        if let Some(part) = attribute(&reader, &e, b"PartName") {
+                            types.overrides.insert(part.trim_start_matches('/').to_string());
+                        }
+                    }
+                    _ => {}
+                },
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        types
+    }
+
+    fn covers(&self, part: &str) -> bool {
+        if self.overrides.contains(part) {
+            return true;
+        }
+        part.rsplit_once('.')
+            .map(|(_, ext)| self.extensions.contains(&ext.to_ascii_lowercase()))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn build_zip(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut buf);
+            let options = zip::write::SimpleFileOptions::default();
+            for (name, content) in parts {
+                writer.start_file(*name, options).unwrap();
+                writer.write_all(content.as_bytes()).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+        buf.into_inner()
+    }
+
+    const CONTENT_TYPES: &str = concat!(
+        "<Types><Default Extension=\"rels\" ContentType=\"r\"/>",
+        "<Default Extension=\"xml\" ContentType=\"x\"/></Types>"
+    );
+    const ROOT_RELS: &str =
+        "<Relationships><Relationship Id=\"rId1\" Target=\"word/document.xml\"/></Relationships>";
+
+    #[test]
+    fn test_minimal_package_passes() {
+        let bytes = build_zip(&[
+            ("[Content_Types].xml", CONTENT_TYPES),
+            ("_rels/.rels", ROOT_RELS),
+            ("word/document.xml", "<w:document><w:body/></w:document>"),
+        ]);
+        assert!(check_package(&bytes).is_ok());
+    }
+
+    #[test]
+    fn test_malformed_xml_detected() {
+        let bytes = build_zip(&[
+            ("[Content_Types].xml", CONTENT_TYPES),
+            ("_rels/.rels", ROOT_RELS),
+            ("word/document.xml", "<w:document><w:body></w:document>"),
+        ]);
+        let issues = check_package(&bytes).unwrap_err();
+        assert!(issues.iter().any(|i| i.starts_with("word/document.xml: malformed XML")));
+    }
+
+    #[test]
+    fn test_dangling_relationship_detected() {
+        let rels = concat!(
+            "<Relationships><Relationship Id=\"rId1\" Target=\"media/missing.png\"/>",
+            "<Relationship Id=\"rId2\" Target=\"https://example.com\" TargetMode=\"External\"/>",
+            "</Relationships>"
+        );
+        let bytes = build_zip(&[
+            ("[Content_Types].xml", CONTENT_TYPES),
+            ("_rels/.rels", ROOT_RELS),
+            ("word/document.xml", "<w:document/>"),
+            ("word/_rels/document.xml.rels", rels),
+        ]);
+        let issues = check_package(&bytes).unwrap_err();
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].contains("word/media/missing.png"));
+    }
+
+    #[test]
+    fn test_not_a_zip() {
+        assert!(check_package(b"definitely not a docx").is_err());
+    }
+
+    #[test]
+    fn test_resolve_part_name() {
+        assert_eq!(resolve_part_name("word/", "media/a.png"), "word/media/a.png");
+        assert_eq!(resolve_part_name("word/", "../docProps/core.xml"), "docProps/core.xml");
+        assert_eq!(resolve_part_name("", "/word/document.xml"), "word/document.xml");
+    }
+}
@@ -0,0 +1,196 @@
+//! Page watermark support
+//!
+//! docx-rs can place a picture in a header but has no writer for VML shapes,
+//! which is what Word itself uses for text watermarks. The header is therefore
+//! built with a placeholder run that is swapped for the VML markup once the
+//! package parts have been rendered, just before they are zipped.
+
+use crate::config::{WatermarkConfig, WatermarkOrientation};
+use crate::docx::postprocess::escape_xml;
+use crate::error::ConversionError;
+use docx_rs::*;
+use tracing::{debug, warn};
+
+/// Placeholder text replaced by the VML text watermark after build
+const TEXT_PLACEHOLDER: &str = "__MD2DOCX_WATERMARK__";
+
+/// Page content area in points, used to size the watermark
+#[derive(Debug, Clone, Copy)]
+pub struct ContentArea {
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Build the default header carrying the watermark
+pub fn build_header(
+    watermark: &WatermarkConfig,
+    area: ContentArea,
+) -> Result<Header, ConversionError> {
+    let run = if let Some(path) = &watermark.image_path {
+        image_run(path, area)?
+    } else {
+        Run::new().add_text(TEXT_PLACEHOLDER)
+    };
+
+    Ok(Header::new().add_paragraph(Paragraph::new().add_run(run)))
+}
+
+/// Rewrite the rendered header parts so the watermark sits behind the text
+pub fn apply_to_package(
+    xml: &mut XMLDocx,
+    watermark: &WatermarkConfig,
+    area: ContentArea,
+    font_family: &str,
+) {
+    let placeholder = format!("<w:t xml:space=\"preserve\">{}</w:t>", TEXT_PLACEHOLDER);
+    let shape = text_shape_xml(watermark, area, font_family);
+    let mut applied = false;
+
+    for header in xml.headers.iter_mut() {
+        let content = String::from_utf8_lossy(header).into_owned();
+        let patched = if watermark.image_path.is_some() {
+            // docx-rs always writes behindDoc="0" for anchored pictures
+            content.replacen("behindDoc=\"0\"", "behindDoc=\"1\"", 1)
+        } else {
+            content.replacen(&placeholder, &shape, 1)
+        };
+        if patched != content {
+            applied = true;
+            *header = patched.into_bytes();
+        }
+    }
+
+    if applied {
+        debug!("Watermark applied to document header");
+    } else {
+        warn!("Watermark placeholder not found in rendered headers");
+    }
+}
+
+/// Create a centred, floating picture run for an image watermark
+fn image_run(path: &str, area: ContentArea) -> Result<Run, ConversionError> {
+    let data = std::fs::read(path).map_err(|e| {
+        ConversionError::DocxGeneration(format!(
+            "Failed to read watermark image {}: {}",
+            path, e
+        ))
+    })?;
+
+    let pic = Pic::new(&data);
+    let (width, height) = fit_within(pic.size, area);
+    let pic = pic
+        .size(width, height)
+        .floating()
+        .overlapping()
+        .relative_from_h(RelativeFromHType::Margin)
+        .relative_from_v(RelativeFromVType::Margin)
+        .position_h(DrawingPosition::Align(PicAlign::Center))
+        .position_v(DrawingPosition::Align(PicAlign::Center));
+
+    Ok(Run::new().add_image(pic))
+}
+
+/// Scale an EMU size down to fit the content area, keeping its aspect ratio
+fn fit_within((width, height): (u32, u32), area: ContentArea) -> (u32, u32) {
+    // 12700 EMU per point
+    let max_width = (area.width * 12700.0) as f64;
+    let max_height = (area.height * 12700.0) as f64;
+    let (w, h) = (width as f64, height as f64);
+    if w <= max_width && h <= max_height || w == 0.0 || h == 0.0 {
+        return (width, height);
+    }
+    let scale = (max_width / w).min(max_height / h);
+    ((w * scale) as u32, (h * scale) as u32)
+}
+
+/// VML for a WordArt-style text watermark, the same markup Word emits
+fn text_shape_xml(watermark: &WatermarkConfig, area: ContentArea, font_family: &str) -> String {
+    let text = watermark.text.as_deref().unwrap_or_default();
+    let width = area.width;
+    let height = (area.width / 4.0).min(area.height);
+    let rotation = match watermark.orientation {
+        WatermarkOrientation::Diagonal => 315,
+        WatermarkOrientation::Horizontal => 0,
+    };
+
+    format!(
+        concat!(
+            "<w:pict>",
+            "<v:shapetype id=\"_x0000_t136\" coordsize=\"21600,21600\" o:spt=\"136\" adj=\"10800\" ",
+            "path=\"m@7,l@8,m@5,21600l@6,21600e\">",
+            "<v:formulas>",
+            "<v:f eqn=\"sum #0 0 10800\"/><v:f eqn=\"prod #0 2 1\"/><v:f eqn=\"sum 21600 0 @1\"/>",
+            "<v:f eqn=\"sum 0 0 @2\"/><v:f eqn=\"sum 21600 0 @3\"/><v:f eqn=\"if @0 @3 0\"/>",
+            "<v:f eqn=\"if @0 21600 @1\"/><v:f eqn=\"if @0 0 @2\"/><v:f eqn=\"if @0 @4 21600\"/>",
+            "<v:f eqn=\"mid @5 @6\"/><v:f eqn=\"mid @8 @5\"/><v:f eqn=\"mid @7 @8\"/>",
+            "<v:f eqn=\"mid @6 @7\"/><v:f eqn=\"sum @6 0 @5\"/>",
+            "</v:formulas>",
+            "<v:path textpathok=\"t\" o:connecttype=\"custom\" ",
+            "o:connectlocs=\"@9,0;@10,10800;@11,21600;@12,10800\" o:connectangles=\"270,180,90,0\"/>",
+            "<v:textpath on=\"t\" fitshape=\"t\"/>",
+            "<v:handles><v:h position=\"#0,bottomRight\" xrange=\"6629,14971\"/></v:handles>",
+            "<o:lock v:ext=\"edit\" text=\"t\" shapetype=\"t\"/>",
+            "</v:shapetype>",
+            "<v:shape id=\"PowerPlusWaterMarkObject\" o:spid=\"_x0000_s2049\" type=\"#_x0000_t136\" ",
+            "style=\"position:absolute;margin-left:0;margin-top:0;width:{width:.1}pt;height:{height:.1}pt;",
+            "rotation:{rotation};z-index:-251657216;mso-position-horizontal:center;",
+            "mso-position-horizontal-relative:margin;mso-position-vertical:center;",
+            "mso-position-vertical-relative:margin\" o:allowincell=\"f\" fillcolor=\"{color}\" stroked=\"f\">",
+            "<v:fill opacity=\"{opacity:.2}\"/>",
+            "<v:textpath style=\"font-family:&quot;{font}&quot;;font-size:1pt\" string=\"{text}\"/>",
+            "<w10:wrap anchorx=\"margin\" anchory=\"margin\"/>",
+            "</v:shape>",
+            "</w:pict>"
+        ),
+        width = width,
+        height = height,
+        rotation = rotation,
+        color = escape_xml(&watermark.color),
+        opacity = watermark.opacity,
+        font = escape_xml(font_family),
+        text = escape_xml(text),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: ContentArea = ContentArea {
+        width: 451.0,
+        height: 698.0,
+    };
+
+    #[test]
+    fn test_text_shape_rotation() {
+        let mut watermark = WatermarkConfig::text("DRAFT");
+        let diagonal = text_shape_xml(&watermark, AREA, "Arial");
+        assert!(diagonal.contains("rotation:315"));
+        assert!(diagonal.contains("string=\"DRAFT\""));
+
+        watermark.orientation = WatermarkOrientation::Horizontal;
+        let horizontal = text_shape_xml(&watermark, AREA, "Arial");
+        assert!(horizontal.contains("rotation:0;"));
+    }
+
+    #[test]
+    fn test_text_shape_escapes_text() {
+        let watermark = WatermarkConfig::text("R&D \"internal\"");
+        let xml = text_shape_xml(&watermark, AREA, "Arial");
+        assert!(xml.contains("string=\"R&amp;D &quot;internal&quot;\""));
+    }
+
+    #[test]
+    fn test_fit_within_keeps_aspect_ratio() {
+        let area = ContentArea {
+            width: 100.0,
+            height: 100.0,
+        };
+        let (w, h) = fit_within((200 * 12700, 100 * 12700), area);
+        assert_eq!(w, 100 * 12700);
+        assert_eq!(h, 50 * 12700);
+
+        let small = (10 * 12700, 10 * 12700);
+        assert_eq!(fit_within(small, area), small);
+    }
+}
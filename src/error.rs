@@ -41,6 +41,44 @@ pub enum ConversionError {
     
     #[error("Code block processing error: {0}")]
     ProcessingError(String),
+    
+    #[error("PDF rendering failed: {0}")]
+    PdfRendering(String),
+    
+    #[error("{} code block(s) failed processing: {}", .0.len(), join_failures(.0))]
+    InvalidCodeBlocks(Vec<CodeBlockFailure>),
+    
+    #[error("Undefined variables: {}", .0.join(", "))]
+    UndefinedVariables(Vec<String>),
+}
+
+/// A code block whose strategy reported errors, collected in strict mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlockFailure {
+    /// 1-based position among the document's code blocks
+    pub index: usize,
+    pub language: Option<String>,
+    pub messages: Vec<String>,
+}
+
+impl fmt::Display for CodeBlockFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block {} ({}): {}",
+            self.index,
+            self.language.as_deref().unwrap_or("no language"),
+            self.messages.join(", ")
+        )
+    }
+}
+
+fn join_failures(failures: &[CodeBlockFailure]) -> String {
+    failures
+        .iter()
+        .map(|failure| failure.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
 }
 
 /// Configuration-specific error types
@@ -157,6 +195,11 @@ impl ConversionError {
         Self::ProcessingError(msg.into())
     }
     
+    /// Create a new PDF rendering error
+    pub fn pdf_rendering<S: Into<String>>(msg: S) -> Self {
+        Self::PdfRendering(msg.into())
+    }
+    
     /// Check if this error is recoverable
     pub fn is_recoverable(&self) -> bool {
         match self {
@@ -172,6 +215,9 @@ impl ConversionError {
             Self::Timeout(_) => true,
             Self::Validation(_) => true,
             Self::ProcessingError(_) => true,
+            Self::PdfRendering(_) => true,
+            Self::InvalidCodeBlocks(_) => false,
+            Self::UndefinedVariables(_) => false,
         }
     }
     
@@ -190,6 +236,9 @@ impl ConversionError {
             Self::Timeout(_) => ErrorCategory::Timeout,
             Self::Validation(_) => ErrorCategory::Validation,
             Self::ProcessingError(_) => ErrorCategory::FileProcessing,
+            Self::PdfRendering(_) => ErrorCategory::Generation,
+            Self::InvalidCodeBlocks(_) => ErrorCategory::Validation,
+            Self::UndefinedVariables(_) => ErrorCategory::Validation,
         }
     }
 }
@@ -393,6 +442,7 @@ mod tests {
         assert!(!ConversionError::docx_generation("test").is_recoverable());
         assert!(ConversionError::file_processing("test").is_recoverable());
         assert!(ConversionError::timeout("test").is_recoverable());
+        assert!(ConversionError::pdf_rendering("test").is_recoverable());
     }
 
     #[test]
@@ -494,6 +544,28 @@ mod tests {
         assert_eq!(error.category(), ErrorCategory::BatchProcessing);
     }
 
+    #[test]
+    fn test_invalid_code_blocks_error() {
+        let error = ConversionError::InvalidCodeBlocks(vec![
+            CodeBlockFailure {
+                index: 2,
+                language: Some("rust".to_string()),
+                messages: vec!["syntax_error: expected `;`".to_string()],
+            },
+            CodeBlockFailure {
+                index: 5,
+                language: None,
+                messages: vec!["a".to_string(), "b".to_string()],
+            },
+        ]);
+        assert_eq!(
+            error.to_string(),
+            "2 code block(s) failed processing: block 2 (rust): syntax_error: expected `;`; block 5 (no language): a, b"
+        );
+        assert!(!error.is_recoverable());
+        assert_eq!(error.category(), ErrorCategory::Validation);
+    }
+
     #[test]
     fn test_io_error_conversion() {
         let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
@@ -6,12 +6,14 @@
 //! - Web API, CLI, and web interface
 //! - Batch processing capabilities
 
+pub mod capabilities;
 pub mod config;
 pub mod conversion;
 pub mod error;
 pub mod llm;
 pub mod markdown;
 pub mod docx;
+#[cfg(feature = "web")]
 pub mod web;
 pub mod logging;
 pub mod numbering;
@@ -23,12 +25,13 @@ pub mod test_utils;
 pub mod test_coverage;
 
 // Re-export main types for convenience
+pub use capabilities::{capabilities, Capabilities};
 pub use config::{ConversionConfig, ConfigurationService};
 pub use conversion::ConversionEngine;
-pub use error::{ConversionError, ConfigError, WebError, CliError};
+pub use error::{CodeBlockFailure, ConversionError, ConfigError, WebError, CliError};
 pub use markdown::MarkdownParser;
 pub use docx::DocxGenerator;
-pub use logging::{LoggingConfig, init_logging};
+pub use logging::{LoggingConfig, NumberingInstrumentation, init_logging};
 pub use numbering::{NumberingState, NumberingError};
 
 /// Result type alias for the library
@@ -15,6 +15,7 @@ Code block configuration options:
 - preserve_line_breaks: true/false - Whether to preserve original line breaks in code blocks
 - line_spacing: Number - Line spacing within code blocks (1.0 = single spacing)
 - paragraph_spacing: Number - Spacing between code block paragraphs in points
+- styles.code_lines.preserve_line_endings: true/false - Keep Windows (\r\n) and old Mac (\r) line endings instead of normalizing them
 
 Heading numbering configuration:
 - Add "numbering" field to heading styles to enable automatic numbering
@@ -1,14 +1,41 @@
 //! Logging configuration and utilities
 
-use tracing::Level;
+use std::cell::Cell;
+use std::str::FromStr;
+use tracing::{Event, Level, Subscriber};
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
-    layer::SubscriberExt,
+    layer::{Context, Layer, SubscriberExt},
     util::SubscriberInitExt,
     EnvFilter,
 };
 use std::io;
 
+thread_local! {
+    static WARNINGS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Layer counting the warning events logged on each thread
+///
+/// Conversion summaries read the count before and after a conversion, which
+/// runs on one thread. Only events that pass the subscriber's filters are
+/// counted.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WarningCounter;
+
+impl<S: Subscriber> Layer<S> for WarningCounter {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() == Level::WARN {
+            WARNINGS.with(|count| count.set(count.get() + 1));
+        }
+    }
+}
+
+/// Warnings counted by [`WarningCounter`] on the current thread so far
+pub fn warnings_on_current_thread() -> usize {
+    WARNINGS.with(Cell::get)
+}
+
 /// Logging configuration
 #[derive(Debug, Clone)]
 pub struct LoggingConfig {
@@ -22,6 +49,54 @@ pub struct LoggingConfig {
     pub include_spans: bool,
     /// Custom log target filter
     pub target_filter: Option<String>,
+    /// Verbosity of the heading numbering module, which logs on every heading
+    pub numbering_instrumentation: NumberingInstrumentation,
+}
+
+/// Log target of the heading numbering module
+pub const NUMBERING_TARGET: &str = "md2docx_converter::numbering";
+
+/// How much tracing output the heading numbering module produces
+///
+/// Reduced levels are applied as a per-target filter directive, so disabled
+/// numbering spans and events are rejected at their callsite without
+/// evaluating their fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberingInstrumentation {
+    /// Follow the global log level
+    #[default]
+    Full,
+    /// Only warnings and errors
+    WarningsOnly,
+    /// No numbering spans or events at all
+    Off,
+}
+
+impl NumberingInstrumentation {
+    /// Filter level for the numbering target, if it differs from the global one
+    fn directive_level(self) -> Option<&'static str> {
+        match self {
+            Self::Full => None,
+            Self::WarningsOnly => Some("warn"),
+            Self::Off => Some("off"),
+        }
+    }
+}
+
+impl FromStr for NumberingInstrumentation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "full" | "on" => Ok(Self::Full),
+            "warn" | "warnings" | "quiet" => Ok(Self::WarningsOnly),
+            "off" | "none" => Ok(Self::Off),
+            other => Err(format!(
+                "unknown numbering instrumentation '{}' (expected full, warn or off)",
+                other
+            )),
+        }
+    }
 }
 
 impl Default for LoggingConfig {
@@ -32,6 +107,7 @@ impl Default for LoggingConfig {
             json_format: false,
             include_spans: true,
             target_filter: None,
+            numbering_instrumentation: NumberingInstrumentation::Full,
         }
     }
 }
@@ -72,6 +148,12 @@ impl LoggingConfig {
         self
     }
     
+    /// Set how much the numbering module logs
+    pub fn with_numbering_instrumentation(mut self, instrumentation: NumberingInstrumentation) -> Self {
+        self.numbering_instrumentation = instrumentation;
+        self
+    }
+    
     /// Create configuration from environment variables
     pub fn from_env() -> Self {
         let mut config = Self::default();
@@ -99,6 +181,13 @@ impl LoggingConfig {
             config.target_filter = Some(target);
         }
         
+        // Reduce numbering output with MD2DOCX_NUMBERING_LOG (full, warn or off)
+        if let Ok(value) = std::env::var("MD2DOCX_NUMBERING_LOG") {
+            if let Ok(instrumentation) = value.parse() {
+                config.numbering_instrumentation = instrumentation;
+            }
+        }
+        
         config
     }
 }
@@ -135,6 +224,7 @@ fn init_pretty_logging(
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
+        .with(WarningCounter)
         .try_init()?;
     
     Ok(())
@@ -155,6 +245,7 @@ fn init_json_logging(
     tracing_subscriber::registry()
         .with(env_filter)
         .with(fmt_layer)
+        .with(WarningCounter)
         .try_init()?;
     
     Ok(())
@@ -185,6 +276,10 @@ fn create_env_filter(config: &LoggingConfig) -> Result<EnvFilter, Box<dyn std::e
         filter = EnvFilter::new(env_filter);
     }
     
+    if let Some(level) = config.numbering_instrumentation.directive_level() {
+        filter = filter.add_directive(format!("{}={}", NUMBERING_TARGET, level).parse()?);
+    }
+    
     Ok(filter)
 }
 
@@ -376,5 +471,51 @@ mod tests {
         assert!(!config.json_format);
         assert!(config.include_spans);
         assert_eq!(config.target_filter, None);
+        assert_eq!(config.numbering_instrumentation, NumberingInstrumentation::Full);
+    }
+
+    #[test]
+    fn test_parse_numbering_instrumentation() {
+        assert_eq!("warn".parse(), Ok(NumberingInstrumentation::WarningsOnly));
+        assert_eq!("OFF".parse(), Ok(NumberingInstrumentation::Off));
+        assert_eq!("full".parse(), Ok(NumberingInstrumentation::Full));
+        assert!("loud".parse::<NumberingInstrumentation>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_conversion_with_numbering_instrumentation_off() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let config = LoggingConfig::new()
+            .with_level(Level::TRACE)
+            .with_numbering_instrumentation(NumberingInstrumentation::Off);
+        let subscriber = tracing_subscriber::registry().with(create_env_filter(&config).unwrap());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        assert!(!tracing::enabled!(target: NUMBERING_TARGET, Level::ERROR));
+        assert!(tracing::enabled!(target: "md2docx_converter::docx", Level::TRACE));
+
+        let mut conversion_config = crate::config::ConversionConfig::default();
+        if let Some(h1) = conversion_config.styles.headings.get_mut(&1) {
+            h1.numbering = Some("%1.".to_string());
+        }
+        let mut engine = crate::conversion::ConversionEngine::new(conversion_config);
+        let docx = engine.convert("# Intro\n\n# Usage\n\nText").await.unwrap();
+        let xml = crate::test_utils::read_docx_part(&docx, "word/document.xml").unwrap();
+        assert!(xml.contains("2. Usage"));
+    }
+
+    #[test]
+    fn test_numbering_warnings_only_filter() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let config = LoggingConfig::new()
+            .with_numbering_instrumentation(NumberingInstrumentation::WarningsOnly);
+        let subscriber = tracing_subscriber::registry().with(create_env_filter(&config).unwrap());
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(tracing::enabled!(target: NUMBERING_TARGET, Level::WARN));
+            assert!(!tracing::enabled!(target: NUMBERING_TARGET, Level::INFO));
+            assert!(tracing::enabled!(target: "md2docx_converter::docx", Level::INFO));
+        });
     }
 }
\ No newline at end of file
@@ -0,0 +1,196 @@
+//! Abbreviation expansion
+//!
+//! Finds abbreviations such as `API` in the plain text of paragraphs, list
+//! items and footnotes, as whole words and case-sensitively. In first-use
+//! mode the first occurrence of each abbreviation, in document order,
+//! becomes "Full Name (ABBR)"; in tooltip mode every occurrence becomes an
+//! [`InlineElement::Abbreviation`] carrying the full name. Code, links,
+//! emphasized text, headings and tables are never rewritten.
+
+use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use std::collections::{HashMap, HashSet};
+
+/// Expand the abbreviations of `terms`, which maps each to its full name
+pub fn apply_to_document(document: &mut MarkdownDocument, terms: &HashMap<String, String>, tooltips: bool) {
+    let mut abbreviations: Vec<(&str, &str)> = terms
+        .iter()
+        .filter(|(abbreviation, _)| !abbreviation.is_empty())
+        .map(|(abbreviation, full_name)| (abbreviation.as_str(), full_name.as_str()))
+        .collect();
+    // Longest first, so `HTTPS` wins over `HTTP` at the same position
+    abbreviations.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(b.0)));
+    let mut expander = Expander {
+        abbreviations,
+        tooltips,
+        seen: HashSet::new(),
+    };
+    for element in &mut document.elements {
+        expander.rewrite_element(element);
+    }
+}
+
+struct Expander<'a> {
+    abbreviations: Vec<(&'a str, &'a str)>,
+    tooltips: bool,
+    /// Abbreviations already expanded in first-use mode
+    seen: HashSet<&'a str>,
+}
+
+impl<'a> Expander<'a> {
+    fn rewrite_element(&mut self, element: &mut MarkdownElement) {
+        match element {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => self.rewrite(content),
+            MarkdownElement::List { items, .. } => self.rewrite_items(items),
+            MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                for element in body {
+                    self.rewrite_element(element);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn rewrite_items(&mut self, items: &mut [ListItem]) {
+        for item in items {
+            for content in item.paragraphs_mut() {
+                self.rewrite(content);
+            }
+            self.rewrite_items(&mut item.sub_items);
+        }
+    }
+
+    fn rewrite(&mut self, inlines: &mut Vec<InlineElement>) {
+        let mut rewritten = Vec::with_capacity(inlines.len());
+        for inline in inlines.drain(..) {
+            match inline {
+                InlineElement::Text(text) => self.rewrite_text(&text, &mut rewritten),
+                other => rewritten.push(other),
+            }
+        }
+        *inlines = rewritten;
+    }
+
+    /// Push the inline elements for a text, with its abbreviations expanded
+    fn rewrite_text(&mut self, text: &str, output: &mut Vec<InlineElement>) {
+        let mut plain = String::new();
+        let mut rest = text;
+        let mut previous = None;
+        while let Some(c) = rest.chars().next() {
+            let found = if previous.is_none_or(|previous| !is_word_char(previous)) {
+                self.abbreviation_at(rest)
+            } else {
+                None
+            };
+            match found {
+                Some((abbreviation, full_name)) if self.tooltips => {
+                    if !plain.is_empty() {
+                        output.push(InlineElement::Text(std::mem::take(&mut plain)));
+                    }
+                    output.push(InlineElement::Abbreviation {
+                        text: abbreviation.to_string(),
+                        title: full_name.to_string(),
+                    });
+                    rest = &rest[abbreviation.len()..];
+                    previous = abbreviation.chars().last();
+                }
+                Some((abbreviation, full_name)) => {
+                    if self.seen.insert(abbreviation) {
+                        plain.push_str(&format!("{} ({})", full_name, abbreviation));
+                    } else {
+                        plain.push_str(abbreviation);
+                    }
+                    rest = &rest[abbreviation.len()..];
+                    previous = abbreviation.chars().last();
+                }
+                None => {
+                    plain.push(c);
+                    rest = &rest[c.len_utf8()..];
+                    previous = Some(c);
+                }
+            }
+        }
+        if !plain.is_empty() {
+            output.push(InlineElement::Text(plain));
+        }
+    }
+
+    /// The abbreviation starting `text` and ending at a word boundary
+    fn abbreviation_at(&self, text: &str) -> Option<(&'a str, &'a str)> {
+        self.abbreviations.iter().copied().find(|(abbreviation, _)| {
+            text.strip_prefix(abbreviation)
+                .is_some_and(|after| !after.starts_with(is_word_char))
+        })
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(content: Vec<InlineElement>) -> MarkdownElement {
+        MarkdownElement::Paragraph { content }
+    }
+
+    fn contents(document: &MarkdownDocument) -> Vec<&[InlineElement]> {
+        document
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                MarkdownElement::Paragraph { content, .. } => Some(content.as_slice()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_first_use_expanded_once() {
+        let terms = HashMap::from([
+            ("API".to_string(), "Application Programming Interface".to_string()),
+            ("HTTP".to_string(), "Hypertext Transfer Protocol".to_string()),
+            ("HTTPS".to_string(), "HTTP Secure".to_string()),
+        ]);
+        let mut document = MarkdownDocument::new();
+        document.add_element(paragraph(vec![
+            InlineElement::Text("The API speaks HTTPS. ".to_string()),
+            InlineElement::Code("API".to_string()),
+        ]));
+        document.add_element(paragraph(vec![InlineElement::Text("APIs and the API, over HTTP.".to_string())]));
+
+        apply_to_document(&mut document, &terms, false);
+        let contents = contents(&document);
+        assert_eq!(
+            contents[0],
+            [
+                InlineElement::Text("The Application Programming Interface (API) speaks HTTP Secure (HTTPS). ".to_string()),
+                InlineElement::Code("API".to_string()),
+            ]
+        );
+        // `APIs` is another word; the second `API` stays short
+        assert_eq!(
+            contents[1],
+            [InlineElement::Text("APIs and the API, over Hypertext Transfer Protocol (HTTP).".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_tooltips_on_every_occurrence() {
+        let terms = HashMap::from([("CPU".to_string(), "Central Processing Unit".to_string())]);
+        let mut document = MarkdownDocument::new();
+        document.add_element(paragraph(vec![InlineElement::Text("CPU time per CPU".to_string())]));
+
+        apply_to_document(&mut document, &terms, true);
+        let cpu = InlineElement::Abbreviation {
+            text: "CPU".to_string(),
+            title: "Central Processing Unit".to_string(),
+        };
+        assert_eq!(
+            contents(&document)[0],
+            [cpu.clone(), InlineElement::Text(" time per ".to_string()), cpu]
+        );
+    }
+}
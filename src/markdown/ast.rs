@@ -1,21 +1,161 @@
 //! Abstract Syntax Tree definitions for Markdown documents
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::markdown::fence_info::CodeAttributes;
+
 /// Represents a complete Markdown document
 #[derive(Debug, Clone)]
 pub struct MarkdownDocument {
     pub elements: Vec<MarkdownElement>,
+    /// Page margins set by `<!-- margins: ... -->` directives, overriding
+    /// the configured margins for this document
+    pub margins: MarginOverrides,
+    /// Source details of the elements that their variants do not carry
+    pub details: ElementDetails,
+}
+
+/// Source details of a document's elements, kept beside the elements so
+/// their variants keep the fields they have always had
+///
+/// Entries are keyed by an element's ordinal among the elements of its
+/// kind, counted in [`MarkdownDocument::traverse`] order. Code that walks
+/// the elements looks details up through [`MarkdownDocument::details_lookup`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ElementDetails {
+    /// Headings underlined with `===`/`---` (setext) rather than prefixed
+    /// with `#` (ATX)
+    pub setext_headings: BTreeSet<usize>,
+    /// Classes of paragraphs from a trailing `{.class}` attribute
+    pub paragraph_classes: BTreeMap<usize, Vec<String>>,
+    /// Attributes of code blocks from the fence info string, such as
+    /// highlighted lines; blocks without any are left out
+    pub code_attributes: BTreeMap<usize, CodeAttributes>,
+}
+
+/// Attributes of a code block whose fence info string sets none
+static NO_CODE_ATTRIBUTES: CodeAttributes = CodeAttributes { highlight: Vec::new(), no_validate: false, title: None };
+
+/// Details of a document's elements by element, valid while the document
+/// is neither moved nor changed
+#[derive(Debug, Clone, Default)]
+pub struct DetailsLookup {
+    details: ElementDetails,
+    /// Ordinal among the elements of its kind, by element address
+    ordinals: HashMap<usize, usize>,
+}
+
+impl DetailsLookup {
+    /// Whether a heading was underlined (setext); false for other elements
+    pub fn setext(&self, element: &MarkdownElement) -> bool {
+        matches!(element, MarkdownElement::Heading { .. })
+            && self.ordinal(element).is_some_and(|ordinal| self.details.setext_headings.contains(&ordinal))
+    }
+
+    /// Classes of a paragraph; empty for other elements
+    pub fn classes(&self, element: &MarkdownElement) -> &[String] {
+        match element {
+            MarkdownElement::Paragraph { .. } => self
+                .ordinal(element)
+                .and_then(|ordinal| self.details.paragraph_classes.get(&ordinal))
+                .map_or(&[], Vec::as_slice),
+            _ => &[],
+        }
+    }
+
+    /// Attributes of a code block; empty for other elements
+    pub fn code_attributes(&self, element: &MarkdownElement) -> &CodeAttributes {
+        match element {
+            MarkdownElement::CodeBlock { .. } => self
+                .ordinal(element)
+                .and_then(|ordinal| self.details.code_attributes.get(&ordinal))
+                .unwrap_or(&NO_CODE_ATTRIBUTES),
+            _ => &NO_CODE_ATTRIBUTES,
+        }
+    }
+
+    /// Whether an element and the elements inside it have the same details
+    /// as their counterparts in another document
+    pub fn same_details(&self, element: &MarkdownElement, other: &DetailsLookup, other_element: &MarkdownElement) -> bool {
+        let mut own = vec![self.own_details(element)];
+        element.traverse_children(&mut |child| own.push(self.own_details(child)));
+        let mut theirs = vec![other.own_details(other_element)];
+        other_element.traverse_children(&mut |child| theirs.push(other.own_details(child)));
+        own == theirs
+    }
+
+    fn own_details(&self, element: &MarkdownElement) -> (bool, &[String], &CodeAttributes) {
+        (self.setext(element), self.classes(element), self.code_attributes(element))
+    }
+
+    fn ordinal(&self, element: &MarkdownElement) -> Option<usize> {
+        self.ordinals.get(&(element as *const MarkdownElement as usize)).copied()
+    }
+}
+
+/// Running ordinals of the elements that can have details
+#[derive(Default)]
+struct Ordinals {
+    headings: usize,
+    paragraphs: usize,
+    code_blocks: usize,
+}
+
+impl Ordinals {
+    /// Ordinal of the next element among those of its kind, if it is of a
+    /// kind with details
+    fn next(&mut self, element: &MarkdownElement) -> Option<usize> {
+        let count = match element {
+            MarkdownElement::Heading { .. } => &mut self.headings,
+            MarkdownElement::Paragraph { .. } => &mut self.paragraphs,
+            MarkdownElement::CodeBlock { .. } => &mut self.code_blocks,
+            _ => return None,
+        };
+        *count += 1;
+        Some(*count - 1)
+    }
+}
+
+/// Per-document page margins in points; unset sides keep the configured value
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MarginOverrides {
+    pub top: Option<f32>,
+    pub bottom: Option<f32>,
+    pub left: Option<f32>,
+    pub right: Option<f32>,
+}
+
+impl MarginOverrides {
+    /// Whether no side is overridden
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Take the sides set in `other`, keeping the rest
+    pub fn merge(&mut self, other: MarginOverrides) {
+        self.top = other.top.or(self.top);
+        self.bottom = other.bottom.or(self.bottom);
+        self.left = other.left.or(self.left);
+        self.right = other.right.or(self.right);
+    }
 }
 
 /// Represents different types of Markdown elements
 #[derive(Debug, Clone)]
 pub enum MarkdownElement {
+    /// Heading; whether it was underlined (setext) is in the document's
+    /// [`ElementDetails`]
     Heading {
         level: u8,
         text: String,
     },
+    /// Paragraph; classes from a trailing `{.class}` attribute are in the
+    /// document's [`ElementDetails`]
     Paragraph {
         content: Vec<InlineElement>,
     },
+    /// Code block; attributes from its fence info string are in the
+    /// document's [`ElementDetails`]
     CodeBlock {
         language: Option<String>,
         code: String,
@@ -28,7 +168,11 @@ pub enum MarkdownElement {
     Table {
         headers: Vec<String>,
         rows: Vec<Vec<String>>,
+        /// Links written in the cells, whose texts are part of the cell text
+        links: Vec<TableLink>,
     },
+    /// Table written as HTML; unlike pipe tables its cells can hold tables
+    HtmlTable(HtmlTable),
     Image {
         alt_text: String,
         url: String,
@@ -37,10 +181,36 @@ pub enum MarkdownElement {
         height: Option<u32>,
     },
     HorizontalRule,
+    /// Footnote text (`[^label]: ...`), rendered in the appended notes section
+    FootnoteDefinition {
+        label: String,
+        content: Vec<InlineElement>,
+    },
+    /// Start of a new page section, from `<!-- section: landscape -->`
+    SectionBreak {
+        orientation: PageOrientation,
+    },
+    /// `<details>` block: the `<summary>` text and the elements inside it
+    Details {
+        summary: String,
+        body: Vec<MarkdownElement>,
+    },
+    /// Blockquote (`> ...`); nested quotes are blockquotes in its content
+    BlockQuote {
+        content: Vec<MarkdownElement>,
+    },
+}
+
+/// Page orientation of a document section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageOrientation {
+    #[default]
+    Portrait,
+    Landscape,
 }
 
 /// Represents inline elements within paragraphs
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum InlineElement {
     Text(String),
     Bold(String),
@@ -52,13 +222,126 @@ pub enum InlineElement {
         url: String,
         title: Option<String>,
     },
+    /// Text tagged with classes, written as `[text]{.class}`, or with a
+    /// language, written as `<span lang="fr">text</span>`
+    Span {
+        text: String,
+        classes: Vec<String>,
+        /// Language tag such as `fr-FR`, for spell-checking the text
+        lang: Option<String>,
+    },
+    /// Reference to a footnote definition (`[^label]`)
+    FootnoteReference(String),
+    /// Number of an entry in the references list, written in place of a link
+    ReferenceMarker(usize),
+    /// Reviewer comment from `<!-- comment: ... -->`, anchored to the element before it
+    Comment(String),
+    /// Index term from `<!-- index: term -->`, marking the place it was written
+    IndexTerm(String),
+    /// Place of an index entry, numbered by the index transform
+    IndexMarker(usize),
+    /// Abbreviation shown with its full name as a tooltip
+    Abbreviation {
+        text: String,
+        title: String,
+    },
+    /// Revision note from `<!-- rev: ... -->`, anchored like a comment
+    RevisionNote(String),
+}
+
+/// A link inside a cell of a pipe table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableLink {
+    /// Data row of the cell, or `None` for the header row
+    pub row: Option<usize>,
+    pub column: usize,
+    /// Byte offset of the link text in the cell text
+    pub offset: usize,
+    pub text: String,
+    pub url: String,
+}
+
+/// A table parsed from an HTML block
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HtmlTable {
+    pub rows: Vec<Vec<HtmlTableCell>>,
+}
+
+/// A `<td>` or `<th>` cell of an HTML table
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HtmlTableCell {
+    pub text: String,
+    /// Whether the cell was a `<th>`
+    pub header: bool,
+    /// `<a href>` links in the cell text, with the cell's row and column
+    pub links: Vec<TableLink>,
+    /// Tables nested inside the cell, rendered after its text
+    pub tables: Vec<HtmlTable>,
 }
 
 /// Represents a list item
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ListItem {
     pub content: Vec<InlineElement>,
     pub sub_items: Vec<ListItem>,
+    /// Paragraphs and code blocks after the item's first paragraph, rendered
+    /// indented under it before any sub-items
+    pub blocks: Vec<ListItemBlock>,
+    /// Task list state: `Some(true)` for `- [x]`, `Some(false)` for `- [ ]`,
+    /// `None` for an ordinary item
+    pub checked: Option<bool>,
+    /// Whether the item is in a numbered list; for sub-items this tells
+    /// their nested list's kind, while top-level items follow their `List`
+    pub ordered: bool,
+}
+
+/// Block-level child of a list item
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListItemBlock {
+    Paragraph(Vec<InlineElement>),
+    CodeBlock {
+        language: Option<String>,
+        code: String,
+        attributes: CodeAttributes,
+    },
+}
+
+/// A top-level code block, as yielded by [`MarkdownDocument::code_blocks`]
+#[derive(Debug, Clone, Copy)]
+pub struct CodeBlockRef<'a> {
+    pub language: Option<&'a str>,
+    pub code: &'a str,
+    pub processed: Option<&'a crate::markdown::code_block::ProcessedCodeBlock>,
+}
+
+/// A top-level code block open for rewriting, as yielded by
+/// [`MarkdownDocument::code_blocks_mut`]
+///
+/// `code` is the Markdown source of the block; what the generator renders
+/// is `processed`'s final code when set.
+#[derive(Debug)]
+pub struct CodeBlockMut<'a> {
+    pub language: &'a mut Option<String>,
+    pub code: &'a mut String,
+    pub processed: &'a mut Option<crate::markdown::code_block::ProcessedCodeBlock>,
+}
+
+impl CodeBlockMut<'_> {
+    /// The processing result, created unprocessed if the block has none yet
+    pub fn processed_or_default(&mut self) -> &mut crate::markdown::code_block::ProcessedCodeBlock {
+        let (code, language) = (&*self.code, &*self.language);
+        self.processed.get_or_insert_with(|| {
+            crate::markdown::code_block::ProcessedCodeBlock::unprocessed(code.clone(), language.clone())
+        })
+    }
+
+    /// Render `code` in place of the block's source, keeping the source as
+    /// the original
+    pub fn replace_code(&mut self, code: String) {
+        let processed = self.processed_or_default();
+        processed.processed_code = Some(code);
+        processed.metadata.is_formatted = true;
+    }
 }
 
 impl MarkdownDocument {
@@ -66,6 +349,8 @@ impl MarkdownDocument {
     pub fn new() -> Self {
         Self {
             elements: Vec::new(),
+            margins: MarginOverrides::default(),
+            details: ElementDetails::default(),
         }
     }
 
@@ -74,6 +359,62 @@ impl MarkdownDocument {
         self.elements.push(element);
     }
 
+    /// Add a paragraph with classes, as written with a trailing `{.class}`
+    pub fn add_classed_paragraph(&mut self, content: Vec<InlineElement>, classes: Vec<String>) {
+        let mut paragraphs = 0;
+        self.traverse(|element| {
+            if matches!(element, MarkdownElement::Paragraph { .. }) {
+                paragraphs += 1;
+            }
+        });
+        if !classes.is_empty() {
+            self.details.paragraph_classes.insert(paragraphs, classes);
+        }
+        self.add_element(MarkdownElement::Paragraph { content });
+    }
+
+    /// Index the element details by element, for walking the document
+    pub fn details_lookup(&self) -> DetailsLookup {
+        let mut ordinals = Ordinals::default();
+        let mut by_address = HashMap::new();
+        self.traverse(|element| {
+            if let Some(ordinal) = ordinals.next(element) {
+                by_address.insert(element as *const MarkdownElement as usize, ordinal);
+            }
+        });
+        DetailsLookup { details: self.details.clone(), ordinals: by_address }
+    }
+
+    /// Visit every paragraph in [`traverse`](Self::traverse) order along
+    /// with its classes, which the visitor may change
+    pub fn for_each_paragraph_mut<F>(&mut self, mut visitor: F)
+    where
+        F: FnMut(&mut Vec<InlineElement>, &mut Vec<String>),
+    {
+        let paragraph_classes = &mut self.details.paragraph_classes;
+        let mut ordinal = 0;
+        let mut visit = |element: &mut MarkdownElement| {
+            if let MarkdownElement::Paragraph { content } = element {
+                let mut classes = paragraph_classes.remove(&ordinal).unwrap_or_default();
+                visitor(content, &mut classes);
+                if !classes.is_empty() {
+                    paragraph_classes.insert(ordinal, classes);
+                }
+                ordinal += 1;
+            }
+        };
+        for element in &mut self.elements {
+            visit(element);
+            element.traverse_children_mut(&mut visit);
+        }
+    }
+
+    /// Write the document back as normalized Markdown (see
+    /// [`writer`](crate::markdown::writer))
+    pub fn to_markdown(&self) -> String {
+        crate::markdown::writer::write_document(self)
+    }
+
     /// Get all elements of a specific type
     pub fn get_elements_by_type<F>(&self, predicate: F) -> Vec<&MarkdownElement>
     where
@@ -102,6 +443,30 @@ impl MarkdownDocument {
         self.elements.iter_mut().filter(|e| e.is_code_block()).collect()
     }
 
+    /// Iterate over the top-level code blocks in document order; code blocks
+    /// inside list items are not included
+    pub fn code_blocks(&self) -> impl Iterator<Item = CodeBlockRef<'_>> {
+        self.elements.iter().filter_map(|element| match element {
+            MarkdownElement::CodeBlock { language, code, processed, .. } => Some(CodeBlockRef {
+                language: language.as_deref(),
+                code,
+                processed: processed.as_ref(),
+            }),
+            _ => None,
+        })
+    }
+
+    /// Iterate mutably over the top-level code blocks, for transforms run
+    /// between parsing and generation
+    pub fn code_blocks_mut(&mut self) -> impl Iterator<Item = CodeBlockMut<'_>> {
+        self.elements.iter_mut().filter_map(|element| match element {
+            MarkdownElement::CodeBlock { language, code, processed, .. } => {
+                Some(CodeBlockMut { language, code, processed })
+            }
+            _ => None,
+        })
+    }
+
     /// Get code blocks by language
     pub fn get_code_blocks_by_language(&self, language: &str) -> Vec<&MarkdownElement> {
         self.get_code_blocks().into_iter()
@@ -178,7 +543,7 @@ impl MarkdownDocument {
                     text.push_str(heading_text);
                     text.push('\n');
                 }
-                MarkdownElement::Paragraph { content } => {
+                MarkdownElement::Paragraph { content, .. } => {
                     for inline in content {
                         text.push_str(&inline.extract_text());
                     }
@@ -200,27 +565,98 @@ impl MarkdownDocument {
 }
 
 impl MarkdownElement {
-    /// Traverse child elements (for lists)
+    /// Traverse child elements (for lists, details bodies and blockquotes)
     pub fn traverse_children<F>(&self, visitor: &mut F)
     where
         F: FnMut(&MarkdownElement),
     {
-        if let MarkdownElement::List { items, .. } = self {
-            for item in items {
-                item.traverse_children(visitor);
+        match self {
+            MarkdownElement::List { items, .. } => {
+                for item in items {
+                    item.traverse_children(visitor);
+                }
+            }
+            MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                for element in body {
+                    visitor(element);
+                    element.traverse_children(visitor);
+                }
             }
+            _ => {}
         }
     }
 
-    /// Traverse child elements mutably (for lists)
+    /// Traverse child elements mutably (for lists and details bodies)
     pub fn traverse_children_mut<F>(&mut self, visitor: &mut F)
     where
         F: FnMut(&mut MarkdownElement),
     {
-        if let MarkdownElement::List { items, .. } = self {
-            for item in items {
-                item.traverse_children_mut(visitor);
+        match self {
+            MarkdownElement::List { items, .. } => {
+                for item in items {
+                    item.traverse_children_mut(visitor);
+                }
+            }
+            MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                for element in body {
+                    visitor(element);
+                    element.traverse_children_mut(visitor);
+                }
             }
+            _ => {}
+        }
+    }
+
+    /// Compare the Markdown source of two elements, ignoring code block
+    /// processing results
+    pub fn same_source(&self, other: &MarkdownElement) -> bool {
+        match (self, other) {
+            (
+                MarkdownElement::Heading { level: a_level, text: a_text },
+                MarkdownElement::Heading { level: b_level, text: b_text },
+            ) => a_level == b_level && a_text == b_text,
+            (
+                MarkdownElement::Paragraph { content: a_content },
+                MarkdownElement::Paragraph { content: b_content },
+            ) => a_content == b_content,
+            (
+                MarkdownElement::CodeBlock { language: a_lang, code: a_code, .. },
+                MarkdownElement::CodeBlock { language: b_lang, code: b_code, .. },
+            ) => a_lang == b_lang && a_code == b_code,
+            (
+                MarkdownElement::List { ordered: a_ordered, items: a_items },
+                MarkdownElement::List { ordered: b_ordered, items: b_items },
+            ) => a_ordered == b_ordered && a_items == b_items,
+            (
+                MarkdownElement::Table { headers: a_headers, rows: a_rows, links: a_links },
+                MarkdownElement::Table { headers: b_headers, rows: b_rows, links: b_links },
+            ) => a_headers == b_headers && a_rows == b_rows && a_links == b_links,
+            (MarkdownElement::HtmlTable(a), MarkdownElement::HtmlTable(b)) => a == b,
+            (
+                MarkdownElement::Image { alt_text: a_alt, url: a_url, title: a_title, width: a_w, height: a_h },
+                MarkdownElement::Image { alt_text: b_alt, url: b_url, title: b_title, width: b_w, height: b_h },
+            ) => a_alt == b_alt && a_url == b_url && a_title == b_title && a_w == b_w && a_h == b_h,
+            (MarkdownElement::HorizontalRule, MarkdownElement::HorizontalRule) => true,
+            (
+                MarkdownElement::FootnoteDefinition { label: a_label, content: a_content },
+                MarkdownElement::FootnoteDefinition { label: b_label, content: b_content },
+            ) => a_label == b_label && a_content == b_content,
+            (
+                MarkdownElement::SectionBreak { orientation: a },
+                MarkdownElement::SectionBreak { orientation: b },
+            ) => a == b,
+            (
+                MarkdownElement::Details { summary: a_summary, body: a_body },
+                MarkdownElement::Details { summary: b_summary, body: b_body },
+            ) => {
+                a_summary == b_summary
+                    && a_body.len() == b_body.len()
+                    && a_body.iter().zip(b_body).all(|(a, b)| a.same_source(b))
+            }
+            (MarkdownElement::BlockQuote { content: a_body }, MarkdownElement::BlockQuote { content: b_body }) => {
+                a_body.len() == b_body.len() && a_body.iter().zip(b_body).all(|(a, b)| a.same_source(b))
+            }
+            _ => false,
         }
     }
 
@@ -232,8 +668,13 @@ impl MarkdownElement {
             MarkdownElement::CodeBlock { .. } => "code_block",
             MarkdownElement::List { .. } => "list",
             MarkdownElement::Table { .. } => "table",
+            MarkdownElement::HtmlTable(_) => "html_table",
             MarkdownElement::Image { .. } => "image",
             MarkdownElement::HorizontalRule => "horizontal_rule",
+            MarkdownElement::FootnoteDefinition { .. } => "footnote_definition",
+            MarkdownElement::SectionBreak { .. } => "section_break",
+            MarkdownElement::Details { .. } => "details",
+            MarkdownElement::BlockQuote { .. } => "blockquote",
         }
     }
 
@@ -246,6 +687,8 @@ impl MarkdownElement {
                 | MarkdownElement::CodeBlock { .. }
                 | MarkdownElement::List { .. }
                 | MarkdownElement::Table { .. }
+                | MarkdownElement::HtmlTable(_)
+                | MarkdownElement::FootnoteDefinition { .. }
         )
     }
 
@@ -313,7 +756,7 @@ impl MarkdownElement {
     pub fn extract_text(&self) -> String {
         match self {
             MarkdownElement::Heading { text, .. } => text.clone(),
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 content.iter().map(|inline| inline.extract_text()).collect::<Vec<_>>().join("")
             }
             MarkdownElement::CodeBlock { processed, code, .. } => {
@@ -325,7 +768,7 @@ impl MarkdownElement {
             MarkdownElement::List { items, .. } => {
                 items.iter().map(|item| item.extract_text()).collect::<Vec<_>>().join("\n")
             }
-            MarkdownElement::Table { headers, rows } => {
+            MarkdownElement::Table { headers, rows, .. } => {
                 let mut text = headers.join(" | ");
                 text.push('\n');
                 for row in rows {
@@ -334,12 +777,56 @@ impl MarkdownElement {
                 }
                 text
             }
+            MarkdownElement::HtmlTable(table) => table.extract_text(),
             MarkdownElement::Image { alt_text, .. } => alt_text.clone(),
-            MarkdownElement::HorizontalRule => String::new(),
+            MarkdownElement::HorizontalRule | MarkdownElement::SectionBreak { .. } => String::new(),
+            MarkdownElement::FootnoteDefinition { content, .. } => {
+                content.iter().map(|inline| inline.extract_text()).collect::<Vec<_>>().join("")
+            }
+            MarkdownElement::Details { summary, body } => std::iter::once(summary.clone())
+                .chain(body.iter().map(|element| element.extract_text()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            MarkdownElement::BlockQuote { content: body } => {
+                body.iter().map(|element| element.extract_text()).collect::<Vec<_>>().join("\n")
+            }
         }
     }
 }
 
+impl HtmlTable {
+    /// Extract plain text, one line per row with nested tables on their own lines
+    pub fn extract_text(&self) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            let cells: Vec<&str> = row.iter().map(|cell| cell.text.as_str()).collect();
+            text.push_str(&cells.join(" | "));
+            text.push('\n');
+            for nested in row.iter().flat_map(|cell| &cell.tables) {
+                text.push_str(&nested.extract_text());
+            }
+        }
+        text
+    }
+
+    /// Number of table levels, counting this one
+    pub fn depth(&self) -> usize {
+        1 + self
+            .rows
+            .iter()
+            .flatten()
+            .flat_map(|cell| &cell.tables)
+            .map(HtmlTable::depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Number of columns in the widest row
+    pub fn column_count(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+}
+
 impl InlineElement {
     /// Extract plain text from inline element
     pub fn extract_text(&self) -> String {
@@ -350,6 +837,14 @@ impl InlineElement {
             InlineElement::Strikethrough(text) => text.clone(),
             InlineElement::Code(text) => text.clone(),
             InlineElement::Link { text, .. } => text.clone(),
+            InlineElement::Span { text, .. } => text.clone(),
+            InlineElement::Abbreviation { text, .. } => text.clone(),
+            InlineElement::FootnoteReference(_)
+            | InlineElement::ReferenceMarker(_)
+            | InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_) => String::new(),
         }
     }
 
@@ -362,6 +857,14 @@ impl InlineElement {
             InlineElement::Strikethrough(_) => "strikethrough",
             InlineElement::Code(_) => "code",
             InlineElement::Link { .. } => "link",
+            InlineElement::Span { .. } => "span",
+            InlineElement::FootnoteReference(_) => "footnote_reference",
+            InlineElement::ReferenceMarker(_) => "reference_marker",
+            InlineElement::Comment(_) => "comment",
+            InlineElement::IndexTerm(_) => "index_term",
+            InlineElement::IndexMarker(_) => "index_marker",
+            InlineElement::Abbreviation { .. } => "abbreviation",
+            InlineElement::RevisionNote(_) => "revision_note",
         }
     }
 
@@ -380,9 +883,28 @@ impl ListItem {
         Self {
             content,
             sub_items: Vec::new(),
+            blocks: Vec::new(),
+            checked: None,
+            ordered: false,
         }
     }
 
+    /// The item's own inline content followed by that of its paragraph blocks
+    pub fn paragraphs(&self) -> impl Iterator<Item = &Vec<InlineElement>> {
+        std::iter::once(&self.content).chain(self.blocks.iter().filter_map(|block| match block {
+            ListItemBlock::Paragraph(content) => Some(content),
+            ListItemBlock::CodeBlock { .. } => None,
+        }))
+    }
+
+    /// Mutable counterpart of [`paragraphs`](Self::paragraphs)
+    pub fn paragraphs_mut(&mut self) -> impl Iterator<Item = &mut Vec<InlineElement>> {
+        std::iter::once(&mut self.content).chain(self.blocks.iter_mut().filter_map(|block| match block {
+            ListItemBlock::Paragraph(content) => Some(content),
+            ListItemBlock::CodeBlock { .. } => None,
+        }))
+    }
+
     /// Add a sub-item to this list item
     pub fn add_sub_item(&mut self, item: ListItem) {
         self.sub_items.push(item);
@@ -411,6 +933,15 @@ impl ListItem {
     /// Extract text from list item
     pub fn extract_text(&self) -> String {
         let mut text = self.content.iter().map(|inline| inline.extract_text()).collect::<Vec<_>>().join("");
+        for block in &self.blocks {
+            text.push('\n');
+            match block {
+                ListItemBlock::Paragraph(content) => {
+                    text.push_str(&content.iter().map(|inline| inline.extract_text()).collect::<Vec<_>>().join(""));
+                }
+                ListItemBlock::CodeBlock { code, .. } => text.push_str(code),
+            }
+        }
         if !self.sub_items.is_empty() {
             text.push('\n');
             for sub_item in &self.sub_items {
@@ -572,6 +1103,35 @@ mod tests {
         assert!(text.contains("Sub item"));
     }
 
+    #[test]
+    fn test_code_block_iterators() {
+        let mut document = MarkdownDocument::new();
+        for (language, code) in [(Some("rust"), "fn a() {}"), (None, "plain"), (Some("rust"), "fn b() {}")] {
+            document.add_element(MarkdownElement::CodeBlock {
+                language: language.map(str::to_string),
+                code: code.to_string(),
+                processed: None,
+            });
+            document.add_element(MarkdownElement::HorizontalRule);
+        }
+
+        let rust: Vec<&str> = document
+            .code_blocks()
+            .filter(|block| block.language == Some("rust"))
+            .map(|block| block.code)
+            .collect();
+        assert_eq!(rust, vec!["fn a() {}", "fn b() {}"]);
+
+        for mut block in document.code_blocks_mut().filter(|block| block.language.is_none()) {
+            let annotated = format!("// plain\n{}", block.code);
+            block.replace_code(annotated);
+        }
+        let plain = document.code_blocks().nth(1).unwrap();
+        assert_eq!(plain.code, "plain");
+        assert_eq!(plain.processed.unwrap().get_final_code(), "// plain\nplain");
+        assert!(document.code_blocks().next().unwrap().processed.is_none());
+    }
+
     #[test]
     fn test_code_block_element() {
         let code_block = MarkdownElement::CodeBlock {
@@ -664,6 +1224,7 @@ mod tests {
                 vec!["Alice".to_string(), "30".to_string()],
                 vec!["Bob".to_string(), "25".to_string()],
             ],
+            links: vec![],
         };
         
         assert_eq!(table.element_type(), "table");
@@ -869,4 +1430,32 @@ mod tests {
         assert_eq!(processed_count, 1);
         assert_eq!(unprocessed_count, 0);
     }
+
+    #[test]
+    fn test_paragraph_classes_by_element() {
+        let text = |text: &str| vec![InlineElement::Text(text.to_string())];
+        let mut doc = MarkdownDocument::new();
+        doc.add_element(MarkdownElement::Paragraph { content: text("plain") });
+        doc.add_element(MarkdownElement::BlockQuote {
+            content: vec![MarkdownElement::Paragraph { content: text("quoted") }],
+        });
+        doc.add_classed_paragraph(text("after"), vec!["note".to_string()]);
+        assert_eq!(doc.details.paragraph_classes.keys().collect::<Vec<_>>(), [&2]);
+
+        doc.for_each_paragraph_mut(|content, classes| {
+            if content == &text("quoted") {
+                classes.push("tip".to_string());
+            }
+        });
+        let details = doc.details_lookup();
+        let MarkdownElement::BlockQuote { content: quote } = &doc.elements[1] else {
+            panic!("Expected blockquote");
+        };
+        assert!(details.classes(&doc.elements[0]).is_empty());
+        assert_eq!(details.classes(&quote[0]), ["tip".to_string()]);
+        assert_eq!(details.classes(&doc.elements[2]), ["note".to_string()]);
+        // The quote itself has no classes, though its paragraph does
+        assert!(details.classes(&doc.elements[1]).is_empty());
+        assert!(!details.same_details(&doc.elements[1], &MarkdownDocument::new().details_lookup(), &doc.elements[1]));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,47 @@
+//! Unicode bullet characters in plain paragraphs
+//!
+//! Text pasted from word processors or web pages often keeps its list
+//! bullets as literal `•` characters, which Markdown reads as one run-on
+//! paragraph. A paragraph is taken to be such a list only when every one of
+//! its lines starts with a bullet glyph followed by whitespace, so a bullet
+//! used as a separator inside a sentence is left alone.
+
+/// Characters recognized as bullets at the start of a line
+pub const BULLET_GLYPHS: &[char] = &['•', '◦', '‣', '⁃', '●', '○', '▪', '▫', '■', '□', '►', '▸'];
+
+/// Text of each line after its bullet, when every non-empty line of a
+/// paragraph's source starts with a bullet glyph and whitespace
+pub fn bullet_lines(source: &str) -> Option<Vec<&str>> {
+    let lines: Vec<&str> = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    lines.into_iter().map(strip_bullet).collect()
+}
+
+fn strip_bullet(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(BULLET_GLYPHS)?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let text = rest.trim_start();
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bullet_lines() {
+        assert_eq!(bullet_lines("• First\n●  Second\n"), Some(vec!["First", "Second"]));
+        assert_eq!(bullet_lines("• First\nnot a bullet"), None);
+        assert_eq!(bullet_lines("Prices • Terms • Contact"), None);
+        assert_eq!(bullet_lines("•tight"), None);
+        assert_eq!(bullet_lines("• "), None);
+    }
+}
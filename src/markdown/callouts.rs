@@ -0,0 +1,62 @@
+//! Emphasis-only paragraphs as callouts
+//!
+//! A paragraph written entirely in bold, or entirely in italics, often
+//! stands in for a callout box. Such paragraphs are given the
+//! [`CALLOUT_CLASS`] class, which is styled by `elements.emphasis_callouts`.
+//! Whitespace between the emphasized runs, comments and index terms do not
+//! count against a paragraph; any other text, or a mix of bold and italics,
+//! leaves it untouched.
+
+use crate::markdown::ast::{InlineElement, MarkdownDocument};
+
+/// Class given to emphasis-only paragraphs
+pub const CALLOUT_CLASS: &str = "callout";
+
+/// Whether paragraph content is entirely bold or entirely italic
+pub fn is_emphasis_only(content: &[InlineElement]) -> bool {
+    let mut emphasis = None;
+    for inline in content {
+        let kind = match inline {
+            InlineElement::Bold(_) => "bold",
+            InlineElement::Italic(_) => "italic",
+            InlineElement::Text(text) if text.trim().is_empty() => continue,
+            InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_) => continue,
+            _ => return false,
+        };
+        if emphasis.replace(kind).is_some_and(|previous| previous != kind) {
+            return false;
+        }
+    }
+    emphasis.is_some()
+}
+
+/// Give emphasis-only paragraphs the callout class
+pub fn apply_to_document(document: &mut MarkdownDocument) {
+    document.for_each_paragraph_mut(|content, classes| {
+        if is_emphasis_only(content) && !classes.iter().any(|class| class == CALLOUT_CLASS) {
+            classes.push(CALLOUT_CLASS.to_string());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emphasis_only_paragraphs() {
+        let bold = |text: &str| InlineElement::Bold(text.to_string());
+        let italic = |text: &str| InlineElement::Italic(text.to_string());
+        let text = |text: &str| InlineElement::Text(text.to_string());
+
+        assert!(is_emphasis_only(&[bold("Warning: back up first.")]));
+        assert!(is_emphasis_only(&[italic("Note"), text(" "), italic("this.")]));
+        assert!(!is_emphasis_only(&[bold("Warning:"), text(" back up first.")]));
+        assert!(!is_emphasis_only(&[bold("Bold"), text(" "), italic("italic")]));
+        assert!(!is_emphasis_only(&[text("   ")]));
+        assert!(!is_emphasis_only(&[]));
+    }
+}
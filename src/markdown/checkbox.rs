@@ -0,0 +1,184 @@
+//! Inline checkbox conversion
+//!
+//! Replaces standalone `[x]`/`[X]` and `[ ]` tokens in prose with ballot box
+//! glyphs. Task list items are unaffected: their markers are parsed into
+//! [`ListItem::checked`] and rendered as [`TASK_CHECKED`] or [`UNCHECKED`]. Tokens directly followed by `(`, `[` or `:` look like link syntax
+//! and are left untouched, as are code spans and code blocks.
+
+use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use std::borrow::Cow;
+
+/// Glyph for a checked box, `[x]`
+pub const CHECKED: &str = "☒";
+/// Glyph for an unchecked box, `[ ]`
+pub const UNCHECKED: &str = "☐";
+/// Glyph for a done task list item, `- [x]`
+pub const TASK_CHECKED: &str = "☑";
+
+/// Replace standalone checkbox tokens in plain text
+pub fn replace_checkboxes(text: &str) -> Cow<'_, str> {
+    if !text.contains('[') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut previous: Option<char> = None;
+    let mut rest = text;
+
+    while let Some(pos) = rest.find('[') {
+        let before = &rest[..pos];
+        result.push_str(before);
+        previous = before.chars().next_back().or(previous);
+        rest = &rest[pos..];
+
+        let glyph = match rest.get(..3) {
+            Some("[x]") | Some("[X]") => Some(CHECKED),
+            Some("[ ]") => Some(UNCHECKED),
+            _ => None,
+        };
+        match glyph {
+            Some(glyph) if is_standalone(previous, rest[3..].chars().next()) => {
+                result.push_str(glyph);
+                rest = &rest[3..];
+                previous = Some(']');
+                changed = true;
+            }
+            _ => {
+                result.push('[');
+                rest = &rest[1..];
+                previous = Some('[');
+            }
+        }
+    }
+    result.push_str(rest);
+
+    if changed {
+        Cow::Owned(result)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Whether a token between these characters stands alone rather than being part of a word or link
+fn is_standalone(before: Option<char>, after: Option<char>) -> bool {
+    let before_ok = before.is_none_or(|c| c.is_whitespace() || c == '(');
+    let after_ok = after.is_none_or(|c| {
+        c.is_whitespace() || (c.is_ascii_punctuation() && !matches!(c, '(' | '[' | ':'))
+    });
+    before_ok && after_ok
+}
+
+/// Rewrite checkbox tokens in the prose of the document
+pub fn apply_to_document(document: &mut MarkdownDocument) {
+    for element in &mut document.elements {
+        match element {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => apply_to_inlines(content),
+            MarkdownElement::List { items, .. } => apply_to_items(items),
+            _ => {}
+        }
+    }
+}
+
+fn apply_to_items(items: &mut [ListItem]) {
+    for item in items {
+        for content in item.paragraphs_mut() {
+            apply_to_inlines(content);
+        }
+        apply_to_items(&mut item.sub_items);
+    }
+}
+
+fn apply_to_inlines(inlines: &mut Vec<InlineElement>) {
+    merge_adjacent_text(inlines);
+    for inline in inlines {
+        match inline {
+            InlineElement::Text(text)
+            | InlineElement::Bold(text)
+            | InlineElement::Italic(text)
+            | InlineElement::Strikethrough(text)
+            | InlineElement::Span { text, .. } => {
+                if let Cow::Owned(replaced) = replace_checkboxes(text) {
+                    *text = replaced;
+                }
+            }
+            InlineElement::Code(_)
+            | InlineElement::Link { .. }
+            | InlineElement::FootnoteReference(_)
+            | InlineElement::ReferenceMarker(_)
+            | InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_)
+            | InlineElement::Abbreviation { .. } => {}
+        }
+    }
+}
+
+/// The parser may split `[ ]` into several text runs; join them so the token is seen whole
+fn merge_adjacent_text(inlines: &mut Vec<InlineElement>) {
+    let mut merged: Vec<InlineElement> = Vec::with_capacity(inlines.len());
+    for inline in inlines.drain(..) {
+        match (merged.last_mut(), inline) {
+            (Some(InlineElement::Text(previous)), InlineElement::Text(text)) => previous.push_str(&text),
+            (_, inline) => merged.push(inline),
+        }
+    }
+    *inlines = merged;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standalone_tokens_replaced() {
+        assert_eq!(replace_checkboxes("[x] done, [ ] todo"), "☒ done, ☐ todo");
+        assert_eq!(replace_checkboxes("Shipped [X]."), "Shipped ☒.");
+        assert_eq!(replace_checkboxes("([ ])"), "(☐)");
+    }
+
+    #[test]
+    fn test_link_like_tokens_untouched() {
+        assert!(matches!(replace_checkboxes("[x](https://example.com)"), Cow::Borrowed(_)));
+        assert!(matches!(replace_checkboxes("[x][ref] and [x]: url"), Cow::Borrowed(_)));
+        assert!(matches!(replace_checkboxes("a[x] [x]b [xy] [  ]"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_apply_to_document_joins_split_text() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::List {
+            ordered: false,
+            items: vec![ListItem {
+                content: vec![
+                    InlineElement::Text("item ".to_string()),
+                    InlineElement::Text("[".to_string()),
+                    InlineElement::Text(" ".to_string()),
+                    InlineElement::Text("]".to_string()),
+                    InlineElement::Code("[x]".to_string()),
+                ],
+                sub_items: vec![],
+                blocks: vec![],
+                checked: None,
+                ordered: false,
+            }],
+        });
+
+        apply_to_document(&mut document);
+
+        match &document.elements[0] {
+            MarkdownElement::List { items, .. } => {
+                assert_eq!(
+                    items[0].content,
+                    vec![
+                        InlineElement::Text("item ☐".to_string()),
+                        InlineElement::Code("[x]".to_string()),
+                    ]
+                );
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+    }
+}
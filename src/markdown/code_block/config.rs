@@ -32,6 +32,13 @@ pub struct GlobalConfig {
     pub default_timeout_ms: u64,
     pub max_cache_size: usize,
     pub enable_parallel_processing: bool,
+    /// Fail the conversion when any code block strategy reports errors
+    /// instead of falling back to the original code
+    #[serde(default)]
+    pub strict: bool,
+    /// Strategy for code blocks whose language no strategy handles
+    #[serde(default)]
+    pub fallback: StrategySelection,
 }
 
 /// Language-specific configuration
@@ -41,6 +48,45 @@ pub struct LanguageConfig {
     pub enable_formatting: bool,
     pub formatter_options: HashMap<String, String>,
     pub custom_options: HashMap<String, String>,
+    /// Strategy to use for this language instead of the one registered for it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<StrategySelection>,
+}
+
+/// Which strategy processes a code block
+///
+/// Written in configuration as `default`, `plain` or the name of a
+/// registered strategy.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum StrategySelection {
+    /// The built-in default strategy
+    #[default]
+    Default,
+    /// No strategy; the code is rendered as written
+    Plain,
+    /// The strategy registered under this language name
+    Named(String),
+}
+
+impl From<String> for StrategySelection {
+    fn from(name: String) -> Self {
+        match name.trim().to_lowercase().as_str() {
+            "default" => Self::Default,
+            "plain" => Self::Plain,
+            other => Self::Named(other.to_string()),
+        }
+    }
+}
+
+impl From<StrategySelection> for String {
+    fn from(selection: StrategySelection) -> Self {
+        match selection {
+            StrategySelection::Default => "default".to_string(),
+            StrategySelection::Plain => "plain".to_string(),
+            StrategySelection::Named(name) => name,
+        }
+    }
 }
 
 impl Default for ProcessingConfig {
@@ -62,6 +108,8 @@ impl Default for GlobalConfig {
             default_timeout_ms: 5000,
             max_cache_size: 1000,
             enable_parallel_processing: false,
+            strict: false,
+            fallback: StrategySelection::Default,
         }
     }
 }
@@ -73,6 +121,7 @@ impl Default for LanguageConfig {
             enable_formatting: false,
             formatter_options: HashMap::new(),
             custom_options: HashMap::new(),
+            strategy: None,
         }
     }
 }
@@ -278,6 +327,12 @@ impl CodeBlockConfig {
         if other.global.enable_parallel_processing != GlobalConfig::default().enable_parallel_processing {
             self.global.enable_parallel_processing = other.global.enable_parallel_processing;
         }
+        if other.global.strict != GlobalConfig::default().strict {
+            self.global.strict = other.global.strict;
+        }
+        if other.global.fallback != GlobalConfig::default().fallback {
+            self.global.fallback = other.global.fallback.clone();
+        }
 
         // Merge language configs
         for (lang, config) in &other.languages {
@@ -411,6 +466,12 @@ impl LanguageConfig {
         self.custom_options.insert(key.to_string(), value.to_string());
         self
     }
+
+    /// Process this language with the given strategy
+    pub fn with_strategy(mut self, strategy: StrategySelection) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -756,4 +817,33 @@ languages: {}
         assert_eq!(config.global.default_timeout_ms, 300_000);
         assert_eq!(config.global.max_cache_size, 100_000);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_strategy_selection_from_yaml() {
+        let yaml = r#"
+global:
+  enable_processing: true
+  default_timeout_ms: 5000
+  max_cache_size: 1000
+  enable_parallel_processing: false
+  fallback: plain
+languages:
+  jsonc:
+    enable_syntax_validation: true
+    enable_formatting: false
+    formatter_options: {}
+    custom_options: {}
+    strategy: JSON
+"#;
+        let config = CodeBlockConfig::from_yaml(yaml).unwrap();
+        assert_eq!(config.global.fallback, StrategySelection::Plain);
+        assert_eq!(
+            config.get_language_config("jsonc").strategy,
+            Some(StrategySelection::Named("json".to_string()))
+        );
+        assert_eq!(CodeBlockConfig::new().global.fallback, StrategySelection::Default);
+
+        let round_trip = CodeBlockConfig::from_yaml(&config.to_yaml().unwrap()).unwrap();
+        assert_eq!(round_trip.global.fallback, StrategySelection::Plain);
+    }
+}
@@ -3,9 +3,11 @@
 use std::sync::Arc;
 use std::time::Instant;
 use crate::markdown::code_block::{
-    StrategyRegistry, CodeBlockConfig, ProcessingConfig, ProcessedCodeBlock, ProcessingError
+    StrategyRegistry, CodeBlockConfig, ProcessingConfig, ProcessedCodeBlock, ProcessingError,
+    StrategySelection,
 };
 use crate::error::ConversionError;
+use crate::markdown::fence_info::CodeAttributes;
 
 /// Code block processor that integrates the strategy system with the parser
 pub struct CodeBlockProcessor {
@@ -51,6 +53,18 @@ impl CodeBlockProcessor {
         &self,
         code: &str,
         language: Option<&str>,
+    ) -> Result<ProcessedCodeBlock, ProcessingError> {
+        self.process_code_block_with_attributes(code, language, &CodeAttributes::default())
+    }
+
+    /// Process a code block, honoring the attributes of its fence info string
+    ///
+    /// A `no_validate` attribute turns off the strategy's syntax validation.
+    pub fn process_code_block_with_attributes(
+        &self,
+        code: &str,
+        language: Option<&str>,
+        attributes: &CodeAttributes,
     ) -> Result<ProcessedCodeBlock, ProcessingError> {
         // Capture language as owned string for use in error handling
         let language_owned = language.map(|s| s.to_string());
@@ -72,12 +86,16 @@ impl CodeBlockProcessor {
         if let Some(lang) = language {
             processing_config.custom_options.insert("language".to_string(), lang.to_string());
         }
+        if attributes.no_validate {
+            processing_config.enable_syntax_validation = false;
+        }
 
-        // Get the appropriate strategy
-        let strategy = if let Some(lang) = language {
-            self.registry.get_strategy(lang)
-        } else {
-            self.registry.get_default_strategy()
+        // Get the appropriate strategy; none means render the code as written
+        let Some(strategy) = self.select_strategy(language) else {
+            return Ok(ProcessedCodeBlock::unprocessed(
+                code.to_string(),
+                language_owned,
+            ));
         };
 
         // Process with timeout handling
@@ -97,6 +115,33 @@ impl CodeBlockProcessor {
         }
     }
 
+    /// Pick the strategy for a language: the language's configured override,
+    /// then the strategy registered for it, then the configured fallback
+    fn select_strategy(&self, language: Option<&str>) -> Option<Arc<dyn crate::markdown::code_block::CodeBlockStrategy>> {
+        if let Some(lang) = language {
+            if let Some(selection) = self.config.get_language_config(lang).strategy {
+                return self.resolve_selection(&selection);
+            }
+            if self.registry.has_strategy_for_language(lang) {
+                return Some(self.registry.get_strategy(lang));
+            }
+        }
+        self.resolve_selection(&self.config.global.fallback)
+    }
+
+    fn resolve_selection(&self, selection: &StrategySelection) -> Option<Arc<dyn crate::markdown::code_block::CodeBlockStrategy>> {
+        match selection {
+            StrategySelection::Default => Some(self.registry.get_default_strategy()),
+            StrategySelection::Plain => None,
+            StrategySelection::Named(name) => Some(
+                self.registry.get_registered_strategy(name).unwrap_or_else(|| {
+                    tracing::warn!("No code block strategy named '{}', using the default strategy", name);
+                    self.registry.get_default_strategy()
+                }),
+            ),
+        }
+    }
+
     /// Process code block with timeout handling
     fn process_with_timeout(
         &self,
@@ -154,6 +199,11 @@ impl CodeBlockProcessor {
         // Register the Bash strategy
         use crate::markdown::code_block::strategies::BashStrategy;
         registry.register_strategy(std::sync::Arc::new(BashStrategy::new()));
+
+        // Register the JSON and YAML strategies
+        use crate::markdown::code_block::strategies::{JsonStrategy, YamlStrategy};
+        registry.register_strategy(std::sync::Arc::new(JsonStrategy::new()));
+        registry.register_strategy(std::sync::Arc::new(YamlStrategy::new()));
         
         // Future strategies to register:
         // - JavaScriptStrategy  
@@ -361,4 +411,40 @@ mod tests {
             _ => panic!("Expected ProcessingError variant"),
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_unknown_language_uses_configured_fallback() {
+        let mut config = CodeBlockConfig::new();
+        config.global.enable_processing = true;
+        config.global.fallback = StrategySelection::Named("json".to_string());
+        let processor = CodeBlockProcessor::with_config(config.clone());
+        let result = processor.process_code_block("{\"a\":1}", Some("mystery")).unwrap();
+        assert_eq!(result.metadata.custom_attributes.get("language"), Some(&"json".to_string()));
+
+        // Languages with their own strategy are unaffected by the fallback
+        let result = processor.process_code_block("key: value", Some("yaml")).unwrap();
+        assert_ne!(result.metadata.custom_attributes.get("language"), Some(&"json".to_string()));
+
+        config.global.fallback = StrategySelection::Plain;
+        let processor = CodeBlockProcessor::with_config(config);
+        let result = processor.process_code_block("{\"a\":1}", Some("mystery")).unwrap();
+        assert!(result.processed_code.is_none());
+        assert!(result.metadata.custom_attributes.is_empty());
+    }
+
+    #[test]
+    fn test_language_strategy_override() {
+        let mut config = CodeBlockConfig::new()
+            .with_language_config("jsonc", LanguageConfig::new().with_strategy(StrategySelection::Named("json".to_string())))
+            .with_language_config("rust", LanguageConfig::new().with_strategy(StrategySelection::Plain));
+        config.global.enable_processing = true;
+        let processor = CodeBlockProcessor::with_config(config);
+
+        let result = processor.process_code_block("{\"a\":1}", Some("JSONC")).unwrap();
+        assert_eq!(result.metadata.custom_attributes.get("language"), Some(&"json".to_string()));
+
+        let result = processor.process_code_block("fn main(){}", Some("rust")).unwrap();
+        assert!(result.processed_code.is_none());
+        assert!(result.metadata.custom_attributes.is_empty());
+    }
+}
@@ -31,7 +31,7 @@ pub struct ParallelConfig {
 impl Default for ParallelConfig {
     fn default() -> Self {
         Self {
-            max_workers: num_cpus::get().min(8), // Limit to 8 threads max
+            max_workers: crate::conversion::default_concurrency(),
             max_queue_size: 1000,
             task_timeout: Duration::from_secs(30),
             enable_work_stealing: true,
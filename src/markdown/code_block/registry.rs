@@ -68,6 +68,20 @@ impl StrategyRegistry {
             })
     }
 
+    /// Get the strategy registered under a language name or alias, without
+    /// falling back to the default strategy
+    pub fn get_registered_strategy(&self, name: &str) -> Option<Arc<dyn CodeBlockStrategy>> {
+        let normalized = name.to_lowercase();
+        self.strategies
+            .get(&normalized)
+            .or_else(|| {
+                self.language_aliases
+                    .get(&normalized)
+                    .and_then(|canonical| self.strategies.get(canonical))
+            })
+            .cloned()
+    }
+
     /// Get the default strategy
     pub fn get_default_strategy(&self) -> Arc<dyn CodeBlockStrategy> {
         self.default_strategy.clone()
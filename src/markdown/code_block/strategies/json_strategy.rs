@@ -0,0 +1,281 @@
+//! JSON code block processing strategy
+
+use std::time::Instant;
+use crate::markdown::code_block::{
+    CodeBlockStrategy, LanguageStrategy, ProcessedCodeBlock, ProcessingConfig,
+    ProcessingError, ProcessingMetadata, ProcessingWarning, language_matches
+};
+
+/// Indentation used when pretty-printing
+const INDENT: &str = "  ";
+
+/// Strategy for processing JSON code blocks
+///
+/// Validates the content with serde_json and, when formatting is enabled,
+/// re-indents it. Keys keep their written order and scalars their written
+/// form; only whitespace between tokens changes.
+#[derive(Debug, Clone)]
+pub struct JsonStrategy;
+
+impl JsonStrategy {
+    /// Create a new JSON strategy instance
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse the code, reporting the first syntax error with its position
+    fn check_json_syntax(&self, code: &str) -> Result<(), ProcessingError> {
+        serde_json::from_str::<serde::de::IgnoredAny>(code)
+            .map(|_| ())
+            .map_err(|e| ProcessingError::syntax_error(&e.to_string(), Some(e.line()), Some(e.column())))
+    }
+
+    /// Pretty-print valid JSON with two-space indentation
+    fn format_json_code(&self, code: &str) -> Result<String, ProcessingError> {
+        self.check_json_syntax(code)?;
+
+        // Drop the whitespace between tokens, keeping string contents intact
+        let mut tokens = Vec::with_capacity(code.len());
+        let mut in_string = false;
+        let mut escaped = false;
+        for c in code.chars() {
+            if in_string {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_string = false;
+                }
+            } else if c == '"' {
+                in_string = true;
+            } else if c.is_whitespace() {
+                continue;
+            }
+            tokens.push(c);
+        }
+
+        let mut result = String::with_capacity(tokens.len() * 2);
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        let newline = |result: &mut String, depth: usize| {
+            result.push('\n');
+            result.push_str(&INDENT.repeat(depth));
+        };
+        for (index, &c) in tokens.iter().enumerate() {
+            if in_string {
+                result.push(c);
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    in_string = false;
+                }
+                continue;
+            }
+            match c {
+                '"' => {
+                    in_string = true;
+                    result.push(c);
+                }
+                '{' | '[' => {
+                    result.push(c);
+                    // Empty containers stay on one line
+                    if !matches!(tokens.get(index + 1), Some('}' | ']')) {
+                        depth += 1;
+                        newline(&mut result, depth);
+                    }
+                }
+                '}' | ']' => {
+                    if !matches!(index.checked_sub(1).map(|i| tokens[i]), Some('{' | '[')) {
+                        depth -= 1;
+                        newline(&mut result, depth);
+                    }
+                    result.push(c);
+                }
+                ',' => {
+                    result.push(c);
+                    newline(&mut result, depth);
+                }
+                ':' => result.push_str(": "),
+                _ => result.push(c),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl CodeBlockStrategy for JsonStrategy {
+    fn process(&self, code: &str, config: &ProcessingConfig) -> Result<ProcessedCodeBlock, ProcessingError> {
+        let start_time = Instant::now();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        let syntax_error = self.check_json_syntax(code).err();
+        let syntax_valid = syntax_error.is_none();
+        if config.enable_syntax_validation {
+            errors.extend(syntax_error);
+        }
+
+        // Invalid JSON is rendered as written
+        let formatted_code = if config.enable_formatting {
+            match self.format_json_code(code) {
+                Ok(formatted) => Some(formatted),
+                Err(e) => {
+                    warnings.push(ProcessingWarning::formatting_warning(&format!(
+                        "Invalid JSON left unformatted: {}",
+                        e.message
+                    )));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Create metadata
+        let mut metadata = ProcessingMetadata::new(self.get_version())
+            .with_processing_time(start_time.elapsed());
+
+        metadata.is_formatted = formatted_code.is_some();
+        metadata.is_validated = config.enable_syntax_validation;
+        metadata.syntax_valid = syntax_valid;
+
+        // Add custom attributes
+        metadata = metadata.with_custom_attribute("language", "json");
+        if config.enable_syntax_validation {
+            metadata = metadata.with_custom_attribute("syntax_checker", "serde_json");
+        }
+        if formatted_code.is_some() {
+            metadata = metadata.with_custom_attribute("formatter", "json_pretty_print");
+        }
+
+        let processed = ProcessedCodeBlock::new(code.to_string(), Some("json".to_string()))
+            .with_metadata(metadata);
+
+        let processed = if let Some(formatted) = formatted_code {
+            processed.with_processed_code(formatted)
+        } else {
+            processed
+        };
+
+        // Add errors and warnings
+        let mut final_processed = processed;
+        for error in errors {
+            final_processed = final_processed.with_error(error);
+        }
+        for warning in warnings {
+            final_processed = final_processed.with_warning(warning);
+        }
+
+        Ok(final_processed)
+    }
+
+    fn supports_language(&self, language: &str) -> bool {
+        language_matches(language, &["json"])
+    }
+
+    fn get_language_name(&self) -> &'static str {
+        "json"
+    }
+
+    fn get_priority(&self) -> u8 {
+        100 // Medium priority for JSON
+    }
+
+    fn get_version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "JSON validation and order-preserving pretty-printing using serde_json"
+    }
+}
+
+impl LanguageStrategy for JsonStrategy {
+    fn validate_syntax(&self, code: &str) -> Result<bool, ProcessingError> {
+        Ok(self.check_json_syntax(code).is_ok())
+    }
+
+    fn format_code(&self, code: &str) -> Result<String, ProcessingError> {
+        self.format_json_code(code)
+    }
+
+    fn get_file_extensions(&self) -> Vec<&'static str> {
+        vec!["json"]
+    }
+
+    fn get_language_aliases(&self) -> Vec<&'static str> {
+        vec!["json"]
+    }
+}
+
+impl Default for JsonStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::code_block::ProcessingConfig;
+
+    #[test]
+    fn test_json_strategy_creation() {
+        let strategy = JsonStrategy::new();
+        assert_eq!(strategy.get_language_name(), "json");
+        assert!(strategy.supports_language("json"));
+        assert!(strategy.supports_language("JSON"));
+        assert!(!strategy.supports_language("yaml"));
+    }
+
+    #[test]
+    fn test_valid_json_pretty_printed_in_written_order() {
+        let strategy = JsonStrategy::new();
+        let config = ProcessingConfig::default().with_formatting(true);
+        let code = r#"{"zeta":1,"alpha":[true, null,{}],"text":"a, b: {c}","esc":"q\"x","n":1.50}"#;
+
+        let processed = strategy.process(code, &config).unwrap();
+        assert!(processed.metadata.syntax_valid);
+        assert!(processed.metadata.is_formatted);
+        assert!(processed.errors.is_empty());
+        assert_eq!(
+            processed.get_final_code(),
+            "{\n  \"zeta\": 1,\n  \"alpha\": [\n    true,\n    null,\n    {}\n  ],\n  \"text\": \"a, b: {c}\",\n  \"esc\": \"q\\\"x\",\n  \"n\": 1.50\n}"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_off_keeps_original() {
+        let strategy = JsonStrategy::new();
+        let config = ProcessingConfig::default().with_formatting(false);
+        let code = r#"{"a":1}"#;
+
+        let processed = strategy.process(code, &config).unwrap();
+        assert!(processed.metadata.syntax_valid);
+        assert!(!processed.metadata.is_formatted);
+        assert!(processed.processed_code.is_none());
+        assert_eq!(processed.get_final_code(), code);
+    }
+
+    #[test]
+    fn test_invalid_json_degrades_to_original() {
+        let strategy = JsonStrategy::new();
+        let config = ProcessingConfig::default().with_formatting(true);
+        let code = "{\"a\": 1,\n \"b\": }";
+
+        let processed = strategy.process(code, &config).unwrap();
+        assert!(!processed.metadata.syntax_valid);
+        assert!(processed.processed_code.is_none());
+        assert_eq!(processed.get_final_code(), code);
+        assert_eq!(processed.errors.len(), 1);
+        assert_eq!(processed.errors[0].error_type, "syntax_error");
+        assert_eq!(processed.errors[0].line, Some(2));
+        assert_eq!(processed.warnings.len(), 1);
+        assert_eq!(processed.warnings[0].warning_type, "formatting_warning");
+    }
+}
@@ -3,7 +3,11 @@
 pub mod rust_strategy;
 pub mod note_strategy;
 pub mod bash_strategy;
+pub mod json_strategy;
+pub mod yaml_strategy;
 
 pub use rust_strategy::*;
 pub use note_strategy::*;
-pub use bash_strategy::*;
\ No newline at end of file
+pub use bash_strategy::*;
+pub use json_strategy::*;
+pub use yaml_strategy::*;
\ No newline at end of file
@@ -0,0 +1,217 @@
+//! YAML code block processing strategy
+
+use std::time::Instant;
+use serde::Deserialize;
+use crate::markdown::code_block::{
+    CodeBlockStrategy, LanguageStrategy, ProcessedCodeBlock, ProcessingConfig,
+    ProcessingError, ProcessingMetadata, ProcessingWarning, language_matches
+};
+
+/// Strategy for processing YAML code blocks
+///
+/// Validates every document in the block with serde_yaml and, when
+/// formatting is enabled, re-emits them in block style. Mapping order is
+/// kept, but comments and anchors do not survive reformatting.
+#[derive(Debug, Clone)]
+pub struct YamlStrategy;
+
+impl YamlStrategy {
+    /// Create a new YAML strategy instance
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse every `---`-separated document in the code
+    fn parse_yaml_documents(&self, code: &str) -> Result<Vec<serde_yaml::Value>, ProcessingError> {
+        serde_yaml::Deserializer::from_str(code)
+            .map(|document| {
+                serde_yaml::Value::deserialize(document).map_err(|e| {
+                    let location = e.location();
+                    ProcessingError::syntax_error(
+                        &e.to_string(),
+                        location.as_ref().map(|l| l.line()),
+                        location.as_ref().map(|l| l.column()),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Re-emit valid YAML in serde_yaml's block style
+    fn format_yaml_code(&self, code: &str) -> Result<String, ProcessingError> {
+        let mut documents = Vec::new();
+        for value in self.parse_yaml_documents(code)? {
+            let text = serde_yaml::to_string(&value)
+                .map_err(|e| ProcessingError::new("formatting_error", &e.to_string()))?;
+            documents.push(text);
+        }
+        Ok(documents.join("---\n").trim_end().to_string())
+    }
+}
+
+impl CodeBlockStrategy for YamlStrategy {
+    fn process(&self, code: &str, config: &ProcessingConfig) -> Result<ProcessedCodeBlock, ProcessingError> {
+        let start_time = Instant::now();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        let syntax_error = self.parse_yaml_documents(code).err();
+        let syntax_valid = syntax_error.is_none();
+        if config.enable_syntax_validation {
+            errors.extend(syntax_error);
+        }
+
+        // Invalid YAML is rendered as written
+        let formatted_code = if config.enable_formatting {
+            match self.format_yaml_code(code) {
+                Ok(formatted) => Some(formatted),
+                Err(e) => {
+                    warnings.push(ProcessingWarning::formatting_warning(&format!(
+                        "Invalid YAML left unformatted: {}",
+                        e.message
+                    )));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        // Create metadata
+        let mut metadata = ProcessingMetadata::new(self.get_version())
+            .with_processing_time(start_time.elapsed());
+
+        metadata.is_formatted = formatted_code.is_some();
+        metadata.is_validated = config.enable_syntax_validation;
+        metadata.syntax_valid = syntax_valid;
+
+        // Add custom attributes
+        metadata = metadata.with_custom_attribute("language", "yaml");
+        if config.enable_syntax_validation {
+            metadata = metadata.with_custom_attribute("syntax_checker", "serde_yaml");
+        }
+        if formatted_code.is_some() {
+            metadata = metadata.with_custom_attribute("formatter", "yaml_pretty_print");
+        }
+
+        let processed = ProcessedCodeBlock::new(code.to_string(), Some("yaml".to_string()))
+            .with_metadata(metadata);
+
+        let processed = if let Some(formatted) = formatted_code {
+            processed.with_processed_code(formatted)
+        } else {
+            processed
+        };
+
+        // Add errors and warnings
+        let mut final_processed = processed;
+        for error in errors {
+            final_processed = final_processed.with_error(error);
+        }
+        for warning in warnings {
+            final_processed = final_processed.with_warning(warning);
+        }
+
+        Ok(final_processed)
+    }
+
+    fn supports_language(&self, language: &str) -> bool {
+        language_matches(language, &["yaml", "yml"])
+    }
+
+    fn get_language_name(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn get_priority(&self) -> u8 {
+        100 // Medium priority for YAML
+    }
+
+    fn get_version(&self) -> &'static str {
+        "1.0.0"
+    }
+
+    fn get_description(&self) -> &'static str {
+        "YAML validation and pretty-printing using serde_yaml"
+    }
+}
+
+impl LanguageStrategy for YamlStrategy {
+    fn validate_syntax(&self, code: &str) -> Result<bool, ProcessingError> {
+        Ok(self.parse_yaml_documents(code).is_ok())
+    }
+
+    fn format_code(&self, code: &str) -> Result<String, ProcessingError> {
+        self.format_yaml_code(code)
+    }
+
+    fn get_file_extensions(&self) -> Vec<&'static str> {
+        vec!["yaml", "yml"]
+    }
+
+    fn get_language_aliases(&self) -> Vec<&'static str> {
+        vec!["yaml", "yml"]
+    }
+}
+
+impl Default for YamlStrategy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::code_block::ProcessingConfig;
+
+    #[test]
+    fn test_yaml_strategy_creation() {
+        let strategy = YamlStrategy::new();
+        assert_eq!(strategy.get_language_name(), "yaml");
+        assert!(strategy.supports_language("yaml"));
+        assert!(strategy.supports_language("YML"));
+        assert!(!strategy.supports_language("json"));
+    }
+
+    #[test]
+    fn test_valid_yaml_pretty_printed() {
+        let strategy = YamlStrategy::new();
+        let config = ProcessingConfig::default().with_formatting(true);
+        let code = "name:   demo\nitems: [b, a]\nnested: {z: 1, y: 2}\n---\nsecond: true\n";
+
+        let processed = strategy.process(code, &config).unwrap();
+        assert!(processed.metadata.syntax_valid);
+        assert!(processed.metadata.is_formatted);
+        assert_eq!(
+            processed.get_final_code(),
+            "name: demo\nitems:\n- b\n- a\nnested:\n  z: 1\n  y: 2\n---\nsecond: true"
+        );
+    }
+
+    #[test]
+    fn test_pretty_print_off_keeps_original() {
+        let strategy = YamlStrategy::new();
+        let config = ProcessingConfig::default().with_formatting(false);
+        let code = "items: [b, a]";
+
+        let processed = strategy.process(code, &config).unwrap();
+        assert!(processed.metadata.syntax_valid);
+        assert!(processed.processed_code.is_none());
+        assert_eq!(processed.get_final_code(), code);
+    }
+
+    #[test]
+    fn test_invalid_yaml_degrades_to_original() {
+        let strategy = YamlStrategy::new();
+        let config = ProcessingConfig::default().with_formatting(true);
+        let code = "key: [unclosed\nother: 1";
+
+        let processed = strategy.process(code, &config).unwrap();
+        assert!(!processed.metadata.syntax_valid);
+        assert_eq!(processed.get_final_code(), code);
+        assert_eq!(processed.errors.len(), 1);
+        assert_eq!(processed.errors[0].error_type, "syntax_error");
+        assert_eq!(processed.warnings.len(), 1);
+    }
+}
@@ -0,0 +1,182 @@
+//! Email autolinks
+//!
+//! Turns `<user@example.com>` autolinks, which the parser keeps with the bare
+//! address as their URL, and bare addresses in running text into `mailto:`
+//! links. Code spans and code blocks are never rewritten.
+
+use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+
+/// Whether `address` is a plain email address such as `user@example.com`
+pub fn is_email(address: &str) -> bool {
+    let Some((local, domain)) = address.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && local.chars().all(is_local_char)
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && is_domain(domain)
+}
+
+fn is_local_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-')
+}
+
+fn is_domain_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '-')
+}
+
+/// Dotted host name whose labels are non-empty and whose top-level label is
+/// at least two letters
+fn is_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.chars().all(is_domain_char)
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+        && labels
+            .last()
+            .is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+/// Split plain text into text and `mailto:` links around bare addresses
+///
+/// Addresses preceded by `/` or `:` are taken to be part of a URL and left
+/// alone. Returns `None` when the text holds no address.
+pub fn split_emails(text: &str) -> Option<Vec<InlineElement>> {
+    let mut parts = Vec::new();
+    let mut copied = 0;
+    let mut search = 0;
+
+    while let Some(offset) = text[search..].find('@') {
+        let at = search + offset;
+        search = at + 1;
+
+        let start = text[..at]
+            .rfind(|c: char| !is_local_char(c))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        let end = text[at + 1..]
+            .find(|c: char| !is_domain_char(c))
+            .map(|i| at + 1 + i)
+            .unwrap_or(text.len());
+        // Sentence punctuation after the address is not part of it
+        let end = at + 1 + text[at + 1..end].trim_end_matches(['.', '-']).len();
+        let address = &text[start..end];
+
+        let inside_url = text[..start].ends_with(['/', ':']);
+        if start < copied || inside_url || !is_email(address) {
+            continue;
+        }
+        if start > copied {
+            parts.push(InlineElement::Text(text[copied..start].to_string()));
+        }
+        parts.push(mailto_link(address));
+        copied = end;
+        search = end;
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+    if copied < text.len() {
+        parts.push(InlineElement::Text(text[copied..].to_string()));
+    }
+    Some(parts)
+}
+
+fn mailto_link(address: &str) -> InlineElement {
+    InlineElement::Link {
+        text: address.to_string(),
+        url: format!("mailto:{}", address),
+        title: None,
+    }
+}
+
+/// Convert email autolinks and bare addresses throughout the document
+pub fn apply_to_document(document: &mut MarkdownDocument) {
+    for element in &mut document.elements {
+        match element {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => apply_to_inlines(content),
+            MarkdownElement::List { items, .. } => apply_to_list_items(items),
+            _ => {}
+        }
+    }
+}
+
+fn apply_to_list_items(items: &mut [ListItem]) {
+    for item in items {
+        for content in item.paragraphs_mut() {
+            apply_to_inlines(content);
+        }
+        apply_to_list_items(&mut item.sub_items);
+    }
+}
+
+fn apply_to_inlines(inlines: &mut Vec<InlineElement>) {
+    let mut rewritten = Vec::with_capacity(inlines.len());
+    for inline in inlines.drain(..) {
+        match inline {
+            InlineElement::Link { text, url, title } if is_email(&url) => {
+                rewritten.push(InlineElement::Link { text, url: format!("mailto:{}", url), title });
+            }
+            InlineElement::Text(text) => match split_emails(&text) {
+                Some(parts) => rewritten.extend(parts),
+                None => rewritten.push(InlineElement::Text(text)),
+            },
+            other => rewritten.push(other),
+        }
+    }
+    *inlines = rewritten;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::MarkdownParser;
+
+    #[test]
+    fn test_email_detection() {
+        assert!(is_email("jane.doe+docs@mail.example.com"));
+        assert!(!is_email("jane@localhost"));
+        assert!(!is_email("@example.com"));
+        assert!(!is_email("jane@example.c0m"));
+        assert!(!is_email("https://example.com"));
+    }
+
+    #[test]
+    fn test_split_bare_addresses() {
+        let parts = split_emails("Write to jane@example.com. Or see https://bob@example.com/x").unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(matches!(&parts[0], InlineElement::Text(t) if t == "Write to "));
+        assert!(matches!(&parts[1], InlineElement::Link { text, url, .. }
+            if text == "jane@example.com" && url == "mailto:jane@example.com"));
+        assert!(matches!(&parts[2], InlineElement::Text(t) if t.starts_with(". Or see https://bob@")));
+        assert!(split_emails("no addresses @ all").is_none());
+    }
+
+    #[test]
+    fn test_autolink_becomes_mailto_and_code_is_untouched() {
+        let markdown = "Contact <team@example.com> or `ops@example.com`.\n\n```\nadmin@example.com\n```\n";
+        let mut document = MarkdownParser::new().parse(markdown).unwrap();
+        apply_to_document(&mut document);
+
+        match &document.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert!(content.iter().any(|inline| matches!(inline, InlineElement::Link { text, url, .. }
+                    if text == "team@example.com" && url == "mailto:team@example.com")));
+                assert!(content.iter().any(|inline| matches!(inline, InlineElement::Code(c) if c == "ops@example.com")));
+                assert_eq!(content.iter().filter(|inline| matches!(inline, InlineElement::Link { .. })).count(), 1);
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+        assert!(matches!(
+            &document.elements[1],
+            MarkdownElement::CodeBlock { code, .. } if code.contains("admin@example.com")
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+//! Emoji shortcode conversion
+//!
+//! Replaces GitHub-style shortcodes such as `:rocket:` with the matching emoji
+//! character. Unknown shortcodes are left untouched, and code spans and code
+//! blocks are never rewritten.
+
+use crate::markdown::ast::{HtmlTable, InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use crate::markdown::table_links;
+use std::borrow::Cow;
+
+/// Look up the emoji for a shortcode name (without the surrounding colons)
+pub fn lookup(name: &str) -> Option<&'static str> {
+    let emoji = match name {
+        "+1" | "thumbsup" => "👍",
+        "-1" | "thumbsdown" => "👎",
+        "100" => "💯",
+        "alarm_clock" => "⏰",
+        "arrow_down" => "⬇️",
+        "arrow_left" => "⬅️",
+        "arrow_right" => "➡️",
+        "arrow_up" => "⬆️",
+        "bangbang" => "‼️",
+        "beer" => "🍺",
+        "bell" => "🔔",
+        "book" => "📖",
+        "bookmark" => "🔖",
+        "boom" => "💥",
+        "bug" => "🐛",
+        "bulb" => "💡",
+        "calendar" => "📆",
+        "chart_with_upwards_trend" => "📈",
+        "check" | "heavy_check_mark" => "✔️",
+        "clap" => "👏",
+        "clipboard" => "📋",
+        "cloud" => "☁️",
+        "coffee" => "☕",
+        "computer" => "💻",
+        "construction" => "🚧",
+        "cry" => "😢",
+        "dart" => "🎯",
+        "email" | "envelope" => "✉️",
+        "exclamation" | "heavy_exclamation_mark" => "❗",
+        "eyes" => "👀",
+        "fire" => "🔥",
+        "gear" => "⚙️",
+        "gift" => "🎁",
+        "globe_with_meridians" => "🌐",
+        "grin" => "😁",
+        "hammer" => "🔨",
+        "heart" => "❤️",
+        "hourglass" => "⌛",
+        "house" => "🏠",
+        "information_source" => "ℹ️",
+        "joy" => "😂",
+        "key" => "🔑",
+        "laughing" => "😆",
+        "link" => "🔗",
+        "lock" => "🔒",
+        "mag" => "🔍",
+        "memo" | "pencil" => "📝",
+        "muscle" => "💪",
+        "no_entry" => "⛔",
+        "ok_hand" => "👌",
+        "package" => "📦",
+        "paperclip" => "📎",
+        "pushpin" => "📌",
+        "question" => "❓",
+        "recycle" => "♻️",
+        "rocket" => "🚀",
+        "rotating_light" => "🚨",
+        "sad" | "disappointed" => "😞",
+        "scissors" => "✂️",
+        "smile" => "😄",
+        "smiley" => "😃",
+        "sparkles" => "✨",
+        "star" => "⭐",
+        "stop_sign" => "🛑",
+        "sunny" => "☀️",
+        "tada" => "🎉",
+        "thinking" => "🤔",
+        "trophy" => "🏆",
+        "unlock" => "🔓",
+        "warning" => "⚠️",
+        "wave" => "👋",
+        "white_check_mark" => "✅",
+        "wink" => "😉",
+        "wrench" => "🔧",
+        "x" => "❌",
+        "zap" => "⚡",
+        _ => return None,
+    };
+    Some(emoji)
+}
+
+/// Replace known shortcodes in plain text, skipping backtick code spans
+pub fn replace_shortcodes(text: &str) -> Cow<'_, str> {
+    if !text.contains(':') {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut rest = text;
+
+    while let Some(pos) = rest.find([':', '`']) {
+        result.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if rest.starts_with('`') {
+            // Copy the code span verbatim; an unmatched backtick is plain text
+            let end = rest[1..].find('`').map(|i| i + 2).unwrap_or(1);
+            result.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+
+        let name_len = rest[1..]
+            .find(|c: char| !is_shortcode_char(c))
+            .unwrap_or(rest.len() - 1);
+        let name = &rest[1..1 + name_len];
+        if name_len > 0 && rest[1 + name_len..].starts_with(':') {
+            if let Some(emoji) = lookup(name) {
+                result.push_str(emoji);
+                rest = &rest[name_len + 2..];
+                changed = true;
+                continue;
+            }
+        }
+
+        // Not a known shortcode: keep the colon, the closing one may open another
+        result.push(':');
+        rest = &rest[1..];
+    }
+    result.push_str(rest);
+
+    if changed {
+        Cow::Owned(result)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn is_shortcode_char(c: char) -> bool {
+    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '+' | '-')
+}
+
+/// Rewrite shortcodes in every text run of the document
+pub fn apply_to_document(document: &mut MarkdownDocument) {
+    for element in &mut document.elements {
+        apply_to_element(element);
+    }
+}
+
+fn apply_to_element(element: &mut MarkdownElement) {
+    match element {
+        MarkdownElement::Heading { text, .. } => replace_in_place(text),
+        MarkdownElement::Paragraph { content, .. }
+        | MarkdownElement::FootnoteDefinition { content, .. } => apply_to_inlines(content),
+        MarkdownElement::List { items, .. } => {
+            for item in items {
+                apply_to_list_item(item);
+            }
+        }
+        MarkdownElement::Table { headers, rows, links } => {
+            table_links::rewrite_table_cells(headers, rows, links, replace_in_place);
+        }
+        MarkdownElement::HtmlTable(table) => apply_to_html_table(table),
+        MarkdownElement::Image { alt_text, .. } => replace_in_place(alt_text),
+        MarkdownElement::Details { summary, body } => {
+            replace_in_place(summary);
+            body.iter_mut().for_each(apply_to_element);
+        }
+        MarkdownElement::BlockQuote { content: body } => body.iter_mut().for_each(apply_to_element),
+        MarkdownElement::CodeBlock { .. }
+        | MarkdownElement::HorizontalRule
+        | MarkdownElement::SectionBreak { .. } => {}
+    }
+}
+
+fn apply_to_html_table(table: &mut HtmlTable) {
+    for cell in table.rows.iter_mut().flatten() {
+        let mut links: Vec<_> = cell.links.iter_mut().collect();
+        table_links::rewrite_cell(&mut cell.text, &mut links, replace_in_place);
+        for nested in &mut cell.tables {
+            apply_to_html_table(nested);
+        }
+    }
+}
+
+fn apply_to_list_item(item: &mut ListItem) {
+    for content in item.paragraphs_mut() {
+        apply_to_inlines(content);
+    }
+    for sub_item in &mut item.sub_items {
+        apply_to_list_item(sub_item);
+    }
+}
+
+fn apply_to_inlines(inlines: &mut [InlineElement]) {
+    for inline in inlines {
+        match inline {
+            InlineElement::Text(text)
+            | InlineElement::Bold(text)
+            | InlineElement::Italic(text)
+            | InlineElement::Strikethrough(text)
+            | InlineElement::Link { text, .. }
+            | InlineElement::Span { text, .. } => replace_in_place(text),
+            InlineElement::Code(_)
+            | InlineElement::FootnoteReference(_)
+            | InlineElement::ReferenceMarker(_)
+            | InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_)
+            | InlineElement::Abbreviation { .. } => {}
+        }
+    }
+}
+
+fn replace_in_place(text: &mut String) {
+    if let Cow::Owned(replaced) = replace_shortcodes(text) {
+        *text = replaced;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_shortcodes() {
+        assert_eq!(replace_shortcodes("Launch :rocket: now"), "Launch 🚀 now");
+        assert_eq!(replace_shortcodes(":tada::+1:"), "🎉👍");
+    }
+
+    #[test]
+    fn test_unknown_shortcodes_untouched() {
+        assert!(matches!(replace_shortcodes(":not_an_emoji:"), Cow::Borrowed(_)));
+        assert_eq!(replace_shortcodes("at 10:30:00"), "at 10:30:00");
+        assert_eq!(replace_shortcodes(":unknown:rocket:"), ":unknown🚀");
+    }
+
+    #[test]
+    fn test_code_spans_skipped() {
+        assert_eq!(
+            replace_shortcodes("`:rocket:` becomes :rocket:"),
+            "`:rocket:` becomes 🚀"
+        );
+    }
+
+    #[test]
+    fn test_apply_to_document_skips_inline_code() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Ship it :rocket: ".to_string()),
+                InlineElement::Code(":rocket:".to_string()),
+            ],
+        });
+        document.add_element(MarkdownElement::CodeBlock {
+            language: None,
+            code: ":fire:".to_string(),
+            processed: None,
+        });
+
+        apply_to_document(&mut document);
+
+        match &document.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert!(matches!(&content[0], InlineElement::Text(t) if t == "Ship it 🚀 "));
+                assert!(matches!(&content[1], InlineElement::Code(c) if c == ":rocket:"));
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+        assert!(matches!(
+            &document.elements[1],
+            MarkdownElement::CodeBlock { code, .. } if code == ":fire:"
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+//! Fence info strings
+//!
+//! The info string of a fenced code block starts with its language and may
+//! go on with attributes: flags after commas, as rustdoc writes them, and
+//! `key=value` pairs, usually in braces:
+//!
+//! ```text
+//! rust,no_run {highlight=2-4}
+//! ```
+//!
+//! Recognized attributes are `highlight` (also `hl_lines`), a list of line
+//! numbers and ranges such as `2-4,7`, `title` (also `filename`), the name
+//! of the file the code comes from, and the `no_run`, `ignore` and
+//! `no_validate` flags, which turn off syntax validation. Others are ignored
+//! with a warning.
+
+use tracing::warn;
+
+/// Attributes of a fenced code block
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CodeAttributes {
+    /// Numbers of the lines to highlight, counting from 1, in ascending order
+    pub highlight: Vec<usize>,
+    /// Skip the language strategy's syntax validation
+    pub no_validate: bool,
+    /// Name of the file the code comes from, such as `src/main.rs`
+    pub title: Option<String>,
+}
+
+impl CodeAttributes {
+    /// Whether no attribute is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether a line, counting from 1, is highlighted
+    pub fn highlights(&self, line: usize) -> bool {
+        self.highlight.binary_search(&line).is_ok()
+    }
+}
+
+/// Split a fence info string into its language and attributes
+pub fn parse(info: &str) -> (Option<String>, CodeAttributes) {
+    let info = info.trim();
+    let end = info.find(|c: char| c == ',' || c == '{' || c.is_whitespace()).unwrap_or(info.len());
+    let language = (end > 0).then(|| info[..end].to_string());
+
+    let mut attributes = CodeAttributes::default();
+    let mut rest = &info[end..];
+    while !rest.is_empty() {
+        let (outside, inside, after) = match rest.find('{') {
+            Some(open) => {
+                let close = rest[open..].find('}').map_or(rest.len(), |close| open + close);
+                (&rest[..open], &rest[open + 1..close], rest.get(close + 1..).unwrap_or(""))
+            }
+            None => (rest, "", ""),
+        };
+        for token in tokens(outside, |c| c == ',' || c.is_whitespace()) {
+            apply(&mut attributes, token);
+        }
+        // Inside braces commas belong to values such as `highlight=2,4`
+        for token in tokens(inside, char::is_whitespace) {
+            apply(&mut attributes, token);
+        }
+        rest = after;
+    }
+    attributes.highlight.sort_unstable();
+    attributes.highlight.dedup();
+    (language, attributes)
+}
+
+/// Non-empty tokens between separators; separators in double quotes count
+/// as part of the token
+fn tokens(text: &str, is_separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    for (at, c) in text.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && is_separator(c) {
+            tokens.push(&text[start..at]);
+            start = at + c.len_utf8();
+        }
+    }
+    tokens.push(&text[start..]);
+    tokens.retain(|token| !token.is_empty());
+    tokens
+}
+
+fn apply(attributes: &mut CodeAttributes, token: &str) {
+    let (key, value) = match token.split_once('=') {
+        Some((key, value)) => (key, Some(value.trim_matches(|c| c == '"' || c == '\''))),
+        None => (token, None),
+    };
+    match (key, value) {
+        ("no_run" | "ignore" | "no_validate", None) => attributes.no_validate = true,
+        ("title" | "filename", Some(title)) if !title.is_empty() => attributes.title = Some(title.to_string()),
+        ("highlight" | "hl_lines", Some(lines)) => match parse_lines(lines) {
+            Some(lines) => attributes.highlight.extend(lines),
+            None => warn!(value = lines, "Ignoring code block highlight with invalid line numbers"),
+        },
+        _ => warn!(attribute = token, "Ignoring unknown code block attribute"),
+    }
+}
+
+/// Line numbers of a list such as `2-4,7` or `2 3`
+fn parse_lines(list: &str) -> Option<Vec<usize>> {
+    let mut lines = Vec::new();
+    for part in list.split(|c: char| c == ',' || c.is_whitespace()).filter(|part| !part.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let (first, last): (usize, usize) = (first.trim().parse().ok()?, last.trim().parse().ok()?);
+        if first == 0 || last < first {
+            return None;
+        }
+        lines.extend(first..=last);
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info_strings() {
+        let (language, attributes) = parse("rust,no_run {highlight=2-4,7}");
+        assert_eq!(language.as_deref(), Some("rust"));
+        assert_eq!(attributes, CodeAttributes { highlight: vec![2, 3, 4, 7], no_validate: true, title: None });
+        assert!(attributes.highlights(3) && !attributes.highlights(5));
+
+        let (language, attributes) = parse("python");
+        assert_eq!(language.as_deref(), Some("python"));
+        assert!(attributes.is_empty());
+
+        let (language, attributes) = parse("js hl_lines=\"1 3\" {.numbered}");
+        assert_eq!(language.as_deref(), Some("js"));
+        assert_eq!(attributes.highlight, vec![1, 3]);
+        assert!(!attributes.no_validate);
+
+        assert_eq!(parse("{highlight=1}"), (None, CodeAttributes { highlight: vec![1], ..Default::default() }));
+        assert!(parse("rust {highlight=4-2}").1.is_empty());
+
+        let (language, attributes) = parse("rust title=\"src/main.rs\" {hl_lines=2}");
+        assert_eq!(language.as_deref(), Some("rust"));
+        assert_eq!(attributes.title.as_deref(), Some("src/main.rs"));
+        assert_eq!(parse("toml {filename=Cargo.toml}").1.title.as_deref(), Some("Cargo.toml"));
+    }
+}
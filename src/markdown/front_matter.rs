@@ -0,0 +1,114 @@
+//! YAML front matter
+//!
+//! A document may start with a YAML mapping between `---` lines:
+//!
+//! ```markdown
+//! ---
+//! theme: academic
+//! ---
+//! # Title
+//! ```
+//!
+//! The closing line may also be `...`. A block that is not a YAML mapping
+//! is not front matter and stays part of the Markdown.
+//!
+//! The scalar fields other than `theme` are the document's metadata, see
+//! [`FrontMatter::metadata`].
+
+use tracing::warn;
+
+/// Front matter split off the start of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrontMatter<'a> {
+    pub fields: serde_yaml::Mapping,
+    /// The Markdown after the closing line
+    pub body: &'a str,
+}
+
+impl FrontMatter<'_> {
+    /// A string field of the front matter
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).and_then(|value| value.as_str())
+    }
+
+    /// Names and values of the scalar fields other than `theme`: those in
+    /// `order` first, in that order, then the rest as written
+    pub fn metadata(&self, order: &[String]) -> Vec<(String, String)> {
+        let mut fields: Vec<(String, String)> = self
+            .fields
+            .iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    serde_yaml::Value::String(value) => value.clone(),
+                    serde_yaml::Value::Number(value) => value.to_string(),
+                    serde_yaml::Value::Bool(value) => value.to_string(),
+                    _ => return None,
+                };
+                Some((key.as_str()?.to_string(), value))
+            })
+            .filter(|(key, _)| key != "theme")
+            .collect();
+        // Stable, so unlisted fields keep their written order
+        fields.sort_by_key(|(key, _)| order.iter().position(|field| field == key).unwrap_or(order.len()));
+        fields
+    }
+}
+
+/// Split the front matter off a document, if it has any
+pub fn split(markdown: &str) -> Option<FrontMatter<'_>> {
+    let markdown = markdown.strip_prefix('\u{FEFF}').unwrap_or(markdown);
+    let rest = markdown.strip_prefix("---")?;
+    let rest = rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let marker = line.trim_end();
+        if marker == "---" || marker == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return match serde_yaml::from_str::<serde_yaml::Value>(yaml) {
+                Ok(serde_yaml::Value::Mapping(fields)) => Some(FrontMatter { fields, body }),
+                Ok(_) => None,
+                Err(e) => {
+                    warn!("Ignoring front matter that is not valid YAML: {}", e);
+                    None
+                }
+            };
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_front_matter() {
+        let front_matter = split("---\ntheme: academic\ntitle: Notes\n---\n# Notes\n").unwrap();
+        assert_eq!(front_matter.get_str("theme"), Some("academic"));
+        assert_eq!(front_matter.body, "# Notes\n");
+
+        let front_matter = split("---\r\ntheme: modern\r\n...\r\nBody").unwrap();
+        assert_eq!(front_matter.get_str("theme"), Some("modern"));
+        assert_eq!(front_matter.body, "Body");
+
+        // A rule and a setext heading, not front matter
+        assert!(split("---\nJust text\n---\n").is_none());
+        assert!(split("Text\n---\ntheme: x\n---\n").is_none());
+        assert!(split("---\ntheme: unterminated\n").is_none());
+    }
+
+    #[test]
+    fn test_metadata_fields() {
+        let front_matter =
+            split("---\nversion: 1.2\ntheme: modern\nreviewed: true\ntags: [a, b]\nauthor: Ada\ntitle: Notes\n---\n").unwrap();
+        let order = ["title".to_string(), "author".to_string(), "date".to_string()];
+        assert_eq!(
+            front_matter.metadata(&order),
+            [("title", "Notes"), ("author", "Ada"), ("version", "1.2"), ("reviewed", "true")]
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
+}
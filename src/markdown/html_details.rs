@@ -0,0 +1,133 @@
+//! `<details>`/`<summary>` disclosure blocks written as HTML
+//!
+//! GitHub-style details usually span several HTML blocks with Markdown in
+//! between:
+//!
+//! ```markdown
+//! <details>
+//! <summary>Show the output</summary>
+//!
+//! Some **Markdown** body
+//!
+//! </details>
+//! ```
+//!
+//! so an HTML block is scanned into open, text and close events and the
+//! parser gathers the elements between an open and its close into a
+//! `Details` element. Tags other than `<details>` and `<summary>` are
+//! dropped and their text kept.
+
+/// A piece of a `<details>` block found in one HTML block, in source order
+#[derive(Debug, Clone, PartialEq)]
+pub enum DetailsEvent {
+    /// `<details>` with the text of its `<summary>`, empty if it has none
+    Open(String),
+    /// Body text written inside the HTML block itself
+    Text(String),
+    /// `</details>`
+    Close,
+}
+
+/// Whether an HTML block opens or closes a details block
+pub fn contains_details(html: &str) -> bool {
+    let lower = html.to_ascii_lowercase();
+    lower.contains("<details") || lower.contains("</details")
+}
+
+/// Scan an HTML block into details events
+pub fn scan(html: &str) -> Vec<DetailsEvent> {
+    let mut events = Vec::new();
+    let mut text = String::new();
+    // Summary text being collected, while inside `<summary>`
+    let mut summary: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut text, &mut summary, &rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        rest = &rest[start + end + 1..];
+
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').find(|s| !s.is_empty()).unwrap_or("");
+        let closing = tag.starts_with('/');
+        match (name, closing) {
+            ("details", false) => {
+                flush_text(&mut events, &mut text);
+                events.push(DetailsEvent::Open(String::new()));
+            }
+            ("details", true) => {
+                flush_text(&mut events, &mut text);
+                events.push(DetailsEvent::Close);
+            }
+            ("summary", false) => {
+                flush_text(&mut events, &mut text);
+                summary = Some(String::new());
+            }
+            ("summary", true) => {
+                if let Some(collected) = summary.take() {
+                    let collected = collapse_whitespace(&collected);
+                    match events.last_mut() {
+                        Some(DetailsEvent::Open(existing)) if existing.is_empty() => *existing = collected,
+                        _ => events.push(DetailsEvent::Text(collected)),
+                    }
+                }
+            }
+            ("br", _) | ("p", _) => push_text(&mut text, &mut summary, " "),
+            _ => {}
+        }
+    }
+    push_text(&mut text, &mut summary, rest);
+    if let Some(collected) = summary {
+        text.push_str(&collected);
+    }
+    flush_text(&mut events, &mut text);
+    events
+}
+
+fn push_text(text: &mut String, summary: &mut Option<String>, chunk: &str) {
+    match summary {
+        Some(summary) => summary.push_str(chunk),
+        None => text.push_str(chunk),
+    }
+}
+
+fn flush_text(events: &mut Vec<DetailsEvent>, text: &mut String) {
+    let collected = collapse_whitespace(text);
+    if !collected.is_empty() {
+        events.push(DetailsEvent::Text(collected));
+    }
+    text.clear();
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_opening_block() {
+        let events = scan("<details open>\n<summary>Show <b>the</b> output</summary>\n");
+        assert_eq!(events, vec![DetailsEvent::Open("Show the output".to_string())]);
+        assert!(contains_details("</DETAILS>"));
+        assert!(!contains_details("<div>plain</div>"));
+    }
+
+    #[test]
+    fn test_scan_single_block_with_body() {
+        let events = scan("<details><summary>Why?</summary>Because\nit is.</details>");
+        assert_eq!(
+            events,
+            vec![
+                DetailsEvent::Open("Why?".to_string()),
+                DetailsEvent::Text("Because it is.".to_string()),
+                DetailsEvent::Close,
+            ]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Images written as HTML `<img>` tags
+//!
+//! Only `src`, `alt`, `title`, `width` and `height` are read. Sizes must be
+//! whole pixels (`200` or `200px`); percentages and other units are ignored.
+//! Tags without a `src`, or whose `src` is a `javascript:` URL, are skipped.
+
+use crate::markdown::ast::MarkdownElement;
+
+/// Parse every `<img>` tag in an HTML fragment into an image element
+pub fn parse_images(html: &str) -> Vec<MarkdownElement> {
+    let mut images = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        if let Some(image) = parse_img_tag(&rest[start + 1..start + end]) {
+            images.push(image);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    images
+}
+
+/// Parse the inside of a tag (between `<` and `>`) if it is an `<img>`
+fn parse_img_tag(tag: &str) -> Option<MarkdownElement> {
+    let tag = tag.trim().trim_end_matches('/');
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    if !tag[..name_end].eq_ignore_ascii_case("img") {
+        return None;
+    }
+
+    let attributes = parse_attributes(&tag[name_end..]);
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    };
+
+    let url = attribute("src")?.trim();
+    if url.is_empty() || url.to_ascii_lowercase().starts_with("javascript:") {
+        return None;
+    }
+    Some(MarkdownElement::Image {
+        alt_text: attribute("alt").unwrap_or_default().to_string(),
+        url: url.to_string(),
+        title: attribute("title").filter(|t| !t.is_empty()).map(str::to_string),
+        width: attribute("width").and_then(parse_pixels),
+        height: attribute("height").and_then(parse_pixels),
+    })
+}
+
+/// Split `key="value" key='value' key=value key` attributes
+pub(crate) fn parse_attributes(source: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = source.trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_string();
+        rest = rest[key_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after_equals) = rest.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+            match after_equals.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after_equals[1..];
+                    let close = inner.find(quote).unwrap_or(inner.len());
+                    value = inner[..close].to_string();
+                    rest = inner.get(close + 1..).unwrap_or_default();
+                }
+                _ => {
+                    let end = after_equals.find(char::is_whitespace).unwrap_or(after_equals.len());
+                    value = after_equals[..end].to_string();
+                    rest = &after_equals[end..];
+                }
+            }
+        }
+        if !key.is_empty() {
+            attributes.push((key, value));
+        }
+        rest = rest.trim_start();
+    }
+    attributes
+}
+
+/// Whole, positive pixel sizes such as `200` or `200px`
+fn parse_pixels(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let number = value.strip_suffix("px").unwrap_or(value);
+    number.parse::<u32>().ok().filter(|pixels| *pixels > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_img_with_dimensions() {
+        let images = parse_images(r#"<img src="images/chart.png" alt='Sales chart' width=200 height="100px" />"#);
+        assert_eq!(images.len(), 1);
+        match &images[0] {
+            MarkdownElement::Image { alt_text, url, title, width, height } => {
+                assert_eq!(alt_text, "Sales chart");
+                assert_eq!(url, "images/chart.png");
+                assert_eq!(*title, None);
+                assert_eq!(*width, Some(200));
+                assert_eq!(*height, Some(100));
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unsupported_sizes_and_unsafe_sources_ignored() {
+        match &parse_images(r#"<IMG SRC=a.png WIDTH="50%" height=abc>"#)[0] {
+            MarkdownElement::Image { url, width, height, .. } => {
+                assert_eq!(url, "a.png");
+                assert_eq!((*width, *height), (None, None));
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+        assert!(parse_images(r#"<img src="javascript:alert(1)">"#).is_empty());
+        assert!(parse_images(r#"<img alt="no source"><image src="a.png">"#).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+//! Inline HTML tags with a style of their own: `<kbd>` and `<samp>`, and
+//! `<span lang="...">`
+//!
+//! The text of `<kbd>` and `<samp>` becomes a span with the tag name as its
+//! class, so it is styled by `styles.inline_html` and by a class of the same
+//! name in `styles.classes`. The text of a `<span>` with a `lang` attribute
+//! becomes a span in that language.
+
+use crate::markdown::html_image::parse_attributes;
+
+/// Inline tags turned into styled spans
+pub const STYLED_TAGS: [&str; 2] = ["kbd", "samp"];
+
+/// The styled tag an inline HTML fragment opens, if any
+pub fn opening_tag(html: &str) -> Option<&'static str> {
+    let name = tag_name(html.trim().strip_prefix('<')?)?;
+    STYLED_TAGS.into_iter().find(|tag| tag.eq_ignore_ascii_case(name))
+}
+
+/// Language of a `<span lang="...">` opening tag, if `html` is one
+///
+/// The language must look like a language tag such as `fr` or `fr-CA`.
+pub fn span_language(html: &str) -> Option<String> {
+    let rest = html.trim().strip_prefix('<')?;
+    let name = tag_name(rest)?;
+    if !name.eq_ignore_ascii_case("span") {
+        return None;
+    }
+    let attributes = rest[name.len()..].strip_suffix('>')?;
+    parse_attributes(attributes)
+        .into_iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("lang"))
+        .map(|(_, value)| value)
+        .filter(|lang| is_language_tag(lang))
+}
+
+/// Whether an inline HTML fragment opens an element whose text becomes a span
+pub fn opens_span(html: &str) -> bool {
+    opening_tag(html).is_some() || span_language(html).is_some()
+}
+
+fn is_language_tag(lang: &str) -> bool {
+    lang.len() <= 35
+        && lang.starts_with(|c: char| c.is_ascii_alphabetic())
+        && lang.split('-').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Whether an inline HTML fragment closes `tag`
+pub fn is_closing_tag(html: &str, tag: &str) -> bool {
+    html.trim()
+        .strip_prefix("</")
+        .and_then(tag_name)
+        .is_some_and(|name| name.eq_ignore_ascii_case(tag))
+}
+
+/// Name of the tag at the start of `rest`, which follows its `<` or `</`
+fn tag_name(rest: &str) -> Option<&str> {
+    let end = rest.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+    let after = rest[end..].trim_start();
+    (end > 0 && (after.starts_with('>') || !after.starts_with('/'))).then(|| &rest[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styled_tags() {
+        assert_eq!(opening_tag("<kbd>"), Some("kbd"));
+        assert_eq!(opening_tag("<SAMP class=\"x\">"), Some("samp"));
+        assert_eq!(opening_tag("<kbd/>"), None);
+        assert_eq!(opening_tag("<code>"), None);
+        assert_eq!(opening_tag("</kbd>"), None);
+        assert!(is_closing_tag("</kbd>", "kbd"));
+        assert!(is_closing_tag("</KBD >", "kbd"));
+        assert!(!is_closing_tag("</samp>", "kbd"));
+    }
+
+    #[test]
+    fn test_span_language() {
+        assert_eq!(span_language("<span lang=\"fr\">").as_deref(), Some("fr"));
+        assert_eq!(span_language("<SPAN class='q' LANG='fr-CA' >").as_deref(), Some("fr-CA"));
+        assert_eq!(span_language("<span>"), None);
+        assert_eq!(span_language("<span lang=\"\">"), None);
+        assert_eq!(span_language("<span lang=\"fr fr\">"), None);
+        assert_eq!(span_language("<div lang=\"fr\">"), None);
+        assert!(opens_span("<kbd>") && opens_span("<span lang=de>") && !opens_span("<span>"));
+    }
+}
@@ -0,0 +1,309 @@
+//! Tables written as HTML blocks
+//!
+//! Only the table structure is read: `<table>`, `<tr>`, `<td>` and `<th>`,
+//! with `<br>` as a line break and `<a href>` as a link in the cell text.
+//! Other tags are dropped and their text kept.
+//! Tables nested deeper than the parser's nesting limit are flattened into
+//! the text of the enclosing cell.
+
+use crate::markdown::ast::{HtmlTable, HtmlTableCell, TableLink};
+use crate::markdown::html_image::parse_attributes;
+use crate::markdown::table_links;
+use tracing::warn;
+
+/// Whether an HTML block contains a table
+pub fn contains_table(html: &str) -> bool {
+    html.to_ascii_lowercase().contains("<table")
+}
+
+/// Parse the top-level tables of an HTML block
+pub fn parse_tables(html: &str, max_depth: usize) -> Vec<HtmlTable> {
+    let mut reader = TableReader::new(max_depth.max(1));
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        reader.text(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        reader.tag(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    reader.text(rest);
+    reader.finish()
+}
+
+#[derive(Default)]
+struct TableBuilder {
+    table: HtmlTable,
+    row: Option<Vec<HtmlTableCell>>,
+    cell: Option<HtmlTableCell>,
+    /// Targets of the links opened in the current cell, in order
+    link_urls: Vec<String>,
+    /// Whether a link of the current cell is open
+    in_link: bool,
+}
+
+impl TableBuilder {
+    fn start_row(&mut self) {
+        self.end_row();
+        self.row = Some(Vec::new());
+    }
+
+    fn end_row(&mut self) {
+        self.end_cell();
+        if let Some(row) = self.row.take() {
+            self.table.rows.push(row);
+        }
+    }
+
+    fn start_cell(&mut self, header: bool) {
+        self.end_cell();
+        if self.row.is_none() {
+            self.row = Some(Vec::new());
+        }
+        self.cell = Some(HtmlTableCell {
+            header,
+            ..HtmlTableCell::default()
+        });
+    }
+
+    fn end_cell(&mut self) {
+        self.end_link();
+        if let Some(mut cell) = self.cell.take() {
+            let (text, spans) = table_links::unmark(&collapse_whitespace(&cell.text));
+            let row = self.row.get_or_insert_with(Vec::new);
+            cell.text = text;
+            cell.links = spans
+                .into_iter()
+                .zip(self.link_urls.drain(..))
+                .map(|((offset, text), url)| TableLink {
+                    row: Some(self.table.rows.len()),
+                    column: row.len(),
+                    offset,
+                    text,
+                    url,
+                })
+                .collect();
+            row.push(cell);
+        }
+        self.link_urls.clear();
+    }
+
+    fn start_link(&mut self, url: String) {
+        if let Some(cell) = self.cell.as_mut().filter(|_| !self.in_link) {
+            table_links::push_link_start(&mut cell.text);
+            self.link_urls.push(url);
+            self.in_link = true;
+        }
+    }
+
+    fn end_link(&mut self) {
+        if let Some(cell) = self.cell.as_mut().filter(|_| self.in_link) {
+            table_links::push_link_end(&mut cell.text);
+        }
+        self.in_link = false;
+    }
+
+    fn finish(mut self) -> HtmlTable {
+        self.end_row();
+        self.table
+    }
+}
+
+struct TableReader {
+    max_depth: usize,
+    open: Vec<TableBuilder>,
+    /// Tables opened beyond `max_depth` whose content is kept as text
+    flattened: usize,
+    tables: Vec<HtmlTable>,
+}
+
+impl TableReader {
+    fn new(max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            open: Vec::new(),
+            flattened: 0,
+            tables: Vec::new(),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(cell) = self.open.last_mut().and_then(|table| table.cell.as_mut()) {
+            cell.text.push_str(&decode_entities(text));
+        }
+    }
+
+    fn tag(&mut self, tag: &str) {
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if self.flattened > 0 {
+            match (name.as_str(), closing) {
+                ("table", false) => self.flattened += 1,
+                ("table", true) => self.flattened -= 1,
+                ("td" | "th" | "br" | "tr", _) => self.text(" "),
+                _ => {}
+            }
+            return;
+        }
+
+        match (name.as_str(), closing) {
+            ("table", false) => {
+                if self.open.len() >= self.max_depth {
+                    warn!(max_depth = self.max_depth, "HTML table nesting too deep; flattening to text");
+                    self.flattened = 1;
+                    self.text(" ");
+                } else {
+                    self.open.push(TableBuilder::default());
+                }
+            }
+            ("table", true) => {
+                if let Some(builder) = self.open.pop() {
+                    let table = builder.finish();
+                    match self.open.last_mut() {
+                        Some(parent) => {
+                            if parent.cell.is_none() {
+                                parent.start_cell(false);
+                            }
+                            if let Some(cell) = parent.cell.as_mut() {
+                                cell.tables.push(table);
+                            }
+                        }
+                        None => self.tables.push(table),
+                    }
+                }
+            }
+            ("tr", false) => self.with_table(TableBuilder::start_row),
+            ("tr", true) => self.with_table(TableBuilder::end_row),
+            ("td", false) => self.with_table(|table| table.start_cell(false)),
+            ("th", false) => self.with_table(|table| table.start_cell(true)),
+            ("td" | "th", true) => self.with_table(TableBuilder::end_cell),
+            ("br", _) => self.line_break(),
+            ("a", false) => {
+                let href = parse_attributes(&tag[name.len()..])
+                    .into_iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case("href"))
+                    .map(|(_, value)| decode_entities(&value));
+                if let Some(href) = href.filter(|href| !href.trim().is_empty()) {
+                    self.with_table(|table| table.start_link(href.trim().to_string()));
+                }
+            }
+            ("a", true) => self.with_table(TableBuilder::end_link),
+            _ => {}
+        }
+    }
+
+    fn line_break(&mut self) {
+        if let Some(cell) = self.open.last_mut().and_then(|table| table.cell.as_mut()) {
+            cell.text.push('\n');
+        }
+    }
+
+    fn with_table(&mut self, action: impl FnOnce(&mut TableBuilder)) {
+        if let Some(table) = self.open.last_mut() {
+            action(table);
+        }
+    }
+
+    fn finish(mut self) -> Vec<HtmlTable> {
+        // Close tables left open at the end of the block
+        while !self.open.is_empty() {
+            self.tag("/table");
+        }
+        self.tables
+    }
+}
+
+/// Collapse runs of whitespace to single spaces, keeping `<br>` line breaks
+fn collapse_whitespace(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    // Source newlines are layout, line breaks come from <br>
+    let text = text.replace(['\n', '\r'], " ");
+    if !text.contains('&') {
+        return text;
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", "\u{00A0}")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NESTED: &str = "<table>\n<tr><th>Name</th><th>Details</th></tr>\n<tr><td>Alpha</td><td>Specs:\n<table><tr><td>cpu</td><td>4</td></tr><tr><td>ram</td><td>8 &amp; more</td></tr></table>\n</td></tr>\n</table>";
+
+    #[test]
+    fn test_parse_simple_table() {
+        let tables = parse_tables("<table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>x<br>y</td></tr></table>", 8);
+        assert_eq!(tables.len(), 1);
+        let rows = &tables[0].rows;
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0][0].header);
+        assert_eq!(rows[0][1].text, "B");
+        assert!(!rows[1][0].header);
+        assert_eq!(rows[1][1].text, "x\ny");
+    }
+
+    #[test]
+    fn test_parse_nested_table() {
+        let tables = parse_tables(NESTED, 8);
+        assert_eq!(tables.len(), 1);
+        let outer = &tables[0];
+        assert_eq!(outer.depth(), 2);
+        let cell = &outer.rows[1][1];
+        assert_eq!(cell.text, "Specs:");
+        assert_eq!(cell.tables.len(), 1);
+        assert_eq!(cell.tables[0].rows[1][1].text, "8 & more");
+    }
+
+    #[test]
+    fn test_nesting_beyond_limit_is_flattened() {
+        let tables = parse_tables(NESTED, 1);
+        let cell = &tables[0].rows[1][1];
+        assert!(cell.tables.is_empty());
+        assert_eq!(cell.text, "Specs: cpu 4 ram 8 & more");
+        assert_eq!(tables[0].depth(), 1);
+    }
+
+    #[test]
+    fn test_cell_links() {
+        let tables = parse_tables(
+            "<table><tr><td>serde  <a href=\"https://serde.rs\">serde</a> and <a href='/x'>x</a></td><td><a name=\"n\">plain</a></td></tr></table>",
+            8,
+        );
+        let cell = &tables[0].rows[0][0];
+        assert_eq!(cell.text, "serde serde and x");
+        let links: Vec<_> = cell.links.iter().map(|link| (link.offset, link.text.as_str(), link.url.as_str())).collect();
+        assert_eq!(links, [(6, "serde", "https://serde.rs"), (16, "x", "/x")]);
+        assert_eq!((cell.links[1].row, cell.links[1].column), (Some(0), 0));
+        assert_eq!(tables[0].rows[0][1].text, "plain");
+        assert!(tables[0].rows[0][1].links.is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_table_is_kept() {
+        let tables = parse_tables("<table><tr><td>open", 8);
+        assert_eq!(tables[0].rows[0][0].text, "open");
+        assert!(contains_table("<TABLE>"));
+        assert!(!contains_table("<div>text</div>"));
+    }
+}
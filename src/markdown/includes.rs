@@ -0,0 +1,155 @@
+//! Transclusion of other Markdown files
+//!
+//! A line holding only `{{include: path}}` or `!INCLUDE "path"` is replaced
+//! by the contents of that file, resolved relative to the file containing
+//! the directive. Included files may include others up to a depth limit; a
+//! file that includes itself, directly or through others, is an error.
+//! Directives inside fenced code blocks are left as written.
+//!
+//! Files are spliced into the source before parsing, so included content
+//! parses as part of the document and heading numbering, footnotes and
+//! anchors run on across files.
+
+use crate::error::ConversionError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Path named by an include directive line, if the line is one
+pub fn directive_path(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let path = if let Some(inner) = line.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}")) {
+        inner.trim().strip_prefix("include:")?.trim()
+    } else {
+        let quoted = line.strip_prefix("!INCLUDE")?.trim();
+        quoted.strip_prefix('"')?.strip_suffix('"')?
+    };
+    (!path.is_empty()).then_some(path)
+}
+
+/// Read a Markdown file and splice in the files it includes
+///
+/// `max_depth` is how many levels of nested includes are allowed below the
+/// file itself.
+pub fn expand_file(path: &Path, max_depth: usize) -> Result<String, ConversionError> {
+    let source = fs::read_to_string(path)?;
+    expand(&source, path, max_depth)
+}
+
+/// Splice the files included by `source`, which was read from `path`
+pub fn expand(source: &str, path: &Path, max_depth: usize) -> Result<String, ConversionError> {
+    let mut stack = vec![canonical(path)];
+    let mut output = String::with_capacity(source.len());
+    expand_into(source, path, max_depth, &mut stack, &mut output)?;
+    Ok(output)
+}
+
+fn expand_into(
+    source: &str,
+    path: &Path,
+    depth_left: usize,
+    stack: &mut Vec<PathBuf>,
+    output: &mut String,
+) -> Result<(), ConversionError> {
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    // Fence character and length of the open code fence, if any
+    let mut fence: Option<(char, usize)> = None;
+
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~');
+        if let Some(c) = marker {
+            let run = trimmed.chars().take_while(|&x| x == c).count();
+            if run >= 3 {
+                match fence {
+                    None => fence = Some((c, run)),
+                    Some((open, len)) if open == c && run >= len && trimmed[run..].trim().is_empty() => fence = None,
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let included = match (fence, directive_path(line)) {
+            (None, Some(target)) => base.join(target),
+            _ => {
+                output.push_str(line);
+                continue;
+            }
+        };
+
+        if depth_left == 0 {
+            return Err(ConversionError::FileProcessing(format!(
+                "Include of {} from {} exceeds the include depth limit",
+                included.display(),
+                path.display()
+            )));
+        }
+        let key = canonical(&included);
+        if stack.contains(&key) {
+            return Err(ConversionError::FileProcessing(format!(
+                "Include cycle: {} includes {}, which is already being included",
+                path.display(),
+                included.display()
+            )));
+        }
+        let content = fs::read_to_string(&included).map_err(|e| {
+            ConversionError::FileProcessing(format!(
+                "Cannot include {} from {}: {}",
+                included.display(),
+                path.display(),
+                e
+            ))
+        })?;
+
+        // Blank lines keep the included blocks from merging with their
+        // neighbours
+        output.push('\n');
+        stack.push(key);
+        expand_into(&content, &included, depth_left - 1, stack, output)?;
+        stack.pop();
+        if !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push('\n');
+    }
+    Ok(())
+}
+
+/// Identity of a file for cycle detection
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directive_syntaxes() {
+        assert_eq!(directive_path("{{include: parts/intro.md}}"), Some("parts/intro.md"));
+        assert_eq!(directive_path("  {{ include:other.md }}\n"), Some("other.md"));
+        assert_eq!(directive_path("!INCLUDE \"chapter 2.md\""), Some("chapter 2.md"));
+        assert_eq!(directive_path("See {{include: x.md}} inline"), None);
+        assert_eq!(directive_path("!INCLUDE unquoted.md"), None);
+        assert_eq!(directive_path("{{include: }}"), None);
+    }
+
+    #[test]
+    fn test_nested_includes_cycles_and_fences() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("parts")).unwrap();
+        fs::write(dir.path().join("main.md"), "# Main\n{{include: parts/a.md}}\n```\n{{include: parts/a.md}}\n```\n").unwrap();
+        fs::write(dir.path().join("parts/a.md"), "A text\n!INCLUDE \"b.md\"").unwrap();
+        fs::write(dir.path().join("parts/b.md"), "B text\n").unwrap();
+
+        let expanded = expand_file(&dir.path().join("main.md"), 8).unwrap();
+        assert_eq!(expanded, "# Main\n\nA text\n\nB text\n\n\n```\n{{include: parts/a.md}}\n```\n");
+
+        // Depth 1 allows a.md but not the b.md it includes
+        let error = expand_file(&dir.path().join("main.md"), 1).unwrap_err();
+        assert!(error.to_string().contains("depth limit"));
+
+        fs::write(dir.path().join("parts/b.md"), "{{include: ../main.md}}\n").unwrap();
+        let error = expand_file(&dir.path().join("main.md"), 8).unwrap_err();
+        assert!(error.to_string().contains("cycle"));
+    }
+}
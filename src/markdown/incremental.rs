@@ -0,0 +1,208 @@
+//! Incremental re-parsing for editor integrations
+//!
+//! Re-parses changed Markdown against the previous AST, reports which
+//! top-level blocks changed and reuses the processing results of code blocks
+//! whose language and source are unchanged.
+
+use crate::error::ConversionError;
+use crate::markdown::ast::{MarkdownDocument, MarkdownElement};
+use crate::markdown::parser::{MarkdownParser, ProcessedBlockLookup};
+use std::ops::Range;
+use tracing::debug;
+
+/// Top-level blocks that differ between two parses
+///
+/// Blocks before and after the changed region are identical in both
+/// documents; `changed` in the new document replaces `replaced` in the
+/// previous one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockChanges {
+    /// Indices of changed elements in the new document
+    pub changed: Range<usize>,
+    /// Indices of the elements they replace in the previous document
+    pub replaced: Range<usize>,
+    /// Code blocks whose processing was taken from the previous document
+    pub reused_code_blocks: usize,
+}
+
+impl BlockChanges {
+    /// Whether the two documents have the same blocks
+    pub fn is_unchanged(&self) -> bool {
+        self.changed.is_empty() && self.replaced.is_empty()
+    }
+}
+
+/// Result of an incremental re-parse
+#[derive(Debug, Clone)]
+pub struct IncrementalParse {
+    pub document: MarkdownDocument,
+    pub changes: BlockChanges,
+}
+
+impl MarkdownParser {
+    /// Parse new Markdown for a document previously parsed into `previous`
+    ///
+    /// Code blocks with the same language and source as a processed block in
+    /// `previous` are not processed again. Blocks processed without syntax
+    /// validation (see [`fence_info`](crate::markdown::fence_info)) are
+    /// always processed.
+    pub fn reparse(
+        &self,
+        previous: &MarkdownDocument,
+        markdown: &str,
+    ) -> Result<IncrementalParse, ConversionError> {
+        let mut reuse = ProcessedBlockLookup::new();
+        let previous_details = previous.details_lookup();
+        for element in &previous.elements {
+            if let MarkdownElement::CodeBlock { language, code, processed: Some(processed) } = element {
+                if previous_details.code_attributes(element).no_validate {
+                    continue;
+                }
+                reuse.insert((language.clone(), code.clone()), processed.clone());
+            }
+        }
+
+        let document = self.parse_reusing(markdown, &reuse)?;
+        let details = document.details_lookup();
+        let reused_code_blocks = document
+            .elements
+            .iter()
+            .filter(|element| match element {
+                MarkdownElement::CodeBlock { language, code, .. } => {
+                    !details.code_attributes(element).no_validate && reuse.contains_key(&(language.clone(), code.clone()))
+                }
+                _ => false,
+            })
+            .count();
+
+        let (changed, replaced) = diff_blocks(previous, &document);
+        debug!(
+            changed = changed.len(),
+            replaced = replaced.len(),
+            reused_code_blocks,
+            "Incremental re-parse complete"
+        );
+
+        Ok(IncrementalParse {
+            document,
+            changes: BlockChanges { changed, replaced, reused_code_blocks },
+        })
+    }
+}
+
+/// Find the changed region between common leading and trailing blocks
+fn diff_blocks(previous: &MarkdownDocument, current: &MarkdownDocument) -> (Range<usize>, Range<usize>) {
+    let (previous_details, current_details) = (previous.details_lookup(), current.details_lookup());
+    let same = |old: &MarkdownElement, new: &MarkdownElement| {
+        old.same_source(new) && previous_details.same_details(old, &current_details, new)
+    };
+    let (previous, current) = (&previous.elements, &current.elements);
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(old, new)| same(old, new))
+        .count();
+    let max_suffix = previous.len().min(current.len()) - prefix;
+    let suffix = previous
+        .iter()
+        .rev()
+        .zip(current.iter().rev())
+        .take(max_suffix)
+        .take_while(|(old, new)| same(old, new))
+        .count();
+
+    (prefix..current.len() - suffix, prefix..previous.len() - suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Title\n\nIntro text.\n\n```rust\nfn main() {}\n```\n\nOutro.\n";
+
+    #[test]
+    fn test_edited_paragraph_reported() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+        let result = parser.reparse(&previous, &DOC.replace("Intro text.", "Intro, edited.")).unwrap();
+
+        assert_eq!(result.changes.changed, 1..2);
+        assert_eq!(result.changes.replaced, 1..2);
+        assert_eq!(result.document.elements.len(), previous.elements.len());
+    }
+
+    #[test]
+    fn test_paragraph_class_change_reported() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+        let result = parser.reparse(&previous, &DOC.replace("Outro.", "Outro. {.note}")).unwrap();
+
+        assert_eq!(result.changes.changed, 3..4);
+        assert_eq!(result.changes.replaced, 3..4);
+    }
+
+    #[test]
+    fn test_heading_syntax_change_reported() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+        let result = parser.reparse(&previous, &DOC.replace("# Title", "Title\n=====")).unwrap();
+
+        assert_eq!(result.changes.changed, 0..1);
+        assert!(result.document.details_lookup().setext(&result.document.elements[0]));
+    }
+
+    #[test]
+    fn test_code_attribute_change_reported() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+        let result = parser.reparse(&previous, &DOC.replace("```rust", "```rust {highlight=1}")).unwrap();
+
+        assert_eq!(result.changes.changed, 2..3);
+        let details = result.document.details_lookup();
+        assert_eq!(details.code_attributes(&result.document.elements[2]).highlight, vec![1]);
+    }
+
+    #[test]
+    fn test_unchanged_code_block_reuses_processing() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+        let previous_time = match &previous.elements[2] {
+            MarkdownElement::CodeBlock { processed: Some(p), .. } => p.metadata.processing_time,
+            other => panic!("Expected processed code block, got {:?}", other),
+        };
+
+        let result = parser.reparse(&previous, &DOC.replace("Outro.", "Outro and more.")).unwrap();
+        assert_eq!(result.changes.reused_code_blocks, 1);
+        assert_eq!(result.changes.changed, 3..4);
+        match &result.document.elements[2] {
+            // The cached result is carried over verbatim rather than recomputed
+            MarkdownElement::CodeBlock { processed: Some(p), .. } => {
+                assert_eq!(p.metadata.processing_time, previous_time)
+            }
+            other => panic!("Expected processed code block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_changed_code_block_is_processed_again() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+        let result = parser.reparse(&previous, &DOC.replace("fn main() {}", "fn main() { run(); }")).unwrap();
+
+        assert_eq!(result.changes.reused_code_blocks, 0);
+        assert_eq!(result.changes.changed, 2..3);
+    }
+
+    #[test]
+    fn test_inserted_and_removed_blocks() {
+        let parser = MarkdownParser::new();
+        let previous = parser.parse(DOC).unwrap();
+
+        let inserted = parser.reparse(&previous, &DOC.replace("Outro.", "New block.\n\nOutro.")).unwrap();
+        assert_eq!(inserted.changes.changed, 3..4);
+        assert!(inserted.changes.replaced.is_empty());
+
+        let unchanged = parser.reparse(&previous, DOC).unwrap();
+        assert!(unchanged.changes.is_unchanged());
+    }
+}
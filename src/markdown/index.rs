@@ -0,0 +1,158 @@
+//! Back-of-book index
+//!
+//! Collects the terms of `<!-- index: term -->` directives into an
+//! alphabetized index. Each [`InlineElement::IndexTerm`] is replaced by an
+//! [`InlineElement::IndexMarker`] numbered in document order; the generator
+//! bookmarks every marker and the index lists the page of each bookmark.
+//! Occurrences of the same term share one entry.
+
+use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use std::collections::HashMap;
+
+/// An entry of the index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub term: String,
+    /// Markers of the term's occurrences, in document order
+    pub markers: Vec<usize>,
+}
+
+/// Name of the bookmark written at an index marker
+///
+/// The leading underscore keeps the bookmarks out of Word's bookmark list.
+pub fn bookmark_name(marker: usize) -> String {
+    format!("_Idx{}", marker)
+}
+
+/// Whether a document has any index terms
+pub fn has_terms(document: &MarkdownDocument) -> bool {
+    document.elements.iter().any(element_has_terms)
+}
+
+/// Replace index terms with numbered markers, returning the index entries
+/// sorted alphabetically
+///
+/// Terms are sorted without regard to case; terms differing only in case
+/// are separate entries.
+pub fn apply_to_document(document: &mut MarkdownDocument) -> Vec<IndexEntry> {
+    let mut collector = Collector::default();
+    for element in &mut document.elements {
+        collector.rewrite_element(element);
+    }
+    let mut entries = collector.entries;
+    entries.sort_by(|a, b| {
+        a.term
+            .to_lowercase()
+            .cmp(&b.term.to_lowercase())
+            .then_with(|| a.term.cmp(&b.term))
+    });
+    entries
+}
+
+fn element_has_terms(element: &MarkdownElement) -> bool {
+    let has_term = |content: &[InlineElement]| content.iter().any(|inline| matches!(inline, InlineElement::IndexTerm(_)));
+    match element {
+        MarkdownElement::Paragraph { content, .. }
+        | MarkdownElement::FootnoteDefinition { content, .. } => has_term(content),
+        MarkdownElement::List { items, .. } => items_have_terms(items, &has_term),
+        MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => body.iter().any(element_has_terms),
+        _ => false,
+    }
+}
+
+fn items_have_terms(items: &[ListItem], has_term: &dyn Fn(&[InlineElement]) -> bool) -> bool {
+    items.iter().any(|item| {
+        item.paragraphs().any(|content| has_term(content)) || items_have_terms(&item.sub_items, has_term)
+    })
+}
+
+#[derive(Default)]
+struct Collector {
+    /// Position of each term in `entries`
+    positions: HashMap<String, usize>,
+    entries: Vec<IndexEntry>,
+    next_marker: usize,
+}
+
+impl Collector {
+    fn rewrite_element(&mut self, element: &mut MarkdownElement) {
+        match element {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => self.rewrite(content),
+            MarkdownElement::List { items, .. } => self.rewrite_items(items),
+            MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                for element in body {
+                    self.rewrite_element(element);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn rewrite_items(&mut self, items: &mut [ListItem]) {
+        for item in items {
+            for content in item.paragraphs_mut() {
+                self.rewrite(content);
+            }
+            self.rewrite_items(&mut item.sub_items);
+        }
+    }
+
+    fn rewrite(&mut self, inlines: &mut [InlineElement]) {
+        for inline in inlines {
+            if let InlineElement::IndexTerm(term) = inline {
+                let marker = self.next_marker;
+                self.next_marker += 1;
+                let position = match self.positions.get(term.as_str()) {
+                    Some(&position) => position,
+                    None => {
+                        self.entries.push(IndexEntry {
+                            term: term.clone(),
+                            markers: Vec::new(),
+                        });
+                        self.positions.insert(term.clone(), self.entries.len() - 1);
+                        self.entries.len() - 1
+                    }
+                };
+                self.entries[position].markers.push(marker);
+                *inline = InlineElement::IndexMarker(marker);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terms_collected_and_sorted() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                InlineElement::Text("Zebras and apples".to_string()),
+                InlineElement::IndexTerm("zebra".to_string()),
+                InlineElement::IndexTerm("Apple".to_string()),
+            ],
+        });
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![InlineElement::IndexTerm("zebra".to_string())],
+        });
+        assert!(has_terms(&document));
+
+        let entries = apply_to_document(&mut document);
+        assert_eq!(
+            entries,
+            vec![
+                IndexEntry { term: "Apple".to_string(), markers: vec![1] },
+                IndexEntry { term: "zebra".to_string(), markers: vec![0, 2] },
+            ]
+        );
+        assert!(!has_terms(&document));
+        match &document.elements[1] {
+            MarkdownElement::Paragraph { content, .. } => assert_eq!(content[0], InlineElement::IndexMarker(2)),
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+        assert_eq!(bookmark_name(2), "_Idx2");
+    }
+}
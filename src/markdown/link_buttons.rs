@@ -0,0 +1,60 @@
+//! Lone links as buttons
+//!
+//! A paragraph holding nothing but a single link is usually a call to
+//! action. Such paragraphs are given the [`BUTTON_CLASS`] class, which is
+//! styled by `elements.link_buttons`. Whitespace, comments and index terms
+//! around the link do not count against a paragraph; links sharing a
+//! paragraph with text or other links are left as inline hyperlinks.
+
+use crate::markdown::ast::{InlineElement, MarkdownDocument};
+
+/// Class given to lone-link paragraphs
+pub const BUTTON_CLASS: &str = "button";
+
+/// Whether paragraph content is a single link and nothing else
+pub fn is_lone_link(content: &[InlineElement]) -> bool {
+    let mut links = 0;
+    for inline in content {
+        match inline {
+            InlineElement::Link { text, .. } if !text.trim().is_empty() => links += 1,
+            InlineElement::Text(text) if text.trim().is_empty() => {}
+            InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_) => {}
+            _ => return false,
+        }
+    }
+    links == 1
+}
+
+/// Give lone-link paragraphs the button class
+pub fn apply_to_document(document: &mut MarkdownDocument) {
+    document.for_each_paragraph_mut(|content, classes| {
+        if is_lone_link(content) && !classes.iter().any(|class| class == BUTTON_CLASS) {
+            classes.push(BUTTON_CLASS.to_string());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lone_link_paragraphs() {
+        let link = |text: &str| InlineElement::Link {
+            text: text.to_string(),
+            url: "https://example.com/signup".to_string(),
+            title: None,
+        };
+        let text = |text: &str| InlineElement::Text(text.to_string());
+
+        assert!(is_lone_link(&[link("Sign up now")]));
+        assert!(is_lone_link(&[text(" "), link("Sign up now"), text("\n")]));
+        assert!(!is_lone_link(&[text("Please "), link("sign up"), text(".")]));
+        assert!(!is_lone_link(&[link("Docs"), text(" "), link("API")]));
+        assert!(!is_lone_link(&[link(" ")]));
+        assert!(!is_lone_link(&[]));
+    }
+}
@@ -0,0 +1,247 @@
+//! Length limit for paragraphs
+//!
+//! A paragraph of many megabytes slows run creation and width estimation
+//! down to a crawl. Paragraphs over the limit are split into several, each
+//! breaking at the last space before the limit where there is one, or cut
+//! short with an ellipsis. Only paragraphs are limited: code blocks, lists,
+//! tables and headings are left as written.
+
+use crate::markdown::ast::{InlineElement, MarkdownDocument, MarkdownElement};
+use std::collections::BTreeMap;
+
+/// Character counts of the paragraphs longer than `max_chars`, in document
+/// order
+pub fn oversized(document: &MarkdownDocument, max_chars: usize) -> Vec<usize> {
+    fn visit(elements: &[MarkdownElement], max_chars: usize, found: &mut Vec<usize>) {
+        for element in elements {
+            match element {
+                MarkdownElement::Paragraph { content, .. } => {
+                    let length = content.iter().map(char_count).sum();
+                    if length > max_chars {
+                        found.push(length);
+                    }
+                }
+                MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                    visit(body, max_chars, found)
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut found = Vec::new();
+    visit(&document.elements, max_chars, &mut found);
+    found
+}
+
+/// Split paragraphs longer than `max_chars` into several, or with
+/// `truncate` keep only their first `max_chars` characters followed by an
+/// ellipsis
+pub fn apply_to_document(document: &mut MarkdownDocument, max_chars: usize, truncate: bool) {
+    let mut classes = ClassesByOrdinal {
+        before: std::mem::take(&mut document.details.paragraph_classes),
+        ..ClassesByOrdinal::default()
+    };
+    limit_elements(&mut document.elements, max_chars.max(1), truncate, &mut classes);
+    document.details.paragraph_classes = classes.after;
+}
+
+/// Paragraph classes moved from the ordinals of the paragraphs before
+/// splitting to those after, where each part keeps the classes
+#[derive(Default)]
+struct ClassesByOrdinal {
+    before: BTreeMap<usize, Vec<String>>,
+    after: BTreeMap<usize, Vec<String>>,
+    next_before: usize,
+    next_after: usize,
+}
+
+impl ClassesByOrdinal {
+    /// Move the classes of the next paragraph onto the `parts` it became
+    fn place(&mut self, parts: usize) {
+        if let Some(classes) = self.before.remove(&self.next_before) {
+            for part in 0..parts {
+                self.after.insert(self.next_after + part, classes.clone());
+            }
+        }
+        self.next_before += 1;
+        self.next_after += parts;
+    }
+}
+
+fn limit_elements(elements: &mut Vec<MarkdownElement>, max_chars: usize, truncate: bool, classes: &mut ClassesByOrdinal) {
+    let mut limited = Vec::with_capacity(elements.len());
+    for mut element in elements.drain(..) {
+        match &mut element {
+            MarkdownElement::Paragraph { content } if content.iter().map(char_count).sum::<usize>() > max_chars => {
+                let mut parts = split_inlines(std::mem::take(content), max_chars);
+                if truncate {
+                    parts.truncate(1);
+                    parts[0].push(InlineElement::Text("…".to_string()));
+                }
+                classes.place(parts.len());
+                limited.extend(parts.into_iter().map(|content| MarkdownElement::Paragraph { content }));
+                continue;
+            }
+            MarkdownElement::Paragraph { .. } => classes.place(1),
+            MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                limit_elements(body, max_chars, truncate, classes)
+            }
+            _ => {}
+        }
+        limited.push(element);
+    }
+    *elements = limited;
+}
+
+/// Characters of an inline element's visible text
+fn char_count(inline: &InlineElement) -> usize {
+    match inline {
+        InlineElement::Text(text)
+        | InlineElement::Bold(text)
+        | InlineElement::Italic(text)
+        | InlineElement::Strikethrough(text)
+        | InlineElement::Code(text)
+        | InlineElement::Link { text, .. }
+        | InlineElement::Span { text, .. }
+        | InlineElement::Abbreviation { text, .. } => text.chars().count(),
+        InlineElement::FootnoteReference(_)
+        | InlineElement::ReferenceMarker(_)
+        | InlineElement::Comment(_)
+        | InlineElement::IndexTerm(_)
+        | InlineElement::IndexMarker(_)
+        | InlineElement::RevisionNote(_) => 0,
+    }
+}
+
+/// Group inline content into parts of at most `max_chars` characters
+///
+/// Plain and emphasized text is broken wherever a part fills up; links,
+/// code spans and the like are moved whole to the next part, or make up a
+/// part of their own when longer than the limit.
+fn split_inlines(content: Vec<InlineElement>, max_chars: usize) -> Vec<Vec<InlineElement>> {
+    let mut parts = Vec::new();
+    let mut part = Vec::new();
+    let mut length = 0;
+    for inline in content {
+        let mut inline = inline;
+        loop {
+            let chars = char_count(&inline);
+            if length + chars <= max_chars {
+                length += chars;
+                part.push(inline);
+                break;
+            }
+            match split_text(&inline, max_chars - length) {
+                Some((head, tail)) => {
+                    part.push(head);
+                    inline = tail;
+                }
+                None if part.is_empty() => {
+                    // Not splittable and over the limit on its own
+                    parts.push(vec![inline]);
+                    break;
+                }
+                None => {}
+            }
+            parts.push(std::mem::take(&mut part));
+            length = 0;
+        }
+    }
+    if !part.is_empty() || parts.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+/// Split a text inline after its first `room` characters, at the last
+/// space within them when there is one; `None` for inlines that are not
+/// split or when nothing fits
+fn split_text(inline: &InlineElement, room: usize) -> Option<(InlineElement, InlineElement)> {
+    let text = match inline {
+        InlineElement::Text(text)
+        | InlineElement::Bold(text)
+        | InlineElement::Italic(text)
+        | InlineElement::Strikethrough(text)
+        | InlineElement::Span { text, .. } => text,
+        _ => return None,
+    };
+    let limit = text.char_indices().nth(room).map_or(text.len(), |(at, _)| at);
+    let at = match text[..limit].rfind(char::is_whitespace) {
+        Some(space) if space > 0 => space + text[space..].chars().next().map_or(0, char::len_utf8),
+        _ => limit,
+    };
+    if at == 0 {
+        return None;
+    }
+    let with_text = |text: &str| match inline {
+        InlineElement::Bold(_) => InlineElement::Bold(text.to_string()),
+        InlineElement::Italic(_) => InlineElement::Italic(text.to_string()),
+        InlineElement::Strikethrough(_) => InlineElement::Strikethrough(text.to_string()),
+        InlineElement::Span { classes, lang, .. } => InlineElement::Span {
+            text: text.to_string(),
+            classes: classes.clone(),
+            lang: lang.clone(),
+        },
+        _ => InlineElement::Text(text.to_string()),
+    };
+    Some((with_text(&text[..at]), with_text(&text[at..])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(content: Vec<InlineElement>) -> MarkdownElement {
+        MarkdownElement::Paragraph { content }
+    }
+
+    fn texts(document: &MarkdownDocument) -> Vec<String> {
+        document.elements.iter().map(MarkdownElement::extract_text).collect()
+    }
+
+    #[test]
+    fn test_split_at_spaces_within_limit() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(paragraph(vec![
+            InlineElement::Text("alpha beta gamma ".to_string()),
+            InlineElement::Bold("delta epsilon".to_string()),
+        ]));
+        document.add_element(MarkdownElement::CodeBlock {
+            language: None,
+            code: "x".repeat(100),
+            processed: None,
+        });
+        assert_eq!(oversized(&document, 12), [30]);
+
+        apply_to_document(&mut document, 12, false);
+        assert_eq!(texts(&document)[..3], ["alpha beta ", "gamma delta ", "epsilon"]);
+        assert!(matches!(&document.elements[2], MarkdownElement::Paragraph { content, .. } if matches!(&content[0], InlineElement::Bold(_))));
+        // Code blocks are untouched
+        assert!(matches!(&document.elements[3], MarkdownElement::CodeBlock { code, .. } if code.len() == 100));
+        assert!(oversized(&document, 12).is_empty());
+    }
+
+    #[test]
+    fn test_split_parts_keep_classes() {
+        let mut document = MarkdownDocument::new();
+        document.add_classed_paragraph(vec![InlineElement::Text("alpha beta gamma".to_string())], vec!["note".to_string()]);
+        document.add_element(MarkdownElement::BlockQuote {
+            content: vec![paragraph(vec![InlineElement::Text("short".to_string())])],
+        });
+        document.add_classed_paragraph(vec![InlineElement::Text("last".to_string())], vec!["end".to_string()]);
+
+        apply_to_document(&mut document, 11, false);
+        let details = document.details_lookup();
+        let classes: Vec<&[String]> = document.elements.iter().map(|element| details.classes(element)).collect();
+        let (note, end) = (["note".to_string()], ["end".to_string()]);
+        assert_eq!(classes, [&note[..], &note[..], &[], &end[..]]);
+    }
+
+    #[test]
+    fn test_truncate_keeps_first_part() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(paragraph(vec![InlineElement::Text("x".repeat(50))]));
+        apply_to_document(&mut document, 20, true);
+        assert_eq!(texts(&document), [format!("{}…", "x".repeat(20))]);
+    }
+}
@@ -2,8 +2,32 @@
 
 pub mod parser;
 pub mod ast;
+pub mod abbreviations;
+pub mod bullets;
+pub mod callouts;
+pub mod checkbox;
 pub mod code_block;
+pub mod email;
+pub mod emoji;
+pub mod fence_info;
+pub mod front_matter;
+pub mod html_details;
+pub mod html_image;
+pub mod html_inline;
+pub mod html_table;
+pub mod includes;
+pub mod incremental;
+pub mod index;
+pub mod link_buttons;
+pub mod long_paragraphs;
+pub mod references;
+pub mod revisions;
+pub mod table_links;
+pub mod variables;
+pub mod whitespace;
+pub mod writer;
 
 pub use parser::MarkdownParser;
 pub use ast::*;
+pub use incremental::{BlockChanges, IncrementalParse};
 pub use code_block::*;
\ No newline at end of file
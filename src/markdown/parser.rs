@@ -1,9 +1,25 @@
 //! Markdown parser using pulldown-cmark
 
 use crate::error::ConversionError;
-use crate::markdown::ast::{MarkdownDocument, MarkdownElement, InlineElement, ListItem};
+use crate::markdown::ast::{MarkdownDocument, MarkdownElement, InlineElement, ListItem, ListItemBlock, MarginOverrides, PageOrientation, TableLink};
+use crate::config::units::parse_length;
+use crate::config::{ParsingProfile, DEFAULT_MAX_NESTING_DEPTH};
 use crate::markdown::code_block::{CodeBlockProcessor, CodeBlockConfig};
+use crate::markdown::bullets;
+use crate::markdown::fence_info::{self, CodeAttributes};
+use crate::markdown::html_details::{self, DetailsEvent};
+use crate::markdown::html_image;
+use crate::markdown::html_inline;
+use crate::markdown::html_table;
 use pulldown_cmark::{Event, Parser, Tag, CodeBlockKind, HeadingLevel};
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Headers, rows and cell links of a pipe table
+type TableContent = (Vec<String>, Vec<Vec<String>>, Vec<TableLink>);
+
+/// Processed code blocks keyed by language and source
+pub(crate) type ProcessedBlockLookup = HashMap<(Option<String>, String), crate::markdown::code_block::ProcessedCodeBlock>;
 
 /// Markdown parser that converts Markdown text to AST
 pub struct MarkdownParser {
@@ -11,20 +27,43 @@ pub struct MarkdownParser {
     options: pulldown_cmark::Options,
     /// Code block processor for handling language-specific processing
     code_block_processor: CodeBlockProcessor,
+    /// Deepest list nesting kept; deeper items are flattened onto the last level
+    max_nesting_depth: usize,
+    /// Dialect being parsed; md2docx's own extensions only apply to GFM
+    profile: ParsingProfile,
+    /// Turn paragraphs whose lines all start with `•`-style glyphs into lists
+    unicode_bullets: bool,
+    /// Split off `— Author` lines ending blockquotes as attribution paragraphs
+    quote_attribution: bool,
 }
 
-impl MarkdownParser {
-    /// Create a new Markdown parser with default options
-    pub fn new() -> Self {
-        let mut options = pulldown_cmark::Options::empty();
+/// Class given to the attribution paragraph split off the end of a blockquote
+pub const ATTRIBUTION_CLASS: &str = "attribution";
+
+/// pulldown-cmark options for a parsing profile
+fn profile_options(profile: ParsingProfile) -> pulldown_cmark::Options {
+    let mut options = pulldown_cmark::Options::empty();
+    if profile == ParsingProfile::Gfm {
         options.insert(pulldown_cmark::Options::ENABLE_TABLES);
         options.insert(pulldown_cmark::Options::ENABLE_FOOTNOTES);
         options.insert(pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
         options.insert(pulldown_cmark::Options::ENABLE_TASKLISTS);
-        
+    }
+    options
+}
+
+impl MarkdownParser {
+    /// Create a new Markdown parser with default options
+    pub fn new() -> Self {
+        let options = profile_options(ParsingProfile::Gfm);
+
         Self { 
             options,
             code_block_processor: CodeBlockProcessor::new(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            profile: ParsingProfile::Gfm,
+            unicode_bullets: false,
+            quote_attribution: false,
         }
     }
 
@@ -33,6 +72,10 @@ impl MarkdownParser {
         Self { 
             options,
             code_block_processor: CodeBlockProcessor::new(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            profile: ParsingProfile::Gfm,
+            unicode_bullets: false,
+            quote_attribution: false,
         }
     }
 
@@ -44,20 +87,24 @@ impl MarkdownParser {
         Self {
             options,
             code_block_processor: CodeBlockProcessor::with_config(code_block_config),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            profile: ParsingProfile::Gfm,
+            unicode_bullets: false,
+            quote_attribution: false,
         }
     }
 
     /// Create a new Markdown parser with code block configuration
     pub fn with_code_block_config(code_block_config: CodeBlockConfig) -> Self {
-        let mut options = pulldown_cmark::Options::empty();
-        options.insert(pulldown_cmark::Options::ENABLE_TABLES);
-        options.insert(pulldown_cmark::Options::ENABLE_FOOTNOTES);
-        options.insert(pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
-        options.insert(pulldown_cmark::Options::ENABLE_TASKLISTS);
-        
+        let options = profile_options(ParsingProfile::Gfm);
+
         Self {
             options,
             code_block_processor: CodeBlockProcessor::with_config(code_block_config),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            profile: ParsingProfile::Gfm,
+            unicode_bullets: false,
+            quote_attribution: false,
         }
     }
 
@@ -71,62 +118,155 @@ impl MarkdownParser {
         self.code_block_processor.update_config(config);
     }
 
+    /// Set the deepest list nesting kept in the AST (at least 1)
+    pub fn set_max_nesting_depth(&mut self, depth: usize) {
+        self.max_nesting_depth = depth.max(1);
+    }
+
+    /// Switch to a parsing profile, replacing the pulldown-cmark options
+    pub fn set_profile(&mut self, profile: ParsingProfile) {
+        self.options = profile_options(profile);
+        self.profile = profile;
+    }
+
+    /// Convert paragraphs made only of lines starting with Unicode bullet
+    /// glyphs (`•`, `◦`, `▪`, ...) into unordered lists
+    pub fn set_unicode_bullets(&mut self, enabled: bool) {
+        self.unicode_bullets = enabled;
+    }
+
+    /// Split a last line starting with `—` off the end of a blockquote into
+    /// its own paragraph with the [`ATTRIBUTION_CLASS`] class
+    pub fn set_quote_attribution(&mut self, enabled: bool) {
+        self.quote_attribution = enabled;
+    }
+
+    /// The active parsing profile
+    pub fn profile(&self) -> ParsingProfile {
+        self.profile
+    }
+
+    /// Whether md2docx's Markdown extensions (`{.class}`, `?width=`) apply
+    fn extensions_enabled(&self) -> bool {
+        self.profile == ParsingProfile::Gfm
+    }
+
     /// Parse Markdown string into document AST
     pub fn parse(&self, markdown: &str) -> Result<MarkdownDocument, ConversionError> {
+        self.parse_reusing(markdown, &ProcessedBlockLookup::new())
+    }
+
+    /// Parse Markdown, taking processed code blocks from `reuse` instead of
+    /// running their strategies again
+    pub(crate) fn parse_reusing(&self, markdown: &str, reuse: &ProcessedBlockLookup) -> Result<MarkdownDocument, ConversionError> {
         let parser = Parser::new_ext(markdown, self.options);
         let mut document = MarkdownDocument::new();
-        let events: Vec<Event> = parser.collect();
-        
+        let (events, ranges): (Vec<Event>, Vec<Range<usize>>) = parser.into_offset_iter().unzip();
+        
+        // Open `<details>` blocks: summary and index of their first element
+        let mut open_details: Vec<(String, usize)> = Vec::new();
+        // Open blockquotes: index of their first element, and how many
+        // quotes nested beyond the maximum depth were merged into the deepest
+        let mut open_quotes: Vec<usize> = Vec::new();
+        let mut merged_quotes = 0;
+        // Headings, paragraphs and code blocks added so far, whose ordinals
+        // key their details
+        let mut headings = 0;
+        let mut paragraphs = 0;
+        let mut code_blocks = 0;
         let mut i = 0;
         while i < events.len() {
             // println!("event = {:?}, i = {}, len = {}", &events[i], i, events.len());
             match &events[i] {
                 Event::Start(Tag::Heading(level, _, _)) => {
+                    let setext = is_setext_heading(&markdown[ranges[i].clone()]);
                     i += 1; // Skip start event
                     let text = self.collect_text_until_end(&events, &mut i, "Heading")?;
+                    if setext {
+                        document.details.setext_headings.insert(headings);
+                    }
+                    headings += 1;
                     document.add_element(MarkdownElement::Heading {
                         level: heading_level_to_u8(*level),
                         text,
                     });
                 },
                 Event::Start(Tag::Paragraph) => {
+                    let source = &markdown[ranges[i].clone()];
                     i += 1; // Skip start event
-                    let (content, standalone_image) = self.collect_paragraph_content(&events, &mut i)?;
+                    let (mut content, standalone_image) = self.collect_paragraph_content(&events, &mut i)?;
+                    if let Some(lines) = bullets::bullet_lines(source).filter(|_| self.unicode_bullets) {
+                        let items = lines
+                            .into_iter()
+                            .map(|line| ListItem {
+                                content: self.parse_inline_line(line),
+                                sub_items: Vec::new(),
+                                blocks: Vec::new(),
+                                checked: None,
+                                ordered: false,
+                            })
+                            .collect();
+                        document.add_element(MarkdownElement::List { ordered: false, items });
+                        continue;
+                    }
+                    let ends_quote = matches!(events.get(i), Some(Event::End(Tag::BlockQuote)));
+                    if let Some((body, attribution)) = split_attribution(source).filter(|_| self.quote_attribution && ends_quote) {
+                        if let Some(body) = body {
+                            let content = self.parse_inline_line(&body);
+                            Self::add_paragraph(&mut document, &mut paragraphs, content, Vec::new());
+                        }
+                        let content = self.parse_inline_line(attribution);
+                        Self::add_paragraph(&mut document, &mut paragraphs, content, vec![ATTRIBUTION_CLASS.to_string()]);
+                        continue;
+                    }
+                    let classes = if self.extensions_enabled() {
+                        Self::take_trailing_classes(&mut content)
+                    } else {
+                        Vec::new()
+                    };
                     
                     // If paragraph contains only an image, treat it as a standalone image
                     if let Some(image) = standalone_image {
                         document.add_element(image);
                     } else if !content.is_empty() {
-                        document.add_element(MarkdownElement::Paragraph { content });
+                        Self::add_paragraph(&mut document, &mut paragraphs, content, classes);
                     }
                 },
                 Event::Start(Tag::CodeBlock(kind)) => {
-                    let language = match kind {
-                        CodeBlockKind::Fenced(lang) => {
-                            if lang.is_empty() {
-                                None
-                            } else {
-                                Some(lang.to_string())
-                            }
-                        },
-                        CodeBlockKind::Indented => None,
+                    let (language, attributes) = match kind {
+                        CodeBlockKind::Fenced(info) => fence_info::parse(info),
+                        CodeBlockKind::Indented => (None, CodeAttributes::default()),
                     };
                     i += 1; // Skip start event
                     let code = self.collect_text_until_end(&events, &mut i, "CodeBlock")?;
                     
-                    // Process the code block using the strategy system
-                    let processed = match self.code_block_processor.process_code_block(
-                        &code,
-                        language.as_deref(),
-                    ) {
-                        Ok(processed_block) => Some(processed_block),
-                        Err(processing_error) => {
-                            // Log the error but continue with unprocessed code block
-                            tracing::warn!("Code block processing failed: {:?}", processing_error);
-                            None
-                        }
+                    // Reuse earlier processing of identical blocks, otherwise
+                    // process the code block using the strategy system
+                    let cached = if reuse.is_empty() || attributes.no_validate {
+                        None
+                    } else {
+                        reuse.get(&(language.clone(), code.clone()))
+                    };
+                    let processed = match cached {
+                        Some(processed_block) => Some(processed_block.clone()),
+                        None => match self.code_block_processor.process_code_block_with_attributes(
+                            &code,
+                            language.as_deref(),
+                            &attributes,
+                        ) {
+                            Ok(processed_block) => Some(processed_block),
+                            Err(processing_error) => {
+                                // Log the error but continue with unprocessed code block
+                                tracing::warn!("Code block processing failed: {:?}", processing_error);
+                                None
+                            }
+                        },
                     };
                     
+                    if !attributes.is_empty() {
+                        document.details.code_attributes.insert(code_blocks, attributes);
+                    }
+                    code_blocks += 1;
                     document.add_element(MarkdownElement::CodeBlock { 
                         language, 
                         code, 
@@ -136,29 +276,148 @@ impl MarkdownParser {
                 Event::Start(Tag::List(first_item_number)) => {
                     let ordered = first_item_number.is_some();
                     i += 1; // Skip start event
-                    let items = self.collect_list_items(&events, &mut i)?;
+                    let items = self.collect_list_items(&events, &mut i, ordered, 0)?;
                     document.add_element(MarkdownElement::List { ordered, items });
                 },
                 Event::Start(Tag::Table(_)) => {
                     i += 1; // Skip start event
-                    let (headers, rows) = self.collect_table_content(&events, &mut i)?;
-                    document.add_element(MarkdownElement::Table { headers, rows });
+                    let (headers, rows, links) = self.collect_table_content(&events, &mut i)?;
+                    document.add_element(MarkdownElement::Table { headers, rows, links });
                 },
 
                 Event::Rule => {
                     document.add_element(MarkdownElement::HorizontalRule);
                     i += 1;
                 },
+                Event::Start(Tag::FootnoteDefinition(label)) => {
+                    let label = label.to_string();
+                    i += 1; // Skip start event
+                    let content = self.collect_footnote_content(&events, &mut i)?;
+                    document.add_element(MarkdownElement::FootnoteDefinition { label, content });
+                },
+                Event::Html(_) => {
+                    // An HTML block arrives as one event per line
+                    let mut html = String::new();
+                    while let Some(Event::Html(chunk)) = events.get(i) {
+                        html.push_str(chunk);
+                        i += 1;
+                    }
+                    for comment in comment_directives(&html) {
+                        Self::attach_block_comment(&mut document, comment);
+                    }
+                    for note in revision_directives(&html) {
+                        Self::attach_block_revision_note(&mut document, note);
+                    }
+                    for term in index_directives(&html) {
+                        Self::attach_block_index_term(&mut document, term);
+                    }
+                    for orientation in section_directives(&html) {
+                        document.add_element(MarkdownElement::SectionBreak { orientation });
+                    }
+                    for margins in margin_directives(&html) {
+                        document.margins.merge(margins);
+                    }
+                    if html_details::contains_details(&html) {
+                        for event in html_details::scan(&html) {
+                            match event {
+                                DetailsEvent::Open(summary) => {
+                                    open_details.push((summary, document.elements.len()));
+                                }
+                                DetailsEvent::Text(text) => Self::add_paragraph(
+                                    &mut document,
+                                    &mut paragraphs,
+                                    vec![InlineElement::Text(text)],
+                                    Vec::new(),
+                                ),
+                                DetailsEvent::Close => {
+                                    if let Some((summary, start)) = open_details.pop() {
+                                        let body = document.elements.split_off(start.min(document.elements.len()));
+                                        document.add_element(MarkdownElement::Details { summary, body });
+                                    }
+                                }
+                            }
+                        }
+                    } else if html_table::contains_table(&html) {
+                        for table in html_table::parse_tables(&html, self.max_nesting_depth) {
+                            document.add_element(MarkdownElement::HtmlTable(table));
+                        }
+                    } else {
+                        for image in html_image::parse_images(&html) {
+                            document.add_element(image);
+                        }
+                    }
+                },
+                Event::Start(Tag::BlockQuote) => {
+                    if open_quotes.len() < self.max_nesting_depth {
+                        open_quotes.push(document.elements.len());
+                    } else {
+                        if merged_quotes == 0 {
+                            tracing::warn!(
+                                max_depth = self.max_nesting_depth,
+                                "Blockquote nesting exceeds the maximum depth; merging deeper quotes"
+                            );
+                        }
+                        merged_quotes += 1;
+                    }
+                    i += 1;
+                },
+                Event::End(Tag::BlockQuote) => {
+                    if merged_quotes > 0 {
+                        merged_quotes -= 1;
+                    } else if let Some(start) = open_quotes.pop() {
+                        let body = document.elements.split_off(start.min(document.elements.len()));
+                        document.add_element(MarkdownElement::BlockQuote { content: body });
+                    }
+                    i += 1;
+                },
                 _ => {
                     // println!("other event = {:?}", &events[i]);
                     i += 1; // Skip other events
                 }
             }
         }
+
+        while let Some(start) = open_quotes.pop() {
+            let body = document.elements.split_off(start.min(document.elements.len()));
+            document.add_element(MarkdownElement::BlockQuote { content: body });
+        }
+
+        // A details block left open runs to the end of the document
+        while let Some((summary, start)) = open_details.pop() {
+            let body = document.elements.split_off(start.min(document.elements.len()));
+            document.add_element(MarkdownElement::Details { summary, body });
+        }
         
         Ok(document)
     }
 
+    /// Add a paragraph, keying its classes by the number of paragraphs added
+    /// before it
+    fn add_paragraph(document: &mut MarkdownDocument, paragraphs: &mut usize, content: Vec<InlineElement>, classes: Vec<String>) {
+        if !classes.is_empty() {
+            document.details.paragraph_classes.insert(*paragraphs, classes);
+        }
+        *paragraphs += 1;
+        document.add_element(MarkdownElement::Paragraph { content });
+    }
+
+    /// Inline content of a single line of Markdown
+    fn parse_inline_line(&self, line: &str) -> Vec<InlineElement> {
+        let events: Vec<Event> = Parser::new_ext(line, self.options).collect();
+        let mut index = 0;
+        while index < events.len() {
+            let start = matches!(events[index], Event::Start(Tag::Paragraph));
+            index += 1;
+            if start {
+                if let Ok((content, _)) = self.collect_paragraph_content(&events, &mut index) {
+                    return content;
+                }
+                break;
+            }
+        }
+        vec![InlineElement::Text(line.to_string())]
+    }
+
     /// Collect text content until matching end tag
     fn collect_text_until_end(&self, events: &[Event], index: &mut usize, end_tag_name: &str) -> Result<String, ConversionError> {
         let mut text = String::new();
@@ -188,6 +447,8 @@ impl MarkdownParser {
                 Event::Code(c) => text.push_str(c),
                 Event::SoftBreak => text.push(' '),
                 Event::HardBreak => text.push('\n'),
+                // Table cells cannot hold a hard break, so `<br>` stands in for one
+                Event::Html(html) if end_tag_name == "TableCell" && is_line_break_tag(html) => text.push('\n'),
                 _ => {}, // Skip other events
             }
             *index += 1;
@@ -196,6 +457,36 @@ impl MarkdownParser {
         Ok(text)
     }
 
+    /// Collect the text of a `<kbd>`/`<samp>` element, whose opening tag
+    /// `html` was just consumed, into a span classed with the tag name, or
+    /// that of a `<span lang="...">` into a span in its language
+    ///
+    /// Stops after the closing tag, or before the end of the enclosing block
+    /// when the element is left open.
+    fn collect_styled_tag(&self, events: &[Event], index: &mut usize, html: &str) -> InlineElement {
+        let lang = html_inline::span_language(html);
+        let tag = match lang {
+            Some(_) => "span",
+            None => html_inline::opening_tag(html).unwrap_or_default(),
+        };
+        let mut text = String::new();
+        while let Some(event) = events.get(*index) {
+            match event {
+                Event::Html(html) if html_inline::is_closing_tag(html, tag) => {
+                    *index += 1;
+                    break;
+                }
+                Event::End(_) => break,
+                Event::Text(t) | Event::Code(t) => text.push_str(t),
+                Event::SoftBreak => text.push(' '),
+                _ => {}
+            }
+            *index += 1;
+        }
+        let classes = if lang.is_some() { Vec::new() } else { vec![tag.to_string()] };
+        InlineElement::Span { text, classes, lang }
+    }
+
     /// Collect paragraph content and detect standalone images
     fn collect_paragraph_content(&self, events: &[Event], index: &mut usize) -> Result<(Vec<InlineElement>, Option<MarkdownElement>), ConversionError> {
         let mut elements = Vec::new();
@@ -217,7 +508,11 @@ impl MarkdownParser {
                     let alt_text = self.collect_text_until_end(events, index, "Image")?;
                     
                     // Parse URL to extract width and height parameters
-                    let (clean_url, width, height) = Self::parse_image_url_params(&url);
+                    let (clean_url, width, height) = if self.extensions_enabled() {
+                        Self::parse_image_url_params(&url)
+                    } else {
+                        (url, None, None)
+                    };
                     
                     // Store the image element for potential standalone use
                     image_element = Some(MarkdownElement::Image {
@@ -266,6 +561,36 @@ impl MarkdownParser {
                     *index += 1;
                     has_other_content = true;
                 },
+                Event::FootnoteReference(label) => {
+                    elements.push(InlineElement::FootnoteReference(label.to_string()));
+                    *index += 1;
+                    has_other_content = true;
+                },
+                Event::Html(html) if html_inline::opens_span(html) => {
+                    *index += 1;
+                    elements.push(self.collect_styled_tag(events, index, html));
+                    has_other_content = true;
+                },
+                Event::Html(html) => {
+                    for comment in comment_directives(html) {
+                        Self::push_inline_comment(&mut elements, InlineElement::Comment(comment));
+                    }
+                    for note in revision_directives(html) {
+                        Self::push_inline_comment(&mut elements, InlineElement::RevisionNote(note));
+                    }
+                    elements.extend(index_directives(html).into_iter().map(InlineElement::IndexTerm));
+                    for image in html_image::parse_images(html) {
+                        if let MarkdownElement::Image { alt_text, url, title, .. } = &image {
+                            elements.push(InlineElement::Link {
+                                text: format!("[Image: {}]", alt_text),
+                                url: url.clone(),
+                                title: title.clone(),
+                            });
+                        }
+                        image_element = Some(image);
+                    }
+                    *index += 1;
+                },
                 Event::Text(text) => {
                     let text_str = text.to_string();
                     // Normalize multiple consecutive spaces to single space
@@ -307,12 +632,46 @@ impl MarkdownParser {
         }
         
         // Post-process: merge consecutive text elements and normalize whitespace
-        let processed_elements = self.merge_and_normalize_text_elements(elements);
+        let processed_elements = self.merge_and_normalize_text_elements(elements)
+            .into_iter()
+            .flat_map(|element| match element {
+                InlineElement::Text(text) if self.extensions_enabled() => Self::split_class_spans(&text),
+                other => vec![other],
+            })
+            .collect();
         
         Ok((processed_elements, None))
     }
 
-    /// Collect inline elements until matching end tag
+    /// Collect the paragraphs of a footnote definition as one run of inline content
+    fn collect_footnote_content(&self, events: &[Event], index: &mut usize) -> Result<Vec<InlineElement>, ConversionError> {
+        let mut content = Vec::new();
+
+        while *index < events.len() {
+            match &events[*index] {
+                Event::End(tag) if self.tag_matches_name(tag, "FootnoteDefinition") => {
+                    *index += 1;
+                    break;
+                },
+                Event::Start(Tag::Paragraph) => {
+                    *index += 1;
+                    let (paragraph, _) = self.collect_paragraph_content(events, index)?;
+                    if !content.is_empty() {
+                        content.push(InlineElement::Text(" ".to_string()));
+                    }
+                    content.extend(paragraph);
+                },
+                _ => {
+                    *index += 1; // Other blocks inside footnotes are not supported
+                }
+            }
+        }
+
+        Ok(content)
+    }
+
+    /// Collect inline elements up to the matching end tag or the start of a
+    /// block-level child; neither is consumed
     fn collect_inline_until_end(&self, events: &[Event], index: &mut usize, end_tag_name: &str) -> Result<Vec<InlineElement>, ConversionError> {
         let mut elements = Vec::new();
         
@@ -320,10 +679,14 @@ impl MarkdownParser {
             // println!("event = {:?}, index = {}, len = {}", &events[*index], *index, events.len());
             match &events[*index] {
                 Event::End(tag) => {
-                    *index += 1;
                     if self.tag_matches_name(tag, end_tag_name) {
                         break;
                     }
+                    *index += 1;
+                },
+                Event::Start(Tag::List(_) | Tag::Paragraph | Tag::CodeBlock(_)) => {
+                    // Block children are handled by the caller
+                    break;
                 },
                 Event::Start(Tag::Strong) => {
                     *index += 1;
@@ -351,6 +714,24 @@ impl MarkdownParser {
                     elements.push(InlineElement::Code(code.to_string()));
                     *index += 1;
                 },
+                Event::FootnoteReference(label) => {
+                    elements.push(InlineElement::FootnoteReference(label.to_string()));
+                    *index += 1;
+                },
+                Event::Html(html) if html_inline::opens_span(html) => {
+                    *index += 1;
+                    elements.push(self.collect_styled_tag(events, index, html));
+                },
+                Event::Html(html) => {
+                    for comment in comment_directives(html) {
+                        Self::push_inline_comment(&mut elements, InlineElement::Comment(comment));
+                    }
+                    for note in revision_directives(html) {
+                        Self::push_inline_comment(&mut elements, InlineElement::RevisionNote(note));
+                    }
+                    elements.extend(index_directives(html).into_iter().map(InlineElement::IndexTerm));
+                    *index += 1;
+                },
                 Event::Text(text) => {
                     let normalized_text = self.normalize_whitespace(&text.to_string());
                     elements.push(InlineElement::Text(normalized_text));
@@ -373,8 +754,8 @@ impl MarkdownParser {
         Ok(elements)
     }
 
-    /// Collect list items until end of list
-    fn collect_list_items(&self, events: &[Event], index: &mut usize) -> Result<Vec<ListItem>, ConversionError> {
+    /// Collect list items until end of list, marking each with the list's kind
+    fn collect_list_items(&self, events: &[Event], index: &mut usize, ordered: bool, depth: usize) -> Result<Vec<ListItem>, ConversionError> {
         let mut items = Vec::new();
         
         while *index < events.len() {
@@ -386,8 +767,29 @@ impl MarkdownParser {
                 },
                 Event::Start(Tag::Item) => {
                     *index += 1;
-                    let content = self.collect_inline_until_end(events, index, "Item")?;
-                    items.push(ListItem::new(content));
+                    let mut item = ListItem::new(Vec::new());
+                    item.ordered = ordered;
+                    let mut flattened = Vec::new();
+                    loop {
+                        self.collect_item_body(events, index, &mut item)?;
+                        let nested_ordered = match events.get(*index) {
+                            Some(Event::Start(Tag::List(first))) => first.is_some(),
+                            _ => break,
+                        };
+                        *index += 1; // Skip nested list start
+                        if depth + 1 < self.max_nesting_depth {
+                            item.sub_items.extend(self.collect_list_items(events, index, nested_ordered, depth + 1)?);
+                        } else {
+                            tracing::warn!(
+                                max_depth = self.max_nesting_depth,
+                                "List nesting exceeds the maximum depth; flattening deeper items"
+                            );
+                            flattened.extend(self.collect_flattened_items(events, index, nested_ordered)?);
+                        }
+                    }
+                    *index += 1; // Skip item end
+                    items.push(item);
+                    items.extend(flattened);
                 },
                 _ => {
                     *index += 1; // Skip other events
@@ -398,11 +800,95 @@ impl MarkdownParser {
         Ok(items)
     }
 
-    /// Collect table content until end of table
-    fn collect_table_content(&self, events: &[Event], index: &mut usize) -> Result<(Vec<String>, Vec<Vec<String>>), ConversionError> {
+    /// Collect an item's content and block children up to its end or a
+    /// nested list, neither of which is consumed
+    ///
+    /// The first paragraph of a loose item becomes its content; later
+    /// paragraphs and code blocks are kept as blocks.
+    fn collect_item_body(&self, events: &[Event], index: &mut usize, item: &mut ListItem) -> Result<(), ConversionError> {
+        while let Some(event) = events.get(*index) {
+            match event {
+                Event::End(Tag::Item) | Event::Start(Tag::List(_)) => break,
+                Event::TaskListMarker(checked) => {
+                    item.checked = Some(*checked);
+                    *index += 1;
+                },
+                Event::Start(Tag::Paragraph) => {
+                    *index += 1;
+                    // In loose lists the task marker opens the first paragraph
+                    if let Some(Event::TaskListMarker(checked)) = events.get(*index) {
+                        item.checked = Some(*checked);
+                        *index += 1;
+                    }
+                    let content = self.collect_inline_until_end(events, index, "Paragraph")?;
+                    *index += 1; // Skip paragraph end
+                    if item.content.is_empty() && item.blocks.is_empty() && item.sub_items.is_empty() {
+                        item.content = content;
+                    } else {
+                        item.blocks.push(ListItemBlock::Paragraph(content));
+                    }
+                },
+                Event::Start(Tag::CodeBlock(kind)) => {
+                    let (language, attributes) = match kind {
+                        CodeBlockKind::Fenced(info) => fence_info::parse(info),
+                        CodeBlockKind::Indented => (None, CodeAttributes::default()),
+                    };
+                    *index += 1;
+                    let code = self.collect_text_until_end(events, index, "CodeBlock")?;
+                    item.blocks.push(ListItemBlock::CodeBlock {
+                        language,
+                        code: code.trim_end_matches('\n').to_string(),
+                        attributes,
+                    });
+                },
+                _ => {
+                    let content = self.collect_inline_until_end(events, index, "Item")?;
+                    item.content.extend(content);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect every item of a nested list (and the lists inside it) as one
+    /// flat sequence, without recursing; each item keeps its own list's kind
+    fn collect_flattened_items(&self, events: &[Event], index: &mut usize, ordered: bool) -> Result<Vec<ListItem>, ConversionError> {
+        let mut items = Vec::new();
+        let mut open_lists = vec![ordered];
+
+        while *index < events.len() && !open_lists.is_empty() {
+            match &events[*index] {
+                Event::Start(Tag::List(first)) => {
+                    open_lists.push(first.is_some());
+                    *index += 1;
+                },
+                Event::End(Tag::List(_)) => {
+                    open_lists.pop();
+                    *index += 1;
+                },
+                Event::Start(Tag::Item) => {
+                    *index += 1;
+                    let mut item = ListItem::new(Vec::new());
+                    item.ordered = open_lists.last().copied().unwrap_or(ordered);
+                    self.collect_item_body(events, index, &mut item)?;
+                    items.push(item);
+                },
+                _ => {
+                    *index += 1;
+                }
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Collect table content until end of table, along with the links in its cells
+    fn collect_table_content(&self, events: &[Event], index: &mut usize) -> Result<TableContent, ConversionError> {
         let mut headers = Vec::new();
         let mut rows = Vec::new();
         let mut current_row = Vec::new();
+        let mut links = Vec::new();
+        let mut in_head = false;
         
         while *index < events.len() {
             match &events[*index] {
@@ -411,10 +897,12 @@ impl MarkdownParser {
                     break;
                 },
                 Event::Start(Tag::TableHead) => {
+                    in_head = true;
                     *index += 1;
                 },
                 Event::End(Tag::TableHead) => {
                     headers = current_row.clone();
+                    in_head = false;
                     *index += 1;
                 },
                 Event::Start(Tag::TableRow) => {
@@ -427,7 +915,23 @@ impl MarkdownParser {
                 },
                 Event::Start(Tag::TableCell) => {
                     *index += 1;
+                    let start = *index;
                     let cell_text = self.collect_text_until_end(events, index, "TableCell")?;
+                    for (offset, event) in events[start..*index].iter().enumerate() {
+                        if let Event::Start(Tag::Link(_, dest_url, _)) = event {
+                            // The cell text up to the link gives its offset
+                            let mut before_index = 0;
+                            let before = self.collect_text_until_end(&events[start..start + offset], &mut before_index, "TableCell")?;
+                            let mut link_index = start + offset + 1;
+                            links.push(TableLink {
+                                row: if in_head { None } else { Some(rows.len()) },
+                                column: current_row.len(),
+                                offset: before.len(),
+                                text: self.collect_text_until_end(events, &mut link_index, "Link")?,
+                                url: dest_url.to_string(),
+                            });
+                        }
+                    }
                     current_row.push(cell_text);
                 },
                 _ => {
@@ -436,7 +940,7 @@ impl MarkdownParser {
             }
         }
         
-        Ok((headers, rows))
+        Ok((headers, rows, links))
     }
 
     /// Check if a tag matches the expected name
@@ -456,6 +960,7 @@ impl MarkdownParser {
             (Tag::TableHead, "TableHead") => true,
             (Tag::TableRow, "TableRow") => true,
             (Tag::TableCell, "TableCell") => true,
+            (Tag::FootnoteDefinition(_), "FootnoteDefinition") => true,
             _ => false,
         }
     }
@@ -549,6 +1054,87 @@ impl MarkdownParser {
         }
     }
 
+    /// Parse a class attribute such as `{.callout}` or `{.note .wide}`
+    fn parse_class_attribute(attribute: &str) -> Option<Vec<String>> {
+        let inner = attribute.strip_prefix('{')?.strip_suffix('}')?;
+        let classes: Vec<String> = inner
+            .split_whitespace()
+            .map(|token| token.strip_prefix('.').filter(|name| Self::is_class_name(name)).map(str::to_string))
+            .collect::<Option<_>>()?;
+        if classes.is_empty() {
+            None
+        } else {
+            Some(classes)
+        }
+    }
+
+    fn is_class_name(name: &str) -> bool {
+        !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    }
+
+    /// Remove a trailing `{.class}` attribute from paragraph content
+    fn take_trailing_classes(content: &mut Vec<InlineElement>) -> Vec<String> {
+        let Some(InlineElement::Text(text)) = content.last_mut() else {
+            return Vec::new();
+        };
+        let trimmed = text.trim_end();
+        let Some(open) = trimmed.rfind('{') else {
+            return Vec::new();
+        };
+        let Some(classes) = Self::parse_class_attribute(&trimmed[open..]) else {
+            return Vec::new();
+        };
+
+        let remaining = trimmed[..open].trim_end().to_string();
+        if remaining.is_empty() {
+            // A paragraph holding only the attribute keeps it as text
+            if content.len() == 1 {
+                return Vec::new();
+            }
+            content.pop();
+        } else {
+            *text = remaining;
+        }
+        classes
+    }
+
+    /// Split `[text]{.class}` spans out of a text run
+    fn split_class_spans(text: &str) -> Vec<InlineElement> {
+        let mut elements = Vec::new();
+        let mut rest = text;
+        let mut plain = String::new();
+
+        while let Some(open) = rest.find('[') {
+            let candidate = &rest[open..];
+            let span = candidate.find("]{").and_then(|close| {
+                let end = candidate[close..].find('}')? + close;
+                let classes = Self::parse_class_attribute(&candidate[close + 1..=end])?;
+                let inner = &candidate[1..close];
+                (!inner.is_empty() && !inner.contains('[')).then(|| (inner.to_string(), classes, end + 1))
+            });
+
+            match span {
+                Some((span_text, classes, consumed)) => {
+                    plain.push_str(&rest[..open]);
+                    if !plain.is_empty() {
+                        elements.push(InlineElement::Text(std::mem::take(&mut plain)));
+                    }
+                    elements.push(InlineElement::Span { text: span_text, classes, lang: None });
+                    rest = &candidate[consumed..];
+                }
+                None => {
+                    plain.push_str(&rest[..=open]);
+                    rest = &rest[open + 1..];
+                }
+            }
+        }
+        plain.push_str(rest);
+        if !plain.is_empty() {
+            elements.push(InlineElement::Text(plain));
+        }
+        elements
+    }
+
     /// Normalize whitespace by replacing multiple consecutive spaces with a single space
     fn normalize_whitespace(&self, text: &str) -> String {
         // Use regex to replace multiple consecutive whitespace characters with a single space
@@ -571,6 +1157,50 @@ impl MarkdownParser {
     }
 
     /// Merge consecutive text elements and normalize whitespace
+    /// Add an inline comment or revision note, anchoring it to the text
+    /// before the space that separates them
+    fn push_inline_comment(elements: &mut Vec<InlineElement>, comment: InlineElement) {
+        let at = match elements.last() {
+            Some(InlineElement::Text(text)) if text.trim().is_empty() => elements.len() - 1,
+            _ => elements.len(),
+        };
+        elements.insert(at, comment);
+    }
+
+    /// Anchor a comment written on its own line to the last run of the block before it
+    fn attach_block_comment(document: &mut MarkdownDocument, comment: String) {
+        match Self::last_block_content(document) {
+            Some(content) => content.push(InlineElement::Comment(comment)),
+            None => tracing::warn!("Comment directive has no preceding paragraph or list to anchor to; dropping it"),
+        }
+    }
+
+    /// Anchor a revision note written on its own line to the last run of the block before it
+    fn attach_block_revision_note(document: &mut MarkdownDocument, note: String) {
+        match Self::last_block_content(document) {
+            Some(content) => content.push(InlineElement::RevisionNote(note)),
+            None => tracing::warn!("Revision note has no preceding paragraph or list to anchor to; dropping it"),
+        }
+    }
+
+    /// Mark an index term written on its own line at the end of the block before it
+    fn attach_block_index_term(document: &mut MarkdownDocument, term: String) {
+        match Self::last_block_content(document) {
+            Some(content) => content.push(InlineElement::IndexTerm(term)),
+            None => tracing::warn!(term = %term, "Index directive has no preceding paragraph or list to mark; dropping it"),
+        }
+    }
+
+    /// Inline content of the last block, or of the last list item when it is a list
+    fn last_block_content(document: &mut MarkdownDocument) -> Option<&mut Vec<InlineElement>> {
+        match document.elements.last_mut()? {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => Some(content),
+            MarkdownElement::List { items, .. } => Some(&mut last_list_item(items)?.content),
+            _ => None,
+        }
+    }
+
     fn merge_and_normalize_text_elements(&self, elements: Vec<InlineElement>) -> Vec<InlineElement> {
         let mut result = Vec::new();
         let mut current_text = String::new();
@@ -625,6 +1255,154 @@ fn heading_level_to_u8(level: HeadingLevel) -> u8 {
     }
 }
 
+/// Whether the source of a heading is underlined (setext) rather than `#`-prefixed (ATX)
+fn is_setext_heading(source: &str) -> bool {
+    !source.trim_start().starts_with('#')
+}
+
+/// The innermost last item of a list
+fn last_list_item(items: &mut [ListItem]) -> Option<&mut ListItem> {
+    let item = items.last_mut()?;
+    if item.sub_items.is_empty() {
+        Some(item)
+    } else {
+        last_list_item(&mut item.sub_items)
+    }
+}
+
+/// Bodies of the `<!-- keyword ... -->` directives in an HTML fragment, in
+/// order; other HTML comments are ignored
+fn html_directives<'a>(html: &'a str, keyword: &str) -> Vec<&'a str> {
+    let mut directives = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find("<!--") {
+        let body = &rest[start + 4..];
+        let Some(end) = body.find("-->") else {
+            break;
+        };
+        if let Some(text) = body[..end].trim().strip_prefix(keyword) {
+            directives.push(text.trim());
+        }
+        rest = &body[end + 3..];
+    }
+    directives
+}
+
+/// Texts of the `<!-- comment: ... -->` review directives in an HTML fragment
+fn comment_directives(html: &str) -> Vec<String> {
+    html_directives(html, "comment:")
+        .into_iter()
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// Texts of the `<!-- rev: ... -->` revision notes in an HTML fragment
+fn revision_directives(html: &str) -> Vec<String> {
+    html_directives(html, "rev:")
+        .into_iter()
+        .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// Split the source of a blockquote paragraph at its attribution line, the
+/// last line when it starts with an em dash
+///
+/// Returns the lines before the attribution, if any, with their quote
+/// markers removed, and the attribution line.
+fn split_attribution(source: &str) -> Option<(Option<String>, &str)> {
+    let mut lines: Vec<&str> = source
+        .lines()
+        .map(|line| line.trim_start_matches(|c: char| c == '>' || c.is_whitespace()))
+        .collect();
+    let attribution = lines.pop().filter(|line| line.starts_with('\u{2014}'))?;
+    let body = (!lines.is_empty()).then(|| lines.join("\n"));
+    Some((body, attribution))
+}
+
+/// Terms of the `<!-- index: term -->` directives in an HTML fragment
+fn index_directives(html: &str) -> Vec<String> {
+    html_directives(html, "index:")
+        .into_iter()
+        .map(|term| term.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|term| !term.is_empty())
+        .collect()
+}
+
+/// Orientations of the `<!-- section: landscape -->` and
+/// `<!-- section: portrait -->` directives in an HTML fragment
+fn section_directives(html: &str) -> Vec<PageOrientation> {
+    html_directives(html, "section:")
+        .into_iter()
+        .filter_map(|value| match value.to_ascii_lowercase().as_str() {
+            "landscape" => Some(PageOrientation::Landscape),
+            "portrait" => Some(PageOrientation::Portrait),
+            _ => {
+                tracing::warn!("Unknown section orientation '{}'; expected landscape or portrait", value);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Margins set by `<!-- margins: 1.5cm -->` (every side) or
+/// `<!-- margins: top=1cm left=2cm -->` directives in an HTML fragment
+///
+/// Values are lengths as in the configuration. Unknown sides and invalid or
+/// negative lengths are skipped with a warning.
+fn margin_directives(html: &str) -> Vec<MarginOverrides> {
+    html_directives(html, "margins:")
+        .into_iter()
+        .map(|value| {
+            let mut margins = MarginOverrides::default();
+            for entry in value.split(|c: char| c == ',' || c.is_whitespace()).filter(|e| !e.is_empty()) {
+                let (side, length) = entry.split_once('=').unwrap_or(("all", entry));
+                let length = match parse_length(length) {
+                    Ok(points) if points >= 0.0 => points,
+                    Ok(_) => {
+                        tracing::warn!("Ignoring negative margin '{}'", entry);
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Ignoring margin '{}': {}", entry, e);
+                        continue;
+                    }
+                };
+                match side.trim().to_ascii_lowercase().as_str() {
+                    "all" => {
+                        margins = MarginOverrides {
+                            top: Some(length),
+                            bottom: Some(length),
+                            left: Some(length),
+                            right: Some(length),
+                        }
+                    }
+                    "top" => margins.top = Some(length),
+                    "bottom" => margins.bottom = Some(length),
+                    "left" => margins.left = Some(length),
+                    "right" => margins.right = Some(length),
+                    other => tracing::warn!(
+                        "Unknown margin side '{}'; expected top, bottom, left or right",
+                        other
+                    ),
+                }
+            }
+            margins
+        })
+        .filter(|margins| !margins.is_empty())
+        .collect()
+}
+
+/// Whether an inline HTML fragment is a `<br>`, `<br/>` or `<br />` tag
+fn is_line_break_tag(html: &str) -> bool {
+    let tag = html.trim();
+    let Some(inner) = tag.strip_prefix('<').and_then(|t| t.strip_suffix('>')) else {
+        return false;
+    };
+    inner.trim_end_matches('/').trim().eq_ignore_ascii_case("br")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -636,7 +1414,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Heading { level, text } => {
+            MarkdownElement::Heading { level, text, .. } => {
                 assert_eq!(*level, 1);
                 assert_eq!(text, "Hello World");
             },
@@ -651,7 +1429,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 assert_eq!(content.len(), 1);
                 match &content[0] {
                     InlineElement::Text(text) => assert_eq!(text, "This is a simple paragraph."),
@@ -670,7 +1448,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::CodeBlock { language, code, processed } => {
+            MarkdownElement::CodeBlock { language, code, processed, .. } => {
                 assert_eq!(language.as_ref().unwrap(), "rust");
                 assert!(code.contains("fn main()"));
                 assert!(code.contains("println!"));
@@ -703,7 +1481,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 assert_eq!(content.len(), 5);
                 
                 match &content[0] {
@@ -742,7 +1520,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 assert_eq!(content.len(), 3);
                 
                 match &content[1] {
@@ -761,7 +1539,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 assert_eq!(content.len(), 3);
                 
                 match &content[0] {
@@ -794,7 +1572,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 match &content[1] {
                     InlineElement::Link { text, url, title } => {
                         assert_eq!(text, "Google");
@@ -981,7 +1759,7 @@ mod tests {
         println!("Parsed elements: {:?}", result.elements);
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Table { headers, rows } => {
+            MarkdownElement::Table { headers, rows, .. } => {
                 assert_eq!(headers.len(), 2);
                 assert_eq!(headers[0], "Name");
                 assert_eq!(headers[1], "Age");
@@ -996,6 +1774,100 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_setext_and_atx_headings_next_to_rules() {
+        let parser = MarkdownParser::new();
+        let markdown = "# ATX One\nSetext Two\n----------\n---\n\nSetext One\n===\n\nNot a heading\n\n---\n## ATX Two";
+        let result = parser.parse(markdown).unwrap();
+        let details = result.details_lookup();
+
+        let summary: Vec<String> = result
+            .elements
+            .iter()
+            .map(|element| match element {
+                MarkdownElement::Heading { level, text } => {
+                    let syntax = if details.setext(element) { "setext" } else { "atx" };
+                    format!("{} H{} {}", syntax, level, text)
+                }
+                MarkdownElement::HorizontalRule => "rule".to_string(),
+                MarkdownElement::Paragraph { .. } => "paragraph".to_string(),
+                other => panic!("unexpected element {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "atx H1 ATX One",
+                "setext H2 Setext Two",
+                "rule",
+                "setext H1 Setext One",
+                "paragraph",
+                "rule",
+                "atx H2 ATX Two",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_cell_links_recorded() {
+        let parser = MarkdownParser::new();
+        let markdown = "| [Name](#name) | Site |\n|------|------|\n| Alice | see [home](https://a.example/) |\n";
+        let result = parser.parse(markdown).unwrap();
+
+        match &result.elements[0] {
+            MarkdownElement::Table { rows, links, .. } => {
+                assert_eq!(rows[0][1], "see home");
+                assert_eq!(
+                    links,
+                    &vec![
+                        TableLink { row: None, column: 0, offset: 0, text: "Name".to_string(), url: "#name".to_string() },
+                        TableLink { row: Some(0), column: 1, offset: 4, text: "home".to_string(), url: "https://a.example/".to_string() },
+                    ]
+                );
+            },
+            _ => panic!("Expected table element"),
+        }
+    }
+
+    #[test]
+    fn test_table_cell_br_becomes_line_break() {
+        let parser = MarkdownParser::new();
+        let markdown = "| Name | Notes |\n|------|-------|\n| Alice | first<br>second |\n| Bob | a<BR />b<span>c</span> |";
+        let result = parser.parse(markdown).unwrap();
+
+        match &result.elements[0] {
+            MarkdownElement::Table { rows, .. } => {
+                assert_eq!(rows[0][1], "first\nsecond");
+                assert_eq!(rows[1][1], "a\nbc");
+            },
+            _ => panic!("Expected table element"),
+        }
+    }
+
+    #[test]
+    fn test_comment_directives_anchor_to_preceding_text() {
+        let parser = MarkdownParser::new();
+        let markdown = "Ship **today** <!-- comment: Confirm the date --> please.\n<!-- comment: Whole paragraph -->\n\n- one\n  - two\n\n<!-- comment: Last item -->\n\n<!-- plain note -->";
+        let result = parser.parse(markdown).unwrap();
+
+        assert_eq!(result.elements.len(), 2);
+        match &result.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert_eq!(content[1], InlineElement::Bold("today".to_string()));
+                assert!(matches!(&content[2], InlineElement::Comment(c) if c == "Confirm the date"));
+                assert!(matches!(content.last(), Some(InlineElement::Comment(c)) if c == "Whole paragraph"));
+            },
+            other => panic!("Expected paragraph, got {:?}", other),
+        }
+        match &result.elements[1] {
+            MarkdownElement::List { items, .. } => {
+                let content = &items[0].sub_items[0].content;
+                assert!(matches!(content.last(), Some(InlineElement::Comment(c)) if c == "Last item"));
+            },
+            other => panic!("Expected list, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_debug_complex_paragraph_events() {
         use pulldown_cmark::{Parser, Event};
@@ -1021,7 +1893,7 @@ mod tests {
         
         // Check paragraph with link and image
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 println!("Paragraph content: {:?}", content);
                 // Should contain: "Check out ", link, " and ", image placeholder
                 assert!(content.len() >= 3);
@@ -1129,7 +2001,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 assert_eq!(content.len(), 1);
                 match &content[0] {
                     InlineElement::Text(text) => {
@@ -1149,7 +2021,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 // The parser should now properly merge and normalize text elements
                 assert_eq!(content.len(), 1);
                 match &content[0] {
@@ -1216,7 +2088,7 @@ mod tests {
         
         assert_eq!(result.elements.len(), 1);
         match &result.elements[0] {
-            MarkdownElement::Paragraph { content } => {
+            MarkdownElement::Paragraph { content, .. } => {
                 // Check that spaces are normalized in text elements
                 let first_text = content.iter().find_map(|element| {
                     match element {
@@ -1264,7 +2136,7 @@ mod tests {
                 // Verify the structure
                 assert_eq!(doc.elements.len(), 1);
                 match &doc.elements[0] {
-                    MarkdownElement::Paragraph { content } => {
+                    MarkdownElement::Paragraph { content, .. } => {
                         println!("\nParagraph content:");
                         for (i, inline) in content.iter().enumerate() {
                             println!("  {}: {:?}", i, inline);
@@ -1332,7 +2204,7 @@ mod tests {
                     println!("✓ Parsed successfully");
                     assert_eq!(doc.elements.len(), 1);
                     match &doc.elements[0] {
-                        MarkdownElement::Paragraph { content } => {
+                        MarkdownElement::Paragraph { content, .. } => {
                             println!("  Content elements: {}", content.len());
                             for (i, elem) in content.iter().enumerate() {
                                 println!("    {}: {:?}", i, elem);
@@ -1347,4 +2219,498 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_paragraph_class_attribute() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("Read this first. {.callout .wide}").unwrap();
+        let details = doc.details_lookup();
+
+        match &doc.elements[0] {
+            element @ MarkdownElement::Paragraph { content } => {
+                assert_eq!(details.classes(element), ["callout".to_string(), "wide".to_string()]);
+                assert_eq!(content.len(), 1);
+                assert!(matches!(&content[0], InlineElement::Text(t) if t == "Read this first."));
+            }
+            _ => panic!("Expected paragraph"),
+        }
+
+        // Braces that are not a class attribute stay in the text
+        let doc = parser.parse("Set {width} here").unwrap();
+        assert!(matches!(&doc.elements[0], MarkdownElement::Paragraph { .. }));
+        assert!(doc.details.paragraph_classes.is_empty());
+    }
+
+    #[test]
+    fn test_span_class_attribute() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("Status: [done]{.badge} today, [not a span] {.x} here").unwrap();
+
+        assert!(doc.details.paragraph_classes.is_empty());
+        match &doc.elements[0] {
+            MarkdownElement::Paragraph { content } => {
+                assert!(matches!(&content[0], InlineElement::Text(t) if t == "Status: "));
+                match &content[1] {
+                    InlineElement::Span { text, classes, lang } => {
+                        assert_eq!(text, "done");
+                        assert_eq!(lang, &None);
+                        assert_eq!(classes, &vec!["badge".to_string()]);
+                    }
+                    other => panic!("Expected span, got {:?}", other),
+                }
+                assert!(matches!(&content[2], InlineElement::Text(t) if t.starts_with(" today")));
+            }
+            _ => panic!("Expected paragraph"),
+        }
+    }
+
+    #[test]
+    fn test_footnote_reference_and_definition() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("Claim[^1] here.\n\n[^1]: The *cited* source.\n").unwrap();
+
+        match &doc.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert!(content.iter().any(|e| matches!(e, InlineElement::FootnoteReference(l) if l == "1")));
+            }
+            other => panic!("Expected paragraph, got {:?}", other),
+        }
+        match &doc.elements[1] {
+            MarkdownElement::FootnoteDefinition { label, content } => {
+                assert_eq!(label, "1");
+                let text: String = content.iter().map(|e| e.extract_text()).collect();
+                assert_eq!(text, "The cited source.");
+            }
+            other => panic!("Expected footnote definition, got {:?}", other),
+        }
+        assert_eq!(doc.elements.len(), 2);
+    }
+
+    fn list_depth(items: &[ListItem]) -> usize {
+        items.iter().map(|item| 1 + list_depth(&item.sub_items)).max().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_nested_list_items() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("- one\n  - one.a\n  - one.b\n- two\n").unwrap();
+
+        match &doc.elements[0] {
+            MarkdownElement::List { items, .. } => {
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].content, vec![InlineElement::Text("one".to_string())]);
+                assert_eq!(items[0].sub_items.len(), 2);
+                assert_eq!(items[0].sub_items[1].content, vec![InlineElement::Text("one.b".to_string())]);
+                assert_eq!(items[1].content, vec![InlineElement::Text("two".to_string())]);
+            }
+            other => panic!("Expected list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_nested_list_kinds() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("1. one\n   - one.a\n   1. one.b\n2. two\n").unwrap();
+
+        match &doc.elements[0] {
+            MarkdownElement::List { ordered: true, items } => {
+                assert!(items.iter().all(|item| item.ordered));
+                let kinds: Vec<bool> = items[0].sub_items.iter().map(|item| item.ordered).collect();
+                assert_eq!(kinds, vec![false, true]);
+            }
+            other => panic!("Expected ordered list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pathological_list_nesting_is_clamped() {
+        let levels = 300;
+        let mut markdown = String::new();
+        for level in 0..levels {
+            markdown.push_str(&"  ".repeat(level));
+            markdown.push_str(&format!("- item {}\n", level));
+        }
+
+        let mut parser = MarkdownParser::new();
+        parser.set_max_nesting_depth(3);
+        let doc = parser.parse(&markdown).unwrap();
+
+        match &doc.elements[0] {
+            MarkdownElement::List { items, .. } => {
+                assert_eq!(list_depth(items), 3);
+                // Nothing is dropped: the deeper items follow on the last level
+                let text = doc.elements[0].extract_text();
+                for level in 0..levels {
+                    assert!(text.contains(&format!("item {}", level)), "missing item {}", level);
+                }
+            }
+            other => panic!("Expected list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_html_table_with_nested_table() {
+        let parser = MarkdownParser::new();
+        let markdown = "Intro\n\n<table>\n<tr><th>Part</th><th>Specs</th></tr>\n<tr><td>Board</td><td>\n<table><tr><td>cpu</td><td>4</td></tr></table>\n</td></tr>\n</table>\n\nAfter\n";
+        let doc = parser.parse(markdown).unwrap();
+
+        assert_eq!(doc.elements.len(), 3);
+        match &doc.elements[1] {
+            MarkdownElement::HtmlTable(table) => {
+                assert_eq!(table.rows.len(), 2);
+                assert_eq!(table.depth(), 2);
+                assert_eq!(table.rows[1][1].tables[0].rows[0][0].text, "cpu");
+            }
+            other => panic!("Expected HTML table, got {:?}", other),
+        }
+        assert!(matches!(&doc.elements[2], MarkdownElement::Paragraph { .. }));
+    }
+
+    #[test]
+    fn test_html_without_table_is_skipped() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("<div>\nnot a table\n</div>\n").unwrap();
+        assert!(doc.elements.is_empty());
+    }
+
+    #[test]
+    fn test_commonmark_profile_keeps_extensions_literal() {
+        let markdown = "~~old~~ ![logo](logo.png?width=20) [note]{.aside}\n";
+
+        let gfm = MarkdownParser::new().parse(markdown).unwrap();
+        match &gfm.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert!(content.contains(&InlineElement::Strikethrough("old".to_string())));
+                assert!(content.iter().any(|e| matches!(e, InlineElement::Link { url, .. } if url == "logo.png")));
+                assert!(content.iter().any(|e| matches!(e, InlineElement::Span { .. })));
+            }
+            other => panic!("Expected paragraph, got {:?}", other),
+        }
+
+        let mut parser = MarkdownParser::new();
+        parser.set_profile(ParsingProfile::Commonmark);
+        let strict = parser.parse(markdown).unwrap();
+        match &strict.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert!(!content.iter().any(|e| matches!(e, InlineElement::Strikethrough(_) | InlineElement::Span { .. })));
+                assert!(content.iter().any(|e| matches!(e, InlineElement::Link { url, .. } if url == "logo.png?width=20")));
+                let text: String = content.iter().map(|e| e.extract_text()).collect();
+                assert!(text.contains("~~old~~") && text.contains("[note]{.aside}"));
+            }
+            other => panic!("Expected paragraph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_item_with_two_paragraphs() {
+        let parser = MarkdownParser::new();
+        let doc = parser.parse("- First paragraph\n\n  Second paragraph\n- Next item\n").unwrap();
+
+        match &doc.elements[0] {
+            MarkdownElement::List { items, .. } => {
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].content, vec![InlineElement::Text("First paragraph".to_string())]);
+                assert_eq!(
+                    items[0].blocks,
+                    vec![ListItemBlock::Paragraph(vec![InlineElement::Text("Second paragraph".to_string())])]
+                );
+                assert_eq!(items[1].content, vec![InlineElement::Text("Next item".to_string())]);
+                assert!(items[1].blocks.is_empty());
+            }
+            other => panic!("Expected list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_item_with_code_block_and_sub_list() {
+        let parser = MarkdownParser::new();
+        let markdown = "1. Run it:\n\n   ```bash\n   cargo run\n   ```\n\n   - nested\n2. Done\n";
+        let doc = parser.parse(markdown).unwrap();
+
+        match &doc.elements[0] {
+            MarkdownElement::List { items, .. } => {
+                assert_eq!(items.len(), 2);
+                assert_eq!(items[0].content, vec![InlineElement::Text("Run it:".to_string())]);
+                assert_eq!(
+                    items[0].blocks,
+                    vec![ListItemBlock::CodeBlock {
+                        language: Some("bash".to_string()),
+                        code: "cargo run".to_string(),
+                        attributes: Default::default(),
+                    }]
+                );
+                assert_eq!(items[0].sub_items.len(), 1);
+                assert_eq!(items[1].content, vec![InlineElement::Text("Done".to_string())]);
+            }
+            other => panic!("Expected list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_section_directives() {
+        let parser = MarkdownParser::new();
+        let doc = parser
+            .parse("Intro\n\n<!-- section: landscape -->\n\n| a |\n|---|\n| 1 |\n\n<!-- section: Portrait -->\n<!-- section: sideways -->\n\nAfter\n")
+            .unwrap();
+
+        let types: Vec<&str> = doc.elements.iter().map(|e| e.element_type()).collect();
+        assert_eq!(types, ["paragraph", "section_break", "table", "section_break", "paragraph"]);
+        assert!(matches!(doc.elements[1], MarkdownElement::SectionBreak { orientation: PageOrientation::Landscape }));
+        assert!(matches!(doc.elements[3], MarkdownElement::SectionBreak { orientation: PageOrientation::Portrait }));
+    }
+
+    #[test]
+    fn test_margin_directives() {
+        let parser = MarkdownParser::new();
+        let doc = parser
+            .parse("<!-- margins: 1in -->\n\nText\n\n<!-- margins: left=36pt, right=huge gutter=1cm top=-2cm -->\n")
+            .unwrap();
+
+        assert_eq!(doc.elements.len(), 1);
+        assert_eq!(
+            doc.margins,
+            MarginOverrides { top: Some(72.0), bottom: Some(72.0), left: Some(36.0), right: Some(72.0) }
+        );
+        assert!(parser.parse("<!-- margins: wide -->\n").unwrap().margins.is_empty());
+    }
+
+    #[test]
+    fn test_html_img_block_and_inline() {
+        let parser = MarkdownParser::new();
+        let doc = parser
+            .parse("<img src=\"a.png\" alt=\"A\" width=\"120\" height=\"80\">\n\nSee <img src=\"b.png\" alt=\"B\"> here\n")
+            .unwrap();
+
+        assert_eq!(doc.elements.len(), 2);
+        match &doc.elements[0] {
+            MarkdownElement::Image { alt_text, url, width, height, .. } => {
+                assert_eq!((alt_text.as_str(), url.as_str()), ("A", "a.png"));
+                assert_eq!((*width, *height), (Some(120), Some(80)));
+            }
+            other => panic!("Expected image, got {:?}", other),
+        }
+        match &doc.elements[1] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert!(content.iter().any(|e| matches!(e, InlineElement::Link { text, url, .. } if text == "[Image: B]" && url == "b.png")));
+            }
+            other => panic!("Expected paragraph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_details_block_with_markdown_body() {
+        let markdown = "Before\n\n<details>\n<summary>Show the <b>output</b></summary>\n\n\
+                        First **body** paragraph.\n\n- one\n- two\n\n```sh\nls\n```\n\n</details>\n\nAfter\n";
+        let doc = MarkdownParser::new().parse(markdown).unwrap();
+
+        assert_eq!(doc.elements.len(), 3);
+        match &doc.elements[1] {
+            MarkdownElement::Details { summary, body } => {
+                assert_eq!(summary, "Show the output");
+                let kinds: Vec<&str> = body.iter().map(MarkdownElement::element_type).collect();
+                assert_eq!(kinds, ["paragraph", "list", "code_block"]);
+            }
+            other => panic!("expected details, got {:?}", other),
+        }
+        assert!(matches!(&doc.elements[2], MarkdownElement::Paragraph { .. }));
+
+        // Inline body text and a missing close tag
+        let doc = MarkdownParser::new()
+            .parse("<details><summary>Why?</summary>Because.</details>\n\n<details>\n<summary>Open</summary>\n\nTail\n")
+            .unwrap();
+        assert_eq!(doc.elements.len(), 2);
+        assert!(matches!(&doc.elements[0], MarkdownElement::Details { summary, body }
+            if summary == "Why?" && body.len() == 1));
+        assert!(matches!(&doc.elements[1], MarkdownElement::Details { summary, body }
+            if summary == "Open" && body.len() == 1));
+    }
+
+    #[test]
+    fn test_unicode_bullet_paragraph_becomes_list() {
+        let markdown = "• First **point**\n• Second point\n▪ Third\n\nPrices • Terms • Contact\n";
+        let mut parser = MarkdownParser::new();
+
+        let doc = parser.parse(markdown).unwrap();
+        assert!(doc.elements.iter().all(|e| matches!(e, MarkdownElement::Paragraph { .. })), "off by default");
+
+        parser.set_unicode_bullets(true);
+        let doc = parser.parse(markdown).unwrap();
+        assert_eq!(doc.elements.len(), 2);
+        match &doc.elements[0] {
+            MarkdownElement::List { ordered: false, items } => {
+                assert_eq!(items.len(), 3);
+                assert_eq!(
+                    items[0].content,
+                    vec![InlineElement::Text("First ".to_string()), InlineElement::Bold("point".to_string())]
+                );
+                assert_eq!(items[2].extract_text(), "Third");
+            }
+            other => panic!("expected list, got {:?}", other),
+        }
+        // A bullet mid-sentence stays text
+        match &doc.elements[1] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert_eq!(content, &vec![InlineElement::Text("Prices • Terms • Contact".to_string())]);
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_kbd_and_samp_become_classed_spans() {
+        let parser = MarkdownParser::new();
+        let document = parser.parse("Hit <kbd>Ctrl</kbd>+<kbd>C</kbd>, see **<samp>done</samp>**, <kbd>open\n").unwrap();
+        match &document.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                let span = |text: &str, class: &str| InlineElement::Span {
+                    text: text.to_string(),
+                    classes: vec![class.to_string()],
+                    lang: None,
+                };
+                assert_eq!(content[1], span("Ctrl", "kbd"));
+                assert_eq!(content[2], InlineElement::Text("+".to_string()));
+                assert_eq!(content[3], span("C", "kbd"));
+                assert!(content.contains(&span("open", "kbd")));
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_index_directives_mark_terms() {
+        let parser = MarkdownParser::new();
+        let document = parser.parse("Ownership <!-- index:   ownership\n  rules -->matters.\n\n<!-- index: borrowing -->\n").unwrap();
+        match &document.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert_eq!(content[1], InlineElement::IndexTerm("ownership rules".to_string()));
+                assert_eq!(content.last(), Some(&InlineElement::IndexTerm("borrowing".to_string())));
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_revision_directives_become_notes() {
+        let parser = MarkdownParser::new();
+        let document = parser.parse("Prices <!-- rev:  in\n  euros -->rise.\n\n<!-- rev: reviewed -->\n").unwrap();
+        match &document.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert_eq!(content[1], InlineElement::RevisionNote("in euros".to_string()));
+                assert_eq!(content.last(), Some(&InlineElement::RevisionNote("reviewed".to_string())));
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_quote_attribution_split_only_when_enabled() {
+        let markdown = "> Stay hungry.\n> \u{2014} Whole Earth Catalog\n";
+        let quote_body = |document: &MarkdownDocument| match document.elements.as_slice() {
+            [MarkdownElement::BlockQuote { content: body }] => body.clone(),
+            other => panic!("expected one blockquote, got {:?}", other),
+        };
+        let mut parser = MarkdownParser::new();
+        assert_eq!(quote_body(&parser.parse(markdown).unwrap()).len(), 1);
+
+        parser.set_quote_attribution(true);
+        let document = parser.parse(markdown).unwrap();
+        let body = quote_body(&document);
+        assert_eq!(body.len(), 2);
+        match &body[1] {
+            MarkdownElement::Paragraph { content } => {
+                assert_eq!(content, &vec![InlineElement::Text("\u{2014} Whole Earth Catalog".to_string())]);
+            }
+            other => panic!("expected paragraph, got {:?}", other),
+        }
+        let attribution = std::collections::BTreeMap::from([(1, vec![ATTRIBUTION_CLASS.to_string()])]);
+        assert_eq!(document.details.paragraph_classes, attribution);
+    }
+
+    #[test]
+    fn test_nested_blockquotes() {
+        fn depths(elements: &[MarkdownElement], depth: usize, found: &mut Vec<(usize, String)>) {
+            for element in elements {
+                match element {
+                    MarkdownElement::BlockQuote { content: body } => depths(body, depth + 1, found),
+                    other => found.push((depth, other.extract_text())),
+                }
+            }
+        }
+        let markdown = "> One
+>
+> > Two
+> >
+> > > Three
+>
+> One again
+
+After
+";
+
+        let mut found = Vec::new();
+        depths(&MarkdownParser::new().parse(markdown).unwrap().elements, 0, &mut found);
+        assert_eq!(
+            found,
+            [(1, "One"), (2, "Two"), (3, "Three"), (1, "One again"), (0, "After")]
+                .map(|(depth, text)| (depth, text.to_string()))
+        );
+
+        // Quotes beyond the maximum depth are merged into the deepest
+        let mut parser = MarkdownParser::new();
+        parser.set_max_nesting_depth(2);
+        let mut found = Vec::new();
+        depths(&parser.parse(markdown).unwrap().elements, 0, &mut found);
+        assert_eq!(found[2], (2, "Three".to_string()));
+        assert_eq!(found[3], (1, "One again".to_string()));
+    }
+
+    #[test]
+    fn test_task_list_markers_captured() {
+        let parser = MarkdownParser::new();
+        for markdown in ["- [x] Done\n- [ ] Todo\n- Plain\n", "- [x] Done\n\n- [ ] Todo\n\n- Plain\n"] {
+            let document = parser.parse(markdown).unwrap();
+            match &document.elements[0] {
+                MarkdownElement::List { items, .. } => {
+                    let states: Vec<_> = items.iter().map(|item| (item.checked, item.content.clone())).collect();
+                    assert_eq!(
+                        states,
+                        [(Some(true), "Done"), (Some(false), "Todo"), (None, "Plain")]
+                            .map(|(checked, text)| (checked, vec![InlineElement::Text(text.to_string())])),
+                        "{:?}",
+                        markdown
+                    );
+                }
+                other => panic!("expected list, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fence_info_attributes_parsed() {
+        let parser = MarkdownParser::new();
+        let document = parser
+            .parse("```rust,no_run {highlight=2-3}\nfn main() {\n    run();\n}\n```\n\n- Item\n\n  ```sh {highlight=1}\n  ls\n  ```\n")
+            .unwrap();
+        match &document.elements[0] {
+            element @ MarkdownElement::CodeBlock { language, processed, .. } => {
+                let details = document.details_lookup();
+                let attributes = details.code_attributes(element);
+                assert_eq!(language.as_deref(), Some("rust"));
+                assert_eq!(attributes.highlight, vec![2, 3]);
+                assert!(attributes.no_validate);
+                assert!(!processed.as_ref().unwrap().metadata.is_validated);
+            }
+            other => panic!("expected code block, got {:?}", other),
+        }
+        match &document.elements[1] {
+            MarkdownElement::List { items, .. } => match &items[0].blocks[0] {
+                ListItemBlock::CodeBlock { language, attributes, .. } => {
+                    assert_eq!(language.as_deref(), Some("sh"));
+                    assert_eq!(attributes.highlight, vec![1]);
+                }
+                other => panic!("expected code block, got {:?}", other),
+            },
+            other => panic!("expected list, got {:?}", other),
+        }
+    }
+}
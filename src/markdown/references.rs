@@ -0,0 +1,228 @@
+//! Link references
+//!
+//! Collects the external links of a document into a numbered references list.
+//! Each link is replaced by its text followed by an
+//! [`InlineElement::ReferenceMarker`]; links to the same URL share one number.
+//! Links without a `scheme://host` part (anchors, relative paths, `mailto:`)
+//! are left untouched.
+
+use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use std::collections::HashMap;
+
+/// An entry of the references list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub number: usize,
+    pub url: String,
+    /// Title, or failing that the text, of the first link to the URL
+    pub text: String,
+    /// Lowercased host without a leading `www.`
+    pub domain: String,
+}
+
+/// Replace external links with reference markers, returning the references in number order
+pub fn apply_to_document(document: &mut MarkdownDocument) -> Vec<Reference> {
+    let mut collector = Collector::default();
+    for element in &mut document.elements {
+        match element {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => collector.rewrite(content),
+            MarkdownElement::List { items, .. } => collector.rewrite_items(items),
+            _ => {}
+        }
+    }
+    collector.references
+}
+
+/// Group references by domain, in order of each domain's first reference
+pub fn group_by_domain(references: &[Reference]) -> Vec<(&str, Vec<&Reference>)> {
+    let mut groups: Vec<(&str, Vec<&Reference>)> = Vec::new();
+    for reference in references {
+        match groups.iter_mut().find(|(domain, _)| *domain == reference.domain) {
+            Some((_, group)) => group.push(reference),
+            None => groups.push((&reference.domain, vec![reference])),
+        }
+    }
+    groups
+}
+
+/// Domain of an absolute URL such as `https://www.Example.com:8080/path`
+///
+/// Returns `None` for URLs without a `scheme://host` part.
+pub fn domain(url: &str) -> Option<String> {
+    let (scheme, rest) = url.trim().split_once("://")?;
+    let valid_scheme = scheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    if !valid_scheme {
+        return None;
+    }
+
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host_port = authority.rsplit('@').next().unwrap_or_default();
+    let host = match host_port.strip_prefix('[') {
+        // IPv6 literal, e.g. [::1]:8080
+        Some(literal) => literal.split(']').next().unwrap_or_default(),
+        None => host_port.split(':').next().unwrap_or_default(),
+    };
+    let host = host.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    if host.is_empty() {
+        None
+    } else {
+        Some(host.to_string())
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    numbers: HashMap<String, usize>,
+    references: Vec<Reference>,
+}
+
+impl Collector {
+    fn rewrite_items(&mut self, items: &mut [ListItem]) {
+        for item in items {
+            for content in item.paragraphs_mut() {
+                self.rewrite(content);
+            }
+            self.rewrite_items(&mut item.sub_items);
+        }
+    }
+
+    fn rewrite(&mut self, inlines: &mut Vec<InlineElement>) {
+        let has_external_link = inlines
+            .iter()
+            .any(|inline| matches!(inline, InlineElement::Link { url, .. } if domain(url).is_some()));
+        if !has_external_link {
+            return;
+        }
+
+        let mut rewritten = Vec::with_capacity(inlines.len() + 1);
+        for inline in inlines.drain(..) {
+            match inline {
+                InlineElement::Link { text, url, title } => match domain(&url) {
+                    Some(domain) => {
+                        let number = self.number(url, title.unwrap_or_else(|| text.clone()), domain);
+                        if !text.is_empty() {
+                            rewritten.push(InlineElement::Text(text));
+                        }
+                        rewritten.push(InlineElement::ReferenceMarker(number));
+                    }
+                    None => rewritten.push(InlineElement::Link { text, url, title }),
+                },
+                other => rewritten.push(other),
+            }
+        }
+        *inlines = rewritten;
+    }
+
+    fn number(&mut self, url: String, text: String, domain: String) -> usize {
+        let url = url.trim().to_string();
+        if let Some(number) = self.numbers.get(&url) {
+            return *number;
+        }
+        let number = self.references.len() + 1;
+        self.numbers.insert(url.clone(), number);
+        self.references.push(Reference {
+            number,
+            url,
+            text,
+            domain,
+        });
+        number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(text: &str, url: &str) -> InlineElement {
+        InlineElement::Link {
+            text: text.to_string(),
+            url: url.to_string(),
+            title: None,
+        }
+    }
+
+    #[test]
+    fn test_domain() {
+        assert_eq!(domain("https://www.Example.com/path?q=1").as_deref(), Some("example.com"));
+        assert_eq!(domain("http://user@docs.rs:8080").as_deref(), Some("docs.rs"));
+        assert_eq!(domain("http://[::1]:3000/").as_deref(), Some("::1"));
+        assert_eq!(domain("#usage"), None);
+        assert_eq!(domain("../other.md"), None);
+        assert_eq!(domain("mailto:someone@example.com"), None);
+    }
+
+    #[test]
+    fn test_duplicate_urls_share_a_number() {
+        let mut document = MarkdownDocument::new();
+        document.add_element(MarkdownElement::Paragraph {
+            content: vec![
+                link("Rust", "https://www.rust-lang.org"),
+                InlineElement::Text(" and ".to_string()),
+                link("Cargo", "https://doc.rust-lang.org/cargo"),
+                InlineElement::Text(", see ".to_string()),
+                link("usage", "#usage"),
+            ],
+        });
+        document.add_element(MarkdownElement::List {
+            ordered: false,
+            items: vec![ListItem {
+                content: vec![link("Rust again", "https://www.rust-lang.org")],
+                sub_items: vec![],
+                blocks: vec![],
+                checked: None,
+                ordered: false,
+            }],
+        });
+
+        let references = apply_to_document(&mut document);
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].url, "https://www.rust-lang.org");
+        assert_eq!(references[0].text, "Rust");
+        assert_eq!(references[1].number, 2);
+
+        match &document.elements[0] {
+            MarkdownElement::Paragraph { content, .. } => {
+                assert_eq!(content[0], InlineElement::Text("Rust".to_string()));
+                assert_eq!(content[1], InlineElement::ReferenceMarker(1));
+                assert_eq!(content[4], InlineElement::ReferenceMarker(2));
+                assert_eq!(content[6], link("usage", "#usage"));
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+        match &document.elements[1] {
+            MarkdownElement::List { items, .. } => {
+                assert_eq!(items[0].content[1], InlineElement::ReferenceMarker(1));
+            }
+            other => panic!("unexpected element {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_group_by_domain_keeps_first_appearance_order() {
+        let reference = |number: usize, domain: &str| Reference {
+            number,
+            url: format!("https://{}/{}", domain, number),
+            text: String::new(),
+            domain: domain.to_string(),
+        };
+        let references = vec![
+            reference(1, "rust-lang.org"),
+            reference(2, "docs.rs"),
+            reference(3, "rust-lang.org"),
+        ];
+        let groups = group_by_domain(&references);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, "rust-lang.org");
+        assert_eq!(groups[0].1.iter().map(|r| r.number).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(groups[1].0, "docs.rs");
+    }
+}
@@ -0,0 +1,74 @@
+//! Revision notes
+//!
+//! Collects the texts of `<!-- rev: ... -->` notes in document order, for
+//! a "Revision notes" section appended to the document, or turns each
+//! [`InlineElement::RevisionNote`] into an [`InlineElement::Comment`] so it
+//! becomes a Word comment where it was written.
+
+use crate::markdown::ast::{InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+
+/// Whether a document has any revision notes
+pub fn has_notes(document: &MarkdownDocument) -> bool {
+    let mut found = false;
+    visit_document(document, &mut |inlines| {
+        found |= inlines.iter().any(|inline| matches!(inline, InlineElement::RevisionNote(_)));
+    });
+    found
+}
+
+/// Take the revision notes out of the document, returning their texts in
+/// document order; with `as_comments` each is left in place as a comment
+pub fn apply_to_document(document: &mut MarkdownDocument, as_comments: bool) -> Vec<String> {
+    let mut notes = Vec::new();
+    for element in &mut document.elements {
+        rewrite_element(element, &mut |inlines: &mut Vec<InlineElement>| {
+            inlines.retain_mut(|inline| match inline {
+                InlineElement::RevisionNote(text) => {
+                    notes.push(text.clone());
+                    if as_comments {
+                        *inline = InlineElement::Comment(std::mem::take(text));
+                    }
+                    as_comments
+                }
+                _ => true,
+            });
+        });
+    }
+    notes
+}
+
+fn visit_document(document: &MarkdownDocument, visit: &mut dyn FnMut(&[InlineElement])) {
+    fn visit_element(element: &MarkdownElement, visit: &mut dyn FnMut(&[InlineElement])) {
+        match element {
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => visit(content),
+            MarkdownElement::List { items, .. } => visit_items(items, visit),
+            MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => body.iter().for_each(|element| visit_element(element, visit)),
+            _ => {}
+        }
+    }
+    fn visit_items(items: &[ListItem], visit: &mut dyn FnMut(&[InlineElement])) {
+        for item in items {
+            item.paragraphs().for_each(|content| visit(content));
+            visit_items(&item.sub_items, visit);
+        }
+    }
+    document.elements.iter().for_each(|element| visit_element(element, visit));
+}
+
+fn rewrite_element(element: &mut MarkdownElement, rewrite: &mut dyn FnMut(&mut Vec<InlineElement>)) {
+    match element {
+        MarkdownElement::Paragraph { content, .. }
+        | MarkdownElement::FootnoteDefinition { content, .. } => rewrite(content),
+        MarkdownElement::List { items, .. } => rewrite_items(items, rewrite),
+        MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => body.iter_mut().for_each(|element| rewrite_element(element, rewrite)),
+        _ => {}
+    }
+}
+
+fn rewrite_items(items: &mut [ListItem], rewrite: &mut dyn FnMut(&mut Vec<InlineElement>)) {
+    for item in items {
+        item.paragraphs_mut().for_each(&mut *rewrite);
+        rewrite_items(&mut item.sub_items, rewrite);
+    }
+}
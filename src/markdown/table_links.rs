@@ -0,0 +1,181 @@
+//! Links inside table cells
+//!
+//! Table cells are kept as plain text, with their links given by the byte
+//! offset and text of each link within it. While a pass rewrites the text of
+//! a cell, its links are marked in the text with private-use characters so
+//! their offsets can be found again however the text around them changed.
+
+use crate::markdown::ast::TableLink;
+
+/// Marks the start of a link's text while a cell is rewritten
+const LINK_START: char = '\u{E000}';
+/// Marks the end of a link's text while a cell is rewritten
+const LINK_END: char = '\u{E001}';
+
+/// Split the text of a cell into plain text and link parts, in order
+///
+/// Links whose offset does not hold their text, or that overlap an earlier
+/// link, are left as plain text.
+pub fn segments<'a>(text: &'a str, links: &[&'a TableLink]) -> Vec<(&'a str, Option<&'a TableLink>)> {
+    let mut parts = Vec::new();
+    let mut position = 0;
+    for index in placed(text, links.iter().copied()) {
+        let link = links[index];
+        if link.offset > position {
+            parts.push((&text[position..link.offset], None));
+        }
+        position = link.offset + link.text.len();
+        parts.push((&text[link.offset..position], Some(link)));
+    }
+    if position < text.len() || parts.is_empty() {
+        parts.push((&text[position..], None));
+    }
+    parts
+}
+
+/// Rewrite the text of a cell, moving its links along with their texts
+///
+/// Links the rewrite loses track of are cleared, and so left as plain text.
+pub fn rewrite_cell(text: &mut String, links: &mut [&mut TableLink], rewrite: impl FnOnce(&mut String)) {
+    let order = placed(text, links.iter().map(|link| &**link));
+    if order.is_empty() {
+        rewrite(text);
+        return;
+    }
+    let mut marked = String::with_capacity(text.len() + order.len() * 6);
+    let mut position = 0;
+    for &index in &order {
+        let link = &links[index];
+        marked.push_str(&text[position..link.offset]);
+        marked.push(LINK_START);
+        marked.push_str(&link.text);
+        marked.push(LINK_END);
+        position = link.offset + link.text.len();
+    }
+    marked.push_str(&text[position..]);
+    rewrite(&mut marked);
+
+    let (plain, spans) = unmark(&marked);
+    *text = plain;
+    if spans.len() != order.len() {
+        order.iter().for_each(|&index| links[index].text.clear());
+        return;
+    }
+    for (index, (offset, link_text)) in order.into_iter().zip(spans) {
+        links[index].offset = offset;
+        links[index].text = link_text;
+    }
+}
+
+/// Indices of the links that can be placed in a cell's text, by offset
+fn placed<'a>(text: &str, links: impl Iterator<Item = &'a TableLink>) -> Vec<usize> {
+    let mut links: Vec<(usize, &TableLink)> = links.enumerate().collect();
+    links.sort_by_key(|(_, link)| link.offset);
+    let mut order = Vec::new();
+    let mut position = 0;
+    for (index, link) in links {
+        let end = link.offset + link.text.len();
+        if link.text.is_empty() || link.offset < position || text.get(link.offset..end) != Some(link.text.as_str()) {
+            continue;
+        }
+        order.push(index);
+        position = end;
+    }
+    order
+}
+
+/// Rewrite every cell of a pipe table, keeping the table's links in step
+pub fn rewrite_table_cells(
+    headers: &mut [String],
+    rows: &mut [Vec<String>],
+    links: &mut [TableLink],
+    mut rewrite: impl FnMut(&mut String),
+) {
+    let header_cells = headers.iter_mut().enumerate().map(|(column, cell)| (None, column, cell));
+    let row_cells = rows
+        .iter_mut()
+        .enumerate()
+        .flat_map(|(row, cells)| cells.iter_mut().enumerate().map(move |(column, cell)| (Some(row), column, cell)));
+    for (row, column, cell) in header_cells.chain(row_cells) {
+        let mut cell_links: Vec<&mut TableLink> = links
+            .iter_mut()
+            .filter(|link| link.row == row && link.column == column)
+            .collect();
+        rewrite_cell(cell, &mut cell_links, &mut rewrite);
+    }
+}
+
+/// Text marked with link markers, without them, and the offset and text of
+/// each marked link
+///
+/// Markers that do not pair up are dropped.
+pub fn unmark(marked: &str) -> (String, Vec<(usize, String)>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut spans = Vec::new();
+    let mut open = None;
+    for c in marked.chars() {
+        match c {
+            LINK_START => open = Some(text.len()),
+            LINK_END => {
+                if let Some(start) = open.take() {
+                    spans.push((start, text[start..].to_string()));
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+    (text, spans)
+}
+
+/// Mark the start of a link's text in text built up piece by piece, to be
+/// found again with [`unmark`]
+pub fn push_link_start(text: &mut String) {
+    text.push(LINK_START);
+}
+
+/// Mark the end of a link's text opened with [`push_link_start`]
+pub fn push_link_end(text: &mut String) {
+    text.push(LINK_END);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(offset: usize, text: &str) -> TableLink {
+        TableLink {
+            row: Some(0),
+            column: 0,
+            offset,
+            text: text.to_string(),
+            url: "https://serde.rs".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_segments_split_at_offsets() {
+        let serde = link(6, "serde");
+        let parts = segments("serde serde", &[&serde]);
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0], ("serde ", None));
+        assert_eq!(parts[1].0, "serde");
+        assert!(parts[1].1.is_some());
+
+        // A stale offset leaves the text plain
+        let stale = link(3, "serde");
+        assert_eq!(segments("serde serde", &[&stale]), [("serde serde", None)]);
+    }
+
+    #[test]
+    fn test_rewrite_keeps_links_in_step() {
+        let mut text = "a  :smile: [x] serde".to_string();
+        let mut serde = link(15, "serde");
+        let mut links = vec![&mut serde];
+        rewrite_cell(&mut text, &mut links, |text| {
+            *text = text.replace(":smile:", "😄").replace("  ", " ");
+        });
+        assert_eq!(text, "a 😄 [x] serde");
+        assert_eq!(&text[serde.offset..], "serde");
+        assert_eq!(serde.text, "serde");
+    }
+}
@@ -0,0 +1,187 @@
+//! Template variable substitution
+//!
+//! Replaces `{{name}}` placeholders with variable values in the text of
+//! headings, paragraphs, lists, tables, link and image targets and details
+//! summaries. Names are letters, digits, `_`, `-` and `.`, optionally with
+//! spaces inside the braces (`{{ name }}`); anything else between double
+//! braces, such as an `{{include: ...}}` directive, is not a placeholder.
+//! Code spans and code blocks are never rewritten.
+
+use crate::markdown::ast::{HtmlTable, InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use crate::markdown::table_links;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+
+/// Replace placeholders throughout the document, returning the names of
+/// undefined variables in alphabetical order
+///
+/// Placeholders of undefined variables are left as written.
+pub fn apply_to_document(document: &mut MarkdownDocument, values: &HashMap<String, String>) -> Vec<String> {
+    let mut substitution = Substitution {
+        values,
+        undefined: BTreeSet::new(),
+    };
+    for element in &mut document.elements {
+        substitution.element(element);
+    }
+    substitution.undefined.into_iter().collect()
+}
+
+/// Replace the placeholders of defined variables in a text, adding the
+/// names of undefined ones to `undefined`
+pub fn substitute<'a>(
+    text: &'a str,
+    values: &HashMap<String, String>,
+    undefined: &mut BTreeSet<String>,
+) -> Cow<'a, str> {
+    if !text.contains("{{") {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut changed = false;
+    let mut rest = text;
+    while let Some(open) = rest.find("{{") {
+        result.push_str(&rest[..open]);
+        rest = &rest[open..];
+        let placeholder = rest[2..]
+            .find("}}")
+            .map(|close| (&rest[..close + 4], rest[2..close + 2].trim()))
+            .filter(|(_, name)| is_variable_name(name));
+        match placeholder {
+            Some((written, name)) => {
+                match values.get(name) {
+                    Some(value) => {
+                        result.push_str(value);
+                        changed = true;
+                    }
+                    None => {
+                        undefined.insert(name.to_string());
+                        result.push_str(written);
+                    }
+                }
+                rest = &rest[written.len()..];
+            }
+            None => {
+                result.push_str("{{");
+                rest = &rest[2..];
+            }
+        }
+    }
+    result.push_str(rest);
+
+    if changed {
+        Cow::Owned(result)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+struct Substitution<'a> {
+    values: &'a HashMap<String, String>,
+    undefined: BTreeSet<String>,
+}
+
+impl Substitution<'_> {
+    fn text(&mut self, text: &mut String) {
+        if let Cow::Owned(replaced) = substitute(text, self.values, &mut self.undefined) {
+            *text = replaced;
+        }
+    }
+
+    fn element(&mut self, element: &mut MarkdownElement) {
+        match element {
+            MarkdownElement::Heading { text, .. } => self.text(text),
+            MarkdownElement::Paragraph { content, .. }
+            | MarkdownElement::FootnoteDefinition { content, .. } => self.inlines(content),
+            MarkdownElement::List { items, .. } => self.list_items(items),
+            MarkdownElement::Table { headers, rows, links } => {
+                table_links::rewrite_table_cells(headers, rows, links, |cell| self.text(cell));
+            }
+            MarkdownElement::HtmlTable(table) => self.html_table(table),
+            MarkdownElement::Image { alt_text, url, .. } => {
+                self.text(alt_text);
+                self.text(url);
+            }
+            MarkdownElement::Details { summary, body } => {
+                self.text(summary);
+                body.iter_mut().for_each(|element| self.element(element));
+            }
+            MarkdownElement::BlockQuote { content: body } => body.iter_mut().for_each(|element| self.element(element)),
+            MarkdownElement::CodeBlock { .. }
+            | MarkdownElement::HorizontalRule
+            | MarkdownElement::SectionBreak { .. } => {}
+        }
+    }
+
+    fn html_table(&mut self, table: &mut HtmlTable) {
+        for cell in table.rows.iter_mut().flatten() {
+            let mut links: Vec<_> = cell.links.iter_mut().collect();
+            table_links::rewrite_cell(&mut cell.text, &mut links, |text| self.text(text));
+            for nested in &mut cell.tables {
+                self.html_table(nested);
+            }
+        }
+    }
+
+    fn list_items(&mut self, items: &mut [ListItem]) {
+        for item in items {
+            for content in item.paragraphs_mut() {
+                self.inlines(content);
+            }
+            self.list_items(&mut item.sub_items);
+        }
+    }
+
+    fn inlines(&mut self, inlines: &mut [InlineElement]) {
+        for inline in inlines {
+            match inline {
+                InlineElement::Text(text)
+                | InlineElement::Bold(text)
+                | InlineElement::Italic(text)
+                | InlineElement::Strikethrough(text)
+                | InlineElement::Span { text, .. } => self.text(text),
+                InlineElement::Link { text, url, .. } => {
+                    self.text(text);
+                    self.text(url);
+                }
+                InlineElement::Code(_)
+                | InlineElement::FootnoteReference(_)
+                | InlineElement::ReferenceMarker(_)
+                | InlineElement::Comment(_)
+                | InlineElement::IndexTerm(_)
+                | InlineElement::IndexMarker(_)
+                | InlineElement::RevisionNote(_)
+                | InlineElement::Abbreviation { .. } => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute_placeholders() {
+        let values = HashMap::from([
+            ("project_name".to_string(), "Atlas".to_string()),
+            ("version".to_string(), "2.1".to_string()),
+        ]);
+        let mut undefined = BTreeSet::new();
+        assert_eq!(
+            substitute("{{project_name}} {{ version }} by {{owner}}", &values, &mut undefined),
+            "Atlas 2.1 by {{owner}}"
+        );
+        assert_eq!(undefined.into_iter().collect::<Vec<_>>(), ["owner"]);
+
+        // Not placeholders
+        let mut undefined = BTreeSet::new();
+        assert!(matches!(substitute("{{include: a.md}} {{}} {{unclosed", &values, &mut undefined), Cow::Borrowed(_)));
+        assert!(undefined.is_empty());
+    }
+}
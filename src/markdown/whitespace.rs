@@ -0,0 +1,179 @@
+//! Whitespace cleanup in prose
+//!
+//! Trailing whitespace, including non-breaking spaces, is trimmed from the
+//! end of paragraphs and before line breaks, runs of spaces are collapsed to
+//! one and tabs are replaced with spaces. Code spans and code blocks keep
+//! their whitespace.
+
+use crate::markdown::ast::{HtmlTable, InlineElement, ListItem, MarkdownDocument, MarkdownElement};
+use crate::markdown::table_links;
+
+/// Which cleanups to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WhitespaceCleanup {
+    /// Trim whitespace at the end of paragraphs and lines
+    pub trim_trailing: bool,
+    /// Replace runs of spaces (regular or non-breaking) with one space
+    pub collapse_spaces: bool,
+    /// Replace each tab with this many spaces
+    pub tab_spaces: Option<usize>,
+}
+
+/// Space characters collapsed and trimmed
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\u{00A0}'
+}
+
+/// Clean up whitespace in prose throughout a document
+pub fn apply_to_document(document: &mut MarkdownDocument, cleanup: WhitespaceCleanup) {
+    for element in &mut document.elements {
+        apply_to_element(element, cleanup);
+    }
+}
+
+fn apply_to_element(element: &mut MarkdownElement, cleanup: WhitespaceCleanup) {
+    match element {
+        MarkdownElement::Heading { text, .. } => clean_text(text, cleanup, true),
+        MarkdownElement::Paragraph { content, .. }
+        | MarkdownElement::FootnoteDefinition { content, .. } => apply_to_inlines(content, cleanup),
+        MarkdownElement::List { items, .. } => {
+            for item in items {
+                apply_to_list_item(item, cleanup);
+            }
+        }
+        MarkdownElement::Table { headers, rows, links } => {
+            table_links::rewrite_table_cells(headers, rows, links, |cell| clean_text(cell, cleanup, true));
+        }
+        MarkdownElement::HtmlTable(table) => apply_to_html_table(table, cleanup),
+        MarkdownElement::Details { summary, body } => {
+            clean_text(summary, cleanup, true);
+            body.iter_mut().for_each(|element| apply_to_element(element, cleanup));
+        }
+        MarkdownElement::BlockQuote { content: body } => body.iter_mut().for_each(|element| apply_to_element(element, cleanup)),
+        MarkdownElement::CodeBlock { .. }
+        | MarkdownElement::Image { .. }
+        | MarkdownElement::HorizontalRule
+        | MarkdownElement::SectionBreak { .. } => {}
+    }
+}
+
+fn apply_to_html_table(table: &mut HtmlTable, cleanup: WhitespaceCleanup) {
+    for cell in table.rows.iter_mut().flatten() {
+        let mut links: Vec<_> = cell.links.iter_mut().collect();
+        table_links::rewrite_cell(&mut cell.text, &mut links, |text| clean_text(text, cleanup, true));
+        for nested in &mut cell.tables {
+            apply_to_html_table(nested, cleanup);
+        }
+    }
+}
+
+fn apply_to_list_item(item: &mut ListItem, cleanup: WhitespaceCleanup) {
+    for content in item.paragraphs_mut() {
+        apply_to_inlines(content, cleanup);
+    }
+    for sub_item in &mut item.sub_items {
+        apply_to_list_item(sub_item, cleanup);
+    }
+}
+
+fn apply_to_inlines(inlines: &mut [InlineElement], cleanup: WhitespaceCleanup) {
+    // Only the last piece of prose ends the paragraph; a code span there is
+    // left alone
+    let last = inlines
+        .iter()
+        .rposition(|inline| {
+            !matches!(
+                inline,
+                InlineElement::Comment(_)
+                    | InlineElement::ReferenceMarker(_)
+                    | InlineElement::IndexTerm(_)
+                    | InlineElement::IndexMarker(_)
+                    | InlineElement::RevisionNote(_)
+            )
+        });
+    for (index, inline) in inlines.iter_mut().enumerate() {
+        let at_end = Some(index) == last;
+        match inline {
+            InlineElement::Text(text)
+            | InlineElement::Bold(text)
+            | InlineElement::Italic(text)
+            | InlineElement::Strikethrough(text)
+            | InlineElement::Link { text, .. }
+            | InlineElement::Span { text, .. } => clean_text(text, cleanup, at_end),
+            InlineElement::Code(_)
+            | InlineElement::FootnoteReference(_)
+            | InlineElement::ReferenceMarker(_)
+            | InlineElement::Comment(_)
+            | InlineElement::IndexTerm(_)
+            | InlineElement::IndexMarker(_)
+            | InlineElement::RevisionNote(_)
+            | InlineElement::Abbreviation { .. } => {}
+        }
+    }
+}
+
+/// Clean up one piece of prose; `at_end` when it ends its paragraph
+fn clean_text(text: &mut String, cleanup: WhitespaceCleanup, at_end: bool) {
+    if cleanup.collapse_spaces {
+        *text = collapse_spaces(text);
+    }
+    if cleanup.trim_trailing {
+        *text = trim_trailing(text, at_end);
+    }
+    if let Some(spaces) = cleanup.tab_spaces {
+        *text = text.replace('\t', &" ".repeat(spaces));
+    }
+}
+
+/// Replace each run of two or more spaces with a single regular space
+pub fn collapse_spaces(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_space(c) && chars.peek().copied().is_some_and(is_space) {
+            while chars.peek().copied().is_some_and(is_space) {
+                chars.next();
+            }
+            result.push(' ');
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Trim whitespace before each line break, and at the end when `at_end`
+pub fn trim_trailing(text: &str, at_end: bool) -> String {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    let last = lines.len() - 1;
+    for (index, line) in lines.iter_mut().enumerate() {
+        if index < last || at_end {
+            *line = line.trim_end_matches(is_space);
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_and_trim() {
+        assert_eq!(collapse_spaces("a  b\u{00A0} \u{00A0}c d"), "a b c d");
+        assert_eq!(collapse_spaces("keep\u{00A0}single"), "keep\u{00A0}single");
+        assert_eq!(trim_trailing("one  \ntwo\u{00A0}\t", true), "one\ntwo");
+        assert_eq!(trim_trailing("mid-paragraph ", false), "mid-paragraph ");
+    }
+
+    #[test]
+    fn test_tabs_to_spaces() {
+        let cleanup = WhitespaceCleanup {
+            tab_spaces: Some(4),
+            ..WhitespaceCleanup::default()
+        };
+        let mut text = "\tIndented\tcolumn".to_string();
+        clean_text(&mut text, cleanup, true);
+        assert_eq!(text, "    Indented    column");
+    }
+}
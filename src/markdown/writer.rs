@@ -0,0 +1,513 @@
+//! Markdown output
+//!
+//! Writes a parsed document back as Markdown, for checking what the parser
+//! understood. The output is normalized rather than a copy of the source:
+//! ATX headings unless the heading was underlined, `-` bullets, lists
+//! numbered from 1, fenced code blocks, `*`/`**` emphasis and markup
+//! characters in text escaped. Parsing it again gives the same document, up
+//! to how text is split into runs. Whatever the source lost on parsing,
+//! such as the formatting inside headings and table cells, is not restored.
+
+use crate::markdown::ast::{
+    DetailsLookup, HtmlTable, InlineElement, ListItem, ListItemBlock, MarginOverrides, MarkdownDocument, MarkdownElement,
+    PageOrientation, TableLink,
+};
+use crate::markdown::fence_info::CodeAttributes;
+use crate::markdown::parser::ATTRIBUTION_CLASS;
+use crate::markdown::table_links;
+
+/// Write a document as Markdown
+pub fn write_document(document: &MarkdownDocument) -> String {
+    let mut blocks = Vec::new();
+    if !document.margins.is_empty() {
+        blocks.push(margins_directive(&document.margins));
+    }
+    blocks.extend(write_elements(&document.elements, &document.details_lookup()));
+    if blocks.is_empty() {
+        return String::new();
+    }
+    blocks.join("\n\n") + "\n"
+}
+
+/// One block of Markdown per element
+fn write_elements(elements: &[MarkdownElement], details: &DetailsLookup) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut previous_list = false;
+    for element in elements {
+        let is_list = matches!(element, MarkdownElement::List { .. });
+        if is_list && previous_list {
+            // Adjacent lists would otherwise be read back as one
+            blocks.push("<!-- -->".to_string());
+        }
+        previous_list = is_list;
+
+        match element {
+            MarkdownElement::Heading { level, text } => blocks.push(heading(*level, text, details.setext(element))),
+            MarkdownElement::Paragraph { content } => {
+                let mut paragraph = escape_line_starts(&inlines(content));
+                let classes: Vec<_> = details.classes(element).iter().filter(|class| *class != ATTRIBUTION_CLASS).collect();
+                if !classes.is_empty() {
+                    let classes: Vec<_> = classes.iter().map(|class| format!(".{}", class)).collect();
+                    paragraph = format!("{} {{{}}}", paragraph, classes.join(" "));
+                }
+                if !paragraph.is_empty() {
+                    blocks.push(paragraph);
+                }
+            }
+            MarkdownElement::CodeBlock { language, code, .. } => {
+                blocks.push(code_block(language.as_deref(), code, details.code_attributes(element)))
+            }
+            MarkdownElement::List { ordered, items } => blocks.push(list(*ordered, items)),
+            MarkdownElement::Table { headers, rows, links } => blocks.push(table(headers, rows, links)),
+            MarkdownElement::HtmlTable(table) => blocks.push(html_table(table)),
+            MarkdownElement::Image { alt_text, url, title, width, height } => {
+                blocks.push(image(alt_text, url, title.as_deref(), *width, *height))
+            }
+            MarkdownElement::HorizontalRule => blocks.push("---".to_string()),
+            MarkdownElement::FootnoteDefinition { label, content } => {
+                blocks.push(format!("[^{}]: {}", label, inlines(content)))
+            }
+            MarkdownElement::SectionBreak { orientation } => {
+                let orientation = match orientation {
+                    PageOrientation::Portrait => "portrait",
+                    PageOrientation::Landscape => "landscape",
+                };
+                blocks.push(format!("<!-- section: {} -->", orientation));
+            }
+            MarkdownElement::Details { summary, body } => {
+                blocks.push(if summary.is_empty() {
+                    "<details>".to_string()
+                } else {
+                    format!("<details>\n<summary>{}</summary>", summary)
+                });
+                blocks.extend(write_elements(body, details));
+                blocks.push("</details>".to_string());
+            }
+            MarkdownElement::BlockQuote { content } => {
+                let quoted = write_elements(content, details).join("\n\n");
+                let lines: Vec<_> = quoted
+                    .lines()
+                    .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+                    .collect();
+                blocks.push(if lines.is_empty() { ">".to_string() } else { lines.join("\n") });
+            }
+        }
+    }
+    blocks
+}
+
+fn heading(level: u8, text: &str, setext: bool) -> String {
+    let mut text = escape_text(&text.replace('\n', " "));
+    if setext && level <= 2 && !text.trim().is_empty() {
+        let underline = if level == 1 { "===" } else { "---" };
+        return format!("{}\n{}", escape_line_starts(&text), underline);
+    }
+    // A trailing `#` would be read as a closing sequence
+    if text.ends_with('#') {
+        text.insert(text.len() - 1, '\\');
+    }
+    format!("{} {}", "#".repeat(level.clamp(1, 6) as usize), text)
+}
+
+fn code_block(language: Option<&str>, code: &str, attributes: &CodeAttributes) -> String {
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let mut info = language.unwrap_or_default().to_string();
+    let mut attribute_tokens = Vec::new();
+    if !attributes.highlight.is_empty() {
+        attribute_tokens.push(format!("highlight={}", line_ranges(&attributes.highlight)));
+    }
+    if let Some(title) = &attributes.title {
+        attribute_tokens.push(format!("title=\"{}\"", title));
+    }
+    if attributes.no_validate {
+        attribute_tokens.push("no_validate".to_string());
+    }
+    if !attribute_tokens.is_empty() {
+        if !info.is_empty() {
+            info.push(' ');
+        }
+        info.push_str(&format!("{{{}}}", attribute_tokens.join(" ")));
+    }
+
+    let mut block = format!("{}{}\n{}", fence, info, code);
+    if !code.is_empty() && !code.ends_with('\n') {
+        block.push('\n');
+    }
+    block.push_str(&fence);
+    block
+}
+
+/// Line numbers as a list of ranges such as `2-4,7`
+fn line_ranges(lines: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &line in lines {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == line => *last = line,
+            _ => ranges.push((line, line)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn list(ordered: bool, items: &[ListItem]) -> String {
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let marker = if ordered { format!("{}. ", index + 1) } else { "- ".to_string() };
+            let task = match item.checked {
+                Some(true) => "[x] ",
+                Some(false) => "[ ] ",
+                None => "",
+            };
+
+            let mut body = format!("{}{}", task, escape_line_starts(&inlines(&item.content)));
+            for block in &item.blocks {
+                body.push_str("\n\n");
+                match block {
+                    ListItemBlock::Paragraph(content) => body.push_str(&escape_line_starts(&inlines(content))),
+                    ListItemBlock::CodeBlock { language, code, attributes } => {
+                        body.push_str(&code_block(language.as_deref(), code, attributes))
+                    }
+                }
+            }
+            if !item.sub_items.is_empty() {
+                body.push_str(if item.blocks.is_empty() { "\n" } else { "\n\n" });
+                let nested: Vec<String> = item
+                    .sub_items
+                    .chunk_by(|a, b| a.ordered == b.ordered)
+                    .map(|run| list(run[0].ordered, run))
+                    .collect();
+                body.push_str(&nested.join("\n"));
+            }
+
+            let indent = " ".repeat(marker.len());
+            let mut lines = body.lines();
+            let mut written = format!("{}{}", marker, lines.next().unwrap_or_default()).trim_end().to_string();
+            for line in lines {
+                written.push('\n');
+                if !line.is_empty() {
+                    written.push_str(&indent);
+                    written.push_str(line);
+                }
+            }
+            written
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn table(headers: &[String], rows: &[Vec<String>], links: &[TableLink]) -> String {
+    let row_line = |row: Option<usize>, cells: &[String]| {
+        let cells: Vec<_> = (0..headers.len())
+            .map(|column| {
+                let text = cells.get(column).map(String::as_str).unwrap_or_default();
+                let cell_links: Vec<&TableLink> =
+                    links.iter().filter(|link| link.row == row && link.column == column).collect();
+                table_cell(text, &cell_links)
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![
+        row_line(None, headers),
+        format!("|{}", " --- |".repeat(headers.len())),
+    ];
+    lines.extend(rows.iter().enumerate().map(|(index, row)| row_line(Some(index), row)));
+    lines.join("\n")
+}
+
+/// A pipe table cell, with its links written around their texts
+fn table_cell(text: &str, links: &[&TableLink]) -> String {
+    let escape = |text: &str| escape_text(text).replace('|', "\\|").replace('\n', "<br>");
+    table_links::segments(text, links)
+        .into_iter()
+        .map(|(part, link)| match link {
+            Some(link) => format!("[{}]({})", escape(part), link_destination(&link.url)),
+            None => escape(part),
+        })
+        .collect()
+}
+
+fn html_table(table: &HtmlTable) -> String {
+    let mut lines = vec!["<table>".to_string()];
+    for row in &table.rows {
+        let cells: String = row
+            .iter()
+            .map(|cell| {
+                let tag = if cell.header { "th" } else { "td" };
+                let nested: String = cell.tables.iter().map(|table| html_table(table).replace('\n', "")).collect();
+                let links: Vec<&TableLink> = cell.links.iter().collect();
+                let text: String = table_links::segments(&cell.text, &links)
+                    .into_iter()
+                    .map(|(part, link)| {
+                        let part = escape_html(part).replace('\n', "<br>");
+                        match link {
+                            Some(link) => format!("<a href=\"{}\">{}</a>", escape_html(&link.url), part),
+                            None => part,
+                        }
+                    })
+                    .collect();
+                format!("<{}>{}{}</{}>", tag, text, nested, tag)
+            })
+            .collect();
+        lines.push(format!("<tr>{}</tr>", cells));
+    }
+    lines.push("</table>".to_string());
+    lines.join("\n")
+}
+
+fn image(alt_text: &str, url: &str, title: Option<&str>, width: Option<u32>, height: Option<u32>) -> String {
+    if width.is_none() && height.is_none() {
+        return format!("![{}]({}{})", escape_text(alt_text), link_destination(url), link_title(title));
+    }
+    let mut tag = format!("<img src=\"{}\" alt=\"{}\"", escape_html(url), escape_html(alt_text));
+    if let Some(title) = title {
+        tag.push_str(&format!(" title=\"{}\"", escape_html(title)));
+    }
+    if let Some(width) = width {
+        tag.push_str(&format!(" width=\"{}\"", width));
+    }
+    if let Some(height) = height {
+        tag.push_str(&format!(" height=\"{}\"", height));
+    }
+    tag + ">"
+}
+
+fn margins_directive(margins: &MarginOverrides) -> String {
+    let sides = [("top", margins.top), ("bottom", margins.bottom), ("left", margins.left), ("right", margins.right)];
+    let sides: Vec<_> = sides
+        .iter()
+        .filter_map(|(side, length)| length.map(|length| format!("{}={}pt", side, length)))
+        .collect();
+    format!("<!-- margins: {} -->", sides.join(" "))
+}
+
+/// Inline content as one line of Markdown, with hard breaks on lines of their own
+fn inlines(content: &[InlineElement]) -> String {
+    content.iter().map(inline).collect()
+}
+
+fn inline(element: &InlineElement) -> String {
+    match element {
+        InlineElement::Text(text) | InlineElement::Abbreviation { text, .. } => escape_text(text),
+        InlineElement::Bold(text) => delimited(text, "**"),
+        InlineElement::Italic(text) => delimited(text, "*"),
+        InlineElement::Strikethrough(text) => delimited(text, "~~"),
+        InlineElement::Code(code) => code_span(code),
+        InlineElement::Link { text, url, title } => {
+            format!("[{}]({}{})", escape_text(text), link_destination(url), link_title(title.as_deref()))
+        }
+        InlineElement::Span { text, lang: Some(lang), .. } => {
+            format!("<span lang=\"{}\">{}</span>", lang, escape_text(text))
+        }
+        InlineElement::Span { text, classes, lang: None } => {
+            let classes: Vec<_> = classes.iter().map(|class| format!(".{}", class)).collect();
+            format!("[{}]{{{}}}", text, classes.join(" "))
+        }
+        InlineElement::FootnoteReference(label) => format!("[^{}]", label),
+        InlineElement::ReferenceMarker(number) => format!("\\[{}\\]", number),
+        InlineElement::Comment(text) => format!("<!-- comment: {} -->", text),
+        InlineElement::IndexTerm(term) => format!("<!-- index: {} -->", term),
+        InlineElement::RevisionNote(text) => format!("<!-- rev: {} -->", text),
+        InlineElement::IndexMarker(_) => String::new(),
+    }
+}
+
+/// Text between emphasis delimiters, which must touch the text on both sides
+fn delimited(text: &str, delimiter: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return escape_text(text);
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    format!("{}{}{}{}{}", leading, delimiter, escape_text(trimmed), delimiter, trailing)
+}
+
+fn code_span(code: &str) -> String {
+    let mut ticks = 1;
+    while code.split(|c| c != '`').any(|run| run.len() == ticks) {
+        ticks += 1;
+    }
+    let fence = "`".repeat(ticks);
+    // One space on each side is stripped when reading the span back
+    let padded = code.starts_with('`')
+        || code.ends_with('`')
+        || (code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty());
+    if padded {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+fn link_destination(url: &str) -> String {
+    if url.is_empty() || url.contains(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | '<' | '>')) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+fn link_title(title: Option<&str>) -> String {
+    title.map_or_else(String::new, |title| format!(" \"{}\"", title.replace('"', "\\\"")))
+}
+
+/// Backslash-escape the characters that could start markup, and write line
+/// breaks as backslash hard breaks
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' | '&' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape what would start a heading, quote, list or rule at the start of a line
+fn escape_line_starts(text: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            match line[digits..].chars().next() {
+                Some('.' | ')') if digits > 0 => format!("{}\\{}", &line[..digits], &line[digits..]),
+                Some('#' | '>' | '-' | '+' | '=') if digits == 0 => format!("\\{}", line),
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markdown::MarkdownParser;
+
+    /// Merge adjacent text runs, which the parser may split differently
+    /// between the source and the written Markdown
+    fn merge_text(elements: &mut [MarkdownElement]) {
+        fn merge(content: &mut Vec<InlineElement>) {
+            let mut merged: Vec<InlineElement> = Vec::new();
+            for inline in content.drain(..) {
+                match (merged.last_mut(), inline) {
+                    (Some(InlineElement::Text(previous)), InlineElement::Text(text)) => previous.push_str(&text),
+                    (_, inline) => merged.push(inline),
+                }
+            }
+            *content = merged;
+        }
+        fn merge_items(items: &mut [ListItem]) {
+            for item in items {
+                item.paragraphs_mut().for_each(merge);
+                merge_items(&mut item.sub_items);
+            }
+        }
+        for element in elements {
+            match element {
+                MarkdownElement::Paragraph { content, .. } | MarkdownElement::FootnoteDefinition { content, .. } => {
+                    merge(content)
+                }
+                MarkdownElement::List { items, .. } => merge_items(items),
+                MarkdownElement::Details { body, .. } | MarkdownElement::BlockQuote { content: body } => {
+                    merge_text(body)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn assert_round_trip(markdown: &str) -> String {
+        let parser = MarkdownParser::new();
+        let mut original = parser.parse(markdown).unwrap();
+        let written = original.to_markdown();
+        let mut reparsed = parser.parse(&written).unwrap();
+        merge_text(&mut original.elements);
+        merge_text(&mut reparsed.elements);
+
+        assert_eq!(original.elements.len(), reparsed.elements.len(), "{}", written);
+        for (a, b) in original.elements.iter().zip(&reparsed.elements) {
+            assert!(a.same_source(b), "{:?}\n!=\n{:?}\nwritten:\n{}", a, b, written);
+        }
+        assert_eq!(original.margins, reparsed.margins);
+        assert_eq!(original.details, reparsed.details);
+        written
+    }
+
+    #[test]
+    fn test_round_trip_text_and_headings() {
+        let written = assert_round_trip(
+            "Title\n=====\n\n## Using C\\#\n\nSome **bold**, *italic*, ~~gone~~ and `code` with a \
+             [link](https://example.com \"Example\").\nSoft break.\n\n\
+             Literal \\*stars\\*, snake\\_case, \\[brackets\\], &amp;copy; and `` a`b `` {.note}\n\n\
+             \\# Not a heading\n",
+        );
+        assert!(written.starts_with("Title\n===\n\n## Using C\\#\n"), "{}", written);
+        assert!(written.contains("\\*stars\\*, snake\\_case, \\[brackets\\], \\&copy; and ``a`b`` {.note}"));
+        assert!(written.contains("\n\\# Not a heading\n"));
+        assert!(written.contains("[link](https://example.com \"Example\")"));
+    }
+
+    #[test]
+    fn test_round_trip_lists_and_tasks() {
+        let written = assert_round_trip(
+            "- One\n- Two\n  - Nested\n    - Deeper\n- [x] Done\n- [ ] Todo\n\n\
+             1. First\n2. Second\n\n   More about the second.\n\n   ```sh\n   ls\n   ```\n\n\
+             * Another list\n+ And another\n\n- 1\\. not a number\n\n\
+             1. Steps\n   - bullet\n   1. numbered\n",
+        );
+        assert!(written.contains("- One\n- Two\n  - Nested\n    - Deeper\n"), "{}", written);
+        assert!(written.contains("1. Steps\n   - bullet\n   1. numbered"), "{}", written);
+        assert!(written.contains("- [x] Done\n- [ ] Todo"));
+    }
+
+    #[test]
+    fn test_round_trip_blocks() {
+        assert_round_trip(
+            "<!-- margins: top=1in left=54 -->\n\n\
+             ```rust {highlight=2-3,5 title=\"src/main.rs\"}\nfn main() {\n    let s = \"```\";\n}\n```\n\n\
+             | Name | Link |\n|------|------|\n| a \\| b | docs [docs](https://docs.rs) here |\n| c | |\n\n\
+             ![Diagram](img/arch.png \"Architecture\")\n\n\
+             <img src=\"logo.png\" alt=\"Logo\" width=\"120\">\n\n\
+             ---\n\n\
+             > Quoted\n>\n> > Nested quote\n\n\
+             <details>\n<summary>More</summary>\n\nHidden text.\n\n</details>\n\nAfter the details.\n\n\
+             <!-- section: landscape -->\n\n\
+             <table><tr><th>A &amp; B</th></tr><tr><td>cell<br>line <a href=\"https://x.example/?a=1&amp;b=2\">x</a></td></tr></table>\n\n\
+             Note[^1] and [a span]{.kbd}.\n\n[^1]: The footnote.\n",
+        );
+    }
+
+    #[test]
+    fn test_line_starts_escaped() {
+        assert_eq!(escape_line_starts("# not\n2. not\n- not\nplain"), "\\# not\n2\\. not\n\\- not\nplain");
+        assert_eq!(line_ranges(&[1, 2, 3, 5, 7, 8]), "1-3,5,7-8");
+        assert_eq!(code_span("a`b"), "``a`b``");
+        assert_eq!(code_span("`x`"), "`` `x` ``");
+    }
+}
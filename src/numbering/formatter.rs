@@ -3,6 +3,7 @@
 //! This module provides the NumberingFormatter for parsing numbering format strings
 //! and formatting them with the current numbering state.
 
+use crate::config::ListNumberStyle;
 use crate::numbering::error::{NumberingError, NumberingResult};
 use crate::numbering::state::NumberingState;
 use std::fmt;
@@ -14,6 +15,8 @@ use regex::Regex;
 pub struct NumberingFormat {
     /// The levels included in this format (e.g., [1, 2] for "%1.%2.")
     pub levels: Vec<u8>,
+    /// How each level's counter is written, matching `levels`
+    pub styles: Vec<ListNumberStyle>,
     /// The template string with placeholders
     pub template: String,
     /// The separator characters between levels
@@ -31,13 +34,22 @@ impl NumberingFormatter {
     /// - "%1.%2." -> two levels with dots: "1.1.", "1.2.", etc.
     /// - "%1.%2.%3" -> three levels: "1.1.1", "1.1.2", etc.
     /// - "%1-%2-%3" -> custom separator: "1-1-1", "1-1-2", etc.
+    /// - "%I." / "%i." -> Roman numerals: "I.", "II.", "III." or "i.", "ii.", etc.
+    /// - "%A." / "%a." -> letters: "A.", "B.", ..., "Z.", "AA.", "AB.", etc.
+    /// - "%1.%a)" -> styles can be mixed: "1.a)", "1.b)", etc.
+    /// 
+    /// A Roman or letter placeholder may name its level after the letter, as
+    /// in "%A2"; without one it stands for the level after the preceding
+    /// placeholder, or level 1 when it comes first. Letters go on past "z"
+    /// as "aa", "ab", ...; Roman numerals past 3999 repeat "M" for each
+    /// further thousand.
     /// 
     /// # Arguments
     /// * `format` - The format string to parse
     /// 
     /// # Returns
     /// * `NumberingResult<NumberingFormat>` - Parsed format or error
-    #[instrument(fields(format_length = format.len()))]
+    #[instrument(level = "debug", fields(format_length = format.len()))]
     pub fn parse_format(format: &str) -> NumberingResult<NumberingFormat> {
         trace!("Starting to parse numbering format: '{}'", format);
         
@@ -53,8 +65,8 @@ impl NumberingFormatter {
             );
         }
 
-        // Use regex to find all %N placeholders
-        let placeholder_regex = match Regex::new(r"%(\d+)") {
+        // Use regex to find all %N, %I, %i, %A and %a placeholders
+        let placeholder_regex = match Regex::new(r"%(?:(\d+)|([IiAa])(\d+)?)") {
             Ok(regex) => regex,
             Err(e) => {
                 error!(
@@ -66,6 +78,7 @@ impl NumberingFormatter {
         };
         
         let mut levels = Vec::new();
+        let mut styles = Vec::new();
         let mut separators = Vec::new();
         let mut last_end = 0;
         let mut placeholder_count = 0;
@@ -75,11 +88,23 @@ impl NumberingFormatter {
         for capture in placeholder_regex.captures_iter(format) {
             placeholder_count += 1;
             let full_match = capture.get(0).unwrap();
-            let level_str = capture.get(1).unwrap().as_str();
+            let style = match capture.get(2).map(|token| token.as_str()) {
+                Some("I") => ListNumberStyle::UpperRoman,
+                Some("i") => ListNumberStyle::LowerRoman,
+                Some("A") => ListNumberStyle::UpperAlpha,
+                Some("a") => ListNumberStyle::LowerAlpha,
+                _ => ListNumberStyle::Decimal,
+            };
+            let implied_level = (levels.last().copied().unwrap_or(0) + 1).to_string();
+            let level_str = match capture.get(1).or_else(|| capture.get(3)) {
+                Some(level) => level.as_str(),
+                None => implied_level.as_str(),
+            };
             
             trace!(
                 placeholder_index = placeholder_count,
                 level_str = level_str,
+                style = ?style,
                 match_start = full_match.start(),
                 match_end = full_match.end(),
                 "Found placeholder"
@@ -117,6 +142,7 @@ impl NumberingFormatter {
             separators.push(separator);
             
             levels.push(level);
+            styles.push(style);
             last_end = full_match.end();
         }
         
@@ -149,6 +175,7 @@ impl NumberingFormatter {
         
         let parsed_format = NumberingFormat {
             levels: levels.clone(),
+            styles,
             template: format.to_string(),
             separators: separators.clone(),
         };
@@ -192,7 +219,7 @@ impl NumberingFormatter {
     /// 
     /// # Returns
     /// * `NumberingResult<String>` - Formatted numbering string or error
-    #[instrument(skip(state), fields(
+    #[instrument(level = "debug", skip(state), fields(
         format_template = %format.template,
         level_count = format.levels.len(),
         state_counters = ?state.get_all_counters()
@@ -229,7 +256,8 @@ impl NumberingFormatter {
                         counter = counter,
                         "Retrieved counter for level"
                     );
-                    result.push_str(&counter.to_string());
+                    let style = format.styles.get(i).copied().unwrap_or_default();
+                    result.push_str(&style.format(counter as usize));
                 }
                 Err(e) => {
                     error!(
@@ -245,7 +273,8 @@ impl NumberingFormatter {
                         fallback_value = 1,
                         "Using fallback counter value due to error"
                     );
-                    result.push_str("1");
+                    let style = format.styles.get(i).copied().unwrap_or_default();
+                    result.push_str(&style.format(1));
                 }
             }
         }
@@ -479,6 +508,58 @@ mod tests {
         assert_eq!(result, "1-1-1");
     }
 
+    #[test]
+    fn test_parse_roman_and_letter_placeholders() {
+        let format = NumberingFormatter::parse_format("%I.%a)").unwrap();
+        assert_eq!(format.levels, vec![1, 2]);
+        assert_eq!(format.styles, vec![ListNumberStyle::UpperRoman, ListNumberStyle::LowerAlpha]);
+        assert_eq!(format.separators, vec!["", ".", ")"]);
+
+        // A following digit names the level; "%1a" is still level 1 then "a"
+        let format = NumberingFormatter::parse_format("%1.%A3").unwrap();
+        assert_eq!(format.levels, vec![1, 3]);
+        let format = NumberingFormatter::parse_format("%1a").unwrap();
+        assert_eq!(format.styles, vec![ListNumberStyle::Decimal]);
+        assert_eq!(format.separators, vec!["", "a"]);
+
+        assert!(NumberingFormatter::validate_format("%i.").is_ok());
+        assert!(NumberingFormatter::validate_format("%A7").is_err());
+        assert!(NumberingFormatter::validate_format("%B.").is_err());
+    }
+
+    #[test]
+    fn test_format_roman_and_letters() {
+        let mut state = NumberingState::new();
+        let mut numbers = Vec::new();
+        for _ in 0..3 {
+            state.process_heading(1).unwrap();
+            state.process_heading(2).unwrap();
+            numbers.push(NumberingFormatter::format_number("%I.%a", &state).unwrap());
+            numbers.push(NumberingFormatter::format_number("%i-%A", &state).unwrap());
+        }
+        assert_eq!(numbers, ["I.a", "i-A", "II.a", "ii-A", "III.a", "iii-A"]);
+    }
+
+    #[test]
+    fn test_format_overflowing_letters_and_numerals() {
+        let mut state = NumberingState::new();
+        state.process_heading(1).unwrap();
+        let mut at = |count: u32, format: &str| {
+            while state.get_counter(1).unwrap() < count {
+                state.increment_level(1).unwrap();
+            }
+            NumberingFormatter::format_number(format, &state).unwrap()
+        };
+        assert_eq!(at(26, "%a"), "z");
+        assert_eq!(at(27, "%a"), "aa");
+        assert_eq!(at(28, "%A"), "AB");
+        assert_eq!(at(702, "%a"), "zz");
+        assert_eq!(at(703, "%a"), "aaa");
+        assert_eq!(at(1994, "%I"), "MCMXCIV");
+        assert_eq!(at(3999, "%i"), "mmmcmxcix");
+        assert_eq!(at(5001, "%I"), "MMMMMI");
+    }
+
     #[test]
     fn test_format_with_text() {
         let mut state = NumberingState::new();
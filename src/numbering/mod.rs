@@ -32,5 +32,5 @@ mod tests;
 pub use error::{NumberingError, NumberingResult};
 pub use formatter::{NumberingFormatter, NumberingFormat};
 pub use logging::{NumberingMetrics, NumberingLogger, HealthStatus};
-pub use processor::HeadingProcessor;
+pub use processor::{HeadingProcessor, NumberedHeading};
 pub use state::NumberingState;
\ No newline at end of file
@@ -4,15 +4,35 @@
 //! into the heading processing pipeline. It combines the NumberingState and 
 //! NumberingFormatter to automatically add numbering prefixes to headings.
 
-use crate::config::models::ConversionConfig;
+use crate::config::models::{ConversionConfig, EmptyHeadingMode};
 use crate::numbering::error::{NumberingError, NumberingResult};
 use crate::numbering::formatter::NumberingFormatter;
 use crate::numbering::logging::{NumberingMetrics, NumberingLogger};
 use crate::numbering::state::NumberingState;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{debug, warn, error, info, trace, instrument};
 
+/// A heading split into its numbering prefix and text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberedHeading {
+    /// Numbering prefix such as `1.2.`, if the heading is numbered
+    pub prefix: Option<String>,
+    /// Heading text without the prefix
+    pub text: String,
+}
+
+impl NumberedHeading {
+    /// A heading without a numbering prefix
+    pub fn unnumbered(text: &str) -> Self {
+        Self {
+            prefix: None,
+            text: text.to_string(),
+        }
+    }
+}
+
 /// Processor for handling heading numbering in the conversion pipeline
 /// 
 /// The HeadingProcessor maintains numbering state across headings and applies
@@ -106,8 +126,17 @@ impl HeadingProcessor {
     /// 
     /// # Returns
     /// * `NumberingResult<String>` - Processed heading text with numbering prefix
-    #[instrument(skip(self), fields(level, text_length = text.len(), has_numbering = self.should_number_level(level)))]
     pub fn process_heading(&mut self, level: u8, text: &str) -> NumberingResult<String> {
+        let heading = self.process_heading_parts(level, text)?;
+        Ok(self.merge_heading(level, &heading))
+    }
+
+    /// Process a heading like [`process_heading`](Self::process_heading), keeping
+    /// the numbering prefix separate from the text
+    ///
+    /// Used when the prefix is rendered as its own run.
+    #[instrument(level = "debug", skip(self), fields(level, text_length = text.len(), has_numbering = self.should_number_level(level)))]
+    pub fn process_heading_parts(&mut self, level: u8, text: &str) -> NumberingResult<NumberedHeading> {
         let start_time = NumberingLogger::log_operation_start(level, text, self.should_number_level(level));
         
         // Validate heading level
@@ -118,6 +147,13 @@ impl HeadingProcessor {
             return Err(error);
         }
 
+        // Empty headings that are not numbered leave the counters untouched
+        if text.trim().is_empty() && self.config.elements.empty_headings != EmptyHeadingMode::Number {
+            debug!("Level {} heading is empty, leaving it unnumbered", level);
+            self.metrics.record_success(start_time.elapsed());
+            return Ok(NumberedHeading::unnumbered(text));
+        }
+
         // Always update the numbering state, even if this level doesn't use numbering
         // This ensures proper state management for other levels that do use numbering
         match self.state.process_heading(level) {
@@ -141,7 +177,7 @@ impl HeadingProcessor {
         if !self.should_number_level(level) {
             debug!("Level {} does not have numbering configured, returning original text", level);
             self.metrics.record_success(start_time.elapsed());
-            return Ok(text.to_string());
+            return Ok(NumberedHeading::unnumbered(text));
         }
 
         // Get the numbering format for this level
@@ -154,26 +190,29 @@ impl HeadingProcessor {
                     level
                 );
                 self.metrics.record_success(start_time.elapsed());
-                return Ok(text.to_string());
+                return Ok(NumberedHeading::unnumbered(text));
             }
         };
 
         // Generate the numbering prefix with comprehensive error handling
         match self.generate_numbering_prefix(level, &numbering_format) {
             Ok(prefix) => {
-                let result = self.merge_numbering_with_text(&prefix, text);
-                NumberingLogger::log_operation_success(level, text, &result, start_time);
+                let heading = NumberedHeading {
+                    prefix: Some(prefix),
+                    text: text.trim().to_string(),
+                };
+                NumberingLogger::log_operation_success(level, text, &self.merge_heading(level, &heading), start_time);
                 self.metrics.record_success(start_time.elapsed());
-                Ok(result)
-            }
-            Err(e) => {
-                self.handle_numbering_error(level, &e, text, start_time)
+                Ok(heading)
             }
+            Err(e) => self
+                .handle_numbering_error(level, &e, text, start_time)
+                .map(|fallback| NumberedHeading::unnumbered(&fallback)),
         }
     }
     
     /// Generate numbering prefix with detailed error handling
-    #[instrument(skip(self), fields(level, format = %numbering_format))]
+    #[instrument(level = "debug", skip(self), fields(level, format = %numbering_format))]
     fn generate_numbering_prefix(&self, level: u8, numbering_format: &str) -> NumberingResult<String> {
         trace!("Generating numbering prefix for level {} with format '{}'", level, numbering_format);
         
@@ -258,16 +297,17 @@ impl HeadingProcessor {
 
     /// Merge numbering prefix with heading text
     /// 
-    /// This method handles the proper spacing between the numbering prefix
-    /// and the heading text, ensuring good readability.
+    /// This method joins the numbering prefix and the heading text with the
+    /// separator configured for the level.
     /// 
     /// # Arguments
+    /// * `level` - Heading level whose separator is used
     /// * `prefix` - Numbering prefix (e.g., "1.1.")
     /// * `text` - Original heading text
     /// 
     /// # Returns
     /// * `String` - Combined text with proper spacing
-    fn merge_numbering_with_text(&self, prefix: &str, text: &str) -> String {
+    fn merge_numbering_with_text(&self, level: u8, prefix: &str, text: &str) -> String {
         // Trim the original text to handle any existing leading/trailing whitespace
         let trimmed_text = text.trim();
         
@@ -275,11 +315,28 @@ impl HeadingProcessor {
             // If text is empty, just return the prefix
             prefix.to_string()
         } else {
-            // Add a single space between prefix and text for readability
-            format!("{} {}", prefix, trimmed_text)
+            format!("{}{}{}", prefix, self.separator(level), trimmed_text)
         }
     }
 
+    /// Join a heading's numbering prefix and text into the rendered string
+    pub fn merge_heading(&self, level: u8, heading: &NumberedHeading) -> String {
+        match &heading.prefix {
+            Some(prefix) => self.merge_numbering_with_text(level, prefix, &heading.text),
+            None => heading.text.clone(),
+        }
+    }
+
+    /// Separator written between the numbering prefix and the heading text
+    pub fn separator(&self, level: u8) -> &str {
+        self.config
+            .styles
+            .headings
+            .get(&level)
+            .map(|style| style.numbering_separator.as_str())
+            .unwrap_or(" ")
+    }
+
     /// Reset the numbering state
     /// 
     /// This can be useful when processing multiple documents or when
@@ -300,6 +357,29 @@ impl HeadingProcessor {
         );
     }
 
+    /// Replace the numbering state with seeded counters
+    ///
+    /// Each entry maps a heading level to the number of its last heading, so
+    /// numbering continues after it: seeding `{1: 3, 2: 2}` numbers the next
+    /// H2 as `3.3.` and the next H1 as `4.`. The state is reset first, so
+    /// levels without an entry start at 1 and seeding twice does not
+    /// accumulate. Levels outside 1-6 are rejected and leave the state
+    /// untouched.
+    #[instrument(skip(self))]
+    pub fn seed_state(&mut self, counters: &HashMap<u8, u32>) -> NumberingResult<()> {
+        if let Some(&level) = counters.keys().find(|level| !(1..=6).contains(*level)) {
+            return Err(NumberingError::invalid_level(level));
+        }
+
+        self.reset_state();
+        for (&level, &last_number) in counters {
+            self.state.seed_level(level, last_number)?;
+        }
+
+        NumberingLogger::log_state_operation("seed", None, &self.state);
+        Ok(())
+    }
+
     /// Get the current numbering state (for debugging/testing)
     /// 
     /// # Returns
@@ -555,6 +635,48 @@ mod tests {
         Arc::new(config)
     }
 
+    fn process_with_empty_heading_mode(mode: EmptyHeadingMode) -> Vec<String> {
+        let mut config = (*create_test_config_with_numbering()).clone();
+        config.elements.empty_headings = mode;
+        let mut processor = HeadingProcessor::new(Arc::new(config));
+        processor
+            .process_headings(vec![(1, "One"), (2, "  "), (2, "Two"), (1, "")])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_process_heading_parts_keeps_prefix_separate() {
+        let mut processor = HeadingProcessor::new(create_test_config_with_numbering());
+        let heading = processor.process_heading_parts(1, "  Intro ").unwrap();
+        assert_eq!(heading.prefix.as_deref(), Some("1."));
+        assert_eq!(heading.text, "Intro");
+        assert_eq!(processor.merge_heading(1, &heading), "1. Intro");
+
+        let mut config = (*create_test_config_with_numbering()).clone();
+        config.styles.headings.get_mut(&1).unwrap().numbering_separator =
+            crate::config::NumberingSeparator::Tab;
+        let tabbed = HeadingProcessor::new(Arc::new(config));
+        assert_eq!(tabbed.merge_heading(1, &heading), "1.\tIntro");
+
+        // Levels without numbering have no prefix
+        let heading = processor.process_heading_parts(4, "Detail").unwrap();
+        assert_eq!(heading, NumberedHeading::unnumbered("Detail"));
+    }
+
+    #[test]
+    fn test_empty_heading_modes() {
+        assert_eq!(
+            process_with_empty_heading_mode(EmptyHeadingMode::Number),
+            vec!["1. One", "1.1.", "1.2. Two", "2."]
+        );
+        for mode in [EmptyHeadingMode::Unnumbered, EmptyHeadingMode::Skip] {
+            assert_eq!(
+                process_with_empty_heading_mode(mode),
+                vec!["1. One", "  ", "1.1. Two", ""]
+            );
+        }
+    }
+
     fn create_test_config_no_numbering() -> Arc<ConversionConfig> {
         Arc::new(ConversionConfig::default())
     }
@@ -963,4 +1085,33 @@ mod tests {
         assert_eq!(processor.get_state().get_counter(2).unwrap(), 1); // Should reset
         assert_eq!(processor.get_state().get_counter(3).unwrap(), 1); // Should reset
     }
+
+    #[test]
+    fn test_seed_state_continues_from_seeded_values() {
+        let mut processor = HeadingProcessor::new(create_test_config_with_numbering());
+        processor.seed_state(&HashMap::from([(1, 3), (2, 2)])).unwrap();
+
+        let results = processor
+            .process_headings(vec![(2, "Next"), (3, "Deep"), (1, "Chapter"), (2, "Sub")])
+            .unwrap();
+        assert_eq!(results, vec!["3.3. Next", "3.3.1 Deep", "4. Chapter", "4.1. Sub"]);
+    }
+
+    #[test]
+    fn test_seed_state_replaces_previous_state() {
+        let mut processor = HeadingProcessor::new(create_test_config_with_numbering());
+        processor.process_headings(vec![(1, "A"), (1, "B")]).unwrap();
+
+        // Unseeded levels start over, a 0 seed too
+        processor.seed_state(&HashMap::from([(2, 4), (3, 0)])).unwrap();
+        assert_eq!(processor.process_heading(2, "Two").unwrap(), "1.5. Two");
+        assert_eq!(processor.process_heading(3, "Three").unwrap(), "1.5.1 Three");
+
+        // Invalid levels are rejected before anything changes
+        assert!(processor.seed_state(&HashMap::from([(1, 9), (7, 1)])).is_err());
+        assert_eq!(processor.get_state().get_counter(2).unwrap(), 5);
+
+        processor.reset_state();
+        assert_eq!(processor.process_heading(1, "Fresh").unwrap(), "1. Fresh");
+    }
 }
\ No newline at end of file
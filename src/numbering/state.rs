@@ -292,6 +292,30 @@ impl NumberingState {
         );
     }
 
+    /// Set a level as if its last heading had been numbered `last_number`
+    ///
+    /// The next heading at the level is numbered `last_number + 1`. A
+    /// `last_number` of 0 leaves the level unprocessed, so it starts at 1.
+    /// Lower levels are left as they are.
+    ///
+    /// # Arguments
+    /// * `level` - Heading level (1-6 for H1-H6)
+    /// * `last_number` - Number of the last heading at this level
+    ///
+    /// # Returns
+    /// * `NumberingResult<()>` - Ok if successful, error if level is invalid
+    pub fn seed_level(&mut self, level: u8, last_number: u32) -> NumberingResult<()> {
+        if level < 1 || level > 6 {
+            return Err(NumberingError::invalid_level(level));
+        }
+
+        let index = (level - 1) as usize;
+        self.counters[index] = last_number.max(1);
+        self.processed_levels[index] = last_number > 0;
+        debug!(level = level, last_number = last_number, "Seeded heading level counter");
+        Ok(())
+    }
+
     /// Get the counters for levels up to and including the specified level
     /// 
     /// This is useful for generating multi-level numbering like "1.2.3"
@@ -461,6 +485,23 @@ mod tests {
         assert!(state.get_counters_up_to_level(7).is_err());
     }
 
+    #[test]
+    fn test_seed_level() {
+        let mut state = NumberingState::new();
+        state.seed_level(1, 3).unwrap();
+        state.seed_level(2, 0).unwrap();
+        assert!(state.is_level_processed(1).unwrap());
+        assert!(!state.is_level_processed(2).unwrap());
+
+        state.process_heading(1).unwrap();
+        assert_eq!(state.get_counter(1).unwrap(), 4);
+        state.process_heading(2).unwrap();
+        assert_eq!(state.get_counter(2).unwrap(), 1);
+
+        assert!(state.seed_level(0, 1).is_err());
+        assert!(state.seed_level(7, 1).is_err());
+    }
+
     #[test]
     fn test_reset_all() {
         let mut state = NumberingState::new();
@@ -1,7 +1,7 @@
 //! Test utilities and mock objects for unit testing
 
-use crate::config::{ConversionConfig, DocumentConfig, StyleConfig, ElementConfig, PageSize, Margins, FontConfig, HeadingStyle, ParagraphStyle, CodeBlockStyle, TableStyle, ImageConfig, ListConfig, LinkConfig};
-use crate::markdown::ast::{MarkdownDocument, MarkdownElement, InlineElement, ListItem};
+use crate::config::{ConversionConfig, DocumentConfig, StyleConfig, ElementConfig, PageSize, Margins, FontConfig, HeadingStyle, NumberingSeparator, ParagraphStyle, CodeBlockStyle, TableStyle, ImageConfig, ListConfig, LinkConfig, EmojiConfig, CheckboxConfig, ReferencesConfig, OutputConfig, PdfConfig, AppendedSectionsConfig, ParsingConfig, EmbedSourceConfig};
+use crate::markdown::ast::{MarkdownDocument, MarkdownElement, InlineElement, ListItem, MarginOverrides, ElementDetails};
 use std::collections::HashMap;
 
 /// Create a minimal valid configuration for testing
@@ -24,6 +24,9 @@ pub fn create_test_config() -> ConversionConfig {
                 bold: false,
                 italic: false,
             },
+            default_tab_stop: 36.0,
+            watermark: None,
+            table_of_contents: crate::config::TableOfContentsConfig::default(),
         },
         styles: StyleConfig {
             headings: {
@@ -39,6 +42,9 @@ pub fn create_test_config() -> ConversionConfig {
                     spacing_after: 6.0,
                     alignment: None,
                     numbering: None,
+                    numbering_style: None,
+                    numbering_separator: NumberingSeparator::default(),
+                    numbering_tab_stop: None,
                 });
                 headings.insert(2, HeadingStyle {
                     font: FontConfig {
@@ -51,6 +57,9 @@ pub fn create_test_config() -> ConversionConfig {
                     spacing_after: 5.0,
                     alignment: None,
                     numbering: None,
+                    numbering_style: None,
+                    numbering_separator: NumberingSeparator::default(),
+                    numbering_tab_stop: None,
                 });
                 headings.insert(3, HeadingStyle {
                     font: FontConfig {
@@ -63,6 +72,9 @@ pub fn create_test_config() -> ConversionConfig {
                     spacing_after: 5.0,
                     alignment: None,
                     numbering: None,
+                    numbering_style: None,
+                    numbering_separator: NumberingSeparator::default(),
+                    numbering_tab_stop: None,
                 });
                 headings
             },
@@ -103,23 +115,71 @@ pub fn create_test_config() -> ConversionConfig {
                     italic: false,
                 },
                 border_width: 1.0,
+                header_background: None,
+                alternate_row_background: None,
+                row_header_column: false,
+                table_alignment: crate::config::TableAlignment::default(),
+                full_width: false,
+                wide_tables: crate::config::WideTableConfig::default(),
             },
+            code_lines: crate::config::CodeLinesStyle::default(),
+            classes: HashMap::new(),
+            title_block: crate::config::TitleBlockConfig::default(),
+            first_heading_title: crate::config::FirstHeadingTitleConfig::default(),
+            heading_bookmarks: crate::config::HeadingBookmarksConfig::default(),
+            inline_html: crate::config::InlineHtmlStyles::default(),
+            blockquote: crate::config::BlockQuoteStyle::default(),
+            named_styles: crate::config::NamedStylesConfig::default(),
         },
         elements: ElementConfig {
             image: ImageConfig {
                 max_width: 600.0,
                 max_height: 400.0,
+                max_pixel_dimension: None,
+                fetch_remote: false,
+                remote_timeout_seconds: 10,
+                remote_max_bytes: 10 * 1024 * 1024,
+                remote_cache: None,
+                total_size: None,
+                placement: crate::config::ImagePlacement::Block,
+                qrcode: crate::config::QrCodeConfig::default(),
             },
             list: ListConfig {
                 indent: 20.0,
                 spacing: 3.0,
+                ordered_styles: vec![crate::config::ListNumberStyle::Decimal],
+                tasks: crate::config::TaskListConfig::default(),
             },
             link: LinkConfig {
                 color: "#0066cc".to_string(),
                 underline: true,
+                autolink_emails: false,
             },
+            emoji: EmojiConfig::default(),
+            checkboxes: CheckboxConfig::default(),
+            whitespace: crate::config::WhitespaceConfig::default(),
+            references: ReferencesConfig::default(),
+            max_nesting_depth: crate::config::DEFAULT_MAX_NESTING_DEPTH,
+            empty_headings: crate::config::EmptyHeadingMode::default(),
+            horizontal_rule: crate::config::HorizontalRuleConfig::default(),
+            footnotes: crate::config::FootnotesConfig::default(),
+            code_captions: crate::config::CodeCaptionConfig::default(),
+            code_title_bar: crate::config::CodeTitleBarConfig::default(),
+            unicode_bullets: crate::config::UnicodeBulletsConfig::default(),
+            quote_attribution: crate::config::QuoteAttributionConfig::default(),
+            emphasis_callouts: crate::config::EmphasisCalloutConfig::default(),
+            link_buttons: crate::config::LinkButtonConfig::default(),
+            abbreviations: crate::config::AbbreviationsConfig::default(),
+            note_block: crate::config::NoteBlockConfig::default(),
+            revision_notes: crate::config::RevisionNotesConfig::default(),
+            long_paragraphs: crate::config::LongParagraphsConfig::default(),
         },
         code_block_processing: None,
+        // Tests always verify the generated package
+        output: OutputConfig { self_check: true, pdf: PdfConfig::default(), embed_source: EmbedSourceConfig::default(), provenance: crate::config::ProvenanceConfig::default() },
+        sections: AppendedSectionsConfig::default(),
+        parsing: ParsingConfig::default(),
+        compact: crate::config::CompactConfig::default(),
     }
 }
 
@@ -155,10 +215,16 @@ pub fn create_test_document() -> MarkdownDocument {
                     ListItem {
                         content: vec![InlineElement::Text("First item".to_string())],
                         sub_items: vec![],
+                        blocks: vec![],
+                        checked: None,
+                        ordered: false,
                     },
                     ListItem {
                         content: vec![InlineElement::Text("Second item".to_string())],
                         sub_items: vec![],
+                        blocks: vec![],
+                        checked: None,
+                        ordered: false,
                     },
                 ],
             },
@@ -168,6 +234,7 @@ pub fn create_test_document() -> MarkdownDocument {
                     vec!["Alice".to_string(), "30".to_string()],
                     vec!["Bob".to_string(), "25".to_string()],
                 ],
+                links: vec![],
             },
             MarkdownElement::Image {
                 alt_text: "Test Image".to_string(),
@@ -178,6 +245,8 @@ pub fn create_test_document() -> MarkdownDocument {
             },
             MarkdownElement::HorizontalRule,
         ],
+        margins: MarginOverrides::default(),
+        details: ElementDetails::default(),
     }
 }
 
@@ -195,6 +264,8 @@ pub fn create_simple_document() -> MarkdownDocument {
                 ],
             },
         ],
+        margins: MarginOverrides::default(),
+        details: ElementDetails::default(),
     }
 }
 
@@ -280,7 +351,7 @@ mod tests {
         
         // Check first element is heading
         match &doc.elements[0] {
-            MarkdownElement::Heading { level, text } => {
+            MarkdownElement::Heading { level, text, .. } => {
                 assert_eq!(*level, 1);
                 assert_eq!(text, "Main Title");
             },
@@ -306,4 +377,15 @@ mod tests {
         assert!(response.contains("yaml"));
         assert!(response.contains("Arial"));
     }
-}
\ No newline at end of file
+}
+
+/// Read a part (e.g. `word/document.xml`) out of generated docx bytes
+pub fn read_docx_part(docx_bytes: &[u8], name: &str) -> Option<String> {
+    use std::io::Read;
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(docx_bytes)).ok()?;
+    let mut file = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    file.read_to_string(&mut content).ok()?;
+    Some(content)
+}
@@ -21,6 +21,7 @@ pub fn create_router(app_state: AppState) -> Router {
     let mut router = Router::new()
         // Health check endpoint
         .route("/health", get(handlers::health_check))
+        .route("/capabilities", get(handlers::get_capabilities))
         // Synchronous API routes
         .route("/api/convert", post(handlers::convert_markdown))
         .route("/api/convert/upload", post(handlers::upload_and_convert))
@@ -22,6 +22,9 @@ pub struct ConvertRequest {
     pub markdown: String,
     pub config: Option<String>,
     pub natural_language: Option<String>,
+    /// Report how long each conversion phase took
+    #[serde(default)]
+    pub timing: bool,
 }
 
 /// Conversion response matching API specification
@@ -30,6 +33,9 @@ pub struct ConvertResponse {
     pub success: bool,
     pub file_data: Option<Vec<u8>>,
     pub error: Option<String>,
+    /// Phase timings, when the request asked for them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<crate::conversion::TimingReport>,
 }
 
 /// Configuration update request
@@ -103,6 +109,12 @@ pub async fn health_check() -> Json<HealthResponse> {
     })
 }
 
+/// Capabilities handler reporting supported languages, formats and features
+pub async fn get_capabilities(State(app_state): State<AppState>) -> Json<crate::Capabilities> {
+    let engine = app_state.conversion_engine.lock().await;
+    Json(crate::capabilities::capabilities_for(&engine.config().output.pdf))
+}
+
 /// Convert Markdown to docx handler
 pub async fn convert_markdown(
     State(app_state): State<AppState>,
@@ -137,6 +149,7 @@ pub async fn convert_markdown(
                     success: false,
                     file_data: None,
                     error: Some(format!("Invalid YAML configuration: {}", e)),
+                    timing: None,
                 }));
             }
         }
@@ -144,13 +157,22 @@ pub async fn convert_markdown(
     
     // Perform the conversion
     let mut engine = app_state.conversion_engine.lock().await;
-    match engine.convert(&request.markdown).await {
-        Ok(docx_bytes) => {
+    let result = if request.timing {
+        engine
+            .convert_with_timing(&request.markdown)
+            .await
+            .map(|(docx_bytes, report)| (docx_bytes, Some(report)))
+    } else {
+        engine.convert(&request.markdown).await.map(|docx_bytes| (docx_bytes, None))
+    };
+    match result {
+        Ok((docx_bytes, timing)) => {
             tracing::info!("Conversion successful, generated {} bytes", docx_bytes.len());
             Ok(Json(ConvertResponse {
                 success: true,
                 file_data: Some(docx_bytes),
                 error: None,
+                timing,
             }))
         }
         Err(e) => {
@@ -159,6 +181,7 @@ pub async fn convert_markdown(
                 success: false,
                 file_data: None,
                 error: Some(format!("Conversion failed: {}", e)),
+                timing: None,
             }))
         }
     }
@@ -630,11 +653,11 @@ pub async fn validate_config(
     tracing::info!("Received configuration validation request");
     
     // Try to parse the YAML configuration
-    match serde_yaml::from_str::<crate::config::ConversionConfig>(&request.config) {
+    match crate::config::ConversionConfig::from_yaml_str(&request.config) {
         Ok(config) => {
             // Validate the parsed configuration
-            match config.validate() {
-                Ok(()) => {
+            match config.validation_report() {
+                None => {
                     // Convert to JSON for frontend consumption
                     match serde_json::to_value(&config) {
                         Ok(json_config) => {
@@ -657,13 +680,13 @@ pub async fn validate_config(
                         }
                     }
                 }
-                Err(validation_error) => {
+                Some(validation_error) => {
                     tracing::warn!("Configuration validation failed: {}", validation_error);
                     Ok(Json(ConfigValidationResponse {
                         success: true,
                         valid: false,
                         parsed_config: None,
-                        error: Some(validation_error.to_string()),
+                        error: Some(validation_error),
                     }))
                 }
             }
@@ -33,6 +33,9 @@ pub struct ResourceConfig {
     pub max_cpu_percent: f32,
     pub request_timeout_seconds: u64,
     pub rate_limit_per_minute: u32,
+    /// Worker-pool size for the task queue and for batch conversions run by
+    /// the server's engine; defaults to the number of CPUs
+    pub max_concurrency: usize,
 }
 
 impl Default for ResourceConfig {
@@ -42,6 +45,7 @@ impl Default for ResourceConfig {
             max_cpu_percent: 80.0,
             request_timeout_seconds: 60,
             rate_limit_per_minute: 60,
+            max_concurrency: crate::conversion::default_concurrency(),
         }
     }
 }
@@ -35,11 +35,14 @@ impl WebServer {
     }
 
     /// Create a new web server with custom resource configuration
+    ///
+    /// The engine's batch concurrency is set to `max_concurrency`.
     pub fn with_resource_config(
-        conversion_engine: ConversionEngine,
+        mut conversion_engine: ConversionEngine,
         port: u16,
         resource_config: ResourceConfig,
     ) -> Self {
+        conversion_engine.set_concurrency(resource_config.max_concurrency);
         Self {
             conversion_engine: Arc::new(tokio::sync::Mutex::new(conversion_engine)),
             port,
@@ -48,8 +48,9 @@ pub struct TaskQueue {
 }
 
 impl TaskQueue {
-    /// Create a new task queue with the specified number of workers
+    /// Create a new task queue with the specified number of workers (at least one)
     pub fn new(conversion_engine: Arc<tokio::sync::Mutex<ConversionEngine>>, worker_count: usize) -> Self {
+        let worker_count = worker_count.max(1);
         let (sender, mut receiver) = mpsc::unbounded_channel();
         let results = Arc::new(RwLock::new(HashMap::new()));
         
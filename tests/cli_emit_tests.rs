@@ -0,0 +1,27 @@
+//! Integration tests for the CLI's `--emit` outputs
+
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_emit_markdown_writes_normalized_source() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("notes.md");
+    fs::write(&input, "Notes\n=====\n\n* one\n* [x] two\n\n> quoted _text_\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_md2docx-cli"))
+        .args(["convert", "-i", input.to_str().unwrap(), "--emit", "markdown"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(dir.path().join("notes.docx").exists());
+    let markdown = fs::read_to_string(dir.path().join("notes.parsed.md")).unwrap();
+    assert_eq!(markdown, "Notes\n===\n\n- one\n- [x] two\n\n> quoted *text*\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_md2docx-cli"))
+        .args(["convert", "-i", input.to_str().unwrap(), "--emit", "html"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
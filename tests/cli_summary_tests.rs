@@ -0,0 +1,55 @@
+//! Integration tests for the CLI's `--summary` recap
+
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn cli(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_md2docx-cli"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn write_inputs(dir: &TempDir) {
+    // The unknown fence attribute is logged as a warning
+    fs::write(
+        dir.path().join("a.md"),
+        "# Title\n\nText.\n\n```rust {frobnicate}\nfn main() {}\n```\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("b.md"), "# Other\n\nPlain.\n").unwrap();
+}
+
+#[test]
+fn test_convert_summary_counts_elements_and_warnings() {
+    let dir = TempDir::new().unwrap();
+    write_inputs(&dir);
+    let input = dir.path().join("a.md");
+
+    let stdout = cli(&["convert", "-i", input.to_str().unwrap(), "--summary"]);
+    let size = fs::metadata(dir.path().join("a.docx")).unwrap().len();
+    let summary = stdout.lines().find(|line| line.starts_with("Summary: ")).unwrap();
+    assert!(
+        summary.starts_with(&format!("Summary: 3 elements, 1 warning, {} bytes, ", size)),
+        "{}",
+        summary
+    );
+
+    let stdout = cli(&["convert", "-i", input.to_str().unwrap()]);
+    assert!(!stdout.contains("Summary:"));
+}
+
+#[test]
+fn test_batch_summary_totals_files() {
+    let dir = TempDir::new().unwrap();
+    write_inputs(&dir);
+
+    let stdout = cli(&["batch", "-i", dir.path().to_str().unwrap(), "--parallel", "2", "--summary"]);
+    let line = |suffix: &str| stdout.lines().find(|line| line.contains(suffix)).unwrap().to_string();
+    assert!(line("a.md: ").contains("3 elements, 1 warning, "), "{}", stdout);
+    assert!(line("b.md: ").contains("2 elements, 0 warnings, "), "{}", stdout);
+    assert!(line("Total: ").contains("Total: 2 files, 5 elements, 1 warning, "), "{}", stdout);
+}
@@ -0,0 +1,36 @@
+//! Integration tests for the CLI's `--var` template variables
+
+use std::fs;
+use std::io::Read;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn document_xml(docx: &[u8]) -> String {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(docx)).unwrap();
+    let mut xml = String::new();
+    archive.by_name("word/document.xml").unwrap().read_to_string(&mut xml).unwrap();
+    xml
+}
+
+#[test]
+fn test_var_arguments_fill_placeholders() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("notes.md");
+    fs::write(&input, "# {{project_name}} {{version}}\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_md2docx-cli"))
+        .args(["convert", "-i", input.to_str().unwrap()])
+        .args(["--var", "project_name=Atlas", "--var", "version=a=b"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let docx = fs::read(dir.path().join("notes.docx")).unwrap();
+    let xml = document_xml(&docx);
+    assert!(xml.contains(">Atlas a=b</w:t>"), "{}", xml);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_md2docx-cli"))
+        .args(["convert", "-i", input.to_str().unwrap(), "--var", "version"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
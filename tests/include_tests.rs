@@ -0,0 +1,69 @@
+//! Integration tests for transcluding Markdown files with include directives
+
+use md2docx_converter::{config::ConversionConfig, conversion::ConversionEngine};
+use std::fs;
+use std::io::Read;
+use tempfile::TempDir;
+
+fn document_xml(docx: &[u8]) -> String {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(docx)).unwrap();
+    let mut xml = String::new();
+    archive.by_name("word/document.xml").unwrap().read_to_string(&mut xml).unwrap();
+    xml
+}
+
+fn numbered_config() -> ConversionConfig {
+    let mut config = ConversionConfig::default();
+    config.styles.headings.get_mut(&1).unwrap().numbering = Some("%1.".to_string());
+    config.styles.headings.get_mut(&2).unwrap().numbering = Some("%1.%2.".to_string());
+    config.parsing.includes.enabled = true;
+    config
+}
+
+#[tokio::test]
+async fn test_included_file_spliced_with_continuous_numbering() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("chapters")).unwrap();
+    fs::write(
+        dir.path().join("main.md"),
+        "# Introduction\n\nOpening text.\n\n{{include: chapters/details.md}}\n\n# Closing\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("chapters/details.md"),
+        "# Details\n\n## Background\n\nIncluded paragraph.\n",
+    )
+    .unwrap();
+
+    let input = dir.path().join("main.md");
+    let output = dir.path().join("main.docx");
+    let mut engine = ConversionEngine::new(numbered_config());
+    engine
+        .convert_file(input.to_str().unwrap(), output.to_str().unwrap())
+        .await
+        .unwrap();
+
+    let xml = document_xml(&fs::read(&output).unwrap());
+    let position = |text: &str| xml.find(text).unwrap_or_else(|| panic!("{} missing", text));
+    assert!(position("1. Introduction") < position("2. Details"));
+    assert!(position("2. Details") < position("2.1. Background"));
+    assert!(position("Included paragraph.") < position("3. Closing"));
+    assert!(!xml.contains("include:"));
+}
+
+#[tokio::test]
+async fn test_include_cycle_fails_conversion() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("a.md"), "# A\n\n!INCLUDE \"b.md\"\n").unwrap();
+    fs::write(dir.path().join("b.md"), "# B\n\n!INCLUDE \"a.md\"\n").unwrap();
+
+    let input = dir.path().join("a.md");
+    let output = dir.path().join("a.docx");
+    let mut engine = ConversionEngine::new(numbered_config());
+    let error = engine
+        .convert_file(input.to_str().unwrap(), output.to_str().unwrap())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("cycle"));
+    assert!(!output.exists());
+}
@@ -93,6 +93,36 @@ async fn test_health_endpoint() {
     assert!(health_response["version"].is_string());
 }
 
+#[tokio::test]
+async fn test_capabilities_endpoint() {
+    let app = create_test_app().await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/capabilities")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let capabilities: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    let languages: Vec<&str> = capabilities["code_languages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|lang| lang["language"].as_str())
+        .collect();
+    assert!(languages.contains(&"rust"));
+    assert!(languages.contains(&"note"));
+    assert_eq!(capabilities["features"]["web"], true);
+}
+
 #[tokio::test]
 async fn test_convert_endpoint_basic() {
     let app = create_test_app().await;